@@ -0,0 +1,223 @@
+//! Parsing benchmarks: pool datums per DEX and Kupo `/matches` responses.
+//!
+//! Pool UTXOs carry their datum inline, so the DEX paths run end to end
+//! (value filtering, datum decoding, pool construction) without a Kupo.
+//!
+//! Run with `cargo bench --bench parse`.
+
+use ciborium::value::Value;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use dexter_kupo_rs::dex::minswap_v2::MinswapV2;
+use dexter_kupo_rs::dex::sundaeswap_v3::SundaeSwapV3;
+use dexter_kupo_rs::dex::wingriders_v2::WingRidersV2;
+use dexter_kupo_rs::kupo::{parse_matches, MatchRef};
+use dexter_kupo_rs::models::Unit;
+use dexter_kupo_rs::{BaseDex, KupoApi, PlutusData, Utxo};
+
+const SNEK_POLICY: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f";
+const SNEK_NAME: &str = "534e454b";
+const MINSWAP_V2_LP_POLICY: &str = "f5808c2c990d86da54bfc97d89cee6efa20cd8461616359478d96b4c";
+const SUNDAESWAP_V3_LP_POLICY: &str = "e0302560ced2fdcbfcb2602697df970cd0d6a38f94b32703f51c312b";
+const WINGRIDERS_V2_POLICY: &str = "6fdc63a1d71dc2c65502b79baae7fb543185702b12c3c5fb639ed737";
+
+fn constr(index: u64, fields: Vec<Value>) -> Value {
+    Value::Tag(121 + index, Box::new(Value::Array(fields)))
+}
+
+fn int(n: u64) -> Value {
+    Value::Integer(n.into())
+}
+
+fn bytes(h: &str) -> Value {
+    Value::Bytes(hex::decode(h).unwrap())
+}
+
+fn to_hex(datum: &Value) -> String {
+    let mut cbor = Vec::new();
+    ciborium::ser::into_writer(datum, &mut cbor).unwrap();
+    hex::encode(cbor)
+}
+
+fn unit(unit: &str, quantity: u64) -> Unit {
+    Unit {
+        unit: unit.to_string(),
+        quantity,
+    }
+}
+
+/// ADA/SNEK pool output holding `extra` (LP, NFT and validity tokens).
+fn pool_utxo(extra: Vec<Unit>, datum: String) -> Utxo {
+    let mut amount = vec![
+        unit("lovelace", 1_000_000_000_000),
+        unit(&format!("{}{}", SNEK_POLICY, SNEK_NAME), 3_000_000_000),
+    ];
+    amount.extend(extra);
+    Utxo {
+        address: "addr1pool".to_string(),
+        tx_hash: "ab".repeat(32),
+        tx_index: 0,
+        output_index: 0,
+        amount,
+        block: "cd".repeat(32),
+        slot: Some(150_000_000),
+        data_hash: Some("ef".repeat(32)),
+        inline_datum: Some(datum),
+        reference_script_hash: None,
+        datum_type: Some("inline".to_string()),
+    }
+}
+
+fn minswap_v2_utxo() -> Utxo {
+    let asset = |policy: &str, name: &str| constr(0, vec![bytes(policy), bytes(name)]);
+    let datum = constr(
+        0,
+        vec![
+            constr(0, vec![]),
+            asset("", ""),
+            asset(SNEK_POLICY, SNEK_NAME),
+            int(50_000_000_000),
+            int(1_000_000_000_000),
+            int(3_000_000_000),
+            int(30),
+            int(30),
+            constr(0, vec![int(1_666)]),
+            constr(0, vec![]),
+        ],
+    );
+    pool_utxo(
+        vec![
+            unit(&format!("{}4d5350", MINSWAP_V2_LP_POLICY), 1),
+            unit(&format!("{}{}", MINSWAP_V2_LP_POLICY, "7d".repeat(32)), 1),
+        ],
+        to_hex(&datum),
+    )
+}
+
+fn sundaeswap_v3_utxo() -> Utxo {
+    let key = |byte: u8| constr(0, vec![Value::Bytes(vec![byte; 28])]);
+    let fee_manager = constr(3, vec![int(2), Value::Array(vec![key(0xaa), key(0xbb)])]);
+    let datum = constr(
+        0,
+        vec![
+            Value::Bytes(vec![0xab; 28]),
+            Value::Array(vec![]),
+            int(50_000_000_000),
+            int(30),
+            int(30),
+            constr(0, vec![fee_manager]),
+            int(0),
+            int(2_000_000),
+        ],
+    );
+    pool_utxo(
+        vec![unit(
+            &format!("{}{}", SUNDAESWAP_V3_LP_POLICY, "ab".repeat(28)),
+            1,
+        )],
+        to_hex(&datum),
+    )
+}
+
+fn wingriders_v2_utxo() -> Utxo {
+    let mut fields = vec![
+        Value::Bytes(vec![0xab; 28]),
+        bytes(""),
+        bytes(""),
+        bytes(SNEK_POLICY),
+        bytes(SNEK_NAME),
+    ];
+    // Swap, protocol, project and reserve fees, fee basis, agent fee, last
+    // interaction, then the four treasuries and two unused ints.
+    fields.extend([6, 29, 0, 0, 10_000, 2_000_000, 0, 1_000, 2_000, 0, 0, 0, 0].map(int));
+    fields.extend([constr(1, vec![]), constr(1, vec![]), constr(0, vec![])]);
+    pool_utxo(
+        vec![
+            unit(&format!("{}4c", WINGRIDERS_V2_POLICY), 1),
+            unit(&format!("{}{}", WINGRIDERS_V2_POLICY, "cd".repeat(32)), 1),
+        ],
+        to_hex(&constr(0, fields)),
+    )
+}
+
+fn bench_pool_datums(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    // Never contacted: every fixture carries its datum inline.
+    let kupo = KupoApi::new("http://127.0.0.1:9");
+    let dexes: Vec<(Box<dyn BaseDex>, Utxo)> = vec![
+        (Box::new(MinswapV2::new(&kupo)), minswap_v2_utxo()),
+        (Box::new(SundaeSwapV3::new(&kupo)), sundaeswap_v3_utxo()),
+        (Box::new(WingRidersV2::new(&kupo)), wingriders_v2_utxo()),
+    ];
+
+    let mut group = c.benchmark_group("pool_from_utxo");
+    for (dex, utxo) in &dexes {
+        let pool = runtime.block_on(dex.liquidity_pool_from_utxo_extend(utxo, ""));
+        assert!(
+            matches!(pool, Ok(Some(_))),
+            "{} fixture does not parse",
+            dex.identifier()
+        );
+        group.bench_function(dex.identifier(), |b| {
+            b.iter(|| runtime.block_on(dex.liquidity_pool_from_utxo_extend(black_box(utxo), "")))
+        });
+    }
+    group.finish();
+
+    let datum = minswap_v2_utxo().inline_datum.unwrap();
+    c.bench_function("plutus_data/from_cbor_hex", |b| {
+        b.iter(|| PlutusData::from_cbor_hex(black_box(&datum)).unwrap())
+    });
+}
+
+/// A `/matches` body of `n` pool-like outputs, as Kupo serves it.
+fn matches_body(n: usize) -> String {
+    let matches: Vec<serde_json::Value> = (0..n)
+        .map(|i| {
+            serde_json::json!({
+                "transaction_index": 3,
+                "transaction_id": format!("{:064x}", i),
+                "output_index": i % 4,
+                "address": "addr1z8snz7c4974vzdpxu65ruphl3zjdvtxw8strf2c2tmqnxz2j2c79gy9l76sdg0xwhd7r0c0kna0tycz4y5s6mlenh8pq0xmsha",
+                "value": {
+                    "coins": 1_000_000_000 + i as u64,
+                    "assets": {
+                        format!("{}.{}", SNEK_POLICY, SNEK_NAME): 3_000_000_000u64,
+                        format!("{}.4d5350", MINSWAP_V2_LP_POLICY): 1,
+                    }
+                },
+                "datum_hash": "ef".repeat(32),
+                "datum_type": "inline",
+                "script_hash": null,
+                "created_at": { "slot_no": 150_000_000 + i as u64, "header_hash": "cd".repeat(32) },
+                "spent_at": null
+            })
+        })
+        .collect();
+    serde_json::to_string(&matches).unwrap()
+}
+
+fn bench_kupo_matches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kupo_matches");
+    for n in [100, 1_000] {
+        let body = matches_body(n);
+        group.throughput(Throughput::Bytes(body.len() as u64));
+        group.bench_with_input(BenchmarkId::new("json_value", n), &body, |b, body| {
+            b.iter(|| serde_json::from_str::<serde_json::Value>(black_box(body)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("borrowed", n), &body, |b, body| {
+            b.iter(|| parse_matches(black_box(body)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("to_utxo", n), &body, |b, body| {
+            b.iter(|| {
+                let matches = parse_matches(black_box(body)).unwrap();
+                matches.iter().map(MatchRef::to_utxo).collect::<Vec<Utxo>>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_pool_datums, bench_kupo_matches);
+criterion_main!(benches);
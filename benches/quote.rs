@@ -0,0 +1,127 @@
+//! Quote math and routing benchmarks over synthetic pools.
+//!
+//! Run with `cargo bench --bench quote`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use dexter_kupo_rs::models::asset::from_identifier;
+use dexter_kupo_rs::quote::{self, estimated_receive_exact, Direction};
+use dexter_kupo_rs::router::{triangular_arbitrage, RoutePool};
+use dexter_kupo_rs::{LiquidityPool, Router, StablePool, Token};
+
+const POLICY: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f";
+
+/// Token `i` of a synthetic universe: one policy, asset name `i`.
+fn token(i: usize) -> Token {
+    from_identifier(&format!("{}{:08x}", POLICY, i), 0)
+}
+
+fn amm_pool(id: usize, a: Token, b: Token, reserve_a: u64, reserve_b: u64) -> LiquidityPool {
+    LiquidityPool::new(
+        "MinswapV2",
+        a,
+        b,
+        reserve_a,
+        reserve_b,
+        "addr1pool",
+        0.3,
+        &format!("pool{}", id),
+    )
+}
+
+fn stable_pool() -> StablePool {
+    StablePool::new(
+        "MinswapStable",
+        vec![(token(0), 5_000_000_000_000), (token(1), 4_900_000_000_000)],
+        "addr1stable",
+        "stable",
+        0.04,
+        200,
+        9_900_000_000_000,
+    )
+    .unwrap()
+}
+
+fn bench_quote_math(c: &mut Criterion) {
+    let pool = amm_pool(
+        0,
+        Token::Lovelace,
+        token(0),
+        1_000_000_000_000,
+        3_000_000_000,
+    );
+    let stable = stable_pool();
+
+    let mut group = c.benchmark_group("quote");
+    group.bench_function("amm_out", |b| {
+        b.iter(|| quote::amm_out(black_box(&pool), Direction::AToB, black_box(250_000_000)))
+    });
+    group.bench_function("estimated_receive_exact", |b| {
+        b.iter(|| {
+            estimated_receive_exact(black_box(&pool), &Token::Lovelace, black_box(250_000_000))
+        })
+    });
+    group.bench_function("stable_out", |b| {
+        b.iter(|| quote::stable_out(black_box(&stable), Direction::AToB, black_box(250_000_000)))
+    });
+    group.finish();
+}
+
+/// `pools` ADA/X pools of one pair at slightly different prices, plus an X/Y
+/// pool per token for `tokens` tokens so pathfinding has a graph to walk.
+fn router(pools: usize, tokens: usize) -> Router {
+    let pair = (0..pools).map(|i| {
+        let reserve_a = 1_000_000_000_000 + i as u64 * 10_000_000_000;
+        amm_pool(i, Token::Lovelace, token(0), reserve_a, 3_000_000_000)
+    });
+    let ada = (1..tokens).map(|t| {
+        amm_pool(
+            pools + t,
+            Token::Lovelace,
+            token(t),
+            500_000_000_000,
+            1_000_000_000_000,
+        )
+    });
+    let cross = (1..tokens).map(|t| {
+        let reserve_b = 2_000_000_000_000 + t as u64 * 1_000_000_000;
+        amm_pool(
+            pools + tokens + t,
+            token(t - 1),
+            token(t),
+            1_000_000_000_000,
+            reserve_b,
+        )
+    });
+    Router::new().with_pools(pair.chain(ada).chain(cross))
+}
+
+fn bench_routing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("router");
+    for pools in [4, 32] {
+        let router = router(pools, 16);
+        let amount = 5_000_000_000;
+        group.bench_with_input(BenchmarkId::new("best_route", pools), &router, |b, r| {
+            b.iter(|| r.best_route(&Token::Lovelace, &token(0), black_box(amount)))
+        });
+        group.bench_with_input(BenchmarkId::new("split_route", pools), &router, |b, r| {
+            b.iter(|| r.split_route(&Token::Lovelace, &token(0), black_box(amount)))
+        });
+    }
+
+    for tokens in [16, 64] {
+        let router = router(4, tokens);
+        group.bench_with_input(BenchmarkId::new("token_graph", tokens), &router, |b, r| {
+            b.iter(|| r.token_graph())
+        });
+        let pools: &[RoutePool] = router.pools();
+        group.bench_with_input(
+            BenchmarkId::new("triangular_arbitrage", tokens),
+            pools,
+            |b, p| b.iter(|| triangular_arbitrage(black_box(p))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_quote_math, bench_routing);
+criterion_main!(benches);
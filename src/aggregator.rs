@@ -0,0 +1,1044 @@
+//! Cross-DEX views of a single pair.
+//!
+//! [`pair_summary`] queries every DEX for the pools of a pair and condenses
+//! them into one [`PairSummary`] — total liquidity, best and weighted price,
+//! deepest pool — for token pages and listing checks.
+//!
+//! [`best_execution`] compares filling an order on the ChadSwap book, through
+//! AMM pools, or split across both. Each venue carries its
+//! [`SettlementLatency`], and [`best_execution_with_latency`] can charge
+//! batcher-settled venues for the slots their orders wait on chain.
+//!
+//! [`Aggregator`] holds the set of DEXes these views run over. Besides the
+//! built-in AMMs, downstream crates can [`register_dex`](Aggregator::register_dex)
+//! their own [`BaseDex`] implementations and hand the same set to the
+//! watcher, chain adapter and exporters.
+
+use anyhow::{anyhow, Result};
+use futures::future::join_all;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::dex::chadswap::{self, ChadSwap};
+use crate::dex::cswap::CSwap;
+use crate::dex::minswap_v1::MinswapV1;
+use crate::dex::minswap_v2::MinswapV2;
+use crate::dex::sundaeswap_v1::SundaeSwapV1;
+use crate::dex::sundaeswap_v3::SundaeSwapV3;
+use crate::dex::vyfinance::VyFinance;
+use crate::dex::wingriders::WingRiders;
+use crate::dex::wingriders_v2::WingRidersV2;
+use crate::dex::{settlement_latency, BaseDex, OverridesConfig, PoolFilter, SettlementLatency};
+use crate::kupo::KupoApi;
+use crate::models::{AssetId, LiquidityPool, Order, OrderBook, Token};
+use crate::quote::{amm_out, Direction};
+use crate::registry::TokenRegistry;
+use crate::utils::RetryBudget;
+
+/// One pool of the pair, oriented as `token_a` / `token_b`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PairPool {
+    pub dex_identifier: String,
+    pub pool_id: String,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    /// Decimal-adjusted units of `token_b` per unit of `token_a`.
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PairSummary {
+    pub token_a: String,
+    pub token_b: String,
+    pub dexes: Vec<String>,
+    pub pool_count: usize,
+    /// Reserves of `token_a` / `token_b` summed across pools (base units).
+    pub total_reserve_a: u64,
+    pub total_reserve_b: u64,
+    /// Twice the ADA side summed across pools, when one side is ADA.
+    pub tvl_lovelace: Option<u64>,
+    /// Highest `token_b` per `token_a` — the best spot price for a seller of `token_a`.
+    pub best_price: Option<f64>,
+    pub best_price_pool: Option<PairPool>,
+    /// Pool with the largest `sqrt(reserve_a * reserve_b)`.
+    pub deepest_pool: Option<PairPool>,
+    /// Pool prices weighted by each pool's `token_a` reserve. Kupo has no trade
+    /// volume, so depth stands in for volume here.
+    pub weighted_price: Option<f64>,
+    pub pools: Vec<PairPool>,
+}
+
+fn decimals(token: &Token) -> i32 {
+    match token {
+        Token::Lovelace => 6,
+        Token::Asset(a) => a.decimals as i32,
+    }
+}
+
+/// Orient `pool` as `token_a` / `token_b`, or `None` if it is another pair.
+fn orient(pool: &LiquidityPool, token_a: &AssetId, token_b: &AssetId) -> Option<PairPool> {
+    let a = AssetId::from(&pool.asset_a);
+    let b = AssetId::from(&pool.asset_b);
+    let (ta, tb, ra, rb) = if a == *token_a && b == *token_b {
+        (&pool.asset_a, &pool.asset_b, pool.reserve_a, pool.reserve_b)
+    } else if a == *token_b && b == *token_a {
+        (&pool.asset_b, &pool.asset_a, pool.reserve_b, pool.reserve_a)
+    } else {
+        return None;
+    };
+    let adj_a = ra as f64 / 10_f64.powi(decimals(ta));
+    let adj_b = rb as f64 / 10_f64.powi(decimals(tb));
+    Some(PairPool {
+        dex_identifier: pool.dex_identifier.clone(),
+        pool_id: pool.pool_id.clone(),
+        reserve_a: ra,
+        reserve_b: rb,
+        price: if adj_a > 0.0 { adj_b / adj_a } else { 0.0 },
+    })
+}
+
+/// Summarise already-fetched pools. Pools of other pairs are ignored.
+pub fn summarize(
+    pools: &[LiquidityPool],
+    token_a: impl Into<AssetId>,
+    token_b: impl Into<AssetId>,
+) -> PairSummary {
+    let token_a = token_a.into();
+    let token_b = token_b.into();
+    let pair: Vec<PairPool> = pools
+        .iter()
+        .filter_map(|p| orient(p, &token_a, &token_b))
+        .filter(|p| p.reserve_a > 0 && p.reserve_b > 0)
+        .collect();
+
+    let mut dexes: Vec<String> = pair.iter().map(|p| p.dex_identifier.clone()).collect();
+    dexes.sort();
+    dexes.dedup();
+
+    let total_reserve_a = pair.iter().map(|p| p.reserve_a).sum();
+    let total_reserve_b = pair.iter().map(|p| p.reserve_b).sum::<u64>();
+    let tvl_lovelace = if token_a.is_lovelace() {
+        Some(total_reserve_a * 2)
+    } else if token_b.is_lovelace() {
+        Some(total_reserve_b * 2)
+    } else {
+        None
+    };
+
+    let best_price_pool = pair
+        .iter()
+        .max_by(|x, y| x.price.total_cmp(&y.price))
+        .cloned();
+    let deepest_pool = pair
+        .iter()
+        .max_by_key(|p| p.reserve_a as u128 * p.reserve_b as u128)
+        .cloned();
+    let weight: f64 = pair.iter().map(|p| p.reserve_a as f64).sum();
+    let weighted_price = (weight > 0.0).then(|| {
+        pair.iter()
+            .map(|p| p.price * p.reserve_a as f64)
+            .sum::<f64>()
+            / weight
+    });
+
+    PairSummary {
+        token_a: token_a.to_string(),
+        token_b: token_b.to_string(),
+        dexes,
+        pool_count: pair.len(),
+        total_reserve_a,
+        total_reserve_b,
+        tvl_lovelace,
+        best_price: best_price_pool.as_ref().map(|p| p.price),
+        best_price_pool,
+        deepest_pool,
+        weighted_price,
+        pools: pair,
+    }
+}
+
+/// Fetch the pools of `token_a` / `token_b` from every DEX concurrently and summarise them.
+///
+/// A DEX that fails to answer is logged and left out rather than failing the summary.
+/// Retries of all requests share one [`RetryBudget`], so an outage fails the
+/// query fast instead of retrying every datum fetch.
+pub async fn pair_summary(
+    dexes: &[&dyn BaseDex],
+    token_a: impl Into<AssetId>,
+    token_b: impl Into<AssetId>,
+) -> Result<PairSummary> {
+    let token_a = token_a.into();
+    let token_b = token_b.into();
+    let pools = pair_pools(dexes, token_a.clone(), token_b.clone()).await?;
+    Ok(summarize(&pools, token_a, token_b))
+}
+
+/// The pools of `token_a` / `token_b` on every DEX, fetched concurrently.
+///
+/// Failing DEXes are logged and left out, as in [`pair_summary`].
+pub async fn pair_pools(
+    dexes: &[&dyn BaseDex],
+    token_a: impl Into<AssetId>,
+    token_b: impl Into<AssetId>,
+) -> Result<Vec<LiquidityPool>> {
+    let token_a = token_a.into();
+    let token_b = token_b.into();
+    let results = RetryBudget::default()
+        .scope(join_all(
+            dexes
+                .iter()
+                .map(|dex| dex.liquidity_pools_from_token(token_b.clone(), token_a.clone())),
+        ))
+        .await;
+
+    let mut pools = Vec::new();
+    for (dex, result) in dexes.iter().zip(results) {
+        match result {
+            Ok(found) => pools.extend(found),
+            Err(e) => eprintln!("[aggregator] {} failed: {}", dex.identifier(), e),
+        }
+    }
+    Ok(pools)
+}
+
+/// Distinct assets `token` trades against in `pools`, sorted.
+pub fn counter_assets(pools: &[LiquidityPool], token: impl Into<AssetId>) -> Vec<AssetId> {
+    let token = token.into();
+    let mut others: Vec<AssetId> = pools
+        .iter()
+        .filter_map(|p| {
+            let (a, b) = (AssetId::from(&p.asset_a), AssetId::from(&p.asset_b));
+            if a == token {
+                Some(b)
+            } else if b == token {
+                Some(a)
+            } else {
+                None
+            }
+        })
+        .collect();
+    others.sort();
+    others.dedup();
+    others
+}
+
+/// Every pool holding `token` across `dexes`, fetched concurrently.
+///
+/// A DEX that fails to answer is logged and left out; retries share one [`RetryBudget`].
+pub async fn pools_with_token(
+    dexes: &[&dyn BaseDex],
+    token: impl Into<AssetId>,
+) -> Result<Vec<LiquidityPool>> {
+    let token = token.into();
+    let results = RetryBudget::default()
+        .scope(join_all(
+            dexes
+                .iter()
+                .map(|dex| dex.liquidity_pools_with_token(token.clone())),
+        ))
+        .await;
+
+    let mut pools = Vec::new();
+    for (dex, result) in dexes.iter().zip(results) {
+        match result {
+            Ok(found) => pools.extend(found),
+            Err(e) => eprintln!("[aggregator] {} failed: {}", dex.identifier(), e),
+        }
+    }
+    Ok(pools)
+}
+
+/// Look `pool_id` up on every DEX concurrently; every DEX that knows it
+/// contributes its (datum-extended) pool.
+///
+/// A DEX that fails to answer is logged and left out; retries share one [`RetryBudget`].
+pub async fn pools_by_id(dexes: &[&dyn BaseDex], pool_id: &str) -> Result<Vec<LiquidityPool>> {
+    let results = RetryBudget::default()
+        .scope(join_all(
+            dexes
+                .iter()
+                .map(|dex| dex.liquidity_pool_from_pool_id(pool_id)),
+        ))
+        .await;
+
+    let mut pools = Vec::new();
+    for (dex, result) in dexes.iter().zip(results) {
+        match result {
+            Ok(found) => pools.extend(found),
+            Err(e) => eprintln!("[aggregator] {} failed: {}", dex.identifier(), e),
+        }
+    }
+    Ok(pools)
+}
+
+/// [`pair_summary`] for tickers or identifiers, resolved through `registry`
+/// (e.g. `"ADA"`, `"SNEK"`). Unknown or ambiguous tickers are errors.
+pub async fn pair_summary_by_ticker(
+    dexes: &[&dyn BaseDex],
+    registry: &TokenRegistry,
+    token_a: &str,
+    token_b: &str,
+) -> Result<PairSummary> {
+    let token_a = registry.resolve(token_a)?;
+    let token_b = registry.resolve(token_b)?;
+    pair_summary(dexes, token_a, token_b).await
+}
+
+/// Which way a [`best_execution`] order trades the token against ADA.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    /// Spend `size` lovelace on the token.
+    Buy,
+    /// Sell `size` base units of the token for lovelace.
+    Sell,
+}
+
+/// Portion of an order filled at one venue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VenueFill {
+    /// `ChadSwap`, or `<dex identifier>:<pool id>`.
+    pub venue: String,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Expected slots until the fill settles.
+    pub settlement_slots: u64,
+    /// Settled by a batcher in a later transaction.
+    pub batched: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BestExecution {
+    pub token: String,
+    pub side: Side,
+    pub size: u64,
+    /// Walking the order book alone; `amount_in` is below `size` when the book is too thin.
+    pub order_book: Option<VenueFill>,
+    /// The single pool returning the most.
+    pub best_pool: Option<VenueFill>,
+    /// Best allocation across the book and every pool.
+    pub split: Vec<VenueFill>,
+    /// Venues of the winning plan: the split, or a single venue when it does
+    /// at least as well.
+    pub venues: Vec<VenueFill>,
+    /// Output of the winning plan (tokens for a buy, lovelace for a sell).
+    pub expected_out: u64,
+    /// Slots until the slowest venue of the winning plan settles.
+    pub settlement_slots: u64,
+    /// Penalty the plan was chosen with, in basis points of a venue's output
+    /// per slot it takes to settle.
+    pub latency_penalty_bps_per_slot: f64,
+    /// `expected_out` less each venue's latency penalty.
+    pub adjusted_out: u64,
+}
+
+/// Slices an order is cut into when allocating it across venues.
+const EXECUTION_SLICES: u64 = 50;
+
+/// Output of walking `book` with `amount_in`, best price first. ChadSwap fees
+/// are not included.
+fn book_out(book: &OrderBook, side: Side, amount_in: u64) -> u64 {
+    let orders: &[Order] = match side {
+        Side::Buy => &book.sell_orders,
+        Side::Sell => &book.buy_orders,
+    };
+    let mut remaining = amount_in as u128;
+    let mut out = 0u128;
+    for order in orders {
+        if remaining == 0 {
+            break;
+        }
+        let (price, den) = (order.price as u128, order.price_denominator.max(1) as u128);
+        if price == 0 {
+            continue;
+        }
+        match side {
+            // Lovelace in, tokens out at `price / den` lovelace per token.
+            Side::Buy => {
+                let tokens = (remaining * den / price).min(order.amount as u128);
+                out += tokens;
+                remaining -= (tokens * price).div_ceil(den).min(remaining);
+                if tokens < order.amount as u128 {
+                    break;
+                }
+            }
+            Side::Sell => {
+                let tokens = remaining.min(order.amount as u128);
+                out += tokens * price / den;
+                remaining -= tokens;
+            }
+        }
+    }
+    out.min(u64::MAX as u128) as u64
+}
+
+/// Input the book can absorb before it runs dry.
+fn book_capacity(book: &OrderBook, side: Side) -> u64 {
+    let total: u128 = match side {
+        Side::Buy => book
+            .sell_orders
+            .iter()
+            .map(|o| {
+                (o.amount as u128 * o.price as u128).div_ceil(o.price_denominator.max(1) as u128)
+            })
+            .sum(),
+        Side::Sell => book.buy_orders.iter().map(|o| o.amount as u128).sum(),
+    };
+    total.min(u64::MAX as u128) as u64
+}
+
+/// `amount` less `bps_per_slot` basis points for each of `slots`.
+fn discounted(amount: u64, slots: u64, bps_per_slot: f64) -> u64 {
+    let penalty = bps_per_slot * slots as f64 / 10_000.0;
+    if penalty <= 0.0 {
+        return amount;
+    }
+    (amount as f64 * (1.0 - penalty).max(0.0)) as u64
+}
+
+enum Venue<'a> {
+    Book(&'a OrderBook),
+    Pool(&'a LiquidityPool, Direction),
+}
+
+impl Venue<'_> {
+    fn name(&self) -> String {
+        match self {
+            Venue::Book(_) => "ChadSwap".to_string(),
+            Venue::Pool(pool, _) => format!("{}:{}", pool.dex_identifier, pool.pool_id),
+        }
+    }
+
+    fn latency(&self) -> SettlementLatency {
+        match self {
+            Venue::Book(_) => chadswap::SETTLEMENT,
+            Venue::Pool(pool, _) => settlement_latency(&pool.dex_identifier),
+        }
+    }
+
+    fn out(&self, side: Side, amount_in: u64) -> u64 {
+        match self {
+            Venue::Book(book) => book_out(book, side, amount_in),
+            Venue::Pool(pool, direction) => amm_out(pool, *direction, amount_in),
+        }
+    }
+}
+
+/// Plan `size` against `book` and the ADA pools of `token` among `pools`.
+///
+/// The split hands the order out in slices, each to the venue whose next
+/// slice returns the most; for the concave outputs of AMMs and books this is
+/// the best allocation up to one slice.
+pub fn plan_execution(
+    book: Option<&OrderBook>,
+    pools: &[LiquidityPool],
+    token: impl Into<AssetId>,
+    side: Side,
+    size: u64,
+) -> BestExecution {
+    plan_execution_with_latency(book, pools, token, side, size, 0.0)
+}
+
+/// [`plan_execution`] with every venue's output discounted by
+/// `penalty_bps_per_slot` basis points per slot of its settlement latency, so
+/// a batcher AMM must beat an order-book fill by enough to pay for the wait
+/// (price drift, and an order open to front-running until it settles).
+pub fn plan_execution_with_latency(
+    book: Option<&OrderBook>,
+    pools: &[LiquidityPool],
+    token: impl Into<AssetId>,
+    side: Side,
+    size: u64,
+    penalty_bps_per_slot: f64,
+) -> BestExecution {
+    let token = token.into();
+    let input = match side {
+        Side::Buy => Token::Lovelace,
+        Side::Sell => token.to_token(0),
+    };
+    let mut venues: Vec<Venue> = Vec::new();
+    if let Some(book) = book {
+        venues.push(Venue::Book(book));
+    }
+    for pool in pools {
+        let (a, b) = (AssetId::from(&pool.asset_a), AssetId::from(&pool.asset_b));
+        let is_pair = (a.is_lovelace() && b == token) || (b.is_lovelace() && a == token);
+        if is_pair && pool.reserve_a > 0 && pool.reserve_b > 0 {
+            venues.push(Venue::Pool(
+                pool,
+                Direction::for_input(&pool.asset_a, &input),
+            ));
+        }
+    }
+
+    let fill = |venue: &Venue, amount_in: u64| {
+        let latency = venue.latency();
+        VenueFill {
+            venue: venue.name(),
+            amount_in,
+            amount_out: venue.out(side, amount_in),
+            settlement_slots: latency.expected_slots,
+            batched: latency.batched,
+        }
+    };
+    let adjusted = |f: &VenueFill| discounted(f.amount_out, f.settlement_slots, penalty_bps_per_slot);
+    let order_book = book.map(|b| {
+        let venue = Venue::Book(b);
+        fill(&venue, size.min(book_capacity(b, side)))
+    });
+    let best_pool = venues
+        .iter()
+        .filter(|v| matches!(v, Venue::Pool(..)))
+        .map(|v| fill(v, size))
+        .max_by_key(adjusted);
+
+    let mut allocated = vec![0u64; venues.len()];
+    let slice = (size / EXECUTION_SLICES).max(1);
+    let mut left = size;
+    while left > 0 && !venues.is_empty() {
+        let step = slice.min(left);
+        let (best, gain) = venues
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let slots = v.latency().expected_slots;
+                let out = |amount| discounted(v.out(side, amount), slots, penalty_bps_per_slot);
+                (i, out(allocated[i] + step).saturating_sub(out(allocated[i])))
+            })
+            .max_by_key(|(i, gain)| (*gain, std::cmp::Reverse(*i)))
+            .unwrap();
+        if gain == 0 {
+            break;
+        }
+        allocated[best] += step;
+        left -= step;
+    }
+    let split: Vec<VenueFill> = venues
+        .iter()
+        .zip(&allocated)
+        .filter(|(_, amount)| **amount > 0)
+        .map(|(v, amount)| fill(v, *amount))
+        .collect();
+    let split_adjusted: u64 = split.iter().map(adjusted).sum();
+
+    // Prefer one venue when it matches the split: fewer transactions.
+    let single = [
+        order_book.clone().filter(|f| f.amount_in == size),
+        best_pool.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .max_by_key(adjusted);
+    let venues = match single {
+        Some(f) if adjusted(&f) >= split_adjusted => vec![f],
+        _ => split.clone(),
+    };
+    let expected_out = venues.iter().map(|f| f.amount_out).sum();
+    let adjusted_out = venues.iter().map(adjusted).sum();
+    let settlement_slots = venues.iter().map(|f| f.settlement_slots).max().unwrap_or(0);
+
+    BestExecution {
+        token: token.to_string(),
+        side,
+        size,
+        order_book,
+        best_pool,
+        split,
+        venues,
+        expected_out,
+        settlement_slots,
+        latency_penalty_bps_per_slot: penalty_bps_per_slot,
+        adjusted_out,
+    }
+}
+
+/// Compare filling `size` of `token` on the ChadSwap order book, through the
+/// ADA pools of `dexes`, and split across both.
+///
+/// A ChadSwap failure is logged and the plan falls back to the pools.
+pub async fn best_execution(
+    dexes: &[&dyn BaseDex],
+    chadswap: &ChadSwap,
+    token: impl Into<AssetId>,
+    side: Side,
+    size: u64,
+) -> Result<BestExecution> {
+    best_execution_with_latency(dexes, chadswap, token, side, size, 0.0).await
+}
+
+/// [`best_execution`] weighing settlement latency as in
+/// [`plan_execution_with_latency`].
+pub async fn best_execution_with_latency(
+    dexes: &[&dyn BaseDex],
+    chadswap: &ChadSwap,
+    token: impl Into<AssetId>,
+    side: Side,
+    size: u64,
+    penalty_bps_per_slot: f64,
+) -> Result<BestExecution> {
+    let token = token.into();
+    let (pools, book) = futures::join!(
+        pools_with_token(dexes, token.clone()),
+        chadswap.get_orders_by_token(token.as_str())
+    );
+    let book = match book {
+        Ok(book) => Some(book),
+        Err(e) => {
+            eprintln!("[aggregator] ChadSwap failed: {}", e);
+            None
+        }
+    };
+    Ok(plan_execution_with_latency(
+        book.as_ref(),
+        &pools?,
+        token,
+        side,
+        size,
+        penalty_bps_per_slot,
+    ))
+}
+
+/// A set of DEXes with unique identifiers, queried together.
+///
+/// DEXes are held as `Arc`s so the set can be shared with a
+/// [`PoolWatcher`](crate::watcher::PoolWatcher) or
+/// [`ChainAdapter`](crate::watcher::ChainAdapter) via [`shared`](Self::shared).
+#[derive(Clone, Default)]
+pub struct Aggregator {
+    dexes: Vec<Arc<dyn BaseDex>>,
+}
+
+impl Aggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in AMM DEXes (those of `--dex all`), each with `filter`.
+    pub fn with_builtin_dexes(kupo: &KupoApi, filter: &PoolFilter) -> Self {
+        Self::with_builtin_dexes_and_overrides(kupo, filter, &OverridesConfig::new())
+            .expect("empty overrides are always valid")
+    }
+
+    /// [`with_builtin_dexes`](Self::with_builtin_dexes) with protocol
+    /// constants replaced by `overrides`.
+    pub fn with_builtin_dexes_and_overrides(
+        kupo: &KupoApi,
+        filter: &PoolFilter,
+        overrides: &OverridesConfig,
+    ) -> Result<Self> {
+        let f = || filter.clone();
+        let o = |identifier: &str| overrides.get(identifier);
+        let builtin: Vec<Arc<dyn BaseDex>> = vec![
+            Arc::new(
+                MinswapV1::new(kupo)
+                    .with_pool_filter(f())
+                    .with_overrides(&o("MinswapV1"))?,
+            ),
+            Arc::new(
+                MinswapV2::new(kupo)
+                    .with_pool_filter(f())
+                    .with_overrides(&o("MinswapV2"))?,
+            ),
+            Arc::new(
+                SundaeSwapV1::new(kupo)
+                    .with_pool_filter(f())
+                    .with_overrides(&o("SundaeSwapV1"))?,
+            ),
+            Arc::new(
+                SundaeSwapV3::new(kupo)
+                    .with_pool_filter(f())
+                    .with_overrides(&o("SundaeSwapV3"))?,
+            ),
+            Arc::new(
+                WingRiders::new(kupo)
+                    .with_pool_filter(f())
+                    .with_overrides(&o("WingRiders"))?,
+            ),
+            Arc::new(
+                WingRidersV2::new(kupo)
+                    .with_pool_filter(f())
+                    .with_overrides(&o("WingRidersV2"))?,
+            ),
+            Arc::new(
+                CSwap::new(kupo)
+                    .with_pool_filter(f())
+                    .with_overrides(&o("CSwap"))?,
+            ),
+            Arc::new(VyFinance::new(kupo).with_pool_filter(f())),
+        ];
+        Ok(Self { dexes: builtin })
+    }
+
+    /// Add a DEX implementation. Identifiers must be unique, since they key
+    /// pools in summaries, exports and watcher events.
+    pub fn register_dex(&mut self, dex: Box<dyn BaseDex>) -> Result<()> {
+        self.register_shared(Arc::from(dex))
+    }
+
+    /// [`register_dex`](Self::register_dex) for a DEX already behind an `Arc`.
+    pub fn register_shared(&mut self, dex: Arc<dyn BaseDex>) -> Result<()> {
+        if self.dex(dex.identifier()).is_some() {
+            return Err(anyhow!("DEX '{}' is already registered", dex.identifier()));
+        }
+        self.dexes.push(dex);
+        Ok(())
+    }
+
+    /// Builder form of [`register_dex`](Self::register_dex).
+    pub fn with_dex(mut self, dex: Box<dyn BaseDex>) -> Result<Self> {
+        self.register_dex(dex)?;
+        Ok(self)
+    }
+
+    pub fn dex(&self, identifier: &str) -> Option<&dyn BaseDex> {
+        self.dexes
+            .iter()
+            .find(|d| d.identifier() == identifier)
+            .map(|d| d.as_ref())
+    }
+
+    pub fn identifiers(&self) -> Vec<&str> {
+        self.dexes.iter().map(|d| d.identifier()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.dexes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dexes.is_empty()
+    }
+
+    /// Borrowed view for the free functions of this module and
+    /// [`top_pools_from`](crate::analytics::top_pools_from).
+    pub fn dexes(&self) -> Vec<&dyn BaseDex> {
+        self.dexes.iter().map(|d| d.as_ref()).collect()
+    }
+
+    /// Shared handles, e.g. for `PoolWatcher::new` or `ChainAdapter::new`.
+    pub fn shared(&self) -> Vec<Arc<dyn BaseDex>> {
+        self.dexes.clone()
+    }
+
+    pub async fn pair_summary(
+        &self,
+        token_a: impl Into<AssetId>,
+        token_b: impl Into<AssetId>,
+    ) -> Result<PairSummary> {
+        pair_summary(&self.dexes(), token_a, token_b).await
+    }
+
+    pub async fn pair_pools(
+        &self,
+        token_a: impl Into<AssetId>,
+        token_b: impl Into<AssetId>,
+    ) -> Result<Vec<LiquidityPool>> {
+        pair_pools(&self.dexes(), token_a, token_b).await
+    }
+
+    pub async fn pools_by_id(&self, pool_id: &str) -> Result<Vec<LiquidityPool>> {
+        pools_by_id(&self.dexes(), pool_id).await
+    }
+
+    pub async fn pools_with_token(&self, token: impl Into<AssetId>) -> Result<Vec<LiquidityPool>> {
+        pools_with_token(&self.dexes(), token).await
+    }
+
+    /// [`best_execution`] over this set's DEXes.
+    pub async fn best_execution(
+        &self,
+        chadswap: &ChadSwap,
+        token: impl Into<AssetId>,
+        side: Side,
+        size: u64,
+    ) -> Result<BestExecution> {
+        best_execution(&self.dexes(), chadswap, token, side, size).await
+    }
+
+    /// Every pool of every DEX, fetched concurrently (e.g. for an export).
+    ///
+    /// A DEX that fails to answer is logged and left out.
+    pub async fn all_liquidity_pools(&self) -> Vec<LiquidityPool> {
+        let results = join_all(self.dexes.iter().map(|dex| dex.all_liquidity_pools())).await;
+        let mut pools = Vec::new();
+        for (dex, result) in self.dexes.iter().zip(results) {
+            match result {
+                Ok(found) => pools.extend(found),
+                Err(e) => eprintln!("[aggregator] {} failed: {}", dex.identifier(), e),
+            }
+        }
+        pools
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    #[test]
+    fn test_summarize_orients_and_aggregates() {
+        let snek = from_identifier(SNEK, 0);
+        let pools = vec![
+            LiquidityPool::new(
+                "MinswapV2",
+                Token::Lovelace,
+                snek.clone(),
+                3_000_000,
+                750,
+                "a",
+                0.3,
+                "p1",
+            ),
+            // Reversed orientation, four times the price.
+            LiquidityPool::new(
+                "SundaeSwapV3",
+                snek.clone(),
+                Token::Lovelace,
+                1_000,
+                1_000_000,
+                "b",
+                0.3,
+                "p2",
+            ),
+        ];
+
+        let s = summarize(
+            &pools,
+            "lovelace",
+            format!("{}.{}", &SNEK[..56], &SNEK[56..]),
+        );
+        assert_eq!(s.pool_count, 2);
+        assert_eq!(s.dexes, vec!["MinswapV2", "SundaeSwapV3"]);
+        assert_eq!(s.total_reserve_a, 4_000_000);
+        assert_eq!(s.total_reserve_b, 1_750);
+        assert_eq!(s.tvl_lovelace, Some(8_000_000));
+        assert_eq!(s.best_price, Some(1000.0));
+        assert_eq!(s.best_price_pool.unwrap().pool_id, "p2");
+        assert_eq!(s.deepest_pool.unwrap().pool_id, "p1");
+        // (250 * 3 + 1000 * 1) / 4
+        assert_eq!(s.weighted_price, Some(437.5));
+    }
+
+    #[test]
+    fn test_plan_execution_splits_book_and_pool() {
+        let snek = from_identifier(SNEK, 0);
+        let pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            snek.clone(),
+            1_000_000_000,
+            1_000_000,
+            "a",
+            0.3,
+            "p1",
+        );
+        let ask = |price: u64, amount: u64, output_ref: &str| Order {
+            asset: snek.clone(),
+            amount,
+            price,
+            price_denominator: 1,
+            is_buy: false,
+            output_ref: output_ref.to_string(),
+            owner: None,
+            created_at: None,
+        };
+        // Pool mid price is 1000 lovelace per token; the book has 500 tokens at 900.
+        let book = OrderBook {
+            token_id: SNEK.to_string(),
+            buy_orders: vec![],
+            sell_orders: vec![ask(900, 500, "o1")],
+        };
+
+        let small = plan_execution(Some(&book), std::slice::from_ref(&pool), SNEK, Side::Buy, 100_000);
+        assert_eq!(small.venues.len(), 1);
+        assert_eq!(small.venues[0].venue, "ChadSwap");
+        assert_eq!(small.expected_out, 111);
+
+        // Too large for the book: the rest goes through the pool.
+        let large = plan_execution(Some(&book), &[pool], SNEK, Side::Buy, 10_000_000);
+        assert_eq!(large.order_book.as_ref().unwrap().amount_in, 450_000);
+        assert_eq!(large.venues.len(), 2);
+        assert!(large.expected_out > large.best_pool.as_ref().unwrap().amount_out);
+        assert_eq!(
+            large.venues.iter().map(|f| f.amount_in).sum::<u64>(),
+            10_000_000
+        );
+    }
+
+    #[test]
+    fn test_latency_penalty_prefers_direct_fills() {
+        let snek = from_identifier(SNEK, 0);
+        let pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            snek.clone(),
+            1_000_000_000_000,
+            1_000_000_000,
+            "a",
+            0.3,
+            "p1",
+        );
+        // 0.5% above the pool's mid price: worse than the pool's 0.3% fee.
+        let book = OrderBook {
+            token_id: SNEK.to_string(),
+            buy_orders: vec![],
+            sell_orders: vec![Order {
+                asset: snek,
+                amount: 1_000_000,
+                price: 1005,
+                price_denominator: 1,
+                is_buy: false,
+                output_ref: "o1".to_string(),
+                owner: None,
+                created_at: None,
+            }],
+        };
+        let pools = std::slice::from_ref(&pool);
+
+        let plain = plan_execution(Some(&book), pools, SNEK, Side::Buy, 100_000_000);
+        assert_eq!(plain.venues[0].venue, "MinswapV2:p1");
+        assert!(plain.venues[0].batched);
+        assert_eq!(plain.settlement_slots, crate::dex::minswap_v2::SETTLEMENT.expected_slots);
+        assert_eq!(plain.adjusted_out, plain.expected_out);
+
+        // 1 bps per slot: the batcher's wait costs more than the book's premium.
+        let weighted =
+            plan_execution_with_latency(Some(&book), pools, SNEK, Side::Buy, 100_000_000, 1.0);
+        assert_eq!(weighted.venues.len(), 1);
+        assert_eq!(weighted.venues[0].venue, "ChadSwap");
+        assert!(!weighted.venues[0].batched);
+        assert_eq!(weighted.settlement_slots, chadswap::SETTLEMENT.expected_slots);
+        assert!(weighted.adjusted_out < weighted.expected_out);
+    }
+
+    #[test]
+    fn test_counter_assets() {
+        let snek = from_identifier(SNEK, 0);
+        let pool = |a: Token, b: Token| LiquidityPool::new("MinswapV2", a, b, 1, 1, "a", 0.3, "p");
+        let pools = vec![
+            pool(Token::Lovelace, snek.clone()),
+            pool(snek.clone(), Token::Lovelace),
+            pool(Token::Lovelace, Token::Lovelace),
+        ];
+        assert_eq!(counter_assets(&pools, SNEK), vec![AssetId::lovelace()]);
+        assert_eq!(counter_assets(&pools, "ADA").len(), 2);
+    }
+
+    #[test]
+    fn test_register_dex_rejects_duplicates() {
+        use crate::dex::generic::{GenericAmmConfig, GenericAmmDex};
+
+        let kupo = KupoApi::new("http://localhost:1442");
+        let config = GenericAmmConfig::from_json_str(
+            r#"{"dex":[{"identifier":"ForkSwap","pool_address":"addr1","lp_token_policy_id":"bb"}]}"#,
+        )
+        .unwrap()
+        .remove(0);
+        let fork = || Box::new(GenericAmmDex::new(&kupo, config.clone()).unwrap());
+
+        let mut aggregator = Aggregator::with_builtin_dexes(&kupo, &PoolFilter::default());
+        let builtin = aggregator.len();
+        aggregator.register_dex(fork()).unwrap();
+        assert!(aggregator.register_dex(fork()).is_err());
+        assert_eq!(aggregator.len(), builtin + 1);
+        assert_eq!(aggregator.dex("ForkSwap").unwrap().pool_address(), "addr1");
+        assert!(aggregator.register_dex(Box::new(MinswapV2::new(&kupo))).is_err());
+    }
+
+    /// Pools straight from the output value; outputs named `bad` fail to parse.
+    struct StubDex {
+        kupo: KupoApi,
+        utxos: Vec<crate::models::Utxo>,
+    }
+
+    #[async_trait::async_trait]
+    impl BaseDex for StubDex {
+        fn identifier(&self) -> &str {
+            "Stub"
+        }
+
+        fn pool_address(&self) -> &str {
+            "addr1stub"
+        }
+
+        fn lp_token_policy_id(&self) -> &str {
+            ""
+        }
+
+        fn kupo(&self) -> &KupoApi {
+            &self.kupo
+        }
+
+        async fn all_liquidity_pool_utxos(&self) -> Result<Vec<crate::models::Utxo>> {
+            Ok(self.utxos.clone())
+        }
+
+        async fn liquidity_pool_from_utxo(
+            &self,
+            utxo: &crate::models::Utxo,
+            _pool_id: &str,
+        ) -> Result<Option<LiquidityPool>> {
+            if utxo.tx_hash == "bad" {
+                return Err(anyhow!("malformed output"));
+            }
+            let units: Vec<_> = utxo.amount.iter().collect();
+            let Some((a, b)) = crate::models::reserve_pair(&units) else {
+                return Ok(None);
+            };
+            Ok(Some(LiquidityPool::new(
+                "Stub",
+                from_identifier(&a.unit, 0),
+                from_identifier(&b.unit, 0),
+                a.quantity,
+                b.quantity,
+                &utxo.address,
+                0.3,
+                &utxo.tx_hash,
+            )))
+        }
+
+        async fn liquidity_pool_from_pool_id(&self, _pool_id: &str) -> Result<Option<LiquidityPool>> {
+            Ok(None)
+        }
+
+        async fn liquidity_pools_from_token(
+            &self,
+            _token_b: AssetId,
+            _token_a: AssetId,
+        ) -> Result<Vec<LiquidityPool>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pools_with_token_skips_unparsable_outputs() {
+        let output = |tx_hash: &str, token: &str| {
+            let value = std::collections::HashMap::from([
+                ("lovelace".to_string(), 5_000_000),
+                (token.to_string(), 7_000),
+            ]);
+            let mut utxo = crate::models::Utxo::from_raw_output("addr1stub", &value, None);
+            utxo.tx_hash = tx_hash.to_string();
+            utxo
+        };
+        let dex = StubDex {
+            kupo: KupoApi::new("http://127.0.0.1:9"),
+            utxos: vec![
+                output("bad", SNEK),
+                output("p1", SNEK),
+                output("p2", &"ab".repeat(29)),
+                output("p3", SNEK),
+            ],
+        };
+
+        let pools = dex.liquidity_pools_with_token(SNEK.into()).await.unwrap();
+        let ids: Vec<&str> = pools.iter().map(|p| p.pool_id.as_str()).collect();
+        assert_eq!(ids, ["p1", "p3"]);
+
+        let pools = pools_with_token(&[&dex], SNEK).await.unwrap();
+        assert_eq!(pools.len(), 2);
+    }
+}
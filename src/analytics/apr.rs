@@ -0,0 +1,195 @@
+//! Fee APR estimated from pool-UTXO turnover.
+//!
+//! Kupo has no trade history, but it keeps every output a pool ever sat in.
+//! Consecutive pool states are diffed: a transition where one reserve grows
+//! while the other shrinks is a swap, and the growth of `reserve_a` (or the
+//! drop, for swaps into `asset_b`) is counted as volume in `asset_a`.
+//! Deposits and withdrawals move both reserves the same way and are ignored.
+//!
+//! Batched orders are netted within a transaction, so the estimate is a lower
+//! bound on real volume.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use crate::dex::BaseDex;
+use crate::models::LiquidityPool;
+use crate::utils::asset_pattern;
+
+const DAY_SLOTS: u64 = 86_400;
+const YEAR_SLOTS: u64 = 365 * DAY_SLOTS;
+
+/// Lookback window of [`pool_apr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AprWindow {
+    #[default]
+    Day,
+    Week,
+}
+
+impl AprWindow {
+    pub fn slots(&self) -> u64 {
+        match self {
+            AprWindow::Day => DAY_SLOTS,
+            AprWindow::Week => 7 * DAY_SLOTS,
+        }
+    }
+}
+
+impl FromStr for AprWindow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "24h" | "1d" | "day" => Ok(AprWindow::Day),
+            "7d" | "week" => Ok(AprWindow::Week),
+            other => Err(anyhow!("unknown window '{}': expected 24h or 7d", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolApr {
+    pub dex_identifier: String,
+    pub pool_id: String,
+    pub pair: String,
+    pub window: AprWindow,
+    /// Swap transitions seen in the window.
+    pub swaps: usize,
+    /// Estimated volume in `asset_a` base units.
+    pub volume_a: u64,
+    /// `volume_a * lp_fee_percent`: fees kept by liquidity providers.
+    pub fees_a: u64,
+    /// Twice the current `reserve_a`.
+    pub tvl_a: u64,
+    /// Fees over the window, annualised, as a percent of `tvl_a`.
+    pub apr_percent: f64,
+}
+
+/// Swap count and `asset_a` volume across `history` (oldest first).
+pub fn turnover(history: &[LiquidityPool]) -> (usize, u64) {
+    let mut swaps = 0;
+    let mut volume = 0u64;
+    for w in history.windows(2) {
+        let (prev, cur) = (&w[0], &w[1]);
+        let a_up = cur.reserve_a > prev.reserve_a;
+        let b_up = cur.reserve_b > prev.reserve_b;
+        let a_moved = cur.reserve_a != prev.reserve_a;
+        let b_moved = cur.reserve_b != prev.reserve_b;
+        if a_moved && b_moved && a_up != b_up {
+            swaps += 1;
+            volume = volume.saturating_add(cur.reserve_a.abs_diff(prev.reserve_a));
+        }
+    }
+    (swaps, volume)
+}
+
+/// APR of `pool` from its `history` over `window`.
+pub fn apr_from_history(
+    pool: &LiquidityPool,
+    history: &[LiquidityPool],
+    window: AprWindow,
+) -> PoolApr {
+    let (swaps, volume_a) = turnover(history);
+    let fees_a = (volume_a as f64 * pool.lp_fee_percent() / 100.0) as u64;
+    let tvl_a = pool.reserve_a.saturating_mul(2);
+    let apr_percent = if tvl_a > 0 {
+        fees_a as f64 / tvl_a as f64 * (YEAR_SLOTS / window.slots()) as f64 * 100.0
+    } else {
+        0.0
+    };
+    PoolApr {
+        dex_identifier: pool.dex_identifier.clone(),
+        pool_id: pool.pool_id.clone(),
+        pair: pool.pair(),
+        window,
+        swaps,
+        volume_a,
+        fees_a,
+        tvl_a,
+        apr_percent,
+    }
+}
+
+/// Estimate the fee APR of `pool_id` on `dex` over `window`.
+///
+/// The pool's history is every output, spent or not, holding its `pool_id`
+/// unit, so this only works for DEXes whose pool id is a unit kept in the pool
+/// UTXO. Returns `None` if the pool is not found.
+pub async fn pool_apr(
+    dex: &dyn BaseDex,
+    pool_id: &str,
+    window: AprWindow,
+) -> Result<Option<PoolApr>> {
+    let Some(pool) = dex.liquidity_pool_from_pool_id(pool_id).await? else {
+        return Ok(None);
+    };
+    let unit = pool.pool_id.replace('.', "");
+    if unit.len() <= 56 {
+        return Err(anyhow!(
+            "{} pool id {} is not an asset unit; history unavailable",
+            dex.identifier(),
+            pool.pool_id
+        ));
+    }
+
+    let kupo = dex.kupo();
+    let start = kupo.tip_slot().await?.saturating_sub(window.slots());
+    let mut utxos = kupo.get(&asset_pattern(&unit), false).await?;
+    utxos.retain(|u| u.amount.iter().any(|a| a.unit == unit));
+    utxos.sort_by_key(|u| (u.slot.unwrap_or(0), u.tx_index));
+
+    // Keep the last state before the window so the first swap in it is diffed.
+    let first = utxos
+        .iter()
+        .rposition(|u| u.slot.unwrap_or(0) < start)
+        .unwrap_or(0);
+    let mut history = Vec::new();
+    for utxo in &utxos[first..] {
+        if let Ok(Some(state)) = dex.liquidity_pool_from_utxo(utxo, &pool.pool_id).await {
+            history.push(state);
+        }
+    }
+    Ok(Some(apr_from_history(&pool, &history, window)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Token;
+
+    fn state(reserve_a: u64, reserve_b: u64) -> LiquidityPool {
+        LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            Token::Lovelace,
+            reserve_a,
+            reserve_b,
+            "addr",
+            0.3,
+            "pool",
+        )
+    }
+
+    #[test]
+    fn test_turnover_counts_swaps_only() {
+        let history = vec![
+            state(1_000_000, 1_000),
+            // swap a -> b
+            state(1_100_000, 910),
+            // deposit
+            state(2_200_000, 1_820),
+            // swap b -> a
+            state(2_000_000, 2_000),
+        ];
+        assert_eq!(turnover(&history), (2, 300_000));
+
+        let apr = apr_from_history(&history[3], &history, AprWindow::Day);
+        assert_eq!(apr.fees_a, 900);
+        assert_eq!(apr.tvl_a, 4_000_000);
+        // 900 / 4_000_000 * 365
+        assert!((apr.apr_percent - 8.2125).abs() < 1e-9);
+    }
+}
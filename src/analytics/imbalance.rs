@@ -0,0 +1,129 @@
+//! Buy/sell imbalance of the swap orders waiting for batchers, per pair.
+//!
+//! Pending orders settle within a few blocks, so a pair whose queue is mostly
+//! buys is likely to move up shortly. Both sides are summed in `token_a`
+//! units (lovelace for ADA pairs), token offers valued at their pool's price,
+//! and [`OrderImbalance::imbalance`] is `(buys - sells) / (buys + sells)`,
+//! from -1 (only sells of `token_b`) to 1 (only buys).
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::models::{AssetId, LiquidityPool};
+use crate::orderflow::{pair_orders, PendingOrder};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrderImbalance {
+    /// `lovelace` when the pair has it, else the lower identifier.
+    pub token_a: String,
+    pub token_b: String,
+    /// Orders offering `token_a` for `token_b`.
+    pub buy_orders: usize,
+    /// Orders offering `token_b` for `token_a`.
+    pub sell_orders: usize,
+    /// `token_a` offered for `token_b`.
+    pub buy_notional: u64,
+    /// Value in `token_a` of the `token_b` offered.
+    pub sell_notional: u64,
+    /// From -1 (all sells) to 1 (all buys); 0 without notional.
+    pub imbalance: f64,
+}
+
+/// Imbalance of every pair with pending orders, most notional first. Orders
+/// are matched to `pools` as in [`volume_by_pair`](crate::orderflow::volume_by_pair).
+pub fn order_imbalances(orders: &[PendingOrder], pools: &[LiquidityPool]) -> Vec<OrderImbalance> {
+    let mut pairs: BTreeMap<(String, String), OrderImbalance> = BTreeMap::new();
+    for placed in pair_orders(orders, pools) {
+        let pair = pairs
+            .entry((placed.token_a.clone(), placed.token_b.clone()))
+            .or_insert_with(|| OrderImbalance {
+                token_a: placed.token_a.clone(),
+                token_b: placed.token_b.clone(),
+                buy_orders: 0,
+                sell_orders: 0,
+                buy_notional: 0,
+                sell_notional: 0,
+                imbalance: 0.0,
+            });
+        if placed.sells_a {
+            pair.buy_orders += 1;
+            pair.buy_notional += placed.value_in_a;
+        } else {
+            pair.sell_orders += 1;
+            pair.sell_notional += placed.value_in_a;
+        }
+    }
+    let mut imbalances: Vec<OrderImbalance> = pairs.into_values().collect();
+    for pair in &mut imbalances {
+        let total = pair.buy_notional as f64 + pair.sell_notional as f64;
+        if total > 0.0 {
+            pair.imbalance = (pair.buy_notional as f64 - pair.sell_notional as f64) / total;
+        }
+    }
+    imbalances.sort_by_key(|p| std::cmp::Reverse(p.buy_notional as u128 + p.sell_notional as u128));
+    imbalances
+}
+
+/// Imbalance of the `token_a`/`token_b` pair (either order), or `None`
+/// without pending orders for it.
+pub fn order_imbalance(
+    orders: &[PendingOrder],
+    pools: &[LiquidityPool],
+    token_a: impl Into<AssetId>,
+    token_b: impl Into<AssetId>,
+) -> Option<OrderImbalance> {
+    let (a, b) = (token_a.into().to_string(), token_b.into().to_string());
+    order_imbalances(orders, pools)
+        .into_iter()
+        .find(|p| (p.token_a == a && p.token_b == b) || (p.token_a == b && p.token_b == a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+    use crate::models::Token;
+    use crate::orderflow::SwapKind;
+    use crate::quote::Direction;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    #[test]
+    fn test_imbalance_of_pending_orders() {
+        // 10 lovelace per SNEK.
+        let pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            from_identifier(SNEK, 0),
+            1_000_000_000,
+            100_000_000,
+            "addr",
+            0.3,
+            "p",
+        );
+        let order = |direction, amount_in| PendingOrder {
+            dex_identifier: "MinswapV2".to_string(),
+            output_ref: String::new(),
+            slot: None,
+            pool_id: "p".to_string(),
+            direction,
+            kind: SwapKind::ExactIn,
+            amount_in,
+            min_receive: 0,
+        };
+        let orders = [
+            order(Direction::AToB, 30_000_000),
+            order(Direction::AToB, 10_000_000),
+            order(Direction::BToA, 1_000_000),
+        ];
+
+        let pair = order_imbalance(&orders, &[pool], SNEK, "lovelace").unwrap();
+        assert_eq!((pair.buy_orders, pair.sell_orders), (2, 1));
+        assert_eq!(
+            (pair.buy_notional, pair.sell_notional),
+            (40_000_000, 10_000_000)
+        );
+        assert!((pair.imbalance - 0.6).abs() < 1e-12);
+        assert!(order_imbalances(&orders, &[]).is_empty());
+    }
+}
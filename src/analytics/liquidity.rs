@@ -0,0 +1,206 @@
+//! Where a token's liquidity sits: its share on each DEX, its largest pool and
+//! the ChadSwap order book behind it.
+//!
+//! Shares are of the token's own reserve, so ADA pools and token/token pools
+//! count alike. The Herfindahl–Hirschman index over pools (0–10,000) sums the
+//! squared percent shares: 10,000 means a single pool holds everything.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::TvlEstimator;
+use crate::dex::chadswap::ChadSwap;
+use crate::dex::BaseDex;
+use crate::models::{AssetId, BookDepth, LiquidityPool, OrderBook};
+
+/// A token's liquidity on one DEX.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DexLiquidity {
+    pub dex_identifier: String,
+    pub pool_count: usize,
+    /// Token base units held by the DEX's pools.
+    pub token_reserve: u64,
+    /// Percent of the token's pooled reserve.
+    pub share_percent: f64,
+    /// TVL of those pools; pools that cannot be valued are left out.
+    pub tvl_lovelace: u64,
+}
+
+/// The pool holding most of the token.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LargestPool {
+    pub dex_identifier: String,
+    pub pool_id: String,
+    pub pair: String,
+    pub token_reserve: u64,
+    pub share_percent: f64,
+    pub tvl_lovelace: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TokenLiquidity {
+    pub token: String,
+    pub pool_count: usize,
+    /// Token base units across every pool.
+    pub token_reserve: u64,
+    pub tvl_lovelace: u64,
+    /// Largest share first.
+    pub dexes: Vec<DexLiquidity>,
+    pub largest_pool: Option<LargestPool>,
+    /// Herfindahl–Hirschman index of the pools' shares, 0–10,000.
+    pub hhi: f64,
+    /// ChadSwap bids and asks, when the book could be fetched.
+    pub order_book: Option<BookDepth>,
+}
+
+fn share(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+/// Report on `token` from `pools` (others are ignored) and its order book.
+///
+/// TVL is valued against the ADA pools among `pools`, as in
+/// [`top_pools`](super::top_pools).
+pub fn liquidity_report(
+    token: impl Into<AssetId>,
+    pools: &[LiquidityPool],
+    book: Option<&OrderBook>,
+) -> TokenLiquidity {
+    let token = token.into();
+    let estimator = TvlEstimator::new(pools);
+    let holdings: Vec<(&LiquidityPool, u64)> = pools
+        .iter()
+        .filter_map(|pool| {
+            if AssetId::from(&pool.asset_a) == token {
+                Some((pool, pool.reserve_a))
+            } else if AssetId::from(&pool.asset_b) == token {
+                Some((pool, pool.reserve_b))
+            } else {
+                None
+            }
+        })
+        .collect();
+    let total: u64 = holdings.iter().map(|(_, reserve)| reserve).sum();
+
+    let mut by_dex: BTreeMap<&str, DexLiquidity> = BTreeMap::new();
+    for (pool, reserve) in &holdings {
+        let dex = by_dex
+            .entry(&pool.dex_identifier)
+            .or_insert_with(|| DexLiquidity {
+                dex_identifier: pool.dex_identifier.clone(),
+                pool_count: 0,
+                token_reserve: 0,
+                share_percent: 0.0,
+                tvl_lovelace: 0,
+            });
+        dex.pool_count += 1;
+        dex.token_reserve += reserve;
+        dex.tvl_lovelace += estimator.tvl(pool).unwrap_or(0);
+    }
+    let mut dexes: Vec<DexLiquidity> = by_dex.into_values().collect();
+    for dex in &mut dexes {
+        dex.share_percent = share(dex.token_reserve, total);
+    }
+    dexes.sort_by_key(|dex| std::cmp::Reverse(dex.token_reserve));
+
+    let largest_pool =
+        holdings
+            .iter()
+            .max_by_key(|(_, reserve)| *reserve)
+            .map(|(pool, reserve)| LargestPool {
+                dex_identifier: pool.dex_identifier.clone(),
+                pool_id: pool.pool_id.clone(),
+                pair: pool.pair(),
+                token_reserve: *reserve,
+                share_percent: share(*reserve, total),
+                tvl_lovelace: estimator.tvl(pool),
+            });
+
+    TokenLiquidity {
+        token: token.to_string(),
+        pool_count: holdings.len(),
+        token_reserve: total,
+        tvl_lovelace: dexes.iter().map(|dex| dex.tvl_lovelace).sum(),
+        dexes,
+        largest_pool,
+        hhi: holdings
+            .iter()
+            .map(|(_, reserve)| share(*reserve, total).powi(2))
+            .sum(),
+        order_book: book.map(OrderBook::depth),
+    }
+}
+
+/// Fetch the pools of `token` from `dexes` and its ChadSwap book, and report
+/// where its liquidity sits.
+///
+/// A ChadSwap failure is logged and the report goes without the book.
+pub async fn token_liquidity(
+    dexes: &[&dyn BaseDex],
+    chadswap: &ChadSwap,
+    token: impl Into<AssetId>,
+) -> Result<TokenLiquidity> {
+    let token = token.into();
+    let (pools, book) = futures::join!(
+        crate::aggregator::pools_with_token(dexes, token.clone()),
+        chadswap.get_orders_by_token(token.as_str())
+    );
+    let book = match book {
+        Ok(book) => Some(book),
+        Err(e) => {
+            eprintln!("[analytics] ChadSwap failed: {}", e);
+            None
+        }
+    };
+    Ok(liquidity_report(token, &pools?, book.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+    use crate::models::Token;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+    const HOSKY: &str = "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235484f534b59";
+
+    #[test]
+    fn test_liquidity_report_shares() {
+        let (snek, hosky) = (from_identifier(SNEK, 0), from_identifier(HOSKY, 0));
+        let pool = |dex: &str, a: &Token, b: &Token, ra: u64, rb: u64, id: &str| {
+            LiquidityPool::new(dex, a.clone(), b.clone(), ra, rb, "addr", 0.3, id)
+        };
+        let pools = vec![
+            pool("MinswapV2", &Token::Lovelace, &snek, 6_000, 60_000, "m1"),
+            pool("MinswapV2", &Token::Lovelace, &snek, 1_000, 10_000, "m2"),
+            pool("SundaeSwapV3", &snek, &hosky, 30_000, 5, "s1"),
+            pool(
+                "SundaeSwapV3",
+                &Token::Lovelace,
+                &hosky,
+                1_000,
+                1_000,
+                "other",
+            ),
+        ];
+
+        let report = liquidity_report(SNEK, &pools, None);
+        assert_eq!((report.pool_count, report.token_reserve), (3, 100_000));
+        let dexes: Vec<(&str, f64)> = report
+            .dexes
+            .iter()
+            .map(|d| (d.dex_identifier.as_str(), d.share_percent))
+            .collect();
+        assert_eq!(dexes, [("MinswapV2", 70.0), ("SundaeSwapV3", 30.0)]);
+        // 30,000 SNEK at 0.1 ADA from the deepest ADA pool, both sides.
+        assert_eq!(report.dexes[1].tvl_lovelace, 6_000);
+        assert_eq!(report.largest_pool.as_ref().unwrap().pool_id, "m1");
+        assert!((report.hhi - (60f64.powi(2) + 10f64.powi(2) + 30f64.powi(2))).abs() < 1e-9);
+        assert!(report.order_book.is_none());
+    }
+}
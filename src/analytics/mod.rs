@@ -0,0 +1,242 @@
+//! Rankings, fee APR, risk signals and liquidity concentration over pool
+//! snapshots, and the buy/sell imbalance of pending orders.
+
+pub mod apr;
+pub mod imbalance;
+pub mod liquidity;
+pub mod risk;
+
+pub use apr::{pool_apr, AprWindow, PoolApr};
+pub use imbalance::{order_imbalance, order_imbalances, OrderImbalance};
+pub use liquidity::{liquidity_report, token_liquidity, TokenLiquidity};
+pub use risk::{risk, MintAuthority, RiskLevel, RiskReport};
+
+use anyhow::{anyhow, Result};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::dex::BaseDex;
+use crate::models::{token_identifier, LiquidityPool};
+
+/// What [`top_pools`] ranks by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankBy {
+    /// Total value locked in lovelace. Token/token pools are valued through the
+    /// deepest ADA pool of either token in the same snapshot.
+    #[default]
+    Tvl,
+    /// Lovelace reserve; token/token pools are left out.
+    AdaReserve,
+}
+
+impl FromStr for RankBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tvl" => Ok(RankBy::Tvl),
+            "ada_reserve" | "ada" => Ok(RankBy::AdaReserve),
+            other => Err(anyhow!(
+                "unknown ranking '{}': expected tvl or ada_reserve",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RankedPool {
+    pub rank: usize,
+    pub dex_identifier: String,
+    pub pool_id: String,
+    pub pair: String,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub ada_reserve: Option<u64>,
+    pub tvl_lovelace: Option<u64>,
+}
+
+fn ada_reserve(pool: &LiquidityPool) -> Option<u64> {
+    if pool.asset_a.is_lovelace() {
+        Some(pool.reserve_a)
+    } else if pool.asset_b.is_lovelace() {
+        Some(pool.reserve_b)
+    } else {
+        None
+    }
+}
+
+/// Token identifier → (lovelace reserve, token reserve) of its deepest ADA pool.
+fn ada_quotes<'a>(
+    pools: impl IntoIterator<Item = &'a LiquidityPool>,
+) -> HashMap<String, (u64, u64)> {
+    let mut quotes: HashMap<String, (u64, u64)> = HashMap::new();
+    for pool in pools {
+        let (token, ada, other) = if pool.asset_a.is_lovelace() {
+            (&pool.asset_b, pool.reserve_a, pool.reserve_b)
+        } else if pool.asset_b.is_lovelace() {
+            (&pool.asset_a, pool.reserve_b, pool.reserve_a)
+        } else {
+            continue;
+        };
+        if other == 0 {
+            continue;
+        }
+        let entry = quotes
+            .entry(token_identifier(token))
+            .or_insert((ada, other));
+        if ada > entry.0 {
+            *entry = (ada, other);
+        }
+    }
+    quotes
+}
+
+fn tvl(pool: &LiquidityPool, quotes: &HashMap<String, (u64, u64)>) -> Option<u64> {
+    if let Some(ada) = ada_reserve(pool) {
+        return Some(ada.saturating_mul(2));
+    }
+    let value = |id: String, amount: u64| {
+        quotes
+            .get(&id)
+            .map(|(ada, tokens)| (amount as u128 * *ada as u128 / *tokens as u128) as u64)
+    };
+    value(token_identifier(&pool.asset_a), pool.reserve_a)
+        .map(|v| v.saturating_mul(2))
+        .or_else(|| {
+            value(token_identifier(&pool.asset_b), pool.reserve_b).map(|v| v.saturating_mul(2))
+        })
+}
+
+/// Values pools in lovelace against the ADA pools of one snapshot, as
+/// [`RankBy::Tvl`] does.
+#[derive(Debug, Clone, Default)]
+pub struct TvlEstimator {
+    quotes: HashMap<String, (u64, u64)>,
+}
+
+impl TvlEstimator {
+    pub fn new<'a>(pools: impl IntoIterator<Item = &'a LiquidityPool>) -> Self {
+        Self {
+            quotes: ada_quotes(pools),
+        }
+    }
+
+    /// TVL of `pool` in lovelace; `None` for token/token pools neither of
+    /// whose tokens has an ADA pool in the snapshot.
+    pub fn tvl(&self, pool: &LiquidityPool) -> Option<u64> {
+        tvl(pool, &self.quotes)
+    }
+}
+
+/// The `n` largest pools of `pools` by `by`. Pools that cannot be valued are left out.
+pub fn top_pools(pools: &[LiquidityPool], n: usize, by: RankBy) -> Vec<RankedPool> {
+    let estimator = TvlEstimator::new(pools);
+    let mut ranked: Vec<(u64, RankedPool)> = pools
+        .iter()
+        .filter_map(|pool| {
+            let ada = ada_reserve(pool);
+            let tvl = estimator.tvl(pool);
+            let key = match by {
+                RankBy::Tvl => tvl?,
+                RankBy::AdaReserve => ada?,
+            };
+            Some((
+                key,
+                RankedPool {
+                    rank: 0,
+                    dex_identifier: pool.dex_identifier.clone(),
+                    pool_id: pool.pool_id.clone(),
+                    pair: pool.pair(),
+                    reserve_a: pool.reserve_a,
+                    reserve_b: pool.reserve_b,
+                    ada_reserve: ada,
+                    tvl_lovelace: tvl,
+                },
+            ))
+        })
+        .collect();
+
+    ranked.sort_by_key(|(key, _)| std::cmp::Reverse(*key));
+    ranked
+        .into_iter()
+        .take(n)
+        .enumerate()
+        .map(|(i, (_, mut pool))| {
+            pool.rank = i + 1;
+            pool
+        })
+        .collect()
+}
+
+/// Fetch every pool of `dexes` concurrently and rank them together.
+///
+/// A DEX that fails is logged and left out of the ranking.
+pub async fn top_pools_from(
+    dexes: &[&dyn BaseDex],
+    n: usize,
+    by: RankBy,
+) -> Result<Vec<RankedPool>> {
+    let results = join_all(dexes.iter().map(|dex| dex.all_liquidity_pools())).await;
+    let mut pools = Vec::new();
+    for (dex, result) in dexes.iter().zip(results) {
+        match result {
+            Ok(found) => pools.extend(found),
+            Err(e) => eprintln!("[analytics] {} failed: {}", dex.identifier(), e),
+        }
+    }
+    Ok(top_pools(&pools, n, by))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+    use crate::models::Token;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+    const HOSKY: &str = "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235484f534b59";
+
+    #[test]
+    fn test_top_pools_ranks_by_tvl_and_ada() {
+        let snek = from_identifier(SNEK, 0);
+        let hosky = from_identifier(HOSKY, 0);
+        let pools = vec![
+            LiquidityPool::new(
+                "MinswapV2",
+                Token::Lovelace,
+                snek.clone(),
+                100,
+                1_000,
+                "a",
+                0.3,
+                "small",
+            ),
+            LiquidityPool::new(
+                "MinswapV2",
+                Token::Lovelace,
+                snek.clone(),
+                5_000,
+                50_000,
+                "a",
+                0.3,
+                "big",
+            ),
+            // 10_000 SNEK at 0.1 ADA = 1_000 lovelace per side.
+            LiquidityPool::new("MinswapV2", snek, hosky, 10_000, 7, "a", 0.3, "token"),
+        ];
+
+        let by_tvl = top_pools(&pools, 2, RankBy::Tvl);
+        let ids: Vec<&str> = by_tvl.iter().map(|p| p.pool_id.as_str()).collect();
+        assert_eq!(ids, ["big", "token"]);
+        assert_eq!(by_tvl[1].tvl_lovelace, Some(2_000));
+        assert_eq!(by_tvl[1].rank, 2);
+
+        let by_ada = top_pools(&pools, 10, RankBy::AdaReserve);
+        assert_eq!(by_ada.len(), 2);
+        assert_eq!("ada_reserve".parse::<RankBy>().unwrap(), RankBy::AdaReserve);
+    }
+}
@@ -0,0 +1,264 @@
+//! Rug-pull heuristics for a single pool.
+//!
+//! Three signals feed a 0–100 score:
+//! - LP concentration: share of the LP supply held by the largest address;
+//! - reserve removal: the largest drop of either reserve between consecutive
+//!   snapshots of the pool (e.g. collected by the watcher);
+//! - mint authority: whether the traded token's minting policy can still mint.
+//!
+//! The score is a heuristic for triage, not a verdict.
+
+use anyhow::Result;
+use ciborium::value::Value;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::dex::cbor::{decode_cbor, value_to_u64};
+use crate::kupo::KupoApi;
+use crate::models::{LiquidityPool, Token};
+use crate::utils::asset_pattern;
+
+/// Whether a minting policy can still mint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MintAuthority {
+    /// Native script whose every path is time-locked in the past.
+    Locked,
+    /// Policy script not known to Kupo, or not parseable.
+    Unknown,
+    /// Signature-based native script or a Plutus policy.
+    Active,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskSignals {
+    /// Percent of the LP supply held by the largest holder (`None` without an LP unit).
+    pub lp_top_holder_percent: Option<f64>,
+    /// Largest drop of either reserve between consecutive snapshots, in percent.
+    pub max_reserve_drop_percent: Option<f64>,
+    /// Worst mint authority across the pool's non-ADA tokens.
+    pub mint_authority: MintAuthority,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskReport {
+    pub dex_identifier: String,
+    pub pool_id: String,
+    pub pair: String,
+    pub signals: RiskSignals,
+    /// 0 (no signal) to 100.
+    pub score: u8,
+    pub level: RiskLevel,
+    pub reasons: Vec<String>,
+}
+
+impl RiskSignals {
+    /// Score the signals: up to 35 points for LP concentration above 50%, up to
+    /// 40 for reserve removal, 25 for an active mint authority (10 if unknown).
+    pub fn score(&self) -> (u8, Vec<String>) {
+        let mut score = 0.0;
+        let mut reasons = Vec::new();
+        if let Some(top) = self.lp_top_holder_percent.filter(|p| *p > 50.0) {
+            score += (top - 50.0) / 50.0 * 35.0;
+            reasons.push(format!("largest LP holder owns {:.1}% of supply", top));
+        }
+        if let Some(drop) = self.max_reserve_drop_percent.filter(|d| *d > 10.0) {
+            score += (drop * 0.5).min(40.0);
+            reasons.push(format!("reserve dropped {:.1}% between snapshots", drop));
+        }
+        match self.mint_authority {
+            MintAuthority::Active => {
+                score += 25.0;
+                reasons.push("token minting policy can still mint".to_string());
+            }
+            MintAuthority::Unknown => score += 10.0,
+            MintAuthority::Locked => {}
+        }
+        (score.round().min(100.0) as u8, reasons)
+    }
+}
+
+fn level(score: u8) -> RiskLevel {
+    match score {
+        0..=29 => RiskLevel::Low,
+        30..=59 => RiskLevel::Medium,
+        _ => RiskLevel::High,
+    }
+}
+
+/// Largest single-step drop of either reserve across `history` (oldest first).
+pub fn max_reserve_drop_percent(history: &[LiquidityPool]) -> Option<f64> {
+    let drop = |prev: u64, cur: u64| {
+        if prev == 0 || cur >= prev {
+            0.0
+        } else {
+            (prev - cur) as f64 / prev as f64 * 100.0
+        }
+    };
+    history
+        .windows(2)
+        .map(|w| drop(w[0].reserve_a, w[1].reserve_a).max(drop(w[0].reserve_b, w[1].reserve_b)))
+        .reduce(f64::max)
+}
+
+/// Whether a native script can still be satisfied at or after `tip`.
+/// `None` if the CBOR is not a native script.
+fn native_can_mint(script: &Value, tip: u64) -> Option<bool> {
+    let parts = script.as_array()?;
+    let tag = value_to_u64(parts.first()?).ok()?;
+    let children = |v: &Value| -> Option<Vec<bool>> {
+        v.as_array()?
+            .iter()
+            .map(|s| native_can_mint(s, tip))
+            .collect()
+    };
+    match tag {
+        // Signature: the key holder can always sign.
+        0 => Some(true),
+        1 => Some(children(parts.get(1)?)?.into_iter().all(|b| b)),
+        2 => Some(children(parts.get(1)?)?.into_iter().any(|b| b)),
+        3 => {
+            let n = value_to_u64(parts.get(1)?).ok()? as usize;
+            Some(children(parts.get(2)?)?.into_iter().filter(|b| *b).count() >= n)
+        }
+        // invalid_before: becomes valid eventually.
+        4 => Some(true),
+        // invalid_hereafter: only valid before the slot.
+        5 => Some(tip < value_to_u64(parts.get(1)?).ok()?),
+        _ => None,
+    }
+}
+
+/// Mint authority of `policy_id` at slot `tip`.
+pub async fn mint_authority(kupo: &KupoApi, policy_id: &str, tip: u64) -> Result<MintAuthority> {
+    let Some(script) = kupo.script(policy_id).await? else {
+        return Ok(MintAuthority::Unknown);
+    };
+    if script.language != "native" {
+        return Ok(MintAuthority::Active);
+    }
+    let authority = decode_cbor(&script.script)
+        .ok()
+        .and_then(|v| native_can_mint(&v, tip))
+        .map_or(MintAuthority::Unknown, |active| {
+            if active {
+                MintAuthority::Active
+            } else {
+                MintAuthority::Locked
+            }
+        });
+    Ok(authority)
+}
+
+/// Share of `total_lp_tokens` held by the largest address holding `lp_unit`.
+///
+/// LP tokens sitting at `pool_address` (the unminted supply some DEXes keep in
+/// the pool UTXO) are not holdings and are ignored.
+pub async fn lp_top_holder_percent(
+    kupo: &KupoApi,
+    lp_unit: &str,
+    total_lp_tokens: u64,
+    pool_address: &str,
+) -> Result<Option<f64>> {
+    let utxos = kupo.get(&asset_pattern(lp_unit), true).await?;
+    let mut holders: HashMap<&str, u64> = HashMap::new();
+    for utxo in utxos.iter().filter(|u| u.address != pool_address) {
+        let qty = utxo.asset_quantity(lp_unit);
+        *holders.entry(utxo.address.as_str()).or_default() += qty;
+    }
+    let supply = if total_lp_tokens > 0 {
+        total_lp_tokens
+    } else {
+        holders.values().sum()
+    };
+    Ok(holders
+        .values()
+        .max()
+        .filter(|_| supply > 0)
+        .map(|top| (*top as f64 / supply as f64 * 100.0).min(100.0)))
+}
+
+/// Compute the risk report of `pool`.
+///
+/// - `lp_unit`: the pool's LP token (policy id + asset name hex); without it the
+///   concentration signal is skipped.
+/// - `history`: earlier snapshots of the pool, oldest first; `pool` is appended
+///   as the latest.
+pub async fn risk(
+    kupo: &KupoApi,
+    pool: &LiquidityPool,
+    lp_unit: Option<&str>,
+    history: &[LiquidityPool],
+) -> Result<RiskReport> {
+    let lp_top_holder_percent = match lp_unit {
+        Some(unit) => {
+            lp_top_holder_percent(kupo, unit, pool.total_lp_tokens, &pool.address).await?
+        }
+        None => None,
+    };
+
+    let mut snapshots = history.to_vec();
+    snapshots.push(pool.clone());
+    let max_reserve_drop_percent = max_reserve_drop_percent(&snapshots);
+
+    let tip = kupo.tip_slot().await?;
+    let mut authority = MintAuthority::Locked;
+    for token in [&pool.asset_a, &pool.asset_b] {
+        if let Token::Asset(asset) = token {
+            authority = authority.max(mint_authority(kupo, &asset.policy_id, tip).await?);
+        }
+    }
+
+    let signals = RiskSignals {
+        lp_top_holder_percent,
+        max_reserve_drop_percent,
+        mint_authority: authority,
+    };
+    let (score, reasons) = signals.score();
+    Ok(RiskReport {
+        dex_identifier: pool.dex_identifier.clone(),
+        pool_id: pool.pool_id.clone(),
+        pair: pool.pair(),
+        signals,
+        score,
+        level: level(score),
+        reasons,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_script_lock_and_score() {
+        // all [ sig, invalid_hereafter 1000 ]
+        let script = Value::Array(vec![
+            Value::Integer(1.into()),
+            Value::Array(vec![
+                Value::Array(vec![Value::Integer(0.into()), Value::Bytes(vec![0; 28])]),
+                Value::Array(vec![Value::Integer(5.into()), Value::Integer(1000.into())]),
+            ]),
+        ]);
+        assert_eq!(native_can_mint(&script, 999), Some(true));
+        assert_eq!(native_can_mint(&script, 1000), Some(false));
+
+        let signals = RiskSignals {
+            lp_top_holder_percent: Some(100.0),
+            max_reserve_drop_percent: Some(90.0),
+            mint_authority: MintAuthority::Active,
+        };
+        let (score, reasons) = signals.score();
+        assert_eq!(score, 100);
+        assert_eq!(level(score), RiskLevel::High);
+        assert_eq!(reasons.len(), 3);
+    }
+}
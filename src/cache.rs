@@ -1,24 +1,175 @@
 use anyhow::{anyhow, Result};
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Save a serializable object to a JSON file.
+/// Format version written by [`save_to_file`]. Bump it when a cached type
+/// (e.g. `VyFinanceCache`) changes shape, and add a step to [`migrate`].
+pub const CACHE_VERSION: u32 = 1;
+
+/// Encoding of a cache file, chosen by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheFormat {
+    /// Pretty-printed JSON; any extension other than the binary ones.
+    Json,
+    /// MessagePack (`.msgpack` / `.mpk`): smaller and much faster to read and
+    /// write for caches of tens of thousands of pools.
+    MessagePack,
+}
+
+impl CacheFormat {
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("msgpack") | Some("mpk") => CacheFormat::MessagePack,
+            _ => CacheFormat::Json,
+        }
+    }
+}
+
+/// On-disk wrapper around every cache payload.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheEnvelope<T> {
+    pub version: u32,
+    /// Unix seconds at which the file was written.
+    pub created_at: u64,
+    pub data: T,
+}
+
+/// Save a serializable object to a JSON or MessagePack file (see
+/// [`CacheFormat::from_path`]), wrapped in a [`CacheEnvelope`].
 pub fn save_to_file<T: Serialize>(data: &T, path: &str) -> Result<()> {
+    let envelope = CacheEnvelope {
+        version: CACHE_VERSION,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        data,
+    };
     let file =
         File::create(path).map_err(|e| anyhow!("Failed to create cache file {}: {}", path, e))?;
-    let writer = BufWriter::new(file);
-    serde_json::to_writer_pretty(writer, data)
-        .map_err(|e| anyhow!("Failed to write cache to {}: {}", path, e))?;
+    let mut writer = BufWriter::new(file);
+    match CacheFormat::from_path(path) {
+        CacheFormat::Json => serde_json::to_writer_pretty(writer, &envelope)
+            .map_err(|e| anyhow!("Failed to write cache to {}: {}", path, e))?,
+        // Named fields keep the payload readable as a map, like JSON, so
+        // `#[serde(default)]` fields and migrations work the same.
+        CacheFormat::MessagePack => rmp_serde::encode::write_named(&mut writer, &envelope)
+            .map_err(|e| anyhow!("Failed to write cache to {}: {}", path, e))?,
+    }
     Ok(())
 }
 
-/// Load a deserializable object from a JSON file.
+/// Load a deserializable object from a JSON or MessagePack file.
+///
+/// Files written by [`save_to_file`] are unwrapped and migrated to
+/// [`CACHE_VERSION`]; plain JSON (hand-written lists, caches from before
+/// versioning) is read as version 0.
 pub fn load_from_file<T: DeserializeOwned>(path: &str) -> Result<T> {
     let file =
         File::open(path).map_err(|e| anyhow!("Failed to open cache file {}: {}", path, e))?;
     let reader = BufReader::new(file);
-    let data = serde_json::from_reader(reader)
-        .map_err(|e| anyhow!("Failed to parse cache file {}: {}", path, e))?;
+    let value: Value = match CacheFormat::from_path(path) {
+        CacheFormat::Json => serde_json::from_reader(reader)
+            .map_err(|e| anyhow!("Failed to parse cache file {}: {}", path, e))?,
+        CacheFormat::MessagePack => rmp_serde::from_read(reader)
+            .map_err(|e| anyhow!("Failed to parse cache file {}: {}", path, e))?,
+    };
+    let (version, data) = unwrap_envelope(value);
+    if version > CACHE_VERSION {
+        return Err(anyhow!(
+            "Cache file {} has version {}, newer than the supported {}; delete it to rebuild",
+            path,
+            version,
+            CACHE_VERSION
+        ));
+    }
+    let data = migrate(data, version)?;
+    serde_json::from_value(data).map_err(|e| {
+        anyhow!(
+            "Cache file {} (version {}) does not match the expected format: {}; delete it to rebuild",
+            path,
+            version,
+            e
+        )
+    })
+}
+
+/// `(version, data)` of an enveloped file, or `(0, value)` for plain JSON.
+fn unwrap_envelope(value: Value) -> (u32, Value) {
+    match value {
+        Value::Object(mut o)
+            if o.len() == 3 && o.contains_key("created_at") && o.contains_key("data") =>
+        {
+            match o.get("version").and_then(Value::as_u64) {
+                Some(version) => (version as u32, o.remove("data").unwrap_or(Value::Null)),
+                None => (0, Value::Object(o)),
+            }
+        }
+        other => (0, other),
+    }
+}
+
+/// Bring a payload written at `from` up to [`CACHE_VERSION`], one version at
+/// a time.
+fn migrate(data: Value, from: u32) -> Result<Value> {
+    let mut data = data;
+    for version in from..CACHE_VERSION {
+        data = match version {
+            // 0 -> 1: the envelope was introduced; payloads are unchanged.
+            0 => data,
+            _ => return Err(anyhow!("No cache migration from version {}", version)),
+        };
+    }
     Ok(data)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_versioned_round_trip_and_legacy() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("cache-test-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let data = HashMap::from([("a".to_string(), 1u8)]);
+        save_to_file(&data, path).unwrap();
+        let raw: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(raw["version"], CACHE_VERSION);
+        assert_eq!(load_from_file::<HashMap<String, u8>>(path).unwrap(), data);
+
+        // Plain JSON from before versioning still loads.
+        std::fs::write(path, r#"{"a": 1}"#).unwrap();
+        assert_eq!(load_from_file::<HashMap<String, u8>>(path).unwrap(), data);
+
+        let newer = r#"{"version": 99, "created_at": 0, "data": {}}"#;
+        std::fs::write(path, newer).unwrap();
+        let err = load_from_file::<HashMap<String, u8>>(path).unwrap_err();
+        assert!(err.to_string().contains("newer"));
+
+        // Wrong shape names the version instead of failing opaquely.
+        std::fs::write(path, r#"{"version": 1, "created_at": 0, "data": [1]}"#).unwrap();
+        let err = load_from_file::<HashMap<String, u8>>(path).unwrap_err();
+        assert!(err.to_string().contains("version 1"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_messagepack_round_trip() {
+        let path = std::env::temp_dir().join(format!("cache-test-{}.msgpack", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert_eq!(CacheFormat::from_path(path), CacheFormat::MessagePack);
+
+        let data = vec![("pool".to_string(), u64::MAX)];
+        save_to_file(&data, path).unwrap();
+        assert!(serde_json::from_slice::<Value>(&std::fs::read(path).unwrap()).is_err());
+        assert_eq!(load_from_file::<Vec<(String, u64)>>(path).unwrap(), data);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -2,6 +2,8 @@
 use anyhow::{anyhow, Result};
 use ciborium::value::Value;
 
+use crate::models::{Credential, CredentialKind};
+
 /// Extract the inner field array from a Plutus constructor tag.
 /// Plutus constructors are CBOR-tagged values: Tag(121+alt, Array([fields...])).
 pub fn constr_fields(v: &Value) -> Result<&Vec<Value>> {
@@ -32,6 +34,46 @@ pub fn is_nonempty_constr(v: &Value) -> bool {
     }
 }
 
+/// Constructor alternative of a Plutus constr: tags 121–127 are 0–6 and
+/// 1280–1400 are 7–127.
+pub fn constr_index(v: &Value) -> Result<u64> {
+    match v {
+        Value::Tag(tag @ 121..=127, _) => Ok(tag - 121),
+        Value::Tag(tag @ 1280..=1400, _) => Ok(tag - 1280 + 7),
+        _ => Err(anyhow!("Expected constr tag, got {:?}", v)),
+    }
+}
+
+/// Inner value of a Plutus `Maybe` / Aiken `Option`: constr 0 [x] is `Some(x)`,
+/// constr 1 [] is `None`.
+pub fn maybe_value(v: &Value) -> Result<Option<&Value>> {
+    match constr_index(v)? {
+        0 => Ok(Some(constr_fields(v)?.first().ok_or_else(|| anyhow!("Just without a value"))?)),
+        _ => Ok(None),
+    }
+}
+
+/// Plutus `Credential`: constr 0 [key hash] or constr 1 [script hash].
+pub fn parse_credential(v: &Value) -> Result<Credential> {
+    let hash = constr_fields(v)?
+        .first()
+        .ok_or_else(|| anyhow!("Credential without hash"))?;
+    let kind = match constr_index(v)? {
+        0 => CredentialKind::Key,
+        1 => CredentialKind::Script,
+        n => return Err(anyhow!("Unknown credential constructor {}", n)),
+    };
+    Ok(Credential { kind, hash: value_to_hex(hash)? })
+}
+
+/// Payment credential of a Plutus `Address` (constr 0 [payment, Maybe stake]).
+pub fn address_payment_credential(v: &Value) -> Result<Credential> {
+    let payment = constr_fields(v)?
+        .first()
+        .ok_or_else(|| anyhow!("Address without payment credential"))?;
+    parse_credential(payment)
+}
+
 /// Read a u64 from a ciborium Integer value.
 pub fn value_to_u64(v: &Value) -> Result<u64> {
     match v {
@@ -7,16 +7,22 @@
 /// Use `get_orders_by_token(token_id)` or `get_all_order_books()` to query orders.
 use anyhow::{anyhow, Result};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use crate::models::asset::{from_identifier, token_identifier};
+use super::latency::SettlementLatency;
 use crate::models::{Order, OrderBook};
 
 const IDENTIFIER: &str = "ChadSwap";
 const CHADSWAP_API_URL: &str = "https://api.chadswap.com/orders";
+/// A fill spends the order UTXO directly: settled once the taker's transaction
+/// lands, about one block.
+pub const SETTLEMENT: SettlementLatency = SettlementLatency::direct(20);
 
 pub struct ChadSwap {
     client: reqwest::Client,
+    /// Orders worth less than this many lovelace are dropped; 0 keeps all.
+    min_order_lovelace: u64,
 }
 
 impl ChadSwap {
@@ -26,13 +32,26 @@ impl ChadSwap {
             .user_agent("Mozilla/5.0 (compatible; dexter-kupo-rs/0.1)")
             .build()
             .expect("Failed to build HTTP client");
-        Self { client }
+        Self {
+            client,
+            min_order_lovelace: 0,
+        }
+    }
+
+    /// Drop dust orders whose remaining amount is worth less than `lovelace`.
+    pub fn with_min_order_lovelace(mut self, lovelace: u64) -> Self {
+        self.min_order_lovelace = lovelace;
+        self
     }
 
     pub fn identifier(&self) -> &str {
         IDENTIFIER
     }
 
+    fn is_dust(&self, order: &Order) -> bool {
+        order.lovelace_value() < self.min_order_lovelace
+    }
+
     /// Fetch the order book for a specific token identifier.
     ///
     /// `token_id` — the concatenated policy+name hex (no dot separator),
@@ -43,6 +62,7 @@ impl ChadSwap {
         let mut buy_orders = Vec::new();
         let mut sell_orders = Vec::new();
         let mut skipped = 0u32;
+        let mut dust = 0u32;
 
         for api_order in &api_orders {
             // API may return unit as "policy_id.asset_name" or "policy_id" + "asset_name"
@@ -51,6 +71,7 @@ impl ChadSwap {
                 continue;
             }
             match order_from_api_order(api_order) {
+                Ok(order) if self.is_dust(&order) => dust += 1,
                 Ok(order) => {
                     if order.is_buy {
                         buy_orders.push(order);
@@ -68,24 +89,32 @@ impl ChadSwap {
         if skipped > 0 {
             eprintln!("[chadswap] get_orders_by_token: skipped {} malformed orders", skipped);
         }
+        if dust > 0 {
+            eprintln!("[chadswap] get_orders_by_token: dropped {} dust orders", dust);
+        }
 
-        Ok(OrderBook {
+        let mut book = OrderBook {
             token_id: token_id.to_string(),
             buy_orders,
             sell_orders,
-        })
+        };
+        book.sort();
+        Ok(book)
     }
 
-    /// Fetch all orders across all tokens, grouped by token identifier.
-    pub async fn get_all_order_books(&self) -> Result<HashMap<String, OrderBook>> {
+    /// Fetch all orders across all tokens, grouped by token identifier in
+    /// identifier order.
+    pub async fn get_all_order_books(&self) -> Result<BTreeMap<String, OrderBook>> {
         let api_orders = self.fetch_api_orders().await?;
 
-        let mut books: HashMap<String, OrderBook> = HashMap::new();
+        let mut books: BTreeMap<String, OrderBook> = BTreeMap::new();
         let mut parsed_ok = 0u32;
         let mut skipped = 0u32;
+        let mut dust = 0u32;
 
         for api_order in &api_orders {
             match order_from_api_order(api_order) {
+                Ok(order) if self.is_dust(&order) => dust += 1,
                 Ok(order) => {
                     parsed_ok += 1;
                     let asset_id = token_identifier(&order.asset);
@@ -107,9 +136,10 @@ impl ChadSwap {
             }
         }
 
+        books.values_mut().for_each(OrderBook::sort);
         eprintln!(
-            "[chadswap] get_all_order_books: parsed={}, skipped={}, tokens={}",
-            parsed_ok, skipped, books.len()
+            "[chadswap] get_all_order_books: parsed={}, skipped={}, dust={}, tokens={}",
+            parsed_ok, skipped, dust, books.len()
         );
 
         Ok(books)
@@ -146,6 +176,12 @@ struct ChadSwapApiOrder {
     // null for fully-filled orders
     #[serde(default)]
     tokens_left: Option<String>,
+    // Order creator, when present
+    #[serde(default, alias = "creator", alias = "ownerAddress")]
+    owner: Option<String>,
+    // Unix seconds/milliseconds or an RFC 3339 string, when present
+    #[serde(default, alias = "timestamp", alias = "created")]
+    created_at: Option<serde_json::Value>,
 }
 
 // ── API → Order mapping ───────────────────────────────────────────────────────
@@ -181,5 +217,76 @@ fn order_from_api_order(api_order: &ChadSwapApiOrder) -> Result<Order> {
         price,
         price_denominator,
         is_buy,
+        output_ref: api_order.output_ref.clone(),
+        owner: api_order.owner.clone().filter(|o| !o.is_empty()),
+        created_at: api_order.created_at.as_ref().and_then(unix_secs),
     })
 }
+
+/// Unix seconds from a timestamp given as seconds, milliseconds (values past
+/// year 33658 in seconds) or an RFC 3339 UTC string.
+fn unix_secs(value: &serde_json::Value) -> Option<u64> {
+    let n = match value {
+        serde_json::Value::Number(n) => n.as_u64()?,
+        serde_json::Value::String(s) => match s.parse::<u64>() {
+            Ok(n) => n,
+            Err(_) => return rfc3339_secs(s),
+        },
+        _ => return None,
+    };
+    Some(if n >= 1_000_000_000_000 { n / 1000 } else { n })
+}
+
+/// `YYYY-MM-DDTHH:MM:SS[.fff][Z|+00:00]`; other offsets are rejected.
+fn rfc3339_secs(s: &str) -> Option<u64> {
+    let (date, time) = s.split_once(['T', ' '])?;
+    let mut d = date.splitn(3, '-').map(|p| p.parse::<i64>().ok());
+    let (y, m, day) = (d.next()??, d.next()??, d.next()??);
+    let time = time
+        .trim_end_matches('Z')
+        .trim_end_matches("+00:00")
+        .split('.')
+        .next()?;
+    let mut t = time.splitn(3, ':').map(|p| p.parse::<i64>().ok());
+    let (hh, mm, ss) = (t.next()??, t.next()??, t.next()??);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Days from civil (Howard Hinnant).
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    u64::try_from(days * 86_400 + hh * 3600 + mm * 60 + ss).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_metadata_and_value() {
+        let api_order: ChadSwapApiOrder = serde_json::from_value(serde_json::json!({
+            "outputRef": "ab#0",
+            "type": "sell",
+            "unit": "f13ac4d66b3ee19a6aa0f2a22298737bd907cc95121662fc971b5275.535452494b45",
+            "unitPrice": "3",
+            "unitPriceDenom": "2",
+            "tokensLeft": "1000",
+            "creator": "addr1qxyz",
+            "createdAt": "2024-01-01T00:00:10.500Z"
+        }))
+        .unwrap();
+        let order = order_from_api_order(&api_order).unwrap();
+        assert_eq!(order.output_ref, "ab#0");
+        assert_eq!(order.owner.as_deref(), Some("addr1qxyz"));
+        assert_eq!(order.created_at, Some(1_704_067_210));
+        assert_eq!(order.age_secs(1_704_067_270), Some(60));
+        assert_eq!(order.lovelace_value(), 1500);
+
+        assert_eq!(unix_secs(&serde_json::json!(1_704_067_210_000u64)), Some(1_704_067_210));
+        assert_eq!(unix_secs(&serde_json::json!("1704067210")), Some(1_704_067_210));
+    }
+}
@@ -0,0 +1,449 @@
+//! When pools were created: the slot of the first output holding the pool's
+//! identifying asset (its NFT, or the LP token where that names the pool).
+//!
+//! Such an asset sits in exactly one output at any slot after it is minted,
+//! so the creation slot is found by bisecting over "did an output exist at
+//! this slot", each probe a [`KupoApi::spent_output_at`] call returning at
+//! most one output, rather than downloading the asset's whole history.
+//!
+//! This needs a Kupo that keeps spent outputs. One started with
+//! `--prune-utxo` (as hosted instances usually are) only has the current
+//! output, which would make every pool look as old as its last update. A
+//! lookup whose answer depends on that is trusted only once Kupo has returned
+//! a spent output, or after [`CreationResolver::with_spent_history`];
+//! otherwise it fails instead of reporting a wrong slot.
+//!
+//! Creation slots never change, so [`CreationResolver`] keeps every slot it
+//! resolves. A pool can only be younger than a window if its current UTXO is,
+//! so [`CreationResolver::new_pools`] looks up just those, and only within
+//! the window.
+
+use anyhow::{anyhow, Result};
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::kupo::KupoApi;
+use crate::models::LiquidityPool;
+
+/// Creation lookups in flight at once.
+const LOOKUP_CONCURRENCY: usize = 8;
+const POLICY_ID_LEN: usize = 56;
+
+/// Kupo pattern (`policy.name`) of the asset a `pool_id` names, or `None`
+/// for ids that are not a unit (e.g. `tx_hash#index`).
+pub fn pool_id_pattern(pool_id: &str) -> Option<String> {
+    if pool_id.contains('.') {
+        return Some(pool_id.to_string());
+    }
+    let is_unit = pool_id.len() >= POLICY_ID_LEN
+        && pool_id.len().is_multiple_of(2)
+        && pool_id.chars().all(|c| c.is_ascii_hexdigit());
+    is_unit.then(|| {
+        format!(
+            "{}.{}",
+            &pool_id[..POLICY_ID_LEN],
+            &pool_id[POLICY_ID_LEN..]
+        )
+    })
+}
+
+/// Earliest slot in `lo..=hi` at which `exists` holds, given that it holds
+/// at `hi` and keeps holding once it does.
+async fn earliest<F, Fut>(mut lo: u64, mut hi: u64, mut exists: F) -> Result<u64>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if exists(mid).await? {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Ok(hi)
+}
+
+/// Resolves and remembers pool creation slots.
+pub struct CreationResolver {
+    kupo: KupoApi,
+    /// Creation slot by Kupo pattern.
+    known: Mutex<HashMap<String, u64>>,
+    /// Kupo is known to keep spent outputs.
+    spent_history: AtomicBool,
+}
+
+impl CreationResolver {
+    pub fn new(kupo: &KupoApi) -> Self {
+        Self {
+            kupo: kupo.clone(),
+            known: Mutex::new(HashMap::new()),
+            spent_history: AtomicBool::new(false),
+        }
+    }
+
+    /// State whether Kupo keeps spent outputs instead of waiting for one to
+    /// show up. Pass `true` only for a Kupo run without `--prune-utxo`.
+    pub fn with_spent_history(self, keeps_spent_outputs: bool) -> Self {
+        self.spent_history
+            .store(keeps_spent_outputs, Ordering::Relaxed);
+        self
+    }
+
+    fn has_spent_history(&self) -> bool {
+        self.spent_history.load(Ordering::Relaxed)
+    }
+
+    /// Whether an output of `pattern` existed at `slot`, which must be before
+    /// the current output's. A hit proves Kupo keeps spent outputs.
+    async fn existed_at(&self, pattern: &str, slot: u64) -> Result<bool> {
+        let found = self.kupo.spent_output_at(pattern, slot).await?.is_some();
+        if found {
+            self.spent_history.store(true, Ordering::Relaxed);
+        }
+        Ok(found)
+    }
+
+    /// Slot of the pool's current output, from the pool or else from Kupo.
+    async fn current_slot(&self, pool: &LiquidityPool, pattern: &str) -> Result<Option<u64>> {
+        if let Some(slot) = pool.metadata.freshness.as_ref().and_then(|f| f.slot) {
+            return Ok(Some(slot));
+        }
+        let current = self.kupo.get(pattern, true).await?;
+        Ok(current.into_iter().filter_map(|utxo| utxo.slot).min())
+    }
+
+    /// Creation slot of `pattern`, searched in `from..=current`. A result of
+    /// `current` means no earlier output was found, which is only conclusive
+    /// if Kupo keeps spent outputs.
+    async fn search(&self, pattern: &str, from: u64, current: u64) -> Result<u64> {
+        earliest(from, current, |slot| self.existed_at(pattern, slot)).await
+    }
+
+    fn unverified(pattern: &str) -> anyhow::Error {
+        anyhow!(
+            "cannot resolve the creation of {}: Kupo returned no spent outputs, so it may \
+             prune them (--prune-utxo); use a Kupo that keeps spent outputs",
+            pattern
+        )
+    }
+
+    fn remember(&self, pattern: String, slot: u64) {
+        self.known.lock().unwrap().insert(pattern, slot);
+    }
+
+    /// Slot `pool` was created in; `None` when its id names no asset or Kupo
+    /// has no output for it. Fails when the answer would rest on spent
+    /// outputs Kupo may have pruned.
+    pub async fn created_slot(&self, pool: &LiquidityPool) -> Result<Option<u64>> {
+        let Some(pattern) = pool_id_pattern(&pool.pool_id) else {
+            return Ok(None);
+        };
+        if let Some(slot) = self.known.lock().unwrap().get(&pattern) {
+            return Ok(Some(*slot));
+        }
+        let Some(current) = self.current_slot(pool, &pattern).await? else {
+            return Ok(None);
+        };
+        let slot = self.search(&pattern, 0, current).await?;
+        if slot == current && !self.has_spent_history() {
+            return Err(Self::unverified(&pattern));
+        }
+        self.remember(pattern, slot);
+        Ok(Some(slot))
+    }
+
+    /// Set `created_slot` on each of `pools`. Lookups that fail are logged
+    /// and leave the pool unresolved.
+    pub async fn resolve(&self, pools: &mut [LiquidityPool]) {
+        let slots: Vec<Option<u64>> = stream::iter(pools.iter())
+            .map(|pool| async move {
+                match self.created_slot(pool).await {
+                    Ok(slot) => slot,
+                    Err(e) => {
+                        eprintln!(
+                            "[creation] {} {} failed: {}",
+                            pool.dex_identifier, pool.pool_id, e
+                        );
+                        None
+                    }
+                }
+            })
+            .buffered(LOOKUP_CONCURRENCY)
+            .collect()
+            .await;
+        for (pool, slot) in pools.iter_mut().zip(slots) {
+            pool.metadata.created_slot = pool.metadata.created_slot.or(slot);
+        }
+    }
+
+    /// Creation slot of `pool` if it is at least `since`, and whether that
+    /// holds without spent history (an earlier output was found). Only slots
+    /// from `since - 1` on are probed.
+    async fn created_since_slot(
+        &self,
+        pool: &LiquidityPool,
+        since: u64,
+    ) -> Result<Option<(u64, bool)>> {
+        let Some(pattern) = pool_id_pattern(&pool.pool_id) else {
+            return Ok(None);
+        };
+        if let Some(slot) = self.known.lock().unwrap().get(&pattern) {
+            return Ok((*slot >= since).then_some((*slot, true)));
+        }
+        let Some(current) = self.current_slot(pool, &pattern).await? else {
+            return Ok(None);
+        };
+        if current < since || (since > 0 && self.existed_at(&pattern, since - 1).await?) {
+            return Ok(None);
+        }
+        let slot = self.search(&pattern, since, current).await?;
+        let conclusive = slot < current;
+        if conclusive || self.has_spent_history() {
+            self.remember(pattern, slot);
+        }
+        Ok(Some((slot, conclusive)))
+    }
+
+    /// Pools among `pools` created within `window` before `tip_slot`, newest
+    /// first, with `created_slot` set. Pools whose UTXO slot is unknown are
+    /// looked up too.
+    ///
+    /// Fails if some pool looks new only because it has no spent outputs and
+    /// Kupo has not shown that it keeps any: on a pruning Kupo every pool
+    /// traded within the window would look new.
+    pub async fn new_pools(
+        &self,
+        pools: Vec<LiquidityPool>,
+        tip_slot: u64,
+        window: Duration,
+    ) -> Result<Vec<LiquidityPool>> {
+        let since = tip_slot.saturating_sub(window.as_secs());
+        let mut recent: Vec<LiquidityPool> = pools
+            .into_iter()
+            .filter(|pool| {
+                let slot = pool.metadata.freshness.as_ref().and_then(|f| f.slot);
+                slot.is_none_or(|slot| slot >= since)
+            })
+            .collect();
+        let found: Vec<Option<(u64, bool)>> = stream::iter(recent.iter())
+            .map(|pool| async move {
+                match self.created_since_slot(pool, since).await {
+                    Ok(found) => found,
+                    Err(e) => {
+                        eprintln!(
+                            "[creation] {} {} failed: {}",
+                            pool.dex_identifier, pool.pool_id, e
+                        );
+                        None
+                    }
+                }
+            })
+            .buffered(LOOKUP_CONCURRENCY)
+            .collect()
+            .await;
+        // Checked after every lookup: any of them may have shown spent history.
+        if !self.has_spent_history() {
+            let inconclusive = recent
+                .iter()
+                .zip(&found)
+                .find(|(_, found)| matches!(found, Some((_, false))));
+            if let Some((pool, _)) = inconclusive {
+                return Err(Self::unverified(&pool.pool_id));
+            }
+        }
+        for (pool, found) in recent.iter_mut().zip(found) {
+            pool.metadata.created_slot = pool.metadata.created_slot.or(found.map(|(slot, _)| slot));
+        }
+        let mut created = created_since(recent, since);
+        created.sort_by_key(|pool| std::cmp::Reverse(pool.metadata.created_slot));
+        Ok(created)
+    }
+}
+
+/// Pools with a resolved `created_slot` of at least `since`.
+fn created_since(pools: Vec<LiquidityPool>, since: u64) -> Vec<LiquidityPool> {
+    pools
+        .into_iter()
+        .filter(|pool| pool.metadata.created_slot.is_some_and(|slot| slot >= since))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kupo::test_server::{self, Response};
+    use crate::models::Token;
+
+    #[test]
+    fn test_pool_id_pattern_and_age() {
+        let nft = "0be55d262b29f564998ff81efe21bdc0022621c12f15af08d0f2ddb1\
+                   4e4654";
+        assert_eq!(
+            pool_id_pattern(nft).unwrap(),
+            "0be55d262b29f564998ff81efe21bdc0022621c12f15af08d0f2ddb1.4e4654"
+        );
+        assert_eq!(pool_id_pattern("ab.cd").unwrap(), "ab.cd");
+        assert_eq!(pool_id_pattern("abcd#0"), None);
+
+        let pool = |id: &str, created_slot| {
+            let mut pool = LiquidityPool::new(
+                "MinswapV2",
+                Token::Lovelace,
+                Token::Lovelace,
+                1,
+                1,
+                "addr",
+                0.3,
+                id,
+            );
+            pool.metadata.created_slot = created_slot;
+            pool
+        };
+        let pools = vec![
+            pool("old", Some(1_000)),
+            pool("new", Some(9_000)),
+            pool("unknown", None),
+        ];
+        assert_eq!(pools[1].age(10_000), Some(Duration::from_secs(1_000)));
+        assert_eq!(pools[2].age(10_000), None);
+        let ids: Vec<String> = created_since(pools, 10_000 - 3_600)
+            .into_iter()
+            .map(|p| p.pool_id)
+            .collect();
+        assert_eq!(ids, ["new"]);
+    }
+
+    #[tokio::test]
+    async fn test_earliest_bisects_to_creation() {
+        let probes = std::cell::Cell::new(0);
+        let created = 123_456;
+        let exists = |slot: u64| {
+            probes.set(probes.get() + 1);
+            async move { Ok(slot >= created) }
+        };
+        assert_eq!(earliest(0, 200_000_000, exists).await.unwrap(), created);
+        // Logarithmic in the range, not linear in the history.
+        assert!(probes.get() <= 28, "{} probes", probes.get());
+
+        // Nothing before the current output: the search ends on it.
+        let never = |_| async { Ok(false) };
+        assert_eq!(earliest(5_000, 9_000, never).await.unwrap(), 9_000);
+        let fails = |_| async { Err(anyhow!("kupo down")) };
+        assert!(earliest(0, 10, fails).await.is_err());
+    }
+
+    /// Outputs of pool assets: `(pattern, created, spent)`.
+    type History = Vec<(&'static str, u64, Option<u64>)>;
+
+    /// A Kupo answering `spent_output_at` queries from `history`; returns its URL.
+    async fn fake_kupo(history: History) -> String {
+        test_server::serve(move |request| {
+            let pattern = request
+                .path
+                .trim_start_matches("/matches/")
+                .split('?')
+                .next()
+                .unwrap();
+            let slot = request.query_param("spent_after").unwrap();
+            let matches: Vec<_> = history
+                .iter()
+                .filter(|(p, created, spent)| {
+                    *p == pattern && *created <= slot && spent.is_some_and(|s| s > slot)
+                })
+                .map(|(_, created, _)| {
+                    serde_json::json!({
+                        "address": "addr1pool",
+                        "transaction_id": "ab",
+                        "output_index": 0,
+                        "created_at": { "slot_no": created, "header_hash": "hh" },
+                        "value": { "coins": 2_000_000, "assets": {} }
+                    })
+                })
+                .collect();
+            Response::json(&serde_json::json!(matches))
+        })
+        .await
+    }
+
+    /// A pool whose id is the asset `<policy><name>` and whose current
+    /// output was created at `current`.
+    fn traded_pool(policy_byte: &str, current: u64) -> LiquidityPool {
+        let mut pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            Token::Lovelace,
+            1,
+            1,
+            "addr",
+            0.3,
+            &format!("{}4e4654", policy_byte.repeat(28)),
+        );
+        pool.metadata.freshness = Some(crate::models::PoolFreshness {
+            slot: Some(current),
+            ..Default::default()
+        });
+        pool
+    }
+
+    fn pattern(policy_byte: &str) -> &'static str {
+        format!("{}.4e4654", policy_byte.repeat(28)).leak()
+    }
+
+    #[tokio::test]
+    async fn test_new_pools_with_spent_history() {
+        let (new, old) = (pattern("aa"), pattern("bb"));
+        let kupo = KupoApi::new(
+            &fake_kupo(vec![
+                // Created at 9_000, traded at 9_500 and 9_800.
+                (new, 9_000, Some(9_500)),
+                (new, 9_500, Some(9_800)),
+                (new, 9_800, None),
+                // Created long before the window, traded within it.
+                (old, 1_000, Some(9_900)),
+                (old, 9_900, None),
+            ])
+            .await,
+        );
+        let resolver = CreationResolver::new(&kupo);
+        let pools = vec![traded_pool("aa", 9_800), traded_pool("bb", 9_900)];
+        let created = resolver
+            .new_pools(pools, 10_000, Duration::from_secs(3_600))
+            .await
+            .unwrap();
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].metadata.created_slot, Some(9_000));
+
+        let mut old_pool = [traded_pool("bb", 9_900)];
+        resolver.resolve(&mut old_pool).await;
+        assert_eq!(old_pool[0].metadata.created_slot, Some(1_000));
+    }
+
+    #[tokio::test]
+    async fn test_pruning_kupo_is_not_reported_as_new_pools() {
+        // Pruned: only current outputs, so no spent output is ever returned.
+        let (a, b) = (pattern("cc"), pattern("dd"));
+        let kupo = KupoApi::new(&fake_kupo(vec![(a, 9_800, None), (b, 9_900, None)]).await);
+        let pools = vec![traded_pool("cc", 9_800), traded_pool("dd", 9_900)];
+
+        let resolver = CreationResolver::new(&kupo);
+        assert!(resolver
+            .new_pools(pools.clone(), 10_000, Duration::from_secs(3_600))
+            .await
+            .is_err());
+        assert!(resolver.created_slot(&pools[0]).await.is_err());
+
+        // Told that Kupo keeps spent outputs, untraded pools count as new.
+        let resolver = CreationResolver::new(&kupo).with_spent_history(true);
+        let created = resolver
+            .new_pools(pools, 10_000, Duration::from_secs(3_600))
+            .await
+            .unwrap();
+        assert_eq!(created.len(), 2);
+    }
+}
@@ -1,24 +1,52 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use futures::future::try_join_all;
 use crate::kupo::KupoApi;
 use crate::models::asset::from_identifier;
-use crate::models::{token_identifier, LiquidityPool, Utxo};
-use super::BaseDex;
+use crate::models::{reserve_pair, AssetId, LiquidityPool, Utxo};
+use super::overrides::DexOverrides;
+use super::{BaseDex, PoolFilter, SkipReason};
 use super::cbor::{constr_fields, decode_cbor, value_to_u64};
+use super::latency::SettlementLatency;
+use crate::quote::exact::ScaledFee;
+use crate::utils::RetryBudget;
 
 const IDENTIFIER: &str = "CSwap";
-const POOL_ADDRESS: &str =
-    "addr1z8ke0c9p89rjfwmuh98jpt8ky74uy5mffjft3zlcld9h7ml3lmln3mwk0y3zsh3gs3dzqlwa9rjzrxawkwm4udw9axhs6fuu6e";
+/// Validator swap math: the fee scales the input inside the product.
+pub const SWAP_MATH: ScaledFee = ScaledFee;
+/// CSwap batchers pick orders up within a few blocks.
+pub const SETTLEMENT: SettlementLatency = SettlementLatency::batched(60);
+const POOL_ADDRESSES: &[&str] = &[
+    "addr1z8ke0c9p89rjfwmuh98jpt8ky74uy5mffjft3zlcld9h7ml3lmln3mwk0y3zsh3gs3dzqlwa9rjzrxawkwm4udw9axhs6fuu6e",
+];
 /// LP tokens are identified by their asset name hex being exactly "63"
 const LP_TOKEN_NAME_HEX: &str = "63";
 
 pub struct CSwap {
     kupo: KupoApi,
+    filter: PoolFilter,
+    pool_addresses: Vec<String>,
 }
 
 impl CSwap {
-    pub fn new(kupo: KupoApi) -> Self {
-        Self { kupo }
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
+        Self {
+            kupo: kupo.into(),
+            filter: PoolFilter::default(),
+            pool_addresses: POOL_ADDRESSES.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    /// Replace the pool addresses (see [`DexOverrides`]).
+    pub fn with_overrides(mut self, overrides: &DexOverrides) -> Result<Self> {
+        overrides.validate(IDENTIFIER)?;
+        self.pool_addresses = overrides.pool_addresses_or(POOL_ADDRESSES);
+        Ok(self)
+    }
+
+    pub fn with_pool_filter(mut self, filter: PoolFilter) -> Self {
+        self.filter = filter;
+        self
     }
 }
 
@@ -67,7 +95,7 @@ impl BaseDex for CSwap {
     }
 
     fn pool_address(&self) -> &str {
-        POOL_ADDRESS
+        &self.pool_addresses[0]
     }
 
     fn lp_token_policy_id(&self) -> &str {
@@ -79,9 +107,18 @@ impl BaseDex for CSwap {
         &self.kupo
     }
 
+    fn pool_filter(&self) -> &PoolFilter {
+        &self.filter
+    }
+
+    fn pool_match_patterns(&self) -> Vec<String> {
+        self.pool_addresses.clone()
+    }
+
     async fn all_liquidity_pool_utxos(&self) -> Result<Vec<Utxo>> {
         // Kupo: bech32 addresses are queried directly (no /* wildcard)
-        self.kupo.get(POOL_ADDRESS, true).await
+        let found = try_join_all(self.pool_addresses.iter().map(|a| self.kupo.get(a, true))).await?;
+        Ok(found.into_iter().flatten().collect())
     }
 
     async fn liquidity_pool_from_utxo(
@@ -89,7 +126,7 @@ impl BaseDex for CSwap {
         utxo: &Utxo,
         pool_id: &str,
     ) -> Result<Option<LiquidityPool>> {
-        if utxo.data_hash.is_none() {
+        if utxo.data_hash.is_none() && utxo.inline_datum.is_none() {
             return Ok(None);
         }
 
@@ -112,54 +149,52 @@ impl BaseDex for CSwap {
         if ![2, 3].contains(&relevant.len()) {
             return Ok(None);
         }
+        let Some((unit_a, unit_b)) = reserve_pair(&relevant) else {
+            return Ok(None);
+        };
 
-        let a_idx = if relevant.len() == 2 { 0 } else { 1 };
-        let b_idx = if relevant.len() == 2 { 1 } else { 2 };
-
-        let a_unit = &relevant[a_idx].unit;
-        let b_unit = &relevant[b_idx].unit;
+        let a_unit = &unit_a.unit;
+        let b_unit = &unit_b.unit;
 
         // CSwap locks 2 ADA (min UTXO) in the pool that cannot be traded.
         // The smart contract uses (R_ada - 2_000_000) as the effective ADA reserve.
-        let raw_a: u64 = relevant[a_idx].quantity.parse()?;
-        let raw_b: u64 = relevant[b_idx].quantity.parse()?;
-        let reserve_a = if *a_unit == "lovelace" { raw_a.saturating_sub(2_000_000) } else { raw_a };
-        let reserve_b = if *b_unit == "lovelace" { raw_b.saturating_sub(2_000_000) } else { raw_b };
+        let raw_a = unit_a.quantity;
+        let raw_b = unit_b.quantity;
+        let reserve_a = if unit_a.is_lovelace() { raw_a.saturating_sub(2_000_000) } else { raw_a };
+        let reserve_b = if unit_b.is_lovelace() { raw_b.saturating_sub(2_000_000) } else { raw_b };
 
         let asset_a = from_identifier(a_unit, 0);
         let asset_b = from_identifier(b_unit, 0);
 
-        Ok(Some(LiquidityPool::new(
-            IDENTIFIER,
-            asset_a,
-            asset_b,
-            reserve_a,
-            reserve_b,
-            &utxo.address,
-            0.3,
-            &pool_id,
-        )))
+        Ok(Some(
+            LiquidityPool::new(
+                IDENTIFIER,
+                asset_a,
+                asset_b,
+                reserve_a,
+                reserve_b,
+                &utxo.address,
+                0.3,
+                &pool_id,
+            )
+            .observed_in(utxo),
+        ))
     }
 
     /// Fetch datum and update fee from LpFee field.
-    async fn liquidity_pool_from_utxo_extend(
+    async fn liquidity_pool_from_utxo_extend_or_skip(
         &self,
         utxo: &Utxo,
         pool_id: &str,
-    ) -> Result<Option<LiquidityPool>> {
+    ) -> Result<Result<LiquidityPool, SkipReason>> {
         let mut pool = match self.liquidity_pool_from_utxo(utxo, pool_id).await? {
             Some(p) => p,
-            None => return Ok(None),
-        };
-
-        let data_hash = match &utxo.data_hash {
-            Some(h) => h,
-            None => return Ok(None),
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
 
-        let datum = match &utxo.inline_datum {
-            Some(d) => d.clone(),
-            None => self.kupo.datum(data_hash).await?,
+        let datum = match self.kupo.utxo_datum(utxo).await? {
+            Some(d) => d,
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
         let d = parse_pool_datum(&datum)?;
 
@@ -167,7 +202,7 @@ impl BaseDex for CSwap {
         // CSwap total fee = LP fee + 15 bps protocol fee
         pool.pool_fee_percent = (d.lp_fee + 15) as f64 / 100.0;
 
-        Ok(Some(pool))
+        Ok(self.filter_pool_or_skip(pool))
     }
 
     async fn liquidity_pool_from_pool_id(&self, pool_id: &str) -> Result<Option<LiquidityPool>> {
@@ -184,36 +219,85 @@ impl BaseDex for CSwap {
 
     async fn liquidity_pools_from_token(
         &self,
-        token_b: &str,
-        token_a: &str,
+        token_b: AssetId,
+        token_a: AssetId,
     ) -> Result<Vec<LiquidityPool>> {
-        let all_utxos = self.all_liquidity_pool_utxos().await?;
-        let mut pools = Vec::new();
-
-        for utxo in &all_utxos {
-            let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
-                Some(p) => p,
-                None => continue,
-            };
-
-            let id_a = token_identifier(&base.asset_a);
-            let id_b = token_identifier(&base.asset_b);
-
-            let matches = (id_a == token_a && id_b == token_b)
-                || (id_a == token_b && id_b == token_a);
-
-            if matches {
-                match self
-                    .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
-                    .await
-                {
-                    Ok(Some(p)) => pools.push(p),
-                    Ok(None) => {}
-                    Err(e) => eprintln!("[cswap] datum error {}: {}", utxo.tx_hash, e),
+        RetryBudget::ensure_scope(async {
+            let all_utxos = self.all_liquidity_pool_utxos().await?;
+            let mut pools = Vec::new();
+
+            for utxo in &all_utxos {
+                let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
+                    Some(p) => p,
+                    None => continue,
+                };
+
+                let id_a = AssetId::from(&base.asset_a);
+                let id_b = AssetId::from(&base.asset_b);
+
+                let matches = (id_a == token_a && id_b == token_b)
+                    || (id_a == token_b && id_b == token_a);
+
+                if matches {
+                    match self
+                        .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
+                        .await
+                    {
+                        Ok(Some(p)) => pools.push(p),
+                        Ok(None) => {}
+                        Err(e) => eprintln!("[cswap] datum error {}: {}", utxo.tx_hash, e),
+                    }
                 }
             }
-        }
 
-        Ok(pools)
+            Ok(pools)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ciborium::value::Value;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_pool_from_raw_output() {
+        let int = |n: u64| Value::Integer(n.into());
+        let bytes = |h: &str| Value::Bytes(hex::decode(h).unwrap());
+        let datum = Value::Tag(
+            121,
+            Box::new(Value::Array(vec![
+                int(1_000),
+                int(15),
+                bytes(""),
+                bytes(""),
+                bytes("279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f"),
+                bytes("534e454b"),
+                int(0),
+            ])),
+        );
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&datum, &mut cbor).unwrap();
+
+        let lp = format!("{}{}", "cc".repeat(28), LP_TOKEN_NAME_HEX);
+        let value = HashMap::from([
+            ("lovelace".to_string(), 102_000_000),
+            ("279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b".to_string(), 7_000),
+            (lp.clone(), 9_000),
+        ]);
+
+        // Inline datum, no datum hash: decoded without asking the Kupo.
+        let dex = CSwap::new(KupoApi::new("http://127.0.0.1:9"));
+        let pool = dex
+            .liquidity_pool_from_raw_output(POOL_ADDRESSES[0], &value, Some(&hex::encode(cbor)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.pool_id, lp);
+        assert_eq!((pool.reserve_a, pool.reserve_b), (100_000_000, 7_000));
+        assert_eq!(pool.total_lp_tokens, 1_000);
+        assert!((pool.pool_fee_percent - 0.3).abs() < 1e-9);
     }
 }
@@ -0,0 +1,240 @@
+/// DJED/SHEN stablecoin reserve – reference data for ADA-pegged pairs.
+///
+/// Like VyFi Bar, this produces no `LiquidityPool`. It reads the Djed
+/// stability pool UTXO (found by its pool NFT) for the ADA reserve and the
+/// circulating DJED and SHEN, and optionally the Djed oracle UTXO for the
+/// ADA/USD rate the protocol mints and burns at.
+///
+/// Pool datum (Plutus):
+///   Constr(0, [
+///     int  ← DJED in circulation (micro-USD)
+///     int  ← SHEN in circulation
+///     int  ← ADA in reserve (lovelace)
+///     ...  ← pool NFT, min ADA (ignored)
+///   ])
+///
+/// Oracle datum (Plutus):
+///   Constr(0, [
+///     Constr(0, [
+///       Constr(0, [int, int])  ← ADA/USD as numerator / denominator
+///       ...                    ← validity range (ignored)
+///     ])
+///     ...
+///   ])
+///
+/// Usage:
+///   - `DjedReader::new(kupo).with_pool(pool_nft).with_oracle(oracle_nft)`
+///   - `get_state()` — reserve, circulation and, with an oracle, the ratio and prices
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::kupo::KupoApi;
+use crate::models::Utxo;
+use super::cbor::{constr_fields, decode_cbor, value_to_u64};
+
+const IDENTIFIER: &str = "Djed";
+
+/// Unit of DJED (`DjedMicroUSD`, 6 decimals).
+pub const DJED_UNIT: &str =
+    "8db269c3ec630e06ae29f74bc39edd1f87c819f1056206e879a1cd61446a65644d6963726f555344";
+/// Unit of SHEN (`ShenMicroUSD`, 6 decimals).
+pub const SHEN_UNIT: &str =
+    "8db269c3ec630e06ae29f74bc39edd1f87c819f1056206e879a1cd615368656e4d6963726f555344";
+
+/// State of the Djed stability pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DjedState {
+    /// Lovelace backing DJED and SHEN.
+    pub ada_reserve: u64,
+    /// DJED base units (micro-USD) in circulation.
+    pub djed_circulating: u64,
+    /// SHEN base units in circulation.
+    pub shen_circulating: u64,
+    /// USD per ADA from the Djed oracle, when one was read.
+    #[serde(default)]
+    pub ada_usd: Option<f64>,
+    /// Slot of the pool UTXO, when Kupo reported it.
+    #[serde(default)]
+    pub slot: Option<u64>,
+    /// Unix timestamp (seconds) at which the state was read.
+    #[serde(default)]
+    pub observed_at: u64,
+}
+
+impl DjedState {
+    /// Reserve value over DJED liabilities (1.0 = exactly backed), or `None`
+    /// without an oracle rate or circulating DJED. The protocol keeps this
+    /// between 4.0 and 8.0 for mints.
+    pub fn reserve_ratio(&self) -> Option<f64> {
+        let ada_usd = self.ada_usd?;
+        if self.djed_circulating == 0 {
+            return None;
+        }
+        Some(self.ada_reserve as f64 * ada_usd / self.djed_circulating as f64)
+    }
+
+    /// Lovelace per DJED base unit at the oracle rate.
+    pub fn djed_price_lovelace(&self) -> Option<f64> {
+        self.ada_usd.filter(|rate| *rate > 0.0).map(|rate| 1.0 / rate)
+    }
+
+    /// Lovelace per SHEN base unit: the reserve left after DJED liabilities,
+    /// shared among SHEN holders. 0.0 when the reserve is under water.
+    pub fn shen_price_lovelace(&self) -> Option<f64> {
+        let djed_price = self.djed_price_lovelace()?;
+        if self.shen_circulating == 0 {
+            return None;
+        }
+        let equity = self.ada_reserve as f64 - self.djed_circulating as f64 * djed_price;
+        Some(equity.max(0.0) / self.shen_circulating as f64)
+    }
+}
+
+pub struct DjedReader {
+    kupo: KupoApi,
+    /// Kupo pattern of the stability pool NFT.
+    pool: Option<String>,
+    /// Kupo pattern of the oracle NFT.
+    oracle: Option<String>,
+}
+
+impl DjedReader {
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
+        Self {
+            kupo: kupo.into(),
+            pool: None,
+            oracle: None,
+        }
+    }
+
+    /// Kupo pattern (`policy.name` or address) of the stability pool UTXO.
+    pub fn with_pool(mut self, pattern: impl Into<String>) -> Self {
+        self.pool = Some(pattern.into());
+        self
+    }
+
+    /// Kupo pattern of the oracle UTXO; without one, states carry no rate.
+    pub fn with_oracle(mut self, pattern: impl Into<String>) -> Self {
+        self.oracle = Some(pattern.into());
+        self
+    }
+
+    pub fn identifier(&self) -> &str {
+        IDENTIFIER
+    }
+
+    /// Read the pool and, when configured, the oracle.
+    pub async fn get_state(&self) -> Result<DjedState> {
+        let pattern = self
+            .pool
+            .as_deref()
+            .ok_or_else(|| anyhow!("Djed: no pool pattern configured"))?;
+        let pool = self.read_datum(pattern).await?;
+        let (djed_circulating, shen_circulating, ada_reserve) = parse_pool_datum(&pool.1)?;
+        let ada_usd = match &self.oracle {
+            Some(oracle) => Some(parse_oracle_datum(&self.read_datum(oracle).await?.1)?),
+            None => None,
+        };
+
+        Ok(DjedState {
+            ada_reserve,
+            djed_circulating,
+            shen_circulating,
+            ada_usd,
+            slot: pool.0.slot,
+            observed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        })
+    }
+
+    /// First unspent UTXO matching `pattern`, with its datum.
+    async fn read_datum(&self, pattern: &str) -> Result<(Utxo, String)> {
+        let utxo = self
+            .kupo
+            .get(pattern, true)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Djed: no UTXO found for {}", pattern))?;
+        let datum = self
+            .kupo
+            .utxo_datum(&utxo)
+            .await?
+            .ok_or_else(|| anyhow!("Djed: UTXO {} has no datum", utxo.tx_hash))?;
+        Ok((utxo, datum))
+    }
+}
+
+/// `(djed_circulating, shen_circulating, ada_reserve)` of a pool datum.
+fn parse_pool_datum(cbor_hex: &str) -> Result<(u64, u64, u64)> {
+    let value = decode_cbor(cbor_hex)?;
+    let fields = constr_fields(&value)?;
+    if fields.len() < 3 {
+        return Err(anyhow!(
+            "Djed pool datum: expected >=3 fields, got {}",
+            fields.len()
+        ));
+    }
+    Ok((
+        value_to_u64(&fields[0])?,
+        value_to_u64(&fields[1])?,
+        value_to_u64(&fields[2])?,
+    ))
+}
+
+/// ADA/USD rate of an oracle datum.
+fn parse_oracle_datum(cbor_hex: &str) -> Result<f64> {
+    let value = decode_cbor(cbor_hex)?;
+    let oracle_fields = constr_fields(&value)?
+        .first()
+        .ok_or_else(|| anyhow!("Djed oracle datum: no oracle fields"))?;
+    let rate = constr_fields(oracle_fields)?
+        .first()
+        .ok_or_else(|| anyhow!("Djed oracle datum: no exchange rate"))?;
+    let rate = constr_fields(rate)?;
+    if rate.len() != 2 {
+        return Err(anyhow!(
+            "Djed oracle datum: rate expected 2 fields, got {}",
+            rate.len()
+        ));
+    }
+    let (numerator, denominator) = (value_to_u64(&rate[0])?, value_to_u64(&rate[1])?);
+    if denominator == 0 {
+        return Err(anyhow!("Djed oracle datum: zero denominator"));
+    }
+    Ok(numerator as f64 / denominator as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_state_and_ratio() {
+        // Constr 0 [1_000, 500, 20_000, h''].
+        let (djed, shen, reserve) = parse_pool_datum("d8799f1903e81901f4194e2040ff").unwrap();
+        assert_eq!((djed, shen, reserve), (1_000, 500, 20_000));
+        // Constr 0 [Constr 0 [Constr 0 [1, 4]]]: 0.25 USD per ADA.
+        let ada_usd = parse_oracle_datum("d8799fd8799fd8799f0104ffffff").unwrap();
+        assert_eq!(ada_usd, 0.25);
+
+        let state = DjedState {
+            ada_reserve: reserve,
+            djed_circulating: djed,
+            shen_circulating: shen,
+            ada_usd: Some(ada_usd),
+            slot: None,
+            observed_at: 0,
+        };
+        // 20,000 lovelace at 0.25 backs 5,000 against 1,000 DJED.
+        assert_eq!(state.reserve_ratio(), Some(5.0));
+        assert_eq!(state.djed_price_lovelace(), Some(4.0));
+        // (20,000 - 1,000 * 4) / 500.
+        assert_eq!(state.shen_price_lovelace(), Some(32.0));
+        let unpriced = DjedState { ada_usd: None, ..state };
+        assert_eq!(unpriced.reserve_ratio(), None);
+    }
+}
@@ -0,0 +1,151 @@
+//! Pool filtering policy shared by all AMM DEXes.
+//!
+//! Every DEX runs its pools through [`PoolFilter::check`] at the end of the
+//! extend step (see [`BaseDex::filter_pool`](super::BaseDex::filter_pool)), so
+//! dust, zap and spam pools are dropped by one configurable policy instead of
+//! per-DEX heuristics.
+
+use crate::models::{LiquidityPool, Token};
+use serde::{Deserialize, Serialize};
+
+/// Rule that caused a pool to be filtered out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterRule {
+    /// ADA side below `min_ada_reserve`.
+    MinAdaReserve,
+    /// One side is an LP token of the same DEX (Minswap "zap" pools).
+    ZapPool,
+    /// One side holds a single unit — an NFT paired against a real token.
+    SpamNft,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolFilter {
+    /// Minimum lovelace reserve for ADA pools; 0 disables the rule.
+    pub min_ada_reserve: u64,
+    pub skip_zap_pools: bool,
+    pub skip_spam_nft_pools: bool,
+    /// Log the rule that filtered each pool to stderr.
+    pub verbose: bool,
+}
+
+impl PoolFilter {
+    /// Matches the behaviour before filters were configurable: only zap pools are dropped.
+    pub const DEFAULT: PoolFilter = PoolFilter {
+        min_ada_reserve: 0,
+        skip_zap_pools: true,
+        skip_spam_nft_pools: false,
+        verbose: false,
+    };
+
+    pub fn with_min_ada_reserve(mut self, lovelace: u64) -> Self {
+        self.min_ada_reserve = lovelace;
+        self
+    }
+
+    pub fn with_skip_zap_pools(mut self, skip: bool) -> Self {
+        self.skip_zap_pools = skip;
+        self
+    }
+
+    pub fn with_skip_spam_nft_pools(mut self, skip: bool) -> Self {
+        self.skip_spam_nft_pools = skip;
+        self
+    }
+
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    /// Whether `rule` is switched on in this policy.
+    pub fn enabled(&self, rule: FilterRule) -> bool {
+        match rule {
+            FilterRule::MinAdaReserve => self.min_ada_reserve > 0,
+            FilterRule::ZapPool => self.skip_zap_pools,
+            FilterRule::SpamNft => self.skip_spam_nft_pools,
+        }
+    }
+
+    /// Returns the first enabled rule the pool violates, if any.
+    pub fn check(&self, pool: &LiquidityPool) -> Option<FilterRule> {
+        if self.min_ada_reserve > 0 {
+            let ada_reserve = if pool.asset_a.is_lovelace() {
+                Some(pool.reserve_a)
+            } else if pool.asset_b.is_lovelace() {
+                Some(pool.reserve_b)
+            } else {
+                None
+            };
+            if ada_reserve.is_some_and(|r| r < self.min_ada_reserve) {
+                return Some(FilterRule::MinAdaReserve);
+            }
+        }
+
+        if self.skip_zap_pools && pool.pool_id.len() >= 56 {
+            let lp_policy = &pool.pool_id[..56];
+            let is_lp = |t: &Token| t.policy_id() == Some(lp_policy);
+            if is_lp(&pool.asset_a) || is_lp(&pool.asset_b) {
+                return Some(FilterRule::ZapPool);
+            }
+        }
+
+        if self.skip_spam_nft_pools && (pool.reserve_a == 1 || pool.reserve_b == 1) {
+            return Some(FilterRule::SpamNft);
+        }
+
+        None
+    }
+
+    /// Log a filtered pool when verbose.
+    pub fn report(&self, rule: FilterRule, dex: &str, pool_id: &str) {
+        if self.verbose {
+            eprintln!("[{}] filtered pool {}: {:?}", dex, pool_id, rule);
+        }
+    }
+}
+
+impl Default for PoolFilter {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+
+    const LP_POLICY: &str = "f5808c2c990d86da54bfc97d89cee6efa20cd8461616359478d96b4c";
+
+    fn pool(asset_b: &str, reserve_a: u64, reserve_b: u64) -> LiquidityPool {
+        LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            from_identifier(asset_b, 0),
+            reserve_a,
+            reserve_b,
+            "addr",
+            0.3,
+            &format!("{}00ff", LP_POLICY),
+        )
+    }
+
+    #[test]
+    fn test_filter_rules() {
+        let token = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+        let zap = format!("{}abcd", LP_POLICY);
+
+        let default = PoolFilter::default();
+        assert_eq!(default.check(&pool(token, 10, 1)), None);
+        assert_eq!(default.check(&pool(&zap, 10, 10)), Some(FilterRule::ZapPool));
+
+        let strict = PoolFilter::default()
+            .with_min_ada_reserve(1_000_000)
+            .with_skip_spam_nft_pools(true);
+        assert_eq!(strict.check(&pool(token, 10, 10)), Some(FilterRule::MinAdaReserve));
+        assert_eq!(strict.check(&pool(token, 5_000_000, 1)), Some(FilterRule::SpamNft));
+        assert_eq!(strict.check(&pool(token, 5_000_000, 10)), None);
+    }
+}
@@ -0,0 +1,446 @@
+//! Config-driven constant-product DEX.
+//!
+//! [`GenericAmmDex`] covers simple AMM forks whose pool UTXO holds the two
+//! reserves (plus optional min-ADA, pool NFT and validity token) and whose
+//! datum is a constructor with the reserves, fee and LP supply at fixed field
+//! indices. A fork is described by a [`GenericAmmConfig`] loaded from TOML or
+//! JSON, so it can be supported without a code release:
+//!
+//! ```toml
+//! [[dex]]
+//! identifier = "ForkSwap"
+//! validity_asset = "<policy id><asset name hex>"
+//! lp_token_policy_id = "<policy id>"
+//! pool_nft_policy_id = "<policy id>"
+//! total_lp_field = 2
+//! fee_field = 3
+//! fee_denominator = 10000
+//! ```
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::cbor::{constr_fields, decode_cbor, value_to_u64};
+use super::{BaseDex, PoolFilter, SkipReason};
+use crate::kupo::KupoApi;
+use crate::models::asset::from_identifier;
+use crate::models::{AssetId, LiquidityPool, Utxo};
+use crate::utils::{asset_pattern, normalize_address, RetryBudget};
+
+fn default_fee_denominator() -> u64 {
+    10_000
+}
+
+fn default_fee_percent() -> f64 {
+    0.3
+}
+
+/// Description of one AMM fork. Exactly one of `pool_address` and
+/// `validity_asset` locates the pool UTXOs. Datum field indices refer to the
+/// fields of the top-level constructor; unset fields fall back to the UTXO
+/// amounts (reserves), `fee_percent` (fee) and 0 (LP supply).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenericAmmConfig {
+    pub identifier: String,
+    /// Bech32, or hex / CBOR hex address bytes or a script hash (converted on load).
+    #[serde(default)]
+    pub pool_address: Option<String>,
+    /// Unit (policy id + asset name hex) held by every pool UTXO.
+    #[serde(default)]
+    pub validity_asset: Option<String>,
+    pub lp_token_policy_id: String,
+    /// Policy of the per-pool NFT; its unit becomes the pool id. Without it
+    /// the pool id is the UTXO out-ref, which changes on every swap.
+    #[serde(default)]
+    pub pool_nft_policy_id: Option<String>,
+    #[serde(default)]
+    pub reserve_a_field: Option<usize>,
+    #[serde(default)]
+    pub reserve_b_field: Option<usize>,
+    #[serde(default)]
+    pub total_lp_field: Option<usize>,
+    /// Fee numerator; the fee is `value / fee_denominator`.
+    #[serde(default)]
+    pub fee_field: Option<usize>,
+    #[serde(default = "default_fee_denominator")]
+    pub fee_denominator: u64,
+    /// Fee used when `fee_field` is unset.
+    #[serde(default = "default_fee_percent")]
+    pub fee_percent: f64,
+}
+
+#[derive(Deserialize)]
+struct ConfigFile {
+    dex: Vec<GenericAmmConfig>,
+}
+
+impl GenericAmmConfig {
+    /// Check that the pools can be located and the fee is computable.
+    pub fn validate(&self) -> Result<()> {
+        match (&self.pool_address, &self.validity_asset) {
+            (Some(_), None) | (None, Some(_)) => {}
+            _ => {
+                return Err(anyhow!(
+                    "{}: set exactly one of pool_address and validity_asset",
+                    self.identifier
+                ))
+            }
+        }
+        if self.fee_field.is_some() && self.fee_denominator == 0 {
+            return Err(anyhow!("{}: fee_denominator must be > 0", self.identifier));
+        }
+        Ok(())
+    }
+
+    /// Parse a `[[dex]]` list from TOML.
+    pub fn from_toml_str(s: &str) -> Result<Vec<Self>> {
+        let file: ConfigFile =
+            toml::from_str(s).map_err(|e| anyhow!("Failed to parse DEX config: {}", e))?;
+        Self::validated(file.dex)
+    }
+
+    /// Parse a `{ "dex": [...] }` list from JSON.
+    pub fn from_json_str(s: &str) -> Result<Vec<Self>> {
+        let file: ConfigFile =
+            serde_json::from_str(s).map_err(|e| anyhow!("Failed to parse DEX config: {}", e))?;
+        Self::validated(file.dex)
+    }
+
+    /// Load a config file; `.toml` files are parsed as TOML, anything else as JSON.
+    pub fn load_file(path: &str) -> Result<Vec<Self>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to open DEX config {}: {}", path, e))?;
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml_str(&content),
+            _ => Self::from_json_str(&content),
+        }
+    }
+
+    /// Validate `configs`, rewriting hex / CBOR / script hash pool addresses
+    /// to bech32.
+    fn validated(mut configs: Vec<Self>) -> Result<Vec<Self>> {
+        for config in &mut configs {
+            config.validate()?;
+            if let Some(address) = config.pool_address.as_mut() {
+                if !address.starts_with("addr") {
+                    *address = normalize_address(address)
+                        .map_err(|e| anyhow!("{}: {}", config.identifier, e))?;
+                }
+            }
+        }
+        Ok(configs)
+    }
+
+    /// Units held by the pool UTXO that are not reserves.
+    fn is_bookkeeping_unit(&self, unit: &str) -> bool {
+        self.validity_asset.as_deref() == Some(unit)
+            || unit.starts_with(&self.lp_token_policy_id)
+            || self
+                .pool_nft_policy_id
+                .as_deref()
+                .is_some_and(|policy| unit.starts_with(policy))
+    }
+}
+
+/// Values read from the pool datum.
+#[derive(Debug, Default, PartialEq)]
+struct GenericDatum {
+    reserve_a: Option<u64>,
+    reserve_b: Option<u64>,
+    total_lp: Option<u64>,
+    fee_numerator: Option<u64>,
+}
+
+fn parse_pool_datum(config: &GenericAmmConfig, cbor_hex: &str) -> Result<GenericDatum> {
+    let value = decode_cbor(cbor_hex)?;
+    let fields = constr_fields(&value)?;
+    let field = |index: Option<usize>| -> Result<Option<u64>> {
+        index
+            .map(|i| {
+                let v = fields.get(i).ok_or_else(|| {
+                    anyhow!(
+                        "{} datum: field {} out of range ({} fields)",
+                        config.identifier,
+                        i,
+                        fields.len()
+                    )
+                })?;
+                value_to_u64(v)
+            })
+            .transpose()
+    };
+    Ok(GenericDatum {
+        reserve_a: field(config.reserve_a_field)?,
+        reserve_b: field(config.reserve_b_field)?,
+        total_lp: field(config.total_lp_field)?,
+        fee_numerator: field(config.fee_field)?,
+    })
+}
+
+pub struct GenericAmmDex {
+    kupo: KupoApi,
+    config: GenericAmmConfig,
+    filter: PoolFilter,
+}
+
+impl GenericAmmDex {
+    pub fn new(kupo: impl Into<KupoApi>, config: GenericAmmConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            kupo: kupo.into(),
+            config,
+            filter: PoolFilter::default(),
+        })
+    }
+
+    /// One DEX per entry of the config file at `path`.
+    pub fn load_file(kupo: &KupoApi, path: &str) -> Result<Vec<Self>> {
+        GenericAmmConfig::load_file(path)?
+            .into_iter()
+            .map(|config| Self::new(kupo, config))
+            .collect()
+    }
+
+    pub fn with_pool_filter(mut self, filter: PoolFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn config(&self) -> &GenericAmmConfig {
+        &self.config
+    }
+}
+
+#[async_trait]
+impl BaseDex for GenericAmmDex {
+    fn identifier(&self) -> &str {
+        &self.config.identifier
+    }
+
+    fn pool_address(&self) -> &str {
+        self.config.pool_address.as_deref().unwrap_or("")
+    }
+
+    fn lp_token_policy_id(&self) -> &str {
+        &self.config.lp_token_policy_id
+    }
+
+    fn kupo(&self) -> &KupoApi {
+        &self.kupo
+    }
+
+    fn pool_filter(&self) -> &PoolFilter {
+        &self.filter
+    }
+
+    fn pool_match_patterns(&self) -> Vec<String> {
+        match (&self.config.pool_address, &self.config.validity_asset) {
+            (Some(address), _) => vec![address.clone()],
+            (None, Some(unit)) => vec![asset_pattern(unit)],
+            (None, None) => Vec::new(),
+        }
+    }
+
+    async fn all_liquidity_pool_utxos(&self) -> Result<Vec<Utxo>> {
+        match (&self.config.pool_address, &self.config.validity_asset) {
+            (Some(address), _) => self.kupo.get(address, true).await,
+            (None, Some(unit)) => {
+                let mut utxos = self.kupo.get(&asset_pattern(unit), true).await?;
+                utxos.retain(|u| u.amount.iter().any(|a| &a.unit == unit));
+                Ok(utxos)
+            }
+            (None, None) => Ok(Vec::new()),
+        }
+    }
+
+    async fn liquidity_pool_from_utxo(
+        &self,
+        utxo: &Utxo,
+        pool_id: &str,
+    ) -> Result<Option<LiquidityPool>> {
+        if let Some(unit) = &self.config.validity_asset {
+            if !utxo.amount.iter().any(|a| &a.unit == unit) {
+                return Ok(None);
+            }
+        }
+
+        let mut pool_id = pool_id.to_string();
+        let relevant: Vec<_> = utxo
+            .amount
+            .iter()
+            .filter(|a| {
+                let is_nft = self
+                    .config
+                    .pool_nft_policy_id
+                    .as_deref()
+                    .is_some_and(|policy| a.unit.starts_with(policy));
+                if is_nft {
+                    pool_id = a.unit.clone();
+                }
+                !self.config.is_bookkeeping_unit(&a.unit)
+            })
+            .collect();
+
+        // A third unit is the min-ADA of a token/token pool.
+        let (a, b) = match relevant.as_slice() {
+            [a, b] => (a, b),
+            [ada, a, b] if ada.is_lovelace() => (a, b),
+            _ => return Ok(None),
+        };
+        if pool_id.is_empty() {
+            pool_id = format!("{}#{}", utxo.tx_hash, utxo.output_index);
+        }
+
+        Ok(Some(
+            LiquidityPool::new(
+                &self.config.identifier,
+                from_identifier(&a.unit, 0),
+                from_identifier(&b.unit, 0),
+                a.quantity,
+                b.quantity,
+                &utxo.address,
+                self.config.fee_percent,
+                &pool_id,
+            )
+            .observed_in(utxo),
+        ))
+    }
+
+    /// Apply the configured datum fields on top of the amount-based pool.
+    async fn liquidity_pool_from_utxo_extend_or_skip(
+        &self,
+        utxo: &Utxo,
+        pool_id: &str,
+    ) -> Result<Result<LiquidityPool, SkipReason>> {
+        let mut pool = match self.liquidity_pool_from_utxo(utxo, pool_id).await? {
+            Some(p) => p,
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
+        };
+
+        let c = &self.config;
+        let needs_datum = c.reserve_a_field.is_some()
+            || c.reserve_b_field.is_some()
+            || c.total_lp_field.is_some()
+            || c.fee_field.is_some();
+        if needs_datum {
+            let datum = match self.kupo.utxo_datum(utxo).await? {
+                Some(d) => d,
+                None => return Ok(Err(SkipReason::no_pool(utxo))),
+            };
+            let d = parse_pool_datum(c, &datum)?;
+            if let Some(reserve) = d.reserve_a {
+                pool.reserve_a = reserve;
+            }
+            if let Some(reserve) = d.reserve_b {
+                pool.reserve_b = reserve;
+            }
+            if let Some(total_lp) = d.total_lp {
+                pool.total_lp_tokens = total_lp;
+            }
+            if let Some(numerator) = d.fee_numerator {
+                pool.pool_fee_percent = numerator as f64 / c.fee_denominator as f64 * 100.0;
+            }
+        }
+
+        Ok(self.filter_pool_or_skip(pool))
+    }
+
+    async fn liquidity_pool_from_pool_id(&self, pool_id: &str) -> Result<Option<LiquidityPool>> {
+        let utxos = self.all_liquidity_pool_utxos().await?;
+        let found = utxos.iter().find(|u| {
+            u.amount.iter().any(|a| a.unit == pool_id)
+                || format!("{}#{}", u.tx_hash, u.output_index) == pool_id
+        });
+
+        match found {
+            Some(utxo) => self.liquidity_pool_from_utxo_extend(utxo, pool_id).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn liquidity_pools_from_token(
+        &self,
+        token_b: AssetId,
+        token_a: AssetId,
+    ) -> Result<Vec<LiquidityPool>> {
+        RetryBudget::ensure_scope(async {
+            let all_utxos = self.all_liquidity_pool_utxos().await?;
+            let mut pools = Vec::new();
+
+            for utxo in &all_utxos {
+                let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
+                    Some(p) => p,
+                    None => continue,
+                };
+
+                let id_a = AssetId::from(&base.asset_a);
+                let id_b = AssetId::from(&base.asset_b);
+
+                let matches =
+                    (id_a == token_a && id_b == token_b) || (id_a == token_b && id_b == token_a);
+
+                if matches {
+                    match self
+                        .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
+                        .await
+                    {
+                        Ok(Some(p)) => pools.push(p),
+                        Ok(None) => {}
+                        Err(e) => eprintln!(
+                            "[{}] datum error {}: {}",
+                            self.config.identifier, utxo.tx_hash, e
+                        ),
+                    }
+                }
+            }
+
+            Ok(pools)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [[dex]]
+        identifier = "ForkSwap"
+        validity_asset = "aa00"
+        lp_token_policy_id = "bb"
+        reserve_b_field = 1
+        total_lp_field = 0
+        fee_field = 2
+    "#;
+
+    #[test]
+    fn test_config_and_datum_fields() {
+        let configs = GenericAmmConfig::from_toml_str(CONFIG).unwrap();
+        let config = &configs[0];
+        assert_eq!(config.fee_denominator, 10_000);
+        assert!(config.is_bookkeeping_unit("aa00") && config.is_bookkeeping_unit("bb01"));
+
+        // Constr 0 [1000, 2000, 30]
+        let datum = parse_pool_datum(config, "d8799f1903e81907d0181eff").unwrap();
+        assert_eq!(
+            datum,
+            GenericDatum {
+                reserve_a: None,
+                reserve_b: Some(2000),
+                total_lp: Some(1000),
+                fee_numerator: Some(30),
+            }
+        );
+
+        let mut bad = config.clone();
+        bad.pool_address = Some("addr1".to_string());
+        assert!(bad.validate().is_err());
+        assert!(GenericAmmConfig::from_json_str(
+            r#"{"dex":[{"identifier":"X","lp_token_policy_id":"bb"}]}"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,42 @@
+//! How long a swap takes to settle on each DEX.
+//!
+//! Batcher AMMs settle in two steps: the user's order lands in a block, then a
+//! batcher spends it against the pool some blocks later. Until then the order
+//! sits on chain for anyone to see and trade ahead of, so a slower venue costs
+//! both price drift and MEV exposure. Order-book fills (ChadSwap) spend the
+//! order UTXO directly and settle with the taker's transaction.
+//!
+//! Figures are typical mainnet cadences in slots (seconds), not guarantees.
+
+use serde::Serialize;
+
+/// Expected delay between submitting a swap and it settling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SettlementLatency {
+    /// Slots until the swap is expected to settle.
+    pub expected_slots: u64,
+    /// Whether a batcher settles the order in a later transaction, leaving it
+    /// visible on chain in between.
+    pub batched: bool,
+}
+
+impl SettlementLatency {
+    /// Settled by a batcher about `slots` after submission.
+    pub const fn batched(slots: u64) -> Self {
+        Self {
+            expected_slots: slots,
+            batched: true,
+        }
+    }
+
+    /// Settled by the submitting transaction itself, about `slots` after submission.
+    pub const fn direct(slots: u64) -> Self {
+        Self {
+            expected_slots: slots,
+            batched: false,
+        }
+    }
+}
+
+/// Latency assumed for batcher DEXes without their own, e.g. generic forks.
+pub const DEFAULT_BATCHED: SettlementLatency = SettlementLatency::batched(60);
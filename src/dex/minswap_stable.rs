@@ -3,11 +3,12 @@
 /// Unlike regular AMM DEXes, MinswapStable:
 ///   - Has NO pool discovery (cannot enumerate all pools)
 ///   - Requires explicit pool address + asset identifiers + decimals
-///   - Reads reserves from the datum (Balance0/Balance1), NOT from UTXO amounts
+///   - Reads reserves from the datum's balance list, NOT from UTXO amounts
+///   - Pools may hold more than two assets (one balance per asset)
 ///   - Uses a fixed fee of 0.1%
 ///
 /// Datum structure (Plutus, Constr(0, [...])):
-///   [0]: Array([Balance0, Balance1])  — reserve balances list
+///   [0]: Array([Balance0, Balance1, ...])  — one balance per pool asset
 ///   [1]: int — TotalLiquidity (D invariant)
 ///   [2]: int — AmplificationCoefficient (A)
 ///   [3]: bytes — OrderHash (ignored)
@@ -20,6 +21,7 @@ use ciborium::value::Value;
 use crate::kupo::KupoApi;
 use crate::models::asset::from_identifier;
 use crate::models::{StablePool, Utxo};
+use crate::utils::normalize_address;
 use super::cbor::{constr_fields, decode_cbor, value_to_u64};
 
 const IDENTIFIER: &str = "MinswapStable";
@@ -30,17 +32,18 @@ pub struct MinswapStable {
 }
 
 impl MinswapStable {
-    pub fn new(kupo: KupoApi) -> Self {
-        Self { kupo }
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
+        Self { kupo: kupo.into() }
     }
 
     pub fn identifier(&self) -> &str {
         IDENTIFIER
     }
 
-    /// Fetch and build a StablePool by its on-chain address.
+    /// Fetch and build a two-asset StablePool by its on-chain address.
     ///
-    /// - `pool_address` — the bech32 pool address (acts as pool_id)
+    /// - `pool_address` — the pool address (acts as pool_id); bech32, hex or
+    ///   CBOR hex bytes, or the bare script hash (see [`normalize_address`])
     /// - `asset_a_id`   — hex identifier for asset A (or "lovelace")
     /// - `asset_b_id`   — hex identifier for asset B
     /// - `decimals_a`   — decimal places for asset A (typically 6)
@@ -53,13 +56,32 @@ impl MinswapStable {
         decimals_a: u8,
         decimals_b: u8,
     ) -> Result<StablePool> {
+        let assets = [(asset_a_id, decimals_a), (asset_b_id, decimals_b)];
+        self.get_pool_with_assets(pool_address, &assets).await
+    }
+
+    /// Fetch and build a StablePool holding `assets` (identifier, decimals),
+    /// in the order of the datum's balances. Fails on fewer than two assets.
+    pub async fn get_pool_with_assets(
+        &self,
+        pool_address: &str,
+        assets: &[(&str, u8)],
+    ) -> Result<StablePool> {
+        if assets.len() < 2 {
+            return Err(anyhow!(
+                "A stable pool holds at least two assets, {} given",
+                assets.len()
+            ));
+        }
+        // Accept hex, CBOR and script hash spellings; Kupo matches bech32 only.
+        let pool_address = &normalize_address(pool_address)?;
         let utxos = self.kupo.get(pool_address, true).await?;
         let utxo = utxos
             .into_iter()
             .next()
             .ok_or_else(|| anyhow!("No UTXOs found at pool address: {}", pool_address))?;
 
-        self.pool_from_utxo(&utxo, asset_a_id, asset_b_id, decimals_a, decimals_b, pool_address)
+        self.pool_from_utxo(&utxo, assets, pool_address)
             .await?
             .ok_or_else(|| anyhow!("Could not build stable pool from UTXO at {}", pool_address))
     }
@@ -67,48 +89,48 @@ impl MinswapStable {
     async fn pool_from_utxo(
         &self,
         utxo: &Utxo,
-        asset_a_id: &str,
-        asset_b_id: &str,
-        decimals_a: u8,
-        decimals_b: u8,
+        assets: &[(&str, u8)],
         pool_id: &str,
     ) -> Result<Option<StablePool>> {
-        let data_hash = match &utxo.data_hash {
-            Some(h) => h.clone(),
+        let datum_cbor = match self.kupo.utxo_datum(utxo).await? {
+            Some(d) => d,
             None => return Ok(None),
         };
-
-        let datum_cbor = match &utxo.inline_datum {
-            Some(d) => d.clone(),
-            None => self.kupo.datum(&data_hash).await?,
-        };
         let datum = parse_stable_datum(&datum_cbor).map_err(|e| {
             anyhow!("Failed parsing stable datum at {}: {}", utxo.address, e)
         })?;
+        if datum.balances.len() != assets.len() {
+            return Err(anyhow!(
+                "Stable pool at {} holds {} assets, {} given",
+                utxo.address,
+                datum.balances.len(),
+                assets.len()
+            ));
+        }
 
-        let asset_a = from_identifier(asset_a_id, decimals_a);
-        let asset_b = from_identifier(asset_b_id, decimals_b);
-
-        Ok(Some(StablePool {
-            dex_identifier: IDENTIFIER.to_string(),
-            asset_a,
-            asset_b,
-            reserve_a: datum.balance_0,
-            reserve_b: datum.balance_1,
-            address: utxo.address.clone(),
-            pool_id: pool_id.to_string(),
-            pool_fee_percent: POOL_FEE_PERCENT,
-            amplification_coefficient: datum.amplification,
-            total_liquidity: datum.total_liquidity,
-        }))
+        let assets = assets
+            .iter()
+            .zip(&datum.balances)
+            .map(|((id, decimals), balance)| (from_identifier(id, *decimals), *balance))
+            .collect();
+
+        let pool = StablePool::new(
+            IDENTIFIER,
+            assets,
+            &utxo.address,
+            pool_id,
+            POOL_FEE_PERCENT,
+            datum.amplification,
+            datum.total_liquidity,
+        )?;
+        Ok(Some(pool.observed_in(utxo)))
     }
 }
 
 // ── Datum parsing ─────────────────────────────────────────────────────────────
 
 struct StableDatum {
-    balance_0: u64,
-    balance_1: u64,
+    balances: Vec<u64>,
     total_liquidity: u64,
     amplification: u64,
 }
@@ -134,7 +156,7 @@ fn parse_stable_datum(cbor_hex: &str) -> Result<StableDatum> {
         ));
     }
 
-    // fields[0]: plain Array([Balance0, Balance1]) — NOT a constructor
+    // fields[0]: plain Array([Balance0, Balance1, ...]) — NOT a constructor
     let balances = match &fields[0] {
         Value::Array(arr) => arr,
         other => {
@@ -150,8 +172,7 @@ fn parse_stable_datum(cbor_hex: &str) -> Result<StableDatum> {
             balances.len()
         ));
     }
-    let balance_0 = value_to_u64(&balances[0])?;
-    let balance_1 = value_to_u64(&balances[1])?;
+    let balances = balances.iter().map(value_to_u64).collect::<Result<Vec<_>>>()?;
 
     // fields[1]: TotalLiquidity
     let total_liquidity = value_to_u64(&fields[1])?;
@@ -162,9 +183,35 @@ fn parse_stable_datum(cbor_hex: &str) -> Result<StableDatum> {
     // fields[3]: OrderHash bytes — ignored
 
     Ok(StableDatum {
-        balance_0,
-        balance_1,
+        balances,
         total_liquidity,
         amplification,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_three_balance_datum() {
+        // Constr 0 [[1, 2, 3], 100, 10, h''].
+        let datum = parse_stable_datum("d8799f9f010203ff18640a40ff").unwrap();
+        assert_eq!(datum.balances, [1, 2, 3]);
+        assert_eq!((datum.total_liquidity, datum.amplification), (100, 10));
+    }
+
+    #[tokio::test]
+    async fn test_single_asset_pool_is_rejected() {
+        // Rejected before any Kupo request is made.
+        let dex = MinswapStable::new(KupoApi::new("http://127.0.0.1:9"));
+        let err = dex
+            .get_pool_with_assets("addr1pool", &[("lovelace", 6)])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("at least two assets"), "{}", err);
+
+        let one = vec![(from_identifier("lovelace", 6), 1)];
+        assert!(StablePool::new(IDENTIFIER, one, "addr1pool", "pool", 0.1, 10, 1).is_err());
+    }
+}
@@ -1,29 +1,136 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use crate::models::{Utxo, LiquidityPool};
-use crate::models::asset::{from_identifier, token_identifier};
+use crate::models::{AssetId, Credential, Utxo, LiquidityPool, PoolAdmin, reserve_pair};
+use crate::models::asset::from_identifier;
 use crate::kupo::KupoApi;
-use super::BaseDex;
+use super::overrides::{DexOverrides, ValidityAsset};
+use super::{BaseDex, PoolFilter, SkipReason};
+use super::cbor::{address_payment_credential, constr_fields, decode_cbor, is_nonempty_constr, parse_asset_constr, value_to_u64};
+use super::latency::SettlementLatency;
+use crate::quote::exact::ScaledFee;
+use crate::utils::RetryBudget;
 
 const IDENTIFIER: &str = "MinswapV1";
+/// Validator swap math: fee kept in the product, `in * 997 / 1000`.
+pub const SWAP_MATH: ScaledFee = ScaledFee;
+/// MinswapV1 batchers pick orders up within a few blocks.
+pub const SETTLEMENT: SettlementLatency = SettlementLatency::batched(60);
 const LP_TOKEN_POLICY_ID: &str = "e4214b7cce62ac6fbba385d164df48e157eae5863521b4b67ca71d86";
 const POOL_NFT_POLICY_ID: &str = "0be55d262b29f564998ff81efe21bdc0022621c12f15af08d0f2ddb1";
 // The validity asset is queried as <policy>.<name> — Kupo returns UTXOs containing it
 const POOL_VALIDITY_ASSET: &str = "13aa2accf2e1561723aa26871e071fdf32c867cff7e7d50ad470d62f.4d494e53574150";
-// Joined form (no dot) used for filtering amounts
-const POOL_VALIDITY_ASSET_JOINED: &str = "13aa2accf2e1561723aa26871e071fdf32c867cff7e7d50ad470d62f4d494e53574150";
 const POOL_FEE_PERCENT: f64 = 0.3;
 
 pub struct MinswapV1 {
     kupo: KupoApi,
+    filter: PoolFilter,
+    validity_asset: ValidityAsset,
+    lp_token_policy_id: String,
+    fee_percent: f64,
 }
 
 impl MinswapV1 {
-    pub fn new(kupo: KupoApi) -> Self {
-        Self { kupo }
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
+        Self {
+            kupo: kupo.into(),
+            filter: PoolFilter::default(),
+            validity_asset: ValidityAsset::new(POOL_VALIDITY_ASSET),
+            lp_token_policy_id: LP_TOKEN_POLICY_ID.to_string(),
+            fee_percent: POOL_FEE_PERCENT,
+        }
+    }
+
+    /// Replace the validity asset, LP policy or fee (see [`DexOverrides`]).
+    pub fn with_overrides(mut self, overrides: &DexOverrides) -> Result<Self> {
+        overrides.validate(IDENTIFIER)?;
+        self.validity_asset = overrides.validity_asset_or(POOL_VALIDITY_ASSET);
+        self.lp_token_policy_id = overrides.lp_token_policy_id_or(LP_TOKEN_POLICY_ID);
+        self.fee_percent = overrides.fee_percent_or(POOL_FEE_PERCENT);
+        Ok(self)
+    }
+
+    pub fn with_pool_filter(mut self, filter: PoolFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Fetch and decode the pool datum of a V1 pool UTXO.
+    pub async fn pool_datum(&self, utxo: &Utxo) -> Result<Option<PoolDatum>> {
+        let datum = match self.kupo.utxo_datum(utxo).await? {
+            Some(d) => d,
+            None => return Ok(None),
+        };
+        parse_pool_datum(&datum).map(Some)
+    }
+}
+
+/// Decoded MinswapV1 pool datum.
+#[derive(Debug, Clone)]
+pub struct PoolDatum {
+    /// `(policy_hex, name_hex)`; lovelace is `("", "")`.
+    pub asset_a: (String, String),
+    pub asset_b: (String, String),
+    pub total_liquidity: u64,
+    /// sqrt(reserve_a * reserve_b) at the last liquidity event, used to mint
+    /// the protocol's share of fees when profit sharing is on.
+    pub root_k_last: u64,
+    /// Payment credential of the profit-sharing `feeTo` address, if enabled.
+    pub fee_sharing_to: Option<Credential>,
+}
+
+impl PoolDatum {
+    pub fn fee_sharing_enabled(&self) -> bool {
+        self.fee_sharing_to.is_some()
     }
 }
 
+/// Parse the MinswapV1 pool datum.
+///
+/// Structure (constructor 0):
+///   [0] asset_a        (constr {policy_bytes, name_bytes})
+///   [1] asset_b        (constr {policy_bytes, name_bytes})
+///   [2] total_liquidity (int)
+///   [3] root_k_last    (int)
+///   [4] fee_sharing    (Maybe ProfitSharing) — Just = constr 0 [constr 0 [fee_to address, Maybe datum hash]]
+fn parse_pool_datum(cbor_hex: &str) -> Result<PoolDatum> {
+    let value = decode_cbor(cbor_hex)?;
+    let fields = constr_fields(&value)?;
+
+    if fields.len() < 4 {
+        return Err(anyhow!("MinswapV1 datum: expected >=4 fields, got {}", fields.len()));
+    }
+
+    let asset_a = parse_asset_constr(&fields[0])?;
+    let asset_b = parse_asset_constr(&fields[1])?;
+    let total_liquidity = value_to_u64(&fields[2])?;
+    let root_k_last = value_to_u64(&fields[3])?;
+
+    // fields[4]: Maybe ProfitSharing. Nothing = empty constr (or absent).
+    let fee_sharing_to = match fields.get(4) {
+        Some(maybe) if is_nonempty_constr(maybe) => Some(fee_to_credential(maybe)?),
+        _ => None,
+    };
+
+    Ok(PoolDatum {
+        asset_a,
+        asset_b,
+        total_liquidity,
+        root_k_last,
+        fee_sharing_to,
+    })
+}
+
+/// Just(ProfitSharing { fee_to: Address { credential, .. }, .. }) → credential.
+fn fee_to_credential(maybe: &ciborium::value::Value) -> Result<Credential> {
+    let profit_sharing = constr_fields(maybe)?
+        .first()
+        .ok_or_else(|| anyhow!("MinswapV1 datum: empty Just"))?;
+    let address = constr_fields(profit_sharing)?
+        .first()
+        .ok_or_else(|| anyhow!("MinswapV1 datum: ProfitSharing without address"))?;
+    address_payment_credential(address)
+}
+
 #[async_trait]
 impl BaseDex for MinswapV1 {
     fn identifier(&self) -> &str {
@@ -32,40 +139,55 @@ impl BaseDex for MinswapV1 {
 
     fn pool_address(&self) -> &str {
         // MinswapV1 has no single pool address — pools are indexed by validity asset
-        POOL_VALIDITY_ASSET
+        &self.validity_asset.pattern
     }
 
     fn lp_token_policy_id(&self) -> &str {
-        LP_TOKEN_POLICY_ID
+        &self.lp_token_policy_id
+    }
+
+    /// LP tokens share the pool NFT's asset name under the LP policy.
+    fn lp_token_unit(&self, pool: &LiquidityPool) -> Option<String> {
+        let name = pool.pool_id.replace('.', "");
+        let name = name.strip_prefix(POOL_NFT_POLICY_ID)?;
+        Some(format!("{}{}", self.lp_token_policy_id, name))
     }
 
     fn kupo(&self) -> &KupoApi {
         &self.kupo
     }
 
+    fn pool_filter(&self) -> &PoolFilter {
+        &self.filter
+    }
+
     /// Fetch all pool UTXOs by querying Kupo for the validity asset.
+    fn pool_match_patterns(&self) -> Vec<String> {
+        vec![self.validity_asset.pattern.clone()]
+    }
+
     async fn all_liquidity_pool_utxos(&self) -> Result<Vec<Utxo>> {
-        self.kupo.get(POOL_VALIDITY_ASSET, true).await
+        self.kupo.get(&self.validity_asset.pattern, true).await
     }
 
     /// Build a LiquidityPool from a UTXO.
     /// MinswapV1 does not need a datum — reserves come from UTXO amounts.
     async fn liquidity_pool_from_utxo(&self, utxo: &Utxo, pool_id: &str) -> Result<Option<LiquidityPool>> {
-        if utxo.data_hash.is_none() {
+        if utxo.data_hash.is_none() && utxo.inline_datum.is_none() {
             return Ok(None);
         }
 
         // Filter out the validity asset, LP tokens, and NFT tokens
         let relevant: Vec<_> = utxo.amount.iter().filter(|a| {
             let u = &a.unit;
-            u != POOL_VALIDITY_ASSET_JOINED
-                && !u.starts_with(LP_TOKEN_POLICY_ID)
+            *u != self.validity_asset.unit
+                && !u.starts_with(&self.lp_token_policy_id)
                 && !u.starts_with(POOL_NFT_POLICY_ID)
         }).collect();
 
-        if relevant.len() < 2 {
+        let Some((unit_a, unit_b)) = reserve_pair(&relevant) else {
             return Ok(None);
-        }
+        };
 
         // Pool ID = first asset with the NFT policy
         let pool_id = utxo.amount.iter()
@@ -73,26 +195,53 @@ impl BaseDex for MinswapV1 {
             .map(|a| a.unit.clone())
             .unwrap_or_else(|| pool_id.to_string());
 
-        // ADA/X pool: index 0 = lovelace, index 1 = token
-        // X/X pool:   index 0 = first non-lovelace, skip it, index 1 & 2 are the pair
-        let asset_a_idx = if relevant.len() == 2 { 0 } else { 1 };
-        let asset_b_idx = if relevant.len() == 2 { 1 } else { 2 };
+        // ADA/X pool: lovelace and the token; X/Y pool: the two tokens (the
+        // lovelace is the min-ADA deposit).
+        let asset_a = from_identifier(&unit_a.unit, 0);
+        let asset_b = from_identifier(&unit_b.unit, 0);
+        let reserve_a = unit_a.quantity;
+        let reserve_b = unit_b.quantity;
 
-        let asset_a = from_identifier(&relevant[asset_a_idx].unit, 0);
-        let asset_b = from_identifier(&relevant[asset_b_idx].unit, 0);
-        let reserve_a = relevant[asset_a_idx].quantity.parse::<u64>()?;
-        let reserve_b = relevant[asset_b_idx].quantity.parse::<u64>()?;
+        Ok(Some(
+            LiquidityPool::new(
+                IDENTIFIER,
+                asset_a,
+                asset_b,
+                reserve_a,
+                reserve_b,
+                &utxo.address,
+                self.fee_percent,
+                &pool_id,
+            )
+            .observed_in(utxo),
+        ))
+    }
 
-        Ok(Some(LiquidityPool::new(
-            IDENTIFIER,
-            asset_a,
-            asset_b,
-            reserve_a,
-            reserve_b,
-            &utxo.address,
-            POOL_FEE_PERCENT,
-            &pool_id,
-        )))
+    /// Fetch the datum for total LP tokens; root-k-last goes into `extras` and
+    /// the profit-sharing recipient into the pool's admins.
+    /// The V1 trading fee is fixed at 0.3%; profit sharing only mints LP to `feeTo`
+    /// on liquidity events and does not change what traders pay.
+    async fn liquidity_pool_from_utxo_extend_or_skip(
+        &self,
+        utxo: &Utxo,
+        pool_id: &str,
+    ) -> Result<Result<LiquidityPool, SkipReason>> {
+        let mut pool = match self.liquidity_pool_from_utxo(utxo, pool_id).await? {
+            Some(p) => p,
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
+        };
+        let datum = match self.pool_datum(utxo).await? {
+            Some(d) => d,
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
+        };
+
+        pool.total_lp_tokens = datum.total_liquidity;
+        pool.set_extra("root_k_last", datum.root_k_last);
+        if let Some(fee_to) = datum.fee_sharing_to {
+            pool.add_admin(PoolAdmin::single("fee_to", fee_to));
+        }
+
+        Ok(self.filter_pool_or_skip(pool))
     }
 
     /// Look up a pool by its NFT pool ID.
@@ -113,32 +262,115 @@ impl BaseDex for MinswapV1 {
         let utxos = self.kupo.get(&full_id, true).await?;
 
         match utxos.first() {
-            Some(utxo) => self.liquidity_pool_from_utxo(utxo, &full_id).await,
+            Some(utxo) => self.liquidity_pool_from_utxo_extend(utxo, &full_id).await,
             None => Ok(None),
         }
     }
 
     /// Find all pools containing both tokens.
     /// Fetches all V1 pool UTXOs via the validity asset query (same as JS),
-    /// then filters client-side. No datum fetch needed.
-    async fn liquidity_pools_from_token(&self, token_b: &str, token_a: &str) -> Result<Vec<LiquidityPool>> {
-        let all_utxos = self.all_liquidity_pool_utxos().await?;
+    /// filters client-side, then fetches datums only for the matches.
+    async fn liquidity_pools_from_token(&self, token_b: AssetId, token_a: AssetId) -> Result<Vec<LiquidityPool>> {
+        RetryBudget::ensure_scope(async {
+            let all_utxos = self.all_liquidity_pool_utxos().await?;
 
-        let mut pools = Vec::new();
-        for utxo in &all_utxos {
-            if let Some(pool) = self.liquidity_pool_from_utxo(utxo, "").await? {
-                let id_a = token_identifier(&pool.asset_a);
-                let id_b = token_identifier(&pool.asset_b);
+            let mut pools = Vec::new();
+            for utxo in &all_utxos {
+                if let Some(base) = self.liquidity_pool_from_utxo(utxo, "").await? {
+                    let id_a = AssetId::from(&base.asset_a);
+                    let id_b = AssetId::from(&base.asset_b);
 
-                let matches = (id_a == token_a && id_b == token_b)
-                    || (id_a == token_b && id_b == token_a);
+                    let matches = (id_a == token_a && id_b == token_b)
+                        || (id_a == token_b && id_b == token_a);
 
-                if matches {
-                    pools.push(pool);
+                    if matches {
+                        match self.liquidity_pool_from_utxo_extend(utxo, &base.pool_id).await {
+                            Ok(Some(pool)) => pools.push(pool),
+                            Ok(None) => {}
+                            Err(e) => eprintln!("[minswap_v1] datum error {}: {}", utxo.tx_hash, e),
+                        }
+                    }
                 }
             }
-        }
 
-        Ok(pools)
+            Ok(pools)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ciborium::value::Value;
+    use std::collections::HashMap;
+
+    fn constr(index: u64, fields: Vec<Value>) -> Value {
+        Value::Tag(121 + index, Box::new(Value::Array(fields)))
+    }
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    fn datum_hex() -> String {
+        let bytes = |h: &str| Value::Bytes(hex::decode(h).unwrap());
+        let asset = |policy: &str, name: &str| constr(0, vec![bytes(policy), bytes(name)]);
+        let datum = constr(
+            0,
+            vec![
+                asset("", ""),
+                asset(&SNEK[..56], &SNEK[56..]),
+                Value::Integer(1_000.into()),
+                Value::Integer(900.into()),
+                constr(1, vec![]),
+            ],
+        );
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&datum, &mut cbor).unwrap();
+        hex::encode(cbor)
+    }
+
+    #[tokio::test]
+    async fn test_pool_datum_inline() {
+        // Inline datum without a datum hash; the Kupo is never contacted.
+        let utxo: Utxo = serde_json::from_value(serde_json::json!({
+            "address": "addr1x",
+            "tx_hash": "ab",
+            "tx_index": 0,
+            "output_index": 0,
+            "amount": [],
+            "block": "",
+            "data_hash": null,
+            "inline_datum": datum_hex(),
+        }))
+        .unwrap();
+
+        let dex = MinswapV1::new(KupoApi::new("http://127.0.0.1:9"));
+        let parsed = dex.pool_datum(&utxo).await.unwrap().unwrap();
+        assert_eq!(parsed.asset_a, (String::new(), String::new()));
+        assert_eq!(parsed.asset_b.1, "534e454b");
+        assert_eq!((parsed.total_liquidity, parsed.root_k_last), (1_000, 900));
+        assert!(parsed.fee_sharing_to.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pool_from_raw_output() {
+        let nft = format!("{}{}", POOL_NFT_POLICY_ID, "aa".repeat(32));
+        let value = HashMap::from([
+            ("lovelace".to_string(), 5_000_000_000),
+            (SNEK.to_string(), 7_000),
+            (nft.clone(), 1),
+            (POOL_VALIDITY_ASSET.to_string(), 1),
+        ]);
+
+        let dex = MinswapV1::new(KupoApi::new("http://127.0.0.1:9"));
+        let pool = dex
+            .liquidity_pool_from_raw_output("addr1pool", &value, Some(&datum_hex()))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.pool_id, nft);
+        assert!(pool.asset_a.is_lovelace());
+        assert_eq!((pool.reserve_a, pool.reserve_b), (5_000_000_000, 7_000));
+        assert_eq!(pool.total_lp_tokens, 1_000);
     }
 }
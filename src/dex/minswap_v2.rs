@@ -1,23 +1,80 @@
 use anyhow::{Result, anyhow};
 use async_trait::async_trait;
-use crate::models::{Token, Utxo, LiquidityPool, token_identifier};
+use crate::models::{AssetId, FeeBreakdown, Token, Utxo, LiquidityPool, reserve_pair, token_identifier};
 use crate::models::asset::from_identifier;
 use crate::kupo::KupoApi;
-use super::BaseDex;
+use super::overrides::{DexOverrides, ValidityAsset};
+use super::skip::SkipReason;
+use super::{BaseDex, FilterRule, PoolFilter};
 use super::cbor::{constr_fields, value_to_u64, parse_asset_constr, decode_cbor};
+use std::collections::HashSet;
+use super::latency::SettlementLatency;
+use crate::quote::exact::ScaledFee;
+use crate::utils::RetryBudget;
 
 const LP_TOKEN_POLICY_ID: &str = "f5808c2c990d86da54bfc97d89cee6efa20cd8461616359478d96b4c";
 const POOL_VALIDITY_ASSET: &str = "f5808c2c990d86da54bfc97d89cee6efa20cd8461616359478d96b4c4d5350";
 const POOL_SCRIPT_HASH_BECH32: &str = "script1agrmwv7exgffcdu27cn5xmnuhsh0p0ukuqpkhdgm800xksw7e2w";
 const IDENTIFIER: &str = "MinswapV2";
+/// Validator swap math: the fee numerator scales the input inside the product.
+pub const SWAP_MATH: ScaledFee = ScaledFee;
+/// MinswapV2 batchers usually settle in the block after the order.
+pub const SETTLEMENT: SettlementLatency = SettlementLatency::batched(40);
+/// Denominator of the datum's fee numerators (base fee and fee sharing).
+const FEE_DENOMINATOR: f64 = 10_000.0;
+
+/// How pool UTXOs are located.
+///
+/// Pools share one payment script but sit under many stake credentials. The
+/// script wildcard finds the usual ones; every real pool also holds the
+/// validity asset, which catches pools on non-standard addresses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PoolDiscovery {
+    /// `<script>/*` address wildcard (the JS behaviour).
+    #[default]
+    ScriptAddress,
+    /// UTXOs holding the pool validity asset.
+    ValidityAsset,
+    /// Both queries, deduplicated by output reference. Opt-in: it doubles
+    /// the discovery requests.
+    Merged,
+}
 
 pub struct MinswapV2 {
     kupo: KupoApi,
+    discovery: PoolDiscovery,
+    filter: PoolFilter,
+    validity_asset: ValidityAsset,
+    lp_token_policy_id: String,
 }
 
 impl MinswapV2 {
-    pub fn new(kupo: KupoApi) -> Self {
-        Self { kupo }
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
+        Self {
+            kupo: kupo.into(),
+            discovery: PoolDiscovery::default(),
+            filter: PoolFilter::default(),
+            validity_asset: ValidityAsset::new(POOL_VALIDITY_ASSET),
+            lp_token_policy_id: LP_TOKEN_POLICY_ID.to_string(),
+        }
+    }
+
+    /// Replace the validity asset or LP policy (see [`DexOverrides`]).
+    pub fn with_overrides(mut self, overrides: &DexOverrides) -> Result<Self> {
+        overrides.validate(IDENTIFIER)?;
+        self.validity_asset = overrides.validity_asset_or(POOL_VALIDITY_ASSET);
+        self.lp_token_policy_id = overrides.lp_token_policy_id_or(LP_TOKEN_POLICY_ID);
+        Ok(self)
+    }
+
+    pub fn with_pool_filter(mut self, filter: PoolFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_discovery(mut self, discovery: PoolDiscovery) -> Self {
+        self.discovery = discovery;
+        self
     }
 
     fn compare_token_with_policy(token: &Token, policy: &str) -> bool {
@@ -32,29 +89,45 @@ impl MinswapV2 {
         utxo: &Utxo,
         pool_id: &str,
     ) -> Result<Option<LiquidityPool>> {
+        Ok(self.extend_or_skip(utxo, pool_id).await?.ok())
+    }
+
+    async fn extend_or_skip(
+        &self,
+        utxo: &Utxo,
+        pool_id: &str,
+    ) -> Result<Result<LiquidityPool, SkipReason>> {
         let mut liquidity_pool = match self.liquidity_pool_from_utxo(utxo, pool_id).await? {
             Some(pool) => pool,
-            None => return Ok(None),
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
 
-        let data_hash = match &utxo.data_hash {
-            Some(h) => h,
-            None => return Ok(None),
-        };
-
-        let datum = match &utxo.inline_datum {
-            Some(d) => d.clone(),
-            None => self.kupo.datum(data_hash).await?,
+        let datum = match self.kupo.utxo_datum(utxo).await? {
+            Some(d) => d,
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
         let parsed = parse_pool_datum(&datum)?;
 
-        // Skip Zap pools (asset B policy == LP token policy), same as JS
-        if parsed.pool_asset_b_policy == LP_TOKEN_POLICY_ID {
-            return Ok(None);
+        // Zap pools (asset B policy == LP token policy) are skipped by default, same as JS.
+        // The base pool can't see this: LP-policy units are excluded from its assets.
+        if parsed.pool_asset_b_policy == self.lp_token_policy_id && self.filter.enabled(FilterRule::ZapPool) {
+            self.filter.report(FilterRule::ZapPool, IDENTIFIER, &liquidity_pool.pool_id);
+            return Ok(Err(SkipReason::ZapPool));
         }
 
         liquidity_pool.pool_fee_percent = parsed.base_fee as f64 / 100.0;
         liquidity_pool.total_lp_tokens = parsed.total_lp_tokens;
+        // With fee sharing on, that share of the swap fee is paid out instead
+        // of accruing to the reserves.
+        if let Some(numerator) = parsed.fee_sharing_numerator {
+            let share = numerator as f64 / FEE_DENOMINATOR;
+            liquidity_pool.set_extra("fee_sharing_numerator", numerator);
+            liquidity_pool.metadata.fee_breakdown = Some(FeeBreakdown {
+                lp_fee_percent: liquidity_pool.pool_fee_percent * (1.0 - share),
+                protocol_fee_percent: liquidity_pool.pool_fee_percent * share,
+                ..Default::default()
+            });
+        }
 
         // Determine if the pool's asset_a order matches the datum's asset_a order.
         // In the datum, lovelace is represented as empty policy + empty name ("" + "").
@@ -68,15 +141,23 @@ impl MinswapV2 {
             asset_a_identifier == format!("{}{}", parsed.pool_asset_a_policy, parsed.pool_asset_a_name)
         };
 
+        // base_fee applies to swaps paying in the datum's asset A, base_fee_b
+        // to swaps paying in its asset B.
+        let fee_a = parsed.base_fee as f64 / 100.0;
+        let fee_b = parsed.base_fee_b as f64 / 100.0;
         if pool_asset_a_matches {
             liquidity_pool.reserve_a = parsed.reserve_a;
             liquidity_pool.reserve_b = parsed.reserve_b;
+            liquidity_pool.metadata.fee_a_to_b = Some(fee_a);
+            liquidity_pool.metadata.fee_b_to_a = Some(fee_b);
         } else {
             liquidity_pool.reserve_a = parsed.reserve_b;
             liquidity_pool.reserve_b = parsed.reserve_a;
+            liquidity_pool.metadata.fee_a_to_b = Some(fee_b);
+            liquidity_pool.metadata.fee_b_to_a = Some(fee_a);
         }
 
-        Ok(Some(liquidity_pool))
+        Ok(self.filter_pool_or_skip(liquidity_pool))
     }
 }
 
@@ -90,6 +171,11 @@ struct PoolDatum {
     pool_asset_b_name: String,
     total_lp_tokens: u64,
     base_fee: u64,
+    /// Fee for swaps paying in asset B; equal to `base_fee` in older datums
+    /// that carry a single fee.
+    base_fee_b: u64,
+    /// Share of the swap fee (out of 10000) paid out when fee sharing is on.
+    fee_sharing_numerator: Option<u64>,
 }
 
 /// Parse the MinswapV2 pool datum CBOR hex.
@@ -101,9 +187,10 @@ struct PoolDatum {
 ///   [3] total_lp_tokens (int)
 ///   [4] reserve_a       (int)
 ///   [5] reserve_b       (int)
-///   [6] base_fee        (int)   -- divide by 100 to get percent
-///   [7] fee_sharing_numerator (int)
-///   [8] ...
+///   [6] base_fee_a      (int)   -- A -> B swaps; divide by 100 to get percent
+///   [7] base_fee_b      (int)   -- B -> A swaps
+///   [8] fee_sharing_numerator (Option<int>: constr 0 [n] = on, constr 1 [] = off)
+///   [9] allow_dynamic_fee (bool)
 fn parse_pool_datum(cbor_hex: &str) -> Result<PoolDatum> {
     let value = decode_cbor(cbor_hex)?;
 
@@ -127,6 +214,13 @@ fn parse_pool_datum(cbor_hex: &str) -> Result<PoolDatum> {
     let reserve_b = value_to_u64(&fields[5])?;
     // fields[6]: base fee (e.g. 100 → 1.0%, 30 → 0.3%)
     let base_fee = value_to_u64(&fields[6])?;
+    // fields[7]: base fee for swaps paying in asset B
+    let base_fee_b = fields.get(7).map(value_to_u64).transpose()?.unwrap_or(base_fee);
+    // fields[8]: optional fee-sharing numerator
+    let fee_sharing_numerator = match fields.get(8) {
+        Some(opt) => constr_fields(opt)?.first().map(value_to_u64).transpose()?,
+        None => None,
+    };
 
     Ok(PoolDatum {
         reserve_a,
@@ -137,6 +231,8 @@ fn parse_pool_datum(cbor_hex: &str) -> Result<PoolDatum> {
         pool_asset_b_name,
         total_lp_tokens,
         base_fee,
+        base_fee_b,
+        fee_sharing_numerator,
     })
 }
 
@@ -151,52 +247,85 @@ impl BaseDex for MinswapV2 {
     }
 
     fn lp_token_policy_id(&self) -> &str {
-        LP_TOKEN_POLICY_ID
+        &self.lp_token_policy_id
+    }
+
+    /// The pool id is the LP token unit.
+    fn lp_token_unit(&self, pool: &LiquidityPool) -> Option<String> {
+        pool.pool_id
+            .starts_with(&self.lp_token_policy_id)
+            .then(|| pool.pool_id.clone())
     }
 
     fn kupo(&self) -> &KupoApi {
         &self.kupo
     }
 
+    fn pool_filter(&self) -> &PoolFilter {
+        &self.filter
+    }
+
+    fn pool_match_patterns(&self) -> Vec<String> {
+        let by_script = format!("{}/{}", POOL_SCRIPT_HASH_BECH32, "*");
+        let by_asset = self.validity_asset.pattern.clone();
+        match self.discovery {
+            PoolDiscovery::ScriptAddress => vec![by_script],
+            PoolDiscovery::ValidityAsset => vec![by_asset],
+            PoolDiscovery::Merged => vec![by_script, by_asset],
+        }
+    }
+
     async fn all_liquidity_pool_utxos(&self) -> Result<Vec<Utxo>> {
         let pattern = format!("{}/{}", POOL_SCRIPT_HASH_BECH32, "*");
-        self.kupo.get(&pattern, true).await
+        match self.discovery {
+            PoolDiscovery::ScriptAddress => self.kupo.get(&pattern, true).await,
+            PoolDiscovery::ValidityAsset => self.kupo.get(&self.validity_asset.pattern, true).await,
+            PoolDiscovery::Merged => {
+                let (by_script, by_asset) = tokio::try_join!(
+                    self.kupo.get(&pattern, true),
+                    self.kupo.get(&self.validity_asset.pattern, true),
+                )?;
+                let mut seen = HashSet::new();
+                Ok(by_script
+                    .into_iter()
+                    .chain(by_asset)
+                    .filter(|u| seen.insert((u.tx_hash.clone(), u.output_index)))
+                    .collect())
+            }
+        }
     }
 
     async fn liquidity_pool_from_utxo(&self, utxo: &Utxo, pool_id: &str) -> Result<Option<LiquidityPool>> {
-        // Filter out UTXOs without data_hash (same as JS)
-        if utxo.data_hash.is_none() {
+        // Filter out UTXOs without a datum (same as JS)
+        if utxo.data_hash.is_none() && utxo.inline_datum.is_none() {
             return Ok(None);
         }
 
         let relevant_assets: Vec<_> = utxo.amount.iter()
             .filter(|asset| {
                 let unit = &asset.unit;
-                unit != POOL_VALIDITY_ASSET && !unit.starts_with(LP_TOKEN_POLICY_ID)
+                *unit != self.validity_asset.unit && !unit.starts_with(&self.lp_token_policy_id)
             })
             .collect();
 
-        if relevant_assets.len() < 2 {
+        let Some((unit_a, unit_b)) = reserve_pair(&relevant_assets) else {
             return Ok(None);
-        }
-
-        let asset_a_idx = if relevant_assets.len() == 2 { 0 } else { 1 };
-        let asset_b_idx = if relevant_assets.len() == 2 { 1 } else { 2 };
+        };
 
         // Find pool ID - get the FIRST one that matches (same as JS .find())
         let pool_id = utxo.amount.iter()
             .find(|a| {
                 let unit = &a.unit;
-                unit.starts_with(LP_TOKEN_POLICY_ID) && *unit != POOL_VALIDITY_ASSET
+                unit.starts_with(&self.lp_token_policy_id) && *unit != self.validity_asset.unit
             })
             .map(|a| a.unit.clone())
             .unwrap_or_else(|| pool_id.to_string());
 
-        let asset_a = from_identifier(&relevant_assets[asset_a_idx].unit, 0);
-        let asset_b = from_identifier(&relevant_assets[asset_b_idx].unit, 0);
+        let asset_a = from_identifier(&unit_a.unit, 0);
+        let asset_b = from_identifier(&unit_b.unit, 0);
 
-        let reserve_a = relevant_assets[asset_a_idx].quantity.parse::<u64>()?;
-        let reserve_b = relevant_assets[asset_b_idx].quantity.parse::<u64>()?;
+        let reserve_a = unit_a.quantity;
+        let reserve_b = unit_b.quantity;
 
         let pool = LiquidityPool::new(
             IDENTIFIER,
@@ -207,20 +336,25 @@ impl BaseDex for MinswapV2 {
             &utxo.address,
             0.3,
             &pool_id,
-        );
+        )
+        .observed_in(utxo);
 
         Ok(Some(pool))
     }
 
-    async fn liquidity_pool_from_utxo_extend(&self, utxo: &Utxo, pool_id: &str) -> Result<Option<LiquidityPool>> {
-        MinswapV2::liquidity_pool_from_utxo_extend(self, utxo, pool_id).await
+    async fn liquidity_pool_from_utxo_extend_or_skip(
+        &self,
+        utxo: &Utxo,
+        pool_id: &str,
+    ) -> Result<Result<LiquidityPool, SkipReason>> {
+        self.extend_or_skip(utxo, pool_id).await
     }
 
     async fn liquidity_pool_from_pool_id(&self, pool_id: &str) -> Result<Option<LiquidityPool>> {
-        let full_pool_id = if pool_id.starts_with(LP_TOKEN_POLICY_ID) {
+        let full_pool_id = if pool_id.starts_with(&self.lp_token_policy_id) {
             pool_id.to_string()
         } else {
-            format!("{}{}", LP_TOKEN_POLICY_ID, pool_id)
+            format!("{}{}", self.lp_token_policy_id, pool_id)
         };
 
         let utxos = self.all_liquidity_pool_utxos().await?;
@@ -234,34 +368,151 @@ impl BaseDex for MinswapV2 {
         }
     }
 
-    async fn liquidity_pools_from_token(&self, token_b: &str, token_a: &str) -> Result<Vec<LiquidityPool>> {
-        // Fetch all UTXOs at the pool address (single HTTP request),
-        // filter by token identifiers from UTXO amounts (no datum needed),
-        // then only fetch datums for the matching pools.
-        let all_utxos = self.all_liquidity_pool_utxos().await?;
-
-        let mut pools = Vec::new();
-        for utxo in &all_utxos {
-            let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
-                Some(p) => p,
-                None => continue,
-            };
-
-            let id_a = token_identifier(&base.asset_a);
-            let id_b = token_identifier(&base.asset_b);
-
-            let matches = (id_a == token_a && id_b == token_b)
-                || (id_a == token_b && id_b == token_a);
-
-            if matches {
-                match self.liquidity_pool_from_utxo_extend(utxo, &base.pool_id).await {
-                    Ok(Some(extended)) => pools.push(extended),
-                    Ok(None) => {}
-                    Err(e) => eprintln!("[error] datum fetch for {}: {}", utxo.tx_hash, e),
+    async fn liquidity_pools_from_token(&self, token_b: AssetId, token_a: AssetId) -> Result<Vec<LiquidityPool>> {
+        RetryBudget::ensure_scope(async {
+            // Fetch all UTXOs at the pool address (single HTTP request),
+            // filter by token identifiers from UTXO amounts (no datum needed),
+            // then only fetch datums for the matching pools.
+            let all_utxos = self.all_liquidity_pool_utxos().await?;
+
+            let mut pools = Vec::new();
+            for utxo in &all_utxos {
+                let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
+                    Some(p) => p,
+                    None => continue,
+                };
+
+                let id_a = AssetId::from(&base.asset_a);
+                let id_b = AssetId::from(&base.asset_b);
+
+                let matches = (id_a == token_a && id_b == token_b)
+                    || (id_a == token_b && id_b == token_a);
+
+                if matches {
+                    match self.liquidity_pool_from_utxo_extend(utxo, &base.pool_id).await {
+                        Ok(Some(extended)) => pools.push(extended),
+                        Ok(None) => {}
+                        Err(e) => eprintln!("[error] datum fetch for {}: {}", utxo.tx_hash, e),
+                    }
                 }
             }
-        }
 
-        Ok(pools)
+            Ok(pools)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kupo::test_server::{self, Response};
+    use ciborium::value::Value;
+
+    fn constr(index: u64, fields: Vec<Value>) -> Value {
+        Value::Tag(121 + index, Box::new(Value::Array(fields)))
+    }
+
+    fn datum_hex(fee_sharing: Value) -> String {
+        let bytes = |h: &str| Value::Bytes(hex::decode(h).unwrap());
+        let asset = |policy: &str, name: &str| constr(0, vec![bytes(policy), bytes(name)]);
+        let int = |n: u64| Value::Integer(n.into());
+        let datum = constr(
+            0,
+            vec![
+                constr(0, vec![]),
+                asset("", ""),
+                asset("279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f", "534e454b"),
+                int(1_000),
+                int(2_000),
+                int(3_000),
+                int(30),
+                int(50),
+                fee_sharing,
+                constr(0, vec![]),
+            ],
+        );
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&datum, &mut cbor).unwrap();
+        hex::encode(cbor)
+    }
+
+    #[test]
+    fn test_parse_fee_sharing() {
+        let sharing = constr(0, vec![Value::Integer(1666.into())]);
+        let on = parse_pool_datum(&datum_hex(sharing)).unwrap();
+        assert_eq!(on.base_fee, 30);
+        assert_eq!(on.base_fee_b, 50);
+        assert_eq!(on.reserve_b, 3_000);
+        assert_eq!(on.fee_sharing_numerator, Some(1666));
+
+        let off = parse_pool_datum(&datum_hex(constr(1, vec![]))).unwrap();
+        assert_eq!(off.fee_sharing_numerator, None);
+    }
+
+    #[tokio::test]
+    async fn test_pool_from_raw_output() {
+        let lp = format!("{}{}", LP_TOKEN_POLICY_ID, "aa".repeat(32));
+        let value = std::collections::HashMap::from([
+            ("lovelace".to_string(), 5_000_000),
+            ("279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b".to_string(), 7_000),
+            (POOL_VALIDITY_ASSET.to_string(), 1),
+            (lp.clone(), 1),
+        ]);
+        let datum = datum_hex(constr(1, vec![]));
+
+        // Inline datum, no datum hash: decoded without asking the Kupo.
+        let dex = MinswapV2::new(KupoApi::new("http://127.0.0.1:9"));
+        let pool = dex
+            .liquidity_pool_from_raw_output("addr1pool", &value, Some(&datum))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.pool_id, lp);
+        // Reserves come from the datum, not the output value.
+        assert_eq!((pool.reserve_a, pool.reserve_b), (2_000, 3_000));
+        assert_eq!(pool.total_lp_tokens, 1_000);
+        assert_eq!(pool.metadata.fee_b_to_a, Some(0.5));
+    }
+
+    /// A Kupo answering every `/matches` query with the same pool output.
+    async fn fake_kupo() -> String {
+        let lp = format!("{}.{}", LP_TOKEN_POLICY_ID, "aa".repeat(32));
+        let body = serde_json::json!([{
+            "address": "addr1pool",
+            "transaction_id": "ab".repeat(32),
+            "output_index": 0,
+            "datum_hash": "cd".repeat(32),
+            "datum_type": "hash",
+            "created_at": { "slot_no": 100, "header_hash": "ef".repeat(32) },
+            "value": {
+                "coins": 5_000_000,
+                "assets": {
+                    "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f.534e454b": 7_000,
+                    "f5808c2c990d86da54bfc97d89cee6efa20cd8461616359478d96b4c.4d5350": 1,
+                    lp: 1,
+                }
+            }
+        }]);
+        test_server::serve(move |_| Response::json(&body)).await
+    }
+
+    #[tokio::test]
+    async fn test_merged_discovery_dedups_outputs() {
+        let kupo = KupoApi::new(&fake_kupo().await);
+        assert_eq!(
+            MinswapV2::new(kupo.clone()).discovery,
+            PoolDiscovery::ScriptAddress
+        );
+
+        let dex = MinswapV2::new(kupo).with_discovery(PoolDiscovery::Merged);
+        let utxos = dex.all_liquidity_pool_utxos().await.unwrap();
+        assert_eq!(utxos.len(), 1);
+        let pool = dex
+            .liquidity_pool_from_utxo(&utxos[0], "")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((pool.reserve_a, pool.reserve_b), (5_000_000, 7_000));
     }
 }
@@ -10,6 +10,7 @@ use crate::dex::minswap_v2::MinswapV2;
 use crate::dex::DexSwap;
 use crate::models::{token_identifier, LiquidityPool, Token, Utxo};
 use crate::plutus::PlutusData;
+use crate::quote::Direction;
 use crate::requests::{AddressType, AssetAmount, PayToAddress, PlutusScript, PlutusVersion, SpendUtxo, SwapFee, SwapParams, UtxoRef};
 
 /// Returns `(reserve_for_token, reserve_for_other)` ordered to match `token`.
@@ -23,10 +24,13 @@ fn corresponding_reserves(pool: &LiquidityPool, token: &Token) -> (u128, u128) {
     }
 }
 
-fn fee_mods(pool_fee_percent: f64) -> (u128, u128) {
+/// `(multiplier, modifier)` for a swap paying in `in_token`; the fee depends
+/// on the direction.
+fn fee_mods(pool: &LiquidityPool, in_token: &Token) -> (u128, u128) {
+    let fee_percent = pool.fee_percent(Direction::for_input(&pool.asset_a, in_token));
     let mult: u128 = 10_000;
     // round-half-away-from-zero, mirroring dexter's Math.round
-    let modifier = mult - (((pool_fee_percent / 100.0) * mult as f64).round() as u128);
+    let modifier = mult - (((fee_percent / 100.0) * mult as f64).round() as u128);
     (mult, modifier)
 }
 
@@ -139,7 +143,7 @@ impl DexSwap for MinswapV2 {
     }
 
     fn estimated_receive(&self, pool: &LiquidityPool, in_token: &Token, in_amount: u64) -> u64 {
-        let (mult, modifier) = fee_mods(pool.pool_fee_percent);
+        let (mult, modifier) = fee_mods(pool, in_token);
         let (r_in, r_out) = corresponding_reserves(pool, in_token);
         let in_amt = in_amount as u128;
         let num = in_amt * r_out * modifier;
@@ -147,7 +151,12 @@ impl DexSwap for MinswapV2 {
         (num / den) as u64
     }
     fn estimated_give(&self, pool: &LiquidityPool, out_token: &Token, out_amount: u64) -> u64 {
-        let (mult, modifier) = fee_mods(pool.pool_fee_percent);
+        let in_token = if token_identifier(out_token) == token_identifier(&pool.asset_a) {
+            &pool.asset_b
+        } else {
+            &pool.asset_a
+        };
+        let (mult, modifier) = fee_mods(pool, in_token);
         let (r_out, r_in) = corresponding_reserves(pool, out_token);
         let out_amt = out_amount as u128;
         if out_amt >= r_out {
@@ -158,7 +167,7 @@ impl DexSwap for MinswapV2 {
         ((num / den) + 1) as u64
     }
     fn price_impact_percent(&self, pool: &LiquidityPool, in_token: &Token, in_amount: u64) -> f64 {
-        let (mult, modifier) = fee_mods(pool.pool_fee_percent);
+        let (mult, modifier) = fee_mods(pool, in_token);
         let (r_in, r_out) = corresponding_reserves(pool, in_token);
         // Use f64 throughout to avoid u128 overflow on large reserve × amount products.
         let in_amt = in_amount as f64;
@@ -263,12 +272,9 @@ impl DexSwap for MinswapV2 {
             .ok_or_else(|| anyhow!("no UTxO at the V2 order script address"))?;
 
         // Return all assets of the order UTxO to the caller.
-        let mut assets: Vec<AssetAmount> = Vec::with_capacity(order_utxo.amount.len());
-        for unit in &order_utxo.amount {
-            let qty = unit.quantity.parse::<u64>()
-                .map_err(|e| anyhow!("order utxo asset quantity not u64: {}", e))?;
-            assets.push(AssetAmount { unit: unit.unit.clone(), quantity: qty });
-        }
+        let assets: Vec<AssetAmount> = order_utxo.amount.iter()
+            .map(|unit| AssetAmount { unit: unit.unit.clone(), quantity: unit.quantity })
+            .collect();
 
         Ok(vec![PayToAddress {
             address: return_address.to_string(),
@@ -318,6 +324,7 @@ mod tests {
                                      "7dd6988c5a86693c76aeec1ea94afa41770be0de21a775ca7a2a1eabdb6a0171"),
             pool_fee_percent: fee_pct,
             total_lp_tokens: 0,
+            metadata: Default::default(),
         }
     }
 
@@ -375,8 +382,9 @@ mod tests {
             tx_hash: "00".repeat(32),
             tx_index: 0,
             output_index: 0,
-            amount: vec![crate::models::Unit { unit: "lovelace".into(), quantity: ada.to_string() }],
+            amount: vec![crate::models::Unit { unit: "lovelace".into(), quantity: ada }],
             block: String::new(),
+            slot: None,
             data_hash: Some("ab".repeat(32)),
             inline_datum: None,
             reference_script_hash: None,
@@ -419,8 +427,9 @@ mod tests {
             tx_hash: "00".repeat(32),
             tx_index: 0,
             output_index: 0,
-            amount: vec![crate::models::Unit { unit: "lovelace".into(), quantity: "5000000".into() }],
+            amount: vec![crate::models::Unit { unit: "lovelace".into(), quantity: 5_000_000 }],
             block: String::new(),
+            slot: None,
             data_hash: None,
             inline_datum: None,
             reference_script_hash: None,
@@ -445,8 +454,9 @@ mod tests {
             tx_hash: "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".into(),
             tx_index: 0,
             output_index: 0,
-            amount: vec![crate::models::Unit { unit: "lovelace".into(), quantity: "5000000".into() }],
+            amount: vec![crate::models::Unit { unit: "lovelace".into(), quantity: 5_000_000 }],
             block: String::new(),
+            slot: None,
             data_hash: None,
             inline_datum: None,
             reference_script_hash: None,
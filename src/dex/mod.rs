@@ -1,8 +1,17 @@
 use async_trait::async_trait;
-use crate::models::{Utxo, LiquidityPool};
+use crate::models::{AssetId, Utxo, LiquidityPool};
 use crate::kupo::KupoApi;
+use crate::quote::exact::{ScaledFee, SwapMath};
+use futures::future;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use std::collections::{HashMap, HashSet};
 
 pub mod cbor;
+pub mod creation;
+pub mod djed;
+pub mod filter;
+pub mod latency;
+pub mod skip;
 pub mod minswap_v1;
 pub mod minswap_v2;
 pub mod minswap_v2_swap;
@@ -13,10 +22,53 @@ pub mod wingriders;
 pub mod wingriders_v2;
 pub mod cswap;
 pub mod chadswap;
+pub mod generic;
+pub mod overrides;
 pub mod vyfinance;
 pub mod vyfi_bar;
 pub mod swap;
 pub use swap::DexSwap;
+pub use creation::CreationResolver;
+pub use filter::{FilterRule, PoolFilter};
+pub use latency::SettlementLatency;
+pub use overrides::{DexOverrides, OverridesConfig};
+pub use skip::{SkipReason, SkipReport};
+
+/// Validator swap arithmetic of the DEX named `identifier` (its module's
+/// `SWAP_MATH`); [`ScaledFee`] for DEXes without their own, e.g. generic forks.
+pub fn swap_math(identifier: &str) -> &'static dyn SwapMath {
+    match identifier {
+        "MinswapV1" => &minswap_v1::SWAP_MATH,
+        "MinswapV2" => &minswap_v2::SWAP_MATH,
+        "SundaeSwapV1" => &sundaeswap_v1::SWAP_MATH,
+        "SundaeSwapV3" => &sundaeswap_v3::SWAP_MATH,
+        "WingRiders" => &wingriders::SWAP_MATH,
+        "WingRidersV2" => &wingriders_v2::SWAP_MATH,
+        "VyFinance" => &vyfinance::SWAP_MATH,
+        "CSwap" => &cswap::SWAP_MATH,
+        _ => &ScaledFee,
+    }
+}
+
+/// Settlement latency of the DEX named `identifier` (its module's
+/// `SETTLEMENT`); [`latency::DEFAULT_BATCHED`] for DEXes without their own.
+pub fn settlement_latency(identifier: &str) -> SettlementLatency {
+    match identifier {
+        "MinswapV1" => minswap_v1::SETTLEMENT,
+        "MinswapV2" => minswap_v2::SETTLEMENT,
+        "SundaeSwapV1" => sundaeswap_v1::SETTLEMENT,
+        "SundaeSwapV3" => sundaeswap_v3::SETTLEMENT,
+        "WingRiders" => wingriders::SETTLEMENT,
+        "WingRidersV2" => wingriders_v2::SETTLEMENT,
+        "VyFinance" => vyfinance::SETTLEMENT,
+        "CSwap" => cswap::SETTLEMENT,
+        "ChadSwap" => chadswap::SETTLEMENT,
+        _ => latency::DEFAULT_BATCHED,
+    }
+}
+
+/// Slot window of one Kupo request in [`BaseDex::pool_utxo_stream`] (~10 days).
+pub const POOL_PAGE_SLOTS: u64 = 864_000;
 
 #[async_trait]
 pub trait BaseDex: Send + Sync {
@@ -25,8 +77,67 @@ pub trait BaseDex: Send + Sync {
     fn lp_token_policy_id(&self) -> &str;
     
     fn kupo(&self) -> &KupoApi;
+
+    /// Unit (policy id + asset name hex) of the LP token of `pool`, where the
+    /// DEX's LP naming is known. Used to match wallet LP holdings to pools.
+    fn lp_token_unit(&self, _pool: &LiquidityPool) -> Option<String> {
+        None
+    }
+
+    /// Filtering policy applied at the end of the extend step.
+    fn pool_filter(&self) -> &PoolFilter {
+        &PoolFilter::DEFAULT
+    }
+
+    /// Run a pool through [`pool_filter`](Self::pool_filter), dropping it if a rule matches.
+    fn filter_pool(&self, pool: LiquidityPool) -> Option<LiquidityPool> {
+        self.filter_pool_or_skip(pool).ok()
+    }
+
+    /// [`filter_pool`](Self::filter_pool), with the matching rule as the skip reason.
+    fn filter_pool_or_skip(&self, pool: LiquidityPool) -> Result<LiquidityPool, SkipReason> {
+        let filter = self.pool_filter();
+        match filter.check(&pool) {
+            Some(rule) => {
+                filter.report(rule, self.identifier(), &pool.pool_id);
+                Err(rule.into())
+            }
+            None => Ok(pool),
+        }
+    }
     
     async fn all_liquidity_pool_utxos(&self) -> Result<Vec<Utxo>, anyhow::Error>;
+
+    /// Kupo patterns whose unspent matches are this DEX's pool UTXOs, used by
+    /// [`pool_utxo_stream`](Self::pool_utxo_stream). Empty when pools are not
+    /// found by plain pattern queries.
+    fn pool_match_patterns(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Pool UTXOs paged lazily from Kupo by slot window, so memory stays bounded
+    /// on DEXes with many pools. UTXOs matched by several patterns are yielded
+    /// once. Without [`pool_match_patterns`](Self::pool_match_patterns) this
+    /// falls back to [`all_liquidity_pool_utxos`](Self::all_liquidity_pool_utxos).
+    fn pool_utxo_stream(&self) -> BoxStream<'_, Result<Utxo, anyhow::Error>> {
+        let patterns = self.pool_match_patterns();
+        if patterns.is_empty() {
+            return stream::once(self.all_liquidity_pool_utxos())
+                .map_ok(|utxos| stream::iter(utxos.into_iter().map(Ok)))
+                .try_flatten()
+                .boxed();
+        }
+        let dedup = patterns.len() > 1;
+        let mut seen = HashSet::new();
+        stream::iter(patterns)
+            .map(|pattern| self.kupo().match_stream(&pattern, true, POOL_PAGE_SLOTS))
+            .flatten()
+            .try_filter(move |u| {
+                let first = !dedup || seen.insert((u.tx_hash.clone(), u.output_index));
+                future::ready(first)
+            })
+            .boxed()
+    }
     
     async fn liquidity_pool_from_utxo(
         &self,
@@ -35,14 +146,62 @@ pub trait BaseDex: Send + Sync {
     ) -> Result<Option<LiquidityPool>, anyhow::Error>;
 
     /// Like liquidity_pool_from_utxo but also fetches the datum for accurate
-    /// reserves and fee. Default implementation just calls liquidity_pool_from_utxo
-    /// (suitable for DEXes that don't need datum parsing).
+    /// reserves and fee, then applies the pool filter. `Ok(Err(reason))` says
+    /// why the UTXO is not a pool. Default implementation just filters
+    /// liquidity_pool_from_utxo (suitable for DEXes that don't need datum parsing);
+    /// DEXes override this rather than [`liquidity_pool_from_utxo_extend`](Self::liquidity_pool_from_utxo_extend).
+    async fn liquidity_pool_from_utxo_extend_or_skip(
+        &self,
+        utxo: &Utxo,
+        pool_id: &str
+    ) -> Result<Result<LiquidityPool, SkipReason>, anyhow::Error> {
+        Ok(match self.liquidity_pool_from_utxo(utxo, pool_id).await? {
+            Some(pool) => self.filter_pool_or_skip(pool),
+            None => Err(SkipReason::no_pool(utxo)),
+        })
+    }
+
+    /// [`liquidity_pool_from_utxo_extend_or_skip`](Self::liquidity_pool_from_utxo_extend_or_skip)
+    /// without the skip reason.
     async fn liquidity_pool_from_utxo_extend(
         &self,
         utxo: &Utxo,
         pool_id: &str
     ) -> Result<Option<LiquidityPool>, anyhow::Error> {
-        self.liquidity_pool_from_utxo(utxo, pool_id).await
+        Ok(self.liquidity_pool_from_utxo_extend_or_skip(utxo, pool_id).await?.ok())
+    }
+
+    /// Base parse + extend for one UTXO, with a structured reason when it yields no pool.
+    async fn liquidity_pool_or_skip(&self, utxo: &Utxo) -> Result<LiquidityPool, SkipReason> {
+        let base = match self.liquidity_pool_from_utxo(utxo, "").await {
+            Ok(Some(p)) => p,
+            Ok(None) => return Err(SkipReason::no_pool(utxo)),
+            Err(e) => return Err(SkipReason::parse_error("base", &e)),
+        };
+
+        match self
+            .liquidity_pool_from_utxo_extend_or_skip(utxo, &base.pool_id)
+            .await
+        {
+            Ok(extended) => extended,
+            Err(e) => Err(SkipReason::parse_error("extend", &e)),
+        }
+    }
+
+    /// Every pool at the DEX (datum-extended), plus a report of the UTXOs that were skipped.
+    async fn all_liquidity_pools_with_report(
+        &self,
+    ) -> Result<(Vec<LiquidityPool>, SkipReport), anyhow::Error> {
+        let utxos = self.all_liquidity_pool_utxos().await?;
+        let mut pools = Vec::new();
+        let mut report = SkipReport::new(self.identifier());
+        for utxo in &utxos {
+            match self.liquidity_pool_or_skip(utxo).await {
+                Ok(pool) => pools.push(pool),
+                Err(reason) => report.push(utxo, reason),
+            }
+        }
+        Ok((pools, report))
     }
 
     async fn liquidity_pool_from_pool_id(
@@ -50,12 +209,47 @@ pub trait BaseDex: Send + Sync {
         pool_id: &str
     ) -> Result<Option<LiquidityPool>, anyhow::Error>;
     
+    /// Decode the pool state created by output `index` of `tx_hash`, spent or not.
+    /// `None` if the output does not exist or is not a pool of this DEX.
+    async fn liquidity_pool_from_outref(
+        &self,
+        tx_hash: &str,
+        index: u32
+    ) -> Result<Option<LiquidityPool>, anyhow::Error> {
+        let utxos = self.kupo().get(&format!("*@{}", tx_hash), false).await?;
+        let Some(utxo) = utxos.iter().find(|u| u.output_index == index) else {
+            return Ok(None);
+        };
+        let Some(base) = self.liquidity_pool_from_utxo(utxo, "").await? else {
+            return Ok(None);
+        };
+        self.liquidity_pool_from_utxo_extend(utxo, &base.pool_id).await
+    }
+
+    /// Decode a pool from an output seen outside Kupo, e.g. an Oura or pallas
+    /// chain-sync event (see [`Utxo::from_raw_output`] for `value`).
+    ///
+    /// Makes no Kupo request for DEXes that read the pool from the output and
+    /// its datum alone; VyFinance still needs its API for the pair ordering.
+    async fn liquidity_pool_from_raw_output(
+        &self,
+        address: &str,
+        value: &HashMap<String, u64>,
+        datum_cbor: Option<&str>
+    ) -> Result<Option<LiquidityPool>, anyhow::Error> {
+        let utxo = Utxo::from_raw_output(address, value, datum_cbor);
+        let Some(base) = self.liquidity_pool_from_utxo(&utxo, "").await? else {
+            return Ok(None);
+        };
+        self.liquidity_pool_from_utxo_extend(&utxo, &base.pool_id).await
+    }
+
     async fn all_liquidity_pools(&self) -> Result<Vec<LiquidityPool>, anyhow::Error> {
         let utxos = self.all_liquidity_pool_utxos().await?;
         let mut pools = Vec::new();
         for utxo in &utxos {
             match self.liquidity_pool_from_utxo(utxo, "").await {
-                Ok(Some(pool)) => pools.push(pool),
+                Ok(Some(pool)) => pools.extend(self.filter_pool(pool)),
                 Ok(None) => {}
                 Err(e) => eprintln!("[{}] pool parse error {}: {}", self.identifier(), utxo.tx_hash, e),
             }
@@ -63,9 +257,43 @@ pub trait BaseDex: Send + Sync {
         Ok(pools)
     }
 
+    /// Every pool holding `token` on either side, whatever it trades against.
+    async fn liquidity_pools_with_token(
+        &self,
+        token: AssetId
+    ) -> Result<Vec<LiquidityPool>, anyhow::Error> {
+        let utxos = self.all_liquidity_pool_utxos().await?;
+        let mut pools = Vec::new();
+        for utxo in &utxos {
+            let base = match self.liquidity_pool_from_utxo(utxo, "").await {
+                Ok(Some(p)) => p,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("[{}] pool parse error {}: {}", self.identifier(), utxo.tx_hash, e);
+                    continue;
+                }
+            };
+            if AssetId::from(&base.asset_a) != token && AssetId::from(&base.asset_b) != token {
+                continue;
+            }
+            match self.liquidity_pool_from_utxo_extend(utxo, &base.pool_id).await {
+                Ok(Some(p)) => pools.push(p),
+                Ok(None) => {}
+                Err(e) => eprintln!("[{}] datum error {}: {}", self.identifier(), utxo.tx_hash, e),
+            }
+        }
+        Ok(pools)
+    }
+
+    /// Pools of the pair, in either orientation. Accepts any spelling of the
+    /// identifiers via `AssetId::from` (e.g. `"lovelace".into()`).
+    ///
+    /// One call is one logical operation: implementations run inside
+    /// [`RetryBudget::ensure_scope`](crate::utils::RetryBudget::ensure_scope),
+    /// so its Kupo requests share a retry budget however it is called.
     async fn liquidity_pools_from_token(
         &self,
-        token_b: &str,
-        token_a: &str
+        token_b: AssetId,
+        token_a: AssetId
     ) -> Result<Vec<LiquidityPool>, anyhow::Error>;
 }
@@ -0,0 +1,282 @@
+//! Runtime overrides of the built-in DEXes' protocol constants.
+//!
+//! Protocol upgrades (a new SundaeSwap pool address, a new WingRiders
+//! validity token) can be picked up from a config file instead of waiting for
+//! a crate release. Keys are DEX identifiers, and only the constants a DEX
+//! actually reads can be set; anything else is rejected when the file is
+//! loaded, so a typo does not silently leave the built-in value in place:
+//!
+//! ```toml
+//! [SundaeSwapV3]
+//! pool_addresses = ["addr1...", "addr1..."]  # or hex / CBOR hex bytes, script hashes
+//!
+//! [WingRiders]
+//! validity_asset = "<policy id>.<asset name hex>"
+//! fee_percent = 0.25
+//! ```
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::utils::normalize_address;
+
+const POLICY_ID_LEN: usize = 56;
+
+/// Overridable constants of each built-in DEX.
+const SUPPORTED: &[(&str, &[&str])] = &[
+    (
+        "MinswapV1",
+        &["validity_asset", "lp_token_policy_id", "fee_percent"],
+    ),
+    ("MinswapV2", &["validity_asset", "lp_token_policy_id"]),
+    ("SundaeSwapV1", &["pool_addresses", "lp_token_policy_id"]),
+    ("SundaeSwapV3", &["pool_addresses", "lp_token_policy_id"]),
+    ("WingRiders", &["validity_asset", "fee_percent"]),
+    ("WingRidersV2", &["validity_asset"]),
+    ("CSwap", &["pool_addresses"]),
+];
+
+fn is_hex(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// A pool validity token in the forms the DEXes query and filter by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidityAsset {
+    pub policy_id: String,
+    /// `policy.name`, the Kupo match pattern.
+    pub pattern: String,
+    /// `policyname`, as found in UTXO amounts.
+    pub unit: String,
+}
+
+impl ValidityAsset {
+    /// Split a unit (dotted or not) without validating it.
+    pub fn new(unit: &str) -> Self {
+        let unit = unit.replace('.', "");
+        let (policy, name) = unit.split_at(POLICY_ID_LEN.min(unit.len()));
+        Self {
+            policy_id: policy.to_string(),
+            pattern: format!("{}.{}", policy, name),
+            unit,
+        }
+    }
+
+    /// Validate and split a unit: a 56-character policy id and a hex asset name.
+    pub fn parse(unit: &str) -> Result<Self> {
+        let joined = unit.replace('.', "");
+        if joined.len() < POLICY_ID_LEN || joined.len() > POLICY_ID_LEN + 64 || !is_hex(&joined) {
+            return Err(anyhow!("Invalid validity asset '{}'", unit));
+        }
+        Ok(Self::new(&joined.to_lowercase()))
+    }
+}
+
+/// Constants to replace for one DEX; unset fields keep the built-in values.
+/// Bech32 form of a configured pool address; bech32 input is kept as written.
+fn pool_address(address: &str) -> String {
+    if address.starts_with("addr") {
+        return address.to_string();
+    }
+    normalize_address(address).unwrap_or_else(|_| address.to_string())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DexOverrides {
+    /// Every address holding the DEX's pools (replaces all built-in ones).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pool_addresses: Vec<String>,
+    /// Token held by every pool UTXO: policy id + asset name hex, dotted or not.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validity_asset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lp_token_policy_id: Option<String>,
+    /// Trading fee in percent, for DEXes whose datum does not carry it
+    /// (WingRiders: the LP share, the treasury fee is added on top).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_percent: Option<f64>,
+}
+
+impl DexOverrides {
+    fn set_fields(&self) -> Vec<&'static str> {
+        let mut fields = Vec::new();
+        if !self.pool_addresses.is_empty() {
+            fields.push("pool_addresses");
+        }
+        if self.validity_asset.is_some() {
+            fields.push("validity_asset");
+        }
+        if self.lp_token_policy_id.is_some() {
+            fields.push("lp_token_policy_id");
+        }
+        if self.fee_percent.is_some() {
+            fields.push("fee_percent");
+        }
+        fields
+    }
+
+    /// Check the values, and that `identifier` reads every field that is set.
+    pub fn validate(&self, identifier: &str) -> Result<()> {
+        let Some((_, supported)) = SUPPORTED.iter().find(|(id, _)| *id == identifier) else {
+            let known: Vec<&str> = SUPPORTED.iter().map(|(id, _)| *id).collect();
+            return Err(anyhow!(
+                "No overridable constants for DEX '{}' (known: {})",
+                identifier,
+                known.join(", ")
+            ));
+        };
+        for field in self.set_fields() {
+            if !supported.contains(&field) {
+                return Err(anyhow!(
+                    "{}: '{}' cannot be overridden (supported: {})",
+                    identifier,
+                    field,
+                    supported.join(", ")
+                ));
+            }
+        }
+        if let Some(address) = self
+            .pool_addresses
+            .iter()
+            .find(|a| !a.starts_with("addr") && normalize_address(a).is_err())
+        {
+            return Err(anyhow!("{}: invalid pool address '{}'", identifier, address));
+        }
+        if let Some(unit) = &self.validity_asset {
+            ValidityAsset::parse(unit).map_err(|e| anyhow!("{}: {}", identifier, e))?;
+        }
+        if let Some(policy) = &self.lp_token_policy_id {
+            if policy.len() != POLICY_ID_LEN || !is_hex(policy) {
+                return Err(anyhow!("{}: invalid LP token policy id '{}'", identifier, policy));
+            }
+        }
+        if let Some(fee) = self.fee_percent {
+            if !(0.0..100.0).contains(&fee) {
+                return Err(anyhow!("{}: fee_percent must be in [0, 100)", identifier));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn pool_addresses_or(&self, defaults: &[&str]) -> Vec<String> {
+        if self.pool_addresses.is_empty() {
+            defaults.iter().map(|a| a.to_string()).collect()
+        } else {
+            self.pool_addresses.iter().map(|a| pool_address(a)).collect()
+        }
+    }
+
+    pub fn validity_asset_or(&self, default: &str) -> ValidityAsset {
+        ValidityAsset::new(self.validity_asset.as_deref().unwrap_or(default))
+    }
+
+    pub fn lp_token_policy_id_or(&self, default: &str) -> String {
+        self.lp_token_policy_id
+            .clone()
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    pub fn fee_percent_or(&self, default: f64) -> f64 {
+        self.fee_percent.unwrap_or(default)
+    }
+}
+
+/// Overrides of several DEXes, keyed by DEX identifier.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct OverridesConfig {
+    dexes: BTreeMap<String, DexOverrides>,
+}
+
+impl OverridesConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the overrides of `identifier`, replacing earlier ones.
+    pub fn insert(&mut self, identifier: &str, overrides: DexOverrides) -> Result<()> {
+        overrides.validate(identifier)?;
+        self.dexes.insert(identifier.to_string(), overrides);
+        Ok(())
+    }
+
+    /// Overrides of `identifier`; empty (all built-in) when none are set.
+    pub fn get(&self, identifier: &str) -> DexOverrides {
+        self.dexes.get(identifier).cloned().unwrap_or_default()
+    }
+
+    /// Identifiers with overrides, sorted.
+    pub fn identifiers(&self) -> Vec<&str> {
+        self.dexes.keys().map(String::as_str).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dexes.is_empty()
+    }
+
+    pub fn from_toml_str(s: &str) -> Result<Self> {
+        let config: Self =
+            toml::from_str(s).map_err(|e| anyhow!("Failed to parse DEX overrides: {}", e))?;
+        config.validated()
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self> {
+        let config: Self = serde_json::from_str(s)
+            .map_err(|e| anyhow!("Failed to parse DEX overrides: {}", e))?;
+        config.validated()
+    }
+
+    /// Load an overrides file; `.toml` files are parsed as TOML, anything else as JSON.
+    pub fn load_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to open DEX overrides {}: {}", path, e))?;
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml_str(&content),
+            _ => Self::from_json_str(&content),
+        }
+    }
+
+    fn validated(self) -> Result<Self> {
+        for (identifier, overrides) in &self.dexes {
+            overrides.validate(identifier)?;
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WR_ASSET: &str = "026a18d04a0c642759bb3d83b12e3344894e5c1c7b2aeb1a2113a570.4c";
+
+    #[test]
+    fn test_load_and_validate_overrides() {
+        let config = OverridesConfig::from_toml_str(&format!(
+            "[SundaeSwapV3]\npool_addresses = [\"addr1new\"]\n\n[WingRiders]\nvalidity_asset = \"{}\"\nfee_percent = 0.25\n",
+            WR_ASSET
+        ))
+        .unwrap();
+        assert_eq!(config.identifiers(), vec!["SundaeSwapV3", "WingRiders"]);
+        assert_eq!(
+            config.get("SundaeSwapV3").pool_addresses_or(&["addr1old"]),
+            vec!["addr1new"]
+        );
+        let wr = config.get("WingRiders");
+        assert_eq!(wr.fee_percent_or(0.3), 0.25);
+        let asset = wr.validity_asset_or("");
+        assert_eq!(asset.unit, WR_ASSET.replace('.', ""));
+        assert_eq!(asset.pattern, WR_ASSET);
+        assert_eq!(config.get("CSwap"), DexOverrides::default());
+
+        // Unsupported field, unknown DEX, unknown key, bad value.
+        assert!(OverridesConfig::from_toml_str("[CSwap]\nfee_percent = 1.0\n").is_err());
+        assert!(OverridesConfig::from_toml_str("[NoSwap]\nfee_percent = 1.0\n").is_err());
+        assert!(OverridesConfig::from_toml_str("[CSwap]\npool_address = \"addr1\"\n").is_err());
+        assert!(OverridesConfig::from_json_str(r#"{"WingRidersV2": {"validity_asset": "zz"}}"#)
+            .is_err());
+    }
+}
@@ -0,0 +1,204 @@
+//! Structured reasons for UTXOs that did not produce a pool.
+//!
+//! The extend step ([`BaseDex::liquidity_pool_from_utxo_extend_or_skip`](super::BaseDex::liquidity_pool_from_utxo_extend_or_skip))
+//! returns the reason alongside the pool, so reasons only known deep inside a
+//! DEX (zap/stable pools, filter rules) reach [`SkipReport`] directly.
+
+use super::FilterRule;
+use crate::kupo::{is_retryable_http, KupoError};
+use crate::models::Utxo;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum SkipReason {
+    /// UTXO carries no datum hash or inline datum.
+    NoDatum,
+    /// Base parse rejected the asset set: fewer than two pool assets once LP,
+    /// NFT and validity units are excluded. `assets` counts all units on the UTXO.
+    TooFewAssets { assets: usize },
+    /// Minswap zap pool (one side is an LP token).
+    ZapPool,
+    /// Stable pool at an AMM address (e.g. WingRidersV2 special pools).
+    StablePool,
+    /// Dropped by a [`PoolFilter`](super::PoolFilter) rule.
+    Filtered { rule: FilterRule },
+    /// Base (`stage = "base"`) or datum (`stage = "extend"`) parsing failed.
+    /// `retryable` is set when the cause was a transient Kupo failure (rate
+    /// limit, 5xx, timeout) rather than the UTXO itself.
+    ParseError {
+        stage: String,
+        error: String,
+        #[serde(default)]
+        retryable: bool,
+    },
+}
+
+impl SkipReason {
+    /// Short snake_case label, used as the key in [`SkipReport::counts`].
+    pub fn label(&self) -> &'static str {
+        match self {
+            SkipReason::NoDatum => "no_datum",
+            SkipReason::TooFewAssets { .. } => "too_few_assets",
+            SkipReason::ZapPool => "zap_pool",
+            SkipReason::StablePool => "stable_pool",
+            SkipReason::Filtered { .. } => "filtered",
+            SkipReason::ParseError { .. } => "parse_error",
+        }
+    }
+
+    /// [`ParseError`](SkipReason::ParseError) for `error`, classified while it
+    /// is still typed.
+    pub fn parse_error(stage: &str, error: &anyhow::Error) -> Self {
+        let retryable = if let Some(e) = error.downcast_ref::<KupoError>() {
+            e.is_retryable()
+        } else if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+            is_retryable_http(e)
+        } else {
+            false
+        };
+        SkipReason::ParseError {
+            stage: stage.to_string(),
+            error: error.to_string(),
+            retryable,
+        }
+    }
+
+    /// Generic reason for a UTXO the base parse or datum lookup gave up on.
+    pub fn no_pool(utxo: &Utxo) -> Self {
+        if utxo.data_hash.is_none() && utxo.inline_datum.is_none() {
+            SkipReason::NoDatum
+        } else {
+            SkipReason::TooFewAssets {
+                assets: utxo.amount.len(),
+            }
+        }
+    }
+}
+
+impl From<FilterRule> for SkipReason {
+    fn from(rule: FilterRule) -> Self {
+        match rule {
+            FilterRule::ZapPool => SkipReason::ZapPool,
+            rule => SkipReason::Filtered { rule },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedUtxo {
+    pub tx_hash: String,
+    pub output_index: u32,
+    #[serde(flatten)]
+    pub reason: SkipReason,
+}
+
+/// All UTXOs skipped during one export, written next to the pool dump.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SkipReport {
+    pub dex: String,
+    /// Number of UTXOs skipped per [`SkipReason::label`].
+    pub counts: BTreeMap<String, usize>,
+    pub skipped: Vec<SkippedUtxo>,
+}
+
+impl SkipReport {
+    pub fn new(dex: &str) -> Self {
+        Self {
+            dex: dex.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn push(&mut self, utxo: &Utxo, reason: SkipReason) {
+        *self.counts.entry(reason.label().to_string()).or_default() += 1;
+        self.skipped.push(SkippedUtxo {
+            tx_hash: utxo.tx_hash.clone(),
+            output_index: utxo.output_index,
+            reason,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.skipped.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.skipped.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Unit;
+
+    #[test]
+    fn test_no_pool_reason() {
+        let utxo = Utxo {
+            address: "addr".to_string(),
+            tx_hash: "aa".to_string(),
+            tx_index: 0,
+            output_index: 0,
+            amount: vec![Unit {
+                unit: "lovelace".to_string(),
+                quantity: 2_000_000,
+            }],
+            block: "bb".to_string(),
+            slot: None,
+            data_hash: None,
+            inline_datum: None,
+            reference_script_hash: None,
+            datum_type: None,
+        };
+        assert_eq!(SkipReason::no_pool(&utxo), SkipReason::NoDatum);
+
+        let inline = Utxo {
+            inline_datum: Some("d87980".to_string()),
+            ..utxo.clone()
+        };
+        assert_eq!(
+            SkipReason::no_pool(&inline),
+            SkipReason::TooFewAssets { assets: 1 }
+        );
+
+        let json = serde_json::to_value(SkipReason::from(FilterRule::ZapPool)).unwrap();
+        assert_eq!(json["reason"], "zap_pool");
+    }
+
+    #[test]
+    fn test_parse_error_classifies_typed_errors() {
+        let retryable = |e: anyhow::Error| match SkipReason::parse_error("extend", &e) {
+            SkipReason::ParseError { retryable, .. } => retryable,
+            other => panic!("{:?}", other),
+        };
+        assert!(retryable(
+            anyhow::Error::new(KupoError::RateLimited).context("datum ab")
+        ));
+        assert!(retryable(
+            KupoError::Status {
+                url: "u".to_string(),
+                status: 503
+            }
+            .into()
+        ));
+        assert!(!retryable(
+            KupoError::DatumNotFound("ab".to_string()).into()
+        ));
+        // An error text that merely mentions a status is not a Kupo failure.
+        assert!(!retryable(anyhow::anyhow!(
+            "fee field returned 500 digits, rate_limited"
+        )));
+
+        let json = serde_json::json!({ "reason": "parse_error", "stage": "base", "error": "x" });
+        assert_eq!(
+            serde_json::from_value::<SkipReason>(json).unwrap(),
+            SkipReason::ParseError {
+                stage: "base".to_string(),
+                error: "x".to_string(),
+                retryable: false,
+            }
+        );
+    }
+}
@@ -1,22 +1,52 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use futures::future::try_join_all;
 use crate::kupo::KupoApi;
 use crate::models::asset::from_identifier;
-use crate::models::{token_identifier, LiquidityPool, Utxo};
-use super::BaseDex;
-use super::cbor::{constr_fields, decode_cbor, value_to_u64};
+use crate::models::{reserve_pair, AssetId, LiquidityPool, Utxo};
+use super::overrides::DexOverrides;
+use super::{BaseDex, PoolFilter, SkipReason};
+use super::cbor::{constr_fields, decode_cbor, value_to_hex, value_to_u64};
+use super::latency::SettlementLatency;
+use crate::quote::exact::CeilFee;
+use crate::utils::RetryBudget;
 
 const IDENTIFIER: &str = "SundaeSwapV1";
-const POOL_ADDRESS: &str = "addr1w9qzpelu9hn45pefc0xr4ac4kdxeswq7pndul2vuj59u8tqaxdznu";
+/// Validator swap math: the fee is rounded up to whole units and deducted first.
+pub const SWAP_MATH: CeilFee = CeilFee;
+/// SundaeSwapV1 scoopers run on a slower cadence than newer batchers.
+pub const SETTLEMENT: SettlementLatency = SettlementLatency::batched(120);
+const POOL_ADDRESSES: &[&str] = &["addr1w9qzpelu9hn45pefc0xr4ac4kdxeswq7pndul2vuj59u8tqaxdznu"];
 const LP_TOKEN_POLICY_ID: &str = "0029cb7c88c7567b63d1a512c0ed626aa169688ec980730c0473b913";
 
 pub struct SundaeSwapV1 {
     kupo: KupoApi,
+    filter: PoolFilter,
+    pool_addresses: Vec<String>,
+    lp_token_policy_id: String,
 }
 
 impl SundaeSwapV1 {
-    pub fn new(kupo: KupoApi) -> Self {
-        Self { kupo }
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
+        Self {
+            kupo: kupo.into(),
+            filter: PoolFilter::default(),
+            pool_addresses: POOL_ADDRESSES.iter().map(|a| a.to_string()).collect(),
+            lp_token_policy_id: LP_TOKEN_POLICY_ID.to_string(),
+        }
+    }
+
+    /// Replace the pool addresses or LP policy (see [`DexOverrides`]).
+    pub fn with_overrides(mut self, overrides: &DexOverrides) -> Result<Self> {
+        overrides.validate(IDENTIFIER)?;
+        self.pool_addresses = overrides.pool_addresses_or(POOL_ADDRESSES);
+        self.lp_token_policy_id = overrides.lp_token_policy_id_or(LP_TOKEN_POLICY_ID);
+        Ok(self)
+    }
+
+    pub fn with_pool_filter(mut self, filter: PoolFilter) -> Self {
+        self.filter = filter;
+        self
     }
 }
 
@@ -24,10 +54,17 @@ impl SundaeSwapV1 {
 ///
 /// Structure (constructor 0):
 ///   [0]: constr — asset pair info (two sub-constrs for assetA/assetB), ignored
-///   [1]: bytes  — pool identifier, ignored
+///   [1]: bytes  — pool ident (short id used in order datums, e.g. `08`)
 ///   [2]: int    — TotalLpTokens
 ///   [3]: constr — fee: { LpFeeNumerator, LpFeeDenominator }
-fn parse_pool_datum(cbor_hex: &str) -> Result<(u64, u64, u64)> {
+struct V1Datum {
+    ident: String,
+    total_lp: u64,
+    numerator: u64,
+    denominator: u64,
+}
+
+fn parse_pool_datum(cbor_hex: &str) -> Result<V1Datum> {
     let value = decode_cbor(cbor_hex)?;
     let fields = constr_fields(&value)?;
 
@@ -35,6 +72,8 @@ fn parse_pool_datum(cbor_hex: &str) -> Result<(u64, u64, u64)> {
         return Err(anyhow!("SundaeSwapV1 datum: expected >=4 fields, got {}", fields.len()));
     }
 
+    // fields[1]: pool ident
+    let ident = value_to_hex(&fields[1])?;
     // fields[2]: TotalLpTokens
     let total_lp = value_to_u64(&fields[2])?;
 
@@ -46,7 +85,12 @@ fn parse_pool_datum(cbor_hex: &str) -> Result<(u64, u64, u64)> {
     let numerator = value_to_u64(&fee_fields[0])?;
     let denominator = value_to_u64(&fee_fields[1])?;
 
-    Ok((total_lp, numerator, denominator))
+    Ok(V1Datum {
+        ident,
+        total_lp,
+        numerator,
+        denominator,
+    })
 }
 
 #[async_trait]
@@ -56,20 +100,35 @@ impl BaseDex for SundaeSwapV1 {
     }
 
     fn pool_address(&self) -> &str {
-        POOL_ADDRESS
+        &self.pool_addresses[0]
     }
 
     fn lp_token_policy_id(&self) -> &str {
-        LP_TOKEN_POLICY_ID
+        &self.lp_token_policy_id
+    }
+
+    /// Pool NFT is `"p " + ident`, the LP token `"lp " + ident`, under one policy.
+    fn lp_token_unit(&self, pool: &LiquidityPool) -> Option<String> {
+        let ident = pool.pool_id.strip_prefix(self.lp_token_policy_id.as_str())?.strip_prefix("7020")?;
+        Some(format!("{}6c7020{}", self.lp_token_policy_id, ident))
     }
 
     fn kupo(&self) -> &KupoApi {
         &self.kupo
     }
 
+    fn pool_filter(&self) -> &PoolFilter {
+        &self.filter
+    }
+
+    fn pool_match_patterns(&self) -> Vec<String> {
+        self.pool_addresses.clone()
+    }
+
     async fn all_liquidity_pool_utxos(&self) -> Result<Vec<Utxo>> {
         // Kupo: bech32 addresses are queried directly (no /* wildcard)
-        self.kupo.get(POOL_ADDRESS, true).await
+        let found = try_join_all(self.pool_addresses.iter().map(|a| self.kupo.get(a, true))).await?;
+        Ok(found.into_iter().flatten().collect())
     }
 
     /// Build a preliminary LiquidityPool from UTXO amounts.
@@ -80,7 +139,7 @@ impl BaseDex for SundaeSwapV1 {
         utxo: &Utxo,
         pool_id: &str,
     ) -> Result<Option<LiquidityPool>> {
-        if utxo.data_hash.is_none() {
+        if utxo.data_hash.is_none() && utxo.inline_datum.is_none() {
             return Ok(None);
         }
 
@@ -90,7 +149,7 @@ impl BaseDex for SundaeSwapV1 {
             .amount
             .iter()
             .filter(|a| {
-                if a.unit.starts_with(LP_TOKEN_POLICY_ID) {
+                if a.unit.starts_with(&self.lp_token_policy_id) {
                     pool_id = a.unit.clone();
                     false
                 } else {
@@ -102,65 +161,64 @@ impl BaseDex for SundaeSwapV1 {
         if ![2, 3].contains(&relevant.len()) {
             return Ok(None);
         }
+        let Some((unit_a, unit_b)) = reserve_pair(&relevant) else {
+            return Ok(None);
+        };
 
-        let a_idx = if relevant.len() == 2 { 0 } else { 1 };
-        let b_idx = if relevant.len() == 2 { 1 } else { 2 };
-
-        let asset_a = from_identifier(&relevant[a_idx].unit, 0);
-        let asset_b = from_identifier(&relevant[b_idx].unit, 0);
-        let reserve_a = relevant[a_idx].quantity.parse::<u64>()?;
-        let reserve_b = relevant[b_idx].quantity.parse::<u64>()?;
-
-        Ok(Some(LiquidityPool::new(
-            IDENTIFIER,
-            asset_a,
-            asset_b,
-            reserve_a,
-            reserve_b,
-            &utxo.address,
-            0.3,
-            &pool_id,
-        )))
+        let asset_a = from_identifier(&unit_a.unit, 0);
+        let asset_b = from_identifier(&unit_b.unit, 0);
+        let reserve_a = unit_a.quantity;
+        let reserve_b = unit_b.quantity;
+
+        Ok(Some(
+            LiquidityPool::new(
+                IDENTIFIER,
+                asset_a,
+                asset_b,
+                reserve_a,
+                reserve_b,
+                &utxo.address,
+                0.3,
+                &pool_id,
+            )
+            .observed_in(utxo),
+        ))
     }
 
-    /// Fetch datum and update fee.
+    /// Fetch datum, update fee and record the pool ident.
     /// Fee = (LpFeeNumerator / LpFeeDenominator) * 100
-    async fn liquidity_pool_from_utxo_extend(
+    async fn liquidity_pool_from_utxo_extend_or_skip(
         &self,
         utxo: &Utxo,
         pool_id: &str,
-    ) -> Result<Option<LiquidityPool>> {
+    ) -> Result<Result<LiquidityPool, SkipReason>> {
         let mut pool = match self.liquidity_pool_from_utxo(utxo, pool_id).await? {
             Some(p) => p,
-            None => return Ok(None),
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
 
-        let data_hash = match &utxo.data_hash {
-            Some(h) => h,
-            None => return Ok(None),
+        let datum = match self.kupo.utxo_datum(utxo).await? {
+            Some(d) => d,
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
+        let d = parse_pool_datum(&datum)?;
 
-        let datum = match &utxo.inline_datum {
-            Some(d) => d.clone(),
-            None => self.kupo.datum(data_hash).await?,
-        };
-        let (total_lp, numerator, denominator) = parse_pool_datum(&datum)?;
-
-        pool.total_lp_tokens = total_lp;
-        pool.pool_fee_percent = if denominator > 0 {
-            (numerator as f64 / denominator as f64) * 100.0
+        pool.total_lp_tokens = d.total_lp;
+        pool.metadata.protocol_pool_id = Some(d.ident);
+        pool.pool_fee_percent = if d.denominator > 0 {
+            (d.numerator as f64 / d.denominator as f64) * 100.0
         } else {
             0.3
         };
 
-        Ok(Some(pool))
+        Ok(self.filter_pool_or_skip(pool))
     }
 
     async fn liquidity_pool_from_pool_id(&self, pool_id: &str) -> Result<Option<LiquidityPool>> {
-        let full_id = if pool_id.starts_with(LP_TOKEN_POLICY_ID) {
+        let full_id = if pool_id.starts_with(&self.lp_token_policy_id) {
             pool_id.to_string()
         } else {
-            format!("{}{}", LP_TOKEN_POLICY_ID, pool_id)
+            format!("{}{}", self.lp_token_policy_id, pool_id)
         };
 
         let utxos = self.all_liquidity_pool_utxos().await?;
@@ -176,36 +234,107 @@ impl BaseDex for SundaeSwapV1 {
 
     async fn liquidity_pools_from_token(
         &self,
-        token_b: &str,
-        token_a: &str,
+        token_b: AssetId,
+        token_a: AssetId,
     ) -> Result<Vec<LiquidityPool>> {
-        let all_utxos = self.all_liquidity_pool_utxos().await?;
-        let mut pools = Vec::new();
-
-        for utxo in &all_utxos {
-            let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
-                Some(p) => p,
-                None => continue,
-            };
-
-            let id_a = token_identifier(&base.asset_a);
-            let id_b = token_identifier(&base.asset_b);
-
-            let matches = (id_a == token_a && id_b == token_b)
-                || (id_a == token_b && id_b == token_a);
-
-            if matches {
-                match self
-                    .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
-                    .await
-                {
-                    Ok(Some(p)) => pools.push(p),
-                    Ok(None) => {}
-                    Err(e) => eprintln!("[sundaeswap_v1] datum error {}: {}", utxo.tx_hash, e),
+        RetryBudget::ensure_scope(async {
+            let all_utxos = self.all_liquidity_pool_utxos().await?;
+            let mut pools = Vec::new();
+
+            for utxo in &all_utxos {
+                let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
+                    Some(p) => p,
+                    None => continue,
+                };
+
+                let id_a = AssetId::from(&base.asset_a);
+                let id_b = AssetId::from(&base.asset_b);
+
+                let matches = (id_a == token_a && id_b == token_b)
+                    || (id_a == token_b && id_b == token_a);
+
+                if matches {
+                    match self
+                        .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
+                        .await
+                    {
+                        Ok(Some(p)) => pools.push(p),
+                        Ok(None) => {}
+                        Err(e) => eprintln!("[sundaeswap_v1] datum error {}: {}", utxo.tx_hash, e),
+                    }
                 }
             }
-        }
 
-        Ok(pools)
+            Ok(pools)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Unit;
+
+    /// Inline datum of an ADA/SNEK pool with ident `08`, 1_234_567_890 LP
+    /// tokens and a 3/1000 fee.
+    const POOL_DATUM: &str = "d8799fd8799fd8799f4040ffd8799f581c279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f44534e454bffff41081a499602d2d8799f031903e8ffff";
+
+    #[tokio::test]
+    async fn test_protocol_pool_id_from_datum() {
+        let unit = |unit: String, quantity| Unit { unit, quantity };
+        let utxo = Utxo {
+            address: POOL_ADDRESSES[0].to_string(),
+            tx_hash: "ab".repeat(32),
+            tx_index: 0,
+            output_index: 0,
+            amount: vec![
+                unit("lovelace".to_string(), 50_000_000_000),
+                unit(
+                    "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b".to_string(),
+                    9_000_000,
+                ),
+                unit(format!("{}702008", LP_TOKEN_POLICY_ID), 1),
+            ],
+            block: "cd".repeat(32),
+            slot: Some(100),
+            data_hash: Some("ef".repeat(32)),
+            inline_datum: Some(POOL_DATUM.to_string()),
+            reference_script_hash: None,
+            datum_type: Some("inline".to_string()),
+        };
+
+        // The datum is inline, so Kupo is never asked.
+        let dex = SundaeSwapV1::new(KupoApi::new("http://127.0.0.1:9"));
+        let pool = dex
+            .liquidity_pool_from_utxo_extend(&utxo, "")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.metadata.protocol_pool_id.as_deref(), Some("08"));
+        assert_eq!(pool.pool_id, format!("{}702008", LP_TOKEN_POLICY_ID));
+        assert_eq!(pool.total_lp_tokens, 1_234_567_890);
+        assert!((pool.pool_fee_percent - 0.3).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_pool_from_raw_output() {
+        let value = std::collections::HashMap::from([
+            ("lovelace".to_string(), 50_000_000_000),
+            (
+                "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b".to_string(),
+                9_000_000,
+            ),
+            (format!("{}702008", LP_TOKEN_POLICY_ID), 1),
+        ]);
+
+        let dex = SundaeSwapV1::new(KupoApi::new("http://127.0.0.1:9"));
+        let pool = dex
+            .liquidity_pool_from_raw_output(POOL_ADDRESSES[0], &value, Some(POOL_DATUM))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.metadata.protocol_pool_id.as_deref(), Some("08"));
+        assert!((pool.pool_fee_percent - 0.3).abs() < 1e-9);
     }
 }
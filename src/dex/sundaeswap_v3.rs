@@ -1,45 +1,77 @@
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use futures::future::try_join_all;
 use crate::kupo::KupoApi;
 use crate::models::asset::from_identifier;
-use crate::models::{token_identifier, LiquidityPool, Utxo};
-use super::BaseDex;
-use super::cbor::{constr_fields, decode_cbor, value_to_u64, value_to_i64};
+use crate::models::{reserve_pair, AssetId, Credential, CredentialKind, LiquidityPool, PoolAdmin, Utxo};
+use super::overrides::DexOverrides;
+use super::{BaseDex, PoolFilter, SkipReason};
+use super::cbor::{constr_fields, constr_index, decode_cbor, maybe_value, value_to_hex, value_to_u64, value_to_i64};
+use super::latency::SettlementLatency;
+use crate::quote::exact::CeilFee;
+use crate::utils::RetryBudget;
 
 const IDENTIFIER: &str = "SundaeSwapV3";
+/// Validator swap math: the fee is rounded up to whole units and deducted first.
+pub const SWAP_MATH: CeilFee = CeilFee;
+/// SundaeSwapV3 scoopers usually settle in the block after the order.
+pub const SETTLEMENT: SettlementLatency = SettlementLatency::batched(40);
 // Two pool contract addresses — pools live at both
-const POOL_ADDRESS_V1: &str =
-    "addr1x8srqftqemf0mjlukfszd97ljuxdp44r372txfcr75wrz26rnxqnmtv3hdu2t6chcfhl2zzjh36a87nmd6dwsu3jenqsslnz7e";
-const POOL_ADDRESS_V2: &str =
-    "addr1z8srqftqemf0mjlukfszd97ljuxdp44r372txfcr75wrz2auzrlrz2kdd83wzt9u9n9qt2swgvhrmmn96k55nq6yuj4qw992w9";
+const POOL_ADDRESSES: &[&str] = &[
+    "addr1x8srqftqemf0mjlukfszd97ljuxdp44r372txfcr75wrz26rnxqnmtv3hdu2t6chcfhl2zzjh36a87nmd6dwsu3jenqsslnz7e",
+    "addr1z8srqftqemf0mjlukfszd97ljuxdp44r372txfcr75wrz2auzrlrz2kdd83wzt9u9n9qt2swgvhrmmn96k55nq6yuj4qw992w9",
+];
 const LP_TOKEN_POLICY_ID: &str =
     "e0302560ced2fdcbfcb2602697df970cd0d6a38f94b32703f51c312b";
 
 pub struct SundaeSwapV3 {
     kupo: KupoApi,
+    filter: PoolFilter,
+    pool_addresses: Vec<String>,
+    lp_token_policy_id: String,
 }
 
 impl SundaeSwapV3 {
-    pub fn new(kupo: KupoApi) -> Self {
-        Self { kupo }
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
+        Self {
+            kupo: kupo.into(),
+            filter: PoolFilter::default(),
+            pool_addresses: POOL_ADDRESSES.iter().map(|a| a.to_string()).collect(),
+            lp_token_policy_id: LP_TOKEN_POLICY_ID.to_string(),
+        }
+    }
+
+    /// Replace the pool addresses or LP policy (see [`DexOverrides`]).
+    pub fn with_overrides(mut self, overrides: &DexOverrides) -> Result<Self> {
+        overrides.validate(IDENTIFIER)?;
+        self.pool_addresses = overrides.pool_addresses_or(POOL_ADDRESSES);
+        self.lp_token_policy_id = overrides.lp_token_policy_id_or(LP_TOKEN_POLICY_ID);
+        Ok(self)
+    }
+
+    pub fn with_pool_filter(mut self, filter: PoolFilter) -> Self {
+        self.filter = filter;
+        self
     }
 }
 
 /// Parse SundaeSwapV3 pool datum.
 ///
 /// Structure (constructor 0):
-///   [0]: bytes — PoolIdentifier (ignored)
+///   [0]: bytes — PoolIdentifier
 ///   [1]: list  — [[policyA, nameA], [policyB, nameB]] (ignored)
 ///   [2]: int   — TotalLpTokens
 ///   [3]: int   — OpeningFee (ignored)
 ///   [4]: int   — FinalFee  → fee = FinalFee / 100
-///   [5]: (skipped — function in JS)
+///   [5]: Option<MultisigScript> — FeeManager, who may change the fees
 ///   [6]: int   — Unknown (ignored)
 ///   [7]: int   — LovelaceDeduction
 struct V3Datum {
+    ident: String,
     total_lp: u64,
     final_fee: u64,
     lovelace_deduction: i64,
+    fee_manager: Option<PoolAdmin>,
 }
 
 fn parse_pool_datum(cbor_hex: &str) -> Result<V3Datum> {
@@ -53,20 +85,74 @@ fn parse_pool_datum(cbor_hex: &str) -> Result<V3Datum> {
         ));
     }
 
+    // [0]: PoolIdentifier
+    let ident = value_to_hex(&fields[0])?;
     // [2]: TotalLpTokens
     let total_lp = value_to_u64(&fields[2])?;
     // [4]: FinalFee
     let final_fee = value_to_u64(&fields[4])?;
     // [7]: LovelaceDeduction
     let lovelace_deduction = value_to_i64(&fields[7])?;
+    // [5]: FeeManager. An unexpected shape leaves it unknown rather than
+    // failing the pool.
+    let fee_manager = maybe_value(&fields[5])
+        .ok()
+        .flatten()
+        .and_then(|script| parse_multisig(script).ok())
+        .map(|(credentials, required)| PoolAdmin {
+            role: "fee_manager".to_string(),
+            credentials,
+            required,
+        });
 
     Ok(V3Datum {
+        ident,
         total_lp,
         final_fee,
         lovelace_deduction,
+        fee_manager,
     })
 }
 
+/// Credentials a Sundae `MultisigScript` names and how many of them must sign.
+/// Nested scripts are flattened; time bounds name no one.
+///
+///   0 Signature [key_hash]      3 AtLeast [required, scripts]
+///   1 AllOf [scripts]           4 Before [time], 5 After [time]
+///   2 AnyOf [scripts]           6 Script [script_hash]
+fn parse_multisig(v: &ciborium::value::Value) -> Result<(Vec<Credential>, usize)> {
+    let fields = constr_fields(v)?;
+    let field = |i: usize| fields.get(i).ok_or_else(|| anyhow!("MultisigScript: missing field {}", i));
+    let single = |kind| -> Result<(Vec<Credential>, usize)> {
+        Ok((vec![Credential { kind, hash: value_to_hex(field(0)?)? }], 1))
+    };
+    let children = |i: usize| -> Result<Vec<(Vec<Credential>, usize)>> {
+        match field(i)? {
+            ciborium::value::Value::Array(scripts) => scripts.iter().map(parse_multisig).collect(),
+            other => Err(anyhow!("MultisigScript: expected a list, got {:?}", other)),
+        }
+    };
+    // Fewest signatures that satisfy `n` of `scripts`.
+    let cheapest = |mut scripts: Vec<(Vec<Credential>, usize)>, n: usize| {
+        scripts.sort_by_key(|(_, required)| *required);
+        let required = scripts.iter().take(n).map(|(_, required)| required).sum();
+        (scripts.into_iter().flat_map(|(credentials, _)| credentials).collect(), required)
+    };
+    match constr_index(v)? {
+        0 => single(CredentialKind::Key),
+        6 => single(CredentialKind::Script),
+        1 => {
+            let scripts = children(0)?;
+            let n = scripts.len();
+            Ok(cheapest(scripts, n))
+        }
+        2 => Ok(cheapest(children(0)?, 1)),
+        3 => Ok(cheapest(children(1)?, value_to_u64(field(0)?)? as usize)),
+        4 | 5 => Ok((Vec::new(), 0)),
+        n => Err(anyhow!("MultisigScript: unknown constructor {}", n)),
+    }
+}
+
 #[async_trait]
 impl BaseDex for SundaeSwapV3 {
     fn identifier(&self) -> &str {
@@ -74,27 +160,36 @@ impl BaseDex for SundaeSwapV3 {
     }
 
     fn pool_address(&self) -> &str {
-        POOL_ADDRESS_V1
+        &self.pool_addresses[0]
     }
 
     fn lp_token_policy_id(&self) -> &str {
-        LP_TOKEN_POLICY_ID
+        &self.lp_token_policy_id
+    }
+
+    /// CIP-68 labels: pool NFT is `000de140 + ident`, the LP token `0014df10 + ident`.
+    fn lp_token_unit(&self, pool: &LiquidityPool) -> Option<String> {
+        let ident = pool.pool_id.strip_prefix(self.lp_token_policy_id.as_str())?.strip_prefix("000de140")?;
+        Some(format!("{}0014df10{}", self.lp_token_policy_id, ident))
     }
 
     fn kupo(&self) -> &KupoApi {
         &self.kupo
     }
 
-    /// Fetch UTXOs from both pool addresses and merge.
+    fn pool_filter(&self) -> &PoolFilter {
+        &self.filter
+    }
+
+    /// Fetch UTXOs from every pool address and merge.
     /// Kupo: bech32 addresses are queried directly (no /* wildcard)
+    fn pool_match_patterns(&self) -> Vec<String> {
+        self.pool_addresses.clone()
+    }
+
     async fn all_liquidity_pool_utxos(&self) -> Result<Vec<Utxo>> {
-        let (v1, v2) = tokio::try_join!(
-            self.kupo.get(POOL_ADDRESS_V1, true),
-            self.kupo.get(POOL_ADDRESS_V2, true),
-        )?;
-        let mut all = v1;
-        all.extend(v2);
-        Ok(all)
+        let found = try_join_all(self.pool_addresses.iter().map(|a| self.kupo.get(a, true))).await?;
+        Ok(found.into_iter().flatten().collect())
     }
 
     async fn liquidity_pool_from_utxo(
@@ -102,7 +197,7 @@ impl BaseDex for SundaeSwapV3 {
         utxo: &Utxo,
         pool_id: &str,
     ) -> Result<Option<LiquidityPool>> {
-        if utxo.data_hash.is_none() {
+        if utxo.data_hash.is_none() && utxo.inline_datum.is_none() {
             return Ok(None);
         }
 
@@ -112,7 +207,7 @@ impl BaseDex for SundaeSwapV3 {
             .amount
             .iter()
             .filter(|a| {
-                if a.unit.starts_with(LP_TOKEN_POLICY_ID) {
+                if a.unit.starts_with(&self.lp_token_policy_id) {
                     pool_id = a.unit.clone();
                     false
                 } else {
@@ -124,51 +219,54 @@ impl BaseDex for SundaeSwapV3 {
         if ![2, 3].contains(&relevant.len()) {
             return Ok(None);
         }
+        let Some((unit_a, unit_b)) = reserve_pair(&relevant) else {
+            return Ok(None);
+        };
 
-        let a_idx = if relevant.len() == 2 { 0 } else { 1 };
-        let b_idx = if relevant.len() == 2 { 1 } else { 2 };
-
-        let asset_a = from_identifier(&relevant[a_idx].unit, 0);
-        let asset_b = from_identifier(&relevant[b_idx].unit, 0);
-        let reserve_a = relevant[a_idx].quantity.parse::<u64>()?;
-        let reserve_b = relevant[b_idx].quantity.parse::<u64>()?;
-
-        Ok(Some(LiquidityPool::new(
-            IDENTIFIER,
-            asset_a,
-            asset_b,
-            reserve_a,
-            reserve_b,
-            &utxo.address,
-            0.3,
-            &pool_id,
-        )))
+        let asset_a = from_identifier(&unit_a.unit, 0);
+        let asset_b = from_identifier(&unit_b.unit, 0);
+        let reserve_a = unit_a.quantity;
+        let reserve_b = unit_b.quantity;
+
+        Ok(Some(
+            LiquidityPool::new(
+                IDENTIFIER,
+                asset_a,
+                asset_b,
+                reserve_a,
+                reserve_b,
+                &utxo.address,
+                0.3,
+                &pool_id,
+            )
+            .observed_in(utxo),
+        ))
     }
 
-    /// Fetch datum, update fee and apply LovelaceDeduction.
-    async fn liquidity_pool_from_utxo_extend(
+    /// Fetch datum, update fee, record the pool ident and apply LovelaceDeduction.
+    async fn liquidity_pool_from_utxo_extend_or_skip(
         &self,
         utxo: &Utxo,
         pool_id: &str,
-    ) -> Result<Option<LiquidityPool>> {
+    ) -> Result<Result<LiquidityPool, SkipReason>> {
         let mut pool = match self.liquidity_pool_from_utxo(utxo, pool_id).await? {
             Some(p) => p,
-            None => return Ok(None),
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
 
-        let data_hash = match &utxo.data_hash {
-            Some(h) => h,
-            None => return Ok(None),
-        };
-
-        let datum = match &utxo.inline_datum {
-            Some(d) => d.clone(),
-            None => self.kupo.datum(data_hash).await?,
+        let datum = match self.kupo.utxo_datum(utxo).await? {
+            Some(d) => d,
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
         let d = parse_pool_datum(&datum)?;
 
         pool.total_lp_tokens = d.total_lp;
         pool.pool_fee_percent = d.final_fee as f64 / 100.0;
+        pool.metadata.protocol_pool_id = Some(d.ident);
+        pool.set_extra("lovelace_deduction", d.lovelace_deduction);
+        if let Some(fee_manager) = d.fee_manager {
+            pool.add_admin(fee_manager);
+        }
 
         // Apply lovelace deduction to whichever side holds ADA
         if d.lovelace_deduction != 0 {
@@ -180,14 +278,14 @@ impl BaseDex for SundaeSwapV3 {
             }
         }
 
-        Ok(Some(pool))
+        Ok(self.filter_pool_or_skip(pool))
     }
 
     async fn liquidity_pool_from_pool_id(&self, pool_id: &str) -> Result<Option<LiquidityPool>> {
-        let full_id = if pool_id.starts_with(LP_TOKEN_POLICY_ID) {
+        let full_id = if pool_id.starts_with(&self.lp_token_policy_id) {
             pool_id.to_string()
         } else {
-            format!("{}{}", LP_TOKEN_POLICY_ID, pool_id)
+            format!("{}{}", self.lp_token_policy_id, pool_id)
         };
 
         let utxos = self.all_liquidity_pool_utxos().await?;
@@ -203,36 +301,154 @@ impl BaseDex for SundaeSwapV3 {
 
     async fn liquidity_pools_from_token(
         &self,
-        token_b: &str,
-        token_a: &str,
+        token_b: AssetId,
+        token_a: AssetId,
     ) -> Result<Vec<LiquidityPool>> {
-        let all_utxos = self.all_liquidity_pool_utxos().await?;
-        let mut pools = Vec::new();
-
-        for utxo in &all_utxos {
-            let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
-                Some(p) => p,
-                None => continue,
-            };
-
-            let id_a = token_identifier(&base.asset_a);
-            let id_b = token_identifier(&base.asset_b);
-
-            let matches = (id_a == token_a && id_b == token_b)
-                || (id_a == token_b && id_b == token_a);
-
-            if matches {
-                match self
-                    .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
-                    .await
-                {
-                    Ok(Some(p)) => pools.push(p),
-                    Ok(None) => {}
-                    Err(e) => eprintln!("[sundaeswap_v3] datum error {}: {}", utxo.tx_hash, e),
+        RetryBudget::ensure_scope(async {
+            let all_utxos = self.all_liquidity_pool_utxos().await?;
+            let mut pools = Vec::new();
+
+            for utxo in &all_utxos {
+                let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
+                    Some(p) => p,
+                    None => continue,
+                };
+
+                let id_a = AssetId::from(&base.asset_a);
+                let id_b = AssetId::from(&base.asset_b);
+
+                let matches = (id_a == token_a && id_b == token_b)
+                    || (id_a == token_b && id_b == token_a);
+
+                if matches {
+                    match self
+                        .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
+                        .await
+                    {
+                        Ok(Some(p)) => pools.push(p),
+                        Ok(None) => {}
+                        Err(e) => eprintln!("[sundaeswap_v3] datum error {}: {}", utxo.tx_hash, e),
+                    }
                 }
             }
-        }
 
-        Ok(pools)
+            Ok(pools)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Unit;
+    use ciborium::value::Value;
+
+    fn constr(index: u64, fields: Vec<Value>) -> Value {
+        Value::Tag(121 + index, Box::new(Value::Array(fields)))
+    }
+
+    fn datum_hex(fee_manager: Value) -> String {
+        let int = |n: u64| Value::Integer(n.into());
+        let datum = constr(
+            0,
+            vec![
+                Value::Bytes(vec![0xab; 28]),
+                Value::Array(vec![]),
+                int(1_000),
+                int(30),
+                int(30),
+                fee_manager,
+                int(0),
+                int(2_000_000),
+            ],
+        );
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&datum, &mut cbor).unwrap();
+        hex::encode(cbor)
+    }
+
+    #[test]
+    fn test_parse_fee_manager() {
+        let key = |byte: u8| constr(0, vec![Value::Bytes(vec![byte; 28])]);
+        let script = constr(6, vec![Value::Bytes(vec![0xcc; 28])]);
+        let two_of_three = constr(
+            3,
+            vec![Value::Integer(2.into()), Value::Array(vec![key(0xaa), key(0xbb), script])],
+        );
+
+        let d = parse_pool_datum(&datum_hex(constr(0, vec![two_of_three]))).unwrap();
+        let manager = d.fee_manager.unwrap();
+        assert_eq!((manager.role.as_str(), manager.required), ("fee_manager", 2));
+        let kinds: Vec<CredentialKind> = manager.credentials.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, [CredentialKind::Key, CredentialKind::Key, CredentialKind::Script]);
+        assert_eq!(manager.credentials[0].hash, "aa".repeat(28));
+        assert_eq!(d.lovelace_deduction, 2_000_000);
+
+        let none = parse_pool_datum(&datum_hex(constr(1, vec![]))).unwrap();
+        assert!(none.fee_manager.is_none());
+    }
+
+    /// Inline datum of an ADA/SNEK pool: 28-byte ident, 9_876_543_210 LP
+    /// tokens, 0.3% fee, no fee manager, 2 ADA lovelace deduction.
+    const POOL_DATUM: &str = "d8799f581cba228444515fbefd2c8725338e49589f206c7f18a33e002b157aac3c9f9f4040ff9f581c279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f44534e454bffff1b000000024cb016ea181e181ed87a80001a001e8480ff";
+    const IDENT: &str = "ba228444515fbefd2c8725338e49589f206c7f18a33e002b157aac3c";
+
+    #[tokio::test]
+    async fn test_protocol_pool_id_from_datum() {
+        let unit = |unit: String, quantity| Unit { unit, quantity };
+        let utxo = Utxo {
+            address: POOL_ADDRESSES[0].to_string(),
+            tx_hash: "ab".repeat(32),
+            tx_index: 0,
+            output_index: 0,
+            amount: vec![
+                unit("lovelace".to_string(), 50_002_000_000),
+                unit(
+                    "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b".to_string(),
+                    9_000_000,
+                ),
+                unit(format!("{}000de140{}", LP_TOKEN_POLICY_ID, IDENT), 1),
+            ],
+            block: "cd".repeat(32),
+            slot: Some(100),
+            data_hash: Some("ef".repeat(32)),
+            inline_datum: Some(POOL_DATUM.to_string()),
+            reference_script_hash: None,
+            datum_type: Some("inline".to_string()),
+        };
+
+        // The datum is inline, so Kupo is never asked.
+        let dex = SundaeSwapV3::new(KupoApi::new("http://127.0.0.1:9"));
+        let pool = dex
+            .liquidity_pool_from_utxo_extend(&utxo, "")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.metadata.protocol_pool_id.as_deref(), Some(IDENT));
+        assert_eq!(pool.total_lp_tokens, 9_876_543_210);
+        assert_eq!(pool.reserve_a, 50_000_000_000);
+        assert!(pool.admins().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pool_from_raw_output() {
+        let value = std::collections::HashMap::from([
+            ("lovelace".to_string(), 50_002_000_000),
+            (
+                "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b".to_string(),
+                9_000_000,
+            ),
+            (format!("{}000de140{}", LP_TOKEN_POLICY_ID, IDENT), 1),
+        ]);
+
+        let dex = SundaeSwapV3::new(KupoApi::new("http://127.0.0.1:9"));
+        let pool = dex
+            .liquidity_pool_from_raw_output(POOL_ADDRESSES[0], &value, Some(POOL_DATUM))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.metadata.protocol_pool_id.as_deref(), Some(IDENT));
+        assert_eq!(pool.reserve_a, 50_000_000_000);
     }
 }
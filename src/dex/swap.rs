@@ -13,6 +13,19 @@ pub trait DexSwap: Send + Sync {
 
     fn estimated_receive(&self, pool: &LiquidityPool, in_token: &Token, in_amount: u64) -> u64;
     fn estimated_give(&self, pool: &LiquidityPool, out_token: &Token, out_amount: u64) -> u64;
+
+    /// [`estimated_receive`](Self::estimated_receive) in integer arithmetic
+    /// throughout (see [`crate::quote::exact`]), safe to derive a min-receive from.
+    fn estimated_receive_exact(&self, pool: &LiquidityPool, in_token: &Token, in_amount: u64) -> u64 {
+        crate::quote::estimated_receive_exact(pool, in_token, in_amount)
+    }
+
+    /// [`estimated_give`](Self::estimated_give) in integer arithmetic, rounded
+    /// up; `None` when the pool cannot pay `out_amount`.
+    fn estimated_give_exact(&self, pool: &LiquidityPool, out_token: &Token, out_amount: u64) -> Option<u64> {
+        crate::quote::estimated_give_exact(pool, out_token, out_amount)
+    }
+
     fn price_impact_percent(&self, pool: &LiquidityPool, in_token: &Token, in_amount: u64) -> f64;
     fn swap_order_fees(&self) -> Vec<SwapFee>;
 
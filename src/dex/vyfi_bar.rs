@@ -14,19 +14,26 @@
 /// Usage:
 ///   - `get_rate(pool_identifier)` — fetch UTXOs for the pool identifier, parse rate
 ///   - `rate_from_utxo(utxo)` — parse rate from a single UTXO (fetches datum)
+///   - `with_bar_pools(ids).get_all_rates()` — rates for every configured bar pool, fetched concurrently
 ///
 /// Pool identifier format: `<policy_id>.` (empty asset name, e.g. for VYFI/xVYFI pool).
 use anyhow::{anyhow, Result};
-use serde::Serialize;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::kupo::KupoApi;
-use crate::models::Utxo;
+use crate::models::{DecimalsOverrides, Utxo};
 use super::cbor::{constr_fields, decode_cbor, value_to_u64};
 
 const IDENTIFIER: &str = "VyfiBar";
+const CONCURRENCY: usize = 5;
 
 /// Exchange rate for a VyFi Bar pool.
-#[derive(Debug, Clone, Serialize)]
+///
+/// Fields added after the first release are `#[serde(default)]` so previously
+/// cached rates still deserialize.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rate {
     /// Identifier of the pool queried.
     pub pool_identifier: String,
@@ -34,15 +41,70 @@ pub struct Rate {
     pub base_asset: u64,
     /// Amount of the derived asset (ReserveA from datum).
     pub derived_asset: u64,
+    /// Unit (`policy + name_hex`) of the base asset.
+    #[serde(default)]
+    pub base_unit: String,
+    #[serde(default)]
+    pub base_decimals: u8,
+    /// The derived token is minted against base units, so it carries the base decimals.
+    #[serde(default)]
+    pub derived_decimals: u8,
+    /// Slot of the bar UTXO the rate was read from, when Kupo reported it.
+    #[serde(default)]
+    pub slot: Option<u64>,
+    /// Unix timestamp (seconds) at which the rate was read.
+    #[serde(default)]
+    pub observed_at: u64,
+}
+
+impl Rate {
+    /// Derived tokens per base token, adjusted for decimals. 0.0 when the bar is empty.
+    pub fn ratio(&self) -> f64 {
+        if self.base_asset == 0 {
+            return 0.0;
+        }
+        let derived = self.derived_asset as f64 / 10_f64.powi(self.derived_decimals as i32);
+        let base = self.base_asset as f64 / 10_f64.powi(self.base_decimals as i32);
+        derived / base
+    }
+
+    /// Seconds elapsed between `observed_at` and `now` (unix seconds).
+    pub fn age_secs(&self, now: u64) -> u64 {
+        now.saturating_sub(self.observed_at)
+    }
 }
 
 pub struct VyfiBar {
     kupo: KupoApi,
+    /// Pool identifiers queried by `get_all_rates`.
+    bar_pools: Vec<String>,
+    /// Source of `Rate::base_decimals`; Kupo has no token metadata.
+    decimals: DecimalsOverrides,
 }
 
 impl VyfiBar {
-    pub fn new(kupo: KupoApi) -> Self {
-        Self { kupo }
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
+        Self {
+            kupo: kupo.into(),
+            bar_pools: Vec::new(),
+            decimals: DecimalsOverrides::default(),
+        }
+    }
+
+    /// Decimals of the base assets; without them rates are ratios of base units.
+    pub fn with_decimals_overrides(mut self, decimals: DecimalsOverrides) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Set the bar pool identifiers enumerated by [`get_all_rates`](Self::get_all_rates).
+    pub fn with_bar_pools(mut self, pool_identifiers: Vec<String>) -> Self {
+        self.bar_pools = pool_identifiers;
+        self
+    }
+
+    pub fn bar_pools(&self) -> &[String] {
+        &self.bar_pools
     }
 
     pub fn identifier(&self) -> &str {
@@ -71,11 +133,36 @@ impl VyfiBar {
         self.rate_from_utxo_with_id(&utxo, pool_identifier).await
     }
 
+    /// Get rates for all configured bar pools, at most `CONCURRENCY` in flight.
+    ///
+    /// Rates are returned in the order of `bar_pools`; pools that fail are logged
+    /// and skipped rather than failing the whole batch.
+    pub async fn get_all_rates(&self) -> Result<Vec<Rate>> {
+        if self.bar_pools.is_empty() {
+            return Err(anyhow!("VyfiBar: no bar pools configured"));
+        }
+
+        let results: Vec<_> = stream::iter(self.bar_pools.iter())
+            .map(|id| async move { (id, self.get_rate(id).await) })
+            .buffered(CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut rates = Vec::with_capacity(results.len());
+        for (id, result) in results {
+            match result {
+                Ok(rate) => rates.push(rate),
+                Err(e) => eprintln!("[vyfi_bar] {}: {}", id, e),
+            }
+        }
+        Ok(rates)
+    }
+
     /// Parse a Rate from a single UTXO, given its pool identifier.
     ///
-    /// Returns `None` if the UTXO has no datum hash (can't parse rate).
+    /// Returns `None` if the UTXO has no datum (can't parse rate).
     pub async fn rate_from_utxo(&self, utxo: &Utxo, pool_identifier: &str) -> Result<Option<Rate>> {
-        if utxo.data_hash.is_none() {
+        if utxo.data_hash.is_none() && utxo.inline_datum.is_none() {
             return Ok(None);
         }
         Ok(Some(self.rate_from_utxo_with_id(utxo, pool_identifier).await?))
@@ -90,9 +177,8 @@ impl VyfiBar {
 
         // Find the base asset: non-lovelace token that does NOT belong to the pool policy.
         let base_unit = utxo
-            .amount
-            .iter()
-            .find(|a| a.unit != "lovelace" && !a.unit.starts_with(policy_id))
+            .non_ada_assets()
+            .find(|a| !a.unit.starts_with(policy_id))
             .ok_or_else(|| {
                 anyhow!(
                     "VyfiBar: no base asset found in UTXO {} for pool {}",
@@ -101,27 +187,30 @@ impl VyfiBar {
                 )
             })?;
 
-        let base_asset = base_unit.quantity.parse::<u64>().map_err(|e| {
-            anyhow!(
-                "VyfiBar: failed to parse base asset quantity '{}': {}",
-                base_unit.quantity,
-                e
-            )
-        })?;
+        let base_asset = base_unit.quantity;
 
         // Fetch datum and parse ReserveA as the derived asset.
-        let data_hash = utxo
-            .data_hash
-            .as_ref()
-            .ok_or_else(|| anyhow!("VyfiBar: UTXO {} has no datum hash", utxo.tx_hash))?;
-
-        let datum_cbor = self.kupo.datum(data_hash).await?;
+        let datum_cbor = self
+            .kupo
+            .utxo_datum(utxo)
+            .await?
+            .ok_or_else(|| anyhow!("VyfiBar: UTXO {} has no datum", utxo.tx_hash))?;
         let derived_asset = parse_bar_datum(&datum_cbor)?;
 
+        let base_decimals = self.decimals.get(&base_unit.unit).unwrap_or(0);
+
         Ok(Rate {
             pool_identifier: pool_identifier.to_string(),
             base_asset,
             derived_asset,
+            base_unit: base_unit.unit.clone(),
+            base_decimals,
+            derived_decimals: base_decimals,
+            slot: utxo.slot,
+            observed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
         })
     }
 }
@@ -149,3 +238,86 @@ fn parse_bar_datum(cbor_hex: &str) -> Result<u64> {
     // ReserveA
     value_to_u64(&inner[0]).map_err(|e| anyhow!("VyfiBar datum ReserveA: {}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kupo::test_server::{self, Response};
+
+    #[test]
+    fn test_ratio_and_legacy_deserialize() {
+        let legacy = r#"{"pool_identifier":"abc.","base_asset":2000000,"derived_asset":1000000}"#;
+        let rate: Rate = serde_json::from_str(legacy).unwrap();
+        assert_eq!(rate.observed_at, 0);
+        assert_eq!(rate.ratio(), 0.5);
+
+        let rate = Rate {
+            base_decimals: 6,
+            derived_decimals: 0,
+            ..rate
+        };
+        assert_eq!(rate.ratio(), 500_000.0);
+    }
+
+    const VYFI: &str = "804f5544c1962a40546827cab750a88404dc7108c0f588b72964754f56594649";
+    /// Policy of the fixture bar pool; its identifier is `<policy>.`.
+    const BAR_POLICY: &str = "b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0";
+    /// Bar datum with ReserveA = 1_500_000_000.
+    const BAR_DATUM: &str = "d8799fd8799f1a59682f00ffff";
+
+    /// A Kupo holding one bar UTXO (3_000_000_000 VYFI) under `BAR_POLICY`.
+    async fn fake_kupo() -> String {
+        test_server::serve(|request| {
+            Response::json(&if request.path.starts_with(&format!("/matches/{}.", BAR_POLICY)) {
+                serde_json::json!([{
+                    "address": "addr1bar",
+                    "transaction_id": "ab".repeat(32),
+                    "output_index": 0,
+                    "datum_hash": "dd".repeat(32),
+                    "datum_type": "hash",
+                    "created_at": { "slot_no": 100, "header_hash": "ef".repeat(32) },
+                    "value": {
+                        "coins": 2_000_000,
+                        "assets": {
+                            format!("{}.{}", &VYFI[..56], &VYFI[56..]): 3_000_000_000u64,
+                            format!("{}.78", BAR_POLICY): 1,
+                        }
+                    }
+                }])
+            } else if request.path.starts_with("/datums/") {
+                serde_json::json!({ "datum": BAR_DATUM })
+            } else {
+                serde_json::json!([])
+            })
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_get_all_rates_from_bar_pools() {
+        let kupo = KupoApi::new(&fake_kupo().await);
+        assert!(VyfiBar::new(kupo.clone()).get_all_rates().await.is_err());
+
+        let known = format!("{}.", BAR_POLICY);
+        let unknown = format!("{}.", "c1".repeat(28));
+        let decimals = DecimalsOverrides::new([(VYFI.to_string(), 6)].into());
+        let dex = VyfiBar::new(kupo)
+            .with_bar_pools(vec![unknown, known.clone()])
+            .with_decimals_overrides(decimals);
+        assert_eq!(dex.bar_pools().len(), 2);
+
+        // The pool with no UTXO is skipped; the other is rated.
+        let rates = dex.get_all_rates().await.unwrap();
+        assert_eq!(rates.len(), 1);
+        let rate = &rates[0];
+        assert_eq!(rate.pool_identifier, known);
+        assert_eq!(rate.base_unit, VYFI);
+        assert_eq!(
+            (rate.base_asset, rate.derived_asset),
+            (3_000_000_000, 1_500_000_000)
+        );
+        assert_eq!((rate.base_decimals, rate.derived_decimals), (6, 6));
+        assert_eq!(rate.slot, Some(100));
+        assert_eq!(rate.ratio(), 0.5);
+    }
+}
@@ -1,8 +1,11 @@
 use super::cbor::{constr_fields, decode_cbor, value_to_u64};
-use super::BaseDex;
+use super::latency::SettlementLatency;
+use super::{BaseDex, PoolFilter};
 use crate::kupo::KupoApi;
 use crate::models::asset::from_identifier;
-use crate::models::{LiquidityPool, Utxo};
+use crate::models::{reserve_pair, AssetId, LiquidityPool, PoolFreshness, PoolMetadata, Utxo};
+use crate::quote::exact::CeilFee;
+use crate::utils::RetryBudget;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -11,12 +14,17 @@ use std::sync::Arc;
 use tokio::sync::{RwLock, Semaphore};
 
 const IDENTIFIER: &str = "VyFinance";
+/// Validator swap math: the fee is rounded up to whole units and deducted first.
+pub const SWAP_MATH: CeilFee = CeilFee;
+/// VyFinance batchers run on a slower cadence.
+pub const SETTLEMENT: SettlementLatency = SettlementLatency::batched(120);
 const VYFI_API_URL: &str = "https://api.vyfi.io/lp?networkId=1&v2=true";
 const CONCURRENCY: usize = 5;
 
 pub struct VyFinance {
     kupo: KupoApi,
     cache: RwLock<Option<VyFinanceCache>>,
+    filter: PoolFilter,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -38,13 +46,19 @@ pub struct VyFinancePoolData {
 pub type VyFinanceCache = HashMap<String, HashMap<String, Vec<VyFinancePoolData>>>;
 
 impl VyFinance {
-    pub fn new(kupo: KupoApi) -> Self {
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
         Self {
-            kupo,
+            kupo: kupo.into(),
             cache: RwLock::new(None),
+            filter: PoolFilter::default(),
         }
     }
 
+    pub fn with_pool_filter(mut self, filter: PoolFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
     /// Look up the units_pair for a pool_id from the cache.
     async fn find_units_pair_for_pool_id(&self, pool_id: &str) -> Option<String> {
         let guard = self.cache.read().await;
@@ -135,15 +149,17 @@ impl VyFinance {
         structured
     }
 
-    /// Query pools with optional cache.
+    /// Query pools with optional cache. The Kupo requests share the enclosing
+    /// [`RetryBudget`], or a fresh default one when none is active.
     pub async fn liquidity_pools_from_token_cached(
         &self,
-        token_b: &str,
-        token_a: &str,
+        token_b: impl Into<AssetId>,
+        token_a: impl Into<AssetId>,
         cache: Option<&VyFinanceCache>,
     ) -> Result<Vec<LiquidityPool>> {
-        let token_a = token_a.replace('.', "");
-        let token_b = token_b.replace('.', "");
+        let token_a = token_a.into().to_string();
+        let token_b = token_b.into().to_string();
+        let budget = RetryBudget::current().unwrap_or_default();
 
         let pool_datas = if let Some(structured) = cache {
             let mut matches = Vec::new();
@@ -160,7 +176,7 @@ impl VyFinance {
             matches
         } else {
             // Fallback: fetch all and filter (slow)
-            let all = self.fetch_all_pool_data().await?;
+            let all = budget.scope(self.fetch_all_pool_data()).await?;
             all.into_iter()
                 .filter(|p| {
                     let tokens: Vec<&str> = p.units_pair.split('/').collect();
@@ -175,7 +191,6 @@ impl VyFinance {
             return Ok(vec![]);
         }
 
-        let kupo_url = self.kupo.api_url().to_string();
         let sem = Arc::new(Semaphore::new(CONCURRENCY));
         let mut handles = Vec::with_capacity(pool_datas.len());
 
@@ -186,21 +201,26 @@ impl VyFinance {
             }
             let pair = pool_data.units_pair.clone();
             let sem = Arc::clone(&sem);
-            let kupo_url = kupo_url.clone();
+            let kupo = self.kupo.clone();
+            let budget = budget.clone();
 
             let handle = tokio::spawn(async move {
                 let _permit = sem.acquire().await.unwrap();
-                let kupo = KupoApi::new(&kupo_url);
 
-                let utxos = match kupo.get(&nft_id, true).await {
-                    Ok(u) => u,
-                    Err(_e) => {
-                        return None;
-                    }
-                };
-
-                let utxo = utxos.into_iter().next()?;
-                build_pool_from_utxo(&utxo, &nft_id, &kupo, Some(&pair)).await
+                // Spawned tasks do not inherit the task-local budget.
+                budget
+                    .scope(async {
+                        let utxos = match kupo.get(&nft_id, true).await {
+                            Ok(u) => u,
+                            Err(_e) => {
+                                return None;
+                            }
+                        };
+
+                        let utxo = utxos.into_iter().next()?;
+                        build_pool_from_utxo(&utxo, &nft_id, &kupo, Some(&pair)).await
+                    })
+                    .await
             });
 
             handles.push(handle);
@@ -209,7 +229,7 @@ impl VyFinance {
         let mut pools = Vec::new();
         for handle in handles {
             if let Ok(Some(pool)) = handle.await {
-                pools.push(pool);
+                pools.extend(self.filter_pool(pool));
             }
         }
 
@@ -229,7 +249,7 @@ async fn build_pool_from_utxo(
     kupo: &KupoApi,
     units_pair: Option<&str>,
 ) -> Option<LiquidityPool> {
-    if utxo.data_hash.is_none() {
+    if utxo.data_hash.is_none() && utxo.inline_datum.is_none() {
         return None;
     }
 
@@ -240,26 +260,15 @@ async fn build_pool_from_utxo(
         .filter(|a| a.unit != nft_joined)
         .collect();
 
-    if relevant.len() < 2 {
-        return None;
-    }
-
-    let a_idx = if relevant.len() == 2 { 0 } else { 1 };
-    let b_idx = if relevant.len() == 2 { 1 } else { 2 };
+    let (unit_a, unit_b) = reserve_pair(&relevant)?;
 
-    let raw_a = relevant[a_idx].quantity.parse::<u64>().ok()?;
-    let raw_b = relevant[b_idx].quantity.parse::<u64>().ok()?;
+    let raw_a = unit_a.quantity;
+    let raw_b = unit_b.quantity;
 
-    let asset_a = from_identifier(&relevant[a_idx].unit, 0);
-    let asset_b = from_identifier(&relevant[b_idx].unit, 0);
+    let asset_a = from_identifier(&unit_a.unit, 0);
+    let asset_b = from_identifier(&unit_b.unit, 0);
 
-    let datum = match &utxo.inline_datum {
-        Some(d) => d.clone(),
-        None => {
-            let data_hash = utxo.data_hash.as_ref()?;
-            kupo.datum(data_hash).await.ok()?
-        }
-    };
+    let datum = kupo.utxo_datum(utxo).await.ok().flatten()?;
     let d = parse_pool_datum(&datum).ok()?;
 
     // Datum bar_fee fields correspond to the units_pair ordering from VyFi API,
@@ -269,7 +278,7 @@ async fn build_pool_from_utxo(
         let pair_tokens: Vec<&str> = pair.split('/').collect();
         if pair_tokens.len() == 2 {
             let first_token = pair_tokens[0].replace('.', "");
-            let utxo_a_unit = &relevant[a_idx].unit;
+            let utxo_a_unit = &unit_a.unit;
             if *utxo_a_unit == first_token {
                 (d.bar_fee_a, d.bar_fee_b)
             } else {
@@ -284,6 +293,10 @@ async fn build_pool_from_utxo(
 
     let reserve_a = raw_a.saturating_sub(fee_for_a);
     let reserve_b = raw_b.saturating_sub(fee_for_b);
+    let extras = serde_json::json!({
+        "bar_fee_a": fee_for_a,
+        "bar_fee_b": fee_for_b,
+    });
 
     Some(LiquidityPool {
         dex_identifier: IDENTIFIER.to_string(),
@@ -295,6 +308,11 @@ async fn build_pool_from_utxo(
         pool_id: pool_nft_id.to_string(),
         pool_fee_percent: 0.3,
         total_lp_tokens: d.total_lp,
+        metadata: PoolMetadata {
+            freshness: Some(PoolFreshness::from_utxo(utxo)),
+            extras,
+            ..Default::default()
+        },
     })
 }
 
@@ -344,6 +362,10 @@ impl BaseDex for VyFinance {
         &self.kupo
     }
 
+    fn pool_filter(&self) -> &PoolFilter {
+        &self.filter
+    }
+
     async fn all_liquidity_pool_utxos(&self) -> Result<Vec<Utxo>> {
         Ok(vec![])
     }
@@ -354,7 +376,8 @@ impl BaseDex for VyFinance {
         pool_id: &str,
     ) -> Result<Option<LiquidityPool>> {
         let units_pair = self.find_units_pair_for_pool_id(pool_id).await;
-        Ok(build_pool_from_utxo(utxo, pool_id, &self.kupo, units_pair.as_deref()).await)
+        let pool = build_pool_from_utxo(utxo, pool_id, &self.kupo, units_pair.as_deref()).await;
+        Ok(pool.and_then(|p| self.filter_pool(p)))
     }
 
     async fn liquidity_pool_from_pool_id(&self, pool_id: &str) -> Result<Option<LiquidityPool>> {
@@ -371,7 +394,9 @@ impl BaseDex for VyFinance {
         let utxos = self.kupo.get(&nft, true).await?;
         match utxos.first() {
             Some(utxo) => {
-                Ok(build_pool_from_utxo(utxo, pool_id, &self.kupo, units_pair.as_deref()).await)
+                let pool =
+                    build_pool_from_utxo(utxo, pool_id, &self.kupo, units_pair.as_deref()).await;
+                Ok(pool.and_then(|p| self.filter_pool(p)))
             }
             None => Ok(None),
         }
@@ -379,8 +404,8 @@ impl BaseDex for VyFinance {
 
     async fn liquidity_pools_from_token(
         &self,
-        token_b: &str,
-        token_a: &str,
+        token_b: AssetId,
+        token_a: AssetId,
     ) -> Result<Vec<LiquidityPool>> {
         self.ensure_cache().await?;
         let guard = self.cache.read().await;
@@ -388,6 +413,39 @@ impl BaseDex for VyFinance {
             .await
     }
 
+    /// Counter-assets come from the pool list cache; each pair is then
+    /// fetched as in `liquidity_pools_from_token`.
+    async fn liquidity_pools_with_token(&self, token: AssetId) -> Result<Vec<LiquidityPool>> {
+        self.ensure_cache().await?;
+        let guard = self.cache.read().await;
+        let Some(structured) = guard.as_ref() else {
+            return Ok(Vec::new());
+        };
+        let mut others: Vec<&String> = structured
+            .get(token.as_str())
+            .map(|m| m.keys().collect())
+            .unwrap_or_default();
+        others.extend(
+            structured
+                .iter()
+                .filter(|(_, m)| m.contains_key(token.as_str()))
+                .map(|(a, _)| a),
+        );
+        others.sort();
+        others.dedup();
+
+        let mut pools = Vec::new();
+        for other in others {
+            pools.extend(
+                self.liquidity_pools_from_token_cached(other, &token, Some(structured))
+                    .await?,
+            );
+        }
+        let mut seen = std::collections::HashSet::new();
+        pools.retain(|p| seen.insert(p.pool_id.clone()));
+        Ok(pools)
+    }
+
     async fn all_liquidity_pools(&self) -> Result<Vec<LiquidityPool>> {
         let pool_data = self.fetch_all_pool_data().await?;
         let cache = Self::structure_pool_data(pool_data);
@@ -398,7 +456,6 @@ impl BaseDex for VyFinance {
             .flatten()
             .collect();
 
-        let kupo_url = self.kupo.api_url().to_string();
         let sem = Arc::new(Semaphore::new(CONCURRENCY));
         let mut handles = Vec::with_capacity(pool_datas.len());
 
@@ -409,11 +466,10 @@ impl BaseDex for VyFinance {
             }
             let pair = pool_data.units_pair.clone();
             let sem = Arc::clone(&sem);
-            let kupo_url = kupo_url.clone();
+            let kupo = self.kupo.clone();
 
             let handle = tokio::spawn(async move {
                 let _permit = sem.acquire().await.unwrap();
-                let kupo = KupoApi::new(&kupo_url);
                 let utxos = match kupo.get(&nft_id, true).await {
                     Ok(u) => u,
                     Err(_e) => {
@@ -437,7 +493,7 @@ impl BaseDex for VyFinance {
         let mut pools = Vec::new();
         for handle in handles {
             if let Ok(Some(pool)) = handle.await {
-                pools.push(pool);
+                pools.extend(self.filter_pool(pool));
             }
         }
         Ok(pools)
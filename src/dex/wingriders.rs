@@ -2,27 +2,61 @@ use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use crate::kupo::KupoApi;
 use crate::models::asset::from_identifier;
-use crate::models::{token_identifier, LiquidityPool, Utxo};
-use super::BaseDex;
+use crate::models::{reserve_pair, AssetId, FeeBreakdown, LiquidityPool, Utxo};
+use super::overrides::{DexOverrides, ValidityAsset};
+use super::{BaseDex, PoolFilter, SkipReason};
 use super::cbor::{constr_fields, decode_cbor, value_to_u64};
+use super::latency::SettlementLatency;
+use crate::quote::exact::CeilFee;
+use crate::utils::RetryBudget;
 
 const IDENTIFIER: &str = "WingRiders";
-const POOL_VALIDITY_POLICY: &str = "026a18d04a0c642759bb3d83b12e3344894e5c1c7b2aeb1a2113a570";
+/// Validator swap math: the fee (LP plus treasury) is rounded up to whole units
+/// and deducted first.
+pub const SWAP_MATH: CeilFee = CeilFee;
+/// WingRiders agents pick orders up within a few blocks.
+pub const SETTLEMENT: SettlementLatency = SettlementLatency::batched(60);
 /// Kupo query pattern (policy.name with dot)
 const POOL_VALIDITY_ASSET: &str = "026a18d04a0c642759bb3d83b12e3344894e5c1c7b2aeb1a2113a570.4c";
-/// Joined form (no dot) — the "check" asset to exclude when finding pool_id
-const POOL_VALIDITY_ASSET_JOINED: &str =
-    "026a18d04a0c642759bb3d83b12e3344894e5c1c7b2aeb1a2113a5704c";
 /// Minimum ADA locked in pool (3 ADA)
 const MIN_POOL_ADA: u64 = 3_000_000;
+/// 0.35% swap fee = 0.30% to LPs + 0.05% to the protocol treasury
+const LP_FEE_PERCENT: f64 = 0.3;
+const TREASURY_FEE_PERCENT: f64 = 0.05;
+/// Agent fee charged per request (2 ADA)
+const AGENT_FEE: u64 = 2_000_000;
+/// LP tokens are minted up front at i64::MAX and held by the pool.
+const LP_TOKEN_SUPPLY: u64 = i64::MAX as u64;
 
 pub struct WingRiders {
     kupo: KupoApi,
+    filter: PoolFilter,
+    validity_asset: ValidityAsset,
+    lp_fee_percent: f64,
 }
 
 impl WingRiders {
-    pub fn new(kupo: KupoApi) -> Self {
-        Self { kupo }
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
+        Self {
+            kupo: kupo.into(),
+            filter: PoolFilter::default(),
+            validity_asset: ValidityAsset::new(POOL_VALIDITY_ASSET),
+            lp_fee_percent: LP_FEE_PERCENT,
+        }
+    }
+
+    /// Replace the validity asset or LP fee (see [`DexOverrides`]); the
+    /// treasury fee stays on top. Pool ids follow the validity asset's policy.
+    pub fn with_overrides(mut self, overrides: &DexOverrides) -> Result<Self> {
+        overrides.validate(IDENTIFIER)?;
+        self.validity_asset = overrides.validity_asset_or(POOL_VALIDITY_ASSET);
+        self.lp_fee_percent = overrides.fee_percent_or(LP_FEE_PERCENT);
+        Ok(self)
+    }
+
+    pub fn with_pool_filter(mut self, filter: PoolFilter) -> Self {
+        self.filter = filter;
+        self
     }
 }
 
@@ -72,19 +106,34 @@ impl BaseDex for WingRiders {
     }
 
     fn pool_address(&self) -> &str {
-        POOL_VALIDITY_ASSET
+        &self.validity_asset.pattern
     }
 
     fn lp_token_policy_id(&self) -> &str {
-        POOL_VALIDITY_POLICY
+        &self.validity_asset.policy_id
+    }
+
+    /// The pool id is the LP token unit (validity policy, pool-specific name).
+    fn lp_token_unit(&self, pool: &LiquidityPool) -> Option<String> {
+        pool.pool_id
+            .starts_with(&self.validity_asset.policy_id)
+            .then(|| pool.pool_id.clone())
     }
 
     fn kupo(&self) -> &KupoApi {
         &self.kupo
     }
 
+    fn pool_filter(&self) -> &PoolFilter {
+        &self.filter
+    }
+
+    fn pool_match_patterns(&self) -> Vec<String> {
+        vec![self.validity_asset.pattern.clone()]
+    }
+
     async fn all_liquidity_pool_utxos(&self) -> Result<Vec<Utxo>> {
-        self.kupo.get(POOL_VALIDITY_ASSET, true).await
+        self.kupo.get(&self.validity_asset.pattern, true).await
     }
 
     async fn liquidity_pool_from_utxo(
@@ -92,7 +141,7 @@ impl BaseDex for WingRiders {
         utxo: &Utxo,
         pool_id: &str,
     ) -> Result<Option<LiquidityPool>> {
-        if utxo.data_hash.is_none() {
+        if utxo.data_hash.is_none() && utxo.inline_datum.is_none() {
             return Ok(None);
         }
 
@@ -100,84 +149,89 @@ impl BaseDex for WingRiders {
         let relevant: Vec<_> = utxo
             .amount
             .iter()
-            .filter(|a| !a.unit.starts_with(POOL_VALIDITY_POLICY))
+            .filter(|a| !a.unit.starts_with(&self.validity_asset.policy_id))
             .collect();
 
-        if relevant.len() < 2 {
+        let Some((unit_a, unit_b)) = reserve_pair(&relevant) else {
             return Ok(None);
-        }
+        };
 
-        // Pool ID = first validity policy asset that is NOT the validity check asset
-        let pool_id = utxo
-            .amount
-            .iter()
-            .find(|a| {
-                a.unit.starts_with(POOL_VALIDITY_POLICY)
-                    && a.unit != POOL_VALIDITY_ASSET_JOINED
-            })
+        // Pool ID = first validity policy asset that is NOT the validity check asset.
+        // That asset is the LP token; the pool holds whatever is not in circulation.
+        let lp_asset = utxo.amount.iter().find(|a| {
+            a.unit.starts_with(&self.validity_asset.policy_id) && a.unit != self.validity_asset.unit
+        });
+        let pool_id = lp_asset
             .map(|a| a.unit.clone())
             .unwrap_or_else(|| pool_id.to_string());
+        let total_lp_tokens = lp_asset
+            .map(|a| a.quantity)
+            .map(|held| LP_TOKEN_SUPPLY.saturating_sub(held))
+            .unwrap_or(0);
 
-        let a_idx = if relevant.len() == 2 { 0 } else { 1 };
-        let b_idx = if relevant.len() == 2 { 1 } else { 2 };
+        let a_unit = &unit_a.unit;
+        let b_unit = &unit_b.unit;
 
-        let a_unit = &relevant[a_idx].unit;
-        let b_unit = &relevant[b_idx].unit;
+        let raw_a = unit_a.quantity;
+        let raw_b = unit_b.quantity;
 
-        let raw_a = relevant[a_idx].quantity.parse::<u64>()?;
-        let raw_b = relevant[b_idx].quantity.parse::<u64>()?;
-
-        let reserve_a = if a_unit == "lovelace" { ada_reserve(raw_a) } else { raw_a };
-        let reserve_b = if b_unit == "lovelace" { ada_reserve(raw_b) } else { raw_b };
+        let reserve_a = if unit_a.is_lovelace() { ada_reserve(raw_a) } else { raw_a };
+        let reserve_b = if unit_b.is_lovelace() { ada_reserve(raw_b) } else { raw_b };
 
         let asset_a = from_identifier(a_unit, 0);
         let asset_b = from_identifier(b_unit, 0);
 
-        Ok(Some(LiquidityPool::new(
+        let mut pool = LiquidityPool::new(
             IDENTIFIER,
             asset_a,
             asset_b,
             reserve_a,
             reserve_b,
             &utxo.address,
-            0.35,
+            self.lp_fee_percent + TREASURY_FEE_PERCENT,
             &pool_id,
-        )))
+        )
+        .observed_in(utxo);
+        pool.total_lp_tokens = total_lp_tokens;
+        Ok(Some(pool))
     }
 
-    /// Fetch datum and subtract treasury from reserves.
-    async fn liquidity_pool_from_utxo_extend(
+    /// Fetch datum, subtract treasury from reserves and attach the fixed fee split.
+    async fn liquidity_pool_from_utxo_extend_or_skip(
         &self,
         utxo: &Utxo,
         pool_id: &str,
-    ) -> Result<Option<LiquidityPool>> {
+    ) -> Result<Result<LiquidityPool, SkipReason>> {
         let mut pool = match self.liquidity_pool_from_utxo(utxo, pool_id).await? {
             Some(p) => p,
-            None => return Ok(None),
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
 
-        let data_hash = match &utxo.data_hash {
-            Some(h) => h,
-            None => return Ok(None),
-        };
-
-        let datum = match &utxo.inline_datum {
-            Some(d) => d.clone(),
-            None => self.kupo.datum(data_hash).await?,
+        let datum = match self.kupo.utxo_datum(utxo).await? {
+            Some(d) => d,
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
         let d = parse_pool_datum(&datum)?;
 
         pool.reserve_a = pool.reserve_a.saturating_sub(d.treasury_a);
         pool.reserve_b = pool.reserve_b.saturating_sub(d.treasury_b);
-
-        Ok(Some(pool))
+        pool.set_extra("treasury_a", d.treasury_a);
+        pool.set_extra("treasury_b", d.treasury_b);
+        pool.metadata.fee_breakdown = Some(FeeBreakdown {
+            lp_fee_percent: self.lp_fee_percent,
+            protocol_fee_percent: TREASURY_FEE_PERCENT,
+            agent_fee_lovelace: Some(AGENT_FEE),
+            ..Default::default()
+        });
+
+        Ok(self.filter_pool_or_skip(pool))
     }
 
     async fn liquidity_pool_from_pool_id(&self, pool_id: &str) -> Result<Option<LiquidityPool>> {
-        let full_id = if pool_id.starts_with(POOL_VALIDITY_POLICY) {
+        let full_id = if pool_id.starts_with(&self.validity_asset.policy_id) {
             pool_id.to_string()
         } else {
-            format!("{}{}", POOL_VALIDITY_POLICY, pool_id)
+            format!("{}{}", self.validity_asset.policy_id, pool_id)
         };
 
         let utxos = self.all_liquidity_pool_utxos().await?;
@@ -193,36 +247,84 @@ impl BaseDex for WingRiders {
 
     async fn liquidity_pools_from_token(
         &self,
-        token_b: &str,
-        token_a: &str,
+        token_b: AssetId,
+        token_a: AssetId,
     ) -> Result<Vec<LiquidityPool>> {
-        let all_utxos = self.all_liquidity_pool_utxos().await?;
-        let mut pools = Vec::new();
-
-        for utxo in &all_utxos {
-            let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
-                Some(p) => p,
-                None => continue,
-            };
-
-            let id_a = token_identifier(&base.asset_a);
-            let id_b = token_identifier(&base.asset_b);
-
-            let matches = (id_a == token_a && id_b == token_b)
-                || (id_a == token_b && id_b == token_a);
-
-            if matches {
-                match self
-                    .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
-                    .await
-                {
-                    Ok(Some(p)) => pools.push(p),
-                    Ok(None) => {}
-                    Err(e) => eprintln!("[wingriders] datum error {}: {}", utxo.tx_hash, e),
+        RetryBudget::ensure_scope(async {
+            let all_utxos = self.all_liquidity_pool_utxos().await?;
+            let mut pools = Vec::new();
+
+            for utxo in &all_utxos {
+                let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
+                    Some(p) => p,
+                    None => continue,
+                };
+
+                let id_a = AssetId::from(&base.asset_a);
+                let id_b = AssetId::from(&base.asset_b);
+
+                let matches = (id_a == token_a && id_b == token_b)
+                    || (id_a == token_b && id_b == token_a);
+
+                if matches {
+                    match self
+                        .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
+                        .await
+                    {
+                        Ok(Some(p)) => pools.push(p),
+                        Ok(None) => {}
+                        Err(e) => eprintln!("[wingriders] datum error {}: {}", utxo.tx_hash, e),
+                    }
                 }
             }
-        }
 
-        Ok(pools)
+            Ok(pools)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ciborium::value::Value;
+    use std::collections::HashMap;
+
+    fn constr(index: u64, fields: Vec<Value>) -> Value {
+        Value::Tag(121 + index, Box::new(Value::Array(fields)))
+    }
+
+    #[tokio::test]
+    async fn test_pool_from_raw_output() {
+        let int = |n: u64| Value::Integer(n.into());
+        let datum = constr(
+            0,
+            vec![
+                Value::Bytes(vec![0xab; 28]),
+                constr(0, vec![constr(0, vec![]), int(0), int(1_000), int(500)]),
+            ],
+        );
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&datum, &mut cbor).unwrap();
+
+        let lp = format!("{}{}", &POOL_VALIDITY_ASSET[..56], "aa".repeat(32));
+        let value = HashMap::from([
+            ("lovelace".to_string(), 103_000_000),
+            ("279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b".to_string(), 7_000),
+            (POOL_VALIDITY_ASSET.to_string(), 1),
+            (lp.clone(), LP_TOKEN_SUPPLY - 5_000),
+        ]);
+
+        // Inline datum, no datum hash: decoded without asking the Kupo.
+        let dex = WingRiders::new(KupoApi::new("http://127.0.0.1:9"));
+        let pool = dex
+            .liquidity_pool_from_raw_output("addr1pool", &value, Some(&hex::encode(cbor)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.pool_id, lp);
+        assert_eq!(pool.total_lp_tokens, 5_000);
+        // Min-ADA deposit and treasuries are not tradable.
+        assert_eq!((pool.reserve_a, pool.reserve_b), (99_999_000, 6_500));
     }
 }
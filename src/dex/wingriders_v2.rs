@@ -2,24 +2,50 @@ use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use crate::kupo::KupoApi;
 use crate::models::asset::from_identifier;
-use crate::models::{token_identifier, LiquidityPool, Utxo};
-use super::BaseDex;
-use super::cbor::{constr_fields, decode_cbor, is_nonempty_constr, value_to_u64};
+use crate::models::{reserve_pair, AssetId, FeeBreakdown, LiquidityPool, PoolAdmin, Utxo};
+use super::skip::SkipReason;
+use super::overrides::{DexOverrides, ValidityAsset};
+use super::{BaseDex, PoolFilter};
+use super::cbor::{address_payment_credential, constr_fields, decode_cbor, is_nonempty_constr, maybe_value, value_to_u64};
+use super::latency::SettlementLatency;
+use crate::quote::exact::CeilFee;
+use crate::utils::RetryBudget;
 
 const IDENTIFIER: &str = "WingRidersV2";
-const POOL_VALIDITY_POLICY: &str = "6fdc63a1d71dc2c65502b79baae7fb543185702b12c3c5fb639ed737";
+/// Validator swap math: the total fee is rounded up to whole units and deducted
+/// first.
+pub const SWAP_MATH: CeilFee = CeilFee;
+/// WingRidersV2 agents usually settle in the block after the order.
+pub const SETTLEMENT: SettlementLatency = SettlementLatency::batched(40);
 const POOL_VALIDITY_ASSET: &str = "6fdc63a1d71dc2c65502b79baae7fb543185702b12c3c5fb639ed737.4c";
-const POOL_VALIDITY_ASSET_JOINED: &str =
-    "6fdc63a1d71dc2c65502b79baae7fb543185702b12c3c5fb639ed7374c";
 const MIN_POOL_ADA: u64 = 3_000_000;
 
 pub struct WingRidersV2 {
     kupo: KupoApi,
+    filter: PoolFilter,
+    validity_asset: ValidityAsset,
 }
 
 impl WingRidersV2 {
-    pub fn new(kupo: KupoApi) -> Self {
-        Self { kupo }
+    pub fn new(kupo: impl Into<KupoApi>) -> Self {
+        Self {
+            kupo: kupo.into(),
+            filter: PoolFilter::default(),
+            validity_asset: ValidityAsset::new(POOL_VALIDITY_ASSET),
+        }
+    }
+
+    /// Replace the validity asset (see [`DexOverrides`]). The pool ids follow
+    /// the validity asset's policy.
+    pub fn with_overrides(mut self, overrides: &DexOverrides) -> Result<Self> {
+        overrides.validate(IDENTIFIER)?;
+        self.validity_asset = overrides.validity_asset_or(POOL_VALIDITY_ASSET);
+        Ok(self)
+    }
+
+    pub fn with_pool_filter(mut self, filter: PoolFilter) -> Self {
+        self.filter = filter;
+        self
     }
 }
 
@@ -36,11 +62,13 @@ impl WingRidersV2 {
 ///   [7]:  int   — ProjectFeeInBasis
 ///   [8]:  int   — ReserveFeeInBasis
 ///   [9]:  int   — FeeBasis (ignored)
-///   [10]: int   — AgentFee (ignored)
+///   [10]: int   — AgentFee (lovelace)
 ///   [11]: int   — LastInteraction (ignored)
 ///   [12]: int   — PoolAssetATreasury
 ///   [13]: int   — PoolAssetBTreasury
-///   [14..19]: misc (ignored)
+///   [14..17]: misc (ignored)
+///   [18]: Maybe Address — ProjectBeneficiary, paid the project fee
+///   [19]: Maybe Address — ReserveBeneficiary, paid the reserve fee
 ///   [20]: constr IF stable pool (has WingRidersV2Special) → skip
 ///
 /// Fee = (SwapFee + ProtocolFee + ProjectFeeInBasis + ReserveFeeInBasis) / 100
@@ -49,10 +77,12 @@ struct WRV2Datum {
     protocol_fee: u64,
     project_fee: u64,
     reserve_fee: u64,
+    agent_fee: u64,
     treasury_a: u64,
     treasury_b: u64,
     project_treasury_a: u64,
     project_treasury_b: u64,
+    beneficiaries: Vec<PoolAdmin>,
     is_stable: bool,
 }
 
@@ -71,6 +101,7 @@ fn parse_pool_datum(cbor_hex: &str) -> Result<WRV2Datum> {
     let protocol_fee = value_to_u64(&fields[6])?;
     let project_fee = value_to_u64(&fields[7])?;
     let reserve_fee = value_to_u64(&fields[8])?;
+    let agent_fee = value_to_u64(&fields[10])?;
     let treasury_a = value_to_u64(&fields[12])?;
     let treasury_b = value_to_u64(&fields[13])?;
 
@@ -87,6 +118,16 @@ fn parse_pool_datum(cbor_hex: &str) -> Result<WRV2Datum> {
         0
     };
 
+    // Beneficiaries are informational: an unexpected shape leaves them out
+    // rather than failing the pool.
+    let beneficiaries = [(18, "project_beneficiary"), (19, "reserve_beneficiary")]
+        .into_iter()
+        .filter_map(|(i, role)| {
+            let address = maybe_value(fields.get(i)?).ok()??;
+            Some(PoolAdmin::single(role, address_payment_credential(address).ok()?))
+        })
+        .collect();
+
     // Stable pool detection: last constr field that is non-empty.
     let is_stable = fields.len() > 20 && is_nonempty_constr(&fields[20]);
 
@@ -95,10 +136,12 @@ fn parse_pool_datum(cbor_hex: &str) -> Result<WRV2Datum> {
         protocol_fee,
         project_fee,
         reserve_fee,
+        agent_fee,
         treasury_a,
         treasury_b,
         project_treasury_a,
         project_treasury_b,
+        beneficiaries,
         is_stable,
     })
 }
@@ -115,19 +158,27 @@ impl BaseDex for WingRidersV2 {
     }
 
     fn pool_address(&self) -> &str {
-        POOL_VALIDITY_ASSET
+        &self.validity_asset.pattern
     }
 
     fn lp_token_policy_id(&self) -> &str {
-        POOL_VALIDITY_POLICY
+        &self.validity_asset.policy_id
     }
 
     fn kupo(&self) -> &KupoApi {
         &self.kupo
     }
 
+    fn pool_filter(&self) -> &PoolFilter {
+        &self.filter
+    }
+
+    fn pool_match_patterns(&self) -> Vec<String> {
+        vec![self.validity_asset.pattern.clone()]
+    }
+
     async fn all_liquidity_pool_utxos(&self) -> Result<Vec<Utxo>> {
-        self.kupo.get(POOL_VALIDITY_ASSET, true).await
+        self.kupo.get(&self.validity_asset.pattern, true).await
     }
 
     async fn liquidity_pool_from_utxo(
@@ -135,98 +186,107 @@ impl BaseDex for WingRidersV2 {
         utxo: &Utxo,
         pool_id: &str,
     ) -> Result<Option<LiquidityPool>> {
-        if utxo.data_hash.is_none() {
+        if utxo.data_hash.is_none() && utxo.inline_datum.is_none() {
             return Ok(None);
         }
 
         let relevant: Vec<_> = utxo
             .amount
             .iter()
-            .filter(|a| !a.unit.starts_with(POOL_VALIDITY_POLICY))
+            .filter(|a| !a.unit.starts_with(&self.validity_asset.policy_id))
             .collect();
 
-        if relevant.len() < 2 {
+        let Some((unit_a, unit_b)) = reserve_pair(&relevant) else {
             return Ok(None);
-        }
+        };
 
         let pool_id = utxo
             .amount
             .iter()
             .find(|a| {
-                a.unit.starts_with(POOL_VALIDITY_POLICY)
-                    && a.unit != POOL_VALIDITY_ASSET_JOINED
+                a.unit.starts_with(&self.validity_asset.policy_id)
+                    && a.unit != self.validity_asset.unit
             })
             .map(|a| a.unit.clone())
             .unwrap_or_else(|| pool_id.to_string());
 
-        let a_idx = if relevant.len() == 2 { 0 } else { 1 };
-        let b_idx = if relevant.len() == 2 { 1 } else { 2 };
-
-        let a_unit = &relevant[a_idx].unit;
-        let b_unit = &relevant[b_idx].unit;
+        let a_unit = &unit_a.unit;
+        let b_unit = &unit_b.unit;
 
-        let raw_a = relevant[a_idx].quantity.parse::<u64>()?;
-        let raw_b = relevant[b_idx].quantity.parse::<u64>()?;
+        let raw_a = unit_a.quantity;
+        let raw_b = unit_b.quantity;
 
-        let reserve_a = if a_unit == "lovelace" { ada_reserve(raw_a) } else { raw_a };
-        let reserve_b = if b_unit == "lovelace" { ada_reserve(raw_b) } else { raw_b };
+        let reserve_a = if unit_a.is_lovelace() { ada_reserve(raw_a) } else { raw_a };
+        let reserve_b = if unit_b.is_lovelace() { ada_reserve(raw_b) } else { raw_b };
 
         let asset_a = from_identifier(a_unit, 0);
         let asset_b = from_identifier(b_unit, 0);
 
-        Ok(Some(LiquidityPool::new(
-            IDENTIFIER,
-            asset_a,
-            asset_b,
-            reserve_a,
-            reserve_b,
-            &utxo.address,
-            0.35,
-            &pool_id,
-        )))
+        Ok(Some(
+            LiquidityPool::new(
+                IDENTIFIER,
+                asset_a,
+                asset_b,
+                reserve_a,
+                reserve_b,
+                &utxo.address,
+                0.35,
+                &pool_id,
+            )
+            .observed_in(utxo),
+        ))
     }
 
     /// Fetch datum, detect stable pools (skip), update multi-component fee,
-    /// and subtract treasury from reserves.
-    async fn liquidity_pool_from_utxo_extend(
+    /// record the per-recipient fee split, and subtract treasury from reserves.
+    async fn liquidity_pool_from_utxo_extend_or_skip(
         &self,
         utxo: &Utxo,
         pool_id: &str,
-    ) -> Result<Option<LiquidityPool>> {
+    ) -> Result<Result<LiquidityPool, SkipReason>> {
         let mut pool = match self.liquidity_pool_from_utxo(utxo, pool_id).await? {
             Some(p) => p,
-            None => return Ok(None),
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
 
-        let data_hash = match &utxo.data_hash {
-            Some(h) => h,
-            None => return Ok(None),
-        };
-
-        let datum = match &utxo.inline_datum {
-            Some(d) => d.clone(),
-            None => self.kupo.datum(data_hash).await?,
+        let datum = match self.kupo.utxo_datum(utxo).await? {
+            Some(d) => d,
+            None => return Ok(Err(SkipReason::no_pool(utxo))),
         };
         let d = parse_pool_datum(&datum)?;
 
         // Skip stable pools (matches JS: returns undefined when WingRidersV2Special found)
         if d.is_stable {
-            return Ok(None);
+            return Ok(Err(SkipReason::StablePool));
         }
 
         pool.reserve_a = pool.reserve_a.saturating_sub(d.treasury_a).saturating_sub(d.project_treasury_a);
         pool.reserve_b = pool.reserve_b.saturating_sub(d.treasury_b).saturating_sub(d.project_treasury_b);
+        pool.set_extra("treasury_a", d.treasury_a);
+        pool.set_extra("treasury_b", d.treasury_b);
+        pool.set_extra("project_treasury_a", d.project_treasury_a);
+        pool.set_extra("project_treasury_b", d.project_treasury_b);
+        for beneficiary in d.beneficiaries {
+            pool.add_admin(beneficiary);
+        }
         pool.pool_fee_percent =
             (d.swap_fee + d.protocol_fee + d.project_fee + d.reserve_fee) as f64 / 100.0;
-
-        Ok(Some(pool))
+        pool.metadata.fee_breakdown = Some(FeeBreakdown {
+            lp_fee_percent: d.swap_fee as f64 / 100.0,
+            protocol_fee_percent: d.protocol_fee as f64 / 100.0,
+            project_fee_percent: d.project_fee as f64 / 100.0,
+            reserve_fee_percent: d.reserve_fee as f64 / 100.0,
+            agent_fee_lovelace: Some(d.agent_fee),
+        });
+
+        Ok(self.filter_pool_or_skip(pool))
     }
 
     async fn liquidity_pool_from_pool_id(&self, pool_id: &str) -> Result<Option<LiquidityPool>> {
-        let full_id = if pool_id.starts_with(POOL_VALIDITY_POLICY) {
+        let full_id = if pool_id.starts_with(&self.validity_asset.policy_id) {
             pool_id.to_string()
         } else {
-            format!("{}{}", POOL_VALIDITY_POLICY, pool_id)
+            format!("{}{}", self.validity_asset.policy_id, pool_id)
         };
 
         let utxos = self.all_liquidity_pool_utxos().await?;
@@ -242,36 +302,91 @@ impl BaseDex for WingRidersV2 {
 
     async fn liquidity_pools_from_token(
         &self,
-        token_b: &str,
-        token_a: &str,
+        token_b: AssetId,
+        token_a: AssetId,
     ) -> Result<Vec<LiquidityPool>> {
-        let all_utxos = self.all_liquidity_pool_utxos().await?;
-        let mut pools = Vec::new();
-
-        for utxo in &all_utxos {
-            let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
-                Some(p) => p,
-                None => continue,
-            };
-
-            let id_a = token_identifier(&base.asset_a);
-            let id_b = token_identifier(&base.asset_b);
-
-            let matches = (id_a == token_a && id_b == token_b)
-                || (id_a == token_b && id_b == token_a);
-
-            if matches {
-                match self
-                    .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
-                    .await
-                {
-                    Ok(Some(p)) => pools.push(p),
-                    Ok(None) => {}
-                    Err(e) => eprintln!("[wingriders_v2] datum error {}: {}", utxo.tx_hash, e),
+        RetryBudget::ensure_scope(async {
+            let all_utxos = self.all_liquidity_pool_utxos().await?;
+            let mut pools = Vec::new();
+
+            for utxo in &all_utxos {
+                let base = match self.liquidity_pool_from_utxo(utxo, "").await? {
+                    Some(p) => p,
+                    None => continue,
+                };
+
+                let id_a = AssetId::from(&base.asset_a);
+                let id_b = AssetId::from(&base.asset_b);
+
+                let matches = (id_a == token_a && id_b == token_b)
+                    || (id_a == token_b && id_b == token_a);
+
+                if matches {
+                    match self
+                        .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
+                        .await
+                    {
+                        Ok(Some(p)) => pools.push(p),
+                        Ok(None) => {}
+                        Err(e) => eprintln!("[wingriders_v2] datum error {}: {}", utxo.tx_hash, e),
+                    }
                 }
             }
-        }
 
-        Ok(pools)
+            Ok(pools)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ciborium::value::Value;
+    use std::collections::HashMap;
+
+    fn constr(index: u64, fields: Vec<Value>) -> Value {
+        Value::Tag(121 + index, Box::new(Value::Array(fields)))
+    }
+
+    #[tokio::test]
+    async fn test_pool_from_raw_output() {
+        let int = |n: u64| Value::Integer(n.into());
+        let bytes = |h: &str| Value::Bytes(hex::decode(h).unwrap());
+        let mut fields = vec![
+            Value::Bytes(vec![0xab; 28]),
+            bytes(""),
+            bytes(""),
+            bytes("279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f"),
+            bytes("534e454b"),
+        ];
+        // Swap, protocol, project and reserve fee, fee basis, agent fee,
+        // last interaction, treasuries A/B, project treasuries A/B, misc.
+        for n in [30, 5, 0, 0, 10_000, 2_000_000, 0, 1_000, 500, 0, 0, 0, 0] {
+            fields.push(int(n));
+        }
+        fields.extend([constr(1, vec![]), constr(1, vec![]), constr(0, vec![])]);
+        let mut cbor = Vec::new();
+        ciborium::ser::into_writer(&constr(0, fields), &mut cbor).unwrap();
+
+        let lp = format!("{}{}", &POOL_VALIDITY_ASSET[..56], "aa".repeat(32));
+        let value = HashMap::from([
+            ("lovelace".to_string(), 103_000_000),
+            ("279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b".to_string(), 7_000),
+            (POOL_VALIDITY_ASSET.to_string(), 1),
+            (lp.clone(), 1),
+        ]);
+
+        // Inline datum, no datum hash: decoded without asking the Kupo.
+        let dex = WingRidersV2::new(KupoApi::new("http://127.0.0.1:9"));
+        let pool = dex
+            .liquidity_pool_from_raw_output("addr1pool", &value, Some(&hex::encode(cbor)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pool.pool_id, lp);
+        assert_eq!((pool.reserve_a, pool.reserve_b), (99_999_000, 6_500));
+        assert!((pool.pool_fee_percent - 0.35).abs() < 1e-9);
+        assert_eq!(pool.metadata.fee_breakdown.unwrap().agent_fee_lovelace, Some(2_000_000));
     }
 }
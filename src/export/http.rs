@@ -0,0 +1,174 @@
+use super::{ExportSink, PoolExport, StablePoolExport};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::io::Write;
+
+const DEFAULT_BATCH_SIZE: usize = 500;
+const DEFAULT_RETRIES: u32 = 5;
+const RETRY_BASE_DELAY_MS: u64 = 1000;
+
+/// One POST body. `source` lets a central ingester tell regional exporters apart.
+#[derive(Serialize)]
+struct WebhookBatch<'a, T: Serialize> {
+    source: &'a str,
+    batch: usize,
+    batches: usize,
+    pools: &'a [T],
+}
+
+/// POSTs pool dumps to a webhook in batches, gzip-compressed, with retries.
+///
+/// Each request carries `Content-Type: application/json` and, when gzip is on,
+/// `Content-Encoding: gzip`. Non-2xx responses are retried with exponential backoff.
+pub struct HttpPostSink {
+    url: String,
+    client: reqwest::Client,
+    source: String,
+    batch_size: usize,
+    gzip: bool,
+    retries: u32,
+    bearer_token: Option<String>,
+}
+
+impl HttpPostSink {
+    pub fn new(url: &str) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self {
+            url: url.to_string(),
+            client,
+            source: String::new(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            gzip: true,
+            retries: DEFAULT_RETRIES,
+            bearer_token: None,
+        }
+    }
+
+    /// Label sent with every batch (e.g. the regional Kupo node name).
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = source.to_string();
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn with_gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn with_bearer_token(mut self, token: &str) -> Self {
+        self.bearer_token = Some(token.to_string());
+        self
+    }
+
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn encode<T: Serialize>(&self, batch: &WebhookBatch<'_, T>) -> Result<Vec<u8>> {
+        let json = serde_json::to_vec(batch)?;
+        if !self.gzip {
+            return Ok(json);
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&json)?;
+        Ok(encoder.finish()?)
+    }
+
+    async fn post(&self, body: Vec<u8>) -> Result<()> {
+        crate::utils::retry(self.retries, RETRY_BASE_DELAY_MS, || {
+            let body = body.clone();
+            async move {
+                let mut request = self
+                    .client
+                    .post(&self.url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .body(body);
+                if self.gzip {
+                    request = request.header(reqwest::header::CONTENT_ENCODING, "gzip");
+                }
+                if let Some(token) = &self.bearer_token {
+                    request = request.bearer_auth(token);
+                }
+                let response = request.send().await?;
+                let status = response.status();
+                if !status.is_success() {
+                    return Err(anyhow!("webhook {} returned {}", self.url, status));
+                }
+                Ok(())
+            }
+        })
+        .await
+    }
+
+    async fn send_batches<T: Serialize + Sync>(&self, rows: &[T]) -> Result<()> {
+        let batches = rows.len().div_ceil(self.batch_size).max(1);
+        for batch in 0..batches {
+            let start = batch * self.batch_size;
+            let end = (start + self.batch_size).min(rows.len());
+            let body = self.encode(&WebhookBatch {
+                source: &self.source,
+                batch,
+                batches,
+                pools: &rows[start..end],
+            })?;
+            self.post(body).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExportSink for HttpPostSink {
+    async fn write_pools(&self, pools: &[PoolExport]) -> Result<()> {
+        self.send_batches(pools).await
+    }
+
+    async fn write_stable_pool(&self, pool: &StablePoolExport) -> Result<()> {
+        self.send_batches(std::slice::from_ref(pool)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_encode_gzip_round_trip() {
+        let sink = HttpPostSink::new("http://localhost:9999/ingest").with_source("eu-1");
+        let rows = vec!["a".to_string(), "b".to_string()];
+        let body = sink
+            .encode(&WebhookBatch {
+                source: &sink.source,
+                batch: 0,
+                batches: 1,
+                pools: &rows,
+            })
+            .unwrap();
+
+        let mut json = String::new();
+        GzDecoder::new(&body[..]).read_to_string(&mut json).unwrap();
+        assert_eq!(
+            json,
+            r#"{"source":"eu-1","batch":0,"batches":1,"pools":["a","b"]}"#
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Pool export rows and pluggable writers.
+//!
+//! `PoolExport` is the flat, string-quantity row format written by the CLI
+//! (`pools_rs.json`). Rows are handed to an [`ExportSink`], so long-running
+//! services can push pool dumps anywhere without wrapping the CLI;
+//! [`export_all`] and [`fetch_pair`] run the same orchestration as the CLI.
+//!
+//! Sinks: [`JsonFileSink`], [`StdoutSink`], [`NdjsonStdoutSink`] and
+//! [`HttpPostSink`]; `SqliteSink` and `ParquetSink` with the `sqlite` and
+//! `parquet` features.
+
+pub mod http;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod pipeline;
+pub mod sink;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use http::HttpPostSink;
+#[cfg(feature = "parquet")]
+pub use parquet::ParquetSink;
+pub use pipeline::{export_all, fetch_pair, ExportOptions, ExportRun};
+pub use sink::{Compression, JsonFileSink, NdjsonStdoutSink, StdoutSink};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSink;
+
+use crate::models::asset::{from_identifier, token_identifier};
+use crate::models::{LiquidityPool, StablePool};
+use crate::quote::PoolQuote;
+use crate::verification::TokenVerifier;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolExport {
+    pub dex: String,
+    pub pool_id: String,
+    pub asset_a: String,
+    pub asset_b: String,
+    pub reserve_a: String,
+    pub reserve_b: String,
+    pub pool_fee_percent: f64,
+    pub total_lp_tokens: String,
+    pub tx_hash: String,
+    pub slot: Option<u64>,
+    pub block_hash: Option<String>,
+    pub observed_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_pool_id: Option<String>,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub extras: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    /// Slot the pool was created in, when resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_slot: Option<u64>,
+    /// Prices, TVL and notional price impact; set by pair queries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote: Option<PoolQuote>,
+}
+
+/// A stable pool row. `asset_a`/`reserve_a` and `asset_b`/`reserve_b` are the
+/// first two balances; `assets` lists every balance, so pools with three or
+/// more assets export all of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StablePoolExport {
+    pub dex: String,
+    pub pool_id: String,
+    pub asset_a: String,
+    pub asset_b: String,
+    pub reserve_a: String,
+    pub reserve_b: String,
+    /// `[asset, reserve]` of every balance, in datum order.
+    #[serde(default)]
+    pub assets: Vec<(String, String)>,
+    pub pool_fee_percent: f64,
+    pub amplification_coefficient: String,
+    pub total_liquidity: String,
+}
+
+pub fn pool_to_export(pool: &LiquidityPool) -> PoolExport {
+    let freshness = pool.metadata.freshness.as_ref();
+    PoolExport {
+        dex: pool.dex_identifier.clone(),
+        pool_id: pool.pool_id.clone(),
+        asset_a: token_identifier(&pool.asset_a),
+        asset_b: token_identifier(&pool.asset_b),
+        reserve_a: pool.reserve_a.to_string(),
+        reserve_b: pool.reserve_b.to_string(),
+        pool_fee_percent: pool.pool_fee_percent,
+        total_lp_tokens: pool.total_lp_tokens.to_string(),
+        tx_hash: freshness.map(|f| f.tx_hash.clone()).unwrap_or_default(),
+        slot: freshness.and_then(|f| f.slot),
+        block_hash: freshness.map(|f| f.block_hash.clone()),
+        observed_at: freshness.map(|f| f.observed_at),
+        protocol_pool_id: pool.metadata.protocol_pool_id.clone(),
+        extras: pool.metadata.extras.clone(),
+        verified: None,
+        created_slot: pool.metadata.created_slot,
+        quote: None,
+    }
+}
+
+pub fn stable_pool_to_export(pool: &StablePool) -> StablePoolExport {
+    StablePoolExport {
+        dex: pool.dex_identifier.clone(),
+        pool_id: pool.pool_id.clone(),
+        asset_a: token_identifier(pool.asset_a()),
+        asset_b: token_identifier(pool.asset_b()),
+        reserve_a: pool.reserve_a().to_string(),
+        reserve_b: pool.reserve_b().to_string(),
+        assets: pool
+            .assets()
+            .iter()
+            .map(|(token, reserve)| (token_identifier(token), reserve.to_string()))
+            .collect(),
+        pool_fee_percent: pool.pool_fee_percent,
+        amplification_coefficient: pool.amplification_coefficient.to_string(),
+        total_liquidity: pool.total_liquidity.to_string(),
+    }
+}
+
+/// Set `verified` on each row and warn about likely scam duplicates.
+pub fn mark_verified(exports: &mut [PoolExport], verifier: &TokenVerifier) {
+    for export in exports.iter_mut() {
+        export.verified = Some(verifier.is_pair_verified(&export.asset_a, &export.asset_b));
+        for id in [&export.asset_a, &export.asset_b] {
+            if let Some(original) = verifier.scam_duplicate_of(&from_identifier(id, 0)) {
+                eprintln!(
+                    "[verify] pool {}: {} reuses the ticker of {}",
+                    export.pool_id, id, original
+                );
+            }
+        }
+    }
+}
+
+/// Destination for exported pool rows.
+#[async_trait]
+pub trait ExportSink: Send + Sync {
+    /// Write one complete dump of pool rows.
+    async fn write_pools(&self, pools: &[PoolExport]) -> Result<()>;
+
+    /// Write a single stable pool. Defaults to an error for sinks that only handle AMM rows.
+    async fn write_stable_pool(&self, _pool: &StablePoolExport) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "stable pool export not supported by this sink"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+
+    #[test]
+    fn test_stable_pool_export_keeps_every_balance() {
+        let ids = [
+            "aa00000000000000000000000000000000000000000000000000000055534443",
+            "bb00000000000000000000000000000000000000000000000000000069555344",
+            "cc000000000000000000000000000000000000000000000000000000444a4544",
+        ];
+        let assets = ids
+            .iter()
+            .zip([100, 200, 300])
+            .map(|(id, reserve)| (from_identifier(id, 6), reserve))
+            .collect();
+        let pool = StablePool::new("MinswapStable", assets, "addr", "pool", 0.1, 10, 600).unwrap();
+
+        let row = stable_pool_to_export(&pool);
+        assert_eq!(
+            (row.asset_a.as_str(), row.reserve_a.as_str()),
+            (ids[0], "100")
+        );
+        assert_eq!(
+            (row.asset_b.as_str(), row.reserve_b.as_str()),
+            (ids[1], "200")
+        );
+        assert_eq!(
+            row.assets,
+            ids.iter()
+                .zip(["100", "200", "300"])
+                .map(|(id, reserve)| (id.to_string(), reserve.to_string()))
+                .collect::<Vec<_>>()
+        );
+    }
+}
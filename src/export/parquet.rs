@@ -0,0 +1,202 @@
+//! Parquet export sink (feature `parquet`).
+//!
+//! Each dump is one uncompressed Parquet file with a column per
+//! [`PoolExport`] field, replaced atomically like [`JsonFileSink`](super::JsonFileSink).
+//! Quantities stay strings; `extras`, `quote` and the stable pool `assets`
+//! are JSON strings.
+
+use super::sink::write_atomic;
+use super::{ExportSink, PoolExport, StablePoolExport};
+use ::parquet::arrow::ArrowWriter;
+use anyhow::Result;
+use arrow_array::{ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Writes pool dumps to a Parquet file.
+pub struct ParquetSink {
+    path: String,
+}
+
+type Column = (Field, ArrayRef);
+
+fn utf8<S: AsRef<str>>(name: &str, values: impl IntoIterator<Item = S>) -> Column {
+    let array: StringArray = values.into_iter().map(Some).collect();
+    (Field::new(name, DataType::Utf8, false), Arc::new(array))
+}
+
+fn nullable_utf8<S: AsRef<str>>(name: &str, values: impl IntoIterator<Item = Option<S>>) -> Column {
+    let array: StringArray = values.into_iter().collect();
+    (Field::new(name, DataType::Utf8, true), Arc::new(array))
+}
+
+fn nullable_u64(name: &str, values: impl IntoIterator<Item = Option<u64>>) -> Column {
+    let array: UInt64Array = values.into_iter().collect();
+    (Field::new(name, DataType::UInt64, true), Arc::new(array))
+}
+
+fn f64s(name: &str, values: impl IntoIterator<Item = f64>) -> Column {
+    let array: Float64Array = values.into_iter().map(Some).collect();
+    (Field::new(name, DataType::Float64, false), Arc::new(array))
+}
+
+fn encode(columns: Vec<Column>) -> Result<Vec<u8>> {
+    let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = columns.into_iter().unzip();
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+    let mut writer = ArrowWriter::try_new(Vec::new(), schema, None)?;
+    writer.write(&batch)?;
+    Ok(writer.into_inner()?)
+}
+
+impl ParquetSink {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl ExportSink for ParquetSink {
+    async fn write_pools(&self, pools: &[PoolExport]) -> Result<()> {
+        let extras = pools
+            .iter()
+            .map(|p| {
+                (!p.extras.is_null())
+                    .then(|| serde_json::to_string(&p.extras))
+                    .transpose()
+            })
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        let quotes = pools
+            .iter()
+            .map(|p| p.quote.as_ref().map(serde_json::to_string).transpose())
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        let verified: BooleanArray = pools.iter().map(|p| p.verified).collect();
+        let bytes = encode(vec![
+            utf8("dex", pools.iter().map(|p| &p.dex)),
+            utf8("pool_id", pools.iter().map(|p| &p.pool_id)),
+            utf8("asset_a", pools.iter().map(|p| &p.asset_a)),
+            utf8("asset_b", pools.iter().map(|p| &p.asset_b)),
+            utf8("reserve_a", pools.iter().map(|p| &p.reserve_a)),
+            utf8("reserve_b", pools.iter().map(|p| &p.reserve_b)),
+            f64s("pool_fee_percent", pools.iter().map(|p| p.pool_fee_percent)),
+            utf8("total_lp_tokens", pools.iter().map(|p| &p.total_lp_tokens)),
+            utf8("tx_hash", pools.iter().map(|p| &p.tx_hash)),
+            nullable_u64("slot", pools.iter().map(|p| p.slot)),
+            nullable_utf8("block_hash", pools.iter().map(|p| p.block_hash.as_ref())),
+            nullable_u64("observed_at", pools.iter().map(|p| p.observed_at)),
+            nullable_utf8(
+                "protocol_pool_id",
+                pools.iter().map(|p| p.protocol_pool_id.as_ref()),
+            ),
+            nullable_utf8("extras", extras),
+            (
+                Field::new("verified", DataType::Boolean, true),
+                Arc::new(verified),
+            ),
+            nullable_u64("created_slot", pools.iter().map(|p| p.created_slot)),
+            nullable_utf8("quote", quotes),
+        ])?;
+        write_atomic(&self.path, bytes).await
+    }
+
+    async fn write_stable_pool(&self, pool: &StablePoolExport) -> Result<()> {
+        let bytes = encode(vec![
+            utf8("dex", [&pool.dex]),
+            utf8("pool_id", [&pool.pool_id]),
+            utf8("asset_a", [&pool.asset_a]),
+            utf8("asset_b", [&pool.asset_b]),
+            utf8("reserve_a", [&pool.reserve_a]),
+            utf8("reserve_b", [&pool.reserve_b]),
+            utf8("assets", [serde_json::to_string(&pool.assets)?]),
+            f64s("pool_fee_percent", [pool.pool_fee_percent]),
+            utf8(
+                "amplification_coefficient",
+                [&pool.amplification_coefficient],
+            ),
+            utf8("total_liquidity", [&pool.total_liquidity]),
+        ])?;
+        write_atomic(&self.path, bytes).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sink::tests::{sample_rows, sample_stable_pool};
+    use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use arrow_array::Array;
+
+    fn read(path: &std::path::Path) -> RecordBatch {
+        let file = std::fs::File::open(path).unwrap();
+        let mut reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        reader.next().unwrap().unwrap()
+    }
+
+    fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> &'a T {
+        batch
+            .column_by_name(name)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<T>()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_parquet_round_trip() {
+        let dir = std::env::temp_dir().join(format!("dexter-parquet-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let rows = sample_rows();
+
+        let path = dir.join("pools.parquet");
+        ParquetSink::new(path.to_str().unwrap())
+            .write_pools(&rows)
+            .await
+            .unwrap();
+        let batch = read(&path);
+        assert_eq!(batch.num_rows(), rows.len());
+        assert_eq!(batch.num_columns(), 17);
+        let pool_ids = column::<StringArray>(&batch, "pool_id");
+        let slots = column::<UInt64Array>(&batch, "slot");
+        let extras = column::<StringArray>(&batch, "extras");
+        for (i, row) in rows.iter().enumerate() {
+            assert_eq!(pool_ids.value(i), row.pool_id);
+            assert_eq!(slots.is_null(i), row.slot.is_none());
+            if let Some(slot) = row.slot {
+                assert_eq!(slots.value(i), slot);
+            }
+            assert_eq!(extras.is_null(i), row.extras.is_null());
+        }
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(extras.value(0)).unwrap(),
+            rows[0].extras
+        );
+
+        let stable = sample_stable_pool();
+        let path = dir.join("stable.parquet");
+        ParquetSink::new(path.to_str().unwrap())
+            .write_stable_pool(&stable)
+            .await
+            .unwrap();
+        let batch = read(&path);
+        assert_eq!(batch.num_rows(), 1);
+        assert_eq!(
+            column::<StringArray>(&batch, "amplification_coefficient").value(0),
+            stable.amplification_coefficient
+        );
+        let assets: Vec<(String, String)> =
+            serde_json::from_str(column::<StringArray>(&batch, "assets").value(0)).unwrap();
+        assert_eq!(assets, stable.assets);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
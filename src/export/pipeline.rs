@@ -0,0 +1,362 @@
+//! Export orchestration: parse every pool UTXO of a DEX with adaptive
+//! concurrency, collect skip reasons, verify and hand the rows to sinks.
+
+use super::{mark_verified, pool_to_export, ExportSink, NdjsonStdoutSink, PoolExport};
+use crate::dex::{BaseDex, SkipReason, SkipReport};
+use crate::models::{DecimalsOverrides, LiquidityPool};
+use crate::quote::pool_quote;
+use crate::utils::adaptive::{AdaptiveConcurrency, Outcome};
+use crate::utils::RetryBudget;
+use crate::verification::TokenVerifier;
+use anyhow::Result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Starting export concurrency; the AIMD controller moves it within
+/// `[1, max_concurrency]`.
+pub const DEFAULT_CONCURRENCY: usize = 5;
+/// Default upper bound for the adaptive export concurrency.
+pub const DEFAULT_MAX_CONCURRENCY: usize = 64;
+
+/// Settings shared by [`export_all`] and [`fetch_pair`].
+pub struct ExportOptions {
+    verifier: Option<TokenVerifier>,
+    max_concurrency: usize,
+    sinks: Vec<Box<dyn ExportSink>>,
+    stream: Option<NdjsonStdoutSink>,
+    progress: bool,
+    notional: Option<f64>,
+    decimals: DecimalsOverrides,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            verifier: None,
+            max_concurrency: DEFAULT_MAX_CONCURRENCY,
+            sinks: Vec::new(),
+            stream: None,
+            progress: false,
+            notional: None,
+            decimals: DecimalsOverrides::default(),
+        }
+    }
+}
+
+impl ExportOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `verified` on every row and warn about scam duplicates.
+    pub fn with_verifier(mut self, verifier: TokenVerifier) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Add a destination for the complete, sorted dump.
+    pub fn with_sink(mut self, sink: Box<dyn ExportSink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Print each row as NDJSON as soon as it is parsed, before the dump is
+    /// sorted and written to the sinks.
+    pub fn with_stream(mut self, stream: NdjsonStdoutSink) -> Self {
+        self.stream = Some(stream);
+        self
+    }
+
+    /// Print a `[done/total]` progress line to stderr during [`export_all`].
+    pub fn with_progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Size, in whole units of asset A, of the trade whose price impact
+    /// [`fetch_pair`] quotes.
+    pub fn with_notional(mut self, notional: f64) -> Self {
+        self.notional = Some(notional);
+        self
+    }
+
+    /// Decimals of tokens Kupo has no metadata for, used by the pair quotes.
+    pub fn with_decimals_overrides(mut self, decimals: DecimalsOverrides) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    pub fn sinks(&self) -> &[Box<dyn ExportSink>] {
+        &self.sinks
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    pub fn verify(&self, rows: &mut [PoolExport]) {
+        if let Some(verifier) = &self.verifier {
+            mark_verified(rows, verifier);
+        }
+    }
+
+    /// Verified export rows of a pair query, each with its [`PoolQuote`](crate::quote::PoolQuote).
+    pub fn pair_rows(&self, pools: &[LiquidityPool]) -> Vec<PoolExport> {
+        let mut rows: Vec<PoolExport> = pools
+            .iter()
+            .map(|pool| {
+                let mut quoted = pool.clone();
+                self.decimals.apply_to_pool(&mut quoted);
+                PoolExport {
+                    quote: Some(pool_quote(&quoted, self.notional)),
+                    ..pool_to_export(pool)
+                }
+            })
+            .collect();
+        self.verify(&mut rows);
+        rows
+    }
+
+    /// Write `rows` to the stream (if any) and every sink.
+    pub async fn write_all(&self, rows: &[PoolExport]) -> Result<()> {
+        if let Some(stream) = &self.stream {
+            stream.write_pools(rows).await?;
+        }
+        self.write_sinks(rows).await
+    }
+
+    async fn write_sinks(&self, rows: &[PoolExport]) -> Result<()> {
+        for sink in &self.sinks {
+            sink.write_pools(rows).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of [`export_all`].
+#[derive(Debug)]
+pub struct ExportRun {
+    /// Exported rows, sorted by pool id and verified.
+    pub pools: Vec<PoolExport>,
+    /// Why the remaining UTXOs were not exported.
+    pub report: SkipReport,
+    /// Pool UTXOs found at the DEX's address.
+    pub total_utxos: usize,
+}
+
+impl ExportRun {
+    pub fn skipped(&self) -> usize {
+        self.total_utxos - self.pools.len()
+    }
+}
+
+/// Export every pool of `dex`: fetch its pool UTXOs, parse them with adaptive
+/// concurrency, then write the sorted rows to the sinks of `opts`.
+pub async fn export_all<D>(dex: Arc<D>, opts: &ExportOptions) -> Result<ExportRun>
+where
+    D: BaseDex + Send + Sync + ?Sized + 'static,
+{
+    let utxos = Arc::new(dex.all_liquidity_pool_utxos().await?);
+    let total = utxos.len();
+    if opts.progress {
+        eprintln!("Found {} UTXOs", total);
+    }
+
+    let limiter = Arc::new(AdaptiveConcurrency::new(
+        DEFAULT_CONCURRENCY.min(opts.max_concurrency),
+        1,
+        opts.max_concurrency,
+    ));
+    let done = Arc::new(AtomicUsize::new(0));
+    let skipped = Arc::new(AtomicUsize::new(0));
+    let mut handles = Vec::with_capacity(total);
+
+    for i in 0..total {
+        let dex = Arc::clone(&dex);
+        let utxos = Arc::clone(&utxos);
+        let limiter = Arc::clone(&limiter);
+        let done = Arc::clone(&done);
+        let skipped = Arc::clone(&skipped);
+        let progress = opts.progress;
+
+        let handle = tokio::spawn(async move {
+            let mut permit = limiter.acquire().await;
+            let utxo = &utxos[i];
+
+            let result: Result<PoolExport, SkipReason> = dex
+                .liquidity_pool_or_skip(utxo)
+                .await
+                .map(|pool| pool_to_export(&pool));
+            match &result {
+                Err(SkipReason::ParseError {
+                    stage,
+                    error,
+                    retryable,
+                }) => {
+                    eprintln!("\n[error] utxo {} {}: {}", utxo.tx_hash, stage, error);
+                    permit.set_outcome(if *retryable {
+                        Outcome::Overloaded
+                    } else {
+                        Outcome::Ignored
+                    });
+                }
+                _ => permit.set_outcome(Outcome::Success),
+            }
+            drop(permit);
+
+            let d = done.fetch_add(1, Ordering::Relaxed) + 1;
+            if result.is_err() {
+                skipped.fetch_add(1, Ordering::Relaxed);
+            }
+            if progress && (d.is_multiple_of(10) || d == total) {
+                let s = skipped.load(Ordering::Relaxed);
+                eprint!(
+                    "\r[{}/{}] pools={} skipped={} concurrency={}   ",
+                    d,
+                    total,
+                    d - s,
+                    s,
+                    limiter.limit()
+                );
+            }
+            result
+        });
+
+        handles.push(handle);
+    }
+
+    let mut pools: Vec<PoolExport> = Vec::new();
+    let mut report = SkipReport::new(dex.identifier());
+    for (i, handle) in handles.into_iter().enumerate() {
+        match handle.await {
+            Ok(Ok(mut pool)) => {
+                if let Some(stream) = &opts.stream {
+                    opts.verify(std::slice::from_mut(&mut pool));
+                    stream.write_row(&pool)?;
+                }
+                pools.push(pool);
+            }
+            Ok(Err(reason)) => report.push(&utxos[i], reason),
+            Err(e) => eprintln!("\n[error] utxo {} task: {}", utxos[i].tx_hash, e),
+        }
+    }
+    if opts.progress {
+        eprintln!();
+    }
+
+    pools.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+    if opts.is_streaming() {
+        opts.write_sinks(&pools).await?;
+    } else {
+        opts.verify(&mut pools);
+        opts.write_all(&pools).await?;
+    }
+
+    Ok(ExportRun {
+        pools,
+        report,
+        total_utxos: total,
+    })
+}
+
+/// Pools of `dex` trading `asset_a` against `asset_b` as verified, quoted
+/// export rows. The query's retries share one [`RetryBudget`] unless the
+/// caller scoped one already.
+pub async fn fetch_pair<D>(
+    dex: &D,
+    asset_a: &str,
+    asset_b: &str,
+    opts: &ExportOptions,
+) -> Result<Vec<PoolExport>>
+where
+    D: BaseDex + ?Sized,
+{
+    let pools = RetryBudget::ensure_scope(
+        dex.liquidity_pools_from_token(asset_b.into(), asset_a.into()),
+    )
+    .await?;
+    Ok(opts.pair_rows(&pools))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_options_verify_and_write() {
+        let opts = ExportOptions::new()
+            .with_verifier(TokenVerifier::default())
+            .with_max_concurrency(0);
+        assert_eq!(opts.max_concurrency, 1);
+        assert!(!opts.is_streaming());
+
+        let mut rows: Vec<PoolExport> = serde_json::from_value(serde_json::json!([{
+            "dex": "MinswapV2", "pool_id": "p", "asset_a": "lovelace", "asset_b": "lovelace",
+            "reserve_a": "1", "reserve_b": "1", "pool_fee_percent": 0.3,
+            "total_lp_tokens": "1", "tx_hash": "t", "slot": null, "block_hash": null,
+            "observed_at": null
+        }]))
+        .unwrap();
+        opts.verify(&mut rows);
+        assert_eq!(rows[0].verified, Some(true));
+        // No sinks: writing is a no-op.
+        opts.write_all(&rows).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pair_query_retries_share_one_budget() {
+        use crate::dex::minswap_v2::MinswapV2;
+        use crate::kupo::test_server::{self, Response};
+        use crate::kupo::KupoApi;
+
+        const MINSWAP_V2: &str = "f5808c2c990d86da54bfc97d89cee6efa20cd8461616359478d96b4c";
+        const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f.534e454b";
+        // Four ADA/SNEK pools whose datums Kupo keeps failing to serve.
+        let pools: Vec<_> = (0..4)
+            .map(|i| {
+                serde_json::json!({
+                    "address": "addr1pool",
+                    "transaction_id": "ab".repeat(32),
+                    "output_index": i,
+                    "datum_hash": format!("{:02x}", i).repeat(32),
+                    "datum_type": "hash",
+                    "created_at": { "slot_no": 100, "header_hash": "ef".repeat(32) },
+                    "value": {
+                        "coins": 5_000_000,
+                        "assets": {
+                            SNEK: 7_000,
+                            format!("{}.4d5350", MINSWAP_V2): 1,
+                            format!("{}.{:02x}", MINSWAP_V2, i): 1,
+                        }
+                    }
+                })
+            })
+            .collect();
+        let datum_requests = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::clone(&datum_requests);
+        let url = test_server::serve(move |request| {
+            if request.path.starts_with("/datums/") {
+                seen.fetch_add(1, Ordering::SeqCst);
+                return Response::status(503);
+            }
+            Response::json(&serde_json::json!(pools))
+        })
+        .await;
+
+        let dex = MinswapV2::new(KupoApi::new(&url));
+        let budget = RetryBudget::new(1);
+        let rows = budget
+            .scope(fetch_pair(&dex, "lovelace", SNEK, &ExportOptions::new()))
+            .await
+            .unwrap();
+        assert!(rows.is_empty());
+        // One attempt per pool plus the single budgeted retry, not 10 per pool.
+        assert_eq!(datum_requests.load(Ordering::SeqCst), 5);
+        assert!(budget.is_exhausted());
+    }
+}
@@ -0,0 +1,389 @@
+use super::{ExportSink, PoolExport, StablePoolExport};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Encoding of a [`JsonFileSink`] dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// Pretty-printed JSON.
+    #[default]
+    None,
+    /// Compact JSON, gzip-compressed (`.gz`).
+    Gzip,
+    /// Compact JSON, zstd-compressed (`.zst`).
+    Zstd,
+}
+
+impl Compression {
+    /// Infer the encoding from the file extension: `.gz` → gzip, `.zst` → zstd.
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// Parse a CLI value: `none`, `gzip`/`gz` or `zstd`/`zst`.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Some(Compression::None),
+            "gzip" | "gz" => Some(Compression::Gzip),
+            "zstd" | "zst" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// File extension appended to the plain JSON path, e.g. `pools_rs.json.zst`.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
+
+    /// Serialize `value` in this encoding.
+    pub fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        Ok(match self {
+            Compression::None => serde_json::to_vec_pretty(value)?,
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                serde_json::to_writer(&mut encoder, value)?;
+                encoder.finish()?
+            }
+            Compression::Zstd => {
+                let mut encoder = zstd::Encoder::new(Vec::new(), 0)?;
+                serde_json::to_writer(&mut encoder, value)?;
+                encoder.finish()?
+            }
+        })
+    }
+}
+
+/// Write `bytes` to a temporary file next to `path` and rename it into place,
+/// so readers see either the previous file or the complete new one.
+pub async fn write_atomic(path: &str, bytes: Vec<u8>) -> Result<()> {
+    let target = PathBuf::from(path);
+    let mut tmp = target.clone().into_os_string();
+    tmp.push(format!(".tmp-{}", std::process::id()));
+    let tmp = PathBuf::from(tmp);
+    let result = tokio::task::spawn_blocking({
+        let tmp = tmp.clone();
+        move || -> std::io::Result<()> {
+            let mut file = std::fs::File::create(&tmp)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+            std::fs::rename(&tmp, &target)
+        }
+    })
+    .await?;
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(anyhow!("Failed to write export to {}: {}", path, e));
+    }
+    Ok(())
+}
+
+/// Writes the whole dump to a file (the CLI default, `pools_rs.json`).
+///
+/// The file is replaced atomically. Paths ending in `.gz` or `.zst` are
+/// written compressed unless [`with_compression`](Self::with_compression)
+/// says otherwise.
+pub struct JsonFileSink {
+    path: String,
+    compression: Compression,
+}
+
+impl JsonFileSink {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            compression: Compression::from_path(path),
+        }
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    async fn write<T: Serialize + ?Sized>(&self, value: &T) -> Result<()> {
+        write_atomic(&self.path, self.compression.encode(value)?).await
+    }
+}
+
+#[async_trait]
+impl ExportSink for JsonFileSink {
+    async fn write_pools(&self, pools: &[PoolExport]) -> Result<()> {
+        self.write(pools).await
+    }
+
+    async fn write_stable_pool(&self, pool: &StablePoolExport) -> Result<()> {
+        self.write(pool).await
+    }
+}
+
+/// Prints pretty JSON to stdout.
+#[derive(Default)]
+pub struct StdoutSink;
+
+impl StdoutSink {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl ExportSink for StdoutSink {
+    async fn write_pools(&self, pools: &[PoolExport]) -> Result<()> {
+        println!("{}", serde_json::to_string_pretty(pools)?);
+        Ok(())
+    }
+
+    async fn write_stable_pool(&self, pool: &StablePoolExport) -> Result<()> {
+        println!("{}", serde_json::to_string_pretty(pool)?);
+        Ok(())
+    }
+}
+
+/// Writes one compact JSON row per line (NDJSON) to stdout, flushing after
+/// every row so the dump can be piped into `jq` or a loader while it is
+/// produced.
+#[derive(Default)]
+pub struct NdjsonStdoutSink;
+
+impl NdjsonStdoutSink {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Print a single row.
+    pub fn write_row<T: Serialize + ?Sized>(&self, row: &T) -> Result<()> {
+        write_ndjson_row(&mut std::io::stdout().lock(), row)
+    }
+}
+
+fn write_ndjson_row<W: Write, T: Serialize + ?Sized>(out: &mut W, row: &T) -> Result<()> {
+    serde_json::to_writer(&mut *out, row)?;
+    out.write_all(b"\n")?;
+    out.flush()?;
+    Ok(())
+}
+
+#[async_trait]
+impl ExportSink for NdjsonStdoutSink {
+    async fn write_pools(&self, pools: &[PoolExport]) -> Result<()> {
+        pools.iter().try_for_each(|pool| self.write_row(pool))
+    }
+
+    async fn write_stable_pool(&self, pool: &StablePoolExport) -> Result<()> {
+        self.write_row(pool)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::quote::PoolQuote;
+    use std::io::Read;
+
+    /// Two rows: one with every optional field set, one with none.
+    pub(crate) fn sample_rows() -> Vec<PoolExport> {
+        let full = PoolExport {
+            dex: "MinswapV2".to_string(),
+            pool_id: "f5808c2c990d86da54bfc97d89cee6efa20cd8461616359478d96b4c".to_string(),
+            asset_a: "lovelace".to_string(),
+            asset_b: "29d222ce763455e3d7a09a665ce554f00ac89d2e99a1a83d267170c64d494e".to_string(),
+            reserve_a: "123456789012".to_string(),
+            reserve_b: "98765432109876".to_string(),
+            pool_fee_percent: 0.3,
+            total_lp_tokens: "5000000000".to_string(),
+            tx_hash: "aa".repeat(32),
+            slot: Some(120_000_000),
+            block_hash: Some("bb".repeat(32)),
+            observed_at: Some(1_700_000_000),
+            protocol_pool_id: Some("0a1b".to_string()),
+            extras: serde_json::json!({ "fee_sharing": 1666 }),
+            verified: Some(true),
+            created_slot: Some(90_000_000),
+            quote: Some(PoolQuote {
+                price_a_in_b: 2.5,
+                price_b_in_a: 0.4,
+                decimals_adjusted: true,
+                tvl_lovelace: Some(246_913_578_024),
+                notional: None,
+                a_to_b: None,
+                b_to_a: None,
+            }),
+        };
+        let bare = PoolExport {
+            dex: "WingRiders".to_string(),
+            pool_id: "0c".repeat(28),
+            slot: None,
+            block_hash: None,
+            observed_at: None,
+            protocol_pool_id: None,
+            extras: serde_json::Value::Null,
+            verified: None,
+            created_slot: None,
+            quote: None,
+            ..full.clone()
+        };
+        vec![full, bare]
+    }
+
+    pub(crate) fn sample_stable_pool() -> StablePoolExport {
+        StablePoolExport {
+            dex: "MinswapStable".to_string(),
+            pool_id: "0d".repeat(28),
+            asset_a: "dd".repeat(28) + "444a4544",
+            asset_b: "ee".repeat(28) + "69555344",
+            reserve_a: "1000000000".to_string(),
+            reserve_b: "999000000".to_string(),
+            assets: vec![
+                ("dd".repeat(28) + "444a4544", "1000000000".to_string()),
+                ("ee".repeat(28) + "69555344", "999000000".to_string()),
+                ("ff".repeat(28) + "5553444d", "998000000".to_string()),
+            ],
+            pool_fee_percent: 0.1,
+            amplification_coefficient: "10".to_string(),
+            total_liquidity: "1999000000".to_string(),
+        }
+    }
+
+    fn as_json<T: Serialize>(value: &T) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_json_file_sink_round_trip() {
+        let dir = std::env::temp_dir().join(format!("dexter-json-sink-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let rows = sample_rows();
+
+        for name in ["pools.json", "pools.json.gz", "pools.json.zst"] {
+            let path = dir.join(name);
+            let sink = JsonFileSink::new(path.to_str().unwrap());
+            sink.write_pools(&rows).await.unwrap();
+            let raw = std::fs::read(&path).unwrap();
+            let json = match sink.compression() {
+                Compression::None => raw,
+                Compression::Gzip => {
+                    let mut json = Vec::new();
+                    flate2::read::GzDecoder::new(&raw[..])
+                        .read_to_end(&mut json)
+                        .unwrap();
+                    json
+                }
+                Compression::Zstd => zstd::decode_all(&raw[..]).unwrap(),
+            };
+            let read: Vec<PoolExport> = serde_json::from_slice(&json).unwrap();
+            assert_eq!(as_json(&read), as_json(&rows));
+        }
+
+        let stable = sample_stable_pool();
+        let path = dir.join("stable.json");
+        JsonFileSink::new(path.to_str().unwrap())
+            .write_stable_pool(&stable)
+            .await
+            .unwrap();
+        let read: StablePoolExport =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(as_json(&read), as_json(&stable));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stdout_sinks() {
+        let rows = sample_rows();
+        StdoutSink::new().write_pools(&rows).await.unwrap();
+        StdoutSink::new()
+            .write_stable_pool(&sample_stable_pool())
+            .await
+            .unwrap();
+
+        let mut out = Vec::new();
+        for row in &rows {
+            write_ndjson_row(&mut out, row).unwrap();
+        }
+        let read: Vec<PoolExport> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(as_json(&read), as_json(&rows));
+    }
+
+    #[tokio::test]
+    async fn test_default_stable_pool_write_is_unsupported() {
+        struct PoolsOnly;
+
+        #[async_trait]
+        impl ExportSink for PoolsOnly {
+            async fn write_pools(&self, _pools: &[PoolExport]) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        assert!(PoolsOnly.write_pools(&sample_rows()).await.is_ok());
+        assert!(PoolsOnly
+            .write_stable_pool(&sample_stable_pool())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_compressed_atomic_write() {
+        let dir = std::env::temp_dir().join(format!("dexter-sink-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let rows = serde_json::json!([{ "pool_id": "abc", "reserve_a": "1000" }]);
+
+        for (name, compression) in [
+            ("pools.json", Compression::None),
+            ("pools.json.gz", Compression::Gzip),
+            ("pools.json.zst", Compression::Zstd),
+        ] {
+            let path = dir.join(name);
+            let path = path.to_str().unwrap();
+            assert_eq!(Compression::from_path(path), compression);
+            write_atomic(path, compression.encode(&rows).unwrap())
+                .await
+                .unwrap();
+
+            let raw = std::fs::read(path).unwrap();
+            let mut json = String::new();
+            match compression {
+                Compression::None => json = String::from_utf8(raw).unwrap(),
+                Compression::Gzip => {
+                    flate2::read::GzDecoder::new(&raw[..])
+                        .read_to_string(&mut json)
+                        .unwrap();
+                }
+                Compression::Zstd => {
+                    json = String::from_utf8(zstd::decode_all(&raw[..]).unwrap()).unwrap()
+                }
+            }
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+                rows
+            );
+        }
+        // Only the three dumps; no temp files left behind.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
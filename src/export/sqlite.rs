@@ -0,0 +1,286 @@
+//! SQLite export sink (feature `sqlite`).
+//!
+//! Every [`write_pools`](ExportSink::write_pools) replaces the contents of the
+//! `pools` table in one transaction, so readers see either the previous dump
+//! or the new one. Stable pools go to `stable_pools`, one row per
+//! `(dex, pool_id)`. `extras`, `quote` and the stable pool `assets` are
+//! stored as JSON text.
+
+use super::{ExportSink, PoolExport, StablePoolExport};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS pools (
+    dex TEXT NOT NULL,
+    pool_id TEXT NOT NULL,
+    asset_a TEXT NOT NULL,
+    asset_b TEXT NOT NULL,
+    reserve_a TEXT NOT NULL,
+    reserve_b TEXT NOT NULL,
+    pool_fee_percent REAL NOT NULL,
+    total_lp_tokens TEXT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    slot INTEGER,
+    block_hash TEXT,
+    observed_at INTEGER,
+    protocol_pool_id TEXT,
+    extras TEXT,
+    verified INTEGER,
+    created_slot INTEGER,
+    quote TEXT
+);
+CREATE TABLE IF NOT EXISTS stable_pools (
+    dex TEXT NOT NULL,
+    pool_id TEXT NOT NULL,
+    asset_a TEXT NOT NULL,
+    asset_b TEXT NOT NULL,
+    reserve_a TEXT NOT NULL,
+    reserve_b TEXT NOT NULL,
+    assets TEXT NOT NULL,
+    pool_fee_percent REAL NOT NULL,
+    amplification_coefficient TEXT NOT NULL,
+    total_liquidity TEXT NOT NULL,
+    PRIMARY KEY (dex, pool_id)
+);
+";
+
+/// Writes pool dumps into a SQLite database file.
+pub struct SqliteSink {
+    path: String,
+}
+
+impl SqliteSink {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Open the database and create the tables if needed, then run `f` on a
+    /// blocking thread.
+    async fn with_connection<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = Connection::open(&path)?;
+            conn.execute_batch(SCHEMA)?;
+            f(&mut conn)
+        })
+        .await?
+        .map_err(|e| anyhow!("SQLite export to {} failed: {}", self.path, e))
+    }
+
+    /// Read back the rows of the last [`write_pools`](ExportSink::write_pools).
+    pub async fn read_pools(&self) -> Result<Vec<PoolExport>> {
+        let rows = self
+            .with_connection(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT dex, pool_id, asset_a, asset_b, reserve_a, reserve_b,
+                            pool_fee_percent, total_lp_tokens, tx_hash, slot, block_hash,
+                            observed_at, protocol_pool_id, extras, verified, created_slot, quote
+                     FROM pools ORDER BY rowid",
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    Ok((
+                        PoolExport {
+                            dex: row.get(0)?,
+                            pool_id: row.get(1)?,
+                            asset_a: row.get(2)?,
+                            asset_b: row.get(3)?,
+                            reserve_a: row.get(4)?,
+                            reserve_b: row.get(5)?,
+                            pool_fee_percent: row.get(6)?,
+                            total_lp_tokens: row.get(7)?,
+                            tx_hash: row.get(8)?,
+                            slot: row.get(9)?,
+                            block_hash: row.get(10)?,
+                            observed_at: row.get(11)?,
+                            protocol_pool_id: row.get(12)?,
+                            extras: serde_json::Value::Null,
+                            verified: row.get(14)?,
+                            created_slot: row.get(15)?,
+                            quote: None,
+                        },
+                        row.get::<_, Option<String>>(13)?,
+                        row.get::<_, Option<String>>(16)?,
+                    ))
+                })?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .await?;
+        rows.into_iter()
+            .map(|(mut pool, extras, quote)| {
+                if let Some(extras) = extras {
+                    pool.extras = serde_json::from_str(&extras)?;
+                }
+                if let Some(quote) = quote {
+                    pool.quote = Some(serde_json::from_str(&quote)?);
+                }
+                Ok(pool)
+            })
+            .collect()
+    }
+
+    /// Read back one stable pool row.
+    pub async fn read_stable_pool(
+        &self,
+        dex: &str,
+        pool_id: &str,
+    ) -> Result<Option<StablePoolExport>> {
+        let (dex, pool_id) = (dex.to_string(), pool_id.to_string());
+        self.with_connection(move |conn| {
+            conn.query_row(
+                "SELECT dex, pool_id, asset_a, asset_b, reserve_a, reserve_b, assets,
+                        pool_fee_percent, amplification_coefficient, total_liquidity
+                 FROM stable_pools WHERE dex = ?1 AND pool_id = ?2",
+                params![dex, pool_id],
+                |row| {
+                    Ok((
+                        StablePoolExport {
+                            dex: row.get(0)?,
+                            pool_id: row.get(1)?,
+                            asset_a: row.get(2)?,
+                            asset_b: row.get(3)?,
+                            reserve_a: row.get(4)?,
+                            reserve_b: row.get(5)?,
+                            assets: Vec::new(),
+                            pool_fee_percent: row.get(7)?,
+                            amplification_coefficient: row.get(8)?,
+                            total_liquidity: row.get(9)?,
+                        },
+                        row.get::<_, String>(6)?,
+                    ))
+                },
+            )
+            .optional()
+        })
+        .await?
+        .map(|(mut pool, assets)| {
+            pool.assets = serde_json::from_str(&assets)?;
+            Ok(pool)
+        })
+        .transpose()
+    }
+}
+
+#[async_trait]
+impl ExportSink for SqliteSink {
+    async fn write_pools(&self, pools: &[PoolExport]) -> Result<()> {
+        let rows = pools
+            .iter()
+            .map(|pool| {
+                let extras = (!pool.extras.is_null())
+                    .then(|| serde_json::to_string(&pool.extras))
+                    .transpose()?;
+                let quote = pool.quote.as_ref().map(serde_json::to_string).transpose()?;
+                Ok((pool.clone(), extras, quote))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.with_connection(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute("DELETE FROM pools", [])?;
+            {
+                let mut insert = tx.prepare(
+                    "INSERT INTO pools VALUES
+                     (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+                )?;
+                for (pool, extras, quote) in &rows {
+                    insert.execute(params![
+                        pool.dex,
+                        pool.pool_id,
+                        pool.asset_a,
+                        pool.asset_b,
+                        pool.reserve_a,
+                        pool.reserve_b,
+                        pool.pool_fee_percent,
+                        pool.total_lp_tokens,
+                        pool.tx_hash,
+                        pool.slot,
+                        pool.block_hash,
+                        pool.observed_at,
+                        pool.protocol_pool_id,
+                        extras,
+                        pool.verified,
+                        pool.created_slot,
+                        quote,
+                    ])?;
+                }
+            }
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn write_stable_pool(&self, pool: &StablePoolExport) -> Result<()> {
+        let pool = pool.clone();
+        let assets = serde_json::to_string(&pool.assets)?;
+        self.with_connection(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO stable_pools VALUES
+                 (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    pool.dex,
+                    pool.pool_id,
+                    pool.asset_a,
+                    pool.asset_b,
+                    pool.reserve_a,
+                    pool.reserve_b,
+                    assets,
+                    pool.pool_fee_percent,
+                    pool.amplification_coefficient,
+                    pool.total_liquidity,
+                ],
+            )
+            .map(|_| ())
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::sink::tests::{sample_rows, sample_stable_pool};
+
+    #[tokio::test]
+    async fn test_sqlite_round_trip() {
+        let path = std::env::temp_dir().join(format!("dexter-sqlite-{}.db", std::process::id()));
+        let sink = SqliteSink::new(path.to_str().unwrap());
+
+        let rows = sample_rows();
+        sink.write_pools(&rows).await.unwrap();
+        let read = sink.read_pools().await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&read).unwrap(),
+            serde_json::to_value(&rows).unwrap()
+        );
+
+        // A second dump replaces the first.
+        sink.write_pools(&rows[..1]).await.unwrap();
+        assert_eq!(sink.read_pools().await.unwrap().len(), 1);
+
+        let stable = sample_stable_pool();
+        sink.write_stable_pool(&stable).await.unwrap();
+        sink.write_stable_pool(&stable).await.unwrap();
+        let read = sink
+            .read_stable_pool(&stable.dex, &stable.pool_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&read).unwrap(),
+            serde_json::to_value(&stable).unwrap()
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+//! Raw view of Kupo matches, for adding DEX support and debugging parse
+//! failures.
+//!
+//! Every matched output is listed with its value (asset names decoded where
+//! they are printable) and its datum, both as CBOR hex and as decoded
+//! [`PlutusData`] in the `cardano-cli` detailed JSON schema.
+
+use anyhow::Result;
+use futures::future::join_all;
+use serde::Serialize;
+
+use crate::kupo::KupoApi;
+use crate::models::Utxo;
+use crate::plutus::PlutusData;
+use crate::utils::normalize_address;
+
+const POLICY_ID_LEN: usize = 56;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InspectedAsset {
+    /// `lovelace` or policy id + asset name hex.
+    pub unit: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_name_hex: Option<String>,
+    /// The asset name as text, when it is printable UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_name: Option<String>,
+    pub quantity: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InspectedOutput {
+    /// `tx_hash#output_index`.
+    pub output_ref: String,
+    pub address: String,
+    pub slot: Option<u64>,
+    pub value: Vec<InspectedAsset>,
+    /// `hash` or `inline`, as reported by Kupo.
+    pub datum_type: Option<String>,
+    pub datum_hash: Option<String>,
+    pub datum_hex: Option<String>,
+    /// Decoded datum.
+    pub datum: Option<serde_json::Value>,
+    /// Why the datum could not be fetched or decoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub datum_error: Option<String>,
+    pub reference_script_hash: Option<String>,
+}
+
+/// Kupo pattern for `input`: a bare policy id matches every asset of the
+/// policy, a policy id + asset name hex (dotted or not) that asset; addresses
+/// and explicit patterns (`*`, `@`, `/`) are used as they are. An address in
+/// CBOR hex (`5839...`, as datums and transaction dumps carry it) is turned
+/// into bech32; raw address hex is not, it reads as a policy id + name.
+pub fn match_pattern(input: &str) -> String {
+    let input = input.trim();
+    let is_hex = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit());
+    if input.contains(['*', '@', '/']) {
+        return input.to_string();
+    }
+    if input.starts_with("58") && is_hex(input) {
+        let is_cbor_bytes = usize::from_str_radix(&input[2..4.min(input.len())], 16)
+            .is_ok_and(|len| input.len() == 4 + 2 * len);
+        if let Some(address) = is_cbor_bytes
+            .then(|| normalize_address(input).ok())
+            .flatten()
+        {
+            return address;
+        }
+    }
+    match input.split_once('.') {
+        Some((policy, name)) if policy.len() == POLICY_ID_LEN && is_hex(policy) => {
+            format!("{}.{}", policy, if name.is_empty() { "*" } else { name })
+        }
+        None if input.len() >= POLICY_ID_LEN && is_hex(input) => {
+            let (policy, name) = input.split_at(POLICY_ID_LEN);
+            format!("{}.{}", policy, if name.is_empty() { "*" } else { name })
+        }
+        _ => input.to_string(),
+    }
+}
+
+fn inspect_asset(unit: &str, quantity: &str) -> InspectedAsset {
+    if unit == "lovelace" || unit.len() < POLICY_ID_LEN {
+        return InspectedAsset {
+            unit: unit.to_string(),
+            policy_id: None,
+            asset_name_hex: None,
+            asset_name: None,
+            quantity: quantity.to_string(),
+        };
+    }
+    let unit = unit.replace('.', "");
+    let (policy, name_hex) = unit.split_at(POLICY_ID_LEN);
+    let asset_name = hex::decode(name_hex)
+        .ok()
+        .and_then(|b| String::from_utf8(b).ok())
+        .filter(|s| !s.is_empty() && !s.chars().any(char::is_control));
+    InspectedAsset {
+        unit: unit.clone(),
+        policy_id: Some(policy.to_string()),
+        asset_name_hex: Some(name_hex.to_string()),
+        asset_name,
+        quantity: quantity.to_string(),
+    }
+}
+
+/// Describe `utxo`; `datum` is its datum CBOR hex, or the error fetching it.
+pub fn inspect_utxo(utxo: &Utxo, datum: Result<Option<String>>) -> InspectedOutput {
+    let (datum_hex, decoded, datum_error) = match datum {
+        Ok(Some(hex)) => match PlutusData::from_cbor_hex(&hex) {
+            Ok(data) => (Some(hex), Some(data.to_json()), None),
+            Err(e) => (Some(hex), None, Some(e.to_string())),
+        },
+        Ok(None) => (None, None, None),
+        Err(e) => (None, None, Some(e.to_string())),
+    };
+    InspectedOutput {
+        output_ref: format!("{}#{}", utxo.tx_hash, utxo.output_index),
+        address: utxo.address.clone(),
+        slot: utxo.slot,
+        value: utxo
+            .amount
+            .iter()
+            .map(|u| inspect_asset(&u.unit, &u.quantity_str()))
+            .collect(),
+        datum_type: utxo.datum_type.clone(),
+        datum_hash: utxo.data_hash.clone(),
+        datum_hex,
+        datum: decoded,
+        datum_error,
+        reference_script_hash: utxo.reference_script_hash.clone(),
+    }
+}
+
+/// Outputs matching `input` (see [`match_pattern`]) with their datums.
+///
+/// Datums are fetched concurrently; a datum that cannot be fetched or decoded
+/// is reported in `datum_error` rather than failing the inspection.
+pub async fn inspect(kupo: &KupoApi, input: &str, unspent: bool) -> Result<Vec<InspectedOutput>> {
+    let utxos = kupo.get(&match_pattern(input), unspent).await?;
+    let datums = join_all(utxos.iter().map(|u| kupo.utxo_datum(u))).await;
+    Ok(utxos
+        .iter()
+        .zip(datums)
+        .map(|(utxo, datum)| inspect_utxo(utxo, datum))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    #[test]
+    fn test_match_pattern() {
+        assert_eq!(match_pattern("addr1xyz"), "addr1xyz");
+        assert_eq!(match_pattern(&SNEK[..56]), format!("{}.*", &SNEK[..56]));
+        assert_eq!(
+            match_pattern(SNEK),
+            format!("{}.{}", &SNEK[..56], &SNEK[56..])
+        );
+        assert_eq!(match_pattern("*@abcd"), "*@abcd");
+        // Enterprise script address of SNEK's policy, CBOR-wrapped.
+        let address = crate::utils::script_address(&SNEK[..56], crate::utils::Network::Mainnet);
+        let (_, bytes) = bech32::decode(address.as_ref().unwrap()).unwrap();
+        assert_eq!(
+            match_pattern(&format!("581d{}", hex::encode(bytes))),
+            address.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_inspect_utxo_decodes_value_and_datum() {
+        let value = HashMap::from([("lovelace".to_string(), 2_000_000), (SNEK.to_string(), 5)]);
+        let utxo = Utxo::from_raw_output("addr1xyz", &value, Some("d8799f0102ff"));
+        let inspected = inspect_utxo(&utxo, Ok(utxo.inline_datum.clone()));
+        assert_eq!(inspected.value[1].asset_name.as_deref(), Some("SNEK"));
+        assert_eq!(inspected.datum.as_ref().unwrap()["fields"][1]["int"], 2);
+
+        let broken = inspect_utxo(&utxo, Ok(Some("ff".to_string())));
+        assert_eq!(broken.datum_hex.as_deref(), Some("ff"));
+        assert!(broken.datum.is_none() && broken.datum_error.is_some());
+    }
+}
@@ -0,0 +1,224 @@
+//! Borrowed view of Kupo `/matches` entries, for bulk ingestion.
+//!
+//! A match response is deserialized straight from the response body:
+//! strings borrow from it (a `Cow` owns only an escaped JSON string, which
+//! addresses, hashes and units never are), quantities are parsed in place,
+//! and no intermediate `serde_json::Value` tree is built.
+//! [`MatchRef::to_utxo`] copies an entry out when it has to outlive the body;
+//! [`KupoApi::for_each_match`](super::KupoApi::for_each_match) hands entries
+//! to a callback without building `Utxo`s at all.
+
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::models::{Unit, Utxo};
+
+/// One entry of a `/matches` response, borrowing from the body.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct MatchRef<'a> {
+    #[serde(borrow)]
+    pub address: Cow<'a, str>,
+    #[serde(borrow)]
+    pub transaction_id: Cow<'a, str>,
+    pub output_index: u32,
+    #[serde(borrow)]
+    pub created_at: CreatedAtRef<'a>,
+    #[serde(borrow)]
+    pub value: ValueRef<'a>,
+    pub datum_hash: Option<&'a str>,
+    pub datum_type: Option<&'a str>,
+    pub script_hash: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct CreatedAtRef<'a> {
+    pub slot_no: Option<u64>,
+    #[serde(borrow)]
+    pub header_hash: Cow<'a, str>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ValueRef<'a> {
+    #[serde(deserialize_with = "quantity")]
+    pub coins: u64,
+    /// `(unit, quantity)` in response order; units as Kupo writes them
+    /// (`policy.name`).
+    #[serde(borrow, deserialize_with = "assets")]
+    pub assets: Vec<(Cow<'a, str>, u64)>,
+}
+
+impl MatchRef<'_> {
+    /// Quantity of `unit` (`lovelace`, or policy id + asset name hex, dotted
+    /// or not); 0 when absent.
+    pub fn quantity(&self, unit: &str) -> u64 {
+        if unit == "lovelace" {
+            return self.value.coins;
+        }
+        let undotted = |u: &str| {
+            u.bytes()
+                .filter(|b| *b != b'.')
+                .eq(unit.bytes().filter(|b| *b != b'.'))
+        };
+        self.value
+            .assets
+            .iter()
+            .find(|(u, _)| undotted(u))
+            .map_or(0, |(_, quantity)| *quantity)
+    }
+
+    /// Owned copy, as [`KupoApi::get`](super::KupoApi::get) returns it.
+    pub fn to_utxo(&self) -> Utxo {
+        let mut amount = Vec::with_capacity(1 + self.value.assets.len());
+        amount.push(Unit {
+            unit: "lovelace".to_string(),
+            quantity: self.value.coins,
+        });
+        amount.extend(self.value.assets.iter().map(|(unit, quantity)| Unit {
+            unit: unit.replace('.', ""),
+            quantity: *quantity,
+        }));
+        Utxo {
+            address: self.address.to_string(),
+            tx_hash: self.transaction_id.to_string(),
+            tx_index: self.output_index,
+            output_index: self.output_index,
+            amount,
+            block: self.created_at.header_hash.to_string(),
+            slot: self.created_at.slot_no,
+            data_hash: self.datum_hash.map(str::to_string),
+            inline_datum: None,
+            reference_script_hash: self.script_hash.map(str::to_string),
+            datum_type: self.datum_type.map(str::to_string),
+        }
+    }
+}
+
+/// Parse a `/matches` body: an array of entries, or a single entry.
+pub fn parse_matches(body: &str) -> serde_json::Result<Vec<MatchRef<'_>>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Body<'a> {
+        #[serde(borrow)]
+        Many(Vec<MatchRef<'a>>),
+        #[serde(borrow)]
+        One(MatchRef<'a>),
+    }
+    // Every Kupo response is an array; skip the buffering of the untagged
+    // fallback for it.
+    if body.trim_start().starts_with('[') {
+        return serde_json::from_str(body);
+    }
+    Ok(match serde_json::from_str(body)? {
+        Body::Many(matches) => matches,
+        Body::One(one) => vec![one],
+    })
+}
+
+struct QuantityVisitor;
+
+impl Visitor<'_> for QuantityVisitor {
+    type Value = u64;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a quantity as a number or numeric string")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<u64, E> {
+        Ok(v)
+    }
+
+    // Anything unparsable reads as 0, as for owned parsing.
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<u64, E> {
+        Ok(u64::try_from(v).unwrap_or(0))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<u64, E> {
+        Ok(v.parse().unwrap_or(0))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<u64, E> {
+        Ok(0)
+    }
+}
+
+fn quantity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    deserializer.deserialize_any(QuantityVisitor)
+}
+
+struct AssetsVisitor;
+
+impl<'de> Visitor<'de> for AssetsVisitor {
+    type Value = Vec<(Cow<'de, str>, u64)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of units to quantities")
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Self::Value, M::Error> {
+        // `Cow` only borrows through `#[serde(borrow)]`.
+        #[derive(Deserialize)]
+        struct Unit<'a>(#[serde(borrow)] Cow<'a, str>);
+        #[derive(Deserialize)]
+        struct Quantity(#[serde(deserialize_with = "quantity")] u64);
+
+        let mut assets = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(Unit(unit)) = map.next_key()? {
+            let Quantity(quantity) = map.next_value()?;
+            assets.push((unit, quantity));
+        }
+        Ok(assets)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Vec::new())
+    }
+}
+
+fn assets<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(Cow<'de, str>, u64)>, D::Error> {
+    deserializer.deserialize_any(AssetsVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_borrowed_matches() {
+        let body = r#"[{
+            "address": "addr1x",
+            "transaction_id": "ab",
+            "output_index": 2,
+            "created_at": {"slot_no": 150, "header_hash": "hh"},
+            "value": {"coins": "2000000", "assets": {"ff.01": 7, "ee": "3"}},
+            "datum_hash": null
+        }]"#;
+        let matches = parse_matches(body).unwrap();
+        let entry = &matches[0];
+        assert!(matches!(entry.address, Cow::Borrowed("addr1x")));
+        assert_eq!(
+            (
+                entry.quantity("lovelace"),
+                entry.quantity("ff01"),
+                entry.quantity("ee")
+            ),
+            (2_000_000, 7, 3)
+        );
+        assert_eq!(entry.quantity("dd"), 0);
+
+        let utxo = entry.to_utxo();
+        assert_eq!((utxo.output_index, utxo.slot), (2, Some(150)));
+        assert_eq!(utxo.asset_quantity("ff01"), 7);
+        assert!(utxo.data_hash.is_none());
+        assert!(matches!(entry.value.assets[0].0, Cow::Borrowed("ff.01")));
+
+        // A bare object is one match.
+        assert_eq!(parse_matches(r#"{"address": "addr1y"}"#).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,355 @@
+use super::{CircuitBreaker, EndpointPolicy, KupoApi, RateLimiter};
+use anyhow::{anyhow, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+
+/// Conservative request rates for the hosted presets (entry-level plans);
+/// raise with [`KupoApiBuilder::max_requests_per_second`] on larger plans.
+const MAESTRO_REQUESTS_PER_SECOND: f64 = 10.0;
+const DEMETER_REQUESTS_PER_SECOND: f64 = 10.0;
+
+/// Cardano network of a hosted Kupo preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostedNetwork {
+    Mainnet,
+    Preprod,
+    Preview,
+}
+
+impl HostedNetwork {
+    fn name(&self) -> &'static str {
+        match self {
+            HostedNetwork::Mainnet => "mainnet",
+            HostedNetwork::Preprod => "preprod",
+            HostedNetwork::Preview => "preview",
+        }
+    }
+}
+
+/// Builder for [`KupoApi`] exposing the underlying HTTP client tuning.
+///
+/// Defaults: 30s request timeout, gzip + deflate responses, 90s idle
+/// connection lifetime, TCP keep-alive every 60s.
+///
+/// ```no_run
+/// # use dexter_kupo_rs::KupoApi;
+/// # use std::time::Duration;
+/// let kupo = KupoApi::builder()
+///     .endpoint("http://localhost:1442")
+///     .pool_max_idle_per_host(32)
+///     .http2_keep_alive_interval(Duration::from_secs(20))
+///     .build()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct KupoApiBuilder {
+    endpoints: Vec<String>,
+    policy: EndpointPolicy,
+    timeout: Duration,
+    connect_timeout: Option<Duration>,
+    gzip: bool,
+    deflate: bool,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
+    http2_prior_knowledge: bool,
+    headers: Vec<(String, String)>,
+    requests_per_second: Option<f64>,
+    circuit_breaker: Option<(u32, Duration)>,
+}
+
+impl Default for KupoApiBuilder {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            policy: EndpointPolicy::Failover,
+            timeout: Duration::from_secs(30),
+            connect_timeout: None,
+            gzip: true,
+            deflate: true,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            http2_keep_alive_interval: None,
+            http2_prior_knowledge: false,
+            headers: Vec::new(),
+            requests_per_second: None,
+            circuit_breaker: None,
+        }
+    }
+}
+
+impl KupoApiBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a Kupo endpoint. Call repeatedly for failover / round-robin.
+    pub fn endpoint(mut self, url: &str) -> Self {
+        self.endpoints.push(url.to_string());
+        self
+    }
+
+    pub fn policy(mut self, policy: EndpointPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Accept gzip-compressed responses (default on).
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.gzip = enable;
+        self
+    }
+
+    /// Accept deflate-compressed responses (default on).
+    pub fn deflate(mut self, enable: bool) -> Self {
+        self.deflate = enable;
+        self
+    }
+
+    /// Maximum idle connections kept open per Kupo host.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// How long an idle pooled connection is kept; `None` keeps it forever.
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.pool_idle_timeout = timeout;
+        self
+    }
+
+    /// TCP keep-alive interval; `None` disables it.
+    pub fn tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.tcp_keepalive = interval;
+        self
+    }
+
+    /// Send HTTP/2 PING frames at this interval to keep connections warm.
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Speak HTTP/2 without upgrade negotiation (Kupo behind an h2c-capable proxy).
+    pub fn http2_prior_knowledge(mut self, enable: bool) -> Self {
+        self.http2_prior_knowledge = enable;
+        self
+    }
+
+    /// Send `name: value` with every request (e.g. an API key).
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Space requests so no more than `requests` per second are sent, shared
+    /// by every clone of the built client. [`build`](Self::build) fails on a
+    /// rate that is not positive.
+    pub fn max_requests_per_second(mut self, requests: f64) -> Self {
+        self.requests_per_second = Some(requests);
+        self
+    }
+
+    /// Enable a circuit breaker: after `failure_threshold` consecutive failed
+    /// requests (connection errors, timeouts, 429 or 5xx on every endpoint)
+    /// requests fail immediately with `KupoError::CircuitOpen` for
+    /// `open_duration`, then one probe request decides whether to resume.
+    pub fn circuit_breaker(mut self, failure_threshold: u32, open_duration: Duration) -> Self {
+        self.circuit_breaker = Some((failure_threshold, open_duration));
+        self
+    }
+
+    /// Maestro's hosted Kupo: `https://<network>.gomaestro-api.org/v1/kupo`,
+    /// authenticated with the `api-key` header.
+    pub fn maestro(self, network: HostedNetwork, api_key: &str) -> Self {
+        self.endpoint(&format!(
+            "https://{}.gomaestro-api.org/v1/kupo",
+            network.name()
+        ))
+        .header("api-key", api_key)
+        .max_requests_per_second(MAESTRO_REQUESTS_PER_SECOND)
+    }
+
+    /// A Demeter Kupo port: `https://<project>.cardano-<network>-v2.kupo-m1.demeter.run`,
+    /// authenticated with the `dmtr-api-key` header.
+    pub fn demeter(self, network: HostedNetwork, project: &str, api_key: &str) -> Self {
+        self.endpoint(&format!(
+            "https://{}.cardano-{}-v2.kupo-m1.demeter.run",
+            project,
+            network.name()
+        ))
+        .header("dmtr-api-key", api_key)
+        .max_requests_per_second(DEMETER_REQUESTS_PER_SECOND)
+    }
+
+    pub fn build(self) -> Result<KupoApi> {
+        if self.endpoints.is_empty() {
+            return Err(anyhow!("KupoApi builder requires at least one endpoint"));
+        }
+
+        let mut client = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .gzip(self.gzip)
+            .deflate(self.deflate)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive);
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            client = client.pool_max_idle_per_host(max);
+        }
+        if let Some(interval) = self.http2_keep_alive_interval {
+            client = client
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_while_idle(true);
+        }
+        if self.http2_prior_knowledge {
+            client = client.http2_prior_knowledge();
+        }
+        if !self.headers.is_empty() {
+            let mut headers = HeaderMap::new();
+            for (name, value) in &self.headers {
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|e| anyhow!("Invalid header name {}: {}", name, e))?;
+                let mut value = HeaderValue::from_str(value)
+                    .map_err(|e| anyhow!("Invalid value for header {}: {}", name, e))?;
+                value.set_sensitive(true);
+                headers.insert(name, value);
+            }
+            client = client.default_headers(headers);
+        }
+        let client = client
+            .build()
+            .map_err(|e| anyhow!("Failed to build HTTP client: {}", e))?;
+
+        let rate_limiter = self
+            .requests_per_second
+            .map(RateLimiter::per_second)
+            .transpose()?;
+        let endpoints: Vec<&str> = self.endpoints.iter().map(String::as_str).collect();
+        Ok(KupoApi::from_parts(
+            &endpoints,
+            self.policy,
+            client,
+            rate_limiter,
+            self.circuit_breaker
+                .map(|(threshold, open)| CircuitBreaker::new(threshold, open)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kupo::test_server::{self, Response};
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Request heads (lowercased) received by a fake Kupo.
+    type Requests = Arc<Mutex<Vec<String>>>;
+
+    /// A Kupo that waits `delay`, then answers `/matches` with one gzipped
+    /// match and anything else with an empty 200; returns its URL.
+    async fn fake_kupo(delay: Duration) -> (String, Requests) {
+        let requests = Requests::default();
+        let seen = Arc::clone(&requests);
+        let matches = serde_json::json!([{
+            "address": "addr1pool",
+            "transaction_id": "ab".repeat(32),
+            "output_index": 0,
+            "created_at": { "slot_no": 100, "header_hash": "ef".repeat(32) },
+            "value": { "coins": 2_000_000, "assets": {} }
+        }]);
+        let mut gzip = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(matches.to_string().as_bytes()).unwrap();
+        let gzipped = gzip.finish().unwrap();
+        let url = test_server::serve(move |request| {
+            seen.lock().unwrap().push(request.head.to_lowercase());
+            let response = if request.path.starts_with("/matches") {
+                Response::status(200)
+                    .with_header("Content-Type", "application/json")
+                    .with_header("Content-Encoding", "gzip")
+                    .with_body(gzipped.clone())
+            } else {
+                Response::status(200)
+            };
+            response.with_delay(delay)
+        })
+        .await;
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_builder_applies_endpoint_headers_and_gzip() {
+        let (url, requests) = fake_kupo(Duration::ZERO).await;
+        let kupo = KupoApi::builder()
+            .endpoint(&url)
+            .header("api-key", "secret")
+            .build()
+            .unwrap();
+
+        let metrics = kupo.health_check().await;
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].url, url);
+        assert!(metrics[0].healthy);
+        // Gzip is on by default: advertised, and compressed bodies are decoded.
+        let utxos = kupo.get("*", true).await.unwrap();
+        assert_eq!(utxos.len(), 1);
+        {
+            let requests = requests.lock().unwrap();
+            assert!(requests[0].starts_with("get /health "));
+            for head in requests.iter() {
+                assert!(head.contains("api-key: secret"), "{}", head);
+                let encoding = head
+                    .lines()
+                    .find(|line| line.starts_with("accept-encoding:"))
+                    .unwrap();
+                assert!(encoding.contains("gzip"), "{}", encoding);
+            }
+        }
+
+        let (url, requests) = fake_kupo(Duration::ZERO).await;
+        let kupo = KupoApi::builder()
+            .endpoint(&url)
+            .gzip(false)
+            .deflate(false)
+            .build()
+            .unwrap();
+        assert!(kupo.health_check().await[0].healthy);
+        assert!(!requests.lock().unwrap()[0].contains("gzip"));
+
+        assert!(KupoApi::builder().build().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_builder_applies_timeout() {
+        let (url, _) = fake_kupo(Duration::from_secs(5)).await;
+        let kupo = KupoApi::builder()
+            .endpoint(&url)
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        let metrics = kupo.health_check().await;
+        assert!(
+            start.elapsed() < Duration::from_secs(2),
+            "{:?}",
+            start.elapsed()
+        );
+        assert!(!metrics[0].healthy);
+    }
+}
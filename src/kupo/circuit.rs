@@ -0,0 +1,129 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::KupoError;
+
+/// State of a [`KupoApi`](super::KupoApi) circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests flow normally.
+    Closed,
+    /// Kupo is considered down; requests fail with [`KupoError::CircuitOpen`].
+    Open,
+    /// The open period elapsed; one probe request decides whether to close.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Instant,
+    probe_started: Option<Instant>,
+}
+
+/// Opens after `failure_threshold` consecutive failed requests (every
+/// endpoint failing with a connection error, timeout, 429 or 5xx) and rejects
+/// requests for `open_duration` before letting a probe through.
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    inner: Mutex<Breaker>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            inner: Mutex::new(Breaker {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: Instant::now(),
+                probe_started: None,
+            }),
+        }
+    }
+
+    pub(crate) fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Admit a request, or fail fast while open.
+    pub(crate) fn acquire(&self) -> Result<(), KupoError> {
+        let mut b = self.inner.lock().unwrap();
+        let now = Instant::now();
+        if b.state == CircuitState::Open {
+            let elapsed = now.duration_since(b.opened_at);
+            if elapsed < self.open_duration {
+                return Err(KupoError::CircuitOpen {
+                    retry_after_ms: (self.open_duration - elapsed).as_millis() as u64,
+                });
+            }
+            b.state = CircuitState::HalfOpen;
+            b.probe_started = None;
+        }
+        if b.state == CircuitState::HalfOpen {
+            // A probe that never reported back (cancelled) expires after one open period.
+            let probing = b
+                .probe_started
+                .is_some_and(|t| now.duration_since(t) < self.open_duration);
+            if probing {
+                return Err(KupoError::CircuitOpen { retry_after_ms: 0 });
+            }
+            b.probe_started = Some(now);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn record_success(&self) {
+        let mut b = self.inner.lock().unwrap();
+        b.state = CircuitState::Closed;
+        b.consecutive_failures = 0;
+        b.probe_started = None;
+    }
+
+    pub(crate) fn record_failure(&self) {
+        let mut b = self.inner.lock().unwrap();
+        b.consecutive_failures = b.consecutive_failures.saturating_add(1);
+        if b.state == CircuitState::HalfOpen || b.consecutive_failures >= self.failure_threshold {
+            b.state = CircuitState::Open;
+            b.opened_at = Instant::now();
+            b.probe_started = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_probes_and_closes() {
+        let breaker = CircuitBreaker::new(2, Duration::from_millis(20));
+        breaker.record_failure();
+        assert!(breaker.acquire().is_ok());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(matches!(
+            breaker.acquire(),
+            Err(KupoError::CircuitOpen { .. })
+        ));
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(breaker.acquire().is_ok());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        // Only one probe at a time.
+        assert!(breaker.acquire().is_err());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert!(breaker.acquire().is_ok());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How requests are spread over multiple Kupo endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EndpointPolicy {
+    /// Always try endpoints in the order given; fall through on failure.
+    #[default]
+    Failover,
+    /// Rotate the starting endpoint on every request; fall through on failure.
+    RoundRobin,
+}
+
+/// How long an endpoint is skipped after a failed request or health check.
+pub const UNHEALTHY_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Snapshot of per-endpoint counters.
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointMetrics {
+    pub url: String,
+    pub healthy: bool,
+    pub requests: u64,
+    pub failures: u64,
+    pub avg_latency_ms: f64,
+}
+
+pub(crate) struct Endpoint {
+    pub(crate) url: String,
+    healthy: AtomicBool,
+    unhealthy_until: Mutex<Option<Instant>>,
+    requests: AtomicU64,
+    failures: AtomicU64,
+    total_latency_ms: AtomicU64,
+}
+
+impl Endpoint {
+    pub(crate) fn new(url: &str) -> Self {
+        Self {
+            url: crate::utils::remove_trailing_slash(url),
+            healthy: AtomicBool::new(true),
+            unhealthy_until: Mutex::new(None),
+            requests: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            total_latency_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Healthy, or its cooldown has expired and it deserves another try.
+    pub(crate) fn is_available(&self) -> bool {
+        if self.healthy.load(Ordering::Relaxed) {
+            return true;
+        }
+        match *self.unhealthy_until.lock().unwrap() {
+            Some(until) => Instant::now() >= until,
+            None => true,
+        }
+    }
+
+    pub(crate) fn record_success(&self, latency: Duration) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.total_latency_ms
+            .fetch_add(latency.as_millis() as u64, Ordering::Relaxed);
+        self.mark_healthy();
+    }
+
+    pub(crate) fn record_failure(&self, latency: Duration) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        self.total_latency_ms
+            .fetch_add(latency.as_millis() as u64, Ordering::Relaxed);
+        self.mark_unhealthy();
+    }
+
+    pub(crate) fn mark_healthy(&self) {
+        self.healthy.store(true, Ordering::Relaxed);
+        *self.unhealthy_until.lock().unwrap() = None;
+    }
+
+    pub(crate) fn mark_unhealthy(&self) {
+        self.healthy.store(false, Ordering::Relaxed);
+        *self.unhealthy_until.lock().unwrap() = Some(Instant::now() + UNHEALTHY_COOLDOWN);
+    }
+
+    pub(crate) fn metrics(&self) -> EndpointMetrics {
+        let requests = self.requests.load(Ordering::Relaxed);
+        let total_latency_ms = self.total_latency_ms.load(Ordering::Relaxed);
+        EndpointMetrics {
+            url: self.url.clone(),
+            healthy: self.healthy.load(Ordering::Relaxed),
+            requests,
+            failures: self.failures.load(Ordering::Relaxed),
+            avg_latency_ms: if requests > 0 {
+                total_latency_ms as f64 / requests as f64
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// Endpoint indices in the order they should be tried for one request.
+/// Available endpoints come first; cooling-down ones are kept as a last resort.
+pub(crate) fn attempt_order(endpoints: &[Endpoint], start: usize) -> Vec<usize> {
+    let n = endpoints.len();
+    let rotated = (0..n).map(|i| (start + i) % n);
+    let (available, cooling): (Vec<usize>, Vec<usize>) =
+        rotated.partition(|&i| endpoints[i].is_available());
+    available.into_iter().chain(cooling).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attempt_order_skips_unhealthy_endpoints() {
+        let endpoints = vec![
+            Endpoint::new("http://a"),
+            Endpoint::new("http://b/"),
+            Endpoint::new("http://c"),
+        ];
+        assert_eq!(attempt_order(&endpoints, 0), vec![0, 1, 2]);
+        assert_eq!(attempt_order(&endpoints, 1), vec![1, 2, 0]);
+
+        endpoints[1].record_failure(Duration::from_millis(10));
+        assert_eq!(attempt_order(&endpoints, 0), vec![0, 2, 1]);
+        assert_eq!(endpoints[1].url, "http://b");
+
+        let metrics = endpoints[1].metrics();
+        assert!(!metrics.healthy);
+        assert_eq!(metrics.failures, 1);
+    }
+}
@@ -0,0 +1,95 @@
+use thiserror::Error;
+
+/// Failure of a Kupo request.
+///
+/// Returned inside `anyhow::Error`; callers can `downcast_ref::<KupoError>()`
+/// to tell transient failures from permanent ones.
+#[derive(Debug, Error)]
+pub enum KupoError {
+    /// HTTP 429 from every endpoint tried.
+    #[error("rate_limited")]
+    RateLimited,
+    #[error("kupo {url} returned {status}")]
+    Status { url: String, status: u16 },
+    #[error("no Kupo endpoint available")]
+    NoEndpoint,
+    /// The circuit breaker is open: Kupo failed repeatedly and requests are
+    /// rejected without being sent.
+    #[error("kupo unavailable: circuit open (retry in {retry_after_ms}ms)")]
+    CircuitOpen { retry_after_ms: u64 },
+    #[error("No datum found for {0}")]
+    DatumNotFound(String),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("invalid Kupo response: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl KupoError {
+    /// Whether repeating the request may succeed: rate limits, 5xx, timeouts
+    /// and connection errors are; 4xx, missing datums, malformed bodies and an
+    /// open circuit are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            KupoError::RateLimited | KupoError::NoEndpoint => true,
+            KupoError::Status { status, .. } => *status >= 500,
+            KupoError::CircuitOpen { .. } | KupoError::DatumNotFound(_) | KupoError::Json(_) => {
+                false
+            }
+            KupoError::Http(e) => is_retryable_http(e),
+        }
+    }
+
+    /// Copy of the variants that carry no foreign error, so coalesced requests
+    /// can hand the same typed error to every waiter.
+    pub(crate) fn try_clone(&self) -> Option<KupoError> {
+        Some(match self {
+            KupoError::RateLimited => KupoError::RateLimited,
+            KupoError::Status { url, status } => KupoError::Status {
+                url: url.clone(),
+                status: *status,
+            },
+            KupoError::NoEndpoint => KupoError::NoEndpoint,
+            KupoError::CircuitOpen { retry_after_ms } => KupoError::CircuitOpen {
+                retry_after_ms: *retry_after_ms,
+            },
+            KupoError::DatumNotFound(hash) => KupoError::DatumNotFound(hash.clone()),
+            KupoError::Http(_) | KupoError::Json(_) => return None,
+        })
+    }
+}
+
+/// Whether `error` is a request rejected by an open circuit breaker, i.e.
+/// Kupo is known to be down.
+pub fn is_circuit_open(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<KupoError>(),
+        Some(KupoError::CircuitOpen { .. })
+    )
+}
+
+pub(crate) fn is_retryable_http(e: &reqwest::Error) -> bool {
+    match e.status() {
+        Some(status) => status.is_server_error() || status.as_u16() == 429,
+        None => !e.is_builder() && !e.is_decode(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryable_classification() {
+        assert!(KupoError::RateLimited.is_retryable());
+        let status = |status| KupoError::Status {
+            url: "http://kupo".to_string(),
+            status,
+        };
+        assert!(status(503).is_retryable());
+        assert!(!status(404).is_retryable());
+        assert!(!KupoError::DatumNotFound("ab".to_string()).is_retryable());
+        let json = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert!(!KupoError::from(json).is_retryable());
+    }
+}
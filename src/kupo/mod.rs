@@ -0,0 +1,579 @@
+use anyhow::Result;
+use crate::models::{KupoScript, Utxo};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+mod borrowed;
+mod builder;
+mod circuit;
+mod endpoint;
+mod error;
+mod rate_limit;
+mod single_flight;
+#[cfg(test)]
+pub(crate) mod test_server;
+
+pub use borrowed::{parse_matches, CreatedAtRef, MatchRef, ValueRef};
+pub use builder::{HostedNetwork, KupoApiBuilder};
+pub use circuit::CircuitState;
+use circuit::CircuitBreaker;
+pub use error::{is_circuit_open, KupoError};
+pub(crate) use error::is_retryable_http;
+
+pub use endpoint::{EndpointMetrics, EndpointPolicy};
+use endpoint::{attempt_order, Endpoint};
+use rate_limit::RateLimiter;
+use single_flight::SingleFlight;
+
+/// Kupo HTTP client.
+///
+/// Usually talks to a single instance, but can be given several endpoints with
+/// a failover or round-robin [`EndpointPolicy`]: a request that fails on one
+/// endpoint (connection error, 5xx, 429) falls through to the next, and the
+/// failing endpoint is skipped for a cooldown period.
+///
+/// Identical match/datum queries issued concurrently (e.g. by a watcher and a
+/// user query) are coalesced into a single HTTP request.
+///
+/// Cloning is cheap: clones share the HTTP connection pool, endpoint health and
+/// in-flight request coalescing, so one client can be handed to spawned tasks.
+#[derive(Clone)]
+pub struct KupoApi {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    endpoints: Vec<Endpoint>,
+    policy: EndpointPolicy,
+    next: AtomicUsize,
+    client: reqwest::Client,
+    match_flights: SingleFlight<Vec<Utxo>>,
+    datum_flights: SingleFlight<String>,
+    limiter: Option<RateLimiter>,
+    breaker: Option<CircuitBreaker>,
+}
+
+/// DEX constructors take `impl Into<KupoApi>`, so several DEXes can share one
+/// client: `MinswapV2::new(&kupo)`, `SundaeSwapV3::new(&kupo)`.
+impl From<&KupoApi> for KupoApi {
+    fn from(kupo: &KupoApi) -> Self {
+        kupo.clone()
+    }
+}
+
+impl From<Arc<KupoApi>> for KupoApi {
+    fn from(kupo: Arc<KupoApi>) -> Self {
+        (*kupo).clone()
+    }
+}
+
+/// `/matches` path for the outputs created in `slots`, oldest first.
+fn page_path(match_pattern: &str, unspent: bool, slots: Range<u64>) -> String {
+    let mut path = format!("/matches/{}?order=oldest_first", match_pattern);
+    if unspent {
+        path.push_str("&unspent");
+    }
+    // Kupo's bounds are both exclusive.
+    if slots.start > 0 {
+        path.push_str(&format!("&created_after={}", slots.start - 1));
+    }
+    path.push_str(&format!("&created_before={}", slots.end));
+    path
+}
+
+fn spent_at_path(match_pattern: &str, slot: u64) -> String {
+    // Exclusive bounds: created at or before `slot`, spent after it.
+    format!(
+        "/matches/{}?spent&created_before={}&spent_after={}",
+        match_pattern,
+        slot.saturating_add(1),
+        slot
+    )
+}
+
+fn default_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(30)) // 30s — fail fast so refresh retries don't block for minutes
+        .gzip(true) // match responses are multi-megabyte JSON
+        .deflate(true)
+        .pool_idle_timeout(std::time::Duration::from_secs(90))
+        .tcp_keepalive(std::time::Duration::from_secs(60))
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+impl KupoApi {
+    pub fn new(api_url: &str) -> Self {
+        Self::with_client(api_url, default_client())
+    }
+
+    /// Configure endpoints, compression and connection pooling before building.
+    pub fn builder() -> KupoApiBuilder {
+        KupoApiBuilder::new()
+    }
+
+    /// Maestro's hosted Kupo on mainnet. See [`KupoApiBuilder::maestro`].
+    pub fn maestro(api_key: &str) -> Result<Self> {
+        KupoApiBuilder::new().maestro(HostedNetwork::Mainnet, api_key).build()
+    }
+
+    /// A Demeter Kupo port on mainnet. See [`KupoApiBuilder::demeter`].
+    pub fn demeter(project: &str, api_key: &str) -> Result<Self> {
+        KupoApiBuilder::new()
+            .demeter(HostedNetwork::Mainnet, project, api_key)
+            .build()
+    }
+
+    /// Primary (first) endpoint URL.
+    pub fn api_url(&self) -> &str {
+        &self.inner.endpoints[0].url
+    }
+
+    pub fn with_client(api_url: &str, client: reqwest::Client) -> Self {
+        Self::with_endpoints_and_client(&[api_url], EndpointPolicy::Failover, client)
+    }
+
+    /// Build a client over several Kupo instances.
+    ///
+    /// Panics if `api_urls` is empty.
+    pub fn with_endpoints(api_urls: &[&str], policy: EndpointPolicy) -> Self {
+        Self::with_endpoints_and_client(api_urls, policy, default_client())
+    }
+
+    pub fn with_endpoints_and_client(
+        api_urls: &[&str],
+        policy: EndpointPolicy,
+        client: reqwest::Client,
+    ) -> Self {
+        Self::from_parts(api_urls, policy, client, None, None)
+    }
+
+    fn from_parts(
+        api_urls: &[&str],
+        policy: EndpointPolicy,
+        client: reqwest::Client,
+        limiter: Option<RateLimiter>,
+        breaker: Option<CircuitBreaker>,
+    ) -> Self {
+        assert!(!api_urls.is_empty(), "KupoApi requires at least one endpoint");
+        Self {
+            inner: Arc::new(Inner {
+                endpoints: api_urls.iter().map(|url| Endpoint::new(url)).collect(),
+                policy,
+                next: AtomicUsize::new(0),
+                client,
+                match_flights: SingleFlight::new(),
+                datum_flights: SingleFlight::new(),
+                limiter,
+                breaker,
+            }),
+        }
+    }
+
+    pub fn endpoint_urls(&self) -> Vec<&str> {
+        self.inner.endpoints.iter().map(|e| e.url.as_str()).collect()
+    }
+
+    /// State of the circuit breaker; `None` unless enabled with
+    /// [`KupoApiBuilder::circuit_breaker`].
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.inner.breaker.as_ref().map(CircuitBreaker::state)
+    }
+
+    /// Request counts, failures, average latency and health per endpoint.
+    pub fn endpoint_metrics(&self) -> Vec<EndpointMetrics> {
+        self.inner.endpoints.iter().map(|e| e.metrics()).collect()
+    }
+
+    /// Probe `/health` on every endpoint and update its health flag.
+    /// Call periodically to bring recovered endpoints back before their cooldown ends.
+    pub async fn health_check(&self) -> Vec<EndpointMetrics> {
+        for endpoint in &self.inner.endpoints {
+            let url = format!("{}/health", endpoint.url);
+            let ok = match self.inner.client.get(&url).send().await {
+                Ok(response) => response.status().is_success(),
+                Err(_) => false,
+            };
+            if ok {
+                endpoint.mark_healthy();
+            } else {
+                eprintln!("[kupo] health check failed for {}", endpoint.url);
+                endpoint.mark_unhealthy();
+            }
+        }
+        self.endpoint_metrics()
+    }
+
+    /// GET `path` (relative to the endpoint root) through the circuit breaker.
+    async fn send(&self, path: &str) -> Result<reqwest::Response> {
+        let Some(breaker) = &self.inner.breaker else {
+            return self.send_to_endpoints(path).await;
+        };
+        breaker.acquire()?;
+        let result = self.send_to_endpoints(path).await;
+        match &result {
+            Err(e) if e.downcast_ref::<KupoError>().is_none_or(KupoError::is_retryable) => {
+                breaker.record_failure()
+            }
+            _ => breaker.record_success(),
+        }
+        result
+    }
+
+    /// GET `path`, walking endpoints per policy.
+    async fn send_to_endpoints(&self, path: &str) -> Result<reqwest::Response> {
+        let start = match self.inner.policy {
+            EndpointPolicy::Failover => 0,
+            EndpointPolicy::RoundRobin => {
+                self.inner.next.fetch_add(1, Ordering::Relaxed) % self.inner.endpoints.len()
+            }
+        };
+
+        let mut last_error = None;
+        for i in attempt_order(&self.inner.endpoints, start) {
+            let endpoint = &self.inner.endpoints[i];
+            let url = format!("{}{}", endpoint.url, path);
+            if let Some(limiter) = &self.inner.limiter {
+                limiter.acquire().await;
+            }
+            let started = Instant::now();
+            match self
+                .inner
+                .client
+                .get(&url)
+                .header("Accept", "application/json")
+                .send()
+                .await
+            {
+                Ok(response) if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                    endpoint.record_failure(started.elapsed());
+                    last_error = Some(KupoError::RateLimited);
+                }
+                Ok(response) if response.status().is_server_error() => {
+                    endpoint.record_failure(started.elapsed());
+                    last_error = Some(KupoError::Status {
+                        url: endpoint.url.clone(),
+                        status: response.status().as_u16(),
+                    });
+                }
+                // The request itself is wrong; other endpoints would answer the same.
+                Ok(response) if response.status().is_client_error() => {
+                    endpoint.record_success(started.elapsed());
+                    return Err(KupoError::Status {
+                        url: endpoint.url.clone(),
+                        status: response.status().as_u16(),
+                    }
+                    .into());
+                }
+                Ok(response) => {
+                    endpoint.record_success(started.elapsed());
+                    return Ok(response);
+                }
+                Err(e) => {
+                    endpoint.record_failure(started.elapsed());
+                    last_error = Some(e.into());
+                }
+            }
+        }
+        Err(last_error.unwrap_or(KupoError::NoEndpoint).into())
+    }
+
+    fn build_matches_path(&self, match_pattern: &str, unspent: bool) -> String {
+        let base = format!("/matches/{}", match_pattern);
+        if unspent {
+            format!("{}?unspent", base)
+        } else {
+            base
+        }
+    }
+
+    async fn fetch_datum(&self, hash: &str) -> Result<serde_json::Value> {
+        let response = self.send(&format!("/datums/{}", hash)).await?;
+        let body = response.text().await.map_err(KupoError::from)?;
+        let parsed: serde_json::Value = serde_json::from_str(&body).map_err(KupoError::from)?;
+        Ok(parsed)
+    }
+
+    /// Fetch UTXOs matching a Kupo pattern. Concurrent identical queries share one request.
+    pub async fn get(&self, match_pattern: &str, unspent: bool) -> Result<Vec<Utxo>> {
+        let key = self.build_matches_path(match_pattern, unspent);
+        self.inner.match_flights
+            .run(&key, || self.fetch_matches(match_pattern, unspent))
+            .await
+    }
+
+    async fn fetch_matches(&self, match_pattern: &str, unspent: bool) -> Result<Vec<Utxo>> {
+        self.fetch_matches_path(&self.build_matches_path(match_pattern, unspent)).await
+    }
+
+    async fn fetch_matches_path(&self, path: &str) -> Result<Vec<Utxo>> {
+        crate::utils::retry(10, 1000, || async {
+            let response = self.send(path).await?;
+            let body = response.text().await.map_err(KupoError::from)?;
+            let matches = parse_matches(&body).map_err(KupoError::from)?;
+            Ok(matches.iter().map(MatchRef::to_utxo).collect())
+        })
+        .await
+    }
+
+    /// Call `f` on every match of `match_pattern` without building `Utxo`s;
+    /// entries borrow from the response body. Returns the number of matches.
+    /// For bulk ingestion, where [`get`](Self::get)'s owned copies dominate.
+    /// Not coalesced.
+    pub async fn for_each_match(
+        &self,
+        match_pattern: &str,
+        unspent: bool,
+        mut f: impl FnMut(&MatchRef<'_>),
+    ) -> Result<usize> {
+        let path = self.build_matches_path(match_pattern, unspent);
+        let body = crate::utils::retry(10, 1000, || async {
+            let response = self.send(&path).await?;
+            Ok::<_, anyhow::Error>(response.text().await.map_err(KupoError::from)?)
+        })
+        .await?;
+        let matches = parse_matches(&body).map_err(KupoError::from)?;
+        matches.iter().for_each(&mut f);
+        Ok(matches.len())
+    }
+
+    /// Unspent-or-not matches of `match_pattern` created in `slots`
+    /// (`start` inclusive, `end` exclusive), oldest first. Not coalesced.
+    pub async fn get_page(
+        &self,
+        match_pattern: &str,
+        unspent: bool,
+        slots: Range<u64>,
+    ) -> Result<Vec<Utxo>> {
+        self.fetch_matches_path(&page_path(match_pattern, unspent, slots))
+            .await
+    }
+
+    /// A spent output of `match_pattern` that existed at `slot`: created at or
+    /// before it and spent after it. For an NFT there is at most one, so the
+    /// response stays small however long the asset's history is. Always
+    /// `None` on a Kupo that prunes spent outputs (`--prune-utxo`).
+    pub async fn spent_output_at(&self, match_pattern: &str, slot: u64) -> Result<Option<Utxo>> {
+        Ok(self
+            .fetch_matches_path(&spent_at_path(match_pattern, slot))
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Matches of `match_pattern`, fetched lazily in windows of `page_slots`
+    /// slots up to the current tip, so only one window is held in memory.
+    pub fn match_stream(
+        &self,
+        match_pattern: &str,
+        unspent: bool,
+        page_slots: u64,
+    ) -> BoxStream<'static, Result<Utxo>> {
+        let kupo = self.clone();
+        let pattern = match_pattern.to_string();
+        let page_slots = page_slots.max(1);
+        stream::try_unfold((0u64, None), move |(start, tip): (u64, Option<u64>)| {
+            let kupo = kupo.clone();
+            let pattern = pattern.clone();
+            async move {
+                let tip = match tip {
+                    Some(tip) => tip,
+                    None => kupo.tip_slot().await?,
+                };
+                if start > tip {
+                    return Ok::<_, anyhow::Error>(None);
+                }
+                let end = start.saturating_add(page_slots);
+                let page = kupo.get_page(&pattern, unspent, start..end).await?;
+                Ok(Some((page, (end, Some(tip)))))
+            }
+        })
+        .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+        .try_flatten()
+        .boxed()
+    }
+
+    /// Fetch a datum (CBOR hex) by hash. Concurrent identical lookups share one request.
+    pub async fn datum(&self, hash: &str) -> Result<String> {
+        self.inner.datum_flights.run(hash, || self.fetch_datum_cbor(hash)).await
+    }
+
+    /// Datum of `utxo`: the inline datum if present, otherwise fetched by hash.
+    /// `None` if the output carries no datum.
+    pub async fn utxo_datum(&self, utxo: &Utxo) -> Result<Option<String>> {
+        match (&utxo.inline_datum, &utxo.data_hash) {
+            (Some(datum), _) => Ok(Some(datum.clone())),
+            (None, Some(hash)) => self.datum(hash).await.map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    async fn fetch_datum_cbor(&self, hash: &str) -> Result<String> {
+        crate::utils::retry(10, 1000, || async {
+            let response = self.fetch_datum(hash).await?;
+            let datum = response.get("datum")
+                .and_then(|d| d.as_str())
+                .ok_or_else(|| KupoError::DatumNotFound(hash.to_string()))?;
+            Ok(datum.to_string())
+        })
+        .await
+    }
+
+    /// Fetch a script by hash (e.g. a minting policy id). `None` if Kupo has not
+    /// seen it in any witness set or reference script.
+    pub async fn script(&self, hash: &str) -> Result<Option<KupoScript>> {
+        crate::utils::retry(10, 1000, || async {
+            let response = self.send(&format!("/scripts/{}", hash)).await?;
+            let body = response.text().await.map_err(KupoError::from)?;
+            let script: Option<KupoScript> = serde_json::from_str(&body).map_err(KupoError::from)?;
+            Ok(script)
+        })
+        .await
+    }
+
+    /// Fetch the most recent checkpoint slot from Kupo's /health endpoint.
+    /// Used to derive a TTL for transactions ("invalid_from_slot").
+    ///
+    /// Reads the `X-Most-Recent-Checkpoint` response header (always present
+    /// regardless of Accept negotiation). Falls back to JSON body parsing if the
+    /// header is missing.
+    pub async fn tip_slot(&self) -> Result<u64> {
+        let response = self.send("/health").await?;
+
+        // Primary: read the X-Most-Recent-Checkpoint header.
+        if let Some(hv) = response.headers().get("x-most-recent-checkpoint") {
+            if let Ok(s) = hv.to_str() {
+                if let Ok(n) = s.trim().parse::<u64>() {
+                    return Ok(n);
+                }
+            }
+        }
+
+        // Fallback: parse the body as JSON.
+        let body = response.text().await?;
+        let v: serde_json::Value = serde_json::from_str(&body).map_err(|e| {
+            anyhow::anyhow!(
+                "Kupo /health body is not JSON and X-Most-Recent-Checkpoint header was missing/invalid: {}",
+                e
+            )
+        })?;
+        if let Some(n) = v.get("most_recent_checkpoint").and_then(|x| x.as_u64()) {
+            return Ok(n);
+        }
+        if let Some(n) = v
+            .get("most_recent_checkpoint")
+            .and_then(|x| x.get("slot_no"))
+            .and_then(|x| x.as_u64())
+        {
+            return Ok(n);
+        }
+        Err(anyhow::anyhow!(
+            "could not parse `most_recent_checkpoint` from Kupo /health response"
+        ))
+    }
+
+    /// Connection state, checkpoint and node tip from Kupo's /health endpoint.
+    pub async fn sync_status(&self) -> Result<KupoSyncStatus> {
+        let response = self.send("/health").await?;
+        let body: serde_json::Value = response.json().await?;
+        parse_sync_status(&body)
+    }
+}
+
+/// How far Kupo's index is behind the node it follows.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct KupoSyncStatus {
+    /// Kupo is connected to its node.
+    pub connected: bool,
+    pub most_recent_checkpoint: u64,
+    pub most_recent_node_tip: Option<u64>,
+}
+
+impl KupoSyncStatus {
+    /// Slots between the node tip and Kupo's checkpoint; 0 when the tip is unknown.
+    pub fn lag(&self) -> u64 {
+        self.most_recent_node_tip
+            .map(|tip| tip.saturating_sub(self.most_recent_checkpoint))
+            .unwrap_or(0)
+    }
+}
+
+/// Slot of a /health field, given either as a number or as `{ "slot_no": n }`.
+fn health_slot(value: Option<&serde_json::Value>) -> Option<u64> {
+    let value = value?;
+    value
+        .as_u64()
+        .or_else(|| value.get("slot_no").and_then(|x| x.as_u64()))
+}
+
+fn parse_sync_status(body: &serde_json::Value) -> Result<KupoSyncStatus> {
+    let checkpoint = health_slot(body.get("most_recent_checkpoint")).ok_or_else(|| {
+        anyhow::anyhow!("could not parse `most_recent_checkpoint` from Kupo /health response")
+    })?;
+    Ok(KupoSyncStatus {
+        connected: body.get("connection_status").and_then(|x| x.as_str()) == Some("connected"),
+        most_recent_checkpoint: checkpoint,
+        most_recent_node_tip: health_slot(body.get("most_recent_node_tip")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sync_status() {
+        let status = parse_sync_status(&serde_json::json!({
+            "connection_status": "connected",
+            "most_recent_checkpoint": 1_000,
+            "most_recent_node_tip": { "slot_no": 1_030 },
+        }))
+        .unwrap();
+        assert!(status.connected);
+        assert_eq!(status.lag(), 30);
+
+        let behind = parse_sync_status(&serde_json::json!({
+            "connection_status": "disconnected",
+            "most_recent_checkpoint": { "slot_no": 5 },
+        }))
+        .unwrap();
+        assert!(!behind.connected);
+        assert_eq!((behind.most_recent_checkpoint, behind.lag()), (5, 0));
+        assert!(parse_sync_status(&serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn test_page_path_and_parse_match() {
+        assert_eq!(
+            page_path("addr1x", true, 0..100),
+            "/matches/addr1x?order=oldest_first&unspent&created_before=100"
+        );
+        assert_eq!(
+            page_path("p.*", false, 100..200),
+            "/matches/p.*?order=oldest_first&created_after=99&created_before=200"
+        );
+        assert_eq!(
+            spent_at_path("p.n", 500),
+            "/matches/p.n?spent&created_before=501&spent_after=500"
+        );
+
+        let body = serde_json::json!([{
+            "address": "addr1x",
+            "transaction_id": "ab",
+            "output_index": 1,
+            "created_at": { "slot_no": 150, "header_hash": "hh" },
+            "value": { "coins": 2_000_000, "assets": { "ff.01": "7" } },
+            "datum_hash": "dh"
+        }])
+        .to_string();
+        let utxo = parse_matches(&body).unwrap()[0].to_utxo();
+        assert_eq!(utxo.output_index, 1);
+        assert_eq!(utxo.slot, Some(150));
+        assert_eq!(utxo.amount[1].unit, "ff01");
+        assert_eq!(utxo.amount[1].quantity, 7);
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Spaces requests at least `interval` apart across all clones of a client.
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Fails unless `requests` is positive (infinity spaces nothing) and
+    /// large enough for its interval to fit a `Duration`.
+    pub(crate) fn per_second(requests: f64) -> Result<Self> {
+        let interval = Some(requests)
+            .filter(|requests| *requests > 0.0)
+            .and_then(|requests| Duration::try_from_secs_f64(1.0 / requests).ok())
+            .ok_or_else(|| {
+                anyhow!(
+                    "max_requests_per_second must be a positive number, got {}",
+                    requests
+                )
+            })?;
+        Ok(Self {
+            interval,
+            next: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Wait for the next request slot.
+    pub(crate) async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_requests_are_spaced() {
+        let limiter = RateLimiter::per_second(100.0).unwrap();
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        // The first slot is immediate, the next two wait 10ms each.
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_invalid_rates_are_rejected() {
+        for requests in [0.0, -0.0, -5.0, f64::NAN, f64::NEG_INFINITY, 1e-300] {
+            assert!(RateLimiter::per_second(requests).is_err(), "{}", requests);
+        }
+        assert!(RateLimiter::per_second(0.5).is_ok());
+        let unlimited = RateLimiter::per_second(f64::INFINITY).unwrap();
+        assert_eq!(unlimited.interval, Duration::ZERO);
+
+        let build = |requests| {
+            crate::KupoApi::builder()
+                .endpoint("http://127.0.0.1:9")
+                .max_requests_per_second(requests)
+                .build()
+        };
+        assert!(build(0.0).is_err());
+        assert!(build(f64::NAN).is_err());
+        assert!(build(10.0).is_ok());
+    }
+}
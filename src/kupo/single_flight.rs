@@ -0,0 +1,115 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+use super::KupoError;
+
+type Slot<T> = Arc<OnceCell<Result<T, SharedError>>>;
+
+/// Error of a coalesced request, kept typed when it is a cloneable [`KupoError`].
+#[derive(Debug)]
+struct SharedError {
+    message: String,
+    kupo: Option<KupoError>,
+}
+
+impl SharedError {
+    fn new(error: anyhow::Error) -> Self {
+        Self {
+            message: error.to_string(),
+            kupo: error.downcast_ref::<KupoError>().and_then(KupoError::try_clone),
+        }
+    }
+
+    fn to_error(&self) -> anyhow::Error {
+        match self.kupo.as_ref().and_then(KupoError::try_clone) {
+            Some(kupo) => kupo.into(),
+            None => anyhow!("{}", self.message),
+        }
+    }
+}
+
+/// Deduplicates concurrent identical requests: callers that arrive while a
+/// request for the same key is in flight wait for it and share its result.
+pub(crate) struct SingleFlight<T> {
+    inflight: Mutex<HashMap<String, Slot<T>>>,
+}
+
+impl<T: Clone> SingleFlight<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            inflight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) async fn run<F, Fut>(&self, key: &str, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let slot = self
+            .inflight
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert_with(|| Arc::new(OnceCell::new()))
+            .clone();
+
+        // If the caller running `f` is cancelled, the next waiter takes over.
+        let result = slot
+            .get_or_init(|| async { f().await.map_err(SharedError::new) })
+            .await;
+        let result = match result {
+            Ok(value) => Ok(value.clone()),
+            Err(e) => Err(e.to_error()),
+        };
+
+        // Whoever finishes first retires the slot so later calls hit Kupo again.
+        let mut inflight = self.inflight.lock().unwrap();
+        if inflight.get(key).is_some_and(|s| Arc::ptr_eq(s, &slot)) {
+            inflight.remove(key);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_request() {
+        let flight = Arc::new(SingleFlight::<u64>::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..5)
+            .map(|_| {
+                let flight = Arc::clone(&flight);
+                let calls = Arc::clone(&calls);
+                tokio::spawn(async move {
+                    flight
+                        .run("pattern", || async {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok(42)
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap(), 42);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Once settled, the next call goes out again.
+        flight.run("pattern", || async { Ok(7) }).await.unwrap();
+        assert_eq!(flight.run("pattern", || async { Ok(8) }).await.unwrap(), 8);
+    }
+}
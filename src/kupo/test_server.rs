@@ -0,0 +1,200 @@
+//! A fake Kupo HTTP server for tests.
+//!
+//! [`serve`] answers every request on a local port through a handler, one
+//! connection per request (`Connection: close`). Requests are read up to the
+//! end of their headers and body however the client splits them into writes.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// A request received by the fake server.
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// Path and query, e.g. `/matches/addr1?unspent`.
+    pub path: String,
+    /// Request line and headers, as sent.
+    pub head: String,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Value of header `name` (case-insensitive), if sent.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    /// Integer query parameter `name`, if present.
+    pub fn query_param(&self, name: &str) -> Option<u64> {
+        self.path
+            .split(['?', '&'])
+            .skip(1)
+            .find_map(|kv| kv.strip_prefix(name)?.strip_prefix('=')?.parse().ok())
+    }
+}
+
+/// The answer to one request.
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Wait before answering.
+    pub delay: Duration,
+}
+
+impl Response {
+    /// 200 with a JSON body.
+    pub fn json(body: &serde_json::Value) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: body.to_string().into_bytes(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// `status` with an empty body.
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, body: Vec<u8>) -> Self {
+        self.body = body;
+        self
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// Serve `handler` on a fresh local port; returns the server URL.
+pub async fn serve<F>(handler: F) -> String
+where
+    F: Fn(&Request) -> Response + Send + Sync + 'static,
+{
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            let handler = Arc::clone(&handler);
+            tokio::spawn(async move {
+                let _ = answer(socket, handler.as_ref()).await;
+            });
+        }
+    });
+    url
+}
+
+async fn answer<F>(mut socket: TcpStream, handler: &F) -> std::io::Result<()>
+where
+    F: Fn(&Request) -> Response,
+{
+    let Some(request) = read_request(&mut socket).await? else {
+        return Ok(());
+    };
+    let response = handler(&request);
+    tokio::time::sleep(response.delay).await;
+
+    let mut head = format!("HTTP/1.1 {} Fake\r\n", response.status);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    ));
+    socket.write_all(head.as_bytes()).await?;
+    socket.write_all(&response.body).await
+}
+
+/// Read one request: the head up to the blank line, then `Content-Length`
+/// bytes of body. `None` if the client hung up first.
+async fn read_request(socket: &mut TcpStream) -> std::io::Result<Option<Request>> {
+    let mut buf = Vec::new();
+    let mut chunk = [0; 4096];
+    let head_end = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i;
+        }
+        let n = socket.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).into_owned();
+    let mut request = Request {
+        path: head.split_whitespace().nth(1).unwrap_or("/").to_string(),
+        head,
+        body: buf.split_off(head_end + 4),
+    };
+    let length = request
+        .header("content-length")
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0);
+    while request.body.len() < length {
+        let n = socket.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        request.body.extend_from_slice(&chunk[..n]);
+    }
+    Ok(Some(request))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_split_across_writes() {
+        let url = serve(|request| {
+            Response::json(&serde_json::json!({
+                "path": request.path,
+                "key": request.header("api-key"),
+                "body": String::from_utf8_lossy(&request.body),
+            }))
+        })
+        .await;
+
+        let mut socket = TcpStream::connect(url.trim_start_matches("http://"))
+            .await
+            .unwrap();
+        for part in [
+            "GET /matches/x?un",
+            "spent HTTP/1.1\r\nApi-Key: k\r\nContent-Le",
+            "ngth: 4\r\n\r\nab",
+            "cd",
+        ] {
+            socket.write_all(part.as_bytes()).await.unwrap();
+            socket.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let mut response = String::new();
+        socket.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 "), "{}", response);
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(body).unwrap(),
+            serde_json::json!({ "path": "/matches/x?unspent", "key": "k", "body": "abcd" })
+        );
+    }
+}
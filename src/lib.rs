@@ -30,8 +30,8 @@
 //!
 //!     // Query pools for a token pair
 //!     let pools = dex.liquidity_pools_from_token(
-//!         "lovelace",
-//!         "f13ac4d66b3ee19a6aa0f2a22298737bd907cc95121662fc971b5275535452494b45"
+//!         "lovelace".into(),
+//!         "f13ac4d66b3ee19a6aa0f2a22298737bd907cc95121662fc971b5275535452494b45".into()
 //!     ).await?;
 //!
 //!     for pool in pools {
@@ -90,21 +90,45 @@
 //! ```
 
 pub mod address;
+pub mod aggregator;
+pub mod analytics;
 pub mod cache;
 pub mod dex;
+pub mod inspect;
+pub mod export;
 pub mod kupo;
 pub mod models;
+pub mod oracles;
+pub mod orderflow;
+pub mod parity;
 pub mod plutus;
+pub mod portfolio;
+pub mod quote;
+pub mod registry;
 pub mod requests;
+pub mod router;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod sim;
+pub mod store;
 pub mod utils;
+pub mod verification;
+pub mod watcher;
 
+pub use aggregator::Aggregator;
 pub use address::{decode_base_address, script_and_stake_to_base_address, WalletAddress};
-pub use cache::{load_from_file, save_to_file};
+pub use cache::{load_from_file, save_to_file, CacheFormat, CACHE_VERSION};
 pub use dex::{BaseDex, DexSwap};
 pub use dex::vyfinance::{VyFinanceCache, VyFinancePoolData};
+pub use export::{ExportSink, PoolExport};
 pub use kupo::KupoApi;
-pub use models::{Asset, LiquidityPool, Order, OrderBook, StablePool, Token, Utxo};
+pub use models::{Asset, AssetId, LiquidityPool, Order, OrderBook, StablePool, Token, Utxo};
 pub use plutus::PlutusData;
+pub use quote::QuoteCache;
+pub use registry::TokenRegistry;
+pub use router::{Route, Router};
+pub use store::PoolStore;
+pub use verification::{TokenLists, TokenVerifier};
 pub use requests::{
     AddressType, AssetAmount, BulkOrderPlan, BulkSwapRequest, CancelSwapRequest, OrderKind,
     PayToAddress, PlutusScript, PlutusVersion, SpendUtxo, SwapFee, SwapParams, SwapRequest,
@@ -1,168 +1,1081 @@
-mod address;
-mod cache;
-mod dex;
-mod kupo;
-mod models;
-mod plutus;
-mod requests;
-mod utils;
-
-use crate::models::asset::token_identifier;
-use dex::chadswap::ChadSwap;
-use dex::cswap::CSwap;
-use dex::minswap_v1::MinswapV1;
-use dex::minswap_v2::MinswapV2;
-use dex::minswap_stable::MinswapStable;
-use dex::sundaeswap_v1::SundaeSwapV1;
-use dex::sundaeswap_v3::SundaeSwapV3;
-use dex::vyfinance::{VyFinance, VyFinanceCache};
-use dex::vyfi_bar::VyfiBar;
-use dex::wingriders::WingRiders;
-use dex::wingriders_v2::WingRidersV2;
-use dex::BaseDex;
-use kupo::KupoApi;
-use models::LiquidityPool;
-use serde::Serialize;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use dexter_kupo_rs::aggregator::Aggregator;
+use dexter_kupo_rs::analytics::{token_liquidity, top_pools_from, RankBy};
+use dexter_kupo_rs::cache;
+use dexter_kupo_rs::dex::chadswap::ChadSwap;
+use dexter_kupo_rs::dex::cswap::CSwap;
+use dexter_kupo_rs::dex::djed::DjedReader;
+use dexter_kupo_rs::dex::minswap_stable::MinswapStable;
+use dexter_kupo_rs::dex::minswap_v1::MinswapV1;
+use dexter_kupo_rs::dex::minswap_v2::MinswapV2;
+use dexter_kupo_rs::dex::sundaeswap_v1::SundaeSwapV1;
+use dexter_kupo_rs::dex::sundaeswap_v3::SundaeSwapV3;
+use dexter_kupo_rs::dex::vyfi_bar::VyfiBar;
+use dexter_kupo_rs::dex::vyfinance::{VyFinance, VyFinanceCache};
+use dexter_kupo_rs::dex::wingriders::WingRiders;
+use dexter_kupo_rs::dex::wingriders_v2::WingRidersV2;
+use dexter_kupo_rs::dex::{BaseDex, CreationResolver, OverridesConfig, PoolFilter};
+use dexter_kupo_rs::export::pipeline::DEFAULT_MAX_CONCURRENCY;
+use dexter_kupo_rs::export::{
+    self, fetch_pair, pool_to_export, stable_pool_to_export, Compression, ExportOptions,
+    ExportSink, HttpPostSink, JsonFileSink, NdjsonStdoutSink, PoolExport, StdoutSink,
+};
+use dexter_kupo_rs::inspect;
+use dexter_kupo_rs::kupo::EndpointPolicy;
+use dexter_kupo_rs::models::{BookDepth, DecimalsOverrides, DepthLevel};
+use dexter_kupo_rs::oracles::{load_feeds, OracleReader};
+use dexter_kupo_rs::parity;
+use dexter_kupo_rs::registry::find_fingerprint;
+use dexter_kupo_rs::router::Router;
+#[cfg(feature = "server")]
+use dexter_kupo_rs::server::{AccessConfig, Server};
+use dexter_kupo_rs::utils::parse_duration;
+use dexter_kupo_rs::watcher::{Alerter, ConfigReload, PoolWatcher, SupplyTracker};
+use dexter_kupo_rs::{AssetId, KupoApi, TokenLists, TokenRegistry, TokenVerifier};
 use std::sync::Arc;
-use tokio::sync::Semaphore;
 
-const CONCURRENCY: usize = 5;
 const KUPO_URL: &str = "http://157.180.117.47:1444";
+const EXPORT_PATH: &str = "pools_rs.json";
+const SKIPPED_PATH: &str = "pools_rs.skipped.json";
+/// DEXes covered by `--dex all`.
+const AMM_DEXES: &[&str] = &[
+    "minswap_v1",
+    "minswap_v2",
+    "sundaeswap_v1",
+    "sundaeswap_v3",
+    "wingriders",
+    "wingriders_v2",
+    "cswap",
+    "vyfinance",
+];
 
-#[derive(Serialize)]
-struct PoolExport {
-    dex: String,
-    pool_id: String,
-    asset_a: String,
-    asset_b: String,
-    reserve_a: String,
-    reserve_b: String,
-    pool_fee_percent: f64,
-    total_lp_tokens: String,
-    tx_hash: String,
+/// DEXes `--dex` accepts besides the AMMs.
+const OTHER_DEXES: &[&str] = &["all", "minswap_stable", "chadswap", "chadswap_all"];
+
+/// A flag, as described by `--help-json` and the shell completions. The
+/// argument parser accepts exactly the flags in [`FLAGS`].
+struct Flag {
+    name: &'static str,
+    /// Placeholder of the value it takes; `None` for switches, `path` completes files.
+    value: Option<&'static str>,
+    choices: &'static [&'static str],
+    help: &'static str,
 }
 
-#[derive(Serialize)]
-struct StablePoolExport {
-    dex: String,
-    pool_id: String,
-    asset_a: String,
-    asset_b: String,
-    reserve_a: String,
-    reserve_b: String,
-    pool_fee_percent: f64,
-    amplification_coefficient: String,
-    total_liquidity: String,
+const fn flag(name: &'static str, value: Option<&'static str>, help: &'static str) -> Flag {
+    Flag {
+        name,
+        value,
+        choices: &[],
+        help,
+    }
+}
+
+const FLAGS: &[Flag] = &[
+    flag("--dex", Some("dex_name"), "DEX to query (default: minswap_v2)"),
+    flag("--cache", Some("path"), "VyFinance cache JSON file"),
+    flag("--vyfi-bar", Some("pool_identifier"), "fetch the VyFi Bar rate of a pool"),
+    flag("--vyfi-bar-all", Some("path"), "fetch VyFi Bar rates for a JSON array of pool identifiers"),
+    flag("--decimals", Some("path"), "JSON map of token identifier to decimals"),
+    flag("--token-list", Some("path"), "JSON allowlist/denylist; adds verified to exported pools"),
+    flag("--tokens", Some("path"), "JSON map of ticker to identifier"),
+    flag("--dex-config", Some("path"), "TOML or JSON overrides of DEX constants; watch and serve reload it"),
+    flag("--webhook", Some("url"), "also POST the export to this URL"),
+    flag("--kupo", Some("url"), "Kupo endpoint; repeat for failover or load balancing"),
+    Flag {
+        choices: &["failover", "round-robin"],
+        ..flag("--kupo-policy", Some("policy"), "policy across --kupo endpoints")
+    },
+    flag("--min-ada", Some("lovelace"), "drop ADA pools and ChadSwap orders below this many lovelace"),
+    flag("--skip-spam", None, "drop pools where one side holds a single unit"),
+    flag("--verbose", None, "log which filter rule dropped each pool"),
+    flag("--max-concurrency", Some("n"), "cap for adaptive export concurrency"),
+    flag("--output", Some("path"), "export path; - streams NDJSON to stdout"),
+    Flag {
+        choices: &["gzip", "zstd", "none"],
+        ..flag("--compress", Some("codec"), "compress the export file")
+    },
+    flag("--notional", Some("units"), "trade size in whole units of asset_a for price impact"),
+    Flag {
+        choices: &["tvl", "ada_reserve"],
+        ..flag("--by", Some("ranking"), "ranking for top")
+    },
+    flag("--tolerance", Some("ratio"), "relative reserve difference verify accepts"),
+    flag("--pair", Some("asset_a asset_b"), "watch only this pair"),
+    flag("--interval", Some("duration"), "watch and serve poll interval, e.g. 10s"),
+    flag("--ndjson", None, "watch prints events as JSON lines"),
+    flag("--in", Some("asset"), "quote input asset"),
+    flag("--out", Some("asset"), "quote output asset"),
+    flag("--amount", Some("units"), "quote input amount in base units"),
+    flag("--listen", Some("addr"), "serve address (default: 0.0.0.0:8080)"),
+    flag("--max-staleness", Some("duration"), "serve /readyz fails once pools are older, e.g. 2m"),
+    flag("--swagger-ui", None, "serve a Swagger UI at /docs"),
+    flag("--alerts", Some("path"), "JSON alert rules; watch prints their alerts, serve streams them on /events"),
+    flag("--api-keys", Some("path"), "TOML or JSON API keys and quotas serve requires"),
+    flag("--dump-dir", Some("dir"), "where serve dumps its state on SIGUSR1 or POST /admin/dump"),
+    flag("--dump-keep", Some("n"), "dumps serve keeps in --dump-dir (default 10)"),
+    flag("--order-flow", Some("duration"), "serve streams pending order imbalance on /events, polled this often"),
+    flag("--oracle-feeds", Some("path"), "JSON oracle feeds watch and serve check oracle_deviation alerts against"),
+    flag("--help-json", None, "print this command line description as JSON"),
+];
+
+/// Subcommands with their positional arguments.
+const SUBCOMMANDS: &[(&str, &str, &str)] = &[
+    ("top", "[n]", "largest pools of --dex"),
+    ("verify", "<js_pools.json>", "diff pools against a dexter JS dump"),
+    ("watch", "", "print pool changes as they happen"),
+    ("serve", "", "HTTP pool API with /events, /healthz and /readyz (server feature)"),
+    ("quote", "", "per-pool quotes and the best route for --in/--out/--amount"),
+    ("book", "<token>", "ChadSwap order book with cumulative depth"),
+    ("liquidity", "<token>", "where a token's liquidity sits, per DEX and on the book"),
+    ("new-pools", "[window]", "pools created within the window (default 1h), newest first"),
+    ("oracles", "<feeds.json>", "latest reference prices of Orcfax/Charli3 feeds"),
+    ("djed", "<pool_nft> [oracle_nft]", "DJED/SHEN reserve, circulation and reserve ratio"),
+    ("fingerprint", "<asset|asset1...> [policy_id]", "CIP-14 fingerprint of an asset, or the asset of a fingerprint"),
+    ("pool", "<pool_id>", "look a pool id up across DEXes"),
+    ("inspect", "<address_or_asset>", "Kupo matches with decoded datums"),
+    ("completions", "<bash|zsh|fish>", "print a shell completion script"),
+];
+
+/// Machine-readable description of the command line, for wrappers.
+fn help_json() -> serde_json::Value {
+    serde_json::json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "dexes": { "amm": AMM_DEXES, "other": OTHER_DEXES },
+        "subcommands": SUBCOMMANDS
+            .iter()
+            .map(|(name, args, help)| serde_json::json!({ "name": name, "args": args, "help": help }))
+            .collect::<Vec<_>>(),
+        "flags": FLAGS
+            .iter()
+            .map(|f| serde_json::json!({
+                "name": f.name,
+                "value": f.value,
+                "choices": f.choices,
+                "help": f.help,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Values offered after `flag`: files for paths, the choice list, or nothing.
+fn flag_values(flag: &Flag) -> Option<Vec<&'static str>> {
+    if flag.name == "--dex" {
+        return Some(AMM_DEXES.iter().chain(OTHER_DEXES).copied().collect());
+    }
+    (!flag.choices.is_empty()).then(|| flag.choices.to_vec())
 }
 
-fn pool_to_export(pool: &LiquidityPool, tx_hash: &str) -> PoolExport {
-    PoolExport {
-        dex: pool.dex_identifier.clone(),
-        pool_id: pool.pool_id.clone(),
-        asset_a: token_identifier(&pool.asset_a),
-        asset_b: token_identifier(&pool.asset_b),
-        reserve_a: pool.reserve_a.to_string(),
-        reserve_b: pool.reserve_b.to_string(),
-        pool_fee_percent: pool.pool_fee_percent,
-        total_lp_tokens: pool.total_lp_tokens.to_string(),
-        tx_hash: tx_hash.to_string(),
+/// Completion script for `shell`, completing subcommands, flags and flag values.
+fn completions(shell: &str, bin: &str) -> Option<String> {
+    let func = format!("_{}", bin.replace('-', "_"));
+    let words: Vec<&str> = SUBCOMMANDS
+        .iter()
+        .map(|(name, _, _)| *name)
+        .chain(FLAGS.iter().map(|f| f.name))
+        .collect();
+    let paths: Vec<&str> = FLAGS
+        .iter()
+        .filter(|f| f.value == Some("path"))
+        .map(|f| f.name)
+        .collect();
+    let mut script = String::new();
+    match shell {
+        "bash" | "zsh" => {
+            let (complete, files, words_var, prev) = if shell == "bash" {
+                (
+                    "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                    "COMPREPLY=($(compgen -f -- \"$cur\"))",
+                    "    local cur=\"${COMP_WORDS[COMP_CWORD]}\"\n",
+                    "${COMP_WORDS[COMP_CWORD-1]}",
+                )
+            } else {
+                ("compadd -- {}", "_files", "", "${words[CURRENT-1]}")
+            };
+            if shell == "zsh" {
+                script.push_str(&format!("#compdef {}\n\n", bin));
+            }
+            script.push_str(&format!("{}() {{\n{}", func, words_var));
+            script.push_str(&format!("    case \"{}\" in\n", prev));
+            for flag in FLAGS {
+                if let Some(values) = flag_values(flag) {
+                    let list = complete.replace("{}", &values.join(" "));
+                    script.push_str(&format!("        {}) {}; return ;;\n", flag.name, list));
+                }
+            }
+            script.push_str(&format!("        {}) {}; return ;;\n", paths.join("|"), files));
+            script.push_str("        completions) ");
+            script.push_str(&complete.replace("{}", "bash zsh fish"));
+            script.push_str("; return ;;\n    esac\n    ");
+            script.push_str(&complete.replace("{}", &words.join(" ")));
+            script.push_str("\n}\n");
+            if shell == "bash" {
+                script.push_str(&format!("complete -F {} {}\n", func, bin));
+            } else {
+                script.push_str(&format!("compdef {} {}\n", func, bin));
+            }
+        }
+        "fish" => {
+            for (name, _, help) in SUBCOMMANDS {
+                script.push_str(&format!(
+                    "complete -c {} -f -n __fish_use_subcommand -a {} -d '{}'\n",
+                    bin, name, help
+                ));
+            }
+            script.push_str(&format!(
+                "complete -c {} -f -n '__fish_seen_subcommand_from completions' -a 'bash zsh fish'\n",
+                bin
+            ));
+            for flag in FLAGS {
+                let long = flag.name.trim_start_matches("--");
+                let values = match (flag_values(flag), flag.value) {
+                    (Some(values), _) => format!(" -x -a '{}'", values.join(" ")),
+                    (None, Some("path")) => " -r -F".to_string(),
+                    (None, Some(_)) => " -x".to_string(),
+                    (None, None) => String::new(),
+                };
+                script.push_str(&format!(
+                    "complete -c {} -l {}{} -d '{}'\n",
+                    bin, long, values, flag.help
+                ));
+            }
+        }
+        _ => return None,
+    }
+    Some(script)
+}
+
+/// The command line split by [`FLAGS`]: the values given with each flag, in
+/// order, and the positional arguments.
+#[derive(Debug, Default)]
+struct Args {
+    flags: Vec<(&'static str, Vec<String>)>,
+    positionals: Vec<String>,
+}
+
+impl Args {
+    /// Values of every occurrence of `flag`.
+    fn all<'a>(&'a self, flag: &'a str) -> impl Iterator<Item = &'a [String]> + 'a {
+        debug_assert!(
+            FLAGS.iter().any(|f| f.name == flag),
+            "{} is not in FLAGS",
+            flag
+        );
+        self.flags
+            .iter()
+            .filter(move |(name, _)| *name == flag)
+            .map(|(_, values)| values.as_slice())
+    }
+
+    fn has(&self, flag: &str) -> bool {
+        self.all(flag).next().is_some()
+    }
+
+    /// Value of the last occurrence of `flag`.
+    fn value<'a>(&'a self, flag: &'a str) -> Option<&'a str> {
+        self.all(flag).last().map(|values| values[0].as_str())
+    }
+
+    fn string(&self, flag: &str) -> Option<String> {
+        self.value(flag).map(str::to_string)
+    }
+
+    /// [`value`](Self::value) converted by `parse`; exits with `error` when
+    /// `parse` rejects it.
+    fn parsed<T>(
+        &self,
+        flag: &str,
+        error: &str,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Option<T> {
+        self.value(flag)
+            .map(|value| parse(value).unwrap_or_else(|| fail(error)))
+    }
+
+    /// Positive duration given with `flag`; `example` is shown when it is zero.
+    fn duration(&self, flag: &str, example: &str) -> Option<std::time::Duration> {
+        self.value(flag).map(|value| match parse_duration(value) {
+            Ok(d) if !d.is_zero() => d,
+            Ok(_) => fail(format!(
+                "{} requires a positive duration (e.g. {})",
+                flag, example
+            )),
+            Err(e) => fail(e),
+        })
+    }
+}
+
+/// Split `args` (without the binary name) by [`FLAGS`]. Each flag takes one
+/// value per word of its placeholder, which must be one of its choices if it
+/// has any; an argument starting with `--` that is not in the table is an error.
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            parsed.positionals.push(arg.clone());
+            continue;
+        }
+        let flag = FLAGS
+            .iter()
+            .find(|f| f.name == arg)
+            .ok_or_else(|| format!("unknown flag {} (see --help-json)", arg))?;
+        let placeholder = flag.value.unwrap_or_default();
+        let arity = placeholder.split_whitespace().count();
+        let values: Vec<String> = args.by_ref().take(arity).cloned().collect();
+        if values.len() < arity {
+            return Err(format!("{} requires <{}>", flag.name, placeholder));
+        }
+        if !flag.choices.is_empty() && !flag.choices.contains(&values[0].as_str()) {
+            return Err(format!(
+                "{} must be one of {}",
+                flag.name,
+                flag.choices.join(", ")
+            ));
+        }
+        parsed.flags.push((flag.name, values));
+    }
+    Ok(parsed)
+}
+
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// Library export options plus the primary destination named in log lines.
+struct ExportTarget {
+    opts: ExportOptions,
+    /// Path of the JSON file, or `stdout`.
+    output: String,
+}
+
+impl ExportTarget {
+    fn describe(&self) -> String {
+        let webhooks = self.opts.sinks().len() - usize::from(!self.opts.is_streaming());
+        if webhooks > 0 {
+            format!("{} (+{} webhook)", self.output, webhooks)
+        } else {
+            self.output.clone()
+        }
     }
 }
 
 fn print_usage(bin: &str) {
-    eprintln!("Usage:");
-    eprintln!(
-        "  {} [--dex <dex_name>] [--cache <path>] [asset_a asset_b]",
+    eprint!("{}", usage(bin));
+}
+
+fn usage(bin: &str) -> String {
+    use std::fmt::Write;
+    let mut u = String::new();
+    let _ = writeln!(u, "Usage:");
+    let _ = writeln!(
+        u,
+        "  {} [--dex <dex_name>] [--cache <path>] [--decimals <path>] [--token-list <path>] [--tokens <path>] [--dex-config <path>] [--webhook <url>] [--kupo <url>]... [--kupo-policy <policy>] [--min-ada <lovelace>] [--skip-spam] [--verbose] [--max-concurrency <n>] [--output <path|->] [--compress gzip|zstd] [--notional <units>] [asset_a asset_b]",
         bin
     );
-    eprintln!(
+    let _ = writeln!(
+        u,
         "  {} --vyfi-bar <pool_identifier>",
         bin
     );
-    eprintln!();
-    eprintln!("  No args          → export all pools to pools_rs.json");
-    eprintln!("  asset_a asset_b  → print matching pools to stdout");
-    eprintln!("  --dex            → choose DEX (default: minswap_v2)");
-    eprintln!("  --cache          → path to vyfinance cache JSON file");
-    eprintln!("  --vyfi-bar       → fetch VyFi Bar rate for a pool identifier");
-    eprintln!();
-    eprintln!("  Available DEXes:");
-    eprintln!("    minswap_v1, minswap_v2");
-    eprintln!("    sundaeswap_v1, sundaeswap_v3");
-    eprintln!("    wingriders, wingriders_v2");
-    eprintln!("    cswap");
-    eprintln!("    vyfinance");
-    eprintln!("    minswap_stable  (requires: pool_address asset_a asset_b [decimals_a] [decimals_b])");
-    eprintln!("    chadswap          (requires: token_id — order book query by token)");
-    eprintln!("    chadswap_all      (no args — fetch all order books via ChadSwap API)");
-    eprintln!();
-    eprintln!("  Use 'lovelace' for ADA.");
-    eprintln!("  Examples:");
-    eprintln!("    cargo run --release -- --dex minswap_v1 lovelace f13ac4d66b3ee19a6aa0f2a22298737bd907cc95121662fc971b5275535452494b45");
-    eprintln!("    cargo run --release -- --dex vyfinance --cache vyfi_cache.json lovelace f13ac4d66b3ee19a6aa0f2a22298737bd907cc95121662fc971b5275535452494b45");
+    let _ = writeln!(u, "  {} --vyfi-bar-all <pool_ids.json>", bin);
+    let _ = writeln!(u, "  {} [--dex <dex_name>|all] top [n] [--by tvl|ada_reserve]", bin);
+    let _ = writeln!(u, "  {} [--dex <dex_name>|all] verify <js_pools.json> [--tolerance <ratio>]", bin);
+    let _ = writeln!(u, "  {} [--dex <dex_name>|all] watch [--pair <asset_a> <asset_b>] [--interval <10s>] [--ndjson] [--alerts <rules.json>] [--oracle-feeds <feeds.json>]", bin);
+    let _ = writeln!(u, "  {} [--dex <dex_name>|all] serve [--listen <addr>] [--interval <20s>] [--max-staleness <2m>] [--swagger-ui] [--alerts <rules.json>] [--api-keys <keys.toml>] [--dump-dir <dir>] [--dump-keep <n>] [--order-flow <30s>] [--oracle-feeds <feeds.json>]", bin);
+    let _ = writeln!(u, "  {} [--dex <dex_name>|all] quote --in <asset> --out <asset> --amount <units>", bin);
+    let _ = writeln!(u, "  {} [--min-ada <lovelace>] book <token>", bin);
+    let _ = writeln!(u, "  {} [--dex <dex_name>|all] liquidity <token>", bin);
+    let _ = writeln!(u, "  {} [--dex <dex_name>|all] new-pools [1h]", bin);
+    let _ = writeln!(u, "  {} oracles <feeds.json>", bin);
+    let _ = writeln!(u, "  {} djed <pool_nft> [oracle_nft]", bin);
+    let _ = writeln!(u, "  {} fingerprint <asset|asset1...> [policy_id]", bin);
+    let _ = writeln!(u, "  {} [--dex <dex_name>|all] pool <pool_id>", bin);
+    let _ = writeln!(u, "  {} inspect <address_or_asset>", bin);
+    let _ = writeln!(u, "  {} completions bash|zsh|fish", bin);
+    let _ = writeln!(u, "  {} --help-json", bin);
+    let _ = writeln!(u);
+    let _ = writeln!(u, "  No args          → export all pools to pools_rs.json (or --output)");
+    let _ = writeln!(u, "  asset_a asset_b  → print matching pools to stdout, with prices, TVL and price impact");
+    let _ = writeln!(u, "  top [n]          → print the n largest pools (default 20) of --dex, or of every AMM with --dex all");
+    let _ = writeln!(u, "  verify <path>    → diff the pools of --dex against a dexter JS pools dump; exits 1 on differences");
+    let _ = writeln!(u, "  watch            → poll --dex and print created pools and reserve/price changes as they happen, plus --alerts rule hits");
+    let _ = writeln!(u, "  serve            → serve the pools of every AMM (or --dex) over HTTP: /pools, /events (SSE), /healthz, /readyz, /openapi.json; needs --features server");
+    let _ = writeln!(u, "  quote            → quote --amount of --in for --out on every pool of --dex, plus the best (split) route");
+    let _ = writeln!(u, "  book <token>     → print the ChadSwap order book as bid/ask columns with cumulative depth");
+    let _ = writeln!(u, "  liquidity <token> → JSON report of the token's share per DEX, largest pool, concentration and ChadSwap depth (every AMM unless --dex)");
+    let _ = writeln!(u, "  new-pools [window] → pools of every AMM (or --dex) created within the window, with created_slot, newest first");
+    let _ = writeln!(u, "  oracles <path>   → read the latest price of every oracle feed in a JSON array file, in ADA per token");
+    let _ = writeln!(u, "  djed <pool_nft>  → DJED/SHEN reserve and circulation; with the oracle NFT also reserve ratio and prices");
+    let _ = writeln!(u, "  fingerprint <x>  → asset1... fingerprint of a unit or ticker; a fingerprint resolves from --tokens or, with its policy id, from Kupo");
+    let _ = writeln!(u, "  pool <pool_id>   → look the pool up on every AMM (or --dex) and print it with its quote");
+    let _ = writeln!(u, "  inspect <pattern> → dump unspent Kupo matches (address, policy id, asset or Kupo pattern) with decoded datums");
+    let _ = writeln!(u, "  completions      → print a completion script, e.g. source <({} completions bash)", bin);
+    let _ = writeln!(u, "  --help-json      → print subcommands, flags and DEXes as JSON for wrappers");
+    let _ = writeln!(u, "  --dex            → choose DEX (default: minswap_v2)");
+    let _ = writeln!(u, "  --cache          → path to vyfinance cache JSON file");
+    let _ = writeln!(u, "  --vyfi-bar       → fetch VyFi Bar rate for a pool identifier");
+    let _ = writeln!(u, "  --vyfi-bar-all   → fetch VyFi Bar rates for every pool identifier in a JSON array file");
+    let _ = writeln!(u, "  --decimals       → JSON map of token identifier → decimals");
+    let _ = writeln!(u, "  --token-list     → JSON allowlist/denylist; adds `verified` to exported pools");
+    let _ = writeln!(u, "  --tokens         → JSON map of ticker → identifier; asset_a/asset_b may then be tickers");
+    let _ = writeln!(u, "  --dex-config     → TOML or JSON file overriding DEX pool addresses, validity assets, LP policies, fees; watch and serve reload it on change");
+    let _ = writeln!(u, "  --webhook        → also POST the export (gzip JSON batches) to this URL");
+    let _ = writeln!(u, "  --kupo           → Kupo endpoint; repeat for failover / load balancing");
+    let _ = writeln!(u, "  --kupo-policy    → failover (default) or round-robin across --kupo endpoints");
+    let _ = writeln!(u, "  --min-ada        → drop ADA pools whose ADA reserve (ChadSwap orders whose value) is below this many lovelace");
+    let _ = writeln!(u, "  --skip-spam      → drop pools where one side holds a single unit (NFT spam)");
+    let _ = writeln!(u, "  --verbose        → log which filter rule dropped each pool");
+    let _ = writeln!(u, "  --max-concurrency → cap for adaptive export concurrency (default 64; starts at 5)");
+    let _ = writeln!(u, "  --output         → export path (default pools_rs.json); '-' streams NDJSON rows to stdout;");
+    let _ = writeln!(u, "                     .db/.sqlite and .parquet need the sqlite and parquet features");
+    let _ = writeln!(u, "  --compress       → write the export as pools_rs.json.gz (gzip) or pools_rs.json.zst (zstd)");
+    let _ = writeln!(u, "  --notional       → trade size in whole units of asset_a for the pair query's price impact");
+    let _ = writeln!(u, "  --by             → ranking for `top`: tvl (default) or ada_reserve");
+    let _ = writeln!(u, "  --tolerance      → relative reserve difference `verify` accepts (default 0: exact)");
+    let _ = writeln!(u, "  --pair           → `watch` only this pair (identifiers or tickers)");
+    let _ = writeln!(u, "  --interval       → `watch` poll interval, e.g. 10s, 500ms, 2m (default 20s)");
+    let _ = writeln!(u, "  --ndjson         → `watch` prints each event as a JSON line instead of a summary");
+    let _ = writeln!(u, "  --in / --out     → `quote` input and output assets (identifiers or tickers)");
+    let _ = writeln!(u, "  --amount         → `quote` input amount in base units (lovelace for ADA)");
+    let _ = writeln!(u);
+    let _ = writeln!(u, "  Available DEXes:");
+    let _ = writeln!(u, "    minswap_v1, minswap_v2");
+    let _ = writeln!(u, "    sundaeswap_v1, sundaeswap_v3");
+    let _ = writeln!(u, "    wingriders, wingriders_v2");
+    let _ = writeln!(u, "    cswap");
+    let _ = writeln!(u, "    vyfinance");
+    let _ = writeln!(u, "    minswap_stable  (requires: pool_address asset_a asset_b [decimals_a] [decimals_b])");
+    let _ = writeln!(u, "    chadswap          (requires: token_id — order book query by token)");
+    let _ = writeln!(u, "    chadswap_all      (no args — fetch all order books via ChadSwap API)");
+    let _ = writeln!(u);
+    let _ = writeln!(u, "  Use 'lovelace' or 'ADA' for ADA. Tickers resolve through --tokens and the --token-list allowlist.");
+    let _ = writeln!(u, "  Examples:");
+    let _ = writeln!(u, "    cargo run --release -- --dex minswap_v1 lovelace f13ac4d66b3ee19a6aa0f2a22298737bd907cc95121662fc971b5275535452494b45");
+    let _ = writeln!(u, "    cargo run --release -- --dex vyfinance --cache vyfi_cache.json lovelace f13ac4d66b3ee19a6aa0f2a22298737bd907cc95121662fc971b5275535452494b45");
+    u
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let raw_args: Vec<String> = std::env::args().collect();
 
-    let mut dex_name = "minswap_v2".to_string();
-    let mut cache_path: Option<String> = None;
-    let mut vyfi_bar_id: Option<String> = None;
-    let mut assets: Vec<String> = Vec::new();
-    let mut i = 1;
-    while i < raw_args.len() {
-        if raw_args[i] == "--dex" {
-            i += 1;
-            if i >= raw_args.len() {
-                eprintln!("--dex requires a value");
+    let args = parse_args(&raw_args[1..]).unwrap_or_else(|e| fail(e));
+    if args.has("--help-json") {
+        println!("{}", serde_json::to_string_pretty(&help_json())?);
+        return Ok(());
+    }
+
+    let dex_given = args.has("--dex");
+    let dex_name = args.value("--dex").unwrap_or("minswap_v2").to_string();
+    let cache_path = args.string("--cache");
+    let vyfi_bar_id = args.string("--vyfi-bar");
+    let vyfi_bar_pools_path = args.string("--vyfi-bar-all");
+    let decimals_path = args.string("--decimals");
+    let token_list_path = args.string("--token-list");
+    let tokens_path = args.string("--tokens");
+    let dex_config_path = args.string("--dex-config");
+    let webhook_url = args.string("--webhook");
+    let mut kupo_urls: Vec<String> = args.all("--kupo").map(|v| v[0].clone()).collect();
+    let kupo_policy = match args.value("--kupo-policy") {
+        Some("round-robin") => EndpointPolicy::RoundRobin,
+        _ => EndpointPolicy::Failover,
+    };
+    let mut filter = PoolFilter::default();
+    if let Some(lovelace) = args.parsed("--min-ada", "--min-ada requires a lovelace amount", |v| {
+        v.parse::<u64>().ok()
+    }) {
+        filter.min_ada_reserve = lovelace;
+    }
+    filter.skip_spam_nft_pools = args.has("--skip-spam");
+    filter.verbose = args.has("--verbose");
+    let max_concurrency = args
+        .parsed("--max-concurrency", "--max-concurrency requires a positive number", |v| {
+            v.parse::<usize>().ok().filter(|n| *n > 0)
+        })
+        .unwrap_or(DEFAULT_MAX_CONCURRENCY);
+    let rank_by = match args.value("--by").map(str::parse::<RankBy>) {
+        Some(Ok(by)) => by,
+        Some(Err(e)) => fail(e),
+        None => RankBy::default(),
+    };
+    let tolerance = args
+        .parsed("--tolerance", "--tolerance requires a non-negative ratio (e.g. 0.001)", |v| {
+            v.parse::<f64>().ok().filter(|t| *t >= 0.0)
+        })
+        .unwrap_or(0.0);
+    let compression = args.value("--compress").and_then(Compression::parse);
+    let output = args.string("--output");
+    let notional = args.parsed("--notional", "--notional requires a positive amount (e.g. 1000)", |v| {
+        v.parse::<f64>().ok().filter(|n| *n > 0.0)
+    });
+    let mut pair = args
+        .all("--pair")
+        .last()
+        .map(|v| (v[0].clone(), v[1].clone()));
+    let interval = args.duration("--interval", "10s");
+    let ndjson = args.has("--ndjson");
+    let quote_in = args.string("--in");
+    let quote_out = args.string("--out");
+    let quote_amount = args.parsed(
+        "--amount",
+        "--amount requires a positive amount in base units (e.g. 500000000)",
+        |v| v.parse::<u64>().ok().filter(|n| *n > 0),
+    );
+    let listen = args.value("--listen").unwrap_or("0.0.0.0:8080").to_string();
+    let max_staleness = args.duration("--max-staleness", "2m");
+    let swagger_ui = args.has("--swagger-ui");
+    let alerts_path = args.string("--alerts");
+    let api_keys_path = args.string("--api-keys");
+    let dump_dir = args.string("--dump-dir");
+    let dump_keep = args.parsed("--dump-keep", "--dump-keep requires a positive number", |v| {
+        v.parse::<usize>().ok().filter(|n| *n > 0)
+    });
+    let order_flow = args.duration("--order-flow", "30s");
+    let oracle_feeds_path = args.string("--oracle-feeds");
+    let mut assets = args.positionals;
+
+    if assets.first().map(String::as_str) == Some("completions") {
+        let bin = std::path::Path::new(&raw_args[0])
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or(env!("CARGO_PKG_NAME"));
+        match assets.get(1).and_then(|shell| completions(shell, bin)) {
+            Some(script) => print!("{}", script),
+            None => {
+                eprintln!("completions requires bash, zsh or fish");
                 std::process::exit(1);
             }
-            dex_name = raw_args[i].clone();
-        } else if raw_args[i] == "--cache" {
-            i += 1;
-            if i >= raw_args.len() {
-                eprintln!("--cache requires a path");
+        }
+        return Ok(());
+    }
+
+    let decimals = match &decimals_path {
+        Some(path) => {
+            let decimals = DecimalsOverrides::load_file(path)?;
+            eprintln!("Loaded {} decimals overrides from {}", decimals.len(), path);
+            decimals
+        }
+        None => DecimalsOverrides::default(),
+    };
+
+    if kupo_urls.is_empty() {
+        kupo_urls.push(KUPO_URL.to_string());
+    }
+    let kupo_urls: Vec<&str> = kupo_urls.iter().map(String::as_str).collect();
+
+    let token_lists: Option<TokenLists> = match &token_list_path {
+        Some(path) => Some(
+            cache::load_from_file(path)
+                .map_err(|e| format!("Failed to load token lists: {}", e))?,
+        ),
+        None => None,
+    };
+    let verifier = token_lists.clone().map(TokenVerifier::new);
+    let mut registry = TokenRegistry::new();
+    if let Some(lists) = &token_lists {
+        registry = registry.with_token_lists(lists);
+    }
+    if let Some(path) = &tokens_path {
+        let count = registry.load_file(path)?;
+        eprintln!("Loaded {} tickers from {}", count, path);
+    }
+    let overrides = match &dex_config_path {
+        Some(path) => {
+            let overrides = OverridesConfig::load_file(path)?;
+            eprintln!("Overriding constants of {} from {}", overrides.identifiers().join(", "), path);
+            overrides
+        }
+        None => OverridesConfig::new(),
+    };
+    if let Some((a, b)) = pair.as_mut() {
+        for asset in [a, b] {
+            match registry.resolve(asset) {
+                Ok(id) => *asset = id.to_string(),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+    let mut opts = ExportOptions::new()
+        .with_max_concurrency(max_concurrency)
+        .with_progress(true)
+        .with_decimals_overrides(decimals.clone());
+    if let Some(verifier) = verifier {
+        opts = opts.with_verifier(verifier);
+    }
+    if let Some(notional) = notional {
+        opts = opts.with_notional(notional);
+    }
+    // The JSON file (unless streaming to stdout) always comes first.
+    let output = match output.as_deref() {
+        Some("-") => {
+            opts = opts.with_stream(NdjsonStdoutSink::new());
+            "stdout".to_string()
+        }
+        Some(path) => {
+            opts = opts.with_sink(file_sink(path, compression));
+            path.to_string()
+        }
+        None => {
+            let path = match compression.and_then(|c| c.extension()) {
+                Some(ext) => format!("{}.{}", EXPORT_PATH, ext),
+                None => EXPORT_PATH.to_string(),
+            };
+            opts = opts.with_sink(Box::new(JsonFileSink::new(&path)));
+            path
+        }
+    };
+    if let Some(url) = &webhook_url {
+        opts = opts.with_sink(Box::new(HttpPostSink::new(url).with_source(kupo_urls[0])));
+    }
+    let target = &ExportTarget { opts, output };
+
+    let kupo = KupoApi::with_endpoints(&kupo_urls, kupo_policy);
+
+    if let Some(path) = vyfi_bar_pools_path {
+        let pool_ids: Vec<String> = cache::load_from_file(&path)?;
+        let dex = VyfiBar::new(kupo)
+            .with_bar_pools(pool_ids)
+            .with_decimals_overrides(decimals);
+        fetch_all_vyfi_bar_rates(dex).await?;
+        return Ok(());
+    }
+
+    if let Some(pool_id) = vyfi_bar_id {
+        let dex = VyfiBar::new(kupo).with_decimals_overrides(decimals);
+        fetch_vyfi_bar_rate(dex, &pool_id).await?;
+        return Ok(());
+    }
+
+    if assets.first().map(String::as_str) == Some("top") {
+        let n = match assets.get(1).map(|v| v.parse::<usize>()) {
+            Some(Ok(n)) => n,
+            Some(Err(_)) => {
+                print_usage(&raw_args[0]);
                 std::process::exit(1);
             }
-            cache_path = Some(raw_args[i].clone());
-        } else if raw_args[i] == "--vyfi-bar" {
-            i += 1;
-            if i >= raw_args.len() {
-                eprintln!("--vyfi-bar requires a pool identifier");
+            None => 20,
+        };
+        let Some(dexes) = amm_dexes(&dex_name, &kupo, &filter, &overrides)? else {
+            eprintln!("top supports the AMM DEXes or 'all', not '{}'", dex_name);
+            std::process::exit(1);
+        };
+        let dexes = dexes.dexes();
+        eprintln!("Ranking pools of {} DEX(es) by {:?}...", dexes.len(), rank_by);
+        let top = top_pools_from(&dexes, n, rank_by).await?;
+        println!("{}", serde_json::to_string_pretty(&top)?);
+        return Ok(());
+    }
+
+    if assets.first().map(String::as_str) == Some("verify") {
+        let Some(path) = assets.get(1) else {
+            print_usage(&raw_args[0]);
+            std::process::exit(1);
+        };
+        let Some(dexes) = amm_dexes(&dex_name, &kupo, &filter, &overrides)? else {
+            eprintln!("verify supports the AMM DEXes or 'all', not '{}'", dex_name);
+            std::process::exit(1);
+        };
+        let reference = parity::load_reference_pools(path)?;
+        eprintln!("Comparing {} JS pools against {} DEX(es)...", reference.len(), dexes.len());
+        let pools = dexes.all_liquidity_pools().await;
+        // Only compare the JS pools of the DEXes that were fetched.
+        let fetched: Vec<String> = dexes.identifiers().iter().map(|d| parity::dex_key(d)).collect();
+        let reference: Vec<_> = reference
+            .into_iter()
+            .filter(|p| fetched.contains(&parity::dex_key(&p.dex)))
+            .collect();
+        let report = parity::compare(&reference, &pools, tolerance);
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        eprintln!(
+            "matched {} | missing in rust {} | missing in js {} | reserve diffs {} | fee diffs {}",
+            report.matched,
+            report.missing_in_rust.len(),
+            report.missing_in_js.len(),
+            report.reserve_mismatches.len(),
+            report.fee_mismatches.len()
+        );
+        if !report.is_clean() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if assets.first().map(String::as_str) == Some("watch") {
+        let Some(dexes) = amm_dexes(&dex_name, &kupo, &filter, &overrides)? else {
+            eprintln!("watch supports the AMM DEXes or 'all', not '{}'", dex_name);
+            std::process::exit(1);
+        };
+        let mut watcher = PoolWatcher::new(dexes.shared());
+        if let Some(interval) = interval {
+            watcher = watcher.with_interval(interval);
+        }
+        if let Some((a, b)) = &pair {
+            watcher = watcher.with_pair(a, b);
+        }
+        // Edits to --dex-config (new addresses, validity assets) apply on the next round.
+        if let Some(path) = &dex_config_path {
+            watcher = watcher.with_config_reload(dex_config_reload(path, &dex_name, &kupo, &filter));
+        }
+        // --alerts rules fire on the watched events, checked against --oracle-feeds.
+        let alerter = match &alerts_path {
+            Some(path) => {
+                let mut alerter = Alerter::new(dexter_kupo_rs::watcher::load_rules(path)?);
+                if let Some(feeds) = &oracle_feeds_path {
+                    alerter = alerter.with_oracles(OracleReader::new(&kupo).with_feeds(load_feeds(feeds)?));
+                }
+                Some(alerter.with_supply_tracker(SupplyTracker::new(&kupo)))
+            }
+            None => None,
+        };
+        eprintln!("Watching {} DEX(es); the first round seeds the pool set...", dexes.len());
+        let mut events = watcher.spawn();
+        while let Some(event) = events.recv().await {
+            if ndjson {
+                println!("{}", serde_json::to_string(&event)?);
+            } else {
+                println!("{}", event.summary());
+            }
+            let Some(alerter) = &alerter else {
+                continue;
+            };
+            alerter.refresh_reference_prices().await;
+            let mut alerts = alerter.evaluate(&event);
+            alerts.extend(alerter.check_supply().await);
+            for alert in alerts {
+                if ndjson {
+                    println!("{}", serde_json::to_string(&alert)?);
+                } else {
+                    println!("alert {} {} {}: {}", alert.dex_identifier, alert.pool_id, alert.pair, alert.message);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    if assets.first().map(String::as_str) == Some("serve") {
+        #[cfg(feature = "server")]
+        {
+            // Without --dex, every AMM is served.
+            let name = if dex_given { dex_name.as_str() } else { "all" };
+            let Some(dexes) = amm_dexes(name, &kupo, &filter, &overrides)? else {
+                eprintln!("serve supports the AMM DEXes or 'all', not '{}'", dex_name);
                 std::process::exit(1);
+            };
+            let mut server = Server::new(&kupo, dexes.shared());
+            if let Some(interval) = interval {
+                server = server.with_interval(interval);
             }
-            vyfi_bar_id = Some(raw_args[i].clone());
-        } else {
-            assets.push(raw_args[i].clone());
+            if let Some(max_staleness) = max_staleness {
+                server = server.with_max_staleness(max_staleness);
+            }
+            if let Some(path) = &dex_config_path {
+                server = server.with_config_reload(dex_config_reload(path, name, &kupo, &filter));
+            }
+            if swagger_ui {
+                server = server.with_swagger_ui();
+            }
+            if let Some(path) = &alerts_path {
+                server = server.with_alerts(dexter_kupo_rs::watcher::load_rules(path)?);
+            }
+            if let Some(path) = &api_keys_path {
+                server = server.with_access(AccessConfig::load_file(path)?);
+            }
+            if let Some(dir) = &dump_dir {
+                server = server.with_dump_dir(dir);
+            }
+            if let Some(keep) = dump_keep {
+                server = server.with_dump_retention(keep);
+            }
+            if let Some(interval) = order_flow {
+                server = server.with_order_flow(interval);
+            }
+            if let Some(path) = &oracle_feeds_path {
+                server = server.with_oracles(OracleReader::new(&kupo).with_feeds(load_feeds(path)?));
+            }
+            eprintln!("Serving {} DEX(es) on http://{}", dexes.len(), listen);
+            server.serve(&listen).await?;
+            return Ok(());
+        }
+        #[cfg(not(feature = "server"))]
+        {
+            let _ = (&listen, max_staleness, swagger_ui, &alerts_path, &api_keys_path, &dump_dir, dump_keep, order_flow, &oracle_feeds_path);
+            eprintln!("serve needs a build with --features server");
+            std::process::exit(1);
         }
-        i += 1;
     }
 
-    let kupo = KupoApi::new(KUPO_URL);
+    if assets.first().map(String::as_str) == Some("quote") {
+        let (Some(token_in), Some(token_out), Some(amount)) = (&quote_in, &quote_out, quote_amount)
+        else {
+            eprintln!("quote requires --in <asset> --out <asset> --amount <units>");
+            std::process::exit(1);
+        };
+        let (token_in, token_out) = match (registry.resolve(token_in), registry.resolve(token_out)) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        let Some(dexes) = amm_dexes(&dex_name, &kupo, &filter, &overrides)? else {
+            eprintln!("quote supports the AMM DEXes or 'all', not '{}'", dex_name);
+            std::process::exit(1);
+        };
+        eprintln!("Quoting {} {} -> {} on {} DEX(es)...", amount, token_in, token_out, dexes.len());
+        let pools = dexes.pair_pools(token_in.clone(), token_out.clone()).await?;
+        let router = Router::new().with_pools(pools);
+        // Decimals do not matter for routing; pools are matched by identifier.
+        let (token_in, token_out) = (token_in.to_token(0), token_out.to_token(0));
+        let quotes = router.pool_routes(&token_in, &token_out, amount);
+        if quotes.is_empty() {
+            eprintln!("No pools found.");
+            std::process::exit(1);
+        }
+        for route in &quotes {
+            let hop = &route.allocations[0];
+            eprintln!(
+                "  {:<14} {} → {} (net {})",
+                hop.dex_identifier, hop.pool_id, route.amount_out, route.net_amount_out
+            );
+        }
+        let best = router.split_route(&token_in, &token_out, amount);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "quotes": quotes,
+                "best_route": best,
+            }))?
+        );
+        return Ok(());
+    }
 
-    if let Some(pool_id) = vyfi_bar_id {
-        fetch_vyfi_bar_rate(VyfiBar::new(kupo), &pool_id).await?;
+    if assets.first().map(String::as_str) == Some("pool") {
+        let Some(pool_id) = assets.get(1) else {
+            print_usage(&raw_args[0]);
+            std::process::exit(1);
+        };
+        // Without --dex, every AMM is asked.
+        let name = if dex_given { dex_name.as_str() } else { "all" };
+        let Some(dexes) = amm_dexes(name, &kupo, &filter, &overrides)? else {
+            eprintln!("pool supports the AMM DEXes or 'all', not '{}'", dex_name);
+            std::process::exit(1);
+        };
+        eprintln!("Looking up pool {} on {} DEX(es)...", pool_id, dexes.len());
+        let pools = dexes.pools_by_id(pool_id).await?;
+        if pools.is_empty() {
+            eprintln!("No pools found.");
+            std::process::exit(1);
+        }
+        StdoutSink::new().write_pools(&target.opts.pair_rows(&pools)).await?;
         return Ok(());
     }
 
+    if assets.first().map(String::as_str) == Some("inspect") {
+        let Some(input) = assets.get(1) else {
+            print_usage(&raw_args[0]);
+            std::process::exit(1);
+        };
+        eprintln!("Matching {}...", inspect::match_pattern(input));
+        let outputs = inspect::inspect(&kupo, input, true).await?;
+        eprintln!("Found {} output(s).", outputs.len());
+        println!("{}", serde_json::to_string_pretty(&outputs)?);
+        return Ok(());
+    }
+
+    if assets.first().map(String::as_str) == Some("book") {
+        let Some(token) = assets.get(1) else {
+            print_usage(&raw_args[0]);
+            std::process::exit(1);
+        };
+        let token = match registry.resolve(token) {
+            Ok(id) => id,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        // ChadSwap is the only order-book DEX so far.
+        let dex = ChadSwap::new().with_min_order_lovelace(filter.min_ada_reserve);
+        let book = dex.get_orders_by_token(token.as_str()).await?;
+        print_book_depth(&book.depth());
+        return Ok(());
+    }
+
+    if assets.first().map(String::as_str) == Some("liquidity") {
+        let Some(token) = assets.get(1) else {
+            print_usage(&raw_args[0]);
+            std::process::exit(1);
+        };
+        let token = match registry.resolve(token) {
+            Ok(id) => id,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        // Without --dex, every AMM is asked.
+        let name = if dex_given { dex_name.as_str() } else { "all" };
+        let Some(dexes) = amm_dexes(name, &kupo, &filter, &overrides)? else {
+            eprintln!("liquidity supports the AMM DEXes or 'all', not '{}'", dex_name);
+            std::process::exit(1);
+        };
+        let chadswap = ChadSwap::new().with_min_order_lovelace(filter.min_ada_reserve);
+        eprintln!("Collecting {} liquidity on {} DEX(es) and ChadSwap...", token, dexes.len());
+        let report = token_liquidity(&dexes.dexes(), &chadswap, token).await?;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if assets.first().map(String::as_str) == Some("new-pools") {
+        let window = match assets.get(1).map(|w| parse_duration(w)).transpose() {
+            Ok(window) => window.unwrap_or(std::time::Duration::from_secs(3_600)),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        // Without --dex, every AMM is asked.
+        let name = if dex_given { dex_name.as_str() } else { "all" };
+        let Some(dexes) = amm_dexes(name, &kupo, &filter, &overrides)? else {
+            eprintln!("new-pools supports the AMM DEXes or 'all', not '{}'", dex_name);
+            std::process::exit(1);
+        };
+        eprintln!("Looking for pools created in the last {:?} on {} DEX(es)...", window, dexes.len());
+        let (pools, tip) = futures::join!(dexes.all_liquidity_pools(), kupo.tip_slot());
+        let pools = CreationResolver::new(&kupo).new_pools(pools, tip?, window).await?;
+        let exports: Vec<PoolExport> = pools.iter().map(pool_to_export).collect();
+        println!("{}", serde_json::to_string_pretty(&exports)?);
+        return Ok(());
+    }
+
+    if assets.first().map(String::as_str) == Some("oracles") {
+        let Some(path) = assets.get(1) else {
+            eprintln!("oracles requires a path to a JSON feeds file");
+            std::process::exit(1);
+        };
+        let reader = OracleReader::new(&kupo).with_feeds(load_feeds(path)?);
+        eprintln!("Reading {} oracle feed(s)...", reader.feeds().len());
+        println!("{}", serde_json::to_string_pretty(&reader.get_prices().await)?);
+        return Ok(());
+    }
+
+    if assets.first().map(String::as_str) == Some("djed") {
+        let Some(pool) = assets.get(1) else {
+            eprintln!("djed requires the stability pool NFT (policy.name)");
+            std::process::exit(1);
+        };
+        let mut reader = DjedReader::new(kupo).with_pool(pool.as_str());
+        if let Some(oracle) = assets.get(2) {
+            reader = reader.with_oracle(oracle.as_str());
+        }
+        let state = reader.get_state().await?;
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "state": state,
+            "reserve_ratio": state.reserve_ratio(),
+            "djed_price_lovelace": state.djed_price_lovelace(),
+            "shen_price_lovelace": state.shen_price_lovelace(),
+        }))?);
+        return Ok(());
+    }
+
+    if assets.first().map(String::as_str) == Some("fingerprint") {
+        let Some(input) = assets.get(1) else {
+            print_usage(&raw_args[0]);
+            std::process::exit(1);
+        };
+        let id = match (AssetId::is_fingerprint(input), assets.get(2)) {
+            (true, Some(policy_id)) => match registry.resolve_fingerprint(input) {
+                Some(id) => Ok(id),
+                None => find_fingerprint(&kupo, input, policy_id).await,
+            },
+            _ => registry.resolve(input),
+        };
+        let id = match id {
+            Ok(id) => id,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        println!("{}", serde_json::to_string_pretty(&serde_json::json!({
+            "asset_id": id,
+            "policy_id": id.policy_id(),
+            "name_hex": id.name_hex(),
+            "fingerprint": id.fingerprint(),
+        }))?);
+        return Ok(());
+    }
+
+    // Tickers are resolved for the AMM pair query, after the subcommands have
+    // had their turn; other modes take raw ids.
+    if assets.len() == 2 && AMM_DEXES.contains(&dex_name.as_str()) {
+        for asset in assets.iter_mut() {
+            match registry.resolve(asset) {
+                Ok(id) => *asset = id.to_string(),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
     match dex_name.as_str() {
-        "minswap_v1" => run(MinswapV1::new(kupo), &assets, &raw_args[0]).await?,
-        "minswap_v2" => run(MinswapV2::new(kupo), &assets, &raw_args[0]).await?,
-        "sundaeswap_v1" => run(SundaeSwapV1::new(kupo), &assets, &raw_args[0]).await?,
-        "sundaeswap_v3" => run(SundaeSwapV3::new(kupo), &assets, &raw_args[0]).await?,
-        "wingriders" => run(WingRiders::new(kupo), &assets, &raw_args[0]).await?,
-        "wingriders_v2" => run(WingRidersV2::new(kupo), &assets, &raw_args[0]).await?,
-        "cswap" => run(CSwap::new(kupo), &assets, &raw_args[0]).await?,
+        "minswap_v1" => {
+            let dex = MinswapV1::new(kupo)
+                .with_pool_filter(filter)
+                .with_overrides(&overrides.get("MinswapV1"))?;
+            run(dex, &assets, &raw_args[0], target).await?
+        }
+        "minswap_v2" => {
+            let dex = MinswapV2::new(kupo)
+                .with_pool_filter(filter)
+                .with_overrides(&overrides.get("MinswapV2"))?;
+            run(dex, &assets, &raw_args[0], target).await?
+        }
+        "sundaeswap_v1" => {
+            let dex = SundaeSwapV1::new(kupo)
+                .with_pool_filter(filter)
+                .with_overrides(&overrides.get("SundaeSwapV1"))?;
+            run(dex, &assets, &raw_args[0], target).await?
+        }
+        "sundaeswap_v3" => {
+            let dex = SundaeSwapV3::new(kupo)
+                .with_pool_filter(filter)
+                .with_overrides(&overrides.get("SundaeSwapV3"))?;
+            run(dex, &assets, &raw_args[0], target).await?
+        }
+        "wingriders" => {
+            let dex = WingRiders::new(kupo)
+                .with_pool_filter(filter)
+                .with_overrides(&overrides.get("WingRiders"))?;
+            run(dex, &assets, &raw_args[0], target).await?
+        }
+        "wingriders_v2" => {
+            let dex = WingRidersV2::new(kupo)
+                .with_pool_filter(filter)
+                .with_overrides(&overrides.get("WingRidersV2"))?;
+            run(dex, &assets, &raw_args[0], target).await?
+        }
+        "cswap" => {
+            let dex = CSwap::new(kupo)
+                .with_pool_filter(filter)
+                .with_overrides(&overrides.get("CSwap"))?;
+            run(dex, &assets, &raw_args[0], target).await?
+        }
         "vyfinance" => {
-            let dex = VyFinance::new(kupo);
+            let dex = VyFinance::new(kupo).with_pool_filter(filter);
             if assets.len() == 2 {
                 let cache = if let Some(path) = &cache_path {
                     match cache::load_from_file::<VyFinanceCache>(path) {
                         Ok(c) => Some(c),
-                        Err(_) => {
-                            eprintln!("Cache file not found or invalid. Fetching from API...");
+                        Err(e) => {
+                            eprintln!("{}. Fetching from API...", e);
                             let pool_data = dex.fetch_all_pool_data().await?;
                             let c = VyFinance::structure_pool_data(pool_data);
                             let _ = cache::save_to_file(&c, path);
@@ -179,11 +1092,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     eprintln!("No pools found.");
                 } else {
                     eprintln!("Found {} pool(s).", pools.len());
-                    let exports: Vec<PoolExport> = pools.iter().map(|p| pool_to_export(p, "")).collect();
-                    println!("{}", serde_json::to_string_pretty(&exports)?);
+                    let exports = target.opts.pair_rows(&pools);
+                    StdoutSink::new().write_pools(&exports).await?;
                 }
             } else if assets.is_empty() {
-                export_all_vyfinance(dex).await?;
+                export_all_vyfinance(dex, target).await?;
             } else {
                 print_usage(&raw_args[0]);
                 std::process::exit(1);
@@ -195,10 +1108,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 print_usage(&raw_args[0]);
                 std::process::exit(1);
             }
-            fetch_chadswap_orders(ChadSwap::new(), &assets[0]).await?;
+            let dex = ChadSwap::new().with_min_order_lovelace(filter.min_ada_reserve);
+            fetch_chadswap_orders(dex, &assets[0]).await?;
         }
         "chadswap_all" => {
-            let dex = ChadSwap::new();
+            let dex = ChadSwap::new().with_min_order_lovelace(filter.min_ada_reserve);
             let books = dex.get_all_order_books().await?;
             eprintln!("[chadswap] found order books for {} tokens", books.len());
             println!("{}", serde_json::to_string_pretty(&books)?);
@@ -237,14 +1151,52 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// AMM DEXes for `name`, or all of them for `"all"`; `None` for non-AMM names.
+fn amm_dexes(
+    name: &str,
+    kupo: &KupoApi,
+    filter: &PoolFilter,
+    overrides: &OverridesConfig,
+) -> anyhow::Result<Option<Aggregator>> {
+    if name == "all" {
+        return Aggregator::with_builtin_dexes_and_overrides(kupo, filter, overrides).map(Some);
+    }
+    let filter = filter.clone();
+    let o = |identifier: &str| overrides.get(identifier);
+    let dex: Box<dyn BaseDex> = match name {
+        "minswap_v1" => Box::new(MinswapV1::new(kupo).with_pool_filter(filter).with_overrides(&o("MinswapV1"))?),
+        "minswap_v2" => Box::new(MinswapV2::new(kupo).with_pool_filter(filter).with_overrides(&o("MinswapV2"))?),
+        "sundaeswap_v1" => Box::new(SundaeSwapV1::new(kupo).with_pool_filter(filter).with_overrides(&o("SundaeSwapV1"))?),
+        "sundaeswap_v3" => Box::new(SundaeSwapV3::new(kupo).with_pool_filter(filter).with_overrides(&o("SundaeSwapV3"))?),
+        "wingriders" => Box::new(WingRiders::new(kupo).with_pool_filter(filter).with_overrides(&o("WingRiders"))?),
+        "wingriders_v2" => Box::new(WingRidersV2::new(kupo).with_pool_filter(filter).with_overrides(&o("WingRidersV2"))?),
+        "cswap" => Box::new(CSwap::new(kupo).with_pool_filter(filter).with_overrides(&o("CSwap"))?),
+        "vyfinance" => Box::new(VyFinance::new(kupo).with_pool_filter(filter)),
+        _ => return Ok(None),
+    };
+    Ok(Aggregator::new().with_dex(dex).ok())
+}
+
+/// Rebuild the `name` DEX set from the overrides file whenever it changes.
+fn dex_config_reload(path: &str, name: &str, kupo: &KupoApi, filter: &PoolFilter) -> ConfigReload {
+    let (name, kupo, filter) = (name.to_string(), kupo.clone(), filter.clone());
+    ConfigReload::new(path, move |path| {
+        let overrides = OverridesConfig::load_file(path)?;
+        Ok(amm_dexes(&name, &kupo, &filter, &overrides)?
+            .map(|d| d.shared())
+            .unwrap_or_default())
+    })
+}
+
 async fn run<D: BaseDex + Send + Sync + 'static>(
     dex: D,
     assets: &[String],
     bin: &str,
+    target: &ExportTarget,
 ) -> Result<(), Box<dyn std::error::Error>> {
     match assets.len() {
-        0 => export_all(dex).await,
-        2 => fetch_pair(dex, &assets[0], &assets[1]).await,
+        0 => export_all(dex, target).await,
+        2 => print_pair(dex, &assets[0], &assets[1], target).await,
         _ => {
             print_usage(bin);
             std::process::exit(1);
@@ -252,20 +1204,20 @@ async fn run<D: BaseDex + Send + Sync + 'static>(
     }
 }
 
-async fn fetch_pair<D: BaseDex>(
+async fn print_pair<D: BaseDex>(
     dex: D,
     asset_a: &str,
     asset_b: &str,
+    target: &ExportTarget,
 ) -> Result<(), Box<dyn std::error::Error>> {
     eprintln!("Querying pools for {} / {}...", asset_a, asset_b);
-    let pools = dex.liquidity_pools_from_token(asset_b, asset_a).await?;
+    let exports = fetch_pair(&dex, asset_a, asset_b, &target.opts).await?;
 
-    if pools.is_empty() {
+    if exports.is_empty() {
         eprintln!("No pools found.");
     } else {
-        eprintln!("Found {} pool(s).", pools.len());
-        let exports: Vec<PoolExport> = pools.iter().map(|p| pool_to_export(p, "")).collect();
-        println!("{}", serde_json::to_string_pretty(&exports)?);
+        eprintln!("Found {} pool(s).", exports.len());
+        StdoutSink::new().write_pools(&exports).await?;
     }
 
     Ok(())
@@ -273,83 +1225,23 @@ async fn fetch_pair<D: BaseDex>(
 
 async fn export_all<D: BaseDex + Send + Sync + 'static>(
     dex: D,
+    target: &ExportTarget,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let dex = Arc::new(dex);
-
     eprintln!("Fetching all pool UTXOs...");
-    let utxos = Arc::new(dex.all_liquidity_pool_utxos().await?);
-    let total = utxos.len();
-    eprintln!("Found {} UTXOs", total);
-
-    let semaphore = Arc::new(Semaphore::new(CONCURRENCY));
-    let done = Arc::new(AtomicUsize::new(0));
-    let skipped = Arc::new(AtomicUsize::new(0));
-    let mut handles = Vec::with_capacity(total);
-
-    for i in 0..total {
-        let dex = Arc::clone(&dex);
-        let utxos = Arc::clone(&utxos);
-        let sem = Arc::clone(&semaphore);
-        let done = Arc::clone(&done);
-        let skipped = Arc::clone(&skipped);
-
-        let handle = tokio::spawn(async move {
-            let _permit = sem.acquire().await.unwrap();
-            let utxo = &utxos[i];
-
-            let result: Option<PoolExport> = async {
-                let base = match dex.liquidity_pool_from_utxo(utxo, "").await {
-                    Ok(Some(p)) => p,
-                    Ok(None) => return None,
-                    Err(e) => {
-                        eprintln!("\n[error] utxo {} base: {}", utxo.tx_hash, e);
-                        return None;
-                    }
-                };
-                match dex
-                    .liquidity_pool_from_utxo_extend(utxo, &base.pool_id)
-                    .await
-                {
-                    Ok(Some(pool)) => Some(pool_to_export(&pool, &utxo.tx_hash)),
-                    Ok(None) => None,
-                    Err(e) => {
-                        eprintln!("\n[error] utxo {} extend: {}", utxo.tx_hash, e);
-                        None
-                    }
-                }
-            }
-            .await;
-
-            let d = done.fetch_add(1, Ordering::Relaxed) + 1;
-            if result.is_none() {
-                skipped.fetch_add(1, Ordering::Relaxed);
-            }
-            if d % 10 == 0 || d == total {
-                let s = skipped.load(Ordering::Relaxed);
-                eprint!("\r[{}/{}] pools={} skipped={}   ", d, total, d - s, s);
-            }
-            result
-        });
-
-        handles.push(handle);
-    }
-
-    let mut pools: Vec<PoolExport> = Vec::new();
-    for handle in handles {
-        if let Ok(Some(pool)) = handle.await {
-            pools.push(pool);
-        }
-    }
-    eprintln!();
-
-    pools.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
-    let json = serde_json::to_string_pretty(&pools)?;
-    std::fs::write("pools_rs.json", &json)?;
+    let run = export::export_all(Arc::new(dex), &target.opts).await?;
     eprintln!(
-        "Exported {} pools to pools_rs.json (skipped {})",
-        pools.len(),
-        total - pools.len()
+        "Exported {} pools to {} (skipped {})",
+        run.pools.len(),
+        target.describe(),
+        run.skipped()
     );
+    // Streaming to stdout keeps the run off disk; the counts still go to stderr.
+    if target.opts.is_streaming() {
+        eprintln!("Skipped {:?}", run.report.counts);
+    } else {
+        cache::save_to_file(&run.report, SKIPPED_PATH)?;
+        eprintln!("Skip report {:?} written to {}", run.report.counts, SKIPPED_PATH);
+    }
 
     Ok(())
 }
@@ -369,6 +1261,58 @@ async fn fetch_chadswap_orders(
     Ok(())
 }
 
+/// Bids and asks side by side, best price on the first row; depth is cumulative.
+fn print_book_depth(depth: &BookDepth) {
+    const EMPTY: &str = "";
+    println!("{}", depth.token_id);
+    println!(
+        "{:>18} {:>16} {:>14} | {:<14} {:>16} {:>18}",
+        "bid depth", "bid amount", "bid price", "ask price", "ask amount", "ask depth"
+    );
+    let rows = depth.bids.len().max(depth.asks.len());
+    for row in 0..rows {
+        let bid = match depth.bids.get(row) {
+            Some(l) => format!("{:>18} {:>16} {:>14.6}", l.cumulative_amount, l.amount, l.price),
+            None => format!("{:>50}", EMPTY),
+        };
+        let ask = match depth.asks.get(row) {
+            Some(l) => format!("{:<14.6} {:>16} {:>18}", l.price, l.amount, l.cumulative_amount),
+            None => String::new(),
+        };
+        println!("{} | {}", bid, ask);
+    }
+    let total = |levels: &[DepthLevel]| levels.last().map_or(0, |l| l.cumulative_lovelace);
+    println!(
+        "{} bid level(s), {} lovelace | {} ask level(s), {} lovelace",
+        depth.bids.len(),
+        total(&depth.bids),
+        depth.asks.len(),
+        total(&depth.asks)
+    );
+}
+
+/// Sink for `--output <path>`: `.db`/`.sqlite` and `.parquet` paths use the
+/// SQLite and Parquet sinks when built with those features, anything else is JSON.
+fn file_sink(path: &str, compression: Option<Compression>) -> Box<dyn ExportSink> {
+    let extension = std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    match extension {
+        #[cfg(feature = "sqlite")]
+        "db" | "sqlite" => Box::new(dexter_kupo_rs::export::SqliteSink::new(path)),
+        #[cfg(feature = "parquet")]
+        "parquet" => Box::new(dexter_kupo_rs::export::ParquetSink::new(path)),
+        _ => {
+            let mut sink = JsonFileSink::new(path);
+            if let Some(c) = compression {
+                sink = sink.with_compression(c);
+            }
+            Box::new(sink)
+        }
+    }
+}
+
 async fn fetch_stable_pool(
     dex: MinswapStable,
     pool_address: &str,
@@ -382,19 +1326,9 @@ async fn fetch_stable_pool(
         .get_pool(pool_address, asset_a, asset_b, decimals_a, decimals_b)
         .await?;
 
-    let export = StablePoolExport {
-        dex: pool.dex_identifier.clone(),
-        pool_id: pool.pool_id.clone(),
-        asset_a: token_identifier(&pool.asset_a),
-        asset_b: token_identifier(&pool.asset_b),
-        reserve_a: pool.reserve_a.to_string(),
-        reserve_b: pool.reserve_b.to_string(),
-        pool_fee_percent: pool.pool_fee_percent,
-        amplification_coefficient: pool.amplification_coefficient.to_string(),
-        total_liquidity: pool.total_liquidity.to_string(),
-    };
-
-    println!("{}", serde_json::to_string_pretty(&export)?);
+    StdoutSink::new()
+        .write_stable_pool(&stable_pool_to_export(&pool))
+        .await?;
     Ok(())
 }
 
@@ -408,15 +1342,86 @@ async fn fetch_vyfi_bar_rate(
     Ok(())
 }
 
-async fn export_all_vyfinance(dex: VyFinance) -> Result<(), Box<dyn std::error::Error>> {
+async fn fetch_all_vyfi_bar_rates(dex: VyfiBar) -> Result<(), Box<dyn std::error::Error>> {
+    eprintln!("[vyfi_bar] fetching rates for {} pools", dex.bar_pools().len());
+    let rates = dex.get_all_rates().await?;
+    println!("{}", serde_json::to_string_pretty(&rates)?);
+    Ok(())
+}
+
+async fn export_all_vyfinance(
+    dex: VyFinance,
+    target: &ExportTarget,
+) -> Result<(), Box<dyn std::error::Error>> {
     let pools = dex.all_liquidity_pools().await?;
-    let exports: Vec<PoolExport> = pools.iter().map(|p| pool_to_export(p, "")).collect();
+    let exports: Vec<PoolExport> = pools.iter().map(pool_to_export).collect();
 
     let mut exports = exports;
     exports.sort_by(|a, b| a.pool_id.cmp(&b.pool_id));
+    target.opts.verify(&mut exports);
 
-    let json = serde_json::to_string_pretty(&exports)?;
-    std::fs::write("pools_rs.json", &json)?;
-    eprintln!("Exported {} VyFinance pools to pools_rs.json", exports.len());
+    target.opts.write_all(&exports).await?;
+    eprintln!(
+        "Exported {} VyFinance pools to {}",
+        exports.len(),
+        target.describe()
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Args, String> {
+        parse_args(&line.split_whitespace().map(String::from).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_parser_accepts_exactly_the_flag_table() {
+        for flag in FLAGS {
+            let arity = flag.value.map_or(0, |v| v.split_whitespace().count());
+            let value = flag.choices.first().copied().unwrap_or("x");
+            let mut line = vec![flag.name];
+            line.extend(std::iter::repeat_n(value, arity));
+            line.push("top");
+            let args = parse(&line.join(" ")).unwrap_or_else(|e| panic!("{}: {}", flag.name, e));
+            assert!(args.has(flag.name), "{}", flag.name);
+            assert_eq!(args.positionals, ["top"], "{} took the wrong arity", flag.name);
+        }
+
+        assert!(parse("--max-concurency 4").is_err());
+        assert!(parse("top --cache").is_err());
+        assert!(parse("--pair lovelace").is_err());
+        assert!(parse("--compress brotli").is_err());
+
+        let args = parse("--kupo a --pair x y --kupo b --dex cswap top 5").unwrap();
+        assert_eq!(args.all("--kupo").map(|v| v[0].as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(args.all("--pair").next().unwrap(), ["x", "y"]);
+        assert_eq!(args.value("--dex"), Some("cswap"));
+        assert_eq!(args.positionals, ["top", "5"]);
+
+        // And main reads no flag outside the table.
+        for read in include_str!("main.rs").split("args.").skip(1) {
+            let Some((method, rest)) = read.split_once('(') else {
+                continue;
+            };
+            let Some(name) = rest.strip_prefix('"').and_then(|r| r.split('"').next()) else {
+                continue;
+            };
+            if method.chars().all(|c| c.is_ascii_lowercase()) && name.starts_with("--") {
+                assert!(FLAGS.iter().any(|f| f.name == name), "{} is not in FLAGS", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_usage_and_completions_list_every_flag() {
+        let usage = usage("dexter");
+        let bash = completions("bash", "dexter").unwrap();
+        for flag in FLAGS {
+            assert!(usage.contains(flag.name), "usage misses {}", flag.name);
+            assert!(bash.contains(flag.name), "completions miss {}", flag.name);
+        }
+    }
+}
@@ -1,4 +1,15 @@
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::LiquidityPool;
+
+/// Decimals used for ADA. Lovelace is never looked up in the override map.
+pub const LOVELACE_DECIMALS: u8 = 6;
+
+pub(crate) fn normalize_identifier(id: &str) -> String {
+    id.replace('.', "").to_lowercase()
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Asset {
@@ -56,17 +67,80 @@ impl Token {
     }
 }
 
+/// Build a `Token` from an identifier.
 pub fn from_identifier(id: &str, decimals: u8) -> Token {
     let id = id.replace('.', "");
     if id == "lovelace" || id.is_empty() {
         return Token::Lovelace;
     }
-    if id.len() < 56 {
+    if id.len() < 56 || !id.is_char_boundary(56) {
         return Token::Lovelace;
     }
     Token::Asset(Asset::from_identifier(&id, decimals))
 }
 
+/// User-supplied decimals for tokens whose decimals are unknown (0), keyed by
+/// normalized identifier (policy + name hex, no dot).
+///
+/// Kupo has no token metadata, so DEX modules build assets with `decimals = 0`;
+/// apply these to pools before pricing them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DecimalsOverrides {
+    decimals: HashMap<String, u8>,
+}
+
+impl DecimalsOverrides {
+    /// Keys may use either `policy.name` or `policyname` form; entries for
+    /// lovelace are ignored (always 6).
+    pub fn new(overrides: HashMap<String, u8>) -> Self {
+        let decimals = overrides
+            .into_iter()
+            .map(|(id, decimals)| (normalize_identifier(&id), decimals))
+            .filter(|(id, _)| id != "lovelace" && !id.is_empty())
+            .collect();
+        Self { decimals }
+    }
+
+    /// Load a JSON file of `{ "<identifier>": <decimals> }`.
+    pub fn load_file(path: &str) -> Result<Self> {
+        let overrides: HashMap<String, u8> = crate::cache::load_from_file(path)
+            .map_err(|e| anyhow!("Failed to load decimals overrides: {}", e))?;
+        Ok(Self::new(overrides))
+    }
+
+    /// Override decimals for an identifier. Lovelace always returns 6.
+    pub fn get(&self, id: &str) -> Option<u8> {
+        let id = normalize_identifier(id);
+        if id == "lovelace" || id.is_empty() {
+            return Some(LOVELACE_DECIMALS);
+        }
+        self.decimals.get(&id).copied()
+    }
+
+    /// Fill in the decimals of `token` when they are unknown (0).
+    pub fn apply(&self, token: &mut Token) {
+        if let Token::Asset(asset) = token {
+            if asset.decimals == 0 {
+                asset.decimals = self.get(&asset.identifier("")).unwrap_or(0);
+            }
+        }
+    }
+
+    /// [`apply`](Self::apply) to both assets of `pool`.
+    pub fn apply_to_pool(&self, pool: &mut LiquidityPool) {
+        self.apply(&mut pool.asset_a);
+        self.apply(&mut pool.asset_b);
+    }
+
+    pub fn len(&self) -> usize {
+        self.decimals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.decimals.is_empty()
+    }
+}
+
 pub fn token_name(token: &Token) -> String {
     match token {
         Token::Lovelace => "ADA".to_string(),
@@ -80,3 +154,31 @@ pub fn token_identifier(token: &Token) -> String {
         Token::Asset(a) => a.identifier(""),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    #[test]
+    fn test_decimals_override_applies_to_unknown_decimals() {
+        let mut map = HashMap::new();
+        map.insert(format!("{}.{}", &SNEK[..56], &SNEK[56..]), 3);
+        map.insert("lovelace".to_string(), 0);
+        let overrides = DecimalsOverrides::new(map);
+        assert_eq!(overrides.len(), 1);
+
+        let mut unknown = from_identifier(SNEK, 0);
+        overrides.apply(&mut unknown);
+        assert_eq!(unknown.as_asset().unwrap().decimals, 3);
+        let mut known = from_identifier(SNEK, 8);
+        overrides.apply(&mut known);
+        assert_eq!(known.as_asset().unwrap().decimals, 8);
+        assert_eq!(overrides.get("lovelace"), Some(LOVELACE_DECIMALS));
+
+        // Nothing is applied implicitly.
+        assert_eq!(from_identifier(SNEK, 0).as_asset().unwrap().decimals, 0);
+        assert_eq!(DecimalsOverrides::default().get(SNEK), None);
+    }
+}
@@ -0,0 +1,254 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use super::asset::{from_identifier, Token};
+
+const POLICY_ID_LEN: usize = 56;
+/// CIP-14 fingerprints are a 160-bit Blake2b hash.
+const FINGERPRINT_HASH_LEN: usize = 20;
+const FINGERPRINT_HRP: &str = "asset";
+/// Policy id plus the longest asset name (32 bytes).
+const MAX_UNIT_LEN: usize = POLICY_ID_LEN + 64;
+
+/// Normalized token identifier: `lovelace`, or policy id + asset name hex with
+/// no separator, lowercase.
+///
+/// Every accepted spelling — `lovelace`, `ADA`, `""`, `policyname`,
+/// `policy.name`, any case — maps to the same value, so identifiers from the
+/// CLI, the API and pool parsing compare equal. `From<&str>` normalizes
+/// without validating; use [`AssetId::parse`] (or `str::parse`) to reject
+/// malformed input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AssetId(String);
+
+fn normalize(id: &str) -> String {
+    let id = id.trim().replace('.', "").to_lowercase();
+    if id.is_empty() || id == "ada" {
+        "lovelace".to_string()
+    } else {
+        id
+    }
+}
+
+impl AssetId {
+    pub fn lovelace() -> Self {
+        AssetId("lovelace".to_string())
+    }
+
+    /// Normalize and validate `id`.
+    pub fn parse(id: &str) -> Result<Self> {
+        let id = normalize(id);
+        if id == "lovelace" {
+            return Ok(AssetId(id));
+        }
+        if id.len() < POLICY_ID_LEN
+            || id.len() > MAX_UNIT_LEN
+            || !id.len().is_multiple_of(2)
+            || !id.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            return Err(anyhow!(
+                "invalid asset id '{}': expected lovelace or policy id + asset name hex",
+                id
+            ));
+        }
+        Ok(AssetId(id))
+    }
+
+    pub fn is_lovelace(&self) -> bool {
+        self.0 == "lovelace"
+    }
+
+    /// Policy id and asset name hex. `None` for lovelace, and for an id built
+    /// with `From<&str>` that cannot be split after the policy id (non-ASCII input).
+    fn split(&self) -> Option<(&str, &str)> {
+        if self.is_lovelace() {
+            return None;
+        }
+        self.0.split_at_checked(POLICY_ID_LEN.min(self.0.len()))
+    }
+
+    /// `None` for lovelace.
+    pub fn policy_id(&self) -> Option<&str> {
+        self.split().map(|(policy, _)| policy)
+    }
+
+    /// `None` for lovelace.
+    pub fn name_hex(&self) -> Option<&str> {
+        self.split().map(|(_, name)| name)
+    }
+
+    /// Normalized form, as used in [`token_identifier`](super::token_identifier).
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// `policy.name` form (Kupo match patterns); `lovelace` unchanged.
+    pub fn dotted(&self) -> String {
+        match (self.policy_id(), self.name_hex()) {
+            (Some(policy), Some(name)) => format!("{}.{}", policy, name),
+            _ => self.0.clone(),
+        }
+    }
+
+    /// CIP-14 fingerprint (`asset1...`): bech32 of the 160-bit Blake2b hash of
+    /// policy id + asset name bytes. `None` for lovelace.
+    ///
+    /// The hash is one-way; going back needs a list of candidate units, see
+    /// [`TokenRegistry::resolve`](crate::registry::TokenRegistry::resolve).
+    pub fn fingerprint(&self) -> Option<String> {
+        if self.is_lovelace() {
+            return None;
+        }
+        let unit = hex::decode(&self.0).ok()?;
+        let hash = blake2b_simd::Params::new()
+            .hash_length(FINGERPRINT_HASH_LEN)
+            .hash(&unit);
+        let hrp = bech32::Hrp::parse(FINGERPRINT_HRP).ok()?;
+        bech32::encode::<bech32::Bech32>(hrp, hash.as_bytes()).ok()
+    }
+
+    /// Whether `input` is a well-formed CIP-14 fingerprint.
+    pub fn is_fingerprint(input: &str) -> bool {
+        bech32::decode(input.trim()).is_ok_and(|(hrp, data)| {
+            hrp.as_str() == FINGERPRINT_HRP && data.len() == FINGERPRINT_HASH_LEN
+        })
+    }
+
+    /// Build a `Token`, with `decimals` resolved as in [`from_identifier`].
+    pub fn to_token(&self, decimals: u8) -> Token {
+        from_identifier(&self.0, decimals)
+    }
+}
+
+impl fmt::Display for AssetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for AssetId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        AssetId::parse(s)
+    }
+}
+
+impl From<&str> for AssetId {
+    fn from(id: &str) -> Self {
+        AssetId(normalize(id))
+    }
+}
+
+impl From<&String> for AssetId {
+    fn from(id: &String) -> Self {
+        AssetId::from(id.as_str())
+    }
+}
+
+impl From<String> for AssetId {
+    fn from(id: String) -> Self {
+        AssetId::from(id.as_str())
+    }
+}
+
+impl From<&AssetId> for AssetId {
+    fn from(id: &AssetId) -> Self {
+        id.clone()
+    }
+}
+
+impl From<&Token> for AssetId {
+    fn from(token: &Token) -> Self {
+        match token {
+            Token::Lovelace => AssetId::lovelace(),
+            Token::Asset(a) => AssetId(normalize(&a.identifier(""))),
+        }
+    }
+}
+
+impl PartialEq<str> for AssetId {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == normalize(other)
+    }
+}
+
+impl PartialEq<&str> for AssetId {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl Serialize for AssetId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for AssetId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        AssetId::parse(&id).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    #[test]
+    fn test_spellings_normalize_to_one_id() {
+        let dotted = format!("{}.{}", &SNEK[..56], &SNEK[56..]);
+        let id = AssetId::from(dotted.to_uppercase().as_str());
+        assert_eq!(id, AssetId::from(SNEK));
+        assert_eq!(id.dotted(), dotted);
+        assert_eq!(id.name_hex(), Some("534e454b"));
+        assert_eq!(AssetId::from(&id.to_token(0)), id);
+
+        for ada in ["lovelace", "ADA", ""] {
+            assert!(AssetId::from(ada).is_lovelace());
+        }
+        assert!("abc".parse::<AssetId>().is_err());
+        assert!(format!("{}0", SNEK).parse::<AssetId>().is_err());
+    }
+
+    #[test]
+    fn test_unvalidated_non_ascii_id_does_not_panic() {
+        // Byte 56 falls inside the two-byte 'é'.
+        let raw = format!("{}é{}", "a".repeat(55), "b".repeat(8));
+        assert!(raw.parse::<AssetId>().is_err());
+
+        let id = AssetId::from(raw.as_str());
+        assert_eq!(id.policy_id(), None);
+        assert_eq!(id.name_hex(), None);
+        assert_eq!(id.dotted(), id.as_str());
+        assert_eq!(id.fingerprint(), None);
+        assert!(id.to_token(0).is_lovelace());
+    }
+
+    #[test]
+    fn test_fingerprint_cip14_vectors() {
+        // Test vectors from CIP-14.
+        let cases = [
+            (
+                "7eae28af2208be856f7a119668ae52a49b73725e326dc16579dcc373",
+                "asset1rjklcrnsdzqp65wjgrg55sy9723kw09mlgvlc3",
+            ),
+            (
+                "1e349c9bdea19fd6c147626a5260bc44b71635f398b67c59881df2097eae28af2208be856f7a119668ae52a49b73725e326dc16579dcc373",
+                "asset1aqrdypg669jgazruv5ah07nuyqe0wxjhe2el6f",
+            ),
+        ];
+        for (unit, fingerprint) in cases {
+            let id = AssetId::parse(unit).unwrap();
+            assert_eq!(id.fingerprint().as_deref(), Some(fingerprint));
+            assert!(AssetId::is_fingerprint(fingerprint));
+        }
+        assert_eq!(AssetId::lovelace().fingerprint(), None);
+        assert!(!AssetId::is_fingerprint(SNEK));
+    }
+}
@@ -1,5 +1,133 @@
-use crate::models::{token_name, Token};
+use crate::models::{token_name, Token, Utxo};
+use crate::quote::{Direction, FeeFraction};
 use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Where and when a pool state was read from chain, so consumers can reject stale data.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PoolFreshness {
+    pub tx_hash: String,
+    pub output_index: u32,
+    /// Slot in which the pool UTXO was created (`None` when the indexer didn't report it).
+    pub slot: Option<u64>,
+    /// Header hash of the block containing the pool UTXO.
+    pub block_hash: String,
+    /// Unix timestamp (seconds) at which the pool UTXO was read.
+    pub observed_at: u64,
+}
+
+impl PoolFreshness {
+    pub fn from_utxo(utxo: &Utxo) -> Self {
+        Self {
+            tx_hash: utxo.tx_hash.clone(),
+            output_index: utxo.output_index,
+            slot: utxo.slot,
+            block_hash: utxo.block.clone(),
+            observed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Split of `pool_fee_percent` by recipient, for DEXes whose swap fee is not
+/// paid entirely to liquidity providers. All percentages are of the input amount.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct FeeBreakdown {
+    /// Share kept in the pool for liquidity providers.
+    pub lp_fee_percent: f64,
+    /// Share paid to the protocol treasury.
+    pub protocol_fee_percent: f64,
+    /// Share paid to the project (token issuer) treasury.
+    pub project_fee_percent: f64,
+    /// Share paid into the pool's reserve treasury.
+    pub reserve_fee_percent: f64,
+    /// Flat per-request fee paid to the batcher/agent, in lovelace.
+    pub agent_fee_lovelace: Option<u64>,
+}
+
+impl FeeBreakdown {
+    pub fn total_percent(&self) -> f64 {
+        self.lp_fee_percent
+            + self.protocol_fee_percent
+            + self.project_fee_percent
+            + self.reserve_fee_percent
+    }
+
+    /// Portion of the swap fee that leaves the pool reserves.
+    pub fn non_lp_percent(&self) -> f64 {
+        self.total_percent() - self.lp_fee_percent
+    }
+}
+
+/// A Cardano payment credential: a key hash or a script hash.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Credential {
+    pub kind: CredentialKind,
+    /// Key or script hash, hex.
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialKind {
+    Key,
+    Script,
+}
+
+/// Who a pool datum names as controlling or collecting part of its fees.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PoolAdmin {
+    /// The datum's name for the role: `fee_to` (MinswapV1), `fee_manager`
+    /// (SundaeSwapV3), `project_beneficiary` or `reserve_beneficiary`
+    /// (WingRidersV2).
+    pub role: String,
+    pub credentials: Vec<Credential>,
+    /// Signatures needed among `credentials` (1 for a single credential).
+    pub required: usize,
+}
+
+impl PoolAdmin {
+    /// Role held by a single credential.
+    pub fn single(role: &str, credential: Credential) -> Self {
+        Self {
+            role: role.to_string(),
+            credentials: vec![credential],
+            required: 1,
+        }
+    }
+}
+
+/// Optional per-pool data beyond the reserves, filled in by the DEX modules and
+/// resolvers that know it. Serialized inline with the pool's own fields.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PoolMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub freshness: Option<PoolFreshness>,
+    /// Short protocol-level pool identifier, where the DEX has one distinct from
+    /// `pool_id` (e.g. the SundaeSwap pool ident referenced by order datums).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_pool_id: Option<String>,
+    /// Per-recipient split of `pool_fee_percent`, where the DEX distinguishes them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_breakdown: Option<FeeBreakdown>,
+    /// Swap fee when `asset_a` is the input, for DEXes with a fee per direction
+    /// (MinswapV2). `None` means `pool_fee_percent`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_a_to_b: Option<f64>,
+    /// Swap fee when `asset_b` is the input.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_b_to_a: Option<f64>,
+    /// DEX-specific datum data that has no dedicated field (treasuries, fee sharing,
+    /// bar fees, ...). A JSON object keyed by field name, or null when there is none.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub extras: serde_json::Value,
+    /// Slot of the output the pool was created in, once resolved (see
+    /// [`CreationResolver`](crate::dex::CreationResolver)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_slot: Option<u64>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LiquidityPool {
@@ -12,6 +140,8 @@ pub struct LiquidityPool {
     pub pool_id: String,
     pub pool_fee_percent: f64,
     pub total_lp_tokens: u64,
+    #[serde(flatten)]
+    pub metadata: PoolMetadata,
 }
 
 impl LiquidityPool {
@@ -35,9 +165,106 @@ impl LiquidityPool {
             pool_id: pool_id.to_string(),
             pool_fee_percent,
             total_lp_tokens: 0,
+            metadata: PoolMetadata::default(),
         }
     }
 
+    /// Record the UTXO this pool state was read from.
+    pub fn observed_in(mut self, utxo: &Utxo) -> Self {
+        self.metadata.freshness = Some(PoolFreshness::from_utxo(utxo));
+        self
+    }
+
+    /// Slots between the UTXO this state was read from and `tip_slot`, i.e. how
+    /// long the state has gone unchanged, if that UTXO's slot is known. Unlike
+    /// [`age`](Self::age), this restarts with every swap or deposit.
+    pub fn staleness_slots(&self, tip_slot: u64) -> Option<u64> {
+        let slot = self.metadata.freshness.as_ref()?.slot?;
+        Some(tip_slot.saturating_sub(slot))
+    }
+
+    /// Time since the pool was created, if its creation slot is resolved.
+    /// Slots are one second long since Shelley.
+    pub fn age(&self, tip_slot: u64) -> Option<Duration> {
+        Some(Duration::from_secs(tip_slot.saturating_sub(self.metadata.created_slot?)))
+    }
+
+    /// Fee switch and fee recipient credentials the datum names, kept in
+    /// `extras["admins"]`; empty where the DEX records none.
+    pub fn admins(&self) -> Vec<PoolAdmin> {
+        self.extra("admins")
+            .and_then(|admins| serde_json::from_value(admins.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Record `admin` in `extras["admins"]`.
+    pub fn add_admin(&mut self, admin: PoolAdmin) {
+        let mut admins = self.admins();
+        admins.push(admin);
+        self.set_extra("admins", serde_json::to_value(admins).unwrap_or_default());
+    }
+
+    /// Set `extras[key]`, turning `extras` into an object if it is still null.
+    pub fn set_extra(&mut self, key: &str, value: impl Into<serde_json::Value>) {
+        if !self.metadata.extras.is_object() {
+            self.metadata.extras = serde_json::Value::Object(serde_json::Map::new());
+        }
+        if let Some(map) = self.metadata.extras.as_object_mut() {
+            map.insert(key.to_string(), value.into());
+        }
+    }
+
+    pub fn extra(&self, key: &str) -> Option<&serde_json::Value> {
+        self.metadata.extras.get(key)
+    }
+
+    /// Swap fee in percent for a swap in `direction`.
+    pub fn fee_percent(&self, direction: Direction) -> f64 {
+        match direction {
+            Direction::AToB => self.metadata.fee_a_to_b,
+            Direction::BToA => self.metadata.fee_b_to_a,
+        }
+        .unwrap_or(self.pool_fee_percent)
+    }
+
+    /// Swap fee for `direction` as the integer fraction the datum encodes.
+    pub fn fee_fraction(&self, direction: Direction) -> FeeFraction {
+        FeeFraction::from_percent(self.fee_percent(direction))
+    }
+
+    /// Fee retained by liquidity providers; the full pool fee when no breakdown is known.
+    pub fn lp_fee_percent(&self) -> f64 {
+        self.metadata.fee_breakdown
+            .as_ref()
+            .map(|b| b.lp_fee_percent)
+            .unwrap_or(self.pool_fee_percent)
+    }
+
+    /// Constant-product invariant `k = reserve_a * reserve_b`.
+    pub fn constant_product(&self) -> u128 {
+        self.reserve_a as u128 * self.reserve_b as u128
+    }
+
+    /// Whether paying out `amount_out` for `amount_in` in `direction` leaves
+    /// `k` no smaller, fees aside. See [`crate::quote::check_amm_quote`] for
+    /// the fee-adjusted check.
+    pub fn preserves_invariant(
+        &self,
+        direction: Direction,
+        amount_in: u64,
+        amount_out: u64,
+    ) -> bool {
+        let (reserve_in, reserve_out) = match direction {
+            Direction::AToB => (self.reserve_a, self.reserve_b),
+            Direction::BToA => (self.reserve_b, self.reserve_a),
+        };
+        if amount_out >= reserve_out {
+            return false;
+        }
+        (reserve_in as u128 + amount_in as u128) * (reserve_out - amount_out) as u128
+            >= self.constant_product()
+    }
+
     pub fn pair(&self) -> String {
         let asset_a_name = token_name(&self.asset_a);
         let asset_b_name = token_name(&self.asset_b);
@@ -68,3 +295,49 @@ impl LiquidityPool {
         format!("{}.{}.{}", self.dex_identifier, self.pair(), self.pool_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_serializes_inline() {
+        let mut pool = LiquidityPool::new(
+            "SundaeSwapV3",
+            Token::Lovelace,
+            Token::Lovelace,
+            1,
+            2,
+            "addr",
+            0.3,
+            "pool",
+        );
+        pool.metadata.protocol_pool_id = Some("ab".to_string());
+        pool.metadata.created_slot = Some(42);
+
+        let json = serde_json::to_value(&pool).unwrap();
+        assert_eq!(json["protocol_pool_id"], "ab");
+        assert_eq!(json["created_slot"], 42);
+        assert!(json.get("metadata").is_none());
+        assert!(json.get("freshness").is_none());
+
+        let read: LiquidityPool = serde_json::from_value(json).unwrap();
+        assert_eq!(read.metadata.created_slot, Some(42));
+
+        // Rows written before any metadata existed still load.
+        let bare = serde_json::to_value(LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            Token::Lovelace,
+            1,
+            2,
+            "addr",
+            0.3,
+            "pool",
+        ))
+        .unwrap();
+        assert!(bare.get("extras").is_none());
+        let read: LiquidityPool = serde_json::from_value(bare).unwrap();
+        assert!(read.metadata.extras.is_null());
+    }
+}
@@ -1,11 +1,19 @@
 pub mod asset;
+pub mod asset_id;
 pub mod liquidity_pool;
 pub mod order;
 pub mod stable_pool;
 pub mod utxo;
 
-pub use asset::{token_identifier, token_name, Asset, Token};
-pub use liquidity_pool::LiquidityPool;
-pub use order::{Order, OrderBook};
+pub use asset::{token_identifier, token_name, Asset, DecimalsOverrides, Token};
+pub use asset_id::AssetId;
+pub use liquidity_pool::{
+    Credential, CredentialKind, FeeBreakdown, LiquidityPool, PoolAdmin, PoolFreshness,
+    PoolMetadata,
+};
+pub use order::{BookDepth, DepthLevel, Order, OrderBook};
 pub use stable_pool::StablePool;
-pub use utxo::{KupoCreatedAt, KupoDatumResponse, KupoUtxoResponse, KupoValue, Unit, Utxo};
+pub use utxo::{
+    reserve_pair, KupoCreatedAt, KupoDatumResponse, KupoScript, KupoUtxoResponse, KupoValue, Unit,
+    Utxo,
+};
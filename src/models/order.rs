@@ -1,5 +1,6 @@
 use crate::models::Token;
 use serde::Serialize;
+use std::cmp::Ordering;
 
 /// A single open order on the ChadSwap order book.
 #[derive(Debug, Clone, Serialize)]
@@ -14,12 +15,217 @@ pub struct Order {
     pub price_denominator: u64,
     /// true = buy order (ADA-only UTXO), false = sell order (ADA + token UTXO)
     pub is_buy: bool,
+    /// `tx_hash#index` of the order UTXO
+    pub output_ref: String,
+    /// Address that placed the order, when the API reports it
+    pub owner: Option<String>,
+    /// Creation time in unix seconds, when the API reports it
+    pub created_at: Option<u64>,
+}
+
+impl Order {
+    /// Lovelace per token base unit: `price / price_denominator`.
+    pub fn effective_price(&self) -> f64 {
+        self.price as f64 / self.price_denominator.max(1) as f64
+    }
+
+    /// Exact comparison of effective prices (cross-multiplied, no float rounding).
+    pub fn cmp_price(&self, other: &Order) -> Ordering {
+        let lhs = self.price as u128 * other.price_denominator.max(1) as u128;
+        let rhs = other.price as u128 * self.price_denominator.max(1) as u128;
+        lhs.cmp(&rhs)
+    }
+
+    /// ADA value of the remaining amount in lovelace: `amount * price / price_denominator`.
+    pub fn lovelace_value(&self) -> u64 {
+        let value =
+            self.amount as u128 * self.price as u128 / self.price_denominator.max(1) as u128;
+        value.min(u64::MAX as u128) as u64
+    }
+
+    /// Seconds since the order was created, if its creation time is known.
+    pub fn age_secs(&self, now_secs: u64) -> Option<u64> {
+        self.created_at.map(|t| now_secs.saturating_sub(t))
+    }
 }
 
 /// All open buy and sell orders for a specific token on ChadSwap.
+///
+/// Books returned by `ChadSwap` are sorted best price first: bids descending,
+/// asks ascending by [`Order::effective_price`], ties by `output_ref`.
 #[derive(Debug, Clone, Serialize)]
 pub struct OrderBook {
     pub token_id: String,
     pub buy_orders: Vec<Order>,
     pub sell_orders: Vec<Order>,
 }
+
+impl OrderBook {
+    /// Sort both sides best price first.
+    pub fn sort(&mut self) {
+        self.buy_orders
+            .sort_by(|a, b| b.cmp_price(a).then_with(|| a.output_ref.cmp(&b.output_ref)));
+        self.sell_orders
+            .sort_by(|a, b| a.cmp_price(b).then_with(|| a.output_ref.cmp(&b.output_ref)));
+    }
+
+    /// Highest bid.
+    pub fn best_bid(&self) -> Option<&Order> {
+        self.buy_orders.first()
+    }
+
+    /// Lowest ask.
+    pub fn best_ask(&self) -> Option<&Order> {
+        self.sell_orders.first()
+    }
+
+    /// Orders aggregated into price levels with cumulative depth, best price
+    /// first on both sides. The book does not need to be sorted.
+    pub fn depth(&self) -> BookDepth {
+        BookDepth {
+            token_id: self.token_id.clone(),
+            bids: depth_levels(&self.buy_orders, true),
+            asks: depth_levels(&self.sell_orders, false),
+        }
+    }
+}
+
+/// Orders of one side sharing an effective price.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DepthLevel {
+    /// Lovelace per token base unit.
+    pub price: f64,
+    /// Tokens at this level.
+    pub amount: u64,
+    /// Lovelace value of `amount` at this price.
+    pub lovelace_value: u64,
+    pub orders: usize,
+    /// Tokens at this and every better level.
+    pub cumulative_amount: u64,
+    /// Lovelace value of this and every better level.
+    pub cumulative_lovelace: u64,
+}
+
+/// Aggregated view of an [`OrderBook`], from [`OrderBook::depth`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BookDepth {
+    pub token_id: String,
+    /// Highest price first.
+    pub bids: Vec<DepthLevel>,
+    /// Lowest price first.
+    pub asks: Vec<DepthLevel>,
+}
+
+fn depth_levels(orders: &[Order], bids: bool) -> Vec<DepthLevel> {
+    let mut sorted: Vec<&Order> = orders.iter().collect();
+    if bids {
+        sorted.sort_by(|a, b| b.cmp_price(a));
+    } else {
+        sorted.sort_by(|a, b| a.cmp_price(b));
+    }
+
+    let mut levels: Vec<DepthLevel> = Vec::new();
+    let mut level_order: Option<&Order> = None;
+    let (mut cumulative_amount, mut cumulative_lovelace) = (0u64, 0u64);
+    for order in sorted {
+        cumulative_amount = cumulative_amount.saturating_add(order.amount);
+        cumulative_lovelace = cumulative_lovelace.saturating_add(order.lovelace_value());
+        match (level_order, levels.last_mut()) {
+            (Some(first), Some(level)) if first.cmp_price(order) == Ordering::Equal => {
+                level.amount = level.amount.saturating_add(order.amount);
+                level.lovelace_value = level.lovelace_value.saturating_add(order.lovelace_value());
+                level.orders += 1;
+                level.cumulative_amount = cumulative_amount;
+                level.cumulative_lovelace = cumulative_lovelace;
+            }
+            _ => {
+                level_order = Some(order);
+                levels.push(DepthLevel {
+                    price: order.effective_price(),
+                    amount: order.amount,
+                    lovelace_value: order.lovelace_value(),
+                    orders: 1,
+                    cumulative_amount,
+                    cumulative_lovelace,
+                });
+            }
+        }
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(price: u64, price_denominator: u64, is_buy: bool, output_ref: &str) -> Order {
+        Order {
+            asset: Token::Lovelace,
+            amount: 1,
+            price,
+            price_denominator,
+            is_buy,
+            output_ref: output_ref.to_string(),
+            owner: None,
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn test_book_sorting() {
+        let mut book = OrderBook {
+            token_id: "t".to_string(),
+            buy_orders: vec![
+                order(1, 2, true, "a"),
+                order(3, 2, true, "b"),
+                order(2, 1, true, "c"),
+            ],
+            sell_orders: vec![
+                order(5, 1, false, "d"),
+                order(9, 3, false, "f"),
+                order(3, 1, false, "e"),
+            ],
+        };
+        book.sort();
+        let refs = |orders: &[Order]| {
+            orders
+                .iter()
+                .map(|o| o.output_ref.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(refs(&book.buy_orders), ["c", "b", "a"]);
+        // 9/3 == 3/1: ties break on output_ref.
+        assert_eq!(refs(&book.sell_orders), ["e", "f", "d"]);
+        assert_eq!(book.best_bid().unwrap().effective_price(), 2.0);
+        assert_eq!(book.best_ask().unwrap().effective_price(), 3.0);
+    }
+
+    #[test]
+    fn test_depth_levels() {
+        let sized = |amount: u64, price: u64, den: u64, is_buy: bool| Order {
+            amount,
+            ..order(price, den, is_buy, "x")
+        };
+        let book = OrderBook {
+            token_id: "t".to_string(),
+            buy_orders: vec![sized(10, 1, 1, true), sized(5, 2, 1, true)],
+            sell_orders: vec![
+                sized(4, 5, 1, false),
+                sized(2, 9, 3, false),
+                sized(1, 3, 1, false),
+            ],
+        };
+        let depth = book.depth();
+        assert_eq!(depth.bids.len(), 2);
+        assert_eq!(depth.bids[0].price, 2.0);
+        assert_eq!(depth.bids[1].cumulative_amount, 15);
+        assert_eq!(depth.bids[1].cumulative_lovelace, 20);
+
+        // 9/3 and 3/1 share a level.
+        assert_eq!(depth.asks.len(), 2);
+        assert_eq!((depth.asks[0].amount, depth.asks[0].orders), (3, 2));
+        assert_eq!(depth.asks[0].lovelace_value, 9);
+        assert_eq!(depth.asks[1].cumulative_amount, 7);
+        assert_eq!(depth.asks[1].cumulative_lovelace, 29);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::models::{token_name, Token};
+use crate::models::{token_name, AssetId, PoolFreshness, Token, Utxo};
+use anyhow::{anyhow, Result};
 use serde::Serialize;
 
 /// A Curve-style stable swap liquidity pool.
@@ -7,32 +8,106 @@ use serde::Serialize;
 /// and a D-invariant (total_liquidity) to maintain near-1:1 pricing between
 /// pegged assets (e.g. USDC/iUSD, ADA/stADA).
 ///
-/// Reserves are read from the datum (Balance0/Balance1), NOT from UTXO amounts.
+/// Reserves are read from the datum's balance list, NOT from UTXO amounts. A
+/// pool can hold more than two assets; the `asset_a`/`reserve_a` accessors
+/// read the first two, which is all a two-asset pool has. [`StablePool::new`]
+/// rejects pools with fewer than two assets.
 #[derive(Debug, Clone, Serialize)]
 pub struct StablePool {
     pub dex_identifier: String,
-    pub asset_a: Token,
-    pub asset_b: Token,
-    /// Balance0 from datum — reserve of asset_a
-    pub reserve_a: u64,
-    /// Balance1 from datum — reserve of asset_b
-    pub reserve_b: u64,
+    /// Every asset with its datum balance, in datum order.
+    assets: Vec<(Token, u64)>,
     pub address: String,
     pub pool_id: String,
     pub pool_fee_percent: f64,
     /// Amplification coefficient (A) — controls curve flatness near peg
     pub amplification_coefficient: u64,
-    /// Total liquidity invariant (D) — total liquidity across both assets
+    /// Total liquidity invariant (D) — total liquidity across all assets
     pub total_liquidity: u64,
+    /// UTXO the pool state was read from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub freshness: Option<PoolFreshness>,
 }
 
 impl StablePool {
+    /// Fails unless the pool holds at least two assets.
+    pub fn new(
+        dex_identifier: &str,
+        assets: Vec<(Token, u64)>,
+        address: &str,
+        pool_id: &str,
+        pool_fee_percent: f64,
+        amplification_coefficient: u64,
+        total_liquidity: u64,
+    ) -> Result<Self> {
+        if assets.len() < 2 {
+            return Err(anyhow!(
+                "Stable pool {} needs at least two assets, got {}",
+                pool_id,
+                assets.len()
+            ));
+        }
+        Ok(Self {
+            dex_identifier: dex_identifier.to_string(),
+            assets,
+            address: address.to_string(),
+            pool_id: pool_id.to_string(),
+            pool_fee_percent,
+            amplification_coefficient,
+            total_liquidity,
+            freshness: None,
+        })
+    }
+
+    /// Record the UTXO this pool state was read from.
+    pub fn observed_in(mut self, utxo: &Utxo) -> Self {
+        self.freshness = Some(PoolFreshness::from_utxo(utxo));
+        self
+    }
+
+    /// Every asset with its datum balance, in datum order; at least two.
+    pub fn assets(&self) -> &[(Token, u64)] {
+        &self.assets
+    }
+
+    /// First asset (Balance0).
+    pub fn asset_a(&self) -> &Token {
+        &self.assets[0].0
+    }
+
+    /// Second asset (Balance1).
+    pub fn asset_b(&self) -> &Token {
+        &self.assets[1].0
+    }
+
+    pub fn reserve_a(&self) -> u64 {
+        self.assets[0].1
+    }
+
+    pub fn reserve_b(&self) -> u64 {
+        self.assets[1].1
+    }
+
+    /// Balance of `asset`, if the pool holds it.
+    pub fn reserve_of(&self, asset: impl Into<AssetId>) -> Option<u64> {
+        let asset = asset.into();
+        self.assets
+            .iter()
+            .find(|(token, _)| AssetId::from(token) == asset)
+            .map(|(_, reserve)| *reserve)
+    }
+
+    pub fn is_two_asset(&self) -> bool {
+        self.assets.len() == 2
+    }
+
+    /// Tickers joined by `/`, e.g. `iUSD/USDM` or `iUSD/USDM/DJED`.
     pub fn pair(&self) -> String {
-        format!(
-            "{}/{}",
-            token_name(&self.asset_a),
-            token_name(&self.asset_b)
-        )
+        self.assets
+            .iter()
+            .map(|(token, _)| token_name(token))
+            .collect::<Vec<_>>()
+            .join("/")
     }
 
     /// Stable swap price using the derivative of the StableSwap invariant:
@@ -40,19 +115,20 @@ impl StablePool {
     ///   P = (y/x) * (1 + A*x/D) / (1 + A*y/D)
     ///
     /// where x, y are decimal-adjusted balances, A is the amplification
-    /// coefficient, and D is the total liquidity invariant.
+    /// coefficient, and D is the total liquidity invariant. Two-asset form,
+    /// over the first two assets.
     pub fn price(&self) -> f64 {
-        let dec_a = match &self.asset_a {
+        let dec_a = match self.asset_a() {
             Token::Lovelace => 6,
             Token::Asset(a) => a.decimals,
         };
-        let dec_b = match &self.asset_b {
+        let dec_b = match self.asset_b() {
             Token::Lovelace => 6,
             Token::Asset(a) => a.decimals,
         };
 
-        let x = self.reserve_a as f64 / 10_f64.powi(dec_a as i32);
-        let y = self.reserve_b as f64 / 10_f64.powi(dec_b as i32);
+        let x = self.reserve_a() as f64 / 10_f64.powi(dec_a as i32);
+        let y = self.reserve_b() as f64 / 10_f64.powi(dec_b as i32);
         let a = self.amplification_coefficient as f64;
         let d = self.total_liquidity as f64 / 10_f64.powi(dec_a.min(dec_b) as i32);
 
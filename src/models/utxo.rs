@@ -1,13 +1,30 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Unit {
     pub unit: String,
-    #[serde(deserialize_with = "deserialize_quantity")]
-    pub quantity: String,
+    /// Parsed once on the way in; serialized as a string, like Kupo and the
+    /// cache files written before it was numeric.
+    #[serde(
+        deserialize_with = "deserialize_quantity",
+        serialize_with = "serialize_quantity"
+    )]
+    pub quantity: u64,
 }
 
-fn deserialize_quantity<'de, D>(deserializer: D) -> Result<String, D::Error>
+impl Unit {
+    pub fn is_lovelace(&self) -> bool {
+        self.unit == "lovelace"
+    }
+
+    /// The quantity as Kupo spells it, for display.
+    pub fn quantity_str(&self) -> String {
+        self.quantity.to_string()
+    }
+}
+
+fn deserialize_quantity<'de, D>(deserializer: D) -> Result<u64, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -17,11 +34,14 @@ where
         Str(String),
         Num(u64),
     }
-    let q = Q::deserialize(deserializer)?;
-    Ok(match q {
-        Q::Str(s) => s,
-        Q::Num(n) => n.to_string(),
-    })
+    match Q::deserialize(deserializer)? {
+        Q::Str(s) => s.parse().map_err(serde::de::Error::custom),
+        Q::Num(n) => Ok(n),
+    }
+}
+
+fn serialize_quantity<S: Serializer>(quantity: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(quantity)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +52,9 @@ pub struct Utxo {
     pub output_index: u32,
     pub amount: Vec<Unit>,
     pub block: String,
+    /// Slot in which this output was created.
+    #[serde(default)]
+    pub slot: Option<u64>,
     pub data_hash: Option<String>,
     pub inline_datum: Option<String>,
     pub reference_script_hash: Option<String>,
@@ -44,9 +67,75 @@ impl Utxo {
         self.amount.iter().find(|u| u.unit == unit)
     }
 
+    /// Lovelace held by the output; 0 if Kupo reported none.
+    pub fn lovelace(&self) -> u64 {
+        self.asset_quantity("lovelace")
+    }
+
+    /// Quantity of `unit` (policy id + asset name hex, dotted or not); 0 when
+    /// absent.
+    pub fn asset_quantity(&self, unit: &str) -> u64 {
+        let unit = unit.replace('.', "");
+        self.get_asset(&unit).map_or(0, |u| u.quantity)
+    }
+
+    /// Every unit except lovelace, in Kupo order.
+    pub fn non_ada_assets(&self) -> impl Iterator<Item = &Unit> {
+        self.amount.iter().filter(|u| !u.is_lovelace())
+    }
+
     pub fn has_data_hash(&self) -> bool {
         self.data_hash.is_some()
     }
+
+    /// Build an output seen outside Kupo (e.g. from chain-sync). `value` maps
+    /// units (`lovelace` or policy id + asset name hex, dots allowed) to
+    /// quantities; `datum_cbor` is carried as an inline datum. There is no
+    /// outref, so `tx_hash` is empty.
+    pub fn from_raw_output(
+        address: &str,
+        value: &HashMap<String, u64>,
+        datum_cbor: Option<&str>,
+    ) -> Self {
+        let mut amount: Vec<Unit> = value
+            .iter()
+            .map(|(unit, qty)| Unit {
+                unit: unit.replace('.', ""),
+                quantity: *qty,
+            })
+            .collect();
+        // Kupo order: lovelace first, then by unit.
+        amount.sort_by(|a, b| {
+            (a.unit != "lovelace", &a.unit).cmp(&(b.unit != "lovelace", &b.unit))
+        });
+        Utxo {
+            address: address.to_string(),
+            tx_hash: String::new(),
+            tx_index: 0,
+            output_index: 0,
+            amount,
+            block: String::new(),
+            slot: None,
+            data_hash: None,
+            inline_datum: datum_cbor.map(str::to_string),
+            reference_script_hash: None,
+            datum_type: datum_cbor.map(|_| "inline".to_string()),
+        }
+    }
+}
+
+/// The two reserve units of a pool output, once bookkeeping units (LP
+/// tokens, NFTs, validity tokens) are filtered out of `units`, which Kupo
+/// lists lovelace first: both units of an ADA/X pool, or the second and
+/// third of an X/Y pool, whose lovelace is only the min-ADA deposit. `None`
+/// with fewer than two units; DEXes that accept only some shapes check the
+/// count first.
+pub fn reserve_pair<'a>(units: &[&'a Unit]) -> Option<(&'a Unit, &'a Unit)> {
+    match units {
+        [a, b] => Some((a, b)),
+        [_, a, b, ..] => Some((a, b)),
+        _ => None,
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,6 +197,8 @@ where
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KupoCreatedAt {
+    #[serde(default)]
+    pub slot_no: Option<u64>,
     #[serde(rename = "header_hash")]
     pub header_hash: String,
 }
@@ -116,3 +207,72 @@ pub struct KupoCreatedAt {
 pub struct KupoDatumResponse {
     pub datum: String,
 }
+
+/// Script as returned by Kupo's `/scripts/{hash}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KupoScript {
+    /// `native`, `plutus:v1`, `plutus:v2` or `plutus:v3`.
+    pub language: String,
+    /// Script CBOR hex.
+    pub script: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_raw_output_orders_units_like_kupo() {
+        let value = HashMap::from([
+            ("ff.01".to_string(), 5),
+            ("lovelace".to_string(), 2_000_000),
+            ("aa01".to_string(), 1),
+        ]);
+        let utxo = Utxo::from_raw_output("addr1", &value, Some("d87980"));
+        let units: Vec<&str> = utxo.amount.iter().map(|u| u.unit.as_str()).collect();
+        assert_eq!(units, ["lovelace", "aa01", "ff01"]);
+        assert_eq!(utxo.inline_datum.as_deref(), Some("d87980"));
+        assert!(utxo.data_hash.is_none());
+    }
+
+    #[test]
+    fn test_unit_quantity_serde() {
+        let units: Vec<Unit> = serde_json::from_str(
+            r#"[{"unit": "lovelace", "quantity": "2000000"}, {"unit": "aa01", "quantity": 7}]"#,
+        )
+        .unwrap();
+        assert_eq!((units[0].quantity, units[1].quantity), (2_000_000, 7));
+        // Written back as strings, as older cache files hold them.
+        assert_eq!(
+            serde_json::to_value(&units[1]).unwrap(),
+            serde_json::json!({"unit": "aa01", "quantity": "7"})
+        );
+        assert!(serde_json::from_str::<Unit>(r#"{"unit": "aa01", "quantity": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_value_helpers_and_reserve_pair() {
+        let value = HashMap::from([
+            ("lovelace".to_string(), 2_000_000),
+            ("aa.01".to_string(), 5),
+            ("bb01".to_string(), 7),
+        ]);
+        let utxo = Utxo::from_raw_output("addr1", &value, None);
+        assert_eq!(utxo.lovelace(), 2_000_000);
+        assert_eq!(utxo.asset_quantity("aa.01"), 5);
+        assert_eq!(utxo.asset_quantity("cc01"), 0);
+        assert_eq!(utxo.non_ada_assets().count(), 2);
+
+        // X/Y pool: the lovelace in front is the min-ADA deposit.
+        let units: Vec<&Unit> = utxo.amount.iter().collect();
+        let (a, b) = reserve_pair(&units).unwrap();
+        assert_eq!((a.unit.as_str(), b.unit.as_str()), ("aa01", "bb01"));
+        // ADA/X pool.
+        let (a, b) = reserve_pair(&units[..2]).unwrap();
+        assert_eq!((a.is_lovelace(), b.quantity), (true, 5));
+        // Units past the third are not looked at; a single unit is no pool.
+        let (a, b) = reserve_pair(&[units[0], units[1], units[2], units[1]]).unwrap();
+        assert_eq!((a.unit.as_str(), b.unit.as_str()), ("aa01", "bb01"));
+        assert!(reserve_pair(&units[..1]).is_none());
+    }
+}
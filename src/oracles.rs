@@ -0,0 +1,269 @@
+//! Reference prices from on-chain oracle feeds, to sanity-check pool prices.
+//!
+//! An [`OracleFeed`] names the Kupo pattern its feed outputs sit at and the
+//! datum format they carry. [`OracleReader`] reads the latest value of each
+//! feed as an [`OraclePrice`] in ADA per whole token, the unit of
+//! [`LiquidityPool::price`](crate::LiquidityPool::price) for ADA pools.
+//!
+//! Supported formats:
+//! - Orcfax: `Constr 0 [Constr 0 [feed_id, created_at_ms, Constr 0 [num, den]], ...]`,
+//!   one output per publication; the newest one wins.
+//! - Charli3: `Constr 0 [Constr 2 [{0: price, 1: created_at_ms, 2: expires_at_ms}]]`
+//!   with the price scaled by 10^6, one output tagged by the feed NFT.
+
+use anyhow::{anyhow, Result};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::kupo::KupoApi;
+use crate::models::Utxo;
+use crate::plutus::PlutusData;
+
+/// Feeds read at once.
+const FEED_CONCURRENCY: usize = 5;
+/// Charli3 prices are integers scaled by this.
+const CHARLI3_SCALE: f64 = 1_000_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleFormat {
+    Orcfax,
+    Charli3,
+}
+
+/// Where an oracle publishes the price of `token`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OracleFeed {
+    /// Display name, e.g. `"FACT-ADA"`.
+    pub name: String,
+    /// Asset identifier of the token priced.
+    pub token: String,
+    /// Kupo pattern of the feed outputs (address or `policy.name`).
+    pub pattern: String,
+    pub format: OracleFormat,
+    /// The feed publishes tokens per ADA rather than ADA per token.
+    #[serde(default)]
+    pub inverted: bool,
+}
+
+/// Latest value of a feed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OraclePrice {
+    pub feed: String,
+    pub token: String,
+    /// ADA per whole token.
+    pub ada_per_token: f64,
+    /// Unix seconds the value was published at.
+    pub published_at: Option<u64>,
+    /// Unix seconds after which the oracle no longer vouches for the value.
+    pub expires_at: Option<u64>,
+    /// Slot of the feed output.
+    pub slot: Option<u64>,
+}
+
+impl OraclePrice {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now > expires_at)
+    }
+}
+
+/// Feeds from a JSON array file, e.g.
+/// `[{"name": "FACT-ADA", "token": "<unit>", "pattern": "<addr>", "format": "orcfax"}]`.
+pub fn load_feeds(path: &str) -> Result<Vec<OracleFeed>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read oracle feeds {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| anyhow!("Invalid oracle feeds {}: {}", path, e))
+}
+
+/// A parsed feed datum: value and publication/expiry times in unix seconds.
+struct FeedValue {
+    value: f64,
+    published_at: Option<u64>,
+    expires_at: Option<u64>,
+}
+
+fn int(data: &PlutusData) -> Option<u64> {
+    match data {
+        PlutusData::Int(n) => u64::try_from(*n).ok(),
+        _ => None,
+    }
+}
+
+fn parse_orcfax(datum: &PlutusData) -> Option<FeedValue> {
+    let PlutusData::Constr(0, fields) = datum else {
+        return None;
+    };
+    let PlutusData::Constr(0, statement) = fields.first()? else {
+        return None;
+    };
+    let PlutusData::Constr(0, rational) = statement.get(2)? else {
+        return None;
+    };
+    let (numerator, denominator) = (int(rational.first()?)?, int(rational.get(1)?)?);
+    (denominator > 0).then(|| FeedValue {
+        value: numerator as f64 / denominator as f64,
+        published_at: statement.get(1).and_then(int).map(|ms| ms / 1000),
+        expires_at: None,
+    })
+}
+
+fn parse_charli3(datum: &PlutusData) -> Option<FeedValue> {
+    let PlutusData::Constr(0, fields) = datum else {
+        return None;
+    };
+    let PlutusData::Constr(2, price_data) = fields.first()? else {
+        return None;
+    };
+    let PlutusData::Map(entries) = price_data.first()? else {
+        return None;
+    };
+    let entry = |key: u64| {
+        entries
+            .iter()
+            .find(|(k, _)| int(k) == Some(key))
+            .and_then(|(_, v)| int(v))
+    };
+    Some(FeedValue {
+        value: entry(0)? as f64 / CHARLI3_SCALE,
+        published_at: entry(1).map(|ms| ms / 1000),
+        expires_at: entry(2).map(|ms| ms / 1000),
+    })
+}
+
+impl OracleFormat {
+    fn parse(&self, datum: &PlutusData) -> Option<FeedValue> {
+        match self {
+            OracleFormat::Orcfax => parse_orcfax(datum),
+            OracleFormat::Charli3 => parse_charli3(datum),
+        }
+    }
+}
+
+pub struct OracleReader {
+    kupo: KupoApi,
+    feeds: Vec<OracleFeed>,
+}
+
+impl OracleReader {
+    pub fn new(kupo: &KupoApi) -> Self {
+        Self {
+            kupo: kupo.clone(),
+            feeds: Vec::new(),
+        }
+    }
+
+    /// Set the feeds read by [`get_prices`](Self::get_prices).
+    pub fn with_feeds(mut self, feeds: Vec<OracleFeed>) -> Self {
+        self.feeds = feeds;
+        self
+    }
+
+    pub fn feeds(&self) -> &[OracleFeed] {
+        &self.feeds
+    }
+
+    /// Latest value of `feed`. Outputs whose datum is not in the feed's
+    /// format are ignored.
+    pub async fn get_price(&self, feed: &OracleFeed) -> Result<OraclePrice> {
+        let utxos = self.kupo.get(&feed.pattern, true).await?;
+        let mut latest: Option<(&Utxo, FeedValue)> = None;
+        for utxo in &utxos {
+            let Some(datum) = self.kupo.utxo_datum(utxo).await? else {
+                continue;
+            };
+            let Some(value) = PlutusData::from_cbor_hex(&datum)
+                .ok()
+                .and_then(|datum| feed.format.parse(&datum))
+            else {
+                continue;
+            };
+            let newer = latest.as_ref().is_none_or(|(prev_utxo, prev)| {
+                (value.published_at, utxo.slot) > (prev.published_at, prev_utxo.slot)
+            });
+            if newer {
+                latest = Some((utxo, value));
+            }
+        }
+        let (utxo, value) =
+            latest.ok_or_else(|| anyhow!("Oracle {}: no feed output found", feed.name))?;
+        let ada_per_token = if feed.inverted {
+            if value.value == 0.0 {
+                return Err(anyhow!("Oracle {}: zero price", feed.name));
+            }
+            1.0 / value.value
+        } else {
+            value.value
+        };
+        Ok(OraclePrice {
+            feed: feed.name.clone(),
+            token: feed.token.clone(),
+            ada_per_token,
+            published_at: value.published_at,
+            expires_at: value.expires_at,
+            slot: utxo.slot,
+        })
+    }
+
+    /// Latest value of every configured feed, in feed order. Feeds that fail
+    /// are logged and skipped.
+    pub async fn get_prices(&self) -> Vec<OraclePrice> {
+        let results: Vec<_> = stream::iter(self.feeds.clone())
+            .map(|feed| async move {
+                let price = self.get_price(&feed).await;
+                (feed, price)
+            })
+            .buffered(FEED_CONCURRENCY)
+            .collect()
+            .await;
+        let mut prices = Vec::with_capacity(results.len());
+        for (feed, result) in results {
+            match result {
+                Ok(price) => prices.push(price),
+                Err(e) => eprintln!("[oracles] {}: {}", feed.name, e),
+            }
+        }
+        prices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_feed_datums() {
+        // Constr 0 [Constr 0 ["FACT-ADA", 1_700_000_000_000, Constr 0 [1, 40]]].
+        let orcfax = PlutusData::Constr(
+            0,
+            vec![PlutusData::Constr(
+                0,
+                vec![
+                    PlutusData::Bytes(b"FACT-ADA".to_vec()),
+                    PlutusData::Int(1_700_000_000_000),
+                    PlutusData::Constr(0, vec![PlutusData::Int(1), PlutusData::Int(40)]),
+                ],
+            )],
+        );
+        let value = OracleFormat::Orcfax.parse(&orcfax).unwrap();
+        assert_eq!(value.value, 0.025);
+        assert_eq!(value.published_at, Some(1_700_000_000));
+
+        let charli3 = PlutusData::Constr(
+            0,
+            vec![PlutusData::Constr(
+                2,
+                vec![PlutusData::Map(vec![
+                    (PlutusData::Int(0), PlutusData::Int(350_000)),
+                    (PlutusData::Int(1), PlutusData::Int(1_700_000_000_000)),
+                    (PlutusData::Int(2), PlutusData::Int(1_700_000_600_000)),
+                ])],
+            )],
+        );
+        let value = OracleFormat::Charli3.parse(&charli3).unwrap();
+        assert_eq!(value.value, 0.35);
+        assert_eq!(value.expires_at, Some(1_700_000_600));
+        // Each format rejects the other's datum.
+        assert!(OracleFormat::Orcfax.parse(&charli3).is_none());
+        assert!(OracleFormat::Charli3.parse(&orcfax).is_none());
+    }
+}
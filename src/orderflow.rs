@@ -0,0 +1,436 @@
+//! Pending swap orders at the batcher order scripts, as a leading volume
+//! indicator.
+//!
+//! Batcher DEXes settle a swap in two steps: the user locks an order at the
+//! DEX's order script, then a batcher spends it against the pool. Orders still
+//! waiting are volume the pool reserves do not show yet. [`OrderFlow`] reads
+//! them from Kupo, decodes the swap orders with one [`OrderDecoder`] per order
+//! script, and [`volume_by_pair`] sums their intended volume per pair.
+//!
+//! Minswap V2 orders (the datum [`DexSwap`](crate::DexSwap) builds) are decoded
+//! out of the box; other order scripts plug in with
+//! [`OrderFlow::with_decoder`].
+
+use anyhow::{anyhow, Result};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::dex::minswap_v2_swap::{DEPOSIT_LOVELACE, LP_TOKEN_POLICY_ID, ORDER_SCRIPT_HASH};
+use crate::kupo::KupoApi;
+use crate::models::{token_identifier, LiquidityPool, Utxo};
+use crate::plutus::PlutusData;
+use crate::quote::Direction;
+
+/// Datum lookups in flight at once while reading orders.
+const DATUM_CONCURRENCY: usize = 8;
+
+/// What the order's amounts mean.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwapKind {
+    /// Spend `amount_in` for at least `min_receive`.
+    ExactIn,
+    /// Receive `min_receive` exactly, spending at most `amount_in`.
+    ExactOut,
+}
+
+/// A swap order waiting for a batcher.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PendingOrder {
+    pub dex_identifier: String,
+    /// `tx_hash#output_index` of the order output.
+    pub output_ref: String,
+    /// Slot in which the order was placed.
+    pub slot: Option<u64>,
+    /// Pool the order targets, as in [`LiquidityPool::pool_id`].
+    pub pool_id: String,
+    pub direction: Direction,
+    pub kind: SwapKind,
+    pub amount_in: u64,
+    pub min_receive: u64,
+}
+
+/// Decodes the swap orders of one DEX's order script.
+pub trait OrderDecoder: Send + Sync {
+    fn identifier(&self) -> &str;
+
+    /// Kupo pattern matching the order outputs, e.g. `<script hash>/*`.
+    fn match_pattern(&self) -> String;
+
+    /// The swap held by `utxo` with datum `datum`; `None` for other orders
+    /// (deposits, withdrawals, ...) and datums of another shape.
+    fn decode(&self, utxo: &Utxo, datum: &PlutusData) -> Option<PendingOrder>;
+}
+
+/// Minswap V2 `OrderDatum`: the pool's LP asset in field 5, the step in field
+/// 6 and the batcher fee in field 7.
+pub struct MinswapV2Orders;
+
+fn int(data: &PlutusData) -> Option<u64> {
+    match data {
+        PlutusData::Int(n) => u64::try_from(*n).ok(),
+        _ => None,
+    }
+}
+
+fn bytes_hex(data: &PlutusData) -> Option<String> {
+    match data {
+        PlutusData::Bytes(bytes) => Some(hex::encode(bytes)),
+        _ => None,
+    }
+}
+
+/// Input of an order that swaps "everything it holds": its one token, or its
+/// lovelace less the batcher fee and deposit.
+fn whole_input(utxo: &Utxo, batcher_fee: u64) -> Option<u64> {
+    let mut tokens = utxo.non_ada_assets();
+    match (tokens.next(), tokens.next()) {
+        (Some(token), None) => Some(token.quantity),
+        (None, _) => utxo
+            .lovelace()
+            .checked_sub(batcher_fee + DEPOSIT_LOVELACE),
+        _ => None,
+    }
+}
+
+impl OrderDecoder for MinswapV2Orders {
+    fn identifier(&self) -> &str {
+        "MinswapV2"
+    }
+
+    fn match_pattern(&self) -> String {
+        format!("{}/*", ORDER_SCRIPT_HASH)
+    }
+
+    fn decode(&self, utxo: &Utxo, datum: &PlutusData) -> Option<PendingOrder> {
+        let PlutusData::Constr(0, fields) = datum else {
+            return None;
+        };
+        let PlutusData::Constr(0, lp) = fields.get(5)? else {
+            return None;
+        };
+        let (policy, name) = (bytes_hex(lp.first()?)?, bytes_hex(lp.get(1)?)?);
+        if policy != LP_TOKEN_POLICY_ID {
+            return None;
+        }
+        let batcher_fee = int(fields.get(7)?)?;
+        let (kind, step) = match fields.get(6)? {
+            PlutusData::Constr(0, step) => (SwapKind::ExactIn, step),
+            PlutusData::Constr(3, step) => (SwapKind::ExactOut, step),
+            _ => return None,
+        };
+        // Direction 1 sells asset A (the lower unit), as the order builder sets it.
+        let direction = match step.first()? {
+            PlutusData::Constr(1, _) => Direction::AToB,
+            PlutusData::Constr(0, _) => Direction::BToA,
+            _ => return None,
+        };
+        let amount_in = match step.get(1)? {
+            PlutusData::Constr(0, amount) => int(amount.first()?)?,
+            PlutusData::Constr(1, _) => whole_input(utxo, batcher_fee)?,
+            _ => return None,
+        };
+        Some(PendingOrder {
+            dex_identifier: self.identifier().to_string(),
+            output_ref: format!("{}#{}", utxo.tx_hash, utxo.output_index),
+            slot: utxo.slot,
+            pool_id: format!("{}{}", policy, name),
+            direction,
+            kind,
+            amount_in,
+            min_receive: int(step.get(2)?)?,
+        })
+    }
+}
+
+/// Reads pending orders from Kupo with a set of [`OrderDecoder`]s.
+pub struct OrderFlow {
+    kupo: KupoApi,
+    decoders: Vec<Box<dyn OrderDecoder>>,
+}
+
+impl OrderFlow {
+    /// Order flow of the DEXes with a built-in decoder (Minswap V2).
+    pub fn new(kupo: &KupoApi) -> Self {
+        Self {
+            kupo: kupo.clone(),
+            decoders: vec![Box::new(MinswapV2Orders)],
+        }
+    }
+
+    /// Also read the orders `decoder` understands. Identifiers must be unique.
+    pub fn with_decoder(mut self, decoder: impl OrderDecoder + 'static) -> Result<Self> {
+        if self
+            .decoders
+            .iter()
+            .any(|d| d.identifier() == decoder.identifier())
+        {
+            return Err(anyhow!(
+                "an order decoder for {} is already registered",
+                decoder.identifier()
+            ));
+        }
+        self.decoders.push(Box::new(decoder));
+        Ok(self)
+    }
+
+    /// Identifiers of the DEXes whose orders are read.
+    pub fn dexes(&self) -> Vec<&str> {
+        self.decoders.iter().map(|d| d.identifier()).collect()
+    }
+
+    /// Swap orders of one decoder's script. Outputs whose datum cannot be
+    /// fetched or decoded are left out.
+    async fn orders_of(&self, decoder: &dyn OrderDecoder) -> Result<Vec<PendingOrder>> {
+        let utxos = self.kupo.get(&decoder.match_pattern(), true).await?;
+        let orders = stream::iter(utxos)
+            .map(|utxo| async move {
+                let datum = self.kupo.utxo_datum(&utxo).await.ok()??;
+                let datum = PlutusData::from_cbor_hex(&datum).ok()?;
+                decoder.decode(&utxo, &datum)
+            })
+            .buffer_unordered(DATUM_CONCURRENCY)
+            .filter_map(|order| async move { order })
+            .collect::<Vec<_>>()
+            .await;
+        Ok(orders)
+    }
+
+    /// Every pending swap order, oldest first. A DEX that fails to answer is
+    /// logged and left out.
+    pub async fn pending_orders(&self) -> Vec<PendingOrder> {
+        let results =
+            futures::future::join_all(self.decoders.iter().map(|d| self.orders_of(d.as_ref())))
+                .await;
+        let mut orders = Vec::new();
+        for (decoder, result) in self.decoders.iter().zip(results) {
+            match result {
+                Ok(found) => orders.extend(found),
+                Err(e) => eprintln!("[orderflow] {} failed: {}", decoder.identifier(), e),
+            }
+        }
+        orders.sort_by(|a, b| (a.slot, &a.output_ref).cmp(&(b.slot, &b.output_ref)));
+        orders
+    }
+}
+
+/// Pending volume of one pair, across DEXes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PairVolume {
+    /// Asset identifiers: `lovelace` first, else in byte order.
+    pub token_a: String,
+    pub token_b: String,
+    pub orders: usize,
+    /// Base units of `token_a` offered (at most, for exact-out orders).
+    pub amount_a_in: u64,
+    /// Base units of `token_b` offered.
+    pub amount_b_in: u64,
+    /// Lovelace value of the orders of ADA pairs, token sides valued at the
+    /// target pool's price; 0 for token/token pairs.
+    pub notional_lovelace: u64,
+}
+
+/// A pending order placed in its pair: `token_a` is `lovelace` when the pair
+/// has it, else the lower identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairOrder<'a> {
+    pub order: &'a PendingOrder,
+    pub token_a: String,
+    pub token_b: String,
+    /// The order offers `token_a` (buys `token_b`).
+    pub sells_a: bool,
+    /// `amount_in` in `token_a` units, at the target pool's price when the
+    /// order offers `token_b`.
+    pub value_in_a: u64,
+}
+
+/// Place `orders` in their pairs, looking their pools up in `pools`. Orders
+/// for pools not in `pools` are left out.
+pub fn pair_orders<'a>(orders: &'a [PendingOrder], pools: &[LiquidityPool]) -> Vec<PairOrder<'a>> {
+    let by_id: HashMap<(&str, &str), &LiquidityPool> = pools
+        .iter()
+        .map(|p| ((p.dex_identifier.as_str(), p.pool_id.as_str()), p))
+        .collect();
+    let rank = |id: &String| (id != "lovelace", id.clone());
+    orders
+        .iter()
+        .filter_map(|order| {
+            let pool = by_id.get(&(order.dex_identifier.as_str(), order.pool_id.as_str()))?;
+            let (token_in, token_out, reserve_in, reserve_out) = match order.direction {
+                Direction::AToB => (&pool.asset_a, &pool.asset_b, pool.reserve_a, pool.reserve_b),
+                Direction::BToA => (&pool.asset_b, &pool.asset_a, pool.reserve_b, pool.reserve_a),
+            };
+            let (id_in, id_out) = (token_identifier(token_in), token_identifier(token_out));
+            let sells_a = rank(&id_in) < rank(&id_out);
+            let value_in_a = if sells_a {
+                order.amount_in
+            } else if reserve_in > 0 {
+                (order.amount_in as u128 * reserve_out as u128 / reserve_in as u128) as u64
+            } else {
+                0
+            };
+            let (token_a, token_b) = if sells_a {
+                (id_in, id_out)
+            } else {
+                (id_out, id_in)
+            };
+            Some(PairOrder {
+                order,
+                token_a,
+                token_b,
+                sells_a,
+                value_in_a,
+            })
+        })
+        .collect()
+}
+
+/// Sum `orders` per pair, looking their pools up in `pools`. Orders for pools
+/// not in `pools` are left out. Busiest pair (by orders) first.
+pub fn volume_by_pair(orders: &[PendingOrder], pools: &[LiquidityPool]) -> Vec<PairVolume> {
+    let mut pairs: BTreeMap<(String, String), PairVolume> = BTreeMap::new();
+    for placed in pair_orders(orders, pools) {
+        let volume = pairs
+            .entry((placed.token_a.clone(), placed.token_b.clone()))
+            .or_insert_with(|| PairVolume {
+                token_a: placed.token_a.clone(),
+                token_b: placed.token_b.clone(),
+                orders: 0,
+                amount_a_in: 0,
+                amount_b_in: 0,
+                notional_lovelace: 0,
+            });
+        volume.orders += 1;
+        if placed.sells_a {
+            volume.amount_a_in += placed.order.amount_in;
+        } else {
+            volume.amount_b_in += placed.order.amount_in;
+        }
+        if placed.token_a == "lovelace" {
+            volume.notional_lovelace += placed.value_in_a;
+        }
+    }
+    let mut volumes: Vec<PairVolume> = pairs.into_values().collect();
+    volumes.sort_by_key(|v| std::cmp::Reverse(v.orders));
+    volumes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+    use crate::models::{Token, Unit};
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+    const LP_NAME: &str = "00aa";
+
+    fn order_datum(direction: u64, amount: PlutusData, min_receive: i128) -> PlutusData {
+        let pkh = PlutusData::bytes_hex(&"11".repeat(28)).unwrap();
+        let address = PlutusData::Constr(0, vec![PlutusData::Constr(0, vec![pkh.clone()])]);
+        PlutusData::Constr(
+            0,
+            vec![
+                PlutusData::Constr(0, vec![pkh]),
+                address.clone(),
+                PlutusData::Constr(0, vec![]),
+                address,
+                PlutusData::Constr(0, vec![]),
+                PlutusData::Constr(
+                    0,
+                    vec![
+                        PlutusData::bytes_hex(LP_TOKEN_POLICY_ID).unwrap(),
+                        PlutusData::bytes_hex(LP_NAME).unwrap(),
+                    ],
+                ),
+                PlutusData::Constr(
+                    0,
+                    vec![
+                        PlutusData::Constr(direction, vec![]),
+                        amount,
+                        PlutusData::Int(min_receive),
+                        PlutusData::Constr(0, vec![]),
+                    ],
+                ),
+                PlutusData::Int(2_000_000),
+                PlutusData::Constr(1, vec![]),
+            ],
+        )
+    }
+
+    fn utxo(index: u32, amount: Vec<(&str, u64)>) -> Utxo {
+        Utxo {
+            address: "addr".to_string(),
+            tx_hash: "ab".repeat(32),
+            tx_index: 0,
+            output_index: index,
+            amount: amount
+                .into_iter()
+                .map(|(unit, quantity)| Unit {
+                    unit: unit.to_string(),
+                    quantity,
+                })
+                .collect(),
+            block: String::new(),
+            slot: Some(100 + index as u64),
+            data_hash: None,
+            inline_datum: None,
+            reference_script_hash: None,
+            datum_type: None,
+        }
+    }
+
+    #[test]
+    fn test_decode_minswap_v2_orders_and_sum_volume() {
+        let decoder = MinswapV2Orders;
+        // 100 ADA for SNEK, a specific amount.
+        let buy = decoder
+            .decode(
+                &utxo(0, vec![("lovelace", 104_000_000)]),
+                &order_datum(
+                    1,
+                    PlutusData::Constr(0, vec![PlutusData::Int(100_000_000)]),
+                    900,
+                ),
+            )
+            .unwrap();
+        assert_eq!(buy.pool_id, format!("{}{}", LP_TOKEN_POLICY_ID, LP_NAME));
+        assert_eq!(
+            (buy.direction, buy.kind),
+            (Direction::AToB, SwapKind::ExactIn)
+        );
+        assert_eq!((buy.amount_in, buy.min_receive), (100_000_000, 900));
+        // All the SNEK the order holds.
+        let sell = decoder
+            .decode(
+                &utxo(1, vec![("lovelace", 4_000_000), (SNEK, 500)]),
+                &order_datum(0, PlutusData::Constr(1, vec![]), 40_000_000),
+            )
+            .unwrap();
+        assert_eq!((sell.direction, sell.amount_in), (Direction::BToA, 500));
+        assert!(decoder
+            .decode(&utxo(2, vec![]), &PlutusData::Constr(0, vec![]))
+            .is_none());
+
+        let pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            from_identifier(SNEK, 0),
+            1_000_000_000,
+            10_000,
+            "addr",
+            0.3,
+            &buy.pool_id,
+        );
+        let volumes = volume_by_pair(&[buy, sell], &[pool]);
+        assert_eq!(volumes.len(), 1);
+        let v = &volumes[0];
+        assert_eq!((v.token_a.as_str(), v.token_b.as_str()), ("lovelace", SNEK));
+        assert_eq!(
+            (v.orders, v.amount_a_in, v.amount_b_in),
+            (2, 100_000_000, 500)
+        );
+        // 500 SNEK at 100,000 lovelace each.
+        assert_eq!(v.notional_lovelace, 150_000_000);
+    }
+}
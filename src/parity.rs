@@ -0,0 +1,338 @@
+//! Pool parity checks against the dexter JS implementation.
+//!
+//! While migrating from the JS codebase, both implementations are run against
+//! the same Kupo and their pool dumps diffed: pools only one side found,
+//! reserves that differ beyond a tolerance, and fee differences.
+//!
+//! The JS dump is a JSON array of dexter `LiquidityPool`s as serialised by a
+//! script (`dex`, `assetA`, `assetB`, `reserveA`, `reserveB`, `identifier`,
+//! `poolFeePercent`); assets may be `"lovelace"`, a unit string or a
+//! `{ policyId, nameHex }` object, and quantities strings or numbers. Rows of
+//! this crate's own export (`pools_rs.json`) are accepted too.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+use crate::models::{AssetId, LiquidityPool};
+
+/// Fee differences below this many percentage points are rounding.
+const FEE_EPSILON: f64 = 1e-6;
+
+/// One pool of the reference (JS) dump.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReferencePool {
+    pub dex: String,
+    pub pool_id: String,
+    pub asset_a: AssetId,
+    pub asset_b: AssetId,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub pool_fee_percent: Option<f64>,
+}
+
+/// Pool found by only one implementation.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnmatchedPool {
+    pub dex: String,
+    pub pool_id: String,
+    pub pair: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReserveMismatch {
+    pub dex: String,
+    pub pool_id: String,
+    /// Reserves oriented as the JS pool.
+    pub js: (u64, u64),
+    pub rust: (u64, u64),
+    /// Largest relative difference of the two sides.
+    pub relative_diff: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeeMismatch {
+    pub dex: String,
+    pub pool_id: String,
+    pub js: f64,
+    pub rust: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ParityReport {
+    pub js_pools: usize,
+    pub rust_pools: usize,
+    pub matched: usize,
+    pub missing_in_rust: Vec<UnmatchedPool>,
+    pub missing_in_js: Vec<UnmatchedPool>,
+    pub reserve_mismatches: Vec<ReserveMismatch>,
+    pub fee_mismatches: Vec<FeeMismatch>,
+}
+
+impl ParityReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing_in_rust.is_empty()
+            && self.missing_in_js.is_empty()
+            && self.reserve_mismatches.is_empty()
+            && self.fee_mismatches.is_empty()
+    }
+}
+
+/// DEX names of both implementations compare equal ("Minswap" / "MinswapV1").
+pub fn dex_key(dex: &str) -> String {
+    let dex = dex.to_lowercase();
+    dex.strip_suffix("v1").map(str::to_string).unwrap_or(dex)
+}
+
+fn id_key(id: &str) -> String {
+    id.replace('.', "").to_lowercase()
+}
+
+/// JS identifiers are sometimes the pool NFT's asset name only.
+fn ids_match(a: &str, b: &str) -> bool {
+    let (a, b) = (id_key(a), id_key(b));
+    !a.is_empty() && !b.is_empty() && (a == b || a.ends_with(&b) || b.ends_with(&a))
+}
+
+fn json_str<'a>(v: &'a JsonValue, keys: &[&str]) -> Option<&'a JsonValue> {
+    keys.iter().find_map(|k| v.get(*k)).filter(|v| !v.is_null())
+}
+
+fn json_u64(v: &JsonValue) -> Option<u64> {
+    match v {
+        JsonValue::Number(n) => n.as_u64(),
+        JsonValue::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn json_asset(v: &JsonValue) -> Option<AssetId> {
+    match v {
+        JsonValue::String(s) => Some(AssetId::from(s.as_str())),
+        JsonValue::Object(_) => {
+            let policy = json_str(v, &["policyId", "policy_id"])?.as_str()?;
+            let name = json_str(v, &["nameHex", "name_hex", "assetName"])
+                .and_then(JsonValue::as_str)
+                .unwrap_or("");
+            Some(AssetId::from(format!("{}{}", policy, name)))
+        }
+        _ => None,
+    }
+}
+
+fn reference_pool(v: &JsonValue) -> Option<ReferencePool> {
+    Some(ReferencePool {
+        dex: json_str(v, &["dex", "dex_identifier"])?
+            .as_str()?
+            .to_string(),
+        pool_id: json_str(v, &["identifier", "pool_id", "poolId"])
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        asset_a: json_asset(json_str(v, &["assetA", "asset_a"])?)?,
+        asset_b: json_asset(json_str(v, &["assetB", "asset_b"])?)?,
+        reserve_a: json_u64(json_str(v, &["reserveA", "reserve_a"])?)?,
+        reserve_b: json_u64(json_str(v, &["reserveB", "reserve_b"])?)?,
+        pool_fee_percent: json_str(v, &["poolFeePercent", "pool_fee_percent"])
+            .and_then(JsonValue::as_f64),
+    })
+}
+
+/// Parse a reference dump; rows that are not pools are an error naming the row.
+pub fn parse_reference_pools(json: &JsonValue) -> Result<Vec<ReferencePool>> {
+    let rows = json
+        .as_array()
+        .ok_or_else(|| anyhow!("reference pools: expected a JSON array"))?;
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            reference_pool(row).ok_or_else(|| anyhow!("reference pools: row {} is not a pool", i))
+        })
+        .collect()
+}
+
+pub fn load_reference_pools(path: &str) -> Result<Vec<ReferencePool>> {
+    let json: JsonValue = crate::cache::load_from_file(path)?;
+    parse_reference_pools(&json)
+}
+
+fn relative_diff(a: u64, b: u64) -> f64 {
+    let max = a.max(b);
+    if max == 0 {
+        0.0
+    } else {
+        a.abs_diff(b) as f64 / max as f64
+    }
+}
+
+/// Diff `js` against `rust`. Pools are paired by DEX and pool id, then, for
+/// pools left over, by DEX and pair when that pair has one pool on each side.
+/// Reserves differing by more than `tolerance` (relative, e.g. `0.001`) and
+/// fees differing at all are reported.
+pub fn compare(js: &[ReferencePool], rust: &[LiquidityPool], tolerance: f64) -> ParityReport {
+    let mut report = ParityReport {
+        js_pools: js.len(),
+        rust_pools: rust.len(),
+        ..Default::default()
+    };
+    let mut rust_used = vec![false; rust.len()];
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    let mut js_left = Vec::new();
+
+    for (j, pool) in js.iter().enumerate() {
+        let found = (0..rust.len()).find(|&r| {
+            !rust_used[r]
+                && dex_key(&rust[r].dex_identifier) == dex_key(&pool.dex)
+                && ids_match(&rust[r].pool_id, &pool.pool_id)
+        });
+        match found {
+            Some(r) => {
+                rust_used[r] = true;
+                pairs.push((j, r));
+            }
+            None => js_left.push(j),
+        }
+    }
+
+    let pair_key = |dex: &str, a: &AssetId, b: &AssetId| {
+        let (a, b) = if a <= b { (a, b) } else { (b, a) };
+        format!("{}|{}|{}", dex_key(dex), a, b)
+    };
+    let mut rust_by_pair: HashMap<String, Vec<usize>> = HashMap::new();
+    for (r, pool) in rust.iter().enumerate().filter(|(r, _)| !rust_used[*r]) {
+        let key = pair_key(
+            &pool.dex_identifier,
+            &AssetId::from(&pool.asset_a),
+            &AssetId::from(&pool.asset_b),
+        );
+        rust_by_pair.entry(key).or_default().push(r);
+    }
+    let mut js_by_pair: HashMap<String, Vec<usize>> = HashMap::new();
+    for &j in &js_left {
+        let pool = &js[j];
+        js_by_pair
+            .entry(pair_key(&pool.dex, &pool.asset_a, &pool.asset_b))
+            .or_default()
+            .push(j);
+    }
+    for &j in &js_left {
+        let pool = &js[j];
+        let key = pair_key(&pool.dex, &pool.asset_a, &pool.asset_b);
+        match (
+            js_by_pair[&key].as_slice(),
+            rust_by_pair.get(&key).map(Vec::as_slice),
+        ) {
+            ([_], Some([r])) => {
+                rust_used[*r] = true;
+                pairs.push((j, *r));
+            }
+            _ => report.missing_in_rust.push(UnmatchedPool {
+                dex: pool.dex.clone(),
+                pool_id: pool.pool_id.clone(),
+                pair: format!("{}/{}", pool.asset_a, pool.asset_b),
+            }),
+        }
+    }
+    for (_, pool) in rust.iter().enumerate().filter(|(r, _)| !rust_used[*r]) {
+        report.missing_in_js.push(UnmatchedPool {
+            dex: pool.dex_identifier.clone(),
+            pool_id: pool.pool_id.clone(),
+            pair: pool.pair(),
+        });
+    }
+
+    report.matched = pairs.len();
+    pairs.sort_unstable();
+    for (j, r) in pairs {
+        let (expected, actual) = (&js[j], &rust[r]);
+        let reversed = AssetId::from(&actual.asset_a) != expected.asset_a
+            && AssetId::from(&actual.asset_b) == expected.asset_a;
+        let rust_reserves = if reversed {
+            (actual.reserve_b, actual.reserve_a)
+        } else {
+            (actual.reserve_a, actual.reserve_b)
+        };
+        let diff = relative_diff(expected.reserve_a, rust_reserves.0)
+            .max(relative_diff(expected.reserve_b, rust_reserves.1));
+        if diff > tolerance {
+            report.reserve_mismatches.push(ReserveMismatch {
+                dex: actual.dex_identifier.clone(),
+                pool_id: actual.pool_id.clone(),
+                js: (expected.reserve_a, expected.reserve_b),
+                rust: rust_reserves,
+                relative_diff: diff,
+            });
+        }
+        if let Some(fee) = expected.pool_fee_percent {
+            if (fee - actual.pool_fee_percent).abs() > FEE_EPSILON {
+                report.fee_mismatches.push(FeeMismatch {
+                    dex: actual.dex_identifier.clone(),
+                    pool_id: actual.pool_id.clone(),
+                    js: fee,
+                    rust: actual.pool_fee_percent,
+                });
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+    use crate::models::Token;
+    use serde_json::json;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    #[test]
+    fn test_compare_reports_each_kind_of_difference() {
+        let js = parse_reference_pools(&json!([
+            {
+                "dex": "MinswapV2", "identifier": "aa01",
+                "assetA": "lovelace",
+                "assetB": { "policyId": &SNEK[..56], "nameHex": &SNEK[56..] },
+                "reserveA": "1000000", "reserveB": 500, "poolFeePercent": 0.3
+            },
+            {
+                "dex": "Minswap", "identifier": "",
+                "assetA": SNEK, "assetB": "lovelace",
+                "reserveA": "100", "reserveB": "2000000", "poolFeePercent": 0.3
+            },
+            { "dex": "WingRiders", "identifier": "w1", "assetA": "lovelace", "assetB": SNEK, "reserveA": 1, "reserveB": 1 }
+        ]))
+        .unwrap();
+
+        let pool = |dex: &str, id: &str, reserve_a, reserve_b, fee| {
+            LiquidityPool::new(
+                dex,
+                Token::Lovelace,
+                from_identifier(SNEK, 0),
+                reserve_a,
+                reserve_b,
+                "addr",
+                fee,
+                id,
+            )
+        };
+        let rust = vec![
+            // Matched by id (JS identifier is the NFT name); reserves within 0.1%.
+            pool("MinswapV2", "ffff.aa01", 1_000_500, 500, 0.3),
+            // Matched by pair, opposite orientation; reserves and fee differ.
+            pool("MinswapV1", "bb", 2_100_000, 100, 0.25),
+            pool("SundaeSwapV3", "s1", 1, 1, 0.3),
+        ];
+
+        let report = compare(&js, &rust, 0.001);
+        assert_eq!(report.matched, 2);
+        assert_eq!(report.missing_in_rust.len(), 1);
+        assert_eq!(report.missing_in_rust[0].pool_id, "w1");
+        assert_eq!(report.missing_in_js[0].pool_id, "s1");
+        assert_eq!(report.reserve_mismatches.len(), 1);
+        assert_eq!(report.reserve_mismatches[0].rust, (100, 2_100_000));
+        assert_eq!(report.fee_mismatches.len(), 1);
+        assert!(!report.is_clean());
+    }
+}
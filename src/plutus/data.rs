@@ -9,8 +9,14 @@
 //! and a definite-length empty array (`80`) when empty — this matches the
 //! byte form Lucid (and Minswap) emit, and is what the spec §7.1 golden
 //! datum requires.
+//!
+//! [`PlutusData::from_cbor_hex`] goes the other way, turning any datum into a
+//! tree for inspection; [`PlutusData::to_json`] renders it in the
+//! `cardano-cli` detailed schema.
 
 use anyhow::{anyhow, Result};
+use ciborium::value::Value;
+use serde_json::json;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PlutusData {
@@ -18,6 +24,7 @@ pub enum PlutusData {
     Int(i128),
     Bytes(Vec<u8>),
     List(Vec<PlutusData>),
+    Map(Vec<(PlutusData, PlutusData)>),
 }
 
 impl PlutusData {
@@ -35,6 +42,89 @@ impl PlutusData {
         Ok(hex::encode(buf))
     }
 
+    /// Decode a CBOR hex datum. Big integers (tags 2 and 3) must fit an `i128`.
+    pub fn from_cbor_hex(cbor_hex: &str) -> Result<Self> {
+        let bytes = hex::decode(cbor_hex).map_err(|e| anyhow!("invalid datum hex: {}", e))?;
+        let value: Value = ciborium::de::from_reader(bytes.as_slice())
+            .map_err(|e| anyhow!("CBOR decode error: {}", e))?;
+        Self::from_value(&value)
+    }
+
+    fn from_value(value: &Value) -> Result<Self> {
+        match value {
+            Value::Integer(i) => Ok(PlutusData::Int((*i).into())),
+            Value::Bytes(b) => Ok(PlutusData::Bytes(b.clone())),
+            Value::Array(items) => Ok(PlutusData::List(
+                items.iter().map(Self::from_value).collect::<Result<_>>()?,
+            )),
+            Value::Map(entries) => Ok(PlutusData::Map(
+                entries
+                    .iter()
+                    .map(|(k, v)| Ok((Self::from_value(k)?, Self::from_value(v)?)))
+                    .collect::<Result<_>>()?,
+            )),
+            Value::Tag(tag @ (2 | 3), inner) => {
+                let Value::Bytes(b) = inner.as_ref() else {
+                    return Err(anyhow!("bignum tag {} without bytes", tag));
+                };
+                let n = b
+                    .iter()
+                    .try_fold(0i128, |n, byte| {
+                        n.checked_mul(256)?.checked_add(*byte as i128)
+                    })
+                    .ok_or_else(|| anyhow!("bignum of {} bytes does not fit i128", b.len()))?;
+                Ok(PlutusData::Int(if *tag == 2 { n } else { -1 - n }))
+            }
+            Value::Tag(tag, inner) => {
+                let (index, fields) = match (*tag, inner.as_ref()) {
+                    (121..=127, Value::Array(fields)) => (tag - 121, fields),
+                    (1280..=1400, Value::Array(fields)) => (tag - 1280 + 7, fields),
+                    (102, Value::Array(pair)) => match pair.as_slice() {
+                        [Value::Integer(i), Value::Array(fields)] => {
+                            let index: i128 = (*i).into();
+                            let index = u64::try_from(index)
+                                .map_err(|_| anyhow!("bad constructor index {}", index))?;
+                            (index, fields)
+                        }
+                        _ => return Err(anyhow!("malformed tag 102 constructor")),
+                    },
+                    _ => return Err(anyhow!("unexpected CBOR tag {} in datum", tag)),
+                };
+                Ok(PlutusData::Constr(
+                    index,
+                    fields.iter().map(Self::from_value).collect::<Result<_>>()?,
+                ))
+            }
+            other => Err(anyhow!("unexpected CBOR value in datum: {:?}", other)),
+        }
+    }
+
+    /// `cardano-cli` detailed-schema JSON (`{"constructor": 0, "fields": [...]}`,
+    /// `{"int": 1}`, `{"bytes": "ab"}`, `{"list": [...]}`, `{"map": [{"k", "v"}]}`).
+    /// Integers beyond `i64` are rendered as strings.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            PlutusData::Constr(index, fields) => json!({
+                "constructor": index,
+                "fields": fields.iter().map(Self::to_json).collect::<Vec<_>>(),
+            }),
+            PlutusData::Int(i) => match i64::try_from(*i) {
+                Ok(n) => json!({ "int": n }),
+                Err(_) => json!({ "int": i.to_string() }),
+            },
+            PlutusData::Bytes(b) => json!({ "bytes": hex::encode(b) }),
+            PlutusData::List(items) => json!({
+                "list": items.iter().map(Self::to_json).collect::<Vec<_>>(),
+            }),
+            PlutusData::Map(entries) => json!({
+                "map": entries
+                    .iter()
+                    .map(|(k, v)| json!({ "k": k.to_json(), "v": v.to_json() }))
+                    .collect::<Vec<_>>(),
+            }),
+        }
+    }
+
     fn write_cbor(&self, out: &mut Vec<u8>) -> Result<()> {
         match self {
             PlutusData::Int(i) => write_int(*i, out),
@@ -62,6 +152,14 @@ impl PlutusData {
                 Ok(())
             }
             PlutusData::List(items) => write_fields(items, out),
+            PlutusData::Map(entries) => {
+                write_head(5, entries.len() as u64, out);
+                for (k, v) in entries {
+                    k.write_cbor(out)?;
+                    v.write_cbor(out)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -214,4 +312,29 @@ mod tests {
             "9f0102ff"
         );
     }
+
+    #[test]
+    fn decode_round_trips_and_renders_json() {
+        let datum = PlutusData::Constr(
+            0,
+            vec![
+                PlutusData::Int(-5),
+                PlutusData::bytes_hex("abcd").unwrap(),
+                PlutusData::Constr(128, vec![]),
+                PlutusData::Map(vec![(PlutusData::Int(1), PlutusData::List(vec![]))]),
+            ],
+        );
+        let hex = cbor(datum.clone());
+        assert_eq!(PlutusData::from_cbor_hex(&hex).unwrap(), datum);
+        assert_eq!(
+            datum.to_json()["fields"][1],
+            serde_json::json!({ "bytes": "abcd" })
+        );
+        assert_eq!(datum.to_json()["fields"][3]["map"][0]["k"]["int"], 1);
+
+        // Positive bignum (tag 2) of 2^64.
+        let big = PlutusData::from_cbor_hex("c249010000000000000000").unwrap();
+        assert_eq!(big, PlutusData::Int(1 << 64));
+        assert_eq!(big.to_json()["int"], "18446744073709551616");
+    }
 }
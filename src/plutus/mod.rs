@@ -1,7 +1,8 @@
 //! Plutus data encoding (CBOR).
 //!
 //! The decode-side counterpart lives in `dex/cbor.rs`. This module is the
-//! encode side — used to build order datums for DEX interaction.
+//! encode side — used to build order datums for DEX interaction — plus a
+//! generic decoder for inspecting arbitrary datums.
 
 pub mod data;
 pub use data::PlutusData;
@@ -0,0 +1,140 @@
+//! Wallet holdings valued against on-chain pools.
+//!
+//! [`lp_positions`] finds the LP tokens held at an address, matches each one to
+//! its pool through [`BaseDex::lp_token_unit`], and reports the share of the
+//! pool and the reserves the holder could withdraw.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::dex::BaseDex;
+use crate::kupo::KupoApi;
+use crate::models::{LiquidityPool, Token};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LpPosition {
+    pub dex_identifier: String,
+    pub pool_id: String,
+    pub pair: String,
+    pub lp_unit: String,
+    pub balance: u64,
+    pub total_lp_tokens: u64,
+    /// `balance / total_lp_tokens`; `None` when the pool does not report its LP supply.
+    pub share: Option<f64>,
+    pub asset_a: Token,
+    pub asset_b: Token,
+    /// Reserves redeemable for `balance` (base units, rounded down).
+    pub amount_a: u64,
+    pub amount_b: u64,
+}
+
+fn pro_rata(reserve: u64, balance: u64, total: u64) -> u64 {
+    if total == 0 {
+        return 0;
+    }
+    (reserve as u128 * balance as u128 / total as u128) as u64
+}
+
+/// Value `balance` LP tokens of `pool`.
+pub fn position_from_pool(pool: &LiquidityPool, lp_unit: &str, balance: u64) -> LpPosition {
+    let total = pool.total_lp_tokens;
+    LpPosition {
+        dex_identifier: pool.dex_identifier.clone(),
+        pool_id: pool.pool_id.clone(),
+        pair: pool.pair(),
+        lp_unit: lp_unit.to_string(),
+        balance,
+        total_lp_tokens: total,
+        share: (total > 0).then(|| balance as f64 / total as f64),
+        asset_a: pool.asset_a.clone(),
+        asset_b: pool.asset_b.clone(),
+        amount_a: pro_rata(pool.reserve_a, balance, total),
+        amount_b: pro_rata(pool.reserve_b, balance, total),
+    }
+}
+
+/// LP positions held at `address` across `dexes`.
+///
+/// Only DEXes with an LP policy under which the wallet holds something are
+/// queried, and a DEX that fails is logged and skipped. Datum-backed LP supply
+/// is read through the extend step, so pools dropped by the DEX's filter are
+/// left out.
+pub async fn lp_positions(
+    kupo: &KupoApi,
+    address: &str,
+    dexes: &[&dyn BaseDex],
+) -> Result<Vec<LpPosition>> {
+    let mut balances: HashMap<String, u64> = HashMap::new();
+    for utxo in kupo.get(address, true).await? {
+        for unit in utxo.non_ada_assets() {
+            *balances.entry(unit.unit.clone()).or_default() += unit.quantity;
+        }
+    }
+
+    let mut positions = Vec::new();
+    for dex in dexes {
+        let policy = dex.lp_token_policy_id();
+        if policy.len() != 56 || !balances.keys().any(|unit| unit.starts_with(policy)) {
+            continue;
+        }
+        let utxos = match dex.all_liquidity_pool_utxos().await {
+            Ok(utxos) => utxos,
+            Err(e) => {
+                eprintln!("[portfolio] {} failed: {}", dex.identifier(), e);
+                continue;
+            }
+        };
+        for utxo in &utxos {
+            let Ok(Some(base)) = dex.liquidity_pool_from_utxo(utxo, "").await else {
+                continue;
+            };
+            let Some(lp_unit) = dex.lp_token_unit(&base) else {
+                continue;
+            };
+            let Some(&balance) = balances.get(&lp_unit) else {
+                continue;
+            };
+            let pool = match dex.liquidity_pool_from_utxo_extend(utxo, &base.pool_id).await {
+                Ok(Some(pool)) => pool,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("[portfolio] {} {}: {}", dex.identifier(), base.pool_id, e);
+                    base
+                }
+            };
+            positions.push(position_from_pool(&pool, &lp_unit, balance));
+        }
+    }
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_from_pool() {
+        let mut pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            Token::Lovelace,
+            10_000_000,
+            3_333,
+            "addr",
+            0.3,
+            "pool",
+        );
+        pool.total_lp_tokens = 1_000;
+
+        let position = position_from_pool(&pool, "lp", 250);
+        assert_eq!(position.share, Some(0.25));
+        assert_eq!(position.amount_a, 2_500_000);
+        assert_eq!(position.amount_b, 833);
+
+        pool.total_lp_tokens = 0;
+        let position = position_from_pool(&pool, "lp", 250);
+        assert_eq!(position.share, None);
+        assert_eq!(position.amount_a, 0);
+    }
+}
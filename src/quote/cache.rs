@@ -0,0 +1,208 @@
+//! Memoised swap quotes for hot routing paths.
+//!
+//! Entries are keyed by `(pool_id, direction, amount bucket)` and tagged with
+//! the tx hash of the pool UTXO they were computed from. A lookup against a
+//! pool whose UTXO has moved (new tx hash) misses and overwrites the entry, so
+//! callers never need to invalidate explicitly after a refresh.
+//!
+//! Amounts are bucketed to [`QuoteCache::precision`] significant digits; a hit
+//! scales the cached output linearly to the requested amount, which is exact
+//! enough inside a bucket for routing but not for building orders — use the
+//! raw functions in [`crate::quote`] for the final minimum-receive.
+
+use super::{amm_out, stable_out, Direction};
+use crate::models::{LiquidityPool, StablePool};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+/// Default number of significant digits kept in an amount bucket.
+const DEFAULT_PRECISION: u32 = 3;
+/// Default maximum number of cached entries before the cache is cleared.
+const DEFAULT_CAPACITY: usize = 100_000;
+
+type Key = (String, Direction, u64);
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Tx hash of the pool UTXO the quote was computed from.
+    version: String,
+    amount_in: u64,
+    amount_out: u64,
+}
+
+#[derive(Debug)]
+pub struct QuoteCache {
+    precision: u32,
+    capacity: usize,
+    entries: RwLock<HashMap<Key, Entry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for QuoteCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QuoteCache {
+    pub fn new() -> Self {
+        Self {
+            precision: DEFAULT_PRECISION,
+            capacity: DEFAULT_CAPACITY,
+            entries: RwLock::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Significant digits per amount bucket (min 1). Higher is more exact, with fewer hits.
+    pub fn with_precision(mut self, digits: u32) -> Self {
+        self.precision = digits.max(1);
+        self
+    }
+
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    /// Quote an AMM pool through the cache.
+    pub fn amm_out(&self, pool: &LiquidityPool, direction: Direction, amount_in: u64) -> u64 {
+        let version = pool.metadata.freshness.as_ref().map(|f| f.tx_hash.as_str());
+        self.get_or_compute(&pool.pool_id, version, direction, amount_in, |a| {
+            amm_out(pool, direction, a)
+        })
+    }
+
+    /// Quote a stable pool through the cache.
+    pub fn stable_out(&self, pool: &StablePool, direction: Direction, amount_in: u64) -> u64 {
+        let version = pool.freshness.as_ref().map(|f| f.tx_hash.as_str());
+        self.get_or_compute(&pool.pool_id, version, direction, amount_in, |a| {
+            stable_out(pool, direction, a)
+        })
+    }
+
+    /// Look up `(pool_id, direction, bucket(amount_in))` for the pool at `version`
+    /// (its UTXO tx hash), computing and storing the quote on a miss.
+    ///
+    /// Pools without a version (no freshness recorded) are never cached.
+    pub fn get_or_compute(
+        &self,
+        pool_id: &str,
+        version: Option<&str>,
+        direction: Direction,
+        amount_in: u64,
+        compute: impl FnOnce(u64) -> u64,
+    ) -> u64 {
+        let Some(version) = version else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return compute(amount_in);
+        };
+        let key = (pool_id.to_string(), direction, self.bucket(amount_in));
+
+        if let Some(entry) = self.entries.read().unwrap().get(&key) {
+            if entry.version == version {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return scale(entry.amount_out, entry.amount_in, amount_in);
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let amount_out = compute(amount_in);
+        let mut entries = self.entries.write().unwrap();
+        if entries.len() >= self.capacity {
+            entries.clear();
+        }
+        entries.insert(
+            key,
+            Entry {
+                version: version.to_string(),
+                amount_in,
+                amount_out,
+            },
+        );
+        amount_out
+    }
+
+    /// Drop every cached quote for `pool_id`.
+    pub fn invalidate_pool(&self, pool_id: &str) {
+        self.entries
+            .write()
+            .unwrap()
+            .retain(|(id, _, _), _| id != pool_id);
+    }
+
+    pub fn clear(&self) {
+        self.entries.write().unwrap().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `(hits, misses)` since creation.
+    pub fn stats(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Round `amount` down to `precision` significant digits.
+    fn bucket(&self, amount: u64) -> u64 {
+        let digits = amount.checked_ilog10().map_or(1, |d| d + 1);
+        if digits <= self.precision {
+            return amount;
+        }
+        let step = 10u64.pow(digits - self.precision);
+        amount / step * step
+    }
+}
+
+fn scale(amount_out: u64, from: u64, to: u64) -> u64 {
+    if from == to || from == 0 {
+        return amount_out;
+    }
+    (amount_out as u128 * to as u128 / from as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_cache_hits_and_invalidates_on_new_tx() {
+        let cache = QuoteCache::new();
+        let calls = Cell::new(0);
+        let quote = |version: &str, amount: u64| {
+            cache.get_or_compute("pool", Some(version), Direction::AToB, amount, |a| {
+                calls.set(calls.get() + 1);
+                a * 2
+            })
+        };
+
+        assert_eq!(quote("tx1", 1_234_000), 2_468_000);
+        // Same bucket (3 significant digits): served from cache, scaled.
+        assert_eq!(quote("tx1", 1_234_500), 2_469_000);
+        assert_eq!(calls.get(), 1);
+
+        // Pool UTXO moved: recomputed.
+        assert_eq!(quote("tx2", 1_234_500), 2_469_000);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(cache.stats(), (1, 2));
+
+        cache.invalidate_pool("pool");
+        assert!(cache.is_empty());
+    }
+}
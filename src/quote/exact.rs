@@ -0,0 +1,360 @@
+//! Exact integer swap math, for min-receive values the validators accept.
+//!
+//! Pool fees are `f64` percentages on [`LiquidityPool`]; [`FeeFraction`]
+//! recovers the integer fraction the datum encodes (basis points or finer)
+//! before any arithmetic, and every product goes through a 256-bit
+//! [`mul_div`], so no quote here is rounded through a float or overflows on
+//! large token reserves. Outputs round down and required inputs round up, the
+//! direction a validator checks them in.
+//!
+//! Validators also differ in how they take the fee: [`SwapMath`] encodes that
+//! per DEX (each DEX module has a `SWAP_MATH`, looked up by
+//! [`crate::dex::swap_math`]), so [`estimated_receive_exact`] matches on-chain
+//! execution to the base unit.
+
+use crate::models::{token_identifier, LiquidityPool, Token};
+use crate::quote::Direction;
+
+/// Denominators tried, smallest first, when recovering a fee fraction.
+const FEE_DENOMINATORS: [u64; 3] = [10_000, 100_000, 1_000_000];
+
+/// A fee as `numerator / denominator` of the input amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeFraction {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl FeeFraction {
+    /// Fraction of a percentage: the smallest of 1/10_000, 1/100_000 and
+    /// 1/1_000_000 that represents it exactly, else rounded to parts per million.
+    pub fn from_percent(fee_percent: f64) -> Self {
+        let fraction = fee_percent.clamp(0.0, 100.0) / 100.0;
+        let exact = FEE_DENOMINATORS.iter().find_map(|&denominator| {
+            let scaled = fraction * denominator as f64;
+            ((scaled - scaled.round()).abs() < 1e-6).then(|| Self {
+                numerator: scaled.round() as u64,
+                denominator,
+            })
+        });
+        exact.unwrap_or(Self {
+            numerator: (fraction * 1_000_000.0).round() as u64,
+            denominator: 1_000_000,
+        })
+    }
+
+    /// The part of the input left after the fee, as a fraction of the input.
+    fn kept(&self) -> u128 {
+        (self.denominator - self.numerator.min(self.denominator)) as u128
+    }
+}
+
+/// `a * b / c` rounded down, through a 256-bit product. `None` when `c` is 0
+/// or the result does not fit `u128`.
+pub fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+    if c == 0 {
+        return None;
+    }
+    let (hi, lo) = mul_wide(a, b);
+    if hi >= c {
+        return None;
+    }
+    // Long division of hi:lo by c, one bit at a time; the remainder stays < c.
+    let mut rem = hi;
+    let mut quotient = 0u128;
+    for i in (0..128).rev() {
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        quotient <<= 1;
+        if carry == 1 || rem >= c {
+            rem = rem.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+    Some(quotient)
+}
+
+/// `a * b / c` rounded up.
+pub fn mul_div_ceil(a: u128, b: u128, c: u128) -> Option<u128> {
+    let floor = mul_div(a, b, c)?;
+    let (hi, lo) = mul_wide(a, b);
+    let (back_hi, back_lo) = mul_wide(floor, c);
+    if (hi, lo) == (back_hi, back_lo) {
+        Some(floor)
+    } else {
+        floor.checked_add(1)
+    }
+}
+
+/// Full 256-bit product of `a` and `b` as `(high, low)` halves.
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+    let middle = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let low = (middle << 64) | (lo_lo & MASK);
+    let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// `x * y = k` output for `amount_in` with `fee` taken from the input,
+/// rounded down.
+pub fn constant_product_out_exact(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    fee: FeeFraction,
+) -> u64 {
+    ScaledFee.amount_out(reserve_in, reserve_out, amount_in, fee)
+}
+
+/// Input needed for `amount_out` with `fee` taken from the input, rounded up;
+/// `None` when the pool cannot pay `amount_out`.
+pub fn constant_product_in_exact(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_out: u64,
+    fee: FeeFraction,
+) -> Option<u64> {
+    if amount_out == 0 {
+        return Some(0);
+    }
+    if amount_out >= reserve_out || fee.kept() == 0 {
+        return None;
+    }
+    let num = reserve_in as u128 * fee.denominator as u128;
+    let den = (reserve_out - amount_out) as u128 * fee.kept();
+    let amount_in = mul_div_ceil(num, amount_out as u128, den)?;
+    u64::try_from(amount_in).ok()
+}
+
+/// A DEX validator's swap arithmetic.
+///
+/// Implementors only say how much of the input survives the fee; outputs are
+/// then the largest the constant-product invariant allows, and required
+/// inputs the smallest whose output reaches the target.
+pub trait SwapMath: Send + Sync {
+    /// Input left after the fee, scaled by `fee.denominator`.
+    fn scaled_input_after_fee(&self, amount_in: u64, fee: FeeFraction) -> u128;
+
+    /// Output for `amount_in`, rounded down.
+    fn amount_out(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        amount_in: u64,
+        fee: FeeFraction,
+    ) -> u64 {
+        if reserve_in == 0 || reserve_out == 0 || amount_in == 0 {
+            return 0;
+        }
+        let in_after_fee = self.scaled_input_after_fee(amount_in, fee);
+        let den = reserve_in as u128 * fee.denominator as u128 + in_after_fee;
+        // Always below reserve_out, so it fits u64.
+        mul_div(in_after_fee, reserve_out as u128, den).unwrap_or(0) as u64
+    }
+
+    /// Smallest input whose [`amount_out`](Self::amount_out) is at least
+    /// `amount_out`; `None` when no input reaches it.
+    fn amount_in(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        amount_out: u64,
+        fee: FeeFraction,
+    ) -> Option<u64> {
+        if amount_out == 0 {
+            return Some(0);
+        }
+        if amount_out >= reserve_out
+            || self.amount_out(reserve_in, reserve_out, u64::MAX, fee) < amount_out
+        {
+            return None;
+        }
+        // Outputs never decrease with the input: binary search the smallest.
+        let (mut lo, mut hi) = (1u64, u64::MAX);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.amount_out(reserve_in, reserve_out, mid, fee) >= amount_out {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        Some(lo)
+    }
+}
+
+/// Fee kept in the product: `amount_in * (denominator - numerator)` enters the
+/// invariant unrounded (Minswap V1 and V2, and the default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScaledFee;
+
+impl SwapMath for ScaledFee {
+    fn scaled_input_after_fee(&self, amount_in: u64, fee: FeeFraction) -> u128 {
+        amount_in as u128 * fee.kept()
+    }
+
+    fn amount_in(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        amount_out: u64,
+        fee: FeeFraction,
+    ) -> Option<u64> {
+        constant_product_in_exact(reserve_in, reserve_out, amount_out, fee)
+    }
+}
+
+/// Fee rounded up to whole base units and deducted from the input before the
+/// swap, `ceil(amount_in * numerator / denominator)` (SundaeSwap, WingRiders,
+/// VyFinance).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CeilFee;
+
+impl SwapMath for CeilFee {
+    fn scaled_input_after_fee(&self, amount_in: u64, fee: FeeFraction) -> u128 {
+        let charged = mul_div_ceil(
+            amount_in as u128,
+            fee.numerator as u128,
+            fee.denominator as u128,
+        )
+        .unwrap_or(amount_in as u128)
+        .min(amount_in as u128);
+        (amount_in as u128 - charged) * fee.denominator as u128
+    }
+}
+
+fn reserves(pool: &LiquidityPool, direction: Direction) -> (u64, u64) {
+    match direction {
+        Direction::AToB => (pool.reserve_a, pool.reserve_b),
+        Direction::BToA => (pool.reserve_b, pool.reserve_a),
+    }
+}
+
+/// Output of `pool` for `in_amount` of `in_token`, in integers throughout and
+/// with its DEX's [`SwapMath`].
+pub fn estimated_receive_exact(pool: &LiquidityPool, in_token: &Token, in_amount: u64) -> u64 {
+    let direction = Direction::for_input(&pool.asset_a, in_token);
+    let (reserve_in, reserve_out) = reserves(pool, direction);
+    crate::dex::swap_math(&pool.dex_identifier).amount_out(
+        reserve_in,
+        reserve_out,
+        in_amount,
+        pool.fee_fraction(direction),
+    )
+}
+
+/// Input of the other asset `pool` needs to pay `out_amount` of `out_token`;
+/// `None` when it cannot.
+pub fn estimated_give_exact(
+    pool: &LiquidityPool,
+    out_token: &Token,
+    out_amount: u64,
+) -> Option<u64> {
+    let direction = if token_identifier(out_token) == token_identifier(&pool.asset_a) {
+        Direction::BToA
+    } else {
+        Direction::AToB
+    };
+    let (reserve_in, reserve_out) = reserves(pool, direction);
+    crate::dex::swap_math(&pool.dex_identifier).amount_in(
+        reserve_in,
+        reserve_out,
+        out_amount,
+        pool.fee_fraction(direction),
+    )
+}
+
+/// Minimum receive for `estimated` at `slippage_percent`, rounded down:
+/// `estimated / (1 + slippage)` with the slippage in parts per million.
+pub fn min_receive_exact(estimated: u64, slippage_percent: f64) -> u64 {
+    let slippage = (slippage_percent.max(0.0) * 10_000.0).round() as u128;
+    mul_div(estimated as u128, 1_000_000, 1_000_000 + slippage).unwrap_or(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quote::constant_product_out;
+
+    #[test]
+    fn test_exact_math_matches_and_survives_large_reserves() {
+        assert_eq!(
+            FeeFraction::from_percent(0.3),
+            FeeFraction {
+                numerator: 30,
+                denominator: 10_000
+            }
+        );
+        assert_eq!(FeeFraction::from_percent(0.005).denominator, 100_000);
+
+        let fee = FeeFraction::from_percent(0.3);
+        for amount in [1, 10_000, 123_456_789] {
+            assert_eq!(
+                constant_product_out_exact(1_000_000_000, 3_000_000_000, amount, fee),
+                constant_product_out(1_000_000_000, 3_000_000_000, amount, 0.3)
+            );
+        }
+
+        // in_after_fee * reserve_out overflows u128 here.
+        let big = u64::MAX / 2;
+        let out = constant_product_out_exact(big, big, big / 10, fee);
+        assert!(out > 0 && out < big / 10);
+        let needed = constant_product_in_exact(big, big, out, fee).unwrap();
+        assert!(needed <= big / 10);
+        assert!(constant_product_out_exact(big, big, needed, fee) >= out);
+        assert!(constant_product_out_exact(big, big, needed - 1, fee) < out);
+
+        assert_eq!(mul_div(u128::MAX, 3, 3), Some(u128::MAX));
+        assert_eq!(mul_div_ceil(10, 1, 3), Some(4));
+        // 2^53 + 1 is not representable as f64.
+        assert_eq!(min_receive_exact((1 << 53) + 1, 0.0), (1 << 53) + 1);
+        assert_eq!(min_receive_exact(1_000_000, 1.0), 990_099);
+    }
+
+    #[test]
+    fn test_swap_math_rounding_per_dex() {
+        let fee = FeeFraction::from_percent(0.3);
+        // 0.3 of a unit of fee: kept fractionally, or charged as a whole unit.
+        assert_eq!(ScaledFee.amount_out(1_000_000, 1_000_000, 100, fee), 99);
+        assert_eq!(ScaledFee.scaled_input_after_fee(100, fee), 997_000);
+        assert_eq!(CeilFee.scaled_input_after_fee(100, fee), 990_000);
+        assert_eq!(CeilFee.amount_out(1_000, 1_000_000, 100, fee), 90_081);
+        assert_eq!(ScaledFee.amount_out(1_000, 1_000_000, 100, fee), 90_661);
+
+        for out in [1, 5_000, 90_081, 500_000] {
+            let needed = CeilFee.amount_in(1_000, 1_000_000, out, fee).unwrap();
+            assert!(CeilFee.amount_out(1_000, 1_000_000, needed, fee) >= out);
+            assert!(CeilFee.amount_out(1_000, 1_000_000, needed - 1, fee) < out);
+        }
+        assert_eq!(CeilFee.amount_in(1_000, 1_000_000, 1_000_000, fee), None);
+
+        let mut pool = LiquidityPool::new(
+            "SundaeSwapV3",
+            Token::Lovelace,
+            crate::models::asset::from_identifier(
+                "aa00000000000000000000000000000000000000000000000000000055534443",
+                0,
+            ),
+            1_000,
+            1_000_000,
+            "addr",
+            0.3,
+            "pool",
+        );
+        assert_eq!(
+            estimated_receive_exact(&pool, &Token::Lovelace, 100),
+            90_081
+        );
+        pool.dex_identifier = "MinswapV2".to_string();
+        assert_eq!(
+            estimated_receive_exact(&pool, &Token::Lovelace, 100),
+            90_661
+        );
+    }
+}
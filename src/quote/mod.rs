@@ -0,0 +1,399 @@
+//! Swap output math for AMM and stable pools, plus a quote cache.
+//!
+//! All functions work in base units with integer arithmetic, so quotes match
+//! what the on-chain validators accept (up to rounding direction).
+//!
+//! [`exact`] repeats the AMM math without floats anywhere, for min-receive
+//! values built straight from a quote.
+//!
+//! [`check_amm_quote`] validates an AMM quote against the fee-adjusted
+//! constant-product invariant. With the `invariant-checks` feature, every
+//! [`amm_out`] is also cross-checked against [`invariant_max_out`] and panics
+//! on a mismatch, so downstream test suites catch math regressions.
+
+pub mod cache;
+pub mod exact;
+pub mod price;
+
+pub use cache::QuoteCache;
+pub use exact::{estimated_give_exact, estimated_receive_exact, FeeFraction};
+pub use price::{pool_quote, NotionalQuote, PoolQuote};
+
+use crate::models::{token_identifier, LiquidityPool, StablePool, Token};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Fee precision: percentages are converted to parts per `FEE_DENOMINATOR`.
+const FEE_DENOMINATOR: u128 = 1_000_000;
+/// Newton iterations before giving up on the StableSwap invariant.
+const MAX_ITERATIONS: usize = 255;
+
+/// Which way a swap goes through a two-asset pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    AToB,
+    BToA,
+}
+
+impl Direction {
+    /// Direction for swapping `in_token` into a pool whose first asset is `asset_a`.
+    pub fn for_input(asset_a: &Token, in_token: &Token) -> Direction {
+        if token_identifier(asset_a) == token_identifier(in_token) {
+            Direction::AToB
+        } else {
+            Direction::BToA
+        }
+    }
+}
+
+fn fee_parts(fee_percent: f64) -> u128 {
+    ((fee_percent * 10_000.0).round().max(0.0) as u128).min(FEE_DENOMINATOR)
+}
+
+/// `x * y = k` output with the fee taken from the input.
+pub fn constant_product_out(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    fee_percent: f64,
+) -> u64 {
+    if reserve_in == 0 || reserve_out == 0 || amount_in == 0 {
+        return 0;
+    }
+    let in_after_fee = amount_in as u128 * (FEE_DENOMINATOR - fee_parts(fee_percent));
+    let num = in_after_fee * reserve_out as u128;
+    let den = reserve_in as u128 * FEE_DENOMINATOR + in_after_fee;
+    (num / den) as u64
+}
+
+/// Largest output the fee-adjusted invariant
+/// `(r_in + amount_in * (1 - fee)) * (r_out - out) >= r_in * r_out` allows.
+///
+/// A reference for [`constant_product_out`] derived from the invariant rather
+/// than the closed form; the two agree exactly. `None` when the intermediate
+/// product overflows `u128`.
+pub fn invariant_max_out(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    fee_percent: f64,
+) -> Option<u64> {
+    if reserve_in == 0 || reserve_out == 0 || amount_in == 0 {
+        return Some(0);
+    }
+    let in_after_fee = amount_in as u128 * (FEE_DENOMINATOR - fee_parts(fee_percent));
+    let k_scaled = (reserve_in as u128 * FEE_DENOMINATOR).checked_mul(reserve_out as u128)?;
+    let new_in = reserve_in as u128 * FEE_DENOMINATOR + in_after_fee;
+    // The output reserve may not drop below k / new_in, rounded up.
+    let min_out_reserve = k_scaled.div_ceil(new_in);
+    Some((reserve_out as u128 - min_out_reserve) as u64)
+}
+
+fn direction_reserves(pool: &LiquidityPool, direction: Direction) -> (u64, u64) {
+    match direction {
+        Direction::AToB => (pool.reserve_a, pool.reserve_b),
+        Direction::BToA => (pool.reserve_b, pool.reserve_a),
+    }
+}
+
+/// Output of an AMM pool for `amount_in` of the `direction` input asset.
+pub fn amm_out(pool: &LiquidityPool, direction: Direction, amount_in: u64) -> u64 {
+    let (r_in, r_out) = direction_reserves(pool, direction);
+    let fee_percent = pool.fee_percent(direction);
+    let out = constant_product_out(r_in, r_out, amount_in, fee_percent);
+    #[cfg(feature = "invariant-checks")]
+    if let Some(reference) = invariant_max_out(r_in, r_out, amount_in, fee_percent) {
+        assert_eq!(
+            out,
+            reference,
+            "amm_out of {} {:?} for {} disagrees with the invariant",
+            pool.uuid(),
+            direction,
+            amount_in
+        );
+    }
+    out
+}
+
+/// Check that `pool` can pay `amount_out` for `amount_in` in `direction`
+/// without breaking its fee-adjusted invariant.
+///
+/// Use it to validate quotes computed elsewhere (a DEX's `estimated_receive`,
+/// a cached quote) before building a min-receive from them.
+pub fn check_amm_quote(
+    pool: &LiquidityPool,
+    direction: Direction,
+    amount_in: u64,
+    amount_out: u64,
+) -> Result<()> {
+    let (r_in, r_out) = direction_reserves(pool, direction);
+    if amount_out >= r_out && amount_out > 0 {
+        return Err(anyhow!(
+            "{} cannot pay {} out of a reserve of {}",
+            pool.uuid(),
+            amount_out,
+            r_out
+        ));
+    }
+    let max_out = invariant_max_out(r_in, r_out, amount_in, pool.fee_percent(direction))
+        .ok_or_else(|| anyhow!("{} reserves overflow the invariant check", pool.uuid()))?;
+    if amount_out > max_out {
+        return Err(anyhow!(
+            "{} {:?}: {} for {} breaks k = {} (at most {})",
+            pool.uuid(),
+            direction,
+            amount_out,
+            amount_in,
+            pool.constant_product(),
+            max_out
+        ));
+    }
+    Ok(())
+}
+
+/// Reserves of `pool` after swapping `amount_in` in `direction`.
+///
+/// The input reserve grows by `amount_in` less the part of the fee that leaves
+/// the pool (protocol/fee-sharing cut of [`FeeBreakdown`](crate::models::FeeBreakdown)),
+/// so only the LP-effective fee accrues.
+pub fn amm_reserves_after(
+    pool: &LiquidityPool,
+    direction: Direction,
+    amount_in: u64,
+) -> (u64, u64) {
+    let out = amm_out(pool, direction, amount_in);
+    // The breakdown splits `pool_fee_percent`; scale it to the direction's fee.
+    let fee_scale = if pool.pool_fee_percent > 0.0 {
+        pool.fee_percent(direction) / pool.pool_fee_percent
+    } else {
+        0.0
+    };
+    let paid_out = pool
+        .metadata.fee_breakdown
+        .as_ref()
+        .map(|b| amount_in as u128 * fee_parts(b.non_lp_percent() * fee_scale) / FEE_DENOMINATOR)
+        .unwrap_or(0) as u64;
+    let added = amount_in - paid_out.min(amount_in);
+    match direction {
+        Direction::AToB => (
+            pool.reserve_a.saturating_add(added),
+            pool.reserve_b.saturating_sub(out),
+        ),
+        Direction::BToA => (
+            pool.reserve_a.saturating_sub(out),
+            pool.reserve_b.saturating_add(added),
+        ),
+    }
+}
+
+/// StableSwap invariant D for two balances (Curve `get_D`, n = 2).
+pub fn stable_d(x: u128, y: u128, amp: u128) -> Option<u128> {
+    let s = x + y;
+    if s == 0 {
+        return Some(0);
+    }
+    if x == 0 || y == 0 {
+        return None;
+    }
+    let ann = amp * 2;
+    let mut d = s;
+    for _ in 0..MAX_ITERATIONS {
+        let mut d_p = d;
+        d_p = d_p * d / (x * 2);
+        d_p = d_p * d / (y * 2);
+        let prev = d;
+        let den = (ann.checked_sub(1)?) * d + 3 * d_p;
+        if den == 0 {
+            return None;
+        }
+        d = (ann * s + d_p * 2) * d / den;
+        if d.abs_diff(prev) <= 1 {
+            return Some(d);
+        }
+    }
+    None
+}
+
+/// Balance of the other asset that keeps D constant when one balance is `x` (Curve `get_y`, n = 2).
+pub fn stable_y(x: u128, d: u128, amp: u128) -> Option<u128> {
+    if x == 0 {
+        return None;
+    }
+    let ann = amp * 2;
+    let c = d * d / (x * 2) * d / (ann * 2);
+    let b = x + d / ann;
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let prev = y;
+        let den = (2 * y + b).checked_sub(d)?;
+        if den == 0 {
+            return None;
+        }
+        y = (y * y + c) / den;
+        if y.abs_diff(prev) <= 1 {
+            return Some(y);
+        }
+    }
+    None
+}
+
+fn token_decimals(token: &Token) -> u32 {
+    match token {
+        Token::Lovelace => 6,
+        Token::Asset(a) => a.decimals as u32,
+    }
+}
+
+/// Output of a stable pool, with balances normalised to the larger decimals
+/// and the fee taken from the output. Returns 0 if the invariant does not
+/// converge, and for pools of more than two assets (the n = 2 invariant does
+/// not hold for them).
+pub fn stable_out(pool: &StablePool, direction: Direction, amount_in: u64) -> u64 {
+    if amount_in == 0 || !pool.is_two_asset() {
+        return 0;
+    }
+    let dec_a = token_decimals(pool.asset_a());
+    let dec_b = token_decimals(pool.asset_b());
+    let target = dec_a.max(dec_b);
+    let mul_a = 10u128.pow(target - dec_a);
+    let mul_b = 10u128.pow(target - dec_b);
+
+    let (bal_in, bal_out, mul_in, mul_out) = match direction {
+        Direction::AToB => (pool.reserve_a(), pool.reserve_b(), mul_a, mul_b),
+        Direction::BToA => (pool.reserve_b(), pool.reserve_a(), mul_b, mul_a),
+    };
+    let x = bal_in as u128 * mul_in;
+    let y = bal_out as u128 * mul_out;
+    let amp = pool.amplification_coefficient as u128;
+
+    let quote = || -> Option<u64> {
+        let d = stable_d(x, y, amp)?;
+        let new_y = stable_y(x + amount_in as u128 * mul_in, d, amp)?;
+        let dy = y.checked_sub(new_y)?.checked_sub(1)?;
+        let fee = dy * fee_parts(pool.pool_fee_percent) / FEE_DENOMINATOR;
+        Some(((dy - fee) / mul_out) as u64)
+    };
+    quote().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+
+    fn stable(reserve_a: u64, reserve_b: u64) -> StablePool {
+        let assets = vec![
+            (
+                from_identifier(
+                    "aa00000000000000000000000000000000000000000000000000000055534443",
+                    6,
+                ),
+                reserve_a,
+            ),
+            (
+                from_identifier(
+                    "bb00000000000000000000000000000000000000000000000000000069555344",
+                    6,
+                ),
+                reserve_b,
+            ),
+        ];
+        StablePool::new(
+            "MinswapStable",
+            assets,
+            "addr",
+            "pool",
+            0.1,
+            10,
+            reserve_a + reserve_b,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_constant_product_out() {
+        // 1% of a 1000/1000 pool at 0.3%: 10 * 0.997 * 1000 / (1000 + 9.97)
+        assert_eq!(
+            constant_product_out(1_000_000, 1_000_000, 10_000, 0.3),
+            9_871
+        );
+        assert_eq!(constant_product_out(0, 1_000_000, 10_000, 0.3), 0);
+    }
+
+    #[test]
+    fn test_quotes_match_the_invariant() {
+        let pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            from_identifier(
+                "aa00000000000000000000000000000000000000000000000000000055534443",
+                0,
+            ),
+            1_234_567,
+            7_654_321,
+            "addr",
+            0.3,
+            "pool",
+        );
+        assert_eq!(pool.constant_product(), 1_234_567u128 * 7_654_321);
+        for amount_in in [0, 1, 999, 10_000, 1_000_000, 50_000_000] {
+            for direction in [Direction::AToB, Direction::BToA] {
+                let out = amm_out(&pool, direction, amount_in);
+                let (r_in, r_out) = direction_reserves(&pool, direction);
+                assert_eq!(invariant_max_out(r_in, r_out, amount_in, 0.3), Some(out));
+                assert!(check_amm_quote(&pool, direction, amount_in, out).is_ok());
+                assert!(check_amm_quote(&pool, direction, amount_in, out + 1).is_err());
+                assert!(pool.preserves_invariant(direction, amount_in, out));
+            }
+        }
+        assert!(!pool.preserves_invariant(Direction::AToB, 1, 7_654_321));
+    }
+
+    #[test]
+    fn test_reserves_after_keep_only_lp_fee() {
+        let mut pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            from_identifier(
+                "aa00000000000000000000000000000000000000000000000000000055534443",
+                0,
+            ),
+            1_000_000,
+            1_000_000,
+            "addr",
+            1.0,
+            "pool",
+        );
+        let out = amm_out(&pool, Direction::AToB, 10_000);
+        assert_eq!(
+            amm_reserves_after(&pool, Direction::AToB, 10_000),
+            (1_010_000, 1_000_000 - out)
+        );
+        // A sixth of the 100 lovelace fee (16, rounded down) is paid out.
+        pool.metadata.fee_breakdown = Some(crate::models::FeeBreakdown {
+            lp_fee_percent: 1.0 * 5.0 / 6.0,
+            protocol_fee_percent: 1.0 / 6.0,
+            ..Default::default()
+        });
+        assert_eq!(
+            amm_reserves_after(&pool, Direction::AToB, 10_000).0,
+            1_009_984
+        );
+
+        // A cheaper B -> A fee only applies in that direction.
+        pool.metadata.fee_b_to_a = Some(0.3);
+        assert_eq!(pool.fee_percent(Direction::AToB), 1.0);
+        assert!(amm_out(&pool, Direction::BToA, 10_000) > out);
+    }
+
+    #[test]
+    fn test_stable_out_near_peg() {
+        let pool = stable(1_000_000_000_000, 1_000_000_000_000);
+        let out = stable_out(&pool, Direction::AToB, 1_000_000_000);
+        // Balanced pool: ~1:1 less the 0.1% fee.
+        assert!(out > 998_000_000 && out < 999_000_000, "out {}", out);
+        let out_back = stable_out(&pool, Direction::BToA, 1_000_000_000);
+        assert_eq!(out, out_back);
+    }
+}
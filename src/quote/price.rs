@@ -0,0 +1,159 @@
+//! Human-readable price summary of a pool: mid prices in both directions,
+//! TVL and the price impact of a trade of a given size.
+
+use crate::models::{LiquidityPool, Token};
+use crate::quote::Direction;
+use serde::{Deserialize, Serialize};
+
+/// A trade of the quoted notional through one side of the pool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotionalQuote {
+    /// Input in base units.
+    pub amount_in: u64,
+    /// Output in base units, after the pool fee.
+    pub amount_out: u64,
+    /// Distance between the mid price and the execution price before fees, in percent.
+    pub price_impact_percent: f64,
+}
+
+/// Prices, TVL and notional trade quotes of one pool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolQuote {
+    /// Asset B per unit of asset A.
+    pub price_a_in_b: f64,
+    /// Asset A per unit of asset B.
+    pub price_b_in_a: f64,
+    /// Whether both prices are in whole units (decimals known for both assets:
+    /// ADA, on-chain metadata or [`DecimalsOverrides`](crate::models::DecimalsOverrides)
+    /// applied to the pool). Otherwise they are ratios of base units.
+    pub decimals_adjusted: bool,
+    /// Twice the ADA reserve; `None` for token/token pools.
+    pub tvl_lovelace: Option<u64>,
+    /// Notional in whole units of asset A.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notional: Option<f64>,
+    /// Selling `notional` of asset A.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub a_to_b: Option<NotionalQuote>,
+    /// Selling the mid-price equivalent of `notional` in asset B.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub b_to_a: Option<NotionalQuote>,
+}
+
+/// Decimals of `token`, if known.
+fn known_decimals(token: &Token) -> Option<u8> {
+    match token {
+        Token::Lovelace => Some(6),
+        Token::Asset(a) => (a.decimals > 0).then_some(a.decimals),
+    }
+}
+
+fn notional_quote(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    fee_percent: f64,
+) -> NotionalQuote {
+    let amount_out = super::constant_product_out(reserve_in, reserve_out, amount_in, fee_percent);
+    // x * y = k fills at reserve_out / (reserve_in + amount_in) against a mid
+    // price of reserve_out / reserve_in.
+    let price_impact_percent = if reserve_in == 0 {
+        0.0
+    } else {
+        amount_in as f64 / (reserve_in as f64 + amount_in as f64) * 100.0
+    };
+    NotionalQuote {
+        amount_in,
+        amount_out,
+        price_impact_percent,
+    }
+}
+
+/// Quote `pool`; with `notional`, also price a trade of that many whole units
+/// of asset A (and its equivalent in asset B) through each side.
+pub fn pool_quote(pool: &LiquidityPool, notional: Option<f64>) -> PoolQuote {
+    let decimals = known_decimals(&pool.asset_a).zip(known_decimals(&pool.asset_b));
+    let (dec_a, dec_b) = decimals.unwrap_or((0, 0));
+    let scale_a = 10_f64.powi(dec_a as i32);
+    let scale_b = 10_f64.powi(dec_b as i32);
+    let units_a = pool.reserve_a as f64 / scale_a;
+    let units_b = pool.reserve_b as f64 / scale_b;
+    let ratio = |num: f64, den: f64| if den == 0.0 { 0.0 } else { num / den };
+
+    let tvl_lovelace = if pool.asset_a.is_lovelace() {
+        Some(pool.reserve_a.saturating_mul(2))
+    } else if pool.asset_b.is_lovelace() {
+        Some(pool.reserve_b.saturating_mul(2))
+    } else {
+        None
+    };
+
+    let notional = notional.filter(|n| *n > 0.0);
+    let (a_to_b, b_to_a) = match notional {
+        Some(n) if pool.reserve_a > 0 && pool.reserve_b > 0 => {
+            let in_a = (n * scale_a).round() as u64;
+            let in_b = (in_a as f64 * pool.reserve_b as f64 / pool.reserve_a as f64).round() as u64;
+            (
+                Some(notional_quote(
+                    pool.reserve_a,
+                    pool.reserve_b,
+                    in_a,
+                    pool.fee_percent(Direction::AToB),
+                )),
+                Some(notional_quote(
+                    pool.reserve_b,
+                    pool.reserve_a,
+                    in_b,
+                    pool.fee_percent(Direction::BToA),
+                )),
+            )
+        }
+        _ => (None, None),
+    };
+
+    PoolQuote {
+        price_a_in_b: ratio(units_b, units_a),
+        price_b_in_a: ratio(units_a, units_b),
+        decimals_adjusted: decimals.is_some(),
+        tvl_lovelace,
+        notional,
+        a_to_b,
+        b_to_a,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+
+    #[test]
+    fn test_pool_quote() {
+        let token = "f13ac4d66b3ee19a6aa0f2a22298737bd907cc95121662fc971b5275535452494b45";
+        let pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            from_identifier(token, 6),
+            1_000_000_000_000,
+            4_000_000_000_000,
+            "addr",
+            0.3,
+            "p",
+        );
+
+        let quote = pool_quote(&pool, Some(10_000.0));
+        assert!(quote.decimals_adjusted);
+        assert_eq!(quote.price_a_in_b, 4.0);
+        assert_eq!(quote.price_b_in_a, 0.25);
+        assert_eq!(quote.tvl_lovelace, Some(2_000_000_000_000));
+
+        // 10k ADA into a 1M ADA reserve moves the price by 10k / 1.01M.
+        let a_to_b = quote.a_to_b.unwrap();
+        assert_eq!(a_to_b.amount_in, 10_000_000_000);
+        assert!((a_to_b.price_impact_percent - 0.990099).abs() < 1e-5);
+        assert!(a_to_b.amount_out < 40_000_000_000);
+        assert_eq!(quote.b_to_a.unwrap().amount_in, 40_000_000_000);
+
+        assert!(pool_quote(&pool, None).a_to_b.is_none());
+    }
+}
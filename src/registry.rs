@@ -0,0 +1,226 @@
+//! Ticker → asset resolution.
+//!
+//! [`TokenRegistry`] maps human tickers (`ADA`, `SNEK`, `MIN`) to [`AssetId`]s
+//! so callers do not have to paste 60+ character hex identifiers. Tickers come
+//! from two tiers:
+//! - explicit entries: a registry file and the token-list allowlist;
+//! - discovered entries: asset names seen in pool snapshots.
+//!
+//! Explicit entries shadow discovered ones, so an allowlisted SNEK wins over
+//! scam tokens reusing the name. A ticker with several candidates in the
+//! winning tier is an error listing them.
+//!
+//! CIP-14 fingerprints (`asset1...`, as explorers show them) resolve against
+//! every known asset; [`find_fingerprint`] asks Kupo for the assets of a
+//! policy when the registry does not know the token.
+
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::kupo::KupoApi;
+use crate::models::{AssetId, LiquidityPool, Token};
+use crate::verification::TokenLists;
+
+#[derive(Debug, Clone)]
+pub struct TokenRegistry {
+    explicit: HashMap<String, BTreeSet<AssetId>>,
+    discovered: HashMap<String, BTreeSet<AssetId>>,
+}
+
+impl Default for TokenRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn ticker_key(ticker: &str) -> String {
+    ticker.trim().to_uppercase()
+}
+
+/// Ticker carried by a token's asset name, when it is printable text.
+fn token_ticker(token: &Token) -> Option<String> {
+    let name = token.as_asset()?.asset_name();
+    let name = name.trim();
+    (!name.is_empty() && name.chars().all(|c| c.is_ascii_graphic())).then(|| ticker_key(name))
+}
+
+impl TokenRegistry {
+    /// Registry knowing only `ADA`.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            explicit: HashMap::new(),
+            discovered: HashMap::new(),
+        };
+        registry.insert("ADA", AssetId::lovelace());
+        registry
+    }
+
+    /// Add an explicit `ticker` → `id` entry.
+    pub fn insert(&mut self, ticker: &str, id: impl Into<AssetId>) {
+        self.explicit
+            .entry(ticker_key(ticker))
+            .or_default()
+            .insert(id.into());
+    }
+
+    /// Load explicit entries from a JSON file of `{ "<ticker>": "<identifier>" }`.
+    pub fn load_file(&mut self, path: &str) -> Result<usize> {
+        let entries: HashMap<String, String> = crate::cache::load_from_file(path)
+            .map_err(|e| anyhow!("Failed to load token registry: {}", e))?;
+        for (ticker, id) in &entries {
+            self.insert(ticker, AssetId::parse(id)?);
+        }
+        Ok(entries.len())
+    }
+
+    /// Register allowlisted tokens under their asset names.
+    pub fn with_token_lists(mut self, lists: &TokenLists) -> Self {
+        for id in &lists.allowlist {
+            let id = AssetId::from(id);
+            if let Some(ticker) = token_ticker(&id.to_token(0)) {
+                self.insert(&ticker, id);
+            }
+        }
+        self
+    }
+
+    /// Register the asset names of `pools` as discovered entries.
+    pub fn add_pools(&mut self, pools: &[LiquidityPool]) {
+        for token in pools.iter().flat_map(|p| [&p.asset_a, &p.asset_b]) {
+            if let Some(ticker) = token_ticker(token) {
+                self.discovered
+                    .entry(ticker)
+                    .or_default()
+                    .insert(AssetId::from(token));
+            }
+        }
+    }
+
+    /// Candidates for `ticker` from the tier that answers it.
+    pub fn candidates(&self, ticker: &str) -> Vec<AssetId> {
+        let key = ticker_key(ticker);
+        self.explicit
+            .get(&key)
+            .or_else(|| self.discovered.get(&key))
+            .map(|ids| ids.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Known asset whose CIP-14 fingerprint is `fingerprint`, from either tier.
+    pub fn resolve_fingerprint(&self, fingerprint: &str) -> Option<AssetId> {
+        let fingerprint = fingerprint.trim().to_lowercase();
+        self.explicit
+            .values()
+            .chain(self.discovered.values())
+            .flatten()
+            .find(|id| id.fingerprint().as_deref() == Some(fingerprint.as_str()))
+            .cloned()
+    }
+
+    /// Resolve a ticker, fingerprint or identifier. Identifiers (`lovelace`,
+    /// hex units in either form) pass through unchanged.
+    pub fn resolve(&self, input: &str) -> Result<AssetId> {
+        if let Ok(id) = AssetId::parse(input) {
+            return Ok(id);
+        }
+        if AssetId::is_fingerprint(input) {
+            return self.resolve_fingerprint(input).ok_or_else(|| {
+                anyhow!(
+                    "unknown fingerprint '{}': pass the policy id+name, or look it up with `fingerprint {} <policy_id>`",
+                    input,
+                    input.trim()
+                )
+            });
+        }
+        let mut candidates = self.candidates(input);
+        match candidates.len() {
+            0 => Err(anyhow!(
+                "unknown token '{}': not an identifier or known ticker",
+                input
+            )),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(anyhow!(
+                "ambiguous ticker '{}': candidates {}",
+                input,
+                candidates
+                    .iter()
+                    .map(AssetId::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/// Asset of `policy_id` whose fingerprint is `fingerprint`, among the assets
+/// Kupo has seen under the policy (spent outputs included, so burnt tokens
+/// resolve too).
+pub async fn find_fingerprint(
+    kupo: &KupoApi,
+    fingerprint: &str,
+    policy_id: &str,
+) -> Result<AssetId> {
+    let fingerprint = fingerprint.trim().to_lowercase();
+    let policy_id = policy_id.trim().to_lowercase();
+    let utxos = kupo.get(&format!("{}.*", policy_id), false).await?;
+    utxos
+        .iter()
+        .flat_map(|utxo| &utxo.amount)
+        .filter(|unit| unit.unit.starts_with(&policy_id))
+        .map(|unit| AssetId::from(unit.unit.as_str()))
+        .find(|id| id.fingerprint().as_deref() == Some(fingerprint.as_str()))
+        .ok_or_else(|| {
+            anyhow!(
+                "no asset of policy {} has fingerprint {}",
+                policy_id,
+                fingerprint
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+    const FAKE_SNEK: &str = "00000000000000000000000000000000000000000000000000000000534e454b";
+
+    #[test]
+    fn test_resolve_prefers_explicit_and_reports_ambiguity() {
+        let pool = |id: &str| {
+            LiquidityPool::new(
+                "MinswapV2",
+                Token::Lovelace,
+                from_identifier(id, 0),
+                1,
+                1,
+                "addr",
+                0.3,
+                id,
+            )
+        };
+        let mut registry = TokenRegistry::new();
+        registry.add_pools(&[pool(SNEK), pool(FAKE_SNEK)]);
+
+        assert!(registry.resolve("ada").unwrap().is_lovelace());
+        assert_eq!(registry.resolve(SNEK).unwrap(), AssetId::from(SNEK));
+        let err = registry.resolve("snek").unwrap_err().to_string();
+        assert!(err.contains("ambiguous") && err.contains(FAKE_SNEK));
+
+        let registry = registry.with_token_lists(&TokenLists {
+            allowlist: vec![SNEK.to_string()],
+            denylist: vec![],
+        });
+        assert_eq!(registry.resolve("SNEK").unwrap(), AssetId::from(SNEK));
+        assert!(registry.resolve("NOPE").is_err());
+
+        let fingerprint = AssetId::from(FAKE_SNEK).fingerprint().unwrap();
+        assert_eq!(
+            registry.resolve(&fingerprint).unwrap(),
+            AssetId::from(FAKE_SNEK)
+        );
+        let unknown = AssetId::from(format!("{}00", SNEK)).fingerprint().unwrap();
+        assert!(registry.resolve(&unknown).is_err());
+    }
+}
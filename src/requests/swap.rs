@@ -89,6 +89,26 @@ impl<'a, D: DexSwap + ?Sized> SwapRequest<'a, D> {
         Ok(self.dex.estimated_receive(pool, in_token, self.swap_in_amount))
     }
 
+    /// Like [`get_estimated_receive`](Self::get_estimated_receive), without floats.
+    pub fn get_estimated_receive_exact(&self) -> Result<u64> {
+        let pool = self.pool.as_ref().ok_or_else(|| anyhow!("pool not set"))?;
+        let in_token = self.swap_in_token.as_ref().ok_or_else(|| anyhow!("swap_in_token not set"))?;
+        Ok(self.dex.estimated_receive_exact(pool, in_token, self.swap_in_amount))
+    }
+
+    /// Like [`get_minimum_receive`](Self::get_minimum_receive), from the exact
+    /// estimate and rounded down in integers, so large amounts never round up
+    /// past what the validator pays.
+    pub fn get_minimum_receive_exact(&self) -> Result<u64> {
+        match self.pricing {
+            PricingMode::MinReceive(v) => Ok(v),
+            PricingMode::Slippage(s) => Ok(crate::quote::exact::min_receive_exact(self.get_estimated_receive_exact()?, s)),
+            PricingMode::Unset => Err(anyhow!(
+                "must call with_slippage_percent (market) or with_minimum_receive (limit)"
+            )),
+        }
+    }
+
     pub fn get_minimum_receive(&self) -> Result<u64> {
         match self.pricing {
             PricingMode::MinReceive(v) => Ok(v),
@@ -133,6 +133,7 @@ mod tests {
                 output_index: 0,
                 amount: vec![],
                 block: String::new(),
+                slot: None,
                 data_hash: None,
                 inline_datum: None,
                 reference_script_hash: None,
@@ -0,0 +1,336 @@
+//! Triangular arbitrage over the token graph: ADA → X → Y → ADA.
+//!
+//! Every cycle of three pools leaving and returning to ADA is quoted through
+//! the pools' own swap math, less the batcher fee of each hop (see
+//! [`CostModel`]). The input is sized for the largest profit, but no hop may
+//! take more than `max_depth_percent` of its pool's input reserve: beyond that
+//! the quote is too sensitive to the pool moving before the orders settle.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::{CostModel, RoutePool};
+use crate::models::{token_identifier, Token};
+use crate::quote::Direction;
+
+/// Largest share of a pool's input reserve one hop may take, by default.
+pub const DEFAULT_MAX_DEPTH_PERCENT: f64 = 5.0;
+
+/// One swap of an arbitrage cycle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArbHop {
+    pub dex_identifier: String,
+    pub pool_id: String,
+    pub token_in: String,
+    pub token_out: String,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+/// A profitable ADA → X → Y → ADA cycle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ArbOpportunity {
+    pub hops: Vec<ArbHop>,
+    /// Lovelace put into the first hop.
+    pub amount_in: u64,
+    /// Lovelace out of the last hop.
+    pub amount_out: u64,
+    /// Batcher fees of the three orders (deposits come back and are left out).
+    pub fee_lovelace: u64,
+    /// `amount_out - amount_in - fee_lovelace`.
+    pub profit_lovelace: u64,
+}
+
+impl ArbOpportunity {
+    /// Whether `dex`'s pool `pool_id` is one of the hops.
+    pub fn uses_pool(&self, dex: &str, pool_id: &str) -> bool {
+        self.hops
+            .iter()
+            .any(|h| h.dex_identifier == dex && h.pool_id == pool_id)
+    }
+}
+
+/// Finds triangular cycles among a set of pools.
+#[derive(Debug, Clone)]
+pub struct ArbScanner {
+    costs: CostModel,
+    max_depth_percent: f64,
+    min_profit_lovelace: u64,
+}
+
+impl Default for ArbScanner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Leg<'a> {
+    pool: &'a RoutePool,
+    direction: Direction,
+    token_in: String,
+    token_out: String,
+}
+
+impl Leg<'_> {
+    fn reserve_in(&self) -> u64 {
+        let (a, b) = match self.pool {
+            RoutePool::Amm(p) => (p.reserve_a, p.reserve_b),
+            RoutePool::Stable(p) => (p.reserve_a(), p.reserve_b()),
+        };
+        match self.direction {
+            Direction::AToB => a,
+            Direction::BToA => b,
+        }
+    }
+}
+
+impl ArbScanner {
+    /// Scanner with the published batcher fees, a 5% depth bound and any
+    /// positive profit.
+    pub fn new() -> Self {
+        Self {
+            costs: CostModel::defaults(),
+            max_depth_percent: DEFAULT_MAX_DEPTH_PERCENT,
+            min_profit_lovelace: 1,
+        }
+    }
+
+    pub fn with_cost_model(mut self, costs: CostModel) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Largest share, in percent, of a pool's input reserve one hop may take.
+    pub fn with_max_depth_percent(mut self, percent: f64) -> Self {
+        self.max_depth_percent = percent.clamp(0.0, 100.0);
+        self
+    }
+
+    /// Report only cycles returning at least `lovelace` after fees.
+    pub fn with_min_profit(mut self, lovelace: u64) -> Self {
+        self.min_profit_lovelace = lovelace.max(1);
+        self
+    }
+
+    /// Every profitable cycle among `pools`, most profitable first.
+    pub fn triangular(&self, pools: &[RoutePool]) -> Vec<ArbOpportunity> {
+        self.scan(pools, None)
+    }
+
+    /// [`triangular`](Self::triangular) limited to cycles through `dex`'s
+    /// pool `pool_id`, e.g. the one that just changed.
+    pub fn triangular_through(
+        &self,
+        pools: &[RoutePool],
+        dex: &str,
+        pool_id: &str,
+    ) -> Vec<ArbOpportunity> {
+        self.scan(pools, Some((dex, pool_id)))
+    }
+
+    fn scan(&self, pools: &[RoutePool], through: Option<(&str, &str)>) -> Vec<ArbOpportunity> {
+        let ada = token_identifier(&Token::Lovelace);
+        let mut by_token: HashMap<String, Vec<Leg>> = HashMap::new();
+        for pool in pools {
+            let (a, b) = pool.assets();
+            let (a, b) = (token_identifier(a), token_identifier(b));
+            if a == b {
+                continue;
+            }
+            for (direction, token_in, token_out) in
+                [(Direction::AToB, &a, &b), (Direction::BToA, &b, &a)]
+            {
+                by_token.entry(token_in.clone()).or_default().push(Leg {
+                    pool,
+                    direction,
+                    token_in: token_in.clone(),
+                    token_out: token_out.clone(),
+                });
+            }
+        }
+
+        let mut found = Vec::new();
+        let legs_from = |token: &str| by_token.get(token).map(Vec::as_slice).unwrap_or(&[]);
+        for first in legs_from(&ada) {
+            for second in legs_from(&first.token_out) {
+                if second.token_out == ada {
+                    continue;
+                }
+                for third in legs_from(&second.token_out) {
+                    let legs = [first, second, third];
+                    let touches = |(dex, pool_id): (&str, &str)| {
+                        legs.iter().any(|leg| {
+                            leg.pool.dex_identifier() == dex && leg.pool.pool_id() == pool_id
+                        })
+                    };
+                    if third.token_out == ada && through.is_none_or(touches) {
+                        found.extend(self.best_size(&legs));
+                    }
+                }
+            }
+        }
+        found.sort_by_key(|arb| std::cmp::Reverse(arb.profit_lovelace));
+        found
+    }
+
+    /// Outputs of each hop for `amount_in`, or `None` when a hop exceeds the
+    /// depth bound.
+    fn run(&self, legs: &[&Leg; 3], amount_in: u64) -> Option<[u64; 3]> {
+        let mut outs = [0; 3];
+        let mut amount = amount_in;
+        for (i, leg) in legs.iter().enumerate() {
+            if amount as f64 > leg.reserve_in() as f64 * self.max_depth_percent / 100.0 {
+                return None;
+            }
+            amount = leg.pool.quote(leg.direction, amount);
+            outs[i] = amount;
+        }
+        Some(outs)
+    }
+
+    fn best_size(&self, legs: &[&Leg; 3]) -> Option<ArbOpportunity> {
+        let fee_lovelace: u64 = legs
+            .iter()
+            .map(|leg| self.costs.hop_cost(leg.pool).fee_lovelace)
+            .sum();
+        let gross = |amount_in: u64| {
+            self.run(legs, amount_in)
+                .map(|outs| outs[2] as i128 - amount_in as i128)
+        };
+
+        // Largest input within the depth bound: hop inputs grow with it.
+        let (mut lo, mut hi) = (0u64, legs[0].reserve_in());
+        while lo < hi {
+            let mid = lo + (hi - lo).div_ceil(2);
+            if self.run(legs, mid).is_some() {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        // Output is concave in the input, so its surplus has a single peak.
+        let (mut left, mut right) = (1u64, lo);
+        while right.saturating_sub(left) > 2 {
+            let m1 = left + (right - left) / 3;
+            let m2 = right - (right - left) / 3;
+            if gross(m1)? < gross(m2)? {
+                left = m1 + 1;
+            } else {
+                right = m2 - 1;
+            }
+        }
+        let amount_in = (left..=right).max_by_key(|a| gross(*a).unwrap_or(i128::MIN))?;
+        let profit = gross(amount_in)? - fee_lovelace as i128;
+        if profit < self.min_profit_lovelace as i128 {
+            return None;
+        }
+
+        let outs = self.run(legs, amount_in)?;
+        let hops = legs
+            .iter()
+            .zip(outs)
+            .scan(amount_in, |input, (leg, out)| {
+                let hop = ArbHop {
+                    dex_identifier: leg.pool.dex_identifier().to_string(),
+                    pool_id: leg.pool.pool_id().to_string(),
+                    token_in: leg.token_in.clone(),
+                    token_out: leg.token_out.clone(),
+                    amount_in: *input,
+                    amount_out: out,
+                };
+                *input = out;
+                Some(hop)
+            })
+            .collect();
+        Some(ArbOpportunity {
+            hops,
+            amount_in,
+            amount_out: outs[2],
+            fee_lovelace,
+            profit_lovelace: profit as u64,
+        })
+    }
+}
+
+/// [`ArbScanner::triangular`] with the default scanner.
+pub fn triangular_arbitrage(pools: &[RoutePool]) -> Vec<ArbOpportunity> {
+    ArbScanner::new().triangular(pools)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+    use crate::models::LiquidityPool;
+
+    const X: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+    const Y: &str =
+        "8db269c3ec630e06ae29f74bc39edd1f87c819f1056206e879a1cd61446a65644d6963726f555344";
+
+    fn pool(id: &str, a: Token, b: Token, reserve_a: u64, reserve_b: u64) -> RoutePool {
+        RoutePool::from(LiquidityPool::new(
+            "MinswapV2",
+            a,
+            b,
+            reserve_a,
+            reserve_b,
+            "addr",
+            0.3,
+            id,
+        ))
+    }
+
+    #[test]
+    fn test_finds_mispriced_triangle() {
+        let (x, y) = (from_identifier(X, 0), from_identifier(Y, 0));
+        // 1 X = 1 ADA, 1 Y = 1 ADA, but the X/Y pool sells Y at 0.9 X.
+        let pools = vec![
+            pool(
+                "ada-x",
+                Token::Lovelace,
+                x.clone(),
+                100_000_000_000,
+                100_000_000_000,
+            ),
+            pool("x-y", x, y.clone(), 100_000_000_000, 111_111_111_111),
+            pool(
+                "ada-y",
+                Token::Lovelace,
+                y,
+                100_000_000_000,
+                100_000_000_000,
+            ),
+        ];
+
+        let found = ArbScanner::new().triangular(&pools);
+        assert_eq!(found.len(), 1);
+        let arb = &found[0];
+        let ids: Vec<&str> = arb.hops.iter().map(|h| h.pool_id.as_str()).collect();
+        assert_eq!(ids, ["ada-x", "x-y", "ada-y"]);
+        assert_eq!(arb.amount_out, arb.hops[2].amount_out);
+        assert_eq!(
+            arb.fee_lovelace,
+            3 * crate::dex::minswap_v2_swap::BATCHER_FEE_LOVELACE
+        );
+        assert_eq!(
+            arb.profit_lovelace,
+            arb.amount_out - arb.amount_in - arb.fee_lovelace
+        );
+        assert!(arb.uses_pool("MinswapV2", "x-y"));
+        // Bounded by depth: no hop takes more than 5% of its input reserve.
+        assert!(arb.hops.iter().all(|h| h.amount_in <= 5_555_555_555));
+
+        // The profit does not cover a fee of 1,000 ADA per order.
+        let fee = crate::requests::SwapFee {
+            id: "batcherFee".into(),
+            title: "Batcher Fee".into(),
+            description: String::new(),
+            value: 1_000_000_000,
+            is_returned: false,
+        };
+        let costly = ArbScanner::new()
+            .with_cost_model(CostModel::zero().with_fees("MinswapV2", vec![fee]))
+            .triangular(&pools);
+        assert!(costly.is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+//! How likely a quoted route still holds when its order executes.
+//!
+//! A quote is computed from pool UTXOs that are already some slots old, and a
+//! batcher settles the order some slots later. Over that horizon the pool
+//! price drifts like a random walk whose volatility is estimated from the
+//! pool's [`ReserveSnapshot`] history (or a default for pools without one).
+//! [`StalenessModel`] turns that into a [`RouteConfidence`]: a band the output
+//! should fall in with ~95% probability, and the probability that the output
+//! is still within a tolerance of the quote.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::{Allocation, RoutePool};
+use crate::store::history::ReserveSnapshot;
+
+/// Default volatility per square root of a slot (one second): about 5% a day,
+/// `0.05 / sqrt(86_400)`.
+pub const DEFAULT_VOLATILITY_PER_SLOT: f64 = 0.000_170_1;
+/// Slots between submitting an order and a batcher settling it by default.
+pub const DEFAULT_EXECUTION_DELAY_SLOTS: u64 = 40;
+/// Output shortfall tolerated by default, in percent (a common slippage).
+pub const DEFAULT_TOLERANCE_PERCENT: f64 = 0.5;
+/// Two-sided 95% quantile of the standard normal distribution.
+const Z_95: f64 = 1.959_964;
+
+/// Staleness-adjusted confidence of a route's output.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteConfidence {
+    /// Output at the low end of the ~95% band.
+    pub low_amount_out: u64,
+    /// Output at the high end of the ~95% band.
+    pub high_amount_out: u64,
+    /// Probability that the executed output is at least the quote less
+    /// `tolerance_percent`.
+    pub holds_probability: f64,
+    pub tolerance_percent: f64,
+    /// Longest pool age plus execution delay over the route's pools, in slots.
+    pub horizon_slots: u64,
+}
+
+/// Pool ages and volatilities to judge quotes by.
+#[derive(Debug, Clone)]
+pub struct StalenessModel {
+    tip_slot: u64,
+    execution_delay_slots: u64,
+    tolerance_percent: f64,
+    default_volatility: f64,
+    /// Log-price volatility per square-root slot, by `(dex, pool_id)`.
+    volatility: HashMap<(String, String), f64>,
+}
+
+impl StalenessModel {
+    /// Model for quotes made at `tip_slot` (e.g. `KupoApi::tip_slot`).
+    pub fn new(tip_slot: u64) -> Self {
+        Self {
+            tip_slot,
+            execution_delay_slots: DEFAULT_EXECUTION_DELAY_SLOTS,
+            tolerance_percent: DEFAULT_TOLERANCE_PERCENT,
+            default_volatility: DEFAULT_VOLATILITY_PER_SLOT,
+            volatility: HashMap::new(),
+        }
+    }
+
+    /// Slots until the order is expected to settle.
+    pub fn with_execution_delay(mut self, slots: u64) -> Self {
+        self.execution_delay_slots = slots;
+        self
+    }
+
+    /// Output shortfall, in percent, still counted as the quote holding.
+    pub fn with_tolerance_percent(mut self, percent: f64) -> Self {
+        self.tolerance_percent = percent.max(0.0);
+        self
+    }
+
+    /// Volatility per square-root slot of pools without history.
+    pub fn with_default_volatility(mut self, per_slot: f64) -> Self {
+        self.default_volatility = per_slot.max(0.0);
+        self
+    }
+
+    /// Estimate each pool's volatility from its snapshots (any order, several
+    /// pools mixed). Pools with fewer than two usable snapshots keep the default.
+    pub fn with_history(mut self, history: &[ReserveSnapshot]) -> Self {
+        let mut by_pool: HashMap<(String, String), Vec<&ReserveSnapshot>> = HashMap::new();
+        for snapshot in history {
+            by_pool
+                .entry((snapshot.dex.clone(), snapshot.pool_id.clone()))
+                .or_default()
+                .push(snapshot);
+        }
+        for (key, snapshots) in by_pool {
+            if let Some(volatility) = volatility_per_slot(snapshots) {
+                self.volatility.insert(key, volatility);
+            }
+        }
+        self
+    }
+
+    /// Volatility used for `dex`'s pool `pool_id`.
+    pub fn volatility(&self, dex: &str, pool_id: &str) -> f64 {
+        self.volatility
+            .get(&(dex.to_string(), pool_id.to_string()))
+            .copied()
+            .unwrap_or(self.default_volatility)
+    }
+
+    /// Slots between `pool`'s UTXO and settlement; pools without a slot count
+    /// as read at the tip.
+    pub fn horizon_slots(&self, pool: &RoutePool) -> u64 {
+        let age = pool
+            .slot()
+            .map(|slot| self.tip_slot.saturating_sub(slot))
+            .unwrap_or(0);
+        age + self.execution_delay_slots
+    }
+
+    /// Confidence of the output `amount_out` split over `hops`.
+    ///
+    /// The pools of one pair move together, so the route's spread is the
+    /// output-weighted sum of its hops' spreads.
+    pub fn confidence(
+        &self,
+        hops: &[(&RoutePool, Allocation)],
+        amount_out: u64,
+    ) -> RouteConfidence {
+        let mut spread = 0.0;
+        let mut horizon = 0;
+        for (pool, allocation) in hops {
+            let slots = self.horizon_slots(pool);
+            horizon = horizon.max(slots);
+            let weight = if amount_out == 0 {
+                0.0
+            } else {
+                allocation.amount_out as f64 / amount_out as f64
+            };
+            let volatility = self.volatility(pool.dex_identifier(), pool.pool_id());
+            spread += weight * volatility * (slots as f64).sqrt();
+        }
+        let out = amount_out as f64;
+        let shortfall = -(1.0 - self.tolerance_percent.min(100.0) / 100.0).ln();
+        let holds_probability = if spread == 0.0 {
+            1.0
+        } else {
+            normal_cdf(shortfall / spread)
+        };
+        RouteConfidence {
+            low_amount_out: (out * (-Z_95 * spread).exp()).floor() as u64,
+            high_amount_out: (out * (Z_95 * spread).exp()).ceil() as u64,
+            holds_probability,
+            tolerance_percent: self.tolerance_percent,
+            horizon_slots: horizon,
+        }
+    }
+}
+
+/// Log-price volatility per square-root slot of one pool's snapshots: the
+/// root of the summed squared log returns over the seconds (slots) they span,
+/// timed by `observed_at`.
+pub fn volatility_per_slot<'a>(
+    snapshots: impl IntoIterator<Item = &'a ReserveSnapshot>,
+) -> Option<f64> {
+    let mut points: Vec<(u64, f64)> = snapshots
+        .into_iter()
+        .filter(|s| s.reserve_a > 0 && s.reserve_b > 0)
+        .map(|s| {
+            (
+                s.observed_at,
+                (s.reserve_a as f64 / s.reserve_b as f64).ln(),
+            )
+        })
+        .collect();
+    points.sort_by_key(|(at, _)| *at);
+    points.dedup_by_key(|(at, _)| *at);
+    let span = points.last()?.0.checked_sub(points.first()?.0)?;
+    if points.len() < 2 || span == 0 {
+        return None;
+    }
+    let squared: f64 = points.windows(2).map(|w| (w[1].1 - w[0].1).powi(2)).sum();
+    Some((squared / span as f64).sqrt())
+}
+
+/// Standard normal CDF (Abramowitz and Stegun 7.1.26, error below 1.5e-7).
+fn normal_cdf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs() / std::f64::consts::SQRT_2);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-(x * x) / 2.0).exp();
+    if x >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{LiquidityPool, PoolFreshness, Token};
+    use crate::quote::Direction;
+
+    fn snapshot(observed_at: u64, reserve_a: u64) -> ReserveSnapshot {
+        ReserveSnapshot {
+            dex: "MinswapV2".to_string(),
+            pool_id: "calm".to_string(),
+            observed_at,
+            slot: None,
+            reserve_a,
+            reserve_b: 1_000_000,
+            total_lp_tokens: 0,
+        }
+    }
+
+    #[test]
+    fn test_confidence_widens_with_age_and_volatility() {
+        // Price moves 1% up and back over 200 seconds.
+        let history = [
+            snapshot(0, 1_000_000),
+            snapshot(100, 1_010_000),
+            snapshot(200, 1_000_000),
+        ];
+        let vol = volatility_per_slot(&history).unwrap();
+        assert!((vol - (2.0 * 0.00995f64.powi(2) / 200.0).sqrt()).abs() < 1e-5);
+
+        let mut pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            Token::Lovelace,
+            1_000_000,
+            1_000_000,
+            "addr",
+            0.3,
+            "calm",
+        );
+        pool.metadata.freshness = Some(PoolFreshness {
+            slot: Some(1_000),
+            ..Default::default()
+        });
+        let pool = RoutePool::from(pool);
+        let allocation = Allocation {
+            dex_identifier: "MinswapV2".to_string(),
+            pool_id: "calm".to_string(),
+            direction: Direction::AToB,
+            amount_in: 10,
+            amount_out: 1_000_000,
+        };
+        let hops = [(&pool, allocation)];
+
+        let model = StalenessModel::new(1_060).with_history(&history);
+        let fresh = model.confidence(&hops, 1_000_000);
+        assert_eq!(fresh.horizon_slots, 60 + DEFAULT_EXECUTION_DELAY_SLOTS);
+        assert!(fresh.low_amount_out < 1_000_000 && fresh.high_amount_out > 1_000_000);
+
+        let stale = StalenessModel::new(100_000)
+            .with_history(&history)
+            .confidence(&hops, 1_000_000);
+        assert!(stale.low_amount_out < fresh.low_amount_out);
+        assert!(stale.holds_probability < fresh.holds_probability);
+
+        let still = StalenessModel::new(1_060)
+            .with_default_volatility(0.0)
+            .confidence(&hops, 1_000_000);
+        assert_eq!(
+            (still.low_amount_out, still.holds_probability),
+            (1_000_000, 1.0)
+        );
+        assert!((normal_cdf(1.959_964) - 0.975).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,143 @@
+//! Per-DEX order costs (batcher/agent fees and deposits) for route selection.
+//!
+//! Every swap through a batcher DEX pays a fixed fee to the batcher and locks a
+//! min-UTxO deposit in the order output that comes back with the swap result.
+//! A split route pays these once per hop, so they decide whether splitting is
+//! worth it for small orders.
+
+use super::RoutePool;
+use crate::dex::minswap_v2_swap::{BATCHER_FEE_LOVELACE, DEPOSIT_LOVELACE};
+use crate::dex::DexSwap;
+use crate::requests::SwapFee;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Defaults per DEX identifier: (batcher fee, deposit) in lovelace.
+const DEFAULT_FEES: &[(&str, u64, u64)] = &[
+    ("MinswapV1", 2_000_000, 2_000_000),
+    ("MinswapV2", BATCHER_FEE_LOVELACE, DEPOSIT_LOVELACE),
+    ("MinswapStable", 2_000_000, 2_000_000),
+    ("SundaeSwapV1", 2_500_000, 2_000_000),
+    ("SundaeSwapV3", 1_000_000, 2_000_000),
+    ("WingRiders", 2_000_000, 2_000_000),
+    ("WingRidersV2", 2_000_000, 2_000_000),
+    ("VyFinance", 1_900_000, 2_000_000),
+];
+
+fn batcher_fee(value: u64) -> SwapFee {
+    SwapFee {
+        id: "batcherFee".into(),
+        title: "Batcher Fee".into(),
+        description: "Paid to the off-chain batcher to process the order.".into(),
+        value,
+        is_returned: false,
+    }
+}
+
+fn deposit(value: u64) -> SwapFee {
+    SwapFee {
+        id: "deposit".into(),
+        title: "Deposit".into(),
+        description: "Held as min-UTxO ADA; returned when the order is processed or cancelled."
+            .into(),
+        value,
+        is_returned: true,
+    }
+}
+
+/// Costs of one hop (one order) of a route, in lovelace.
+#[derive(Debug, Clone, Serialize)]
+pub struct HopCost {
+    pub dex_identifier: String,
+    pub pool_id: String,
+    /// Fees paid and not returned (batcher / agent fees).
+    pub fee_lovelace: u64,
+    /// Deposits locked in the order and returned with the swap output.
+    pub deposit_lovelace: u64,
+    pub fees: Vec<SwapFee>,
+}
+
+/// Costs of a whole route.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RouteCosts {
+    pub hops: Vec<HopCost>,
+    pub fee_lovelace: u64,
+    /// ADA the sender must lock on top of the input; comes back with the outputs.
+    pub deposit_lovelace: u64,
+    /// `fee_lovelace` expressed in the output token (`None` if it could not be priced).
+    pub fee_in_output: Option<u64>,
+}
+
+impl RouteCosts {
+    pub fn from_hops(hops: Vec<HopCost>) -> Self {
+        Self {
+            fee_lovelace: hops.iter().map(|h| h.fee_lovelace).sum(),
+            deposit_lovelace: hops.iter().map(|h| h.deposit_lovelace).sum(),
+            hops,
+            fee_in_output: None,
+        }
+    }
+}
+
+/// Order fees per DEX. DEXes without an entry are treated as free.
+#[derive(Debug, Clone, Default)]
+pub struct CostModel {
+    fees: HashMap<String, Vec<SwapFee>>,
+}
+
+impl CostModel {
+    /// No fees at all: routes are ranked on AMM output only.
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    /// Published batcher fees and deposits of the supported DEXes.
+    pub fn defaults() -> Self {
+        let fees = DEFAULT_FEES
+            .iter()
+            .map(|(dex, fee, dep)| (dex.to_string(), vec![batcher_fee(*fee), deposit(*dep)]))
+            .collect();
+        Self { fees }
+    }
+
+    /// Replace the fees of `dex` (e.g. after a batcher fee change).
+    pub fn with_fees(mut self, dex: &str, fees: Vec<SwapFee>) -> Self {
+        self.fees.insert(dex.to_string(), fees);
+        self
+    }
+
+    /// Take the fees a [`DexSwap`] implementation advertises.
+    pub fn with_dex(self, dex: &dyn DexSwap) -> Self {
+        let fees = dex.swap_order_fees();
+        self.with_fees(dex.identifier(), fees)
+    }
+
+    pub fn fees(&self, dex: &str) -> &[SwapFee] {
+        self.fees.get(dex).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Cost of one order through `pool`. A pool-specific agent fee read from the
+    /// datum (WingRidersV2) replaces the DEX-wide batcher fee.
+    pub fn hop_cost(&self, pool: &RoutePool) -> HopCost {
+        let mut fees = self.fees(pool.dex_identifier()).to_vec();
+        let agent_fee = match pool {
+            RoutePool::Amm(p) => p.metadata.fee_breakdown.as_ref().and_then(|b| b.agent_fee_lovelace),
+            RoutePool::Stable(_) => None,
+        };
+        if let Some(agent_fee) = agent_fee {
+            fees.retain(|f| f.is_returned);
+            fees.push(batcher_fee(agent_fee));
+        }
+        HopCost {
+            dex_identifier: pool.dex_identifier().to_string(),
+            pool_id: pool.pool_id().to_string(),
+            fee_lovelace: fees
+                .iter()
+                .filter(|f| !f.is_returned)
+                .map(|f| f.value)
+                .sum(),
+            deposit_lovelace: fees.iter().filter(|f| f.is_returned).map(|f| f.value).sum(),
+            fees,
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Token graph of the router's pools, for external pathfinders and
+//! visualisation: nodes are assets, edges are pools.
+//!
+//! Serializes to JSON (amounts as strings, like the pool exports) or to
+//! Graphviz DOT with [`TokenGraph::to_dot`].
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::RoutePool;
+use crate::models::{token_identifier, token_name, Token};
+use crate::quote::Direction;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TokenNode {
+    /// Asset identifier (`lovelace` or policy id + hex name).
+    pub id: String,
+    /// Readable name, for labels.
+    pub name: String,
+    /// Number of pools holding this asset.
+    pub degree: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PoolEdge {
+    pub dex: String,
+    pub pool_id: String,
+    /// `amm` or `stable`.
+    pub kind: &'static str,
+    pub asset_a: String,
+    pub asset_b: String,
+    pub reserve_a: String,
+    pub reserve_b: String,
+    /// Fee in percent when `asset_a` is the input.
+    pub fee_a_to_b: f64,
+    /// Fee in percent when `asset_b` is the input.
+    pub fee_b_to_a: f64,
+    /// StableSwap amplification coefficient, for stable pools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amplification: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TokenGraph {
+    /// Sorted by id.
+    pub nodes: Vec<TokenNode>,
+    pub edges: Vec<PoolEdge>,
+}
+
+fn edge(pool: &RoutePool) -> PoolEdge {
+    match pool {
+        RoutePool::Amm(p) => PoolEdge {
+            dex: p.dex_identifier.clone(),
+            pool_id: p.pool_id.clone(),
+            kind: "amm",
+            asset_a: token_identifier(&p.asset_a),
+            asset_b: token_identifier(&p.asset_b),
+            reserve_a: p.reserve_a.to_string(),
+            reserve_b: p.reserve_b.to_string(),
+            fee_a_to_b: p.fee_percent(Direction::AToB),
+            fee_b_to_a: p.fee_percent(Direction::BToA),
+            amplification: None,
+        },
+        RoutePool::Stable(p) => PoolEdge {
+            dex: p.dex_identifier.clone(),
+            pool_id: p.pool_id.clone(),
+            kind: "stable",
+            asset_a: token_identifier(p.asset_a()),
+            asset_b: token_identifier(p.asset_b()),
+            reserve_a: p.reserve_a().to_string(),
+            reserve_b: p.reserve_b().to_string(),
+            fee_a_to_b: p.pool_fee_percent,
+            fee_b_to_a: p.pool_fee_percent,
+            amplification: Some(p.amplification_coefficient.to_string()),
+        },
+    }
+}
+
+/// Graph of `pools`: one node per asset, one edge per pool.
+pub fn token_graph<'a>(pools: impl IntoIterator<Item = &'a RoutePool>) -> TokenGraph {
+    let mut nodes: BTreeMap<String, TokenNode> = BTreeMap::new();
+    let mut add_node = |token: &Token| {
+        nodes
+            .entry(token_identifier(token))
+            .or_insert_with(|| TokenNode {
+                id: token_identifier(token),
+                name: token_name(token),
+                degree: 0,
+            })
+            .degree += 1;
+    };
+    let mut edges = Vec::new();
+    for pool in pools {
+        let (a, b) = pool.assets();
+        add_node(a);
+        add_node(b);
+        edges.push(edge(pool));
+    }
+    TokenGraph {
+        nodes: nodes.into_values().collect(),
+        edges,
+    }
+}
+
+/// Quote `s` as a DOT string literal.
+fn dot_str(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl TokenGraph {
+    /// Undirected Graphviz graph; edges are labelled with DEX and fee.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph tokens {\n");
+        for node in &self.nodes {
+            dot.push_str(&format!(
+                "  {} [label={}];\n",
+                dot_str(&node.id),
+                dot_str(&node.name)
+            ));
+        }
+        for edge in &self.edges {
+            let label = format!("{} {}%", edge.dex, edge.fee_a_to_b);
+            dot.push_str(&format!(
+                "  {} -- {} [label={}, id={}];\n",
+                dot_str(&edge.asset_a),
+                dot_str(&edge.asset_b),
+                dot_str(&label),
+                dot_str(&edge.pool_id)
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+    use crate::models::LiquidityPool;
+
+    #[test]
+    fn test_token_graph_json_and_dot() {
+        let snek = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+        let pool = |id: &str| {
+            RoutePool::from(LiquidityPool::new(
+                "MinswapV2",
+                Token::Lovelace,
+                from_identifier(snek, 0),
+                1_000,
+                2_000,
+                "addr",
+                0.3,
+                id,
+            ))
+        };
+        let pools = [pool("p1"), pool("p2")];
+        let graph = token_graph(&pools);
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.nodes[1].id, "lovelace");
+        assert_eq!(graph.nodes[1].degree, 2);
+        assert_eq!(graph.edges[0].reserve_b, "2000");
+
+        let json = serde_json::to_value(&graph).unwrap();
+        assert_eq!(json["edges"][1]["pool_id"], "p2");
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("graph tokens {"));
+        assert!(dot.contains(&format!("\"lovelace\" -- \"{}\"", snek)));
+    }
+}
@@ -0,0 +1,537 @@
+//! Route swaps over the pools of one pair, across DEXes.
+//!
+//! The router holds a snapshot of AMM and stable pools and answers "what do I
+//! get for `amount` of `token_in`" either through the single best pool or by
+//! splitting the order so that the combined price impact is smallest. Routes
+//! are ranked on net output: AMM output less the batcher fees of every hop
+//! (see [`CostModel`]).
+//!
+//! With a [`StalenessModel`], every route also carries a [`RouteConfidence`]:
+//! how far its output may move by the time the order settles, given the age
+//! of each pool's UTXO and the pool's recent volatility.
+//!
+//! [`ArbScanner`] looks for the opposite: ADA → X → Y → ADA cycles that
+//! return more ADA than they take, after pool and batcher fees.
+
+pub mod arbitrage;
+pub mod confidence;
+pub mod cost;
+pub mod graph;
+
+pub use arbitrage::{triangular_arbitrage, ArbHop, ArbOpportunity, ArbScanner};
+pub use confidence::{RouteConfidence, StalenessModel};
+pub use cost::{CostModel, HopCost, RouteCosts};
+pub use graph::{token_graph, PoolEdge, TokenGraph, TokenNode};
+
+use crate::models::{token_identifier, LiquidityPool, StablePool, Token};
+use crate::quote::{self, Direction, QuoteCache};
+use serde::Serialize;
+
+/// Number of chunks an order is cut into when splitting.
+const DEFAULT_SPLIT_STEPS: u64 = 20;
+
+/// A pool the router can quote against.
+#[derive(Debug, Clone)]
+pub enum RoutePool {
+    Amm(Box<LiquidityPool>),
+    Stable(StablePool),
+}
+
+impl RoutePool {
+    pub fn dex_identifier(&self) -> &str {
+        match self {
+            RoutePool::Amm(p) => &p.dex_identifier,
+            RoutePool::Stable(p) => &p.dex_identifier,
+        }
+    }
+
+    pub fn pool_id(&self) -> &str {
+        match self {
+            RoutePool::Amm(p) => &p.pool_id,
+            RoutePool::Stable(p) => &p.pool_id,
+        }
+    }
+
+    pub fn assets(&self) -> (&Token, &Token) {
+        match self {
+            RoutePool::Amm(p) => (&p.asset_a, &p.asset_b),
+            RoutePool::Stable(p) => (p.asset_a(), p.asset_b()),
+        }
+    }
+
+    /// Swap direction for `token_in -> token_out`, or `None` if this pool is another pair.
+    pub fn direction(&self, token_in: &Token, token_out: &Token) -> Option<Direction> {
+        let (a, b) = self.assets();
+        let (a, b) = (token_identifier(a), token_identifier(b));
+        let (i, o) = (token_identifier(token_in), token_identifier(token_out));
+        if a == i && b == o {
+            Some(Direction::AToB)
+        } else if a == o && b == i {
+            Some(Direction::BToA)
+        } else {
+            None
+        }
+    }
+
+    /// Slot of the UTXO the pool state was read from, when known.
+    pub fn slot(&self) -> Option<u64> {
+        match self {
+            RoutePool::Amm(p) => p.metadata.freshness.as_ref()?.slot,
+            RoutePool::Stable(p) => p.freshness.as_ref()?.slot,
+        }
+    }
+
+    /// Uncached output for `amount_in`.
+    pub fn quote(&self, direction: Direction, amount_in: u64) -> u64 {
+        match self {
+            RoutePool::Amm(p) => quote::amm_out(p, direction, amount_in),
+            RoutePool::Stable(p) => quote::stable_out(p, direction, amount_in),
+        }
+    }
+
+    fn quote_cached(&self, cache: &QuoteCache, direction: Direction, amount_in: u64) -> u64 {
+        match self {
+            RoutePool::Amm(p) => cache.amm_out(p, direction, amount_in),
+            RoutePool::Stable(p) => cache.stable_out(p, direction, amount_in),
+        }
+    }
+}
+
+impl From<LiquidityPool> for RoutePool {
+    fn from(pool: LiquidityPool) -> Self {
+        RoutePool::Amm(Box::new(pool))
+    }
+}
+
+impl From<StablePool> for RoutePool {
+    fn from(pool: StablePool) -> Self {
+        RoutePool::Stable(pool)
+    }
+}
+
+/// Part of an order sent through one pool.
+#[derive(Debug, Clone, Serialize)]
+pub struct Allocation {
+    pub dex_identifier: String,
+    pub pool_id: String,
+    pub direction: Direction,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+/// An order routed through one or more pools of the same pair.
+#[derive(Debug, Clone, Serialize)]
+pub struct Route {
+    pub amount_in: u64,
+    /// Sum of the allocation outputs (AMM math only).
+    pub amount_out: u64,
+    /// `amount_out` less the non-returned order fees, priced in the output token.
+    pub net_amount_out: u64,
+    pub allocations: Vec<Allocation>,
+    pub costs: RouteCosts,
+    /// Set when the router has a [`StalenessModel`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<RouteConfidence>,
+}
+
+impl Route {
+    pub fn is_split(&self) -> bool {
+        self.allocations.len() > 1
+    }
+}
+
+pub struct Router {
+    pools: Vec<RoutePool>,
+    cache: QuoteCache,
+    costs: CostModel,
+    split_steps: u64,
+    staleness: Option<StalenessModel>,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self {
+            pools: Vec::new(),
+            cache: QuoteCache::new(),
+            costs: CostModel::defaults(),
+            split_steps: DEFAULT_SPLIT_STEPS,
+            staleness: None,
+        }
+    }
+
+    pub fn with_pools<P: Into<RoutePool>>(mut self, pools: impl IntoIterator<Item = P>) -> Self {
+        self.pools.extend(pools.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_cache(mut self, cache: QuoteCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Order fees used to rank routes; [`CostModel::zero`] ranks on AMM output only.
+    pub fn with_cost_model(mut self, costs: CostModel) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Number of chunks used by [`split_route`](Self::split_route) (min 1).
+    /// More steps get closer to the optimal split at the cost of more quotes.
+    pub fn with_split_steps(mut self, steps: u64) -> Self {
+        self.split_steps = steps.max(1);
+        self
+    }
+
+    /// Attach a [`RouteConfidence`] from `model` to every route.
+    pub fn with_staleness(mut self, model: StalenessModel) -> Self {
+        self.staleness = Some(model);
+        self
+    }
+
+    pub fn add_pool(&mut self, pool: impl Into<RoutePool>) {
+        self.pools.push(pool.into());
+    }
+
+    pub fn pools(&self) -> &[RoutePool] {
+        &self.pools
+    }
+
+    pub fn cache(&self) -> &QuoteCache {
+        &self.cache
+    }
+
+    pub fn cost_model(&self) -> &CostModel {
+        &self.costs
+    }
+
+    /// The router's pools as a [`TokenGraph`], for external pathfinders.
+    pub fn token_graph(&self) -> TokenGraph {
+        token_graph(&self.pools)
+    }
+
+    /// Pools trading `token_in -> token_out`, with the direction to use.
+    pub fn pools_for_pair(
+        &self,
+        token_in: &Token,
+        token_out: &Token,
+    ) -> Vec<(&RoutePool, Direction)> {
+        self.pools
+            .iter()
+            .filter_map(|p| p.direction(token_in, token_out).map(|d| (p, d)))
+            .collect()
+    }
+
+    /// Single-pool route through every pool of the pair, best net output first.
+    ///
+    /// Pools that return nothing for `amount_in` are left out.
+    pub fn pool_routes(&self, token_in: &Token, token_out: &Token, amount_in: u64) -> Vec<Route> {
+        let mut routes: Vec<Route> = self
+            .pools_for_pair(token_in, token_out)
+            .into_iter()
+            .filter_map(|(pool, direction)| {
+                let amount_out = pool.quote(direction, amount_in);
+                if amount_out == 0 {
+                    return None;
+                }
+                let alloc = allocation(pool, direction, amount_in, amount_out);
+                Some(self.route(token_in, token_out, amount_in, vec![(pool, alloc)]))
+            })
+            .collect();
+        routes.sort_by_key(|r| std::cmp::Reverse(r.net_amount_out));
+        routes
+    }
+
+    /// Route the whole order through the pool with the highest net output.
+    pub fn best_route(&self, token_in: &Token, token_out: &Token, amount_in: u64) -> Option<Route> {
+        self.pools_for_pair(token_in, token_out)
+            .into_iter()
+            .filter_map(|(pool, direction)| {
+                let amount_out = pool.quote_cached(&self.cache, direction, amount_in);
+                if amount_out == 0 {
+                    return None;
+                }
+                let alloc = allocation(pool, direction, amount_in, amount_out);
+                let route = self.route(token_in, token_out, amount_in, vec![(pool, alloc)]);
+                Some((pool, direction, route.net_amount_out))
+            })
+            .max_by_key(|(_, _, net)| *net)
+            .map(|(pool, direction, _)| {
+                // Cached quotes are bucketed; report the exact output for the winner.
+                let amount_out = pool.quote(direction, amount_in);
+                let alloc = allocation(pool, direction, amount_in, amount_out);
+                self.route(token_in, token_out, amount_in, vec![(pool, alloc)])
+            })
+    }
+
+    /// Split the order across the pools of the pair to maximise the combined net output.
+    ///
+    /// The order is cut into `split_steps` chunks and each chunk goes to the pool
+    /// with the best marginal output given what it already received. Output is
+    /// concave in the input for both curve types, so this converges on the
+    /// equal-marginal-price split as the step count grows. Since every hop pays
+    /// its own batcher fee, the smallest allocation is then dropped and the split
+    /// redone for as long as that improves the net output. Falls back to the
+    /// single best pool when splitting does not help.
+    pub fn split_route(
+        &self,
+        token_in: &Token,
+        token_out: &Token,
+        amount_in: u64,
+    ) -> Option<Route> {
+        let mut candidates = self.pools_for_pair(token_in, token_out);
+        if candidates.is_empty() || amount_in == 0 {
+            return None;
+        }
+        let single = self.best_route(token_in, token_out, amount_in);
+        if candidates.len() == 1 {
+            return single;
+        }
+
+        let mut best: Option<Route> = None;
+        while candidates.len() > 1 {
+            let hops = self.allocate(&candidates, amount_in);
+            let smallest = hops
+                .iter()
+                .min_by_key(|(_, a)| a.amount_in)
+                .map(|(pool, _)| pool.pool_id().to_string());
+            let used: Vec<&str> = hops.iter().map(|(pool, _)| pool.pool_id()).collect();
+            candidates.retain(|(pool, _)| used.contains(&pool.pool_id()));
+
+            let route = self.route(token_in, token_out, amount_in, hops);
+            if best
+                .as_ref()
+                .is_some_and(|b| b.net_amount_out >= route.net_amount_out)
+            {
+                break;
+            }
+            best = Some(route);
+            match smallest {
+                Some(id) => candidates.retain(|(pool, _)| pool.pool_id() != id),
+                None => break,
+            }
+        }
+
+        match (single, best) {
+            (Some(single), Some(split)) if split.net_amount_out > single.net_amount_out => {
+                Some(split)
+            }
+            (Some(single), _) => Some(single),
+            (None, split) => split.filter(|r| r.amount_out > 0),
+        }
+    }
+
+    /// Greedy chunked allocation of `amount_in` over `candidates`.
+    fn allocate<'a>(
+        &self,
+        candidates: &[(&'a RoutePool, Direction)],
+        amount_in: u64,
+    ) -> Vec<(&'a RoutePool, Allocation)> {
+        let steps = self.split_steps.min(amount_in);
+        let chunk = amount_in / steps;
+        let mut allocated = vec![0u64; candidates.len()];
+        let mut outputs = vec![0u64; candidates.len()];
+
+        for step in 0..steps {
+            let size = if step == steps - 1 {
+                amount_in - chunk * (steps - 1)
+            } else {
+                chunk
+            };
+            let best = candidates
+                .iter()
+                .enumerate()
+                .map(|(i, (pool, direction))| {
+                    let out = pool.quote(*direction, allocated[i] + size);
+                    (i, out, out.saturating_sub(outputs[i]))
+                })
+                .max_by_key(|(_, _, gain)| *gain);
+            if let Some((i, out, _)) = best {
+                allocated[i] += size;
+                outputs[i] = out;
+            }
+        }
+
+        candidates
+            .iter()
+            .zip(allocated.iter().zip(outputs.iter()))
+            .filter(|(_, (amount, _))| **amount > 0)
+            .map(|((pool, direction), (amount, out))| {
+                (*pool, allocation(pool, *direction, *amount, *out))
+            })
+            .collect()
+    }
+
+    /// Assemble a route and price its order fees in the output token.
+    fn route(
+        &self,
+        token_in: &Token,
+        token_out: &Token,
+        amount_in: u64,
+        hops: Vec<(&RoutePool, Allocation)>,
+    ) -> Route {
+        let mut costs = RouteCosts::from_hops(
+            hops.iter()
+                .map(|(pool, _)| self.costs.hop_cost(pool))
+                .collect(),
+        );
+        let amount_out: u64 = hops.iter().map(|(_, a)| a.amount_out).sum();
+        let confidence = self
+            .staleness
+            .as_ref()
+            .map(|model| model.confidence(&hops, amount_out));
+        let allocations: Vec<Allocation> = hops.into_iter().map(|(_, a)| a).collect();
+
+        costs.fee_in_output = if costs.fee_lovelace == 0 {
+            Some(0)
+        } else if token_out.is_lovelace() {
+            Some(costs.fee_lovelace)
+        } else if token_in.is_lovelace() {
+            // The route's own execution rate.
+            Some(mul_div(costs.fee_lovelace, amount_out, amount_in))
+        } else {
+            self.lovelace_value_in(token_out, costs.fee_lovelace)
+        };
+
+        Route {
+            amount_in,
+            amount_out,
+            net_amount_out: amount_out.saturating_sub(costs.fee_in_output.unwrap_or(0)),
+            allocations,
+            costs,
+            confidence,
+        }
+    }
+
+    /// `lovelace` priced in `token` at the spot price of the deepest ADA pool of that token.
+    fn lovelace_value_in(&self, token: &Token, lovelace: u64) -> Option<u64> {
+        self.pools_for_pair(&Token::Lovelace, token)
+            .into_iter()
+            .filter_map(|(pool, direction)| match (pool, direction) {
+                (RoutePool::Amm(p), Direction::AToB) => Some((p.reserve_a, p.reserve_b)),
+                (RoutePool::Amm(p), Direction::BToA) => Some((p.reserve_b, p.reserve_a)),
+                (RoutePool::Stable(_), _) => None,
+            })
+            .filter(|(ada, _)| *ada > 0)
+            .max_by_key(|(ada, _)| *ada)
+            .map(|(ada, other)| mul_div(lovelace, other, ada))
+    }
+}
+
+fn mul_div(a: u64, b: u64, c: u64) -> u64 {
+    if c == 0 {
+        return 0;
+    }
+    (a as u128 * b as u128 / c as u128) as u64
+}
+
+fn allocation(
+    pool: &RoutePool,
+    direction: Direction,
+    amount_in: u64,
+    amount_out: u64,
+) -> Allocation {
+    Allocation {
+        dex_identifier: pool.dex_identifier().to_string(),
+        pool_id: pool.pool_id().to_string(),
+        direction,
+        amount_in,
+        amount_out,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    fn pool(dex: &str, id: &str, reserve_ada: u64, reserve_token: u64) -> LiquidityPool {
+        LiquidityPool::new(
+            dex,
+            Token::Lovelace,
+            from_identifier(SNEK, 0),
+            reserve_ada,
+            reserve_token,
+            "addr",
+            0.3,
+            id,
+        )
+    }
+
+    #[test]
+    fn test_split_route_beats_single_pool() {
+        let router = Router::new().with_pools([
+            pool("MinswapV2", "a", 1_000_000_000, 1_000_000_000),
+            pool("SundaeSwapV3", "b", 1_000_000_000, 1_000_000_000),
+            pool("WingRiders", "c", 10_000_000, 10),
+        ]);
+        let ada = Token::Lovelace;
+        let snek = from_identifier(SNEK, 0);
+
+        let single = router.best_route(&ada, &snek, 200_000_000).unwrap();
+        let split = router.split_route(&ada, &snek, 200_000_000).unwrap();
+        assert!(split.amount_out > single.amount_out);
+        assert_eq!(split.allocations.len(), 2);
+        assert!(split.allocations.iter().all(|a| a.amount_in == 100_000_000));
+
+        // Reverse direction is found through the same pools.
+        let back = router.split_route(&snek, &ada, 1_000).unwrap();
+        assert_eq!(back.allocations[0].direction, Direction::BToA);
+    }
+
+    #[test]
+    fn test_costs_decide_small_orders() {
+        let router = Router::new().with_pools([
+            pool("MinswapV2", "a", 1_000_000_000, 1_000_000_000),
+            pool("SundaeSwapV3", "b", 1_000_000_000, 1_000_000_000),
+        ]);
+        let ada = Token::Lovelace;
+        let snek = from_identifier(SNEK, 0);
+
+        // Identical pools: the cheaper batcher wins, and a 10 ADA order is not split.
+        let route = router.split_route(&ada, &snek, 10_000_000).unwrap();
+        assert!(!route.is_split());
+        assert_eq!(route.allocations[0].pool_id, "b");
+        assert_eq!(route.costs.fee_lovelace, 1_000_000);
+        assert_eq!(route.costs.deposit_lovelace, 2_000_000);
+        assert!(route.net_amount_out < route.amount_out);
+
+        let free = Router::new()
+            .with_cost_model(CostModel::zero())
+            .with_pools(router.pools().to_vec());
+        let route = free.best_route(&snek, &ada, 10_000_000).unwrap();
+        assert_eq!(route.net_amount_out, route.amount_out);
+    }
+
+    #[test]
+    fn test_pool_routes_ranked_by_net_output() {
+        let router = Router::new().with_pools([
+            pool("WingRiders", "c", 10_000_000, 10),
+            pool("MinswapV2", "a", 1_000_000_000, 1_000_000_000),
+            pool("SundaeSwapV3", "b", 1_000_000_000, 2_000_000_000),
+        ]);
+        let ada = Token::Lovelace;
+        let snek = from_identifier(SNEK, 0);
+
+        let routes = router.pool_routes(&ada, &snek, 10_000_000);
+        let ids: Vec<&str> = routes
+            .iter()
+            .map(|r| r.allocations[0].pool_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["b", "a", "c"]);
+        assert_eq!(
+            routes[0].net_amount_out,
+            router
+                .best_route(&ada, &snek, 10_000_000)
+                .unwrap()
+                .net_amount_out
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! API keys and per-key request quotas.
+//!
+//! With [`Server::with_access`](super::Server::with_access), every endpoint
+//! but the `/healthz` and `/readyz` probes needs the token of a configured
+//! key, as `Authorization: Bearer <token>` or, for clients that cannot set
+//! headers (a browser `EventSource` on `/events`), an `access_token` query
+//! parameter. A missing or unknown token gets 401.
+//!
+//! Each key draws from a token bucket of `requests_per_minute` (falling back
+//! to `default_requests_per_minute`; unlimited when neither is set) that
+//! refills continuously and bursts up to one minute's worth. A request over
+//! quota gets 429 with `Retry-After`; others carry `X-RateLimit-Limit` and
+//! `X-RateLimit-Remaining`.
+//!
+//! `/admin/` endpoints (the state [`dump`](super::dump)) take neither API
+//! keys nor quotas: they need the key file's `admin_token`, or without one a
+//! client on the loopback interface, and answer 403 otherwise. That holds
+//! without any keys configured too.
+//!
+//! ```toml
+//! default_requests_per_minute = 120
+//! admin_token = "0b9e...5a"
+//!
+//! [[keys]]
+//! name = "dashboard"
+//! token = "d4c1...e9"
+//! requests_per_minute = 600
+//! ```
+
+use anyhow::{anyhow, Result};
+use axum::extract::{ConnectInfo, Query, Request, State};
+use axum::http::header::{AUTHORIZATION, RETRY_AFTER, WWW_AUTHENTICATE};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::SharedState;
+
+/// Endpoints open without a key, so orchestrators can probe the server.
+const PUBLIC_PATHS: &[&str] = &["/healthz", "/readyz"];
+
+/// Endpoints under this prefix need the admin token or a loopback client.
+const ADMIN_PREFIX: &str = "/admin/";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiKey {
+    /// Label used for quotas and logs; never sent by clients.
+    pub name: String,
+    pub token: String,
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccessConfig {
+    #[serde(default)]
+    pub keys: Vec<ApiKey>,
+    /// Quota of keys without their own `requests_per_minute`.
+    #[serde(default)]
+    pub default_requests_per_minute: Option<u32>,
+    /// Token of the `/admin/` endpoints; without one they only answer
+    /// loopback clients.
+    #[serde(default)]
+    pub admin_token: Option<String>,
+}
+
+/// Byte comparison whose time does not depend on where the inputs differ.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl AccessConfig {
+    pub fn from_toml_str(s: &str) -> Result<Self> {
+        let config: Self =
+            toml::from_str(s).map_err(|e| anyhow!("Failed to parse API keys: {}", e))?;
+        config.validated()
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self> {
+        let config: Self =
+            serde_json::from_str(s).map_err(|e| anyhow!("Failed to parse API keys: {}", e))?;
+        config.validated()
+    }
+
+    /// Load a key file; `.toml` files are parsed as TOML, anything else as JSON.
+    pub fn load_file(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read API keys {}: {}", path, e))?;
+        if path.ends_with(".toml") {
+            Self::from_toml_str(&content)
+        } else {
+            Self::from_json_str(&content)
+        }
+        .map_err(|e| anyhow!("{}: {}", path, e))
+    }
+
+    fn validated(self) -> Result<Self> {
+        if self.keys.is_empty() {
+            return Err(anyhow!("no API keys configured"));
+        }
+        let (mut names, mut tokens) = (HashSet::new(), HashSet::new());
+        for key in &self.keys {
+            if key.token.is_empty() {
+                return Err(anyhow!("API key '{}' has an empty token", key.name));
+            }
+            if !names.insert(&key.name) {
+                return Err(anyhow!("API key '{}' is defined twice", key.name));
+            }
+            if !tokens.insert(&key.token) {
+                return Err(anyhow!("API key '{}' reuses another key's token", key.name));
+            }
+        }
+        if let Some(admin) = &self.admin_token {
+            if admin.is_empty() {
+                return Err(anyhow!("admin_token is empty"));
+            }
+            if tokens.contains(admin) {
+                return Err(anyhow!("admin_token reuses an API key's token"));
+            }
+        }
+        let quotas = self.keys.iter().map(|k| k.requests_per_minute);
+        if quotas
+            .chain([self.default_requests_per_minute])
+            .any(|q| q == Some(0))
+        {
+            return Err(anyhow!("requests_per_minute must be positive"));
+        }
+        Ok(self)
+    }
+
+    /// The key whose token is `token`.
+    pub fn key(&self, token: &str) -> Option<&ApiKey> {
+        self.keys
+            .iter()
+            .find(|key| constant_time_eq(key.token.as_bytes(), token.as_bytes()))
+    }
+
+    /// Requests per minute allowed to `key`; `None` for unlimited.
+    pub fn quota(&self, key: &ApiKey) -> Option<u32> {
+        key.requests_per_minute.or(self.default_requests_per_minute)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets per key name.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take one request from `key`'s bucket at `now`: the requests left, or
+    /// how long until the next one is allowed.
+    pub fn check(&self, key: &str, per_minute: u32, now: Instant) -> Result<u32, Duration> {
+        let capacity = per_minute as f64;
+        let per_sec = capacity / 60.0;
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens < 1.0 {
+            return Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec));
+        }
+        bucket.tokens -= 1.0;
+        Ok(bucket.tokens as u32)
+    }
+}
+
+/// Keys plus the quota state of a running server.
+#[derive(Debug)]
+pub struct Access {
+    pub config: AccessConfig,
+    pub limiter: RateLimiter,
+}
+
+impl Access {
+    pub fn new(config: AccessConfig) -> Self {
+        Self {
+            config,
+            limiter: RateLimiter::new(),
+        }
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+fn query_token(uri: &Uri) -> Option<String> {
+    let Query(mut params) = Query::<HashMap<String, String>>::try_from_uri(uri).ok()?;
+    params.remove("access_token")
+}
+
+/// Whether an admin request may go through: with an admin token, for whoever
+/// presents it; without one, for loopback peers only.
+fn admin_allowed(admin_token: Option<&str>, token: Option<&str>, peer: Option<SocketAddr>) -> bool {
+    match admin_token {
+        Some(admin) => token.is_some_and(|t| constant_time_eq(t.as_bytes(), admin.as_bytes())),
+        None => peer.is_some_and(|peer| peer.ip().to_canonical().is_loopback()),
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+/// Middleware rejecting requests without a valid key or over its quota, and
+/// admin requests from anyone but the admin.
+pub(super) async fn authorize(
+    State(state): State<SharedState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.uri().path().starts_with(ADMIN_PREFIX) {
+        let admin_token = state
+            .access
+            .as_ref()
+            .and_then(|access| access.config.admin_token.as_deref());
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0);
+        if !admin_allowed(admin_token, bearer_token(request.headers()), peer) {
+            return error(
+                StatusCode::FORBIDDEN,
+                "admin endpoints need the admin token or a loopback client",
+            );
+        }
+        return next.run(request).await;
+    }
+    let Some(access) = &state.access else {
+        return next.run(request).await;
+    };
+    if PUBLIC_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+    let token = bearer_token(request.headers())
+        .map(str::to_string)
+        .or_else(|| query_token(request.uri()));
+    let Some(key) = token.as_deref().and_then(|t| access.config.key(t)) else {
+        let mut response = error(StatusCode::UNAUTHORIZED, "missing or unknown API key");
+        response
+            .headers_mut()
+            .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        return response;
+    };
+    let Some(limit) = access.config.quota(key) else {
+        return next.run(request).await;
+    };
+    match access.limiter.check(&key.name, limit, Instant::now()) {
+        Ok(remaining) => {
+            let mut response = next.run(request).await;
+            let headers = response.headers_mut();
+            headers.insert("x-ratelimit-limit", HeaderValue::from(limit));
+            headers.insert("x-ratelimit-remaining", HeaderValue::from(remaining));
+            response
+        }
+        Err(retry_after) => {
+            let mut response = error(StatusCode::TOO_MANY_REQUESTS, "request quota exceeded");
+            let secs = retry_after.as_secs_f64().ceil() as u64;
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(secs.max(1)));
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_and_quotas() {
+        let config = AccessConfig::from_toml_str(
+            r#"
+            default_requests_per_minute = 60
+
+            [[keys]]
+            name = "dashboard"
+            token = "secret-a"
+            requests_per_minute = 2
+
+            [[keys]]
+            name = "ops"
+            token = "secret-b"
+            "#,
+        )
+        .unwrap();
+        let dashboard = config.key("secret-a").unwrap();
+        assert_eq!(dashboard.name, "dashboard");
+        assert_eq!(config.quota(dashboard), Some(2));
+        assert_eq!(config.quota(config.key("secret-b").unwrap()), Some(60));
+        assert!(config.key("secret").is_none());
+
+        let limiter = RateLimiter::new();
+        let start = Instant::now();
+        assert_eq!(limiter.check("dashboard", 2, start), Ok(1));
+        assert_eq!(limiter.check("dashboard", 2, start), Ok(0));
+        let wait = limiter.check("dashboard", 2, start).unwrap_err();
+        assert_eq!(wait.as_secs_f64().round(), 30.0);
+        assert!(limiter.check("ops", 60, start).is_ok());
+        let later = start + Duration::from_secs(31);
+        assert!(limiter.check("dashboard", 2, later).is_ok());
+
+        let dup = r#"{"keys": [{"name": "a", "token": "t"}, {"name": "b", "token": "t"}]}"#;
+        assert!(AccessConfig::from_json_str(dup).is_err());
+        let admin = r#"{"keys": [{"name": "a", "token": "t"}], "admin_token": "t"}"#;
+        assert!(AccessConfig::from_json_str(admin).is_err());
+    }
+
+    #[test]
+    fn test_admin_allowed() {
+        let local: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let mapped: SocketAddr = "[::ffff:127.0.0.1]:50000".parse().unwrap();
+        let remote: SocketAddr = "203.0.113.7:50000".parse().unwrap();
+
+        assert!(admin_allowed(None, None, Some(local)));
+        assert!(admin_allowed(None, None, Some(mapped)));
+        assert!(!admin_allowed(None, Some("anything"), Some(remote)));
+        assert!(!admin_allowed(None, None, None));
+
+        assert!(admin_allowed(Some("root"), Some("root"), Some(remote)));
+        assert!(!admin_allowed(Some("root"), Some("key"), Some(local)));
+        assert!(!admin_allowed(Some("root"), None, Some(local)));
+    }
+}
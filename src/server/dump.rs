@@ -0,0 +1,220 @@
+//! On-demand dumps of a running server's state, for debugging incidents
+//! without restarting it.
+//!
+//! With [`Server::with_dump_dir`](super::Server::with_dump_dir), `SIGUSR1`
+//! (on Unix) or `POST /admin/dump` writes the pool store, the watcher's state
+//! and the refresh status to `dexter-dump-<unix millis>.json` in that
+//! directory. The watcher is locked for a whole refresh round; when a round
+//! (e.g. one retrying an unreachable Kupo) holds it for more than
+//! [`WATCHER_WAIT`], the dump is written without it. Only the newest
+//! [`DEFAULT_KEEP`] dumps are kept (see
+//! [`Server::with_dump_retention`](super::Server::with_dump_retention)), so
+//! repeated requests cannot fill the disk. The endpoint answers with the path
+//! written, and only to loopback clients or the admin token (see
+//! [`auth`](super::auth)).
+//!
+//! ```sh
+//! kill -USR1 $(pidof dexter-kupo-rs)
+//! ```
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::AppState;
+use crate::models::LiquidityPool;
+use crate::watcher::WatcherSnapshot;
+
+/// Longest a dump waits for a refresh round to release the watcher.
+pub const WATCHER_WAIT: Duration = Duration::from_secs(5);
+
+/// Dumps kept in the dump dir unless configured otherwise.
+pub const DEFAULT_KEEP: usize = 10;
+
+const PREFIX: &str = "dexter-dump-";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RefreshDump {
+    /// Unix seconds of the last round in which every DEX was fetched.
+    pub refreshed_at: Option<u64>,
+    /// Seconds since the last round started, complete or not.
+    pub round_started_secs_ago: Option<u64>,
+    pub failed_dexes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Dump {
+    /// Unix milliseconds when the dump was taken.
+    pub taken_at_ms: u128,
+    pub refresh: RefreshDump,
+    /// Pools served by the API, sorted by DEX and pool id.
+    pub store: Vec<LiquidityPool>,
+    /// `None` when a refresh round held the watcher past [`WATCHER_WAIT`].
+    pub watcher: Option<WatcherSnapshot>,
+}
+
+/// Capture `state`. Waits up to [`WATCHER_WAIT`] for a refresh round in
+/// progress, so the store and the watcher are dumped from the same round.
+pub(super) async fn capture(state: &AppState) -> Dump {
+    let watcher = tokio::time::timeout(WATCHER_WAIT, state.watcher.lock())
+        .await
+        .map(|watcher| watcher.snapshot())
+        .ok();
+    let refresh = {
+        let status = state.refresh.read().unwrap();
+        RefreshDump {
+            refreshed_at: status.refreshed_at,
+            round_started_secs_ago: status.round_started.map(|at| at.elapsed().as_secs()),
+            failed_dexes: status.failed_dexes.clone(),
+        }
+    };
+    let mut store: Vec<LiquidityPool> = state.store.read().unwrap().pools().cloned().collect();
+    store.sort_by(|a, b| (&a.dex_identifier, &a.pool_id).cmp(&(&b.dex_identifier, &b.pool_id)));
+    Dump {
+        taken_at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0),
+        refresh,
+        store,
+        watcher,
+    }
+}
+
+/// Write `dump` as pretty JSON into `dir`, creating it if needed, and return
+/// the file's path.
+pub async fn write(dir: &Path, dump: &Dump) -> Result<PathBuf> {
+    let json = serde_json::to_vec_pretty(dump)?;
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| anyhow!("Failed to create dump dir {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}{}.json", PREFIX, dump.taken_at_ms));
+    tokio::fs::write(&path, json)
+        .await
+        .map_err(|e| anyhow!("Failed to write dump {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Delete all but the newest `keep` dumps in `dir`; other files are left
+/// alone.
+pub async fn prune(dir: &Path, keep: usize) -> Result<()> {
+    let read_err = |e: std::io::Error| anyhow!("Failed to read dump dir {}: {}", dir.display(), e);
+    let mut entries = tokio::fs::read_dir(dir).await.map_err(read_err)?;
+    let mut dumps = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(read_err)? {
+        let taken_at_ms = entry.file_name().to_str().and_then(|name| {
+            name.strip_prefix(PREFIX)?
+                .strip_suffix(".json")?
+                .parse::<u128>()
+                .ok()
+        });
+        if let Some(taken_at_ms) = taken_at_ms {
+            dumps.push((taken_at_ms, entry.path()));
+        }
+    }
+    dumps.sort_unstable_by_key(|dump| std::cmp::Reverse(dump.0));
+    for (_, path) in dumps.into_iter().skip(keep) {
+        tokio::fs::remove_file(&path)
+            .await
+            .map_err(|e| anyhow!("Failed to remove dump {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Capture `state`, write it to its dump dir and drop the dumps beyond its
+/// retention.
+pub(super) async fn dump(state: &AppState) -> Result<PathBuf> {
+    let dir = state
+        .dump_dir
+        .as_deref()
+        .ok_or_else(|| anyhow!("no dump dir configured"))?;
+    let path = write(dir, &capture(state).await).await?;
+    prune(dir, state.dump_keep.max(1)).await?;
+    Ok(path)
+}
+
+/// Dump `state` on every `SIGUSR1` until the process exits.
+#[cfg(unix)]
+pub(super) async fn on_sigusr1(state: super::SharedState) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut signals = match signal(SignalKind::user_defined1()) {
+        Ok(signals) => signals,
+        Err(e) => {
+            eprintln!("[server] cannot listen for SIGUSR1: {}", e);
+            return;
+        }
+    };
+    while signals.recv().await.is_some() {
+        match dump(&state).await {
+            Ok(path) => eprintln!("[server] state dumped to {}", path.display()),
+            Err(e) => eprintln!("[server] dump failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kupo::KupoApi;
+    use crate::models::Token;
+    use crate::server::ReadinessConfig;
+    use crate::watcher::PoolWatcher;
+
+    #[tokio::test]
+    async fn test_dump_writes_store_and_watcher() {
+        let dir = std::env::temp_dir().join(format!("dexter-dump-test-{}", std::process::id()));
+        let mut state = AppState::new(
+            KupoApi::new("http://127.0.0.1:9"),
+            PoolWatcher::new(Vec::new()),
+            ReadinessConfig::default(),
+        );
+        state.dump_dir = Some(dir.clone());
+        state.store.write().unwrap().extend([LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            Token::Lovelace,
+            1,
+            2,
+            "addr",
+            0.3,
+            "pool",
+        )]);
+
+        let path = dump(&state).await.unwrap();
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(json["store"][0]["pool_id"], "pool");
+        assert_eq!(json["watcher"]["seeded"], false);
+        assert!(json["refresh"]["refreshed_at"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_prune_keeps_newest() {
+        let dir = std::env::temp_dir().join(format!("dexter-prune-test-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        for taken_at_ms in [1_000, 3_000, 2_000] {
+            let name = format!("{}{}.json", PREFIX, taken_at_ms);
+            tokio::fs::write(dir.join(name), "{}").await.unwrap();
+        }
+        tokio::fs::write(dir.join("notes.txt"), "").await.unwrap();
+
+        prune(&dir, 2).await.unwrap();
+        let mut left: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            left,
+            [
+                "dexter-dump-2000.json",
+                "dexter-dump-3000.json",
+                "notes.txt"
+            ]
+        );
+    }
+}
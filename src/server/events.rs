@@ -0,0 +1,127 @@
+//! Server-sent events: the watcher's pool events and alerts, live.
+//!
+//! `GET /events` streams every [`PoolEvent`] of the server's refresh loop and
+//! every [`Alert`] its rules fire (see [`Server::with_alerts`](super::Server)).
+//! With [`Server::with_order_flow`](super::Server), an `order_flow` event per
+//! pair carries the [`OrderImbalance`] of its pending orders each poll.
+//! Each SSE event is named after its kind (`pool_created`, `pool_updated`,
+//! `config_reloaded`, `alert`, `order_flow`) and pool events and alerts carry
+//! the same JSON as `watch --ndjson`, so a browser `EventSource` can subscribe
+//! per kind. A client too slow to
+//! keep up gets a `lagged` event with the number of events it missed and
+//! should reload `/pools`.
+
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::stream::{self, Stream};
+use serde::Serialize;
+use std::convert::Infallible;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::analytics::OrderImbalance;
+use crate::watcher::{Alert, PoolEvent};
+
+/// Events buffered per subscriber before it lags.
+pub const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum StreamEvent {
+    Pool(PoolEvent),
+    Alert(Alert),
+    OrderFlow(OrderImbalance),
+}
+
+impl StreamEvent {
+    /// SSE event name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamEvent::Pool(PoolEvent::PoolCreated { .. }) => "pool_created",
+            StreamEvent::Pool(PoolEvent::PoolUpdated { .. }) => "pool_updated",
+            StreamEvent::Pool(PoolEvent::ConfigReloaded { .. }) => "config_reloaded",
+            StreamEvent::Alert(_) => "alert",
+            StreamEvent::OrderFlow(_) => "order_flow",
+        }
+    }
+
+    fn to_sse(&self) -> Event {
+        match Event::default().event(self.name()).json_data(self) {
+            Ok(event) => event,
+            Err(e) => Event::default().event("error").data(e.to_string()),
+        }
+    }
+}
+
+/// SSE response following `events` from now on.
+pub fn stream(
+    events: broadcast::Receiver<StreamEvent>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = stream::unfold(events, |mut events| async move {
+        let event = match events.recv().await {
+            Ok(event) => event.to_sse(),
+            Err(RecvError::Lagged(missed)) => {
+                Event::default().event("lagged").data(missed.to_string())
+            }
+            Err(RecvError::Closed) => return None,
+        };
+        Some((Ok(event), events))
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{LiquidityPool, Token};
+    use crate::watcher::AlertRule;
+
+    #[test]
+    fn test_event_names_and_payloads() {
+        let pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            Token::Lovelace,
+            1,
+            2,
+            "addr",
+            0.3,
+            "pool",
+        );
+        let created = StreamEvent::Pool(PoolEvent::PoolCreated {
+            pool: Box::new(pool),
+        });
+        assert_eq!(created.name(), "pool_created");
+        let json = serde_json::to_value(&created).unwrap();
+        assert_eq!(json["event"], "pool_created");
+        assert_eq!(json["pool"]["pool_id"], "pool");
+
+        let alert = StreamEvent::Alert(Alert {
+            rule: AlertRule::FeeChanged,
+            dex_identifier: "MinswapV2".to_string(),
+            pool_id: "pool".to_string(),
+            pair: "ADA/ADA".to_string(),
+            message: "fee changed 0.3% -> 1%".to_string(),
+            previous_reserves: (1, 2),
+            current_reserves: (1, 2),
+            arbitrage: None,
+            deviation: None,
+            supply: None,
+        });
+        assert_eq!(alert.name(), "alert");
+        assert_eq!(
+            serde_json::to_value(&alert).unwrap()["rule"]["rule"],
+            "fee_changed"
+        );
+
+        let flow = StreamEvent::OrderFlow(OrderImbalance {
+            token_a: "lovelace".to_string(),
+            token_b: "token".to_string(),
+            buy_orders: 1,
+            sell_orders: 0,
+            buy_notional: 5,
+            sell_notional: 0,
+            imbalance: 1.0,
+        });
+        assert_eq!(flow.name(), "order_flow");
+        assert_eq!(serde_json::to_value(&flow).unwrap()["imbalance"], 1.0);
+    }
+}
@@ -0,0 +1,490 @@
+//! GraphQL endpoint (`graphql` feature) over the server's pools.
+//!
+//! `POST /graphql` takes a standard `{"query", "variables", "operationName"}`
+//! body. Queries select only the fields they need:
+//!
+//! - `pools(dex, assetA, assetB, offset, limit)` and `pool(dex, poolId)`: the
+//!   refreshed pool store, filtered by DEX and by one asset or a pair
+//! - `quote(tokenIn, tokenOut, amount)`: every single-pool route of the pair
+//!   and the best (split) route, as `quote` prints
+//! - `stablePool(address, assetA, assetB)`: a Minswap stable pool read from
+//!   Kupo on request
+//! - `orderBook(token)`: ChadSwap's book for a token, fetched on request
+//!
+//! Assets are `lovelace`/`ADA` or policy id + asset name hex, dotted or not.
+//! Token amounts are decimal strings, as in `/pools`. The schema is served as
+//! SDL at `GET /graphql/schema.graphql`.
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Result, Schema};
+
+use super::query::asset_key;
+use super::SharedState;
+use crate::dex::chadswap::ChadSwap;
+use crate::dex::minswap_stable::MinswapStable;
+use crate::models::asset::{from_identifier, token_identifier, token_name};
+use crate::models::{self, LiquidityPool, Token};
+use crate::router::{self, Router};
+
+/// Deepest query nesting accepted.
+const MAX_DEPTH: usize = 8;
+
+pub type PoolSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// The schema; each request needs the server state as data.
+pub fn schema() -> PoolSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(ChadSwap::new())
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+fn token(asset: &str) -> Token {
+    from_identifier(&asset_key(asset), 0)
+}
+
+fn amount(value: &str) -> Result<u64> {
+    value
+        .parse()
+        .map_err(|_| format!("amount must be base units, got '{}'", value).into())
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Pools sorted by DEX and pool id. With `assetA` only, pools holding that
+    /// asset; with both assets, pools of the pair.
+    async fn pools(
+        &self,
+        ctx: &Context<'_>,
+        dex: Option<String>,
+        asset_a: Option<String>,
+        asset_b: Option<String>,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Pool>> {
+        let state = ctx.data::<SharedState>()?;
+        let store = state.store.read().unwrap();
+        let candidates: Vec<&LiquidityPool> = match (&asset_a, &asset_b) {
+            (Some(a), Some(b)) => store.pair(&asset_key(a), &asset_key(b)),
+            (Some(asset), None) | (None, Some(asset)) => store.by_asset(&asset_key(asset)),
+            (None, None) => store.pools().collect(),
+        };
+        let mut matching: Vec<&LiquidityPool> = candidates
+            .into_iter()
+            .filter(|pool| dex.as_ref().is_none_or(|dex| &pool.dex_identifier == dex))
+            .collect();
+        matching
+            .sort_by(|a, b| (&a.dex_identifier, &a.pool_id).cmp(&(&b.dex_identifier, &b.pool_id)));
+        Ok(matching
+            .into_iter()
+            .skip(offset.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|pool| Pool(pool.clone()))
+            .collect())
+    }
+
+    async fn pool(&self, ctx: &Context<'_>, dex: String, pool_id: String) -> Result<Option<Pool>> {
+        let state = ctx.data::<SharedState>()?;
+        let store = state.store.read().unwrap();
+        Ok(store.get(&dex, &pool_id).cloned().map(Pool))
+    }
+
+    /// Routes for swapping `amount` base units of `tokenIn` into `tokenOut`
+    /// over the pools of the pair.
+    async fn quote(
+        &self,
+        ctx: &Context<'_>,
+        token_in: String,
+        token_out: String,
+        amount: String,
+    ) -> Result<Quote> {
+        let amount_in = self::amount(&amount)?;
+        let state = ctx.data::<SharedState>()?;
+        let (token_in, token_out) = (token(&token_in), token(&token_out));
+        let pools: Vec<LiquidityPool> = state
+            .store
+            .read()
+            .unwrap()
+            .pair(&token_identifier(&token_in), &token_identifier(&token_out))
+            .into_iter()
+            .cloned()
+            .collect();
+        let router = Router::new().with_pools(pools);
+        Ok(Quote {
+            routes: router
+                .pool_routes(&token_in, &token_out, amount_in)
+                .into_iter()
+                .map(Route)
+                .collect(),
+            best_route: router
+                .split_route(&token_in, &token_out, amount_in)
+                .map(Route),
+        })
+    }
+
+    /// A Minswap stable pool, read from Kupo now.
+    async fn stable_pool(
+        &self,
+        ctx: &Context<'_>,
+        address: String,
+        asset_a: String,
+        asset_b: String,
+        #[graphql(default = 6)] decimals_a: u8,
+        #[graphql(default = 6)] decimals_b: u8,
+    ) -> Result<StablePool> {
+        let state = ctx.data::<SharedState>()?;
+        let pool = MinswapStable::new(state.kupo.clone())
+            .get_pool(
+                &address,
+                &asset_key(&asset_a),
+                &asset_key(&asset_b),
+                decimals_a,
+                decimals_b,
+            )
+            .await?;
+        Ok(StablePool(pool))
+    }
+
+    /// ChadSwap's order book for `token`, fetched now.
+    async fn order_book(&self, ctx: &Context<'_>, token: String) -> Result<OrderBook> {
+        let chadswap = ctx.data::<ChadSwap>()?;
+        Ok(OrderBook(
+            chadswap.get_orders_by_token(&asset_key(&token)).await?,
+        ))
+    }
+}
+
+/// An AMM pool.
+pub struct Pool(LiquidityPool);
+
+#[Object]
+impl Pool {
+    async fn dex(&self) -> &str {
+        &self.0.dex_identifier
+    }
+
+    async fn pool_id(&self) -> &str {
+        &self.0.pool_id
+    }
+
+    async fn address(&self) -> &str {
+        &self.0.address
+    }
+
+    async fn asset_a(&self) -> String {
+        token_identifier(&self.0.asset_a)
+    }
+
+    async fn asset_b(&self) -> String {
+        token_identifier(&self.0.asset_b)
+    }
+
+    /// Ticker pair, e.g. `ADA/SNEK`.
+    async fn pair(&self) -> String {
+        self.0.pair()
+    }
+
+    async fn reserve_a(&self) -> String {
+        self.0.reserve_a.to_string()
+    }
+
+    async fn reserve_b(&self) -> String {
+        self.0.reserve_b.to_string()
+    }
+
+    async fn total_lp_tokens(&self) -> String {
+        self.0.total_lp_tokens.to_string()
+    }
+
+    async fn fee_percent(&self) -> f64 {
+        self.0.pool_fee_percent
+    }
+
+    /// Decimal-adjusted price of asset B in asset A.
+    async fn price(&self) -> f64 {
+        self.0.price()
+    }
+
+    async fn tx_hash(&self) -> Option<&str> {
+        self.0.metadata.freshness.as_ref().map(|f| f.tx_hash.as_str())
+    }
+
+    async fn slot(&self) -> Option<u64> {
+        self.0.metadata.freshness.as_ref().and_then(|f| f.slot)
+    }
+
+    /// Slot the pool was created in, when resolved.
+    async fn created_slot(&self) -> Option<u64> {
+        self.0.metadata.created_slot
+    }
+}
+
+/// A Curve-style stable pool.
+pub struct StablePool(models::StablePool);
+
+#[Object]
+impl StablePool {
+    async fn dex(&self) -> &str {
+        &self.0.dex_identifier
+    }
+
+    async fn pool_id(&self) -> &str {
+        &self.0.pool_id
+    }
+
+    async fn asset_a(&self) -> String {
+        token_identifier(self.0.asset_a())
+    }
+
+    async fn asset_b(&self) -> String {
+        token_identifier(self.0.asset_b())
+    }
+
+    async fn pair(&self) -> String {
+        self.0.pair()
+    }
+
+    async fn reserve_a(&self) -> String {
+        self.0.reserve_a().to_string()
+    }
+
+    async fn reserve_b(&self) -> String {
+        self.0.reserve_b().to_string()
+    }
+
+    /// Every asset with its balance, in datum order; pools may hold more
+    /// than the two above.
+    async fn assets(&self) -> Vec<StableAsset> {
+        self.0
+            .assets()
+            .iter()
+            .map(|(token, reserve)| StableAsset {
+                asset: token_identifier(token),
+                reserve: reserve.to_string(),
+            })
+            .collect()
+    }
+
+    async fn fee_percent(&self) -> f64 {
+        self.0.pool_fee_percent
+    }
+
+    async fn amplification_coefficient(&self) -> String {
+        self.0.amplification_coefficient.to_string()
+    }
+
+    async fn total_liquidity(&self) -> String {
+        self.0.total_liquidity.to_string()
+    }
+
+    async fn price(&self) -> f64 {
+        self.0.price()
+    }
+}
+
+#[derive(async_graphql::SimpleObject)]
+pub struct StableAsset {
+    asset: String,
+    reserve: String,
+}
+
+/// Open ChadSwap orders for one token, best price first.
+pub struct OrderBook(models::OrderBook);
+
+#[Object]
+impl OrderBook {
+    async fn token_id(&self) -> &str {
+        &self.0.token_id
+    }
+
+    async fn token(&self) -> String {
+        token_name(&from_identifier(&self.0.token_id, 0))
+    }
+
+    async fn bids(&self, limit: Option<usize>) -> Vec<Order> {
+        orders(&self.0.buy_orders, limit)
+    }
+
+    async fn asks(&self, limit: Option<usize>) -> Vec<Order> {
+        orders(&self.0.sell_orders, limit)
+    }
+
+    async fn best_bid(&self) -> Option<Order> {
+        self.0.best_bid().cloned().map(Order)
+    }
+
+    async fn best_ask(&self) -> Option<Order> {
+        self.0.best_ask().cloned().map(Order)
+    }
+}
+
+fn orders(orders: &[models::Order], limit: Option<usize>) -> Vec<Order> {
+    orders
+        .iter()
+        .take(limit.unwrap_or(usize::MAX))
+        .cloned()
+        .map(Order)
+        .collect()
+}
+
+pub struct Order(models::Order);
+
+#[Object]
+impl Order {
+    /// Remaining tokens, in base units.
+    async fn amount(&self) -> String {
+        self.0.amount.to_string()
+    }
+
+    /// Lovelace per token base unit.
+    async fn price(&self) -> f64 {
+        self.0.effective_price()
+    }
+
+    async fn lovelace_value(&self) -> String {
+        self.0.lovelace_value().to_string()
+    }
+
+    async fn output_ref(&self) -> &str {
+        &self.0.output_ref
+    }
+
+    async fn owner(&self) -> Option<&str> {
+        self.0.owner.as_deref()
+    }
+
+    /// Unix seconds.
+    async fn created_at(&self) -> Option<u64> {
+        self.0.created_at
+    }
+}
+
+#[derive(async_graphql::SimpleObject)]
+pub struct Quote {
+    /// One route per pool of the pair, best net output first.
+    routes: Vec<Route>,
+    /// The order split across pools where that beats the best single pool.
+    best_route: Option<Route>,
+}
+
+pub struct Route(router::Route);
+
+#[Object]
+impl Route {
+    async fn amount_in(&self) -> String {
+        self.0.amount_in.to_string()
+    }
+
+    async fn amount_out(&self) -> String {
+        self.0.amount_out.to_string()
+    }
+
+    /// `amountOut` less batcher fees, priced in the output token.
+    async fn net_amount_out(&self) -> String {
+        self.0.net_amount_out.to_string()
+    }
+
+    async fn fee_lovelace(&self) -> String {
+        self.0.costs.fee_lovelace.to_string()
+    }
+
+    async fn deposit_lovelace(&self) -> String {
+        self.0.costs.deposit_lovelace.to_string()
+    }
+
+    async fn allocations(&self) -> Vec<Allocation> {
+        self.0.allocations.iter().cloned().map(Allocation).collect()
+    }
+}
+
+pub struct Allocation(router::Allocation);
+
+#[Object]
+impl Allocation {
+    async fn dex(&self) -> &str {
+        &self.0.dex_identifier
+    }
+
+    async fn pool_id(&self) -> &str {
+        &self.0.pool_id
+    }
+
+    async fn amount_in(&self) -> String {
+        self.0.amount_in.to_string()
+    }
+
+    async fn amount_out(&self) -> String {
+        self.0.amount_out.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kupo::KupoApi;
+    use crate::server::{AppState, ReadinessConfig};
+    use crate::watcher::PoolWatcher;
+    use std::sync::Arc;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    #[tokio::test]
+    async fn test_pools_and_quote_queries() {
+        let snek = from_identifier(SNEK, 0);
+        let state = AppState::new(
+            KupoApi::new("http://127.0.0.1:9"),
+            PoolWatcher::new(Vec::new()),
+            ReadinessConfig::default(),
+        );
+        state.store.write().unwrap().extend([
+            LiquidityPool::new(
+                "MinswapV2",
+                Token::Lovelace,
+                snek.clone(),
+                1_000_000_000,
+                5_000_000,
+                "addr",
+                0.3,
+                "m",
+            ),
+            LiquidityPool::new(
+                "SundaeSwapV3",
+                Token::Lovelace,
+                snek,
+                500_000_000,
+                2_600_000,
+                "addr",
+                0.3,
+                "s",
+            ),
+        ]);
+        let state: SharedState = Arc::new(state);
+        let query = format!(
+            r#"{{
+                pools(assetA: "ADA", assetB: "{}", limit: 1) {{ dex poolId reserveA }}
+                quote(tokenIn: "lovelace", tokenOut: "{}", amount: "10000000") {{
+                    routes {{ allocations {{ poolId }} }}
+                    bestRoute {{ amountOut }}
+                }}
+            }}"#,
+            SNEK, SNEK
+        );
+        let response = schema()
+            .execute(async_graphql::Request::new(query).data(state))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(
+            data["pools"],
+            serde_json::json!([{ "dex": "MinswapV2", "poolId": "m", "reserveA": "1000000000" }])
+        );
+        assert_eq!(data["quote"]["routes"].as_array().unwrap().len(), 2);
+        assert!(data["quote"]["bestRoute"]["amountOut"].is_string());
+
+        let bad = schema()
+            .execute(r#"{ quote(tokenIn: "lovelace", tokenOut: "lovelace", amount: "x") { routes { amountOut } } }"#)
+            .await;
+        assert!(bad.errors[0].message.contains("base units"));
+    }
+}
@@ -0,0 +1,131 @@
+//! Liveness and readiness probes.
+//!
+//! `/healthz` only says the process is serving. `/readyz` says whether the
+//! pool data behind the API can be trusted: Kupo answers its health endpoint,
+//! is connected to its node and within [`ReadinessConfig::max_sync_lag`]
+//! slots of the tip, and the pool store was fully refreshed within
+//! [`ReadinessConfig::max_staleness`]. It answers 503 with the reasons
+//! otherwise, so Kubernetes stops routing to a replica with stale data.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::kupo::KupoSyncStatus;
+
+/// Default oldest acceptable pool store.
+pub const DEFAULT_MAX_STALENESS: Duration = Duration::from_secs(120);
+/// Default slots Kupo may trail the node tip (about two minutes).
+pub const DEFAULT_MAX_SYNC_LAG: u64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadinessConfig {
+    pub max_staleness: Duration,
+    pub max_sync_lag: u64,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            max_staleness: DEFAULT_MAX_STALENESS,
+            max_sync_lag: DEFAULT_MAX_SYNC_LAG,
+        }
+    }
+}
+
+/// Body of `/readyz`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    /// Why the server is not ready; empty when it is.
+    pub reasons: Vec<String>,
+    pub kupo: Option<KupoSyncStatus>,
+    /// Seconds since the last complete refresh; `None` before the first.
+    pub store_age_secs: Option<u64>,
+    pub pools: usize,
+    /// DEXes that failed in the last refresh.
+    pub failed_dexes: Vec<String>,
+}
+
+/// Readiness from Kupo's sync status and the store's last complete refresh
+/// (unix seconds).
+pub fn readiness(
+    config: &ReadinessConfig,
+    kupo: Result<KupoSyncStatus>,
+    refreshed_at: Option<u64>,
+    pools: usize,
+    failed_dexes: Vec<String>,
+    now: u64,
+) -> Readiness {
+    let mut reasons = Vec::new();
+    let kupo = match kupo {
+        Ok(status) => {
+            if !status.connected {
+                reasons.push("kupo is not connected to its node".to_string());
+            }
+            if status.lag() > config.max_sync_lag {
+                reasons.push(format!(
+                    "kupo is {} slots behind the node tip (max {})",
+                    status.lag(),
+                    config.max_sync_lag
+                ));
+            }
+            Some(status)
+        }
+        Err(e) => {
+            reasons.push(format!("kupo unreachable: {}", e));
+            None
+        }
+    };
+    let store_age_secs = refreshed_at.map(|at| now.saturating_sub(at));
+    match store_age_secs {
+        None => reasons.push("pool store not refreshed yet".to_string()),
+        Some(age) if age > config.max_staleness.as_secs() => reasons.push(format!(
+            "pool store last refreshed {}s ago (max {}s)",
+            age,
+            config.max_staleness.as_secs()
+        )),
+        Some(_) => {}
+    }
+    Readiness {
+        ready: reasons.is_empty(),
+        reasons,
+        kupo,
+        store_age_secs,
+        pools,
+        failed_dexes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn synced(checkpoint: u64) -> Result<KupoSyncStatus> {
+        Ok(KupoSyncStatus {
+            connected: true,
+            most_recent_checkpoint: checkpoint,
+            most_recent_node_tip: Some(1_000),
+        })
+    }
+
+    #[test]
+    fn test_readiness_reasons() {
+        let config = ReadinessConfig::default();
+        let ready = readiness(&config, synced(990), Some(100), 5, Vec::new(), 150);
+        assert!(ready.ready && ready.reasons.is_empty());
+        assert_eq!(ready.store_age_secs, Some(50));
+
+        let lagging = readiness(&config, synced(500), Some(100), 5, Vec::new(), 150);
+        assert_eq!(lagging.reasons.len(), 1);
+        assert!(lagging.reasons[0].contains("500 slots behind"));
+
+        let down = readiness(&config, Err(anyhow!("refused")), None, 0, Vec::new(), 150);
+        assert!(!down.ready);
+        assert_eq!(down.reasons.len(), 2);
+
+        let stale = readiness(&config, synced(1_000), Some(0), 5, Vec::new(), 500);
+        assert!(stale.reasons[0].starts_with("pool store last refreshed 500s ago"));
+    }
+}
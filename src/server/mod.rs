@@ -0,0 +1,513 @@
+//! HTTP server mode: a pool API kept fresh by a [`PoolWatcher`].
+//!
+//! [`Server`] polls its DEXes on an interval into a shared [`PoolStore`] and
+//! serves it as JSON:
+//!
+//! - `GET /pools`: pools as [`PoolExport`] rows sorted by DEX and pool id,
+//!   filtered and paged by [`PoolQuery`]
+//! - `GET /pools/{dex}/{pool_id}`: one pool
+//! - `GET /events`: server-sent [`events`] for pool updates, new pools,
+//!   alerts and pending order flow
+//! - `GET /healthz`: the process is up
+//! - `GET /readyz`: Kupo is reachable and synced and the store is fresh
+//!   (see [`health`])
+//! - `GET /openapi.json`: the [`openapi`] document, and with
+//!   [`Server::with_swagger_ui`] a Swagger UI at `GET /docs`
+//!
+//! With the `graphql` feature, `POST /graphql` answers [`graphql`] queries over
+//! pools, stable pools, order books and quotes.
+//!
+//! [`Server::with_access`] puts everything but the probes behind API keys with
+//! per-key quotas (see [`auth`]).
+//!
+//! Pool responses are served from the store and carry an `ETag`, honour
+//! `If-None-Match` and report the store's `Age` in seconds (see [`query`]).
+//! With [`Server::with_dump_dir`], `SIGUSR1` or `POST /admin/dump` writes the
+//! server's state to disk for debugging (see [`dump`]); the endpoint only
+//! answers loopback clients or the admin token.
+//!
+//! `?max_age=<secs>` makes a request run a refresh round against Kupo first
+//! when the last one started longer ago, for consumers that need fresher data
+//! than the interval gives (`max_age=0` always refetches); concurrent
+//! requests share one round.
+//!
+//! Requires the `server` feature.
+
+pub mod auth;
+pub mod dump;
+pub mod events;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod health;
+pub mod openapi;
+pub mod query;
+
+pub use auth::AccessConfig;
+pub use events::StreamEvent;
+pub use health::{Readiness, ReadinessConfig};
+pub use query::{MaxAge, PoolQuery};
+
+use anyhow::Result;
+use axum::extract::{Path, Query, State};
+use axum::http::header::AGE;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+use crate::analytics::order_imbalances;
+use crate::dex::BaseDex;
+use crate::export::pool_to_export;
+use crate::kupo::KupoApi;
+use crate::models::LiquidityPool;
+use crate::oracles::OracleReader;
+use crate::orderflow::OrderFlow;
+use crate::store::PoolStore;
+use crate::watcher::{AlertRule, Alerter, ConfigReload, PoolWatcher, SupplyTracker};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(20);
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Default)]
+struct RefreshStatus {
+    /// Unix seconds of the last round in which every DEX was fetched.
+    refreshed_at: Option<u64>,
+    /// Start of the last round that fetched, complete or not.
+    round_started: Option<Instant>,
+    failed_dexes: Vec<String>,
+}
+
+struct AppState {
+    kupo: KupoApi,
+    /// Held for a whole refresh round, so forced and scheduled rounds never
+    /// overlap.
+    watcher: tokio::sync::Mutex<PoolWatcher>,
+    alerter: Option<Alerter>,
+    readiness: ReadinessConfig,
+    store: RwLock<PoolStore>,
+    refresh: RwLock<RefreshStatus>,
+    events: broadcast::Sender<StreamEvent>,
+    access: Option<auth::Access>,
+    dump_dir: Option<PathBuf>,
+    dump_keep: usize,
+    #[cfg(feature = "graphql")]
+    graphql: graphql::PoolSchema,
+}
+
+impl AppState {
+    fn new(kupo: KupoApi, watcher: PoolWatcher, readiness: ReadinessConfig) -> Self {
+        Self {
+            kupo,
+            watcher: tokio::sync::Mutex::new(watcher),
+            alerter: None,
+            readiness,
+            store: RwLock::new(PoolStore::new()),
+            refresh: RwLock::new(RefreshStatus::default()),
+            events: broadcast::channel(events::CHANNEL_CAPACITY).0,
+            access: None,
+            dump_dir: None,
+            dump_keep: dump::DEFAULT_KEEP,
+            #[cfg(feature = "graphql")]
+            graphql: graphql::schema(),
+        }
+    }
+}
+
+type SharedState = Arc<AppState>;
+
+pub struct Server {
+    kupo: KupoApi,
+    watcher: PoolWatcher,
+    interval: Duration,
+    readiness: ReadinessConfig,
+    alerter: Option<Alerter>,
+    access: Option<AccessConfig>,
+    dump_dir: Option<PathBuf>,
+    dump_keep: usize,
+    swagger_ui: bool,
+    order_flow: Option<Duration>,
+    oracles: Option<OracleReader>,
+}
+
+impl Server {
+    pub fn new(kupo: &KupoApi, dexes: Vec<Arc<dyn BaseDex>>) -> Self {
+        Self {
+            kupo: kupo.clone(),
+            watcher: PoolWatcher::new(dexes),
+            interval: DEFAULT_INTERVAL,
+            readiness: ReadinessConfig::default(),
+            alerter: None,
+            access: None,
+            dump_dir: None,
+            dump_keep: dump::DEFAULT_KEEP,
+            swagger_ui: false,
+            order_flow: None,
+            oracles: None,
+        }
+    }
+
+    /// Time between pool refreshes.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// `/readyz` fails once the last complete refresh is older than this.
+    pub fn with_max_staleness(mut self, max_staleness: Duration) -> Self {
+        self.readiness.max_staleness = max_staleness;
+        self
+    }
+
+    /// `/readyz` fails while Kupo trails the node tip by more than `slots`.
+    pub fn with_max_sync_lag(mut self, slots: u64) -> Self {
+        self.readiness.max_sync_lag = slots;
+        self
+    }
+
+    /// Rebuild the DEX set when `reload`'s file changes (see
+    /// [`PoolWatcher::with_config_reload`]).
+    pub fn with_config_reload(mut self, reload: ConfigReload) -> Self {
+        self.watcher = self.watcher.with_config_reload(reload);
+        self
+    }
+
+    /// Stream an `alert` event on `/events` whenever one of `rules` fires.
+    pub fn with_alerts(mut self, rules: Vec<AlertRule>) -> Self {
+        self.alerter = Some(Alerter::new(rules));
+        self
+    }
+
+    /// Reference prices for `oracle_deviation` alert rules, re-read at most
+    /// once a minute before a round's alerts are evaluated.
+    pub fn with_oracles(mut self, reader: OracleReader) -> Self {
+        self.oracles = Some(reader);
+        self
+    }
+
+    /// Require one of `config`'s API keys, within its quota, on every
+    /// endpoint but `/healthz` and `/readyz`.
+    pub fn with_access(mut self, config: AccessConfig) -> Self {
+        self.access = Some(config);
+        self
+    }
+
+    /// Dump the server's state into `dir` on `SIGUSR1` or `POST /admin/dump`.
+    pub fn with_dump_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dump_dir = Some(dir.into());
+        self
+    }
+
+    /// Keep only the newest `keep` dumps (at least one) in the dump dir;
+    /// [`dump::DEFAULT_KEEP`] by default.
+    pub fn with_dump_retention(mut self, keep: usize) -> Self {
+        self.dump_keep = keep;
+        self
+    }
+
+    /// Poll the DEXes' order contracts every `interval` and stream an
+    /// `order_flow` event per pair with pending orders (see [`OrderFlow`]).
+    pub fn with_order_flow(mut self, interval: Duration) -> Self {
+        self.order_flow = Some(interval);
+        self
+    }
+
+    /// Also serve a Swagger UI for the OpenAPI document at `/docs`.
+    pub fn with_swagger_ui(mut self) -> Self {
+        self.swagger_ui = true;
+        self
+    }
+
+    /// Refresh pools in the background and serve the API on `addr` until the
+    /// listener fails.
+    pub async fn serve(self, addr: &str) -> Result<()> {
+        let mut state = AppState::new(self.kupo, self.watcher, self.readiness);
+        state.alerter = match (self.alerter, self.oracles) {
+            (Some(alerter), Some(oracles)) => Some(alerter.with_oracles(oracles)),
+            (alerter, _) => alerter,
+        }
+        .map(|alerter| alerter.with_supply_tracker(SupplyTracker::new(&state.kupo)));
+        state.access = self.access.map(auth::Access::new);
+        state.dump_dir = self.dump_dir;
+        state.dump_keep = self.dump_keep;
+        let state = Arc::new(state);
+        tokio::spawn(refresh(self.interval, state.clone()));
+        if let Some(interval) = self.order_flow {
+            tokio::spawn(order_flow(interval, state.clone()));
+        }
+        #[cfg(unix)]
+        if state.dump_dir.is_some() {
+            tokio::spawn(dump::on_sigusr1(state.clone()));
+        }
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let app = router(state, self.swagger_ui);
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+fn router(state: SharedState, swagger_ui: bool) -> Router {
+    let mut router = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/pools", get(pools))
+        .route("/pools/{dex}/{pool_id}", get(pool))
+        .route("/events", get(stream_events))
+        .route("/openapi.json", get(openapi_json));
+    #[cfg(feature = "graphql")]
+    {
+        router = router
+            .route("/graphql", axum::routing::post(graphql_query))
+            .route("/graphql/schema.graphql", get(graphql_sdl));
+    }
+    if state.dump_dir.is_some() {
+        router = router.route("/admin/dump", axum::routing::post(admin_dump));
+    }
+    if swagger_ui {
+        router = router.route("/docs", get(|| async { Html(openapi::SWAGGER_UI) }));
+    }
+    router
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            auth::authorize,
+        ))
+        .with_state(state)
+}
+
+/// Refresh forever, one round per `interval`.
+async fn refresh(interval: Duration, state: SharedState) {
+    loop {
+        refresh_round(&state).await;
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Poll every DEX once, replace the store and publish the round's events (and
+/// the alerts they fire) to `/events` subscribers.
+async fn refresh_round(state: &AppState) {
+    let mut watcher = state.watcher.lock().await;
+    refresh_locked(state, &mut watcher).await;
+}
+
+async fn refresh_locked(state: &AppState, watcher: &mut PoolWatcher) {
+    let started = Instant::now();
+    match watcher.poll().await {
+        Ok(round) => {
+            if let Some(alerter) = &state.alerter {
+                alerter.refresh_reference_prices().await;
+            }
+            let mut store = PoolStore::new();
+            store.extend(watcher.pools().cloned());
+            *state.store.write().unwrap() = store;
+            let mut status = state.refresh.write().unwrap();
+            if watcher.failed_dexes().is_empty() {
+                status.refreshed_at = Some(now_secs());
+            }
+            status.round_started = Some(started);
+            status.failed_dexes = watcher.failed_dexes().to_vec();
+            drop(status);
+            for event in round {
+                let alerts = state.alerter.as_ref().map(|a| a.evaluate(&event));
+                // Sending only fails while nobody is subscribed.
+                let _ = state.events.send(StreamEvent::Pool(event));
+                for alert in alerts.into_iter().flatten() {
+                    let _ = state.events.send(StreamEvent::Alert(alert));
+                }
+            }
+        }
+        Err(e) => eprintln!("[server] refresh failed: {}", e),
+    }
+    if let Some(alerter) = &state.alerter {
+        for alert in alerter.check_supply().await {
+            let _ = state.events.send(StreamEvent::Alert(alert));
+        }
+    }
+}
+
+/// Publish the imbalance of each pair's pending orders, one poll per
+/// `interval`. Orders are placed against the store's pools.
+async fn order_flow(interval: Duration, state: SharedState) {
+    let flow = OrderFlow::new(&state.kupo);
+    loop {
+        let orders = flow.pending_orders().await;
+        let pools: Vec<LiquidityPool> = state.store.read().unwrap().pools().cloned().collect();
+        for imbalance in order_imbalances(&orders, &pools) {
+            let _ = state.events.send(StreamEvent::OrderFlow(imbalance));
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Seconds since the last complete refresh; `None` before the first.
+fn store_age(state: &AppState) -> Option<u64> {
+    let refreshed_at = state.refresh.read().unwrap().refreshed_at;
+    refreshed_at.map(|at| now_secs().saturating_sub(at))
+}
+
+/// Run a refresh round first unless the last one started at most `max_age`
+/// seconds before the request. Requests queued behind a round that started
+/// after them reuse it, so `max_age=0` bursts cost one round.
+async fn ensure_fresh(state: &AppState, max_age: Option<u64>) {
+    let Some(max_age) = max_age.map(Duration::from_secs) else {
+        return;
+    };
+    let arrived = Instant::now();
+    let stale = || {
+        let started = state.refresh.read().unwrap().round_started;
+        started.is_none_or(|started| arrived.saturating_duration_since(started) > max_age)
+    };
+    if !stale() {
+        return;
+    }
+    let mut watcher = state.watcher.lock().await;
+    if stale() {
+        refresh_locked(state, &mut watcher).await;
+    }
+}
+
+/// Adds the store's `Age` to a pool response.
+fn with_age(state: &AppState, mut response: Response) -> Response {
+    if let Some(age) = store_age(state) {
+        response.headers_mut().insert(AGE, HeaderValue::from(age));
+    }
+    response
+}
+
+async fn healthz() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+async fn stream_events(State(state): State<SharedState>) -> Response {
+    events::stream(state.events.subscribe()).into_response()
+}
+
+#[cfg(feature = "graphql")]
+async fn graphql_query(
+    State(state): State<SharedState>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let request = request.data(state.clone());
+    Json(state.graphql.execute(request).await)
+}
+
+#[cfg(feature = "graphql")]
+async fn graphql_sdl(State(state): State<SharedState>) -> String {
+    state.graphql.sdl()
+}
+
+async fn admin_dump(State(state): State<SharedState>) -> Response {
+    match dump::dump(&state).await {
+        Ok(path) => Json(serde_json::json!({ "path": path })).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+async fn openapi_json() -> Json<serde_json::Value> {
+    Json(openapi::document())
+}
+
+async fn readyz(State(state): State<SharedState>) -> (StatusCode, Json<Readiness>) {
+    let kupo = state.kupo.sync_status().await;
+    let (refreshed_at, failed_dexes) = {
+        let status = state.refresh.read().unwrap();
+        (status.refreshed_at, status.failed_dexes.clone())
+    };
+    let pools = state.store.read().unwrap().len();
+    let readiness = health::readiness(
+        &state.readiness,
+        kupo,
+        refreshed_at,
+        pools,
+        failed_dexes,
+        now_secs(),
+    );
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
+
+async fn pools(
+    State(state): State<SharedState>,
+    Query(params): Query<PoolQuery>,
+    headers: HeaderMap,
+) -> Response {
+    ensure_fresh(&state, params.max_age).await;
+    let page = query::select(&state.store.read().unwrap(), &params);
+    let mut response = query::conditional_json(&headers, &page.pools);
+    response
+        .headers_mut()
+        .insert("x-total-count", HeaderValue::from(page.total));
+    with_age(&state, response)
+}
+
+async fn pool(
+    State(state): State<SharedState>,
+    Path((dex, pool_id)): Path<(String, String)>,
+    Query(freshness): Query<MaxAge>,
+    headers: HeaderMap,
+) -> Response {
+    ensure_fresh(&state, freshness.max_age).await;
+    let row = state
+        .store
+        .read()
+        .unwrap()
+        .get(&dex, &pool_id)
+        .map(pool_to_export);
+    match row {
+        Some(row) => with_age(&state, query::conditional_json(&headers, &row)),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": format!("Unknown pool {}:{}", dex, pool_id) })),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_max_age_forces_one_round() {
+        let state = AppState::new(
+            KupoApi::new("http://127.0.0.1:9"),
+            PoolWatcher::new(Vec::new()),
+            ReadinessConfig::default(),
+        );
+        let started = || state.refresh.read().unwrap().round_started;
+
+        ensure_fresh(&state, None).await;
+        assert!(started().is_none());
+
+        ensure_fresh(&state, Some(60)).await;
+        let first = started().unwrap();
+        assert!(store_age(&state).is_some());
+
+        ensure_fresh(&state, Some(60)).await;
+        assert_eq!(started(), Some(first));
+
+        ensure_fresh(&state, Some(0)).await;
+        assert!(started().unwrap() > first);
+    }
+}
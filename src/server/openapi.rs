@@ -0,0 +1,320 @@
+//! OpenAPI 3.1 description of the server's endpoints, served at
+//! `/openapi.json`, and an optional Swagger UI page rendering it at `/docs`.
+//!
+//! The document is written out by hand next to the routes it describes; keep
+//! the two in step when adding an endpoint.
+
+use serde_json::{json, Value};
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn json_response(description: &str, schema: Value) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": schema } },
+    })
+}
+
+/// A 200 carrying an `ETag`, plus the empty 304 answering a matching
+/// `If-None-Match`.
+fn conditional_responses(description: &str, schema: Value) -> (Value, Value) {
+    let etag = json!({ "description": "Hash of the body", "schema": { "type": "string" } });
+    let age = json!({
+        "description": "Seconds since the last complete refresh",
+        "schema": { "type": "integer" },
+    });
+    let mut ok = json_response(description, schema);
+    ok["headers"] = json!({ "ETag": etag, "Age": age });
+    let not_modified = json!({
+        "description": "Unchanged since the `If-None-Match` ETag",
+        "headers": { "ETag": etag },
+    });
+    (ok, not_modified)
+}
+
+fn query_param(name: &str, schema: Value, description: &str) -> Value {
+    json!({ "name": name, "in": "query", "required": false, "schema": schema, "description": description })
+}
+
+fn max_age() -> Value {
+    query_param(
+        "max_age",
+        json!({ "type": "integer", "minimum": 0 }),
+        "Refresh from Kupo first if the last refresh started more than this many seconds ago; 0 always refreshes",
+    )
+}
+
+fn if_none_match() -> Value {
+    json!({
+        "name": "If-None-Match",
+        "in": "header",
+        "required": false,
+        "schema": { "type": "string" },
+        "description": "ETag of a previous response",
+    })
+}
+
+fn nullable(kind: &str) -> Value {
+    json!({ "type": [kind, "null"] })
+}
+
+/// Amounts are decimal strings, so they survive JavaScript's 53-bit numbers.
+fn amount() -> Value {
+    json!({ "type": "string", "pattern": "^[0-9]+$" })
+}
+
+fn schemas() -> Value {
+    json!({
+        "PoolExport": {
+            "type": "object",
+            "required": [
+                "dex", "pool_id", "asset_a", "asset_b", "reserve_a", "reserve_b",
+                "pool_fee_percent", "total_lp_tokens", "tx_hash",
+            ],
+            "properties": {
+                "dex": { "type": "string", "examples": ["MinswapV2"] },
+                "pool_id": { "type": "string" },
+                "asset_a": {
+                    "type": "string",
+                    "description": "`lovelace` or policy id + asset name hex",
+                },
+                "asset_b": { "type": "string" },
+                "reserve_a": amount(),
+                "reserve_b": amount(),
+                "pool_fee_percent": { "type": "number" },
+                "total_lp_tokens": amount(),
+                "tx_hash": { "type": "string" },
+                "slot": nullable("integer"),
+                "block_hash": nullable("string"),
+                "observed_at": { "type": ["integer", "null"], "description": "Unix seconds" },
+                "protocol_pool_id": { "type": "string" },
+                "extras": { "type": "object", "description": "DEX-specific fields" },
+                "verified": { "type": "boolean" },
+            },
+        },
+        "KupoSyncStatus": {
+            "type": "object",
+            "required": ["connected", "most_recent_checkpoint", "most_recent_node_tip"],
+            "properties": {
+                "connected": { "type": "boolean" },
+                "most_recent_checkpoint": { "type": "integer" },
+                "most_recent_node_tip": nullable("integer"),
+            },
+        },
+        "Readiness": {
+            "type": "object",
+            "required": ["ready", "reasons", "kupo", "store_age_secs", "pools", "failed_dexes"],
+            "properties": {
+                "ready": { "type": "boolean" },
+                "reasons": { "type": "array", "items": { "type": "string" } },
+                "kupo": { "oneOf": [schema_ref("KupoSyncStatus"), { "type": "null" }] },
+                "store_age_secs": nullable("integer"),
+                "pools": { "type": "integer" },
+                "failed_dexes": { "type": "array", "items": { "type": "string" } },
+            },
+        },
+        "Error": {
+            "type": "object",
+            "required": ["error"],
+            "properties": { "error": { "type": "string" } },
+        },
+    })
+}
+
+/// The OpenAPI document of every endpoint.
+pub fn document() -> Value {
+    let (mut pools_ok, pools_not_modified) = conditional_responses(
+        "Pools",
+        json!({ "type": "array", "items": schema_ref("PoolExport") }),
+    );
+    pools_ok["headers"]["X-Total-Count"] = json!({
+        "description": "Matching pools before `offset` and `limit`",
+        "schema": { "type": "integer" },
+    });
+    let (pool_ok, pool_not_modified) = conditional_responses("The pool", schema_ref("PoolExport"));
+    let count = json!({ "type": "integer", "minimum": 0 });
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "dexter-kupo-rs pool API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Cardano DEX liquidity pools indexed through Kupo.",
+        },
+        "paths": {
+            "/pools": {
+                "get": {
+                    "summary": "Pools sorted by DEX and pool id, filtered and paged",
+                    "operationId": "listPools",
+                    "parameters": [
+                        query_param(
+                            "asset",
+                            json!({ "type": "string" }),
+                            "Only pools holding this asset: `lovelace`, or policy id + asset name hex",
+                        ),
+                        query_param("min_tvl", count.clone(), "Only pools worth at least this many lovelace"),
+                        query_param("offset", count.clone(), "Pools to skip"),
+                        query_param("limit", count, "Most pools to return"),
+                        max_age(),
+                        if_none_match(),
+                    ],
+                    "responses": {
+                        "200": pools_ok,
+                        "304": pools_not_modified,
+                        "400": { "description": "Malformed query parameters" },
+                    },
+                },
+            },
+            "/pools/{dex}/{pool_id}": {
+                "get": {
+                    "summary": "One pool",
+                    "operationId": "getPool",
+                    "parameters": [
+                        { "name": "dex", "in": "path", "required": true, "schema": { "type": "string" } },
+                        { "name": "pool_id", "in": "path", "required": true, "schema": { "type": "string" } },
+                        max_age(),
+                        if_none_match(),
+                    ],
+                    "responses": {
+                        "200": pool_ok,
+                        "304": pool_not_modified,
+                        "404": json_response("Unknown pool", schema_ref("Error")),
+                    },
+                },
+            },
+            "/events": {
+                "get": {
+                    "summary": "Server-sent pool_created, pool_updated, config_reloaded, alert and order_flow events",
+                    "operationId": "events",
+                    "responses": {
+                        "200": {
+                            "description": "Event stream; each event's data is the JSON `watch --ndjson` prints, \
+                                `lagged` events carry the number of events a slow client missed",
+                            "content": { "text/event-stream": { "schema": { "type": "string" } } },
+                        },
+                    },
+                },
+            },
+            "/healthz": {
+                "get": {
+                    "summary": "Liveness: the process is serving",
+                    "operationId": "healthz",
+                    "security": [],
+                    "responses": {
+                        "200": json_response(
+                            "Up",
+                            json!({ "type": "object", "properties": { "status": { "const": "ok" } } }),
+                        ),
+                    },
+                },
+            },
+            "/readyz": {
+                "get": {
+                    "summary": "Readiness: Kupo synced and pools fresh",
+                    "operationId": "readyz",
+                    "security": [],
+                    "responses": {
+                        "200": json_response("Ready", schema_ref("Readiness")),
+                        "503": json_response("Not ready; see `reasons`", schema_ref("Readiness")),
+                    },
+                },
+            },
+            "/admin/dump": {
+                "post": {
+                    "summary": "Write the pool store and watcher state to the dump dir (only with --dump-dir)",
+                    "operationId": "dumpState",
+                    "responses": {
+                        "200": json_response(
+                            "Dump written",
+                            json!({ "type": "object", "properties": { "path": { "type": "string" } } }),
+                        ),
+                        "500": json_response("Dump failed", schema_ref("Error")),
+                    },
+                },
+            },
+            "/openapi.json": {
+                "get": {
+                    "summary": "This document",
+                    "operationId": "openapi",
+                    "responses": { "200": json_response("OpenAPI document", json!({ "type": "object" })) },
+                },
+            },
+        },
+        // Keys are only enforced when the server is started with them.
+        "security": [{}, { "apiKey": [] }],
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "apiKey": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "API key; `?access_token=` works too. Not needed for /healthz and /readyz.",
+                },
+            },
+        },
+    })
+}
+
+/// Swagger UI page for `/openapi.json`; assets load from a CDN.
+pub const SWAGGER_UI: &str = r##"<!doctype html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>dexter-kupo-rs pool API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });</script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refs<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(r)) = map.get("$ref") {
+                    out.push(r);
+                }
+                map.values().for_each(|v| refs(v, out));
+            }
+            Value::Array(items) => items.iter().for_each(|v| refs(v, out)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_document_paths_and_refs_resolve() {
+        let doc = document();
+        for path in [
+            "/pools",
+            "/pools/{dex}/{pool_id}",
+            "/events",
+            "/healthz",
+            "/readyz",
+        ] {
+            assert!(
+                doc["paths"][path]["get"].is_object(),
+                "{} undocumented",
+                path
+            );
+        }
+        let mut found = Vec::new();
+        refs(&doc, &mut found);
+        assert!(!found.is_empty());
+        for r in found {
+            let name = r.strip_prefix("#/components/schemas/").unwrap();
+            assert!(
+                doc["components"]["schemas"][name].is_object(),
+                "{} unresolved",
+                r
+            );
+        }
+    }
+}
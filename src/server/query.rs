@@ -0,0 +1,215 @@
+//! Filtering, paging and conditional responses for the pool endpoints.
+//!
+//! `GET /pools` takes `?asset=` (pools holding the asset), `?min_tvl=`
+//! (lovelace, valued like [`RankBy::Tvl`](crate::analytics::RankBy::Tvl)) and
+//! `?offset=`/`?limit=` over the DEX, pool id order; the count before paging
+//! is sent as `X-Total-Count`. Pool responses carry an `ETag`, and a request
+//! whose `If-None-Match` already holds it gets an empty 304, so dashboards
+//! polling every few seconds only transfer pools when they changed.
+//!
+//! Both endpoints take `?max_age=<secs>`: when the last refresh round started
+//! longer ago, one is run against Kupo before answering.
+
+use axum::http::header::{ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::analytics::TvlEstimator;
+use crate::export::{pool_to_export, PoolExport};
+use crate::models::LiquidityPool;
+use crate::store::PoolStore;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct PoolQuery {
+    /// Asset identifier (`lovelace`/`ADA`, or policy id + asset name hex,
+    /// dotted or not).
+    pub asset: Option<String>,
+    /// Minimum TVL in lovelace; pools that cannot be valued are left out.
+    pub min_tvl: Option<u64>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+    /// Oldest acceptable refresh, in seconds; older stores are refreshed first.
+    pub max_age: Option<u64>,
+}
+
+/// Freshness parameter of `GET /pools/{dex}/{pool_id}`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct MaxAge {
+    pub max_age: Option<u64>,
+}
+
+/// One page of matching pools.
+#[derive(Debug, Clone)]
+pub struct PoolPage {
+    /// Matching pools before `offset` and `limit`.
+    pub total: usize,
+    pub pools: Vec<PoolExport>,
+}
+
+/// Store key of an asset parameter: `ADA` is `lovelace`, dots are dropped.
+pub(super) fn asset_key(asset: &str) -> String {
+    if asset.eq_ignore_ascii_case("ada") {
+        return "lovelace".to_string();
+    }
+    asset.replace('.', "").to_lowercase()
+}
+
+/// Pools of `store` matching `query`, sorted by DEX and pool id, then paged.
+pub fn select(store: &PoolStore, query: &PoolQuery) -> PoolPage {
+    let candidates: Vec<&LiquidityPool> = match &query.asset {
+        Some(asset) => store.by_asset(&asset_key(asset)),
+        None => store.pools().collect(),
+    };
+    let mut matching: Vec<&LiquidityPool> = match query.min_tvl {
+        Some(min_tvl) => {
+            let estimator = TvlEstimator::new(store.pools());
+            candidates
+                .into_iter()
+                .filter(|pool| estimator.tvl(pool).is_some_and(|tvl| tvl >= min_tvl))
+                .collect()
+        }
+        None => candidates,
+    };
+    matching.sort_by(|a, b| (&a.dex_identifier, &a.pool_id).cmp(&(&b.dex_identifier, &b.pool_id)));
+    PoolPage {
+        total: matching.len(),
+        pools: matching
+            .into_iter()
+            .skip(query.offset.unwrap_or(0))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .map(pool_to_export)
+            .collect(),
+    }
+}
+
+/// Strong ETag of a response body.
+pub fn etag(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Whether `If-None-Match` in `headers` already holds `etag`.
+pub fn not_modified(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// `value` as JSON with its ETag, or an empty 304 when the client has it.
+pub fn conditional_json(headers: &HeaderMap, value: &impl Serialize) -> Response {
+    let body = match serde_json::to_vec(value) {
+        Ok(body) => body,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let tag = etag(&body);
+    let etag_header = HeaderValue::from_str(&tag).expect("hex ETag is a valid header");
+    if not_modified(headers, &tag) {
+        return (StatusCode::NOT_MODIFIED, [(ETAG, etag_header)]).into_response();
+    }
+    (
+        [
+            (ETAG, etag_header),
+            (
+                axum::http::header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+    use crate::models::Token;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+    const HOSKY: &str = "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235484f534b59";
+
+    fn pool(id: &str, asset_a: Token, asset_b: Token, reserve_a: u64) -> LiquidityPool {
+        LiquidityPool::new(
+            "MinswapV2",
+            asset_a,
+            asset_b,
+            reserve_a,
+            1_000,
+            "addr",
+            0.3,
+            id,
+        )
+    }
+
+    #[test]
+    fn test_select_filters_and_pages() {
+        let snek = from_identifier(SNEK, 0);
+        let hosky = from_identifier(HOSKY, 0);
+        let mut store = PoolStore::new();
+        store.extend([
+            pool("a", Token::Lovelace, snek.clone(), 5_000),
+            pool("b", Token::Lovelace, hosky.clone(), 100),
+            pool("c", snek.clone(), hosky, 1_000),
+        ]);
+
+        let all = select(&store, &PoolQuery::default());
+        assert_eq!(all.total, 3);
+        assert_eq!(all.pools[0].pool_id, "a");
+
+        let page = select(
+            &store,
+            &PoolQuery {
+                offset: Some(1),
+                limit: Some(1),
+                ..Default::default()
+            },
+        );
+        assert_eq!((page.total, page.pools[0].pool_id.as_str()), (3, "b"));
+
+        let snek_pools = select(
+            &store,
+            &PoolQuery {
+                asset: Some(format!("{}.{}", &SNEK[..56], &SNEK[56..])),
+                ..Default::default()
+            },
+        );
+        assert_eq!(snek_pools.total, 2);
+
+        // c is valued through SNEK's ADA pool: 1_000 SNEK = 5_000 lovelace, doubled.
+        let deep = select(
+            &store,
+            &PoolQuery {
+                min_tvl: Some(1_000),
+                ..Default::default()
+            },
+        );
+        let ids: Vec<&str> = deep.pools.iter().map(|p| p.pool_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_etag_and_if_none_match() {
+        let tag = etag(b"[]");
+        assert_eq!(tag, etag(b"[]"));
+        assert_ne!(tag, etag(b"[1]"));
+
+        let mut headers = HeaderMap::new();
+        assert!(!not_modified(&headers, &tag));
+        headers.insert(
+            IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", W/{}", tag)).unwrap(),
+        );
+        assert!(not_modified(&headers, &tag));
+
+        let response = conditional_json(&headers, &Vec::<u8>::new());
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    }
+}
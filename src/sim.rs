@@ -0,0 +1,295 @@
+//! Deterministic backtests over recorded pool history.
+//!
+//! A [`Simulation`] starts from pool templates (assets, fees) and replays
+//! [`ReserveSnapshot`]s in time order: every distinct `observed_at` is one
+//! step, at which the snapshotted reserves are applied and the strategy
+//! callback runs. Inside the callback, [`StepContext`] quotes with the crate's
+//! AMM math and executes swaps against a virtual account; a swap moves the
+//! simulated pool's reserves until the next snapshot of that pool replaces
+//! them. Same inputs, same report.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::models::{token_identifier, LiquidityPool, Token};
+use crate::quote::{amm_out, amm_reserves_after, Direction};
+use crate::store::history::ReserveSnapshot;
+use crate::store::PoolStore;
+
+/// A swap executed by the strategy.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimTrade {
+    pub step: usize,
+    /// `observed_at` of the step, unix seconds.
+    pub time: u64,
+    pub dex: String,
+    pub pool_id: String,
+    pub token_in: String,
+    pub amount_in: u64,
+    pub token_out: String,
+    pub amount_out: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SimReport {
+    pub steps: usize,
+    pub trades: Vec<SimTrade>,
+    /// Final balances by asset identifier.
+    pub balances: BTreeMap<String, u64>,
+}
+
+pub struct Simulation {
+    store: PoolStore,
+    history: Vec<ReserveSnapshot>,
+    balances: BTreeMap<String, u64>,
+}
+
+impl Simulation {
+    /// Simulation over `pools`; their reserves are the state before the first step.
+    pub fn new(pools: impl IntoIterator<Item = LiquidityPool>) -> Self {
+        let mut store = PoolStore::new();
+        store.extend(pools);
+        Self {
+            store,
+            history: Vec::new(),
+            balances: BTreeMap::new(),
+        }
+    }
+
+    /// Snapshots to replay; any order. Snapshots of unknown pools are ignored.
+    pub fn with_history(mut self, history: Vec<ReserveSnapshot>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Starting balance of `token` in the virtual account.
+    pub fn with_balance(mut self, token: &Token, amount: u64) -> Self {
+        self.balances.insert(token_identifier(token), amount);
+        self
+    }
+
+    /// Replay the history, calling `strategy` once per step.
+    ///
+    /// An error from the strategy stops the run and is returned.
+    pub fn run<F>(mut self, mut strategy: F) -> Result<SimReport>
+    where
+        F: FnMut(&mut StepContext<'_>) -> Result<()>,
+    {
+        let mut history = std::mem::take(&mut self.history);
+        history.sort_by(|a, b| {
+            (a.observed_at, &a.dex, &a.pool_id).cmp(&(b.observed_at, &b.dex, &b.pool_id))
+        });
+
+        let mut trades = Vec::new();
+        let mut steps = 0;
+        let mut rest = history.as_slice();
+        while let Some(first) = rest.first() {
+            let time = first.observed_at;
+            let len = rest.iter().take_while(|s| s.observed_at == time).count();
+            let (step, tail) = rest.split_at(len);
+            rest = tail;
+
+            for snapshot in step {
+                if let Some(pool) = self.store.get(&snapshot.dex, &snapshot.pool_id) {
+                    let mut pool = pool.clone();
+                    pool.reserve_a = snapshot.reserve_a;
+                    pool.reserve_b = snapshot.reserve_b;
+                    pool.total_lp_tokens = snapshot.total_lp_tokens;
+                    self.store.upsert(pool);
+                }
+            }
+
+            let mut ctx = StepContext {
+                index: steps,
+                time,
+                store: &mut self.store,
+                balances: &mut self.balances,
+                trades: &mut trades,
+            };
+            strategy(&mut ctx)?;
+            steps += 1;
+        }
+
+        Ok(SimReport {
+            steps,
+            trades,
+            balances: self.balances,
+        })
+    }
+}
+
+/// State visible to the strategy at one step.
+pub struct StepContext<'a> {
+    index: usize,
+    time: u64,
+    store: &'a mut PoolStore,
+    balances: &'a mut BTreeMap<String, u64>,
+    trades: &'a mut Vec<SimTrade>,
+}
+
+impl StepContext<'_> {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// `observed_at` of the step's snapshots, unix seconds.
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    /// Simulated pools at this step.
+    pub fn pools(&self) -> &PoolStore {
+        self.store
+    }
+
+    pub fn balance(&self, token: &Token) -> u64 {
+        self.balances
+            .get(&token_identifier(token))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn balances(&self) -> &BTreeMap<String, u64> {
+        self.balances
+    }
+
+    fn pool_and_direction(
+        &self,
+        dex: &str,
+        pool_id: &str,
+        token_in: &Token,
+    ) -> Result<(&LiquidityPool, Direction)> {
+        let pool = self
+            .store
+            .get(dex, pool_id)
+            .ok_or_else(|| anyhow!("Unknown pool {}:{}", dex, pool_id))?;
+        let id = token_identifier(token_in);
+        if id != token_identifier(&pool.asset_a) && id != token_identifier(&pool.asset_b) {
+            return Err(anyhow!("Pool {}:{} does not trade {}", dex, pool_id, id));
+        }
+        Ok((pool, Direction::for_input(&pool.asset_a, token_in)))
+    }
+
+    /// Output of swapping `amount_in` of `token_in` through the pool now.
+    pub fn quote(&self, dex: &str, pool_id: &str, token_in: &Token, amount_in: u64) -> Result<u64> {
+        let (pool, direction) = self.pool_and_direction(dex, pool_id, token_in)?;
+        Ok(amm_out(pool, direction, amount_in))
+    }
+
+    /// Swap from the account through the pool, moving its reserves. Fails if
+    /// the balance is short or the output is below `min_out`.
+    pub fn swap(
+        &mut self,
+        dex: &str,
+        pool_id: &str,
+        token_in: &Token,
+        amount_in: u64,
+        min_out: u64,
+    ) -> Result<u64> {
+        let (pool, direction) = self.pool_and_direction(dex, pool_id, token_in)?;
+        let amount_out = amm_out(pool, direction, amount_in);
+        if amount_out < min_out {
+            return Err(anyhow!(
+                "Output {} below minimum {} on {}:{}",
+                amount_out,
+                min_out,
+                dex,
+                pool_id
+            ));
+        }
+        let id_in = token_identifier(token_in);
+        let balance = self.balances.get(&id_in).copied().unwrap_or(0);
+        if balance < amount_in {
+            return Err(anyhow!(
+                "Balance {} of {} below {}",
+                balance,
+                id_in,
+                amount_in
+            ));
+        }
+
+        let token_out = match direction {
+            Direction::AToB => token_identifier(&pool.asset_b),
+            Direction::BToA => token_identifier(&pool.asset_a),
+        };
+        let (reserve_a, reserve_b) = amm_reserves_after(pool, direction, amount_in);
+        let mut pool = pool.clone();
+        pool.reserve_a = reserve_a;
+        pool.reserve_b = reserve_b;
+        self.store.upsert(pool);
+
+        self.balances.insert(id_in.clone(), balance - amount_in);
+        let out_balance = self.balances.entry(token_out.clone()).or_insert(0);
+        *out_balance = out_balance.saturating_add(amount_out);
+        self.trades.push(SimTrade {
+            step: self.index,
+            time: self.time,
+            dex: dex.to_string(),
+            pool_id: pool_id.to_string(),
+            token_in: id_in,
+            amount_in,
+            token_out,
+            amount_out,
+        });
+        Ok(amount_out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    fn snapshot(observed_at: u64, reserve_a: u64, reserve_b: u64) -> ReserveSnapshot {
+        ReserveSnapshot {
+            dex: "MinswapV2".to_string(),
+            pool_id: "p".to_string(),
+            observed_at,
+            slot: None,
+            reserve_a,
+            reserve_b,
+            total_lp_tokens: 1,
+        }
+    }
+
+    #[test]
+    fn test_replay_buys_when_price_drops() {
+        let snek = from_identifier(SNEK, 0);
+        let pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            snek.clone(),
+            1_000_000,
+            1_000_000,
+            "addr",
+            0.3,
+            "p",
+        );
+        // SNEK gets cheaper at t=20, then recovers.
+        let history = vec![
+            snapshot(30, 1_000_000, 1_000_000),
+            snapshot(10, 1_000_000, 1_000_000),
+            snapshot(20, 1_000_000, 2_000_000),
+        ];
+        let report = Simulation::new([pool])
+            .with_history(history)
+            .with_balance(&Token::Lovelace, 10_000)
+            .run(|ctx| {
+                let out = ctx.quote("MinswapV2", "p", &Token::Lovelace, 10_000)?;
+                if out > 15_000 && ctx.balance(&Token::Lovelace) > 0 {
+                    ctx.swap("MinswapV2", "p", &Token::Lovelace, 10_000, out)?;
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(report.steps, 3);
+        assert_eq!(report.trades.len(), 1);
+        assert_eq!(report.trades[0].time, 20);
+        assert_eq!(report.balances["lovelace"], 0);
+        assert_eq!(report.balances[SNEK], report.trades[0].amount_out);
+    }
+}
@@ -0,0 +1,207 @@
+//! Reserve history rows and their retention policy.
+//!
+//! History grows with every refresh, so long-running watchers compact it:
+//! snapshots younger than the raw window are kept as recorded, older ones are
+//! downsampled to the last snapshot of each interval (hourly by default), and
+//! with a maximum age anything older is dropped.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::models::LiquidityPool;
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+/// Full-resolution history kept by default.
+pub const DEFAULT_RAW_DAYS: u64 = 7;
+/// Bucket older snapshots are downsampled to by default.
+pub const DEFAULT_DOWNSAMPLE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// One reserve observation of a pool.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReserveSnapshot {
+    pub dex: String,
+    pub pool_id: String,
+    /// Unix seconds.
+    pub observed_at: u64,
+    pub slot: Option<u64>,
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+    pub total_lp_tokens: u64,
+}
+
+impl ReserveSnapshot {
+    /// Snapshot of `pool`, timed by its freshness or else by `now`.
+    pub fn from_pool(pool: &LiquidityPool, now: u64) -> Self {
+        let freshness = pool.metadata.freshness.as_ref();
+        Self {
+            dex: pool.dex_identifier.clone(),
+            pool_id: pool.pool_id.clone(),
+            observed_at: freshness.map(|f| f.observed_at).unwrap_or(now),
+            slot: freshness.and_then(|f| f.slot),
+            reserve_a: pool.reserve_a,
+            reserve_b: pool.reserve_b,
+            total_lp_tokens: pool.total_lp_tokens,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetentionPolicy {
+    raw_window: Duration,
+    downsample_interval: Duration,
+    max_age: Option<Duration>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            raw_window: Duration::from_secs(DEFAULT_RAW_DAYS * DAY_SECS),
+            downsample_interval: DEFAULT_DOWNSAMPLE_INTERVAL,
+            max_age: None,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep every snapshot of the last `days` days.
+    pub fn with_raw_days(mut self, days: u64) -> Self {
+        self.raw_window = Duration::from_secs(days * DAY_SECS);
+        self
+    }
+
+    /// Downsample older snapshots to one per `interval` (at least a second).
+    pub fn with_downsample_interval(mut self, interval: Duration) -> Self {
+        self.downsample_interval = interval.max(Duration::from_secs(1));
+        self
+    }
+
+    /// Drop snapshots older than `days` days, downsampled or not.
+    pub fn with_max_age_days(mut self, days: u64) -> Self {
+        self.max_age = Some(Duration::from_secs(days * DAY_SECS));
+        self
+    }
+
+    /// Snapshots observed before this are downsampled.
+    pub fn raw_cutoff(&self, now: u64) -> u64 {
+        now.saturating_sub(self.raw_window.as_secs())
+    }
+
+    /// Snapshots observed before this are deleted.
+    pub fn expiry_cutoff(&self, now: u64) -> Option<u64> {
+        self.max_age.map(|age| now.saturating_sub(age.as_secs()))
+    }
+
+    pub fn bucket_secs(&self) -> u64 {
+        self.downsample_interval.as_secs().max(1)
+    }
+
+    /// Apply the policy to an in-memory history; the result is sorted by
+    /// pool, then time.
+    pub fn compact(&self, mut snapshots: Vec<ReserveSnapshot>, now: u64) -> Vec<ReserveSnapshot> {
+        let raw_cutoff = self.raw_cutoff(now);
+        let expiry = self.expiry_cutoff(now).unwrap_or(0);
+        let bucket = self.bucket_secs();
+        snapshots.retain(|s| s.observed_at >= expiry);
+        snapshots.sort_by(|a, b| {
+            (&a.dex, &a.pool_id, a.observed_at).cmp(&(&b.dex, &b.pool_id, b.observed_at))
+        });
+
+        // Index of the last snapshot of every (pool, bucket) before the cutoff.
+        let mut last: HashMap<(&str, &str, u64), usize> = HashMap::new();
+        for (i, s) in snapshots.iter().enumerate() {
+            if s.observed_at < raw_cutoff {
+                last.insert((&s.dex, &s.pool_id, s.observed_at / bucket), i);
+            }
+        }
+        let keep: Vec<bool> = snapshots
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                s.observed_at >= raw_cutoff
+                    || last.get(&(&s.dex, &s.pool_id, s.observed_at / bucket)) == Some(&i)
+            })
+            .collect();
+        let mut keep = keep.into_iter();
+        snapshots.retain(|_| keep.next().unwrap_or(true));
+        snapshots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PoolFreshness, Token};
+
+    fn snapshot(pool_id: &str, observed_at: u64) -> ReserveSnapshot {
+        ReserveSnapshot {
+            dex: "MinswapV2".to_string(),
+            pool_id: pool_id.to_string(),
+            observed_at,
+            slot: None,
+            reserve_a: observed_at,
+            reserve_b: 1,
+            total_lp_tokens: 1,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_from_pool() {
+        let mut pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            Token::Lovelace,
+            u64::MAX,
+            2,
+            "addr",
+            0.3,
+            "p",
+        );
+        assert_eq!(ReserveSnapshot::from_pool(&pool, 100).observed_at, 100);
+
+        pool.metadata.freshness = Some(PoolFreshness {
+            slot: Some(7),
+            observed_at: 50,
+            ..Default::default()
+        });
+        let snapshot = ReserveSnapshot::from_pool(&pool, 100);
+        assert_eq!((snapshot.observed_at, snapshot.slot), (50, Some(7)));
+        assert_eq!(snapshot.reserve_a, u64::MAX);
+    }
+
+    #[test]
+    fn test_compact_downsamples_and_expires() {
+        let now = 30 * DAY_SECS;
+        let policy = RetentionPolicy::new()
+            .with_raw_days(1)
+            .with_max_age_days(10);
+        let old = now - 2 * DAY_SECS;
+        let history = vec![
+            snapshot("a", now - 100),
+            snapshot("a", now - 50),
+            // Same hour, older than the raw window: only the later one survives.
+            snapshot("a", old - old % 3600 + 10),
+            snapshot("a", old - old % 3600 + 20),
+            snapshot("b", old - old % 3600 + 10),
+            snapshot("a", now - 20 * DAY_SECS),
+        ];
+        let kept: Vec<(String, u64)> = policy
+            .compact(history, now)
+            .into_iter()
+            .map(|s| (s.pool_id, s.observed_at))
+            .collect();
+        assert_eq!(
+            kept,
+            vec![
+                ("a".to_string(), old - old % 3600 + 20),
+                ("a".to_string(), now - 100),
+                ("a".to_string(), now - 50),
+                ("b".to_string(), old - old % 3600 + 10),
+            ]
+        );
+    }
+}
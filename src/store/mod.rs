@@ -0,0 +1,207 @@
+//! In-memory pool store.
+//!
+//! [`PoolStore`] holds the latest state of every fetched pool, keyed like the
+//! watcher (`<dex>:<pool_id>`), with secondary indexes by pair, by asset, by
+//! DEX and by pool id. Feed it whole snapshots with [`PoolStore::upsert`] or
+//! keep it current with [`PoolStore::apply`] on watcher events. It is not
+//! synchronized; share it behind a `RwLock`.
+//!
+//! With the `redis` feature, [`redis::RedisPoolStore`] shares one snapshot
+//! between processes; with `postgres`, [`postgres::PgStore`] persists pools,
+//! orders and reserve history. [`history::RetentionPolicy`] bounds how much
+//! of that history is kept.
+
+pub mod history;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "redis")]
+pub mod redis;
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::models::{token_identifier, LiquidityPool};
+use crate::watcher::{pool_key, PoolEvent};
+
+type Index = HashMap<String, BTreeSet<String>>;
+
+#[derive(Debug, Clone, Default)]
+pub struct PoolStore {
+    pools: HashMap<String, LiquidityPool>,
+    by_pair: HashMap<(String, String), BTreeSet<String>>,
+    by_asset: Index,
+    by_dex: Index,
+    by_pool_id: Index,
+}
+
+/// Asset identifiers of `a` and `b` in canonical (sorted) order.
+pub(crate) fn pair_key(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+fn unindex(index: &mut Index, value: &str, key: &str) {
+    if let Some(keys) = index.get_mut(value) {
+        keys.remove(key);
+        if keys.is_empty() {
+            index.remove(value);
+        }
+    }
+}
+
+impl PoolStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pools.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty()
+    }
+
+    /// Insert or replace a pool, returning its previous state.
+    pub fn upsert(&mut self, pool: LiquidityPool) -> Option<LiquidityPool> {
+        let key = pool_key(&pool);
+        let previous = self.pools.remove(&key);
+        if let Some(previous) = &previous {
+            self.unindex(&key, previous);
+        }
+        let a = token_identifier(&pool.asset_a);
+        let b = token_identifier(&pool.asset_b);
+        self.by_pair
+            .entry(pair_key(&a, &b))
+            .or_default()
+            .insert(key.clone());
+        self.by_asset.entry(a).or_default().insert(key.clone());
+        self.by_asset.entry(b).or_default().insert(key.clone());
+        self.by_dex
+            .entry(pool.dex_identifier.clone())
+            .or_default()
+            .insert(key.clone());
+        self.by_pool_id
+            .entry(pool.pool_id.clone())
+            .or_default()
+            .insert(key.clone());
+        self.pools.insert(key, pool);
+        previous
+    }
+
+    /// Upsert every pool of a snapshot.
+    pub fn extend(&mut self, pools: impl IntoIterator<Item = LiquidityPool>) {
+        for pool in pools {
+            self.upsert(pool);
+        }
+    }
+
+    /// Apply a watcher event: created and updated pools are upserted.
+    pub fn apply(&mut self, event: &PoolEvent) {
+        if let Some(pool) = event.pool() {
+            self.upsert(pool.clone());
+        }
+    }
+
+    pub fn remove(&mut self, dex: &str, pool_id: &str) -> Option<LiquidityPool> {
+        let key = format!("{}:{}", dex, pool_id);
+        let pool = self.pools.remove(&key)?;
+        self.unindex(&key, &pool);
+        Some(pool)
+    }
+
+    fn unindex(&mut self, key: &str, pool: &LiquidityPool) {
+        let a = token_identifier(&pool.asset_a);
+        let b = token_identifier(&pool.asset_b);
+        let pair = pair_key(&a, &b);
+        if let Some(keys) = self.by_pair.get_mut(&pair) {
+            keys.remove(key);
+            if keys.is_empty() {
+                self.by_pair.remove(&pair);
+            }
+        }
+        unindex(&mut self.by_asset, &a, key);
+        unindex(&mut self.by_asset, &b, key);
+        unindex(&mut self.by_dex, &pool.dex_identifier, key);
+        unindex(&mut self.by_pool_id, &pool.pool_id, key);
+    }
+
+    fn resolve<'a>(&'a self, keys: Option<&'a BTreeSet<String>>) -> Vec<&'a LiquidityPool> {
+        keys.into_iter()
+            .flatten()
+            .filter_map(|key| self.pools.get(key))
+            .collect()
+    }
+
+    pub fn get(&self, dex: &str, pool_id: &str) -> Option<&LiquidityPool> {
+        self.pools.get(&format!("{}:{}", dex, pool_id))
+    }
+
+    /// Pools with this id on any DEX.
+    pub fn by_pool_id(&self, pool_id: &str) -> Vec<&LiquidityPool> {
+        self.resolve(self.by_pool_id.get(pool_id))
+    }
+
+    /// Pools trading `asset_a` against `asset_b` (asset identifiers, either order).
+    pub fn pair(&self, asset_a: &str, asset_b: &str) -> Vec<&LiquidityPool> {
+        self.resolve(self.by_pair.get(&pair_key(asset_a, asset_b)))
+    }
+
+    /// Pools holding `asset` on either side.
+    pub fn by_asset(&self, asset: &str) -> Vec<&LiquidityPool> {
+        self.resolve(self.by_asset.get(asset))
+    }
+
+    pub fn by_dex(&self, dex: &str) -> Vec<&LiquidityPool> {
+        self.resolve(self.by_dex.get(dex))
+    }
+
+    pub fn pools(&self) -> impl Iterator<Item = &LiquidityPool> {
+        self.pools.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+    use crate::models::Token;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+
+    fn pool(dex: &str, id: &str, reserve_a: u64) -> LiquidityPool {
+        LiquidityPool::new(
+            dex,
+            Token::Lovelace,
+            from_identifier(SNEK, 0),
+            reserve_a,
+            10,
+            "addr",
+            0.3,
+            id,
+        )
+    }
+
+    #[test]
+    fn test_indexes_follow_upserts_and_removals() {
+        let mut store = PoolStore::new();
+        store.extend([pool("MinswapV2", "a", 1), pool("SundaeSwapV3", "b", 1)]);
+        assert_eq!(store.pair(SNEK, "lovelace").len(), 2);
+        assert_eq!(store.by_asset(SNEK).len(), 2);
+        assert_eq!(store.by_dex("MinswapV2").len(), 1);
+
+        let event = PoolEvent::PoolCreated {
+            pool: Box::new(pool("MinswapV2", "a", 5)),
+        };
+        store.apply(&event);
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.by_pool_id("a")[0].reserve_a, 5);
+
+        assert!(store.remove("SundaeSwapV3", "b").is_some());
+        assert!(store.by_dex("SundaeSwapV3").is_empty());
+        assert_eq!(store.pair("lovelace", SNEK).len(), 1);
+        assert!(store.get("MinswapV2", "a").is_some());
+    }
+}
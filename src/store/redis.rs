@@ -0,0 +1,232 @@
+//! Redis-backed pool store (feature `redis`).
+//!
+//! Several processes share one pool snapshot: a writer (typically the watcher
+//! process) upserts pools, readers query them or mirror them into a local
+//! [`PoolStore`]. Every upsert is published on `<namespace>:updates` as the
+//! pool's JSON, so subscribers stay current without polling.
+//!
+//! Layout, all under `<namespace>`:
+//! - `:pools` — hash of `<dex>:<pool_id>` → pool JSON;
+//! - `:pair:<a>:<b>` (sorted identifiers), `:asset:<id>`, `:dex:<dex>` — sets
+//!   of pool keys.
+
+use ::redis::aio::MultiplexedConnection;
+use ::redis::{AsyncCommands, Client};
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use tokio::sync::mpsc;
+
+use super::{pair_key, PoolStore};
+use crate::models::{token_identifier, LiquidityPool};
+use crate::watcher::{pool_key, PoolEvent};
+
+pub const DEFAULT_NAMESPACE: &str = "dexter";
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Clone)]
+pub struct RedisPoolStore {
+    client: Client,
+    conn: MultiplexedConnection,
+    namespace: String,
+}
+
+/// Index sets a pool belongs to.
+fn index_keys(namespace: &str, pool: &LiquidityPool) -> Vec<String> {
+    let a = token_identifier(&pool.asset_a);
+    let b = token_identifier(&pool.asset_b);
+    let (lo, hi) = pair_key(&a, &b);
+    vec![
+        format!("{}:pair:{}:{}", namespace, lo, hi),
+        format!("{}:asset:{}", namespace, a),
+        format!("{}:asset:{}", namespace, b),
+        format!("{}:dex:{}", namespace, pool.dex_identifier),
+    ]
+}
+
+fn parse_pools(raw: Vec<Option<String>>) -> Result<Vec<LiquidityPool>> {
+    raw.into_iter()
+        .flatten()
+        .map(|json| serde_json::from_str(&json).map_err(|e| anyhow!("Bad pool in Redis: {}", e)))
+        .collect()
+}
+
+impl RedisPoolStore {
+    /// Connect to `url` (e.g. `redis://127.0.0.1/`) using [`DEFAULT_NAMESPACE`].
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client = Client::open(url).map_err(|e| anyhow!("Invalid Redis URL {}: {}", url, e))?;
+        let conn = client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| anyhow!("Failed to connect to Redis {}: {}", url, e))?;
+        Ok(Self {
+            client,
+            conn,
+            namespace: DEFAULT_NAMESPACE.to_string(),
+        })
+    }
+
+    /// Prefix for every key, so several deployments can share one Redis.
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.to_string();
+        self
+    }
+
+    fn pools_key(&self) -> String {
+        format!("{}:pools", self.namespace)
+    }
+
+    fn updates_channel(&self) -> String {
+        format!("{}:updates", self.namespace)
+    }
+
+    /// Insert or replace a pool and publish it to subscribers.
+    pub async fn upsert(&self, pool: &LiquidityPool) -> Result<()> {
+        let key = pool_key(pool);
+        let json = serde_json::to_string(pool)?;
+        let mut conn = self.conn.clone();
+        let previous: Option<String> = conn.hget(self.pools_key(), &key).await?;
+
+        let mut pipe = ::redis::pipe();
+        pipe.atomic();
+        if let Some(previous) = previous {
+            let previous: LiquidityPool = serde_json::from_str(&previous)?;
+            for index in index_keys(&self.namespace, &previous) {
+                pipe.srem(index, &key).ignore();
+            }
+        }
+        pipe.hset(self.pools_key(), &key, &json).ignore();
+        for index in index_keys(&self.namespace, pool) {
+            pipe.sadd(index, &key).ignore();
+        }
+        pipe.publish(self.updates_channel(), &json).ignore();
+        pipe.query_async::<()>(&mut conn).await?;
+        Ok(())
+    }
+
+    pub async fn extend(&self, pools: &[LiquidityPool]) -> Result<()> {
+        for pool in pools {
+            self.upsert(pool).await?;
+        }
+        Ok(())
+    }
+
+    /// Apply a watcher event: created and updated pools are upserted.
+    pub async fn apply(&self, event: &PoolEvent) -> Result<()> {
+        match event.pool() {
+            Some(pool) => self.upsert(pool).await,
+            None => Ok(()),
+        }
+    }
+
+    pub async fn remove(&self, dex: &str, pool_id: &str) -> Result<Option<LiquidityPool>> {
+        let key = format!("{}:{}", dex, pool_id);
+        let Some(pool) = self.get(dex, pool_id).await? else {
+            return Ok(None);
+        };
+        let mut pipe = ::redis::pipe();
+        pipe.atomic().hdel(self.pools_key(), &key).ignore();
+        for index in index_keys(&self.namespace, &pool) {
+            pipe.srem(index, &key).ignore();
+        }
+        pipe.query_async::<()>(&mut self.conn.clone()).await?;
+        Ok(Some(pool))
+    }
+
+    pub async fn get(&self, dex: &str, pool_id: &str) -> Result<Option<LiquidityPool>> {
+        let key = format!("{}:{}", dex, pool_id);
+        let raw: Option<String> = self.conn.clone().hget(self.pools_key(), key).await?;
+        Ok(parse_pools(vec![raw])?.pop())
+    }
+
+    async fn members(&self, index: String) -> Result<Vec<LiquidityPool>> {
+        let mut conn = self.conn.clone();
+        let keys: Vec<String> = conn.smembers(index).await?;
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let raw: Vec<Option<String>> = ::redis::cmd("HMGET")
+            .arg(self.pools_key())
+            .arg(&keys)
+            .query_async(&mut conn)
+            .await?;
+        parse_pools(raw)
+    }
+
+    /// Pools trading `asset_a` against `asset_b` (asset identifiers, either order).
+    pub async fn pair(&self, asset_a: &str, asset_b: &str) -> Result<Vec<LiquidityPool>> {
+        let (lo, hi) = pair_key(asset_a, asset_b);
+        self.members(format!("{}:pair:{}:{}", self.namespace, lo, hi))
+            .await
+    }
+
+    pub async fn by_asset(&self, asset: &str) -> Result<Vec<LiquidityPool>> {
+        self.members(format!("{}:asset:{}", self.namespace, asset))
+            .await
+    }
+
+    pub async fn by_dex(&self, dex: &str) -> Result<Vec<LiquidityPool>> {
+        self.members(format!("{}:dex:{}", self.namespace, dex))
+            .await
+    }
+
+    /// The whole shared snapshot as a local [`PoolStore`].
+    pub async fn snapshot(&self) -> Result<PoolStore> {
+        let raw: Vec<String> = self.conn.clone().hvals(self.pools_key()).await?;
+        let mut store = PoolStore::new();
+        store.extend(parse_pools(raw.into_iter().map(Some).collect())?);
+        Ok(store)
+    }
+
+    /// Pools published by other instances' upserts, on a background task.
+    ///
+    /// Messages that fail to parse are logged and skipped. The task stops
+    /// when the receiver is dropped or the connection closes.
+    pub async fn subscribe(&self) -> Result<mpsc::Receiver<LiquidityPool>> {
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(self.updates_channel()).await?;
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            let mut messages = pubsub.into_on_message();
+            while let Some(msg) = messages.next().await {
+                let pool = msg
+                    .get_payload::<String>()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|json| Ok(serde_json::from_str::<LiquidityPool>(&json)?));
+                match pool {
+                    Ok(pool) => {
+                        if tx.send(pool).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => eprintln!("[redis] bad pool update: {}", e),
+                }
+            }
+        });
+        Ok(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::asset::from_identifier;
+    use crate::models::Token;
+
+    #[test]
+    fn test_index_keys_use_sorted_pair() {
+        let snek = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+        let pool = LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            from_identifier(snek, 0),
+            1,
+            1,
+            "addr",
+            0.3,
+            "p",
+        );
+        let keys = index_keys("dexter", &pool);
+        assert_eq!(keys[0], format!("dexter:pair:{}:lovelace", snek));
+        assert_eq!(keys[3], "dexter:dex:MinswapV2");
+    }
+}
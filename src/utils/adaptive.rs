@@ -0,0 +1,165 @@
+//! AIMD concurrency controller for bulk Kupo work (pool exports).
+//!
+//! The limit grows by roughly one slot per window of fast, successful requests
+//! and halves when a request is rate limited or much slower than the best
+//! latency seen so far, so exports open up against a local Kupo and back off
+//! against a shared one.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// Requests slower than `SLOW_FACTOR` × the fastest observed latency count as congestion.
+const SLOW_FACTOR: u32 = 4;
+/// Latencies under this never count as congestion (local Kupo jitter).
+const SLOW_FLOOR: Duration = Duration::from_millis(50);
+
+/// How a request finished, as far as the controller is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    /// 429, timeout or connection error — back off.
+    Overloaded,
+    /// Failed for reasons unrelated to load (e.g. datum parse error); ignored.
+    Ignored,
+}
+
+#[derive(Debug)]
+struct State {
+    limit: f64,
+    in_flight: usize,
+    best_latency: Option<Duration>,
+    /// Completions since the last decrease; decreases wait for a full window.
+    since_decrease: usize,
+}
+
+#[derive(Debug)]
+pub struct AdaptiveConcurrency {
+    min: usize,
+    max: usize,
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+impl AdaptiveConcurrency {
+    pub fn new(initial: usize, min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        let max = max.max(min);
+        Self {
+            min,
+            max,
+            state: Mutex::new(State {
+                limit: initial.clamp(min, max) as f64,
+                in_flight: 0,
+                best_latency: None,
+                since_decrease: 0,
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Current concurrency limit.
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit as usize
+    }
+
+    /// Wait for a slot under the current limit.
+    pub async fn acquire(self: &Arc<Self>) -> AdaptivePermit {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.in_flight < state.limit as usize {
+                    state.in_flight += 1;
+                    // Pass the wakeup on if there is still room (limit may have grown).
+                    if state.in_flight < state.limit as usize {
+                        self.notify.notify_one();
+                    }
+                    return AdaptivePermit {
+                        ctrl: Arc::clone(self),
+                        started: Instant::now(),
+                        outcome: Outcome::Ignored,
+                    };
+                }
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    fn complete(&self, latency: Duration, outcome: Outcome) {
+        let mut state = self.state.lock().unwrap();
+        state.in_flight = state.in_flight.saturating_sub(1);
+        state.since_decrease += 1;
+
+        let best = *state.best_latency.get_or_insert(latency);
+        let slow = latency > SLOW_FLOOR && latency > best * SLOW_FACTOR;
+
+        match outcome {
+            Outcome::Overloaded => self.decrease(&mut state),
+            Outcome::Success if slow => self.decrease(&mut state),
+            Outcome::Success => {
+                if latency < best {
+                    state.best_latency = Some(latency);
+                }
+                state.limit = (state.limit + 1.0 / state.limit).min(self.max as f64);
+            }
+            Outcome::Ignored => {}
+        }
+        drop(state);
+        self.notify.notify_one();
+    }
+
+    fn decrease(&self, state: &mut State) {
+        if state.since_decrease < state.limit as usize {
+            return;
+        }
+        state.limit = (state.limit / 2.0).max(self.min as f64);
+        state.since_decrease = 0;
+    }
+}
+
+/// Slot held while a request is in flight; reports its latency and outcome on drop.
+#[derive(Debug)]
+pub struct AdaptivePermit {
+    ctrl: Arc<AdaptiveConcurrency>,
+    started: Instant,
+    outcome: Outcome,
+}
+
+impl AdaptivePermit {
+    pub fn set_outcome(&mut self, outcome: Outcome) {
+        self.outcome = outcome;
+    }
+}
+
+impl Drop for AdaptivePermit {
+    fn drop(&mut self) {
+        self.ctrl.complete(self.started.elapsed(), self.outcome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_aimd_grows_and_halves() {
+        let ctrl = Arc::new(AdaptiveConcurrency::new(4, 1, 16));
+        for _ in 0..40 {
+            let mut permit = ctrl.acquire().await;
+            permit.set_outcome(Outcome::Success);
+        }
+        let grown = ctrl.limit();
+        assert!(grown > 4 && grown <= 16, "limit {}", grown);
+
+        let mut permit = ctrl.acquire().await;
+        permit.set_outcome(Outcome::Overloaded);
+        drop(permit);
+        assert_eq!(ctrl.limit(), grown / 2);
+
+        // A second overload inside the same window is ignored.
+        let mut permit = ctrl.acquire().await;
+        permit.set_outcome(Outcome::Overloaded);
+        drop(permit);
+        assert_eq!(ctrl.limit(), grown / 2);
+    }
+}
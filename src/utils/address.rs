@@ -0,0 +1,376 @@
+//! Shelley address parsing and construction, on any network.
+//!
+//! Header byte = address type (upper nibble) | network id (lower nibble):
+//!   * 0–3   base: payment + stake credential (key/key, script/key, key/script, script/script)
+//!   * 4–5   pointer: payment credential + a certificate pointer (not decoded)
+//!   * 6–7   enterprise: payment credential only (key, script)
+//!   * 14–15 reward (`stake1...`): stake credential only (key, script)
+//!
+//! Network id 1 is mainnet (`addr`, `stake`); 0 is every testnet
+//! (`addr_test`, `stake_test`). Byron addresses are base58 and not handled.
+//!
+//! [`normalize_address`] turns the other spellings found in explorers and
+//! datums (raw hex bytes, CBOR-wrapped hex, bare script hashes) into bech32,
+//! the only form Kupo matches addresses by.
+
+use anyhow::{anyhow, Result};
+
+use crate::models::{Credential, CredentialKind};
+
+const HASH_LEN: usize = 28;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    fn id(self) -> u8 {
+        match self {
+            Network::Mainnet => 1,
+            Network::Testnet => 0,
+        }
+    }
+
+    fn from_id(id: u8) -> Self {
+        if id == 1 {
+            Network::Mainnet
+        } else {
+            Network::Testnet
+        }
+    }
+
+    fn address_hrp(self) -> &'static str {
+        match self {
+            Network::Mainnet => "addr",
+            Network::Testnet => "addr_test",
+        }
+    }
+
+    fn stake_hrp(self) -> &'static str {
+        match self {
+            Network::Mainnet => "stake",
+            Network::Testnet => "stake_test",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressKind {
+    Base,
+    Pointer,
+    Enterprise,
+    Reward,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedAddress {
+    pub kind: AddressKind,
+    pub network: Network,
+    /// `None` for reward addresses.
+    pub payment: Option<Credential>,
+    /// Set for base and reward addresses.
+    pub stake: Option<Credential>,
+    pub bech32: String,
+}
+
+impl ParsedAddress {
+    /// Whether the payment credential is a script (a DEX pool or order
+    /// address, typically).
+    pub fn is_script(&self) -> bool {
+        self.payment
+            .as_ref()
+            .is_some_and(|c| c.kind == CredentialKind::Script)
+    }
+
+    /// Reward address of the stake credential, if there is one.
+    pub fn stake_address(&self) -> Option<String> {
+        let stake = self.stake.as_ref()?;
+        stake_address(stake, self.network).ok()
+    }
+}
+
+fn credential(kind: CredentialKind, bytes: &[u8]) -> Credential {
+    Credential {
+        kind,
+        hash: hex::encode(bytes),
+    }
+}
+
+fn kind_if(script: bool) -> CredentialKind {
+    if script {
+        CredentialKind::Script
+    } else {
+        CredentialKind::Key
+    }
+}
+
+/// Parse a bech32 Shelley address (`addr`, `addr_test`, `stake`, `stake_test`).
+pub fn parse_address(address: &str) -> Result<ParsedAddress> {
+    let (hrp, data) = bech32::decode(address)
+        .map_err(|e| anyhow!("bech32 decode failed for `{}`: {}", address, e))?;
+    let (&header, body) = data
+        .split_first()
+        .ok_or_else(|| anyhow!("address has no payload"))?;
+    let (addr_type, network) = (header >> 4, Network::from_id(header & 0x0f));
+    let expected_hrp = if addr_type >= 14 {
+        network.stake_hrp()
+    } else {
+        network.address_hrp()
+    };
+    if hrp.as_str() != expected_hrp {
+        return Err(anyhow!(
+            "HRP `{}` does not match the header (expected `{}`)",
+            hrp.as_str(),
+            expected_hrp
+        ));
+    }
+    let expected_len = match addr_type {
+        0..=3 => 2 * HASH_LEN,
+        4 | 5 => HASH_LEN + 3, // at least: three one-byte pointer fields
+        6 | 7 | 14 | 15 => HASH_LEN,
+        _ => return Err(anyhow!("unsupported address type {}", addr_type)),
+    };
+    let length_ok = if matches!(addr_type, 4 | 5) {
+        body.len() >= expected_len
+    } else {
+        body.len() == expected_len
+    };
+    if !length_ok {
+        return Err(anyhow!(
+            "address type {} payload must be {} bytes, got {}",
+            addr_type,
+            expected_len,
+            body.len()
+        ));
+    }
+
+    let (kind, payment, stake) = match addr_type {
+        0..=3 => (
+            AddressKind::Base,
+            Some(credential(kind_if(addr_type & 1 == 1), &body[..HASH_LEN])),
+            Some(credential(kind_if(addr_type & 2 == 2), &body[HASH_LEN..])),
+        ),
+        4 | 5 => (
+            AddressKind::Pointer,
+            Some(credential(kind_if(addr_type == 5), &body[..HASH_LEN])),
+            None,
+        ),
+        6 | 7 => (
+            AddressKind::Enterprise,
+            Some(credential(kind_if(addr_type == 7), body)),
+            None,
+        ),
+        _ => (
+            AddressKind::Reward,
+            None,
+            Some(credential(kind_if(addr_type == 15), body)),
+        ),
+    };
+    Ok(ParsedAddress {
+        kind,
+        network,
+        payment,
+        stake,
+        bech32: address.to_string(),
+    })
+}
+
+/// Bech32 address of raw address bytes (header byte first).
+pub fn address_from_bytes(bytes: &[u8]) -> Result<String> {
+    let &header = bytes
+        .first()
+        .ok_or_else(|| anyhow!("address has no payload"))?;
+    let network = Network::from_id(header & 0x0f);
+    let hrp = if header >> 4 >= 14 {
+        network.stake_hrp()
+    } else {
+        network.address_hrp()
+    };
+    let hrp = bech32::Hrp::parse(hrp).map_err(|e| anyhow!("bech32 HRP error: {}", e))?;
+    let address = bech32::encode::<bech32::Bech32>(hrp, bytes)
+        .map_err(|e| anyhow!("bech32 encode error: {}", e))?;
+    // Rejects unknown types and payloads of the wrong length.
+    parse_address(&address)?;
+    Ok(address)
+}
+
+/// Bech32 form of an address given as bech32, hex address bytes, hex CBOR
+/// (a byte string wrapping the address bytes) or a 28-byte script hash (its
+/// mainnet enterprise address).
+pub fn normalize_address(input: &str) -> Result<String> {
+    let input = input.trim();
+    let is_hex = !input.is_empty() && input.bytes().all(|b| b.is_ascii_hexdigit());
+    if !is_hex {
+        let address = input.to_lowercase();
+        parse_address(&address)?;
+        return Ok(address);
+    }
+    let bytes =
+        hex::decode(input).map_err(|e| anyhow!("invalid address hex '{}': {}", input, e))?;
+    if bytes.len() == HASH_LEN {
+        return script_address(input, Network::Mainnet);
+    }
+    // CBOR byte string with a one-byte length: 0x58 <len> <bytes>.
+    let bytes = match bytes.as_slice() {
+        [0x58, len, rest @ ..] if *len as usize == rest.len() => rest,
+        bytes => bytes,
+    };
+    address_from_bytes(bytes).map_err(|e| anyhow!("invalid address '{}': {}", input, e))
+}
+
+/// Payment credential of `address`.
+pub fn payment_credential(address: &str) -> Result<Credential> {
+    parse_address(address)?
+        .payment
+        .ok_or_else(|| anyhow!("`{}` has no payment credential", address))
+}
+
+/// Script hash of `address`'s payment credential; errors for key addresses.
+/// The inverse of [`script_address`].
+pub fn address_script_hash(address: &str) -> Result<String> {
+    let payment = payment_credential(address)?;
+    match payment.kind {
+        CredentialKind::Script => Ok(payment.hash),
+        CredentialKind::Key => Err(anyhow!("`{}` is not a script address", address)),
+    }
+}
+
+fn hash_bytes(credential: &Credential) -> Result<Vec<u8>> {
+    let bytes =
+        hex::decode(&credential.hash).map_err(|e| anyhow!("invalid credential hash hex: {}", e))?;
+    if bytes.len() != HASH_LEN {
+        return Err(anyhow!(
+            "credential hash must be {} bytes, got {}",
+            HASH_LEN,
+            bytes.len()
+        ));
+    }
+    Ok(bytes)
+}
+
+fn encode(hrp: &str, header: u8, credentials: &[&Credential]) -> Result<String> {
+    let mut payload = vec![header];
+    for credential in credentials {
+        payload.extend(hash_bytes(credential)?);
+    }
+    let hrp = bech32::Hrp::parse(hrp).map_err(|e| anyhow!("bech32 HRP error: {}", e))?;
+    bech32::encode::<bech32::Bech32>(hrp, &payload)
+        .map_err(|e| anyhow!("bech32 encode error: {}", e))
+}
+
+fn script_bit(credential: &Credential) -> u8 {
+    (credential.kind == CredentialKind::Script) as u8
+}
+
+/// Base address of `payment` with `stake`.
+pub fn base_address(payment: &Credential, stake: &Credential, network: Network) -> Result<String> {
+    let addr_type = script_bit(payment) | (script_bit(stake) << 1);
+    encode(
+        network.address_hrp(),
+        (addr_type << 4) | network.id(),
+        &[payment, stake],
+    )
+}
+
+/// Enterprise address (no stake credential) of `payment`.
+pub fn enterprise_address(payment: &Credential, network: Network) -> Result<String> {
+    let addr_type = 6 | script_bit(payment);
+    encode(
+        network.address_hrp(),
+        (addr_type << 4) | network.id(),
+        &[payment],
+    )
+}
+
+/// Reward address (`stake1...`) of `stake`.
+pub fn stake_address(stake: &Credential, network: Network) -> Result<String> {
+    let addr_type = 14 | script_bit(stake);
+    encode(
+        network.stake_hrp(),
+        (addr_type << 4) | network.id(),
+        &[stake],
+    )
+}
+
+/// Enterprise script address of `script_hash_hex` on `network`.
+pub fn script_address(script_hash_hex: &str, network: Network) -> Result<String> {
+    let script = Credential {
+        kind: CredentialKind::Script,
+        hash: script_hash_hex.to_string(),
+    };
+    enterprise_address(&script, network)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SENDER_ADDR: &str = "addr1qyfd4vf3pwalnfxucjut2xx653s9ukguwnlrnjjq4qvld76r7sjjnw3fd7elhw73fqtcjae3yxd9xwwn2x265mnadv3qyun95l";
+    const SENDER_PKH: &str = "12dab1310bbbf9a4dcc4b8b518daa4605e591c74fe39ca40a819f6fb";
+    const SENDER_STAKE_KH: &str = "43f42529ba296fb3fbbbd14817897731219a5339d35195aa6e7d6b22";
+    const ORDER_ADDR: &str = "addr1z8p79rpkcdz8x9d6tft0x0dx5mwuzac2sa4gm8cvkw5hcnzr7sjjnw3fd7elhw73fqtcjae3yxd9xwwn2x265mnadv3qhj56am";
+    const ORDER_SCRIPT_HASH: &str = "c3e28c36c3447315ba5a56f33da6a6ddc1770a876a8d9f0cb3a97c4c";
+
+    #[test]
+    fn test_parse_and_build_addresses() {
+        let sender = parse_address(SENDER_ADDR).unwrap();
+        assert_eq!(
+            (sender.kind, sender.network, sender.is_script()),
+            (AddressKind::Base, Network::Mainnet, false)
+        );
+        let payment = sender.payment.clone().unwrap();
+        let stake = sender.stake.clone().unwrap();
+        assert_eq!(
+            (payment.hash.as_str(), stake.hash.as_str()),
+            (SENDER_PKH, SENDER_STAKE_KH)
+        );
+        assert_eq!(
+            base_address(&payment, &stake, Network::Mainnet).unwrap(),
+            SENDER_ADDR
+        );
+
+        // The stake address parses back to the same credential.
+        let reward = parse_address(&sender.stake_address().unwrap()).unwrap();
+        assert_eq!(
+            (reward.kind, reward.stake),
+            (AddressKind::Reward, Some(stake))
+        );
+
+        let order = parse_address(ORDER_ADDR).unwrap();
+        assert!(order.is_script());
+        assert_eq!(address_script_hash(ORDER_ADDR).unwrap(), ORDER_SCRIPT_HASH);
+        assert!(address_script_hash(SENDER_ADDR).is_err());
+
+        // Script hash round-trips through enterprise addresses on both networks.
+        for network in [Network::Mainnet, Network::Testnet] {
+            let address = script_address(ORDER_SCRIPT_HASH, network).unwrap();
+            let parsed = parse_address(&address).unwrap();
+            assert_eq!(
+                (parsed.kind, parsed.network),
+                (AddressKind::Enterprise, network)
+            );
+            assert_eq!(address_script_hash(&address).unwrap(), ORDER_SCRIPT_HASH);
+        }
+        assert!(script_address(ORDER_SCRIPT_HASH, Network::Testnet)
+            .unwrap()
+            .starts_with("addr_test1w"));
+    }
+
+    #[test]
+    fn test_normalize_address_spellings() {
+        let (_, bytes) = bech32::decode(ORDER_ADDR).unwrap();
+        let raw = hex::encode(&bytes);
+        let cbor = format!("5839{}", raw);
+        for input in [ORDER_ADDR, &ORDER_ADDR.to_uppercase(), &raw, &cbor] {
+            assert_eq!(normalize_address(input).unwrap(), ORDER_ADDR);
+        }
+        assert_eq!(
+            normalize_address(ORDER_SCRIPT_HASH).unwrap(),
+            script_address(ORDER_SCRIPT_HASH, Network::Mainnet).unwrap()
+        );
+        assert!(normalize_address("addr1notanaddress").is_err());
+        assert!(normalize_address(&raw[..raw.len() - 2]).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! Retries with jittered exponential backoff and per-operation budgets.
+//!
+//! [`retry`] bounds the attempts of one request. A logical operation (a pair
+//! query, an export) makes many requests, so during an outage the per-request
+//! limits multiply; running the operation inside [`RetryBudget::scope`] caps the
+//! retries of all requests it makes together. Once the budget is spent, every
+//! further failure is returned immediately.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Retries shared by one logical operation when no budget is given.
+pub const DEFAULT_RETRY_BUDGET: u32 = 20;
+/// Upper bound of a single backoff delay.
+const MAX_DELAY_MS: u64 = 30_000;
+
+tokio::task_local! {
+    static BUDGET: RetryBudget;
+}
+
+/// Errors that know whether repeating the failed operation may succeed.
+/// Used by [`retry`] to give up early on permanent failures.
+pub trait Retryable {
+    fn is_retryable(&self) -> bool;
+}
+
+impl Retryable for crate::kupo::KupoError {
+    fn is_retryable(&self) -> bool {
+        crate::kupo::KupoError::is_retryable(self)
+    }
+}
+
+/// Typed Kupo, HTTP and JSON errors are classified; anything else (e.g. a
+/// webhook returning a non-success status) is retried.
+impl Retryable for anyhow::Error {
+    fn is_retryable(&self) -> bool {
+        if let Some(e) = self.downcast_ref::<crate::kupo::KupoError>() {
+            e.is_retryable()
+        } else if let Some(e) = self.downcast_ref::<reqwest::Error>() {
+            crate::kupo::is_retryable_http(e)
+        } else {
+            !self.is::<serde_json::Error>()
+        }
+    }
+}
+
+/// Retries available to every [`retry`] call made inside [`scope`](Self::scope).
+/// Clones share the same count.
+#[derive(Debug, Clone)]
+pub struct RetryBudget {
+    remaining: Arc<AtomicU32>,
+}
+
+impl Default for RetryBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_RETRY_BUDGET)
+    }
+}
+
+impl RetryBudget {
+    pub fn new(retries: u32) -> Self {
+        Self {
+            remaining: Arc::new(AtomicU32::new(retries)),
+        }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.remaining.load(Ordering::Relaxed)
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Take one retry; `false` once the budget is spent.
+    pub fn try_spend(&self) -> bool {
+        self.remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    /// Run `operation` with this budget applied to the retries it makes on the
+    /// current task. Work spawned onto other tasks is not covered.
+    pub async fn scope<F: Future>(&self, operation: F) -> F::Output {
+        BUDGET.scope(self.clone(), operation).await
+    }
+
+    /// The budget of the enclosing [`scope`](Self::scope), if any.
+    pub fn current() -> Option<RetryBudget> {
+        BUDGET.try_with(RetryBudget::clone).ok()
+    }
+
+    /// Run `operation` in the enclosing budget, or in a fresh default one when
+    /// none is active, so a logical operation is capped however it is called.
+    pub async fn ensure_scope<F: Future>(operation: F) -> F::Output {
+        match Self::current() {
+            Some(_) => operation.await,
+            None => Self::default().scope(operation).await,
+        }
+    }
+}
+
+/// Delay before retry `attempt` (0-based): `base_delay_ms * 2^attempt`, capped
+/// at 30s, of which the upper half is randomised so concurrent clients that
+/// failed together do not retry together.
+pub fn backoff_delay(base_delay_ms: u64, attempt: u32) -> Duration {
+    let delay = base_delay_ms
+        .saturating_mul(1u64 << attempt.min(5))
+        .min(MAX_DELAY_MS);
+    let half = delay / 2;
+    Duration::from_millis(half + fastrand::u64(0..=delay - half))
+}
+
+/// Run `f` up to `retries + 1` times with jittered exponential backoff,
+/// stopping at the first error that is not [`Retryable`] or when the
+/// enclosing [`RetryBudget`] is spent.
+pub async fn retry<T, E, F, Fut>(retries: u32, base_delay_ms: u64, f: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::fmt::Debug + Retryable,
+{
+    retry_if(retries, base_delay_ms, f, E::is_retryable).await
+}
+
+/// [`retry`] with an explicit `is_retryable` predicate.
+pub async fn retry_if<T, E, F, Fut, P>(
+    mut retries: u32,
+    base_delay_ms: u64,
+    mut f: F,
+    is_retryable: P,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::fmt::Debug,
+    P: Fn(&E) -> bool,
+{
+    let budget = RetryBudget::current();
+    let mut attempt = 0u32;
+    loop {
+        match f().await {
+            Ok(result) => return Ok(result),
+            Err(e) if retries == 0 || !is_retryable(&e) => return Err(e),
+            Err(e) if budget.as_ref().is_some_and(|b| !b.try_spend()) => {
+                eprintln!("[retry] retry budget exhausted, giving up ({:?})", e);
+                return Err(e);
+            }
+            Err(e) => {
+                let delay = backoff_delay(base_delay_ms, attempt);
+                eprintln!(
+                    "[retry] attempt {} failed ({:?}), retrying in {}ms...",
+                    attempt + 1,
+                    e,
+                    delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+                retries -= 1;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kupo::KupoError;
+
+    #[tokio::test]
+    async fn test_retry_stops_on_permanent_error() {
+        let calls = AtomicU32::new(0);
+        let result: Result<(), anyhow::Error> = retry(5, 1, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(KupoError::DatumNotFound("ab".to_string()).into())
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        calls.store(0, Ordering::SeqCst);
+        let _: Result<(), anyhow::Error> = retry(2, 1, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(KupoError::RateLimited.into())
+        })
+        .await;
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_budget_is_shared_across_requests() {
+        let calls = AtomicU32::new(0);
+        let failing = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), anyhow::Error>(KupoError::RateLimited.into())
+        };
+        let budget = RetryBudget::new(3);
+        budget
+            .scope(async {
+                let _ = retry(10, 1, failing).await;
+                let _ = retry(10, 1, failing).await;
+            })
+            .await;
+        // Two first attempts plus the three budgeted retries.
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+        assert!(budget.is_exhausted());
+
+        let delay = backoff_delay(1_000, 2);
+        assert!(delay >= Duration::from_millis(2_000) && delay <= Duration::from_millis(4_000));
+    }
+
+    #[tokio::test]
+    async fn test_ensure_scope_caps_unscoped_operations() {
+        let calls = AtomicU32::new(0);
+        let failing = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err::<(), anyhow::Error>(KupoError::RateLimited.into())
+        };
+        let operation = || async {
+            for _ in 0..3 {
+                let _ = retry(10, 1, failing).await;
+            }
+        };
+
+        RetryBudget::ensure_scope(operation()).await;
+        // Three first attempts plus the default budget, not 3 * 10 retries.
+        assert_eq!(calls.load(Ordering::SeqCst), 3 + DEFAULT_RETRY_BUDGET);
+
+        // Inside a scope, that budget is used instead.
+        calls.store(0, Ordering::SeqCst);
+        let budget = RetryBudget::new(2);
+        budget.scope(RetryBudget::ensure_scope(operation())).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 5);
+        assert!(budget.is_exhausted());
+    }
+}
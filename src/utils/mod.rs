@@ -1,4 +1,16 @@
 use anyhow::{anyhow, Result};
+use std::time::Duration;
+
+pub mod adaptive;
+pub mod address;
+pub mod backoff;
+
+pub use address::{
+    address_from_bytes, address_script_hash, base_address, enterprise_address, normalize_address,
+    parse_address, payment_credential, script_address, stake_address, AddressKind, Network,
+    ParsedAddress,
+};
+pub use backoff::{retry, retry_if, RetryBudget, Retryable};
 
 pub fn join_policy_id(policy_id: &str) -> String {
     policy_id.replace('.', "")
@@ -12,6 +24,19 @@ pub fn split_policy_id(policy_id: &str) -> String {
     }
 }
 
+/// Kupo match pattern for an asset unit (policy id + asset name hex, with or
+/// without a dot). A bare policy id matches every asset under it; a unit that
+/// is not hex is never split and goes through as a policy pattern.
+pub fn asset_pattern(unit: &str) -> String {
+    if unit.contains('.') {
+        unit.to_string()
+    } else if unit.len() > 56 && unit.bytes().all(|b| b.is_ascii_hexdigit()) {
+        format!("{}.{}", &unit[..56], &unit[56..])
+    } else {
+        format!("{}.*", unit)
+    }
+}
+
 pub fn is_shelly_address(address: &str) -> bool {
     address.starts_with("addr1") || address.starts_with("stake1")
 }
@@ -24,30 +49,33 @@ pub fn remove_trailing_slash(url: &str) -> String {
     }
 }
 
+/// Parse a CLI duration: `500ms`, `10s`, `2m`, `1h`, or bare seconds.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let n: u64 = number
+        .parse()
+        .map_err(|_| anyhow!("invalid duration '{}' (e.g. 10s, 500ms, 2m)", s))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(n)),
+        "" | "s" => Ok(Duration::from_secs(n)),
+        "m" => Ok(Duration::from_secs(n * 60)),
+        "h" => Ok(Duration::from_secs(n * 3600)),
+        _ => Err(anyhow!("invalid duration unit '{}' in '{}'", unit, s)),
+    }
+}
+
 /// Convert a Plutus script hash (28 bytes hex) to a mainnet enterprise script address (bech32 `addr1w...`).
 ///
 /// Cardano enterprise script address format:
 ///   - Header byte: 0x71 (type 7 = script credential, no staking, network 1 = mainnet)
 ///   - Payload: 28-byte script hash
 ///   - Encoded as bech32 with HRP "addr"
+///
+/// See [`script_address`] for other networks.
 pub fn script_hash_to_address(script_hash_hex: &str) -> Result<String> {
-    let hash_bytes = hex::decode(script_hash_hex)
-        .map_err(|e| anyhow!("invalid script hash hex: {}", e))?;
-    if hash_bytes.len() != 28 {
-        return Err(anyhow!(
-            "script hash must be 28 bytes, got {}",
-            hash_bytes.len()
-        ));
-    }
-    // Header byte 0x71 = 0b0111_0001:
-    //   upper nibble 0111 = type 7 (script credential, no staking)
-    //   lower nibble 0001 = network ID 1 (mainnet)
-    let mut payload = Vec::with_capacity(29);
-    payload.push(0x71);
-    payload.extend_from_slice(&hash_bytes);
-    let hrp = bech32::Hrp::parse("addr").map_err(|e| anyhow!("bech32 HRP error: {}", e))?;
-    bech32::encode::<bech32::Bech32>(hrp, &payload)
-        .map_err(|e| anyhow!("bech32 encode error: {}", e))
+    script_address(script_hash_hex, Network::Mainnet)
 }
 
 #[cfg(test)]
@@ -79,6 +107,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_asset_pattern() {
+        let policy = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f";
+        let dotted = format!("{}.534e454b", policy);
+        assert_eq!(asset_pattern(&format!("{}534e454b", policy)), dotted);
+        assert_eq!(asset_pattern(&dotted), dotted);
+        assert_eq!(asset_pattern(policy), format!("{}.*", policy));
+        // Short or non-hex units are not split (and must not panic).
+        assert_eq!(asset_pattern("abc"), "abc.*");
+        let odd = "é".repeat(40);
+        assert_eq!(asset_pattern(&odd), format!("{}.*", odd));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("10s").unwrap(), Duration::from_secs(10));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("7").unwrap(), Duration::from_secs(7));
+        assert!(parse_duration("fast").is_err());
+        assert!(parse_duration("3d").is_err());
+    }
+
     #[test]
     fn test_script_hash_to_address_invalid_length() {
         assert!(script_hash_to_address("abcd").is_err());
@@ -89,26 +140,3 @@ mod tests {
         assert!(script_hash_to_address("zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz").is_err());
     }
 }
-
-pub async fn retry<T, E, F, Fut>(mut retries: u32, base_delay_ms: u64, mut f: F) -> Result<T, E>
-where
-    F: FnMut() -> Fut,
-    Fut: std::future::Future<Output = Result<T, E>>,
-    E: std::fmt::Debug,
-{
-    let mut attempt = 0u32;
-    loop {
-        match f().await {
-            Ok(result) => return Ok(result),
-            Err(e) if retries == 0 => return Err(e),
-            Err(e) => {
-                // Exponential backoff: base_delay * 2^attempt, capped at 30s
-                let delay = (base_delay_ms * (1u64 << attempt.min(5))).min(30_000);
-                eprintln!("[retry] attempt {} failed ({:?}), retrying in {}ms...", attempt + 1, e, delay);
-                tokio::time::sleep(tokio::time::Duration::from_millis(delay)).await;
-                retries -= 1;
-                attempt += 1;
-            }
-        }
-    }
-}
@@ -0,0 +1,149 @@
+//! Optional token verification layer.
+//!
+//! Cross-checks pool assets against a user-supplied allowlist/denylist and
+//! flags likely scam duplicates: tokens that reuse the ticker of an
+//! allowlisted token under a different policy.
+
+use crate::models::asset::{from_identifier, token_identifier};
+use crate::models::{LiquidityPool, Token};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// On-disk format for token lists.
+///
+/// ```json
+/// { "allowlist": ["<policy><name_hex>", ...], "denylist": ["<policy><name_hex>", ...] }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenLists {
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+    #[serde(default)]
+    pub denylist: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TokenVerifier {
+    allowlist: HashSet<String>,
+    denylist: HashSet<String>,
+    /// Lowercased ticker → allowlisted identifier carrying that ticker.
+    tickers: HashMap<String, String>,
+}
+
+fn normalize(id: &str) -> String {
+    id.replace('.', "").to_lowercase()
+}
+
+fn ticker_key(token: &Token) -> Option<String> {
+    let name = token.as_asset()?.asset_name();
+    let name = name.trim().to_lowercase();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+impl TokenVerifier {
+    pub fn new(lists: TokenLists) -> Self {
+        let allowlist: HashSet<String> = lists.allowlist.iter().map(|id| normalize(id)).collect();
+        let denylist = lists.denylist.iter().map(|id| normalize(id)).collect();
+
+        let mut tickers = HashMap::new();
+        for id in &allowlist {
+            if let Some(ticker) = ticker_key(&from_identifier(id, 0)) {
+                tickers.insert(ticker, id.clone());
+            }
+        }
+
+        Self {
+            allowlist,
+            denylist,
+            tickers,
+        }
+    }
+
+    /// Load allowlist/denylist from a JSON file (see [`TokenLists`]).
+    pub fn from_file(path: &str) -> Result<Self> {
+        let lists: TokenLists = crate::cache::load_from_file(path)
+            .map_err(|e| anyhow!("Failed to load token lists: {}", e))?;
+        Ok(Self::new(lists))
+    }
+
+    pub fn is_denied(&self, token: &Token) -> bool {
+        !token.is_lovelace() && self.denylist.contains(&token_identifier(token))
+    }
+
+    /// ADA and allowlisted tokens are verified; denylisted tokens never are.
+    pub fn is_verified(&self, token: &Token) -> bool {
+        match token {
+            Token::Lovelace => true,
+            _ => {
+                let id = token_identifier(token);
+                !self.denylist.contains(&id) && self.allowlist.contains(&id)
+            }
+        }
+    }
+
+    /// Returns the allowlisted identifier this token impersonates, if any:
+    /// same ticker as an allowlisted token but a different policy.
+    pub fn scam_duplicate_of(&self, token: &Token) -> Option<&str> {
+        let ticker = ticker_key(token)?;
+        let original = self.tickers.get(&ticker)?;
+        if *original == token_identifier(token) {
+            return None;
+        }
+        Some(original.as_str())
+    }
+
+    /// A pool is verified when both of its assets are.
+    pub fn is_pool_verified(&self, pool: &LiquidityPool) -> bool {
+        self.is_verified(&pool.asset_a) && self.is_verified(&pool.asset_b)
+    }
+
+    /// Identifier-based variant of [`is_pool_verified`](Self::is_pool_verified),
+    /// used for export rows that only carry identifiers.
+    pub fn is_pair_verified(&self, asset_a: &str, asset_b: &str) -> bool {
+        self.is_verified(&from_identifier(asset_a, 0))
+            && self.is_verified(&from_identifier(asset_b, 0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNEK: &str = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+    const FAKE_SNEK: &str = "00000000000000000000000000000000000000000000000000000000534e454b";
+
+    #[test]
+    fn test_scam_duplicate_detection() {
+        let verifier = TokenVerifier::new(TokenLists {
+            allowlist: vec![format!("{}.{}", &SNEK[..56], &SNEK[56..])],
+            denylist: vec![],
+        });
+
+        let snek = from_identifier(SNEK, 0);
+        let fake = from_identifier(FAKE_SNEK, 0);
+
+        assert!(verifier.is_verified(&Token::Lovelace));
+        assert!(verifier.is_verified(&snek));
+        assert!(!verifier.is_verified(&fake));
+        assert_eq!(verifier.scam_duplicate_of(&snek), None);
+        assert_eq!(verifier.scam_duplicate_of(&fake), Some(SNEK));
+        assert!(verifier.is_pair_verified("lovelace", SNEK));
+        assert!(!verifier.is_pair_verified("lovelace", FAKE_SNEK));
+    }
+
+    #[test]
+    fn test_denylist_overrides_allowlist() {
+        let verifier = TokenVerifier::new(TokenLists {
+            allowlist: vec![SNEK.to_string()],
+            denylist: vec![SNEK.to_string()],
+        });
+        let snek = from_identifier(SNEK, 0);
+        assert!(verifier.is_denied(&snek));
+        assert!(!verifier.is_verified(&snek));
+    }
+}
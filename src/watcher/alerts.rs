@@ -0,0 +1,640 @@
+//! Threshold alerts on top of [`PoolWatcher`](super::PoolWatcher) events.
+//!
+//! An [`Alerter`] checks every [`PoolEvent::PoolUpdated`] against its rules and
+//! emits an [`Alert`] per rule that fired, on a channel and optionally as a
+//! JSON POST to a webhook.
+//!
+//! [`AlertRule::TriangularArbitrage`] looks beyond the updated pool: the
+//! alerter keeps the latest state of every pool it has seen and, on each
+//! change, reports the ADA → X → Y → ADA cycles through that pool (see
+//! [`ArbScanner`]).
+//!
+//! [`AlertRule::OracleDeviation`] compares pool prices with the price the
+//! reference prices of an [`OracleReader`] imply, re-read at most once per
+//! [`ORACLE_REFRESH`]. Token/token pools are compared when both tokens have a
+//! reference price.
+//!
+//! [`AlertRule::SupplyMinted`] is not tied to a pool event: with a
+//! [`SupplyTracker`], [`Alerter::check_supply`] re-reads the supply of the
+//! known pools' tokens at most once per [`SUPPLY_REFRESH`] and alerts on every
+//! pool of a token that was minted.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+use super::supply::{SupplyChange, SupplyTracker};
+use super::PoolEvent;
+use crate::models::{AssetId, LiquidityPool, Token};
+use crate::oracles::{OraclePrice, OracleReader};
+use crate::router::{ArbOpportunity, ArbScanner, RoutePool};
+
+const CHANNEL_CAPACITY: usize = 1024;
+const WEBHOOK_RETRIES: u32 = 3;
+const RETRY_BASE_DELAY_MS: u64 = 500;
+/// Oracle feeds are re-read at most this often.
+pub const ORACLE_REFRESH: Duration = Duration::from_secs(60);
+/// Token supplies are re-read at most this often.
+pub const SUPPLY_REFRESH: Duration = Duration::from_secs(300);
+
+/// A condition on the change between two rounds of one pool.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum AlertRule {
+    /// Either reserve fell by more than `percent` since the previous round.
+    ReserveDrop { percent: f64 },
+    /// Either reserve fell to `min_reserve` base units or below (from above it).
+    PoolDrained { min_reserve: u64 },
+    /// `pool_fee_percent` changed.
+    FeeChanged,
+    /// A cycle through the changed pool returns at least `min_profit_lovelace`
+    /// after fees, with no hop taking more than `max_depth_percent` of its
+    /// pool's input reserve.
+    TriangularArbitrage {
+        min_profit_lovelace: u64,
+        #[serde(default = "default_max_depth_percent")]
+        max_depth_percent: f64,
+    },
+    /// The pool's price is more than `percent` away from the one its tokens'
+    /// oracle reference prices imply.
+    OracleDeviation { percent: f64 },
+    /// The supply of one of the pool's tokens grew by more than `percent`
+    /// since it was last read.
+    SupplyMinted { percent: f64 },
+}
+
+fn default_max_depth_percent() -> f64 {
+    crate::router::arbitrage::DEFAULT_MAX_DEPTH_PERCENT
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    pub rule: AlertRule,
+    pub dex_identifier: String,
+    pub pool_id: String,
+    pub pair: String,
+    pub message: String,
+    pub previous_reserves: (u64, u64),
+    pub current_reserves: (u64, u64),
+    /// The cycle found, for [`AlertRule::TriangularArbitrage`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arbitrage: Option<ArbOpportunity>,
+    /// The deviation found, for [`AlertRule::OracleDeviation`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deviation: Option<Box<PriceDeviation>>,
+    /// The mint, for [`AlertRule::SupplyMinted`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supply: Option<Box<SupplyChange>>,
+}
+
+/// A pool's price against the one its oracle reference prices imply.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PriceDeviation {
+    /// [`LiquidityPool::price`]: `asset_a` per `asset_b`, decimal-adjusted.
+    pub pool_price: f64,
+    /// The same price from the reference prices.
+    pub oracle_price: f64,
+    /// `(pool_price - oracle_price) / oracle_price`, in percent.
+    pub deviation_percent: f64,
+    /// The reference price of each non-ADA side.
+    pub references: Vec<OraclePrice>,
+}
+
+fn drop_percent(previous: u64, current: u64) -> f64 {
+    if previous == 0 || current >= previous {
+        return 0.0;
+    }
+    (previous - current) as f64 / previous as f64 * 100.0
+}
+
+impl AlertRule {
+    /// Description of the change if this rule fires for `previous -> current`.
+    /// [`TriangularArbitrage`](Self::TriangularArbitrage) and
+    /// [`OracleDeviation`](Self::OracleDeviation) need more than the pool and
+    /// never fire here; [`Alerter`] evaluates them.
+    pub fn check(&self, previous: &LiquidityPool, current: &LiquidityPool) -> Option<String> {
+        match *self {
+            AlertRule::ReserveDrop { percent } => {
+                let drop_a = drop_percent(previous.reserve_a, current.reserve_a);
+                let drop_b = drop_percent(previous.reserve_b, current.reserve_b);
+                let (side, drop) = if drop_a >= drop_b {
+                    ("a", drop_a)
+                } else {
+                    ("b", drop_b)
+                };
+                (drop > percent).then(|| format!("reserve_{} dropped {:.2}%", side, drop))
+            }
+            AlertRule::PoolDrained { min_reserve } => {
+                let drained = |prev: u64, cur: u64| prev > min_reserve && cur <= min_reserve;
+                (drained(previous.reserve_a, current.reserve_a)
+                    || drained(previous.reserve_b, current.reserve_b))
+                .then(|| {
+                    format!(
+                        "pool drained: reserves {}/{}",
+                        current.reserve_a, current.reserve_b
+                    )
+                })
+            }
+            AlertRule::FeeChanged => {
+                (previous.pool_fee_percent != current.pool_fee_percent).then(|| {
+                    format!(
+                        "fee changed {}% -> {}%",
+                        previous.pool_fee_percent, current.pool_fee_percent
+                    )
+                })
+            }
+            AlertRule::TriangularArbitrage { .. }
+            | AlertRule::OracleDeviation { .. }
+            | AlertRule::SupplyMinted { .. } => None,
+        }
+    }
+}
+
+/// Rules from a JSON array file, e.g.
+/// `[{"rule": "reserve_drop", "percent": 30.0}, {"rule": "fee_changed"}]`.
+pub fn load_rules(path: &str) -> Result<Vec<AlertRule>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read alert rules {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| anyhow!("Invalid alert rules {}: {}", path, e))
+}
+
+pub struct Alerter {
+    rules: Vec<AlertRule>,
+    webhook: Option<String>,
+    client: reqwest::Client,
+    /// Latest state of every pool seen, by `(dex, pool_id)`; only kept when
+    /// an arbitrage or supply rule needs it.
+    pools: Mutex<HashMap<(String, String), LiquidityPool>>,
+    oracles: Option<OracleReader>,
+    /// Latest reference price by token.
+    reference_prices: Mutex<HashMap<AssetId, OraclePrice>>,
+    prices_read_at: Mutex<Option<Instant>>,
+    supply: Option<SupplyTracker>,
+    supply_read_at: Mutex<Option<Instant>>,
+}
+
+impl Alerter {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("Failed to build HTTP client");
+        Self {
+            rules,
+            webhook: None,
+            client,
+            pools: Mutex::new(HashMap::new()),
+            oracles: None,
+            reference_prices: Mutex::new(HashMap::new()),
+            prices_read_at: Mutex::new(None),
+            supply: None,
+            supply_read_at: Mutex::new(None),
+        }
+    }
+
+    /// Track the supply of the known pools' tokens for
+    /// [`AlertRule::SupplyMinted`].
+    pub fn with_supply_tracker(mut self, tracker: SupplyTracker) -> Self {
+        self.supply = Some(tracker);
+        self
+    }
+
+    /// Alerts for the tokens minted since the last read, one per rule and
+    /// known pool holding the token. Reads at most once per
+    /// [`SUPPLY_REFRESH`]; does nothing without a tracker or a
+    /// [`SupplyMinted`](AlertRule::SupplyMinted) rule.
+    pub async fn check_supply(&self) -> Vec<Alert> {
+        let Some(tracker) = &self.supply else {
+            return Vec::new();
+        };
+        if !self
+            .rules
+            .iter()
+            .any(|rule| matches!(rule, AlertRule::SupplyMinted { .. }))
+        {
+            return Vec::new();
+        }
+        let read_at = *self.supply_read_at.lock().unwrap();
+        if read_at.is_some_and(|at| at.elapsed() < SUPPLY_REFRESH) {
+            return Vec::new();
+        }
+        let pools: Vec<LiquidityPool> = self.pools.lock().unwrap().values().cloned().collect();
+        let mut tokens: Vec<AssetId> = pools
+            .iter()
+            .flat_map(|pool| [AssetId::from(&pool.asset_a), AssetId::from(&pool.asset_b)])
+            .collect();
+        tokens.sort();
+        tokens.dedup();
+        let changes = tracker.observe(&tokens).await;
+        *self.supply_read_at.lock().unwrap() = Some(Instant::now());
+
+        let mut alerts = Vec::new();
+        for change in changes.iter().filter(|change| change.is_mint()) {
+            let token = AssetId::from(change.token.as_str());
+            let holders = pools.iter().filter(|pool| {
+                AssetId::from(&pool.asset_a) == token || AssetId::from(&pool.asset_b) == token
+            });
+            for pool in holders {
+                for rule in &self.rules {
+                    let AlertRule::SupplyMinted { percent } = *rule else {
+                        continue;
+                    };
+                    if change.change_percent <= percent {
+                        continue;
+                    }
+                    let reserves = (pool.reserve_a, pool.reserve_b);
+                    alerts.push(Alert {
+                        rule: *rule,
+                        dex_identifier: pool.dex_identifier.clone(),
+                        pool_id: pool.pool_id.clone(),
+                        pair: pool.pair(),
+                        message: format!(
+                            "{} supply minted {:+.2}%: {} -> {}",
+                            change.token, change.change_percent, change.previous, change.current
+                        ),
+                        previous_reserves: reserves,
+                        current_reserves: reserves,
+                        arbitrage: None,
+                        deviation: None,
+                        supply: Some(Box::new(change.clone())),
+                    });
+                }
+            }
+        }
+        alerts
+    }
+
+    /// Read reference prices for [`AlertRule::OracleDeviation`] from
+    /// `reader`'s feeds.
+    pub fn with_oracles(mut self, reader: OracleReader) -> Self {
+        self.oracles = Some(reader);
+        self
+    }
+
+    /// Replace the reference prices of the tokens in `prices`.
+    pub fn set_reference_prices(&self, prices: impl IntoIterator<Item = OraclePrice>) {
+        let mut reference = self.reference_prices.lock().unwrap();
+        for price in prices {
+            reference.insert(AssetId::from(price.token.as_str()), price);
+        }
+    }
+
+    /// Re-read the oracle feeds if they were last read more than
+    /// [`ORACLE_REFRESH`] ago. Does nothing without oracles.
+    pub async fn refresh_reference_prices(&self) {
+        let Some(reader) = &self.oracles else {
+            return;
+        };
+        let read_at = *self.prices_read_at.lock().unwrap();
+        if read_at.is_some_and(|at| at.elapsed() < ORACLE_REFRESH) {
+            return;
+        }
+        let prices = reader.get_prices().await;
+        self.set_reference_prices(prices);
+        *self.prices_read_at.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// How far `pool`'s price is from the one the reference prices imply, or
+    /// `None` unless every non-ADA side has an unexpired reference price.
+    pub fn price_deviation(&self, pool: &LiquidityPool) -> Option<PriceDeviation> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let prices = self.reference_prices.lock().unwrap();
+        let mut references = Vec::new();
+        let mut ada_per = |token: &Token| match token {
+            Token::Lovelace => Some(1.0),
+            Token::Asset(_) => {
+                let reference = prices
+                    .get(&AssetId::from(token))
+                    .filter(|p| !p.is_expired(now) && p.ada_per_token > 0.0)?;
+                references.push(reference.clone());
+                Some(reference.ada_per_token)
+            }
+        };
+        let (ada_per_a, ada_per_b) = (ada_per(&pool.asset_a)?, ada_per(&pool.asset_b)?);
+        if references.is_empty() {
+            return None;
+        }
+        let pool_price = pool.price();
+        let oracle_price = ada_per_b / ada_per_a;
+        Some(PriceDeviation {
+            pool_price,
+            oracle_price,
+            deviation_percent: (pool_price - oracle_price) / oracle_price * 100.0,
+            references,
+        })
+    }
+
+    /// Also POST each alert as JSON to `url`.
+    pub fn with_webhook(mut self, url: &str) -> Self {
+        self.webhook = Some(url.to_string());
+        self
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Alerts fired by one watcher event. Only updates can fire; created
+    /// pools are remembered for arbitrage rules.
+    pub fn evaluate(&self, event: &PoolEvent) -> Vec<Alert> {
+        let tracks_pools = self.rules.iter().any(|rule| {
+            matches!(
+                rule,
+                AlertRule::TriangularArbitrage { .. } | AlertRule::SupplyMinted { .. }
+            )
+        });
+        let (previous, current) = match event {
+            PoolEvent::PoolUpdated { previous, current } => (previous, current),
+            PoolEvent::PoolCreated { pool } if tracks_pools => {
+                self.remember(pool);
+                return Vec::new();
+            }
+            _ => return Vec::new(),
+        };
+        let pools = tracks_pools.then(|| self.remember(current));
+        let alert = |rule: &AlertRule, message: String, arbitrage, deviation| Alert {
+            rule: *rule,
+            dex_identifier: current.dex_identifier.clone(),
+            pool_id: current.pool_id.clone(),
+            pair: current.pair(),
+            message,
+            previous_reserves: (previous.reserve_a, previous.reserve_b),
+            current_reserves: (current.reserve_a, current.reserve_b),
+            arbitrage,
+            deviation,
+            supply: None,
+        };
+        let mut alerts = Vec::new();
+        for rule in &self.rules {
+            if let AlertRule::TriangularArbitrage {
+                min_profit_lovelace,
+                max_depth_percent,
+            } = *rule
+            {
+                let cycles = ArbScanner::new()
+                    .with_min_profit(min_profit_lovelace)
+                    .with_max_depth_percent(max_depth_percent)
+                    .triangular_through(
+                        pools.as_deref().unwrap_or(&[]),
+                        &current.dex_identifier,
+                        &current.pool_id,
+                    );
+                for arb in cycles {
+                    let message = format!(
+                        "triangular arbitrage: {} lovelace profit on {} in via {}",
+                        arb.profit_lovelace,
+                        arb.amount_in,
+                        arb.hops
+                            .iter()
+                            .map(|h| format!("{}:{}", h.dex_identifier, h.pool_id))
+                            .collect::<Vec<_>>()
+                            .join(" -> ")
+                    );
+                    alerts.push(alert(rule, message, Some(arb), None));
+                }
+            } else if let AlertRule::OracleDeviation { percent } = *rule {
+                let Some(deviation) = self.price_deviation(current) else {
+                    continue;
+                };
+                if deviation.deviation_percent.abs() > percent {
+                    let message = format!(
+                        "price {:.6} is {:+.2}% from oracle {:.6} ({})",
+                        deviation.pool_price,
+                        deviation.deviation_percent,
+                        deviation.oracle_price,
+                        deviation
+                            .references
+                            .iter()
+                            .map(|r| r.feed.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                    alerts.push(alert(rule, message, None, Some(Box::new(deviation))));
+                }
+            } else if let Some(message) = rule.check(previous, current) {
+                alerts.push(alert(rule, message, None, None));
+            }
+        }
+        alerts
+    }
+
+    /// Record `pool`'s latest state and return every known pool.
+    fn remember(&self, pool: &LiquidityPool) -> Vec<RoutePool> {
+        let mut pools = self.pools.lock().unwrap();
+        pools.insert(
+            (pool.dex_identifier.clone(), pool.pool_id.clone()),
+            pool.clone(),
+        );
+        pools.values().cloned().map(RoutePool::from).collect()
+    }
+
+    async fn post(&self, url: &str, alert: &Alert) -> Result<()> {
+        crate::utils::retry(WEBHOOK_RETRIES, RETRY_BASE_DELAY_MS, || async {
+            let status = self.client.post(url).json(alert).send().await?.status();
+            if !status.is_success() {
+                return Err(anyhow!("alert webhook {} returned {}", url, status));
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Evaluate watcher events on a background task, forwarding alerts to the
+    /// returned channel (and the webhook, if set).
+    ///
+    /// Webhook failures are logged and do not stop the task; it ends when the
+    /// event channel closes or the alert receiver is dropped.
+    pub fn spawn(self, mut events: mpsc::Receiver<PoolEvent>) -> mpsc::Receiver<Alert> {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                self.refresh_reference_prices().await;
+                let mut alerts = self.evaluate(&event);
+                alerts.extend(self.check_supply().await);
+                for alert in alerts {
+                    if let Some(url) = &self.webhook {
+                        if let Err(e) = self.post(url, &alert).await {
+                            eprintln!("[alerts] {}", e);
+                        }
+                    }
+                    if tx.send(alert).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Token;
+
+    fn pool(reserve_a: u64, reserve_b: u64, fee: f64) -> Box<LiquidityPool> {
+        Box::new(LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            Token::Lovelace,
+            reserve_a,
+            reserve_b,
+            "addr",
+            fee,
+            "pool",
+        ))
+    }
+
+    #[test]
+    fn test_rules_fire_on_updates() {
+        let alerter = Alerter::new(vec![
+            AlertRule::ReserveDrop { percent: 20.0 },
+            AlertRule::PoolDrained { min_reserve: 100 },
+            AlertRule::FeeChanged,
+        ]);
+
+        let event = PoolEvent::PoolUpdated {
+            previous: pool(1_000, 1_000, 0.3),
+            current: pool(900, 50, 0.3),
+        };
+        let alerts = alerter.evaluate(&event);
+        let rules: Vec<AlertRule> = alerts.iter().map(|a| a.rule).collect();
+        assert_eq!(
+            rules,
+            [
+                AlertRule::ReserveDrop { percent: 20.0 },
+                AlertRule::PoolDrained { min_reserve: 100 }
+            ]
+        );
+        assert_eq!(alerts[0].message, "reserve_b dropped 95.00%");
+
+        let event = PoolEvent::PoolUpdated {
+            previous: pool(1_000, 1_000, 0.3),
+            current: pool(1_000, 1_000, 1.0),
+        };
+        assert_eq!(alerter.evaluate(&event)[0].rule, AlertRule::FeeChanged);
+
+        let created = PoolEvent::PoolCreated {
+            pool: pool(0, 0, 0.3),
+        };
+        assert!(alerter.evaluate(&created).is_empty());
+    }
+
+    #[test]
+    fn test_oracle_deviation() {
+        use crate::models::asset::from_identifier;
+
+        let snek = "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b";
+        let hosky = "a0028f350aaabe0545fdcb56b039bfb08e4bb4d8c4d7c3c7d481c235484f534b59";
+        // 0.5 SNEK per ADA, i.e. 2 ADA per SNEK.
+        let current = Box::new(LiquidityPool::new(
+            "MinswapV2",
+            from_identifier(snek, 6),
+            Token::Lovelace,
+            1_000_000,
+            2_000_000,
+            "addr",
+            0.3,
+            "pool",
+        ));
+        let event = PoolEvent::PoolUpdated {
+            previous: current.clone(),
+            current,
+        };
+        let alerter = Alerter::new(vec![AlertRule::OracleDeviation { percent: 10.0 }]);
+        assert!(alerter.evaluate(&event).is_empty());
+
+        let reference = |feed: &str, token: &str, ada_per_token| OraclePrice {
+            feed: feed.into(),
+            token: token.into(),
+            ada_per_token,
+            published_at: None,
+            expires_at: None,
+            slot: None,
+        };
+        alerter.set_reference_prices([reference("SNEK-ADA", snek, 1.9)]);
+        assert!(alerter.evaluate(&event).is_empty());
+        // The oracle implies 0.625 SNEK per ADA.
+        alerter.set_reference_prices([reference("SNEK-ADA", snek, 1.6)]);
+        let alerts = alerter.evaluate(&event);
+        assert_eq!(alerts.len(), 1);
+        let deviation = alerts[0].deviation.as_ref().unwrap();
+        assert!((deviation.deviation_percent + 20.0).abs() < 1e-9);
+        assert!(alerts[0].message.contains("-20.00% from oracle"));
+
+        // SNEK/HOSKY needs both references: 1.6 / 0.004 = 400 HOSKY per SNEK.
+        let cross = LiquidityPool::new(
+            "MinswapV2",
+            from_identifier(snek, 6),
+            from_identifier(hosky, 0),
+            1_000_000,
+            400,
+            "addr",
+            0.3,
+            "cross",
+        );
+        assert!(alerter.price_deviation(&cross).is_none());
+        alerter.set_reference_prices([reference("HOSKY-ADA", hosky, 0.004)]);
+        let deviation = alerter.price_deviation(&cross).unwrap();
+        assert!(deviation.deviation_percent.abs() < 1e-9);
+        assert_eq!(deviation.references.len(), 2);
+    }
+
+    #[test]
+    fn test_arbitrage_rule_sees_other_pools() {
+        use crate::models::asset::from_identifier;
+
+        let x = from_identifier(
+            "279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b",
+            0,
+        );
+        let y = from_identifier(
+            "8db269c3ec630e06ae29f74bc39edd1f87c819f1056206e879a1cd61446a65644d6963726f555344",
+            0,
+        );
+        let amm = |id: &str, a: &Token, b: &Token, reserve_b: u64| {
+            Box::new(LiquidityPool::new(
+                "MinswapV2",
+                a.clone(),
+                b.clone(),
+                100_000_000_000,
+                reserve_b,
+                "addr",
+                0.3,
+                id,
+            ))
+        };
+        let alerter = Alerter::new(vec![AlertRule::TriangularArbitrage {
+            min_profit_lovelace: 1_000_000,
+            max_depth_percent: 5.0,
+        }]);
+        for pool in [
+            amm("ada-x", &Token::Lovelace, &x, 100_000_000_000),
+            amm("ada-y", &Token::Lovelace, &y, 100_000_000_000),
+        ] {
+            assert!(alerter
+                .evaluate(&PoolEvent::PoolCreated { pool })
+                .is_empty());
+        }
+
+        // The X/Y pool moves to 0.9 X per Y: ADA -> X -> Y -> ADA pays.
+        let event = PoolEvent::PoolUpdated {
+            previous: amm("x-y", &x, &y, 100_000_000_000),
+            current: amm("x-y", &x, &y, 111_111_111_111),
+        };
+        let alerts = alerter.evaluate(&event);
+        assert_eq!(alerts.len(), 1);
+        let arb = alerts[0].arbitrage.as_ref().unwrap();
+        assert!(arb.profit_lovelace >= 1_000_000);
+        assert_eq!(alerts[0].pool_id, "x-y");
+        assert!(alerts[0].message.starts_with("triangular arbitrage"));
+        let json = serde_json::to_value(&alerts[0]).unwrap();
+        assert_eq!(json["arbitrage"]["hops"][1]["pool_id"], "x-y");
+    }
+}
@@ -0,0 +1,255 @@
+//! Pool events from a chain-sync stream instead of Kupo polling.
+//!
+//! [`ChainAdapter`] takes transaction outputs — Oura v1 JSON events via
+//! [`ChainAdapter::handle_oura_event`], or any other source (e.g. pallas
+//! blocks) converted to [`ChainOutput`] — runs each through the base parser of
+//! every configured DEX, and diffs the decoded pools like
+//! [`PoolWatcher`](super::PoolWatcher) does, emitting [`PoolEvent`]s.
+//!
+//! Inline datums are decoded locally. Hash-only datums are taken from Oura
+//! `plutus_datum` events when seen first, otherwise fetched through the
+//! DEX's Kupo. VyFinance is not suited: its parser calls the VyFi API per
+//! output. Rollbacks are not tracked: a rolled-back pool state is
+//! superseded by the next output of that pool.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{changed, pool_key, PoolEvent};
+use crate::dex::BaseDex;
+use crate::models::{LiquidityPool, Utxo};
+use crate::plutus::PlutusData;
+
+/// Hash-only datums kept from `plutus_datum` events before being dropped.
+const MAX_PENDING_DATUMS: usize = 10_000;
+
+/// A transaction output from a chain-sync source.
+#[derive(Debug, Clone, Default)]
+pub struct ChainOutput {
+    pub tx_hash: String,
+    pub output_index: u32,
+    pub slot: Option<u64>,
+    pub address: String,
+    /// Unit (`lovelace` or policy id + asset name hex) → quantity.
+    pub value: HashMap<String, u64>,
+    pub datum_hash: Option<String>,
+    /// Inline datum (or the resolved hash datum) as CBOR hex.
+    pub datum_cbor: Option<String>,
+}
+
+impl ChainOutput {
+    fn to_utxo(&self) -> Utxo {
+        let mut utxo =
+            Utxo::from_raw_output(&self.address, &self.value, self.datum_cbor.as_deref());
+        utxo.tx_hash = self.tx_hash.clone();
+        utxo.tx_index = self.output_index;
+        utxo.output_index = self.output_index;
+        utxo.slot = self.slot;
+        // A datum not seen on the stream is left for the DEX to fetch by hash.
+        if self.datum_cbor.is_none() && self.datum_hash.is_some() {
+            utxo.data_hash = self.datum_hash.clone();
+            utxo.datum_type = Some("hash".to_string());
+        }
+        utxo
+    }
+}
+
+pub struct ChainAdapter {
+    dexes: Vec<Arc<dyn BaseDex>>,
+    last: HashMap<String, LiquidityPool>,
+    datums: HashMap<String, String>,
+}
+
+/// Convert Oura's detailed-schema Plutus JSON to CBOR hex. Maps are not
+/// supported (no pool datum of the supported DEXes uses one).
+fn plutus_json_to_cbor(json: &JsonValue) -> Result<String> {
+    fn convert(json: &JsonValue) -> Result<PlutusData> {
+        if let Some(constructor) = json.get("constructor").and_then(JsonValue::as_u64) {
+            let fields = json
+                .get("fields")
+                .and_then(JsonValue::as_array)
+                .map(|f| f.iter().map(convert).collect::<Result<Vec<_>>>())
+                .transpose()?
+                .unwrap_or_default();
+            return Ok(PlutusData::Constr(constructor, fields));
+        }
+        if let Some(int) = json.get("int") {
+            let value = match int {
+                JsonValue::Number(n) => n.to_string(),
+                JsonValue::String(s) => s.clone(),
+                _ => return Err(anyhow!("invalid plutus int: {}", int)),
+            };
+            return Ok(PlutusData::Int(value.parse()?));
+        }
+        if let Some(bytes) = json.get("bytes").and_then(JsonValue::as_str) {
+            return PlutusData::bytes_hex(bytes);
+        }
+        if let Some(list) = json.get("list").and_then(JsonValue::as_array) {
+            return Ok(PlutusData::List(
+                list.iter().map(convert).collect::<Result<Vec<_>>>()?,
+            ));
+        }
+        Err(anyhow!("unsupported plutus json: {}", json))
+    }
+    convert(json)?.to_cbor_hex()
+}
+
+/// Parse an Oura v1 `tx_output` event into a [`ChainOutput`].
+fn oura_output(event: &JsonValue) -> Option<ChainOutput> {
+    let output = event.get("tx_output")?;
+    let context = event.get("context");
+    let ctx_str = |key: &str| {
+        context
+            .and_then(|c| c.get(key))
+            .and_then(JsonValue::as_str)
+            .map(str::to_string)
+    };
+    let ctx_u64 = |key: &str| context.and_then(|c| c.get(key)).and_then(JsonValue::as_u64);
+
+    let mut value = HashMap::new();
+    value.insert("lovelace".to_string(), output.get("amount")?.as_u64()?);
+    for asset in output
+        .get("assets")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let policy = asset.get("policy").and_then(JsonValue::as_str)?;
+        let name = asset.get("asset").and_then(JsonValue::as_str).unwrap_or("");
+        let amount = asset.get("amount").and_then(JsonValue::as_u64)?;
+        *value.entry(format!("{}{}", policy, name)).or_default() += amount;
+    }
+
+    let inline = output.get("inline_datum").filter(|d| !d.is_null());
+    let datum_cbor = inline
+        .and_then(|d| d.get("plutus_data"))
+        .and_then(|d| plutus_json_to_cbor(d).ok());
+    let datum_hash = output
+        .get("datum_hash")
+        .and_then(JsonValue::as_str)
+        .or_else(|| {
+            inline
+                .and_then(|d| d.get("datum_hash"))
+                .and_then(JsonValue::as_str)
+        })
+        .map(str::to_string);
+
+    Some(ChainOutput {
+        tx_hash: ctx_str("tx_hash").unwrap_or_default(),
+        output_index: ctx_u64("output_idx").unwrap_or(0) as u32,
+        slot: ctx_u64("slot"),
+        address: output.get("address")?.as_str()?.to_string(),
+        value,
+        datum_hash,
+        datum_cbor,
+    })
+}
+
+impl ChainAdapter {
+    pub fn new(dexes: Vec<Arc<dyn BaseDex>>) -> Self {
+        Self {
+            dexes,
+            last: HashMap::new(),
+            datums: HashMap::new(),
+        }
+    }
+
+    /// Start from a pool snapshot (e.g. `all_liquidity_pools`), so the first
+    /// output of a known pool is reported as an update rather than a creation.
+    pub fn seed(&mut self, pools: impl IntoIterator<Item = LiquidityPool>) {
+        for pool in pools {
+            self.last.insert(pool_key(&pool), pool);
+        }
+    }
+
+    /// Decode one output. Outputs that are not a pool of any DEX produce nothing.
+    pub async fn handle_output(&mut self, mut output: ChainOutput) -> Result<Vec<PoolEvent>> {
+        if output.datum_cbor.is_none() {
+            if let Some(hash) = &output.datum_hash {
+                output.datum_cbor = self.datums.remove(hash);
+            }
+        }
+        let utxo = output.to_utxo();
+        let mut events = Vec::new();
+        for dex in &self.dexes {
+            // Arbitrary outputs may trip a DEX's parser; that just means "not ours".
+            let Ok(Some(base)) = dex.liquidity_pool_from_utxo(&utxo, "").await else {
+                continue;
+            };
+            let Some(pool) = dex
+                .liquidity_pool_from_utxo_extend(&utxo, &base.pool_id)
+                .await?
+            else {
+                continue;
+            };
+            let key = pool_key(&pool);
+            match self.last.get(&key) {
+                None => events.push(PoolEvent::PoolCreated {
+                    pool: Box::new(pool.clone()),
+                }),
+                Some(previous) if changed(previous, &pool) => events.push(PoolEvent::PoolUpdated {
+                    previous: Box::new(previous.clone()),
+                    current: Box::new(pool.clone()),
+                }),
+                Some(_) => {}
+            }
+            self.last.insert(key, pool);
+            break;
+        }
+        Ok(events)
+    }
+
+    /// Handle one Oura v1 JSON event. `plutus_datum` events are kept for the
+    /// outputs that reference them by hash; `tx_output` events are decoded;
+    /// anything else is ignored.
+    pub async fn handle_oura_event(&mut self, event: &JsonValue) -> Result<Vec<PoolEvent>> {
+        if let Some(datum) = event.get("plutus_datum") {
+            let hash = datum.get("datum_hash").and_then(JsonValue::as_str);
+            let json = datum.get("plutus_data");
+            if let (Some(hash), Some(json)) = (hash, json) {
+                if let Ok(cbor) = plutus_json_to_cbor(json) {
+                    if self.datums.len() >= MAX_PENDING_DATUMS {
+                        self.datums.clear();
+                    }
+                    self.datums.insert(hash.to_string(), cbor);
+                }
+            }
+            return Ok(Vec::new());
+        }
+        match oura_output(event) {
+            Some(output) => self.handle_output(output).await,
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_oura_output_and_plutus_json() {
+        let event = json!({
+            "context": { "tx_hash": "ab", "output_idx": 2, "slot": 100 },
+            "tx_output": {
+                "address": "addr1x",
+                "amount": 5_000_000,
+                "assets": [{ "policy": "ff", "asset": "01", "amount": 7 }],
+                "inline_datum": {
+                    "datum_hash": "dh",
+                    "plutus_data": { "constructor": 0, "fields": [{ "int": 1 }, { "bytes": "cafe" }] }
+                }
+            }
+        });
+        let output = oura_output(&event).unwrap();
+        assert_eq!(output.output_index, 2);
+        assert_eq!(output.slot, Some(100));
+        assert_eq!(output.value["lovelace"], 5_000_000);
+        assert_eq!(output.value["ff01"], 7);
+        assert_eq!(output.datum_hash.as_deref(), Some("dh"));
+        assert_eq!(output.datum_cbor.as_deref(), Some("d8799f0142cafeff"));
+    }
+}
@@ -0,0 +1,415 @@
+//! Poll DEXes and turn pool snapshots into events.
+//!
+//! [`PoolWatcher`] fetches every pool of the monitored DEXes on an interval and
+//! diffs each round against the previous one. A pool id seen for the first time
+//! produces [`PoolEvent::PoolCreated`]; a known pool whose reserves, fee or LP
+//! supply moved produces [`PoolEvent::PoolUpdated`].
+//!
+//! Known pool ids can be persisted with [`PoolWatcher::with_known_pools_file`],
+//! so a restarted watcher still reports pools created while it was down and
+//! does not re-announce old ones. With [`PoolWatcher::with_config_reload`] the
+//! DEX set is rebuilt whenever its config file changes, announced by
+//! [`PoolEvent::ConfigReloaded`].
+//!
+//! [`ChainAdapter`] produces the same events from a chain-sync stream (Oura,
+//! pallas) instead of polling.
+
+pub mod alerts;
+pub mod chain;
+pub mod reload;
+pub mod supply;
+
+pub use alerts::{load_rules, Alert, AlertRule, Alerter, PriceDeviation};
+pub use chain::{ChainAdapter, ChainOutput};
+pub use reload::ConfigReload;
+pub use supply::{SupplyChange, SupplyTracker};
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::cache::{load_from_file, save_to_file};
+use crate::dex::BaseDex;
+use crate::kupo::is_circuit_open;
+use crate::models::{token_identifier, LiquidityPool};
+
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(20);
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PoolEvent {
+    /// Pool id not seen before on any monitored DEX.
+    PoolCreated { pool: Box<LiquidityPool> },
+    /// Known pool whose state changed since the previous round.
+    PoolUpdated {
+        previous: Box<LiquidityPool>,
+        current: Box<LiquidityPool>,
+    },
+    /// The config file changed and the DEX set was rebuilt from it.
+    ConfigReloaded {
+        path: String,
+        /// Identifiers of the DEXes now monitored.
+        dexes: Vec<String>,
+    },
+}
+
+impl PoolEvent {
+    /// The created or updated pool; `None` for config reloads.
+    pub fn pool(&self) -> Option<&LiquidityPool> {
+        match self {
+            PoolEvent::PoolCreated { pool } => Some(pool),
+            PoolEvent::PoolUpdated { current, .. } => Some(current),
+            PoolEvent::ConfigReloaded { .. } => None,
+        }
+    }
+
+    /// One-line description: reserves and price, with the change for updates.
+    pub fn summary(&self) -> String {
+        match self {
+            PoolEvent::PoolCreated { pool } => format!(
+                "[{}] {} {} created: reserves {} / {}, price {:.6}",
+                pool.dex_identifier,
+                pool.pair(),
+                pool.pool_id,
+                pool.reserve_a,
+                pool.reserve_b,
+                pool.price()
+            ),
+            PoolEvent::PoolUpdated { previous, current } => format!(
+                "[{}] {} {}: reserve_a {} -> {} ({}), reserve_b {} -> {} ({}), price {:.6} -> {:.6} ({})",
+                current.dex_identifier,
+                current.pair(),
+                current.pool_id,
+                previous.reserve_a,
+                current.reserve_a,
+                percent_change(previous.reserve_a as f64, current.reserve_a as f64),
+                previous.reserve_b,
+                current.reserve_b,
+                percent_change(previous.reserve_b as f64, current.reserve_b as f64),
+                previous.price(),
+                current.price(),
+                percent_change(previous.price(), current.price())
+            ),
+            PoolEvent::ConfigReloaded { path, dexes } => {
+                format!("config reloaded from {}: watching {}", path, dexes.join(", "))
+            }
+        }
+    }
+}
+
+fn percent_change(before: f64, after: f64) -> String {
+    if before == 0.0 {
+        return "n/a".to_string();
+    }
+    format!("{:+.2}%", (after - before) / before * 100.0)
+}
+
+/// Key identifying a pool across DEXes.
+pub(crate) fn pool_key(pool: &LiquidityPool) -> String {
+    format!("{}:{}", pool.dex_identifier, pool.pool_id)
+}
+
+pub(crate) fn changed(previous: &LiquidityPool, current: &LiquidityPool) -> bool {
+    previous.reserve_a != current.reserve_a
+        || previous.reserve_b != current.reserve_b
+        || previous.total_lp_tokens != current.total_lp_tokens
+        || previous.pool_fee_percent != current.pool_fee_percent
+}
+
+/// Point-in-time view of a watcher's state, for debugging dumps.
+#[derive(Debug, Clone, Serialize)]
+pub struct WatcherSnapshot {
+    /// Identifiers of the monitored DEXes.
+    pub dexes: Vec<String>,
+    pub pair: Option<(String, String)>,
+    pub seeded: bool,
+    pub known_pools: BTreeSet<String>,
+    pub failed_dexes: Vec<String>,
+    /// Latest state of every tracked pool, sorted by DEX and pool id.
+    pub pools: Vec<LiquidityPool>,
+}
+
+pub struct PoolWatcher {
+    dexes: Vec<Arc<dyn BaseDex>>,
+    interval: Duration,
+    known_path: Option<String>,
+    known: BTreeSet<String>,
+    /// Only pools of this pair (sorted asset identifiers) are tracked.
+    pair: Option<(String, String)>,
+    /// False until the first round (or a loaded known-pools file) has seeded `known`.
+    seeded: bool,
+    last: HashMap<String, LiquidityPool>,
+    reload: Option<ConfigReload>,
+    /// DEXes that failed, or were skipped, in the last round.
+    failed: Vec<String>,
+}
+
+impl PoolWatcher {
+    pub fn new(dexes: Vec<Arc<dyn BaseDex>>) -> Self {
+        Self {
+            dexes,
+            interval: DEFAULT_INTERVAL,
+            known_path: None,
+            known: BTreeSet::new(),
+            pair: None,
+            seeded: false,
+            last: HashMap::new(),
+            reload: None,
+            failed: Vec::new(),
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Track only pools trading `asset_a` against `asset_b` (asset
+    /// identifiers, either order).
+    pub fn with_pair(mut self, asset_a: &str, asset_b: &str) -> Self {
+        let (a, b) = (asset_a.to_string(), asset_b.to_string());
+        self.pair = Some(if a <= b { (a, b) } else { (b, a) });
+        self
+    }
+
+    fn tracks(&self, pool: &LiquidityPool) -> bool {
+        let Some((a, b)) = &self.pair else {
+            return true;
+        };
+        let (x, y) = (
+            token_identifier(&pool.asset_a),
+            token_identifier(&pool.asset_b),
+        );
+        (&x == a && &y == b) || (&x == b && &y == a)
+    }
+
+    /// Persist known pool ids to `path` (a JSON array), loading it if it exists.
+    ///
+    /// Without a file the first round only seeds the known set; with one, pools
+    /// missing from it are reported as created on the first round.
+    pub fn with_known_pools_file(mut self, path: &str) -> Result<Self> {
+        if Path::new(path).exists() {
+            self.known = load_from_file(path)?;
+            self.seeded = true;
+        }
+        self.known_path = Some(path.to_string());
+        Ok(self)
+    }
+
+    /// Rebuild the DEX set when `reload`'s file changes, checked before every
+    /// round. Known pools are kept, so pools found at new addresses are
+    /// reported as created.
+    pub fn with_config_reload(mut self, reload: ConfigReload) -> Self {
+        self.reload = Some(reload);
+        self
+    }
+
+    pub fn known_pools(&self) -> &BTreeSet<String> {
+        &self.known
+    }
+
+    /// Latest state of every tracked pool.
+    pub fn pools(&self) -> impl Iterator<Item = &LiquidityPool> {
+        self.last.values()
+    }
+
+    /// Identifiers of the DEXes whose pools the last round could not fetch.
+    pub fn failed_dexes(&self) -> &[String] {
+        &self.failed
+    }
+
+    /// Copy of the watcher's state.
+    pub fn snapshot(&self) -> WatcherSnapshot {
+        let mut pools: Vec<LiquidityPool> = self.last.values().cloned().collect();
+        pools.sort_by(|a, b| (&a.dex_identifier, &a.pool_id).cmp(&(&b.dex_identifier, &b.pool_id)));
+        WatcherSnapshot {
+            dexes: self
+                .dexes
+                .iter()
+                .map(|d| d.identifier().to_string())
+                .collect(),
+            pair: self.pair.clone(),
+            seeded: self.seeded,
+            known_pools: self.known.clone(),
+            failed_dexes: self.failed.clone(),
+            pools,
+        }
+    }
+
+    /// Fetch one round from every DEX and return the events it produced.
+    ///
+    /// A DEX that fails is logged and skipped for the round; its pools keep their
+    /// previous state. When Kupo's circuit breaker is open the rest of the round
+    /// is skipped.
+    pub async fn poll(&mut self) -> Result<Vec<PoolEvent>> {
+        let mut events = self.reload_config();
+        let mut pools = Vec::new();
+        self.failed.clear();
+        for (i, dex) in self.dexes.iter().enumerate() {
+            match dex.all_liquidity_pools().await {
+                Ok(found) => pools.extend(found),
+                Err(e) if is_circuit_open(&e) => {
+                    eprintln!("[watcher] Kupo down, skipping round: {}", e);
+                    let skipped = self.dexes[i..].iter().map(|d| d.identifier().to_string());
+                    self.failed.extend(skipped);
+                    break;
+                }
+                Err(e) => {
+                    eprintln!("[watcher] {} failed: {}", dex.identifier(), e);
+                    self.failed.push(dex.identifier().to_string());
+                }
+            }
+        }
+        let before = self.known.len();
+        events.extend(self.diff(pools));
+        if self.known.len() != before {
+            if let Some(path) = &self.known_path {
+                save_to_file(&self.known, path)?;
+            }
+        }
+        Ok(events)
+    }
+
+    /// Swap in a rebuilt DEX set if the config file changed.
+    fn reload_config(&mut self) -> Vec<PoolEvent> {
+        let Some(reload) = &mut self.reload else {
+            return Vec::new();
+        };
+        match reload.check() {
+            Some(Ok(dexes)) => {
+                self.dexes = dexes;
+                vec![PoolEvent::ConfigReloaded {
+                    path: reload.path().to_string(),
+                    dexes: self
+                        .dexes
+                        .iter()
+                        .map(|d| d.identifier().to_string())
+                        .collect(),
+                }]
+            }
+            Some(Err(e)) => {
+                eprintln!(
+                    "[watcher] {} not reloaded, keeping the previous config: {}",
+                    reload.path(),
+                    e
+                );
+                Vec::new()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn diff(&mut self, pools: Vec<LiquidityPool>) -> Vec<PoolEvent> {
+        let announce = self.seeded;
+        let mut events = Vec::new();
+        for pool in pools {
+            if !self.tracks(&pool) {
+                continue;
+            }
+            let key = pool_key(&pool);
+            if self.known.insert(key.clone()) {
+                if announce {
+                    events.push(PoolEvent::PoolCreated {
+                        pool: Box::new(pool.clone()),
+                    });
+                }
+            } else if let Some(previous) = self.last.get(&key) {
+                if changed(previous, &pool) {
+                    events.push(PoolEvent::PoolUpdated {
+                        previous: Box::new(previous.clone()),
+                        current: Box::new(pool.clone()),
+                    });
+                }
+            }
+            self.last.insert(key, pool);
+        }
+        self.seeded = true;
+        events
+    }
+
+    /// Poll forever on a background task, sending events to the returned channel.
+    ///
+    /// The task stops when the receiver is dropped. Poll errors (e.g. failing to
+    /// persist known pools) are logged and the next round is attempted.
+    pub fn spawn(mut self) -> mpsc::Receiver<PoolEvent> {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            loop {
+                match self.poll().await {
+                    Ok(events) => {
+                        for event in events {
+                            if tx.send(event).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("[watcher] poll failed: {}", e),
+                }
+                if tx.is_closed() {
+                    return;
+                }
+                tokio::time::sleep(self.interval).await;
+            }
+        });
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Token;
+
+    fn pool(id: &str, reserve_a: u64) -> LiquidityPool {
+        LiquidityPool::new(
+            "MinswapV2",
+            Token::Lovelace,
+            Token::Lovelace,
+            reserve_a,
+            10,
+            "addr",
+            0.3,
+            id,
+        )
+    }
+
+    #[test]
+    fn test_diff_seeds_then_reports_new_and_changed_pools() {
+        let mut watcher = PoolWatcher::new(Vec::new());
+        assert!(watcher.diff(vec![pool("a", 1)]).is_empty());
+
+        let events = watcher.diff(vec![pool("a", 2), pool("b", 1)]);
+        assert_eq!(events.len(), 2);
+        assert!(
+            matches!(&events[0], PoolEvent::PoolUpdated { previous, .. } if previous.reserve_a == 1)
+        );
+        assert!(matches!(&events[1], PoolEvent::PoolCreated { pool } if pool.pool_id == "b"));
+
+        assert!(watcher.diff(vec![pool("a", 2), pool("b", 1)]).is_empty());
+        assert_eq!(watcher.known_pools().len(), 2);
+
+        let snapshot = watcher.snapshot();
+        assert!(snapshot.seeded);
+        let ids: Vec<&str> = snapshot.pools.iter().map(|p| p.pool_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_pair_filter_and_summary() {
+        let mut watcher = PoolWatcher::new(Vec::new()).with_pair("lovelace", "lovelace");
+        watcher.diff(vec![pool("a", 100)]);
+        let events = watcher.diff(vec![pool("a", 110)]);
+        assert!(events[0]
+            .summary()
+            .contains("reserve_a 100 -> 110 (+10.00%)"));
+
+        let mut other = PoolWatcher::new(Vec::new()).with_pair("lovelace", "abc");
+        other.diff(vec![pool("a", 1)]);
+        assert!(other.known_pools().is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+//! Reload a watcher's DEX set when its config file changes.
+//!
+//! [`ConfigReload`] remembers the file's modification time and size. When
+//! either changes, its builder turns the file into a new DEX set, which the
+//! [`PoolWatcher`](super::PoolWatcher) swaps in before its next round and
+//! announces with [`PoolEvent::ConfigReloaded`](super::PoolEvent). A file that
+//! fails to load is logged and the previous DEXes are kept until it changes
+//! again, so a half-written edit never stops the watcher.
+
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::dex::BaseDex;
+
+/// Builds the DEX set from the config file at the given path.
+pub type DexBuilder = Box<dyn Fn(&str) -> Result<Vec<Arc<dyn BaseDex>>> + Send + Sync>;
+
+pub struct ConfigReload {
+    path: String,
+    stamp: Option<(SystemTime, u64)>,
+    build: DexBuilder,
+}
+
+fn stamp(path: &str) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl ConfigReload {
+    /// Watch `path`; its current contents count as already applied.
+    pub fn new<F>(path: &str, build: F) -> Self
+    where
+        F: Fn(&str) -> Result<Vec<Arc<dyn BaseDex>>> + Send + Sync + 'static,
+    {
+        Self {
+            path: path.to_string(),
+            stamp: stamp(path),
+            build: Box::new(build),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The rebuilt DEX set, or the error building it, if the file changed
+    /// since the last check; `None` otherwise.
+    pub fn check(&mut self) -> Option<Result<Vec<Arc<dyn BaseDex>>>> {
+        let current = stamp(&self.path);
+        if current == self.stamp {
+            return None;
+        }
+        self.stamp = current;
+        Some((self.build)(&self.path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::minswap_v2::MinswapV2;
+    use crate::kupo::KupoApi;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_check_rebuilds_only_on_change() {
+        let path = std::env::temp_dir().join(format!("dexter-reload-{}.toml", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        std::fs::write(&path, "ok").unwrap();
+        let mut reload = ConfigReload::new(&path, |path| {
+            match std::fs::read_to_string(path)?.as_str() {
+                "broken" => Err(anyhow!("broken config")),
+                _ => Ok(vec![
+                    Arc::new(MinswapV2::new(KupoApi::new("http://localhost:1442")))
+                        as Arc<dyn BaseDex>,
+                ]),
+            }
+        });
+        assert!(reload.check().is_none());
+
+        std::fs::write(&path, "broken").unwrap();
+        assert!(reload.check().unwrap().is_err());
+        assert!(reload.check().is_none());
+
+        std::fs::write(&path, "ok again").unwrap();
+        assert_eq!(reload.check().unwrap().unwrap().len(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
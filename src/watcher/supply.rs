@@ -0,0 +1,127 @@
+//! Total supply of the tokens traded in watched pools, to catch mints.
+//!
+//! Kupo has no supply endpoint, so [`SupplyTracker::supply`] sums the token
+//! over every unspent output holding it. That is one Kupo query per token and
+//! grows with the token's holder count: track the tokens of a few pairs, not
+//! of every pool on chain.
+//!
+//! A mint dilutes every pool pricing the token before it reaches them, so
+//! [`AlertRule::SupplyMinted`](super::AlertRule::SupplyMinted) reports it on
+//! the pools that hold the token.
+
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::kupo::KupoApi;
+use crate::models::AssetId;
+
+/// Supply lookups in flight at once.
+const SUPPLY_CONCURRENCY: usize = 4;
+
+/// A token's supply moving between two observations.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SupplyChange {
+    pub token: String,
+    pub previous: u64,
+    pub current: u64,
+    /// `(current - previous) / previous`, in percent; negative for burns.
+    pub change_percent: f64,
+}
+
+impl SupplyChange {
+    fn new(token: &AssetId, previous: u64, current: u64) -> Self {
+        let change_percent = if previous == 0 {
+            100.0
+        } else {
+            (current as f64 - previous as f64) / previous as f64 * 100.0
+        };
+        Self {
+            token: token.to_string(),
+            previous,
+            current,
+            change_percent,
+        }
+    }
+
+    pub fn is_mint(&self) -> bool {
+        self.current > self.previous
+    }
+}
+
+/// Remembers the last supply seen of each token.
+pub struct SupplyTracker {
+    kupo: KupoApi,
+    known: Mutex<HashMap<AssetId, u64>>,
+}
+
+impl SupplyTracker {
+    pub fn new(kupo: &KupoApi) -> Self {
+        Self {
+            kupo: kupo.clone(),
+            known: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Units of `token` across every unspent output.
+    pub async fn supply(&self, token: &AssetId) -> Result<u64> {
+        let utxos = self.kupo.get(&token.dotted(), true).await?;
+        Ok(utxos
+            .iter()
+            .map(|utxo| utxo.asset_quantity(token.as_str()))
+            .sum())
+    }
+
+    /// Read the supply of each of `tokens` (lovelace is skipped) and return
+    /// the ones that changed since the last call. A token's first read only
+    /// records it; failed reads are logged and keep the previous supply.
+    pub async fn observe(&self, tokens: &[AssetId]) -> Vec<SupplyChange> {
+        let tokens: Vec<AssetId> = tokens
+            .iter()
+            .filter(|token| !token.is_lovelace())
+            .cloned()
+            .collect();
+        let supplies: Vec<(AssetId, Result<u64>)> = stream::iter(tokens)
+            .map(|token| async move {
+                let supply = self.supply(&token).await;
+                (token, supply)
+            })
+            .buffered(SUPPLY_CONCURRENCY)
+            .collect()
+            .await;
+        let mut known = self.known.lock().unwrap();
+        let mut changes = Vec::new();
+        for (token, supply) in supplies {
+            match supply {
+                Ok(current) => {
+                    if let Some(previous) = known.insert(token.clone(), current) {
+                        if previous != current {
+                            changes.push(SupplyChange::new(&token, previous, current));
+                        }
+                    }
+                }
+                Err(e) => eprintln!("[supply] {}: {}", token, e),
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supply_change() {
+        let token =
+            AssetId::from("279c909f348e533da5808898f87f9a14bb2c3dfbbacccd631d927a3f534e454b");
+        let mint = SupplyChange::new(&token, 1_000, 1_250);
+        assert!(mint.is_mint());
+        assert_eq!(mint.change_percent, 25.0);
+        let burn = SupplyChange::new(&token, 1_000, 900);
+        assert!(!burn.is_mint());
+        assert_eq!(burn.change_percent, -10.0);
+    }
+}
@@ -157,6 +157,7 @@ mod crate_test {
             ),
             pool_fee_percent: 0.3,
             total_lp_tokens: 0,
+            metadata: Default::default(),
         }
     }
 
@@ -184,8 +185,9 @@ mod crate_test {
             tx_hash: "aa".repeat(32),
             tx_index: 0,
             output_index: 0,
-            amount: vec![Unit { unit: "lovelace".into(), quantity: "5000000".into() }],
+            amount: vec![Unit { unit: "lovelace".into(), quantity: 5000000 }],
             block: String::new(),
+            slot: None,
             data_hash: None,
             inline_datum: None,
             reference_script_hash: None,
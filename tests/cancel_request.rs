@@ -14,8 +14,9 @@ fn cancel_request_smoke() {
         tx_hash: "00".repeat(32),
         tx_index: 0,
         output_index: 0,
-        amount: vec![Unit { unit: "lovelace".into(), quantity: "2004000000".into() }],
+        amount: vec![Unit { unit: "lovelace".into(), quantity: 2004000000 }],
         block: String::new(),
+        slot: None,
         data_hash: Some("ab".repeat(32)),
         inline_datum: None,
         reference_script_hash: None,
@@ -118,6 +118,7 @@ fn build_swap_order_reproduces_onchain_golden() {
         pool_id: format!("{}{}", LP_TOKEN_POLICY, LP_TOKEN_NAME),
         pool_fee_percent: 0.3,
         total_lp_tokens: 0,
+        metadata: Default::default(),
     };
 
     let params = SwapParams {
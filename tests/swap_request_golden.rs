@@ -22,6 +22,7 @@ fn swap_request_limit_path_reproduces_golden() {
         pool_id: "f5808c2c990d86da54bfc97d89cee6efa20cd8461616359478d96b4c7dd6988c5a86693c76aeec1ea94afa41770be0de21a775ca7a2a1eabdb6a0171".into(),
         pool_fee_percent: 0.3,
         total_lp_tokens: 0,
+        metadata: Default::default(),
     };
 
     let pays = SwapRequest::new(&dex)
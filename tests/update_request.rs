@@ -19,6 +19,7 @@ fn make_pool() -> LiquidityPool {
         pool_id: "f5808c2c990d86da54bfc97d89cee6efa20cd8461616359478d96b4c7dd6988c5a86693c76aeec1ea94afa41770be0de21a775ca7a2a1eabdb6a0171".into(),
         pool_fee_percent: 0.3,
         total_lp_tokens: 0,
+        metadata: Default::default(),
     }
 }
 
@@ -29,8 +30,9 @@ fn make_order_utxo() -> Utxo {
         tx_hash: "00".repeat(32),
         tx_index: 0,
         output_index: 0,
-        amount: vec![Unit { unit: "lovelace".into(), quantity: "2004000000".into() }],
+        amount: vec![Unit { unit: "lovelace".into(), quantity: 2004000000 }],
         block: String::new(),
+        slot: None,
         data_hash: Some("ab".repeat(32)),
         inline_datum: None,
         reference_script_hash: None,